@@ -0,0 +1,28 @@
+// "Save to AI News" bookmarklet for the local API save endpoint.
+//
+// 1. Enable the local API in settings and copy the port and token.
+// 2. Replace PORT and TOKEN below, then minify into a bookmark URL:
+//    javascript:(()=>{...})()
+(() => {
+  const PORT = 17890;
+  const TOKEN = "replace_with_local_api_token";
+
+  fetch(`http://127.0.0.1:${PORT}/api/save`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${TOKEN}`,
+    },
+    body: JSON.stringify({
+      url: location.href,
+      title: document.title,
+      html: document.documentElement.outerHTML,
+    }),
+  })
+    .then(async (res) => {
+      const body = await res.json().catch(() => ({}));
+      if (res.status === 201) alert("已保存到 AI News");
+      else alert(`保存失败 (${res.status}): ${body.error ?? ""}`);
+    })
+    .catch((err) => alert(`无法连接到 AI News: ${err}`));
+})();
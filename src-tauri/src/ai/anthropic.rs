@@ -0,0 +1,113 @@
+// Anthropic's Messages API: `{base}/v1/messages` with an `x-api-key` header.
+// The system prompt is a top-level field, `max_tokens` is required, and
+// there is no embeddings endpoint.
+
+use serde_json::{json, Value};
+
+use super::{AiConfig, ChatRequest, StreamEvent, WireRequest};
+
+const API_VERSION: &str = "2023-06-01";
+// Sent when a request sets no limit; enough for a translated chunk
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+// The base URL may be given with or without the `/v1`
+fn endpoint(base_url: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    if base.ends_with("/v1") {
+        format!("{}/messages", base)
+    } else {
+        format!("{}/v1/messages", base)
+    }
+}
+
+pub fn chat_request(config: &AiConfig, request: &ChatRequest, stream: bool) -> WireRequest {
+    let mut body = json!({
+        "model": config.model,
+        "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        "messages": [{"role": "user", "content": request.user}],
+    });
+    if !request.system.is_empty() {
+        body["system"] = json!(request.system);
+    }
+    if stream {
+        body["stream"] = json!(true);
+    }
+    WireRequest {
+        url: endpoint(&config.base_url),
+        headers: vec![("x-api-key", config.api_key.clone()), ("anthropic-version", API_VERSION.to_string())],
+        body,
+    }
+}
+
+// The text blocks of the reply, joined
+pub fn parse_completion(json: &Value) -> Option<String> {
+    let texts: Vec<&str> = json["content"]
+        .as_array()?
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect();
+    (!texts.is_empty()).then(|| texts.concat())
+}
+
+pub fn parse_event(data: &str) -> StreamEvent {
+    let Ok(json) = serde_json::from_str::<Value>(data) else { return StreamEvent::Other };
+    match json["type"].as_str() {
+        Some("content_block_delta") => match json["delta"]["text"].as_str() {
+            Some(delta) if !delta.is_empty() => StreamEvent::Delta(delta.to_string()),
+            _ => StreamEvent::Other,
+        },
+        Some("message_stop") => StreamEvent::Done,
+        Some("error") => StreamEvent::Failed(
+            json["error"]["message"].as_str().map(str::to_string).unwrap_or_else(|| json["error"].to_string()),
+        ),
+        _ => StreamEvent::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config(base_url: &str) -> AiConfig {
+        AiConfig { base_url: base_url.into(), api_key: "key".into(), model: "claude".into() }
+    }
+
+    #[test]
+    fn chat_request_puts_the_system_prompt_at_the_top_level() {
+        let request = ChatRequest { system: "Be brief.".into(), user: "Hi".into(), max_tokens: None, timeout: Duration::from_secs(5) };
+        let wire = chat_request(&config("https://api.anthropic.com"), &request, true);
+        assert_eq!(wire.url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(wire.body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(wire.body["system"], "Be brief.");
+        assert_eq!(wire.body["messages"], json!([{"role": "user", "content": "Hi"}]));
+        assert_eq!(wire.body["stream"], true);
+        assert!(wire.headers.contains(&("x-api-key", "key".to_string())));
+
+        let request = ChatRequest { system: String::new(), max_tokens: Some(8), ..request };
+        let wire = chat_request(&config("https://api.anthropic.com/v1/"), &request, false);
+        assert_eq!(wire.url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(wire.body["max_tokens"], 8);
+        assert!(wire.body.get("system").is_none());
+        assert!(wire.body.get("stream").is_none());
+    }
+
+    #[test]
+    fn replies_and_stream_events() {
+        let reply = json!({"content": [{"type": "text", "text": "Hello "}, {"type": "tool_use"}, {"type": "text", "text": "there"}]});
+        assert_eq!(parse_completion(&reply), Some("Hello there".into()));
+        assert_eq!(parse_completion(&json!({"content": []})), None);
+
+        assert_eq!(
+            parse_event(r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hi"}}"#),
+            StreamEvent::Delta("Hi".into())
+        );
+        assert_eq!(parse_event(r#"{"type":"message_stop"}"#), StreamEvent::Done);
+        assert_eq!(
+            parse_event(r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#),
+            StreamEvent::Failed("Overloaded".into())
+        );
+        assert_eq!(parse_event(r#"{"type":"ping"}"#), StreamEvent::Other);
+    }
+}
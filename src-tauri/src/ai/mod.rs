@@ -0,0 +1,383 @@
+// Provider-agnostic AI calls.
+//
+// Features (summaries, translation, roundup themes) describe what they want
+// as a `ChatRequest` and send it through `Ai`, which implements
+// `ChatProvider` for the wire format the configured endpoint speaks:
+// OpenAI-compatible `/chat/completions` by default, Anthropic's Messages
+// API when the base URL is on anthropic.com. The provider modules only
+// build requests and parse replies, as plain functions of their inputs;
+// sending goes through `retry`, which takes an `ai_limits` permit and
+// retries transient failures and rate limits.
+
+mod anthropic;
+mod openai;
+mod retry;
+mod summary;
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::get_setting;
+use crate::i18n::{t, tr, Msg};
+
+pub(crate) use summary::{generate_ai_summary, local_brief, summarize_or_template, summarize_text};
+
+const DEFAULT_MODEL: &str = "qwen3-max";
+const EMBED_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct AiConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl AiConfig {
+    // Endpoint settings from the database first, then environment variables
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let setting = |key: &str, env: &str| {
+            get_setting(conn, key, "").ok().filter(|s| !s.is_empty()).or_else(|| std::env::var(env).ok())
+        };
+        let base_url = setting("ai_base_url", "AI_BASE_URL").ok_or_else(|| t(Msg::AiBaseUrlMissing))?;
+        let api_key = setting("ai_api_key", "AI_API_KEY").ok_or_else(|| t(Msg::AiApiKeyMissing))?;
+        let model = setting("ai_model", "AI_MODEL").unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        Ok(AiConfig { base_url, api_key, model })
+    }
+
+    // Environment variables only
+    pub fn from_env() -> Option<Self> {
+        let base_url = std::env::var("AI_BASE_URL").ok().filter(|s| !s.is_empty())?;
+        let api_key = std::env::var("AI_API_KEY").ok().filter(|s| !s.is_empty())?;
+        let model = std::env::var("AI_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+        Some(AiConfig { base_url, api_key, model })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    // `/chat/completions` and `/embeddings`, also spoken by DashScope, DeepSeek, Ollama, ...
+    OpenAi,
+    Anthropic,
+}
+
+impl ProviderKind {
+    pub fn detect(base_url: &str) -> Self {
+        let host = reqwest::Url::parse(base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+        if host == "anthropic.com" || host.ends_with(".anthropic.com") {
+            ProviderKind::Anthropic
+        } else {
+            ProviderKind::OpenAi
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    // Left out of the request when empty
+    pub system: String,
+    pub user: String,
+    // None = the provider's default (Anthropic requires one, see `anthropic`)
+    pub max_tokens: Option<u32>,
+    // For the whole exchange, including a streamed body
+    pub timeout: Duration,
+}
+
+// An HTTP request as a provider builds it, before it's sent
+#[derive(Debug, Clone)]
+pub struct WireRequest {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: serde_json::Value,
+}
+
+// What one server-sent event of a streamed reply means
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    Delta(String),
+    Done,
+    Failed(String),
+    // Keep-alives, role announcements, usage, ...
+    Other,
+}
+
+pub trait ChatProvider {
+    // The reply to `request`, trimmed
+    fn complete(&self, request: &ChatRequest) -> impl Future<Output = Result<String, String>> + Send;
+
+    // Same, with `on_delta` called with each piece of the reply as it arrives
+    fn complete_stream<F: FnMut(&str) + Send>(
+        &self,
+        request: &ChatRequest,
+        on_delta: F,
+    ) -> impl Future<Output = Result<String, String>> + Send;
+
+    // One vector per input, in input order
+    fn embed(&self, model: &str, input: &[String]) -> impl Future<Output = Result<Vec<Vec<f32>>, String>> + Send;
+}
+
+// Splits a server-sent event stream into its `data:` payloads, across chunk boundaries
+#[derive(Default)]
+struct SseBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseBuffer {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+        payloads
+    }
+}
+
+// The configured provider
+pub struct Ai<'a> {
+    client: &'a reqwest::Client,
+    config: &'a AiConfig,
+    kind: ProviderKind,
+}
+
+impl<'a> Ai<'a> {
+    pub fn new(client: &'a reqwest::Client, config: &'a AiConfig) -> Self {
+        Ai { client, config, kind: ProviderKind::detect(&config.base_url) }
+    }
+
+    fn chat_request(&self, request: &ChatRequest, stream: bool) -> WireRequest {
+        match self.kind {
+            ProviderKind::OpenAi => openai::chat_request(self.config, request, stream),
+            ProviderKind::Anthropic => anthropic::chat_request(self.config, request, stream),
+        }
+    }
+
+    fn parse_completion(&self, json: &serde_json::Value) -> Option<String> {
+        match self.kind {
+            ProviderKind::OpenAi => openai::parse_completion(json),
+            ProviderKind::Anthropic => anthropic::parse_completion(json),
+        }
+    }
+
+    fn parse_event(&self, data: &str) -> StreamEvent {
+        match self.kind {
+            ProviderKind::OpenAi => openai::parse_event(data),
+            ProviderKind::Anthropic => anthropic::parse_event(data),
+        }
+    }
+}
+
+fn reply(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(t(Msg::AiBadFormat));
+    }
+    Ok(text.to_string())
+}
+
+impl ChatProvider for Ai<'_> {
+    async fn complete(&self, request: &ChatRequest) -> Result<String, String> {
+        let wire = self.chat_request(request, false);
+        let (response, _permit) = retry::send(self.client, &self.config.base_url, &wire, request.timeout).await?;
+        let json: serde_json::Value = response.json().await.map_err(|e| tr(Msg::AiParseFailed, e))?;
+        reply(&self.parse_completion(&json).ok_or_else(|| t(Msg::AiBadFormat))?)
+    }
+
+    async fn complete_stream<F: FnMut(&str) + Send>(&self, request: &ChatRequest, mut on_delta: F) -> Result<String, String> {
+        let wire = self.chat_request(request, true);
+        let (mut response, _permit) = retry::send(self.client, &self.config.base_url, &wire, request.timeout).await?;
+
+        // Some compatible servers ignore `stream` and answer in one piece
+        let is_json = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if is_json {
+            let json: serde_json::Value = response.json().await.map_err(|e| tr(Msg::AiParseFailed, e))?;
+            let text = reply(&self.parse_completion(&json).ok_or_else(|| t(Msg::AiBadFormat))?)?;
+            on_delta(&text);
+            return Ok(text);
+        }
+
+        let mut text = String::new();
+        let mut buffer = SseBuffer::default();
+        while let Some(chunk) = response.chunk().await.map_err(|e| tr(Msg::AiRequestFailed, e))? {
+            for data in buffer.push(&chunk) {
+                match self.parse_event(&data) {
+                    StreamEvent::Delta(delta) => {
+                        on_delta(&delta);
+                        text.push_str(&delta);
+                    }
+                    StreamEvent::Done => return reply(&text),
+                    StreamEvent::Failed(error) => return Err(tr(Msg::AiErrorResponse, error)),
+                    StreamEvent::Other => {}
+                }
+            }
+        }
+        reply(&text)
+    }
+
+    async fn embed(&self, model: &str, input: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let wire = match self.kind {
+            ProviderKind::OpenAi => openai::embed_request(self.config, model, input),
+            ProviderKind::Anthropic => return Err(t(Msg::AiEmbeddingsUnsupported)),
+        };
+        let (response, _permit) = retry::send(self.client, &self.config.base_url, &wire, EMBED_TIMEOUT).await?;
+        let json: serde_json::Value = response.json().await.map_err(|e| tr(Msg::AiParseFailed, e))?;
+        let vectors = openai::parse_embeddings(&json).ok_or_else(|| t(Msg::AiBadFormat))?;
+        if vectors.len() != input.len() {
+            return Err(t(Msg::AiBadFormat));
+        }
+        Ok(vectors)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub provider: ProviderKind,
+    pub model: String,
+    pub reply: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    // Only probed when an embedding model was given
+    pub embedding_dimensions: Option<usize>,
+    pub embedding_error: Option<String>,
+}
+
+// Check the endpoint with a tiny completion and, given a model, an embedding
+pub async fn probe(client: &reqwest::Client, config: &AiConfig, embedding_model: Option<&str>) -> ProbeResult {
+    let ai = Ai::new(client, config);
+    let request = ChatRequest {
+        system: String::new(),
+        user: "Reply with the single word OK.".to_string(),
+        max_tokens: Some(8),
+        timeout: Duration::from_secs(30),
+    };
+    let started = Instant::now();
+    let chat = ai.complete(&request).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let embedding = match embedding_model.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(model) => Some(ai.embed(model, &["ping".to_string()]).await),
+        None => None,
+    };
+    let (reply, error) = match chat {
+        Ok(reply) => (Some(reply), None),
+        Err(e) => (None, Some(e)),
+    };
+    let (embedding_dimensions, embedding_error) = match embedding {
+        Some(Ok(vectors)) => (vectors.first().map(Vec::len), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    ProbeResult {
+        provider: ai.kind,
+        model: config.model.clone(),
+        reply,
+        error,
+        latency_ms,
+        embedding_dimensions,
+        embedding_error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    // Answers one request with `response` and returns the base URL
+    fn serve(response: String) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0u8; 8192]).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        base_url
+    }
+
+    fn complete_stream(base_url: String) -> (Result<String, String>, Vec<String>) {
+        let client = reqwest::Client::new();
+        let config = AiConfig { base_url, api_key: "sk-test".into(), model: "m".into() };
+        let request =
+            ChatRequest { system: String::new(), user: "Hi".into(), max_tokens: None, timeout: Duration::from_secs(5) };
+        let mut deltas = Vec::new();
+        let result = tauri::async_runtime::block_on(
+            Ai::new(&client, &config).complete_stream(&request, |delta| deltas.push(delta.to_string())),
+        );
+        (result, deltas)
+    }
+
+    #[test]
+    fn provider_is_detected_from_the_host() {
+        assert_eq!(ProviderKind::detect("https://api.anthropic.com/v1"), ProviderKind::Anthropic);
+        assert_eq!(ProviderKind::detect("https://dashscope.aliyuncs.com/compatible-mode/v1"), ProviderKind::OpenAi);
+        assert_eq!(ProviderKind::detect("https://anthropic.com.example.net"), ProviderKind::OpenAi);
+        assert_eq!(ProviderKind::detect("not a url"), ProviderKind::OpenAi);
+    }
+
+    #[test]
+    fn sse_payloads_are_split_across_chunks() {
+        let mut buffer = SseBuffer::default();
+        assert_eq!(buffer.push(b"event: delta\ndata: {\"a\""), Vec::<String>::new());
+        assert_eq!(buffer.push(b":1}\r\n\r\ndata:[DONE]\n"), ["{\"a\":1}", "[DONE]"]);
+        assert!(buffer.pending.is_empty());
+        assert!(reply("  OK \n").is_ok_and(|text| text == "OK"));
+        assert!(reply(" \n").is_err());
+    }
+
+    #[test]
+    fn streamed_reply_is_assembled_from_deltas() {
+        let events = [
+            r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"lo"}}]}"#,
+            "data: [DONE]",
+        ]
+        .join("\n\n");
+        let base_url = serve(format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}\n\n",
+            events.len() + 2,
+            events
+        ));
+        let (result, deltas) = complete_stream(base_url);
+        assert_eq!(result.unwrap(), "Hello");
+        assert_eq!(deltas, ["Hel", "lo"]);
+    }
+
+    #[test]
+    fn unstreamed_and_failed_replies() {
+        let body = r#"{"choices":[{"message":{"content":" OK "}}]}"#;
+        let base_url = serve(format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        let (result, deltas) = complete_stream(base_url);
+        assert_eq!(result.unwrap(), "OK");
+        assert_eq!(deltas, ["OK"], "a server ignoring `stream` still reports the reply");
+
+        let body = "data: {\"error\":{\"message\":\"quota exceeded\"}}\n\n";
+        let base_url = serve(format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        let (result, deltas) = complete_stream(base_url);
+        assert!(result.unwrap_err().contains("quota exceeded"));
+        assert!(deltas.is_empty());
+    }
+}
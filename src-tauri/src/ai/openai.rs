@@ -0,0 +1,121 @@
+// OpenAI-compatible endpoints: `{base}/chat/completions` and `{base}/embeddings`,
+// authenticated with a bearer token. Streams end with a `[DONE]` payload.
+
+use serde_json::{json, Value};
+
+use super::{AiConfig, ChatRequest, StreamEvent, WireRequest};
+
+fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), path)
+}
+
+fn headers(config: &AiConfig) -> Vec<(&'static str, String)> {
+    vec![("Authorization", format!("Bearer {}", config.api_key))]
+}
+
+pub fn chat_request(config: &AiConfig, request: &ChatRequest, stream: bool) -> WireRequest {
+    let mut messages = Vec::new();
+    if !request.system.is_empty() {
+        messages.push(json!({"role": "system", "content": request.system}));
+    }
+    messages.push(json!({"role": "user", "content": request.user}));
+
+    let mut body = json!({ "model": config.model, "messages": messages });
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+    if stream {
+        body["stream"] = json!(true);
+    }
+    WireRequest { url: endpoint(&config.base_url, "chat/completions"), headers: headers(config), body }
+}
+
+pub fn parse_completion(json: &Value) -> Option<String> {
+    json["choices"][0]["message"]["content"].as_str().map(str::to_string)
+}
+
+pub fn parse_event(data: &str) -> StreamEvent {
+    if data == "[DONE]" {
+        return StreamEvent::Done;
+    }
+    let Ok(json) = serde_json::from_str::<Value>(data) else { return StreamEvent::Other };
+    if let Some(error) = json.get("error") {
+        let message = error["message"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string());
+        return StreamEvent::Failed(message);
+    }
+    match json["choices"][0]["delta"]["content"].as_str() {
+        Some(delta) if !delta.is_empty() => StreamEvent::Delta(delta.to_string()),
+        _ => StreamEvent::Other,
+    }
+}
+
+pub fn embed_request(config: &AiConfig, model: &str, input: &[String]) -> WireRequest {
+    WireRequest {
+        url: endpoint(&config.base_url, "embeddings"),
+        headers: headers(config),
+        body: json!({ "model": model, "input": input }),
+    }
+}
+
+// Vectors in input order (the `index` field, where a server reorders them)
+pub fn parse_embeddings(json: &Value) -> Option<Vec<Vec<f32>>> {
+    let mut items: Vec<(u64, Vec<f32>)> = json["data"]
+        .as_array()?
+        .iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let vector = item["embedding"]
+                .as_array()?
+                .iter()
+                .map(|v| v.as_f64().map(|f| f as f32))
+                .collect::<Option<Vec<f32>>>()?;
+            Some((item["index"].as_u64().unwrap_or(position as u64), vector))
+        })
+        .collect::<Option<_>>()?;
+    items.sort_by_key(|(index, _)| *index);
+    Some(items.into_iter().map(|(_, vector)| vector).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> AiConfig {
+        AiConfig { base_url: "https://llm.example.com/v1/".into(), api_key: "sk-test".into(), model: "m".into() }
+    }
+
+    #[test]
+    fn chat_request_leaves_out_what_is_not_set() {
+        let request = ChatRequest { system: String::new(), user: "Hi".into(), max_tokens: None, timeout: Duration::from_secs(5) };
+        let wire = chat_request(&config(), &request, false);
+        assert_eq!(wire.url, "https://llm.example.com/v1/chat/completions");
+        assert_eq!(wire.headers, [("Authorization", "Bearer sk-test".to_string())]);
+        assert_eq!(wire.body, json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}]}));
+
+        let request = ChatRequest { system: "Be brief.".into(), max_tokens: Some(8), ..request };
+        let wire = chat_request(&config(), &request, true);
+        assert_eq!(wire.body["messages"][0], json!({"role": "system", "content": "Be brief."}));
+        assert_eq!(wire.body["max_tokens"], 8);
+        assert_eq!(wire.body["stream"], true);
+    }
+
+    #[test]
+    fn stream_events() {
+        assert_eq!(parse_event("[DONE]"), StreamEvent::Done);
+        assert_eq!(parse_event(r#"{"choices":[{"delta":{"content":"Hel"}}]}"#), StreamEvent::Delta("Hel".into()));
+        assert_eq!(parse_event(r#"{"choices":[{"delta":{"role":"assistant","content":""}}]}"#), StreamEvent::Other);
+        assert_eq!(parse_event(r#"{"error":{"message":"quota exceeded"}}"#), StreamEvent::Failed("quota exceeded".into()));
+        assert_eq!(parse_event("not json"), StreamEvent::Other);
+        assert_eq!(parse_completion(&json!({"choices": [{"message": {"content": "OK"}}]})), Some("OK".into()));
+        assert_eq!(parse_completion(&json!({"choices": []})), None);
+    }
+
+    #[test]
+    fn embeddings_come_back_in_input_order() {
+        let json = json!({"data": [{"index": 1, "embedding": [0.5, 1.0]}, {"index": 0, "embedding": [0.25, 0.0]}]});
+        assert_eq!(parse_embeddings(&json), Some(vec![vec![0.25, 0.0], vec![0.5, 1.0]]));
+        assert_eq!(parse_embeddings(&json!({"data": [{"embedding": ["x"]}]})), None);
+        assert_eq!(parse_embeddings(&json!({})), None);
+    }
+}
@@ -0,0 +1,189 @@
+// Sending with rate limiting and retries.
+//
+// Every request first waits for an `ai_limits` permit for its provider.
+// Failed requests get up to ATTEMPTS attempts with exponential backoff (2s,
+// 4s); rate-limit responses wait as long as the provider asks and don't use
+// up an attempt, within their own budget of RATE_LIMIT_WAITS. Client errors
+// other than 408/429 (bad key, unknown model, ...) fail at once. The permit
+// is released while waiting and otherwise held until the caller has read
+// the response body.
+
+use std::time::Duration;
+
+use super::WireRequest;
+use crate::ai_limits::{self, Permit};
+use crate::i18n::{tr, Msg};
+
+const ATTEMPTS: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_secs(2);
+const MAX_DELAY: Duration = Duration::from_secs(120);
+const RATE_LIMIT_WAITS: u32 = 3;
+
+// A successful response and the permit it was sent under
+pub async fn send(
+    client: &reqwest::Client,
+    base_url: &str,
+    request: &WireRequest,
+    timeout: Duration,
+) -> Result<(reqwest::Response, Permit), String> {
+    let mut attempts = 0;
+    let mut rate_limit_waits = 0;
+    let mut backoff = BASE_DELAY;
+
+    loop {
+        attempts += 1;
+
+        let permit = ai_limits::acquire(base_url).await;
+        let mut builder = client.post(&request.url).json(&request.body).timeout(timeout);
+        for (name, value) in &request.headers {
+            builder = builder.header(*name, value);
+        }
+
+        let mut delay = backoff;
+        match builder.send().await {
+            Ok(resp) if resp.status().is_success() => return Ok((resp, permit)),
+            Ok(resp) => {
+                let status = resp.status();
+                let requested = retry_after(resp.headers());
+                let error_text = resp.text().await.unwrap_or_default();
+                eprintln!("AI API error ({}): {}", status, error_text);
+                let error = tr(Msg::AiErrorResponse, format!("({}) {}", status, error_text));
+
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    if rate_limit_waits >= RATE_LIMIT_WAITS {
+                        return Err(error);
+                    }
+                    rate_limit_waits += 1;
+                    attempts -= 1;
+                    delay = requested.unwrap_or(backoff).min(MAX_DELAY);
+                } else if status.is_client_error() && status != reqwest::StatusCode::REQUEST_TIMEOUT {
+                    // Retrying won't help
+                    return Err(error);
+                } else if attempts >= ATTEMPTS {
+                    return Err(error);
+                } else if let Some(requested) = requested {
+                    delay = requested.min(MAX_DELAY);
+                }
+            }
+            Err(e) => {
+                eprintln!("AI request attempt {} failed: {}", attempts, e);
+
+                if attempts >= ATTEMPTS {
+                    return Err(tr(Msg::AiRequestFailed, e));
+                }
+            }
+        }
+
+        // Wait before retry, without holding the provider's permit
+        drop(permit);
+        tokio::time::sleep(delay).await;
+        backoff *= 2;
+    }
+}
+
+// How long the provider asks us to wait: `Retry-After` (seconds or HTTP date), else the
+// OpenAI-style `x-ratelimit-reset-requests` / `-tokens` durations ("1s", "6m0s", "250ms")
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if let Some(value) = header("retry-after") {
+        if let Ok(secs) = value.parse::<f64>() {
+            return Some(Duration::from_secs_f64(secs.max(0.0)));
+        }
+        if let Ok(at) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default());
+        }
+    }
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .into_iter()
+        .filter_map(|name| header(name).and_then(parse_reset_duration))
+        .max()
+}
+
+// Go-style durations such as "1m30s", "0.5s" or "250ms"
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        total += number * match &rest[..unit_end] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        rest = &rest[unit_end..];
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::io::{Read, Write};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    // Answers one connection per response, in order
+    fn serve(responses: &'static [&'static str]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0u8; 4096]).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    fn wire(url: String) -> WireRequest {
+        WireRequest { url, headers: Vec::new(), body: serde_json::json!({}) }
+    }
+
+    #[test]
+    fn wait_comes_from_retry_after_or_the_reset_headers() {
+        assert_eq!(retry_after(&headers(&[("retry-after", "7")])), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after(&headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")])), Some(Duration::ZERO), "date in the past");
+        assert_eq!(
+            retry_after(&headers(&[("x-ratelimit-reset-requests", "1m30s"), ("x-ratelimit-reset-tokens", "250ms")])),
+            Some(Duration::from_secs(90)),
+            "the longer reset wins"
+        );
+        assert_eq!(retry_after(&headers(&[])), None);
+
+        assert_eq!(parse_reset_duration("0.5s"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_reset_duration("5 days"), None);
+        assert_eq!(parse_reset_duration("12"), None, "unit missing");
+    }
+
+    #[test]
+    fn rate_limits_are_waited_out_and_client_errors_fail_at_once() {
+        let client = reqwest::Client::new();
+        let url = serve(&[
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 4\r\nConnection: close\r\n\r\nslow",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+        ]);
+        let sent = tauri::async_runtime::block_on(send(&client, "http://retry.test", &wire(url), Duration::from_secs(5)));
+        assert_eq!(sent.unwrap().0.status(), 200);
+
+        let url = serve(&["HTTP/1.1 401 Unauthorized\r\nContent-Length: 7\r\nConnection: close\r\n\r\nbad key"]);
+        let started = std::time::Instant::now();
+        let sent = tauri::async_runtime::block_on(send(&client, "http://retry.test", &wire(url), Duration::from_secs(5)));
+        assert!(sent.is_err());
+        assert!(started.elapsed() < BASE_DELAY, "no backoff for a bad key");
+    }
+}
@@ -0,0 +1,115 @@
+// Article summaries: the AI summary where a provider is configured, a local
+// brief otherwise: extractive (see `extractive`), or the template when the
+// text is too short to pick sentences from.
+
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+
+use super::{Ai, AiConfig, ChatProvider, ChatRequest};
+use crate::crawler::HttpClients;
+use crate::i18n::{tr, Msg};
+use crate::{extractive, glossary, i18n, GeneratedSummary, SummaryStatus};
+
+pub(crate) async fn summarize_text(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    content: &str,
+    on_delta: impl FnMut(&str) + Send,
+) -> Result<String, String> {
+    let (config, glossary) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (AiConfig::load(&conn)?, glossary::list(&conn)?)
+    };
+
+    // AI APIs usually need proxy for international services
+    // But if using Chinese AI services (like DashScope), they work without proxy
+    let request = ChatRequest {
+        system: format!(
+            "请用中文总结以下内容，控制在100字以内，突出重点信息。{}",
+            glossary::prompt_hint(&glossary, content, i18n::Lang::Zh)
+        ),
+        user: content.to_string(),
+        max_tokens: Some(200),
+        timeout: std::time::Duration::from_secs(30),
+    };
+    Ai::new(http.get(true), &config).complete_stream(&request, on_delta).await
+}
+
+// Helper function to make Chinese brief summary (template as fallback)
+pub(crate) fn make_zh_brief(title: &str, content: &str, _source: &str) -> String {
+    let safe_content = if content.chars().count() > 20 {
+        content.chars().take(20).collect::<String>()
+    } else {
+        content.to_string()
+    };
+    format!("这篇英文资讯围绕「{}」展开，介绍了{}等关键内容。建议点击标题查看原文。", title, safe_content)
+}
+
+// Summary without AI: the article's key sentences, else the template brief
+pub(crate) fn local_brief(title: &str, content: &str, source: &str) -> String {
+    extractive::summarize(content).unwrap_or_else(|| make_zh_brief(title, content, source))
+}
+
+// AI summary when configured, else (or when the AI call fails) the local brief
+pub(crate) async fn summarize_or_template(
+    http: &HttpClients,
+    ai_config: Option<&AiConfig>,
+    glossary: &[glossary::GlossaryEntry],
+    title: &str,
+    content: &str,
+    source: &str,
+) -> GeneratedSummary {
+    let Some(config) = ai_config else {
+        return GeneratedSummary { text: local_brief(title, content, source), status: SummaryStatus::Template, error: None };
+    };
+    match generate_ai_summary(http.get(true), config, glossary, title, content).await {
+        Ok(text) => GeneratedSummary { text, status: SummaryStatus::Ai, error: None },
+        Err(e) => {
+            eprintln!("AI summary failed for '{}', using local brief: {}", title, e);
+            GeneratedSummary { text: local_brief(title, content, source), status: SummaryStatus::Failed, error: Some(e) }
+        }
+    }
+}
+
+// Generate AI summary, retried with backoff by the AI client
+pub(crate) async fn generate_ai_summary(
+    client: &reqwest::Client,
+    config: &AiConfig,
+    glossary: &[glossary::GlossaryEntry],
+    title: &str,
+    content: &str,
+) -> Result<String, String> {
+    // Truncate content to avoid token limits (use chars to avoid UTF-8 boundary issues)
+    let truncated_content = if content.chars().count() > 3000 {
+        content.chars().take(3000).collect::<String>()
+    } else {
+        content.to_string()
+    };
+
+    let request = ChatRequest {
+        system: format!(
+            "请用中文总结以下内容，控制在 100 字以内，突出重点信息。{}",
+            glossary::prompt_hint(glossary, &format!("{} {}", title, truncated_content), i18n::Lang::Zh)
+        ),
+        user: format!("标题：{}\n\n内容：{}", title, truncated_content),
+        max_tokens: Some(200),
+        timeout: std::time::Duration::from_secs(30),
+    };
+    Ai::new(client, config).complete(&request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_brief_quotes_the_start_of_the_content() {
+        let brief = make_zh_brief("Open weights", "abcdefghijklmnopqrstuvwxyz", "Blog");
+        assert!(brief.contains("「Open weights」"));
+        assert!(brief.contains("abcdefghijklmnopqrst等"));
+        assert!(!brief.contains('u'));
+        // Too short to pick sentences from
+        assert_eq!(local_brief("Open weights", "Short.", "Blog"), make_zh_brief("Open weights", "Short.", "Blog"));
+    }
+}
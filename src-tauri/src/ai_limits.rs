@@ -0,0 +1,211 @@
+// Request limits for AI providers.
+//
+// Providers differ a lot in how many requests they accept, so every AI call
+// (summaries, translation, roundup themes) takes a permit from a shared
+// governor first. Limits are configured per provider host, with a default
+// for hosts not listed: `max_concurrent` requests in flight and
+// `requests_per_minute` started in any rolling minute. A caller waits until
+// both allow another request; the permit is returned when it's dropped.
+// The active limits live in memory and are reapplied on save and profile
+// switch, like the language and timezone.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::{get_setting, platform, set_setting};
+
+const WINDOW: Duration = Duration::from_secs(60);
+// How often a caller blocked on concurrency looks again
+const POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderLimit {
+    // Host of the provider's base URL, e.g. `dashscope.aliyuncs.com`
+    pub host: String,
+    pub max_concurrent: u32,
+    // 0 = no per-minute limit
+    pub requests_per_minute: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiLimitsConfig {
+    pub default_max_concurrent: u32,
+    pub default_requests_per_minute: u32,
+    pub providers: Vec<ProviderLimit>,
+}
+
+impl Default for AiLimitsConfig {
+    fn default() -> Self {
+        AiLimitsConfig {
+            default_max_concurrent: platform::crawl_profile().ai_max_concurrent,
+            default_requests_per_minute: 60,
+            providers: Vec::new(),
+        }
+    }
+}
+
+impl AiLimitsConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = AiLimitsConfig::default();
+        Ok(AiLimitsConfig {
+            default_max_concurrent: get_setting(conn, "ai_limits.default_max_concurrent", "")?
+                .parse()
+                .unwrap_or(defaults.default_max_concurrent),
+            default_requests_per_minute: get_setting(conn, "ai_limits.default_requests_per_minute", "")?
+                .parse()
+                .unwrap_or(defaults.default_requests_per_minute),
+            providers: serde_json::from_str(&get_setting(conn, "ai_limits.providers", "[]")?).unwrap_or_default(),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let providers: Vec<ProviderLimit> = self
+            .providers
+            .iter()
+            .map(|p| ProviderLimit { host: p.host.trim().to_lowercase(), ..p.clone() })
+            .filter(|p| !p.host.is_empty())
+            .collect();
+        set_setting(conn, "ai_limits.default_max_concurrent", &self.default_max_concurrent.to_string())?;
+        set_setting(conn, "ai_limits.default_requests_per_minute", &self.default_requests_per_minute.to_string())?;
+        set_setting(conn, "ai_limits.providers", &serde_json::to_string(&providers).unwrap_or_else(|_| "[]".to_string()))?;
+        Ok(())
+    }
+
+    // (max concurrent, requests per minute) for a host; concurrency is at least 1
+    fn limits(&self, host: &str) -> (usize, usize) {
+        let (concurrent, rpm) = self
+            .providers
+            .iter()
+            .find(|p| p.host == host)
+            .map(|p| (p.max_concurrent, p.requests_per_minute))
+            .unwrap_or((self.default_max_concurrent, self.default_requests_per_minute));
+        ((concurrent as usize).max(1), rpm as usize)
+    }
+}
+
+#[derive(Default)]
+struct ProviderState {
+    in_flight: usize,
+    // Start times of requests within the last WINDOW
+    started: VecDeque<Instant>,
+}
+
+static CONFIG: RwLock<Option<AiLimitsConfig>> = RwLock::new(None);
+static PROVIDERS: Mutex<Option<HashMap<String, ProviderState>>> = Mutex::new(None);
+
+pub fn set(config: AiLimitsConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+fn host(base_url: &str) -> String {
+    reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_else(|| base_url.to_lowercase())
+}
+
+// Held while an AI request runs
+pub struct Permit {
+    host: String,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut providers = PROVIDERS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(state) = providers.get_or_insert_with(HashMap::new).get_mut(&self.host) {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+// Wait until the provider behind `base_url` accepts another request
+pub async fn acquire(base_url: &str) -> Permit {
+    let host = host(base_url);
+    loop {
+        let (max_concurrent, rpm) = CONFIG
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|config| config.limits(&host))
+            .unwrap_or_else(|| AiLimitsConfig::default().limits(&host));
+
+        let wait = {
+            let mut providers = PROVIDERS.lock().unwrap_or_else(|e| e.into_inner());
+            let state = providers.get_or_insert_with(HashMap::new).entry(host.clone()).or_default();
+            let now = Instant::now();
+            while state.started.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) {
+                state.started.pop_front();
+            }
+            if state.in_flight >= max_concurrent {
+                POLL
+            } else if rpm > 0 && state.started.len() >= rpm {
+                // The oldest request in the window leaves it first
+                state.started.front().map(|t| WINDOW.saturating_sub(now.duration_since(*t))).unwrap_or(POLL)
+            } else {
+                state.in_flight += 1;
+                state.started.push_back(now);
+                return Permit { host };
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_limits_fall_back_to_the_defaults() {
+        let config = AiLimitsConfig {
+            default_max_concurrent: 0,
+            default_requests_per_minute: 30,
+            providers: vec![ProviderLimit { host: "api.deepseek.com".into(), max_concurrent: 2, requests_per_minute: 0 }],
+        };
+        assert_eq!(config.limits("api.deepseek.com"), (2, 0));
+        assert_eq!(config.limits("api.openai.com"), (1, 30), "concurrency is at least one");
+        assert_eq!(host("https://API.DeepSeek.com/v1"), "api.deepseek.com");
+        assert_eq!(host("not a url"), "not a url");
+    }
+
+    #[test]
+    fn saved_hosts_are_normalized() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        AiLimitsConfig {
+            default_max_concurrent: 3,
+            default_requests_per_minute: 20,
+            providers: vec![
+                ProviderLimit { host: " DashScope.Aliyuncs.com ".into(), max_concurrent: 1, requests_per_minute: 10 },
+                ProviderLimit { host: " ".into(), max_concurrent: 1, requests_per_minute: 10 },
+            ],
+        }
+        .save(&conn)
+        .unwrap();
+        let loaded = AiLimitsConfig::load(&conn).unwrap();
+        assert_eq!((loaded.default_max_concurrent, loaded.default_requests_per_minute), (3, 20));
+        assert_eq!(loaded.providers.len(), 1);
+        assert_eq!(loaded.providers[0].host, "dashscope.aliyuncs.com");
+    }
+
+    #[test]
+    fn permits_wait_for_a_free_slot() {
+        let defaults = AiLimitsConfig::default();
+        set(AiLimitsConfig {
+            providers: vec![ProviderLimit { host: "limits.test".into(), max_concurrent: 1, requests_per_minute: 0 }],
+            ..defaults
+        });
+        tauri::async_runtime::block_on(async {
+            let first = acquire("https://limits.test/v1").await;
+            let blocked = tokio::time::timeout(Duration::from_millis(250), acquire("https://limits.test/v1")).await;
+            assert!(blocked.is_err(), "second request waits while the first runs");
+            drop(first);
+            let second = tokio::time::timeout(Duration::from_secs(2), acquire("https://limits.test/v1")).await;
+            assert!(second.is_ok());
+        });
+    }
+}
@@ -0,0 +1,179 @@
+// Per-article user annotations: a free-form note, text highlights and tags.
+// Used by exporters (Obsidian) and by anything that needs user tags.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::i18n::{tr, Msg};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_notes (
+            article_id TEXT PRIMARY KEY,
+            note TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_highlights (
+            id TEXT PRIMARY KEY,
+            article_id TEXT NOT NULL,
+            text TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_article_highlights_article ON article_highlights(article_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_tags (
+            article_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (article_id, tag)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct Highlight {
+    pub id: String,
+    pub text: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Annotations {
+    pub note: String,
+    pub highlights: Vec<Highlight>,
+    pub tags: Vec<String>,
+}
+
+pub fn load(conn: &Connection, article_id: &str) -> Result<Annotations, String> {
+    let note = conn
+        .query_row(
+            "SELECT note FROM article_notes WHERE article_id = ?1",
+            params![article_id],
+            |row| row.get::<_, String>(0),
+        )
+        .unwrap_or_default();
+
+    let mut stmt = conn
+        .prepare("SELECT id, text, created_at FROM article_highlights WHERE article_id = ?1 ORDER BY created_at ASC")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let highlights = stmt
+        .query_map(params![article_id], |row| {
+            Ok(Highlight {
+                id: row.get(0)?,
+                text: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    Ok(Annotations {
+        note,
+        highlights,
+        tags: load_tags(conn, article_id)?,
+    })
+}
+
+pub fn load_tags(conn: &Connection, article_id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT tag FROM article_tags WHERE article_id = ?1 ORDER BY tag ASC")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let tags = stmt
+        .query_map(params![article_id], |row| row.get::<_, String>(0))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(tags)
+}
+
+// An empty note removes the row
+pub fn set_note(conn: &Connection, article_id: &str, note: &str) -> Result<(), String> {
+    if note.trim().is_empty() {
+        conn.execute("DELETE FROM article_notes WHERE article_id = ?1", params![article_id])
+            .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    } else {
+        conn.execute(
+            "INSERT OR REPLACE INTO article_notes (article_id, note, updated_at) VALUES (?1, ?2, ?3)",
+            params![article_id, note, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    Ok(())
+}
+
+pub fn add_highlight(conn: &Connection, article_id: &str, text: &str) -> Result<Highlight, String> {
+    let highlight = Highlight {
+        id: uuid::Uuid::new_v4().to_string(),
+        text: text.trim().to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO article_highlights (id, article_id, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![highlight.id, article_id, highlight.text, highlight.created_at],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(highlight)
+}
+
+pub fn delete_highlight(conn: &Connection, highlight_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM article_highlights WHERE id = ?1", params![highlight_id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Replaces the full tag set; tags are trimmed, deduplicated and empty ones dropped
+pub fn set_tags(conn: &Connection, article_id: &str, tags: &[String]) -> Result<Vec<String>, String> {
+    conn.execute("DELETE FROM article_tags WHERE article_id = ?1", params![article_id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        add_tag(conn, article_id, tag)?;
+    }
+    load_tags(conn, article_id)
+}
+
+pub fn add_tag(conn: &Connection, article_id: &str, tag: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO article_tags (article_id, tag) VALUES (?1, ?2)",
+        params![article_id, tag],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_highlights_and_tags_round_trip() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        set_note(&conn, "a1", "Check the ablations").unwrap();
+        let highlight = add_highlight(&conn, "a1", "  most of the accuracy  ").unwrap();
+        assert_eq!(highlight.text, "most of the accuracy");
+        let tags: Vec<String> = ["llm ", " ", "evals", "llm"].iter().map(|t| t.to_string()).collect();
+        assert_eq!(set_tags(&conn, "a1", &tags).unwrap(), ["evals", "llm"]);
+
+        let loaded = load(&conn, "a1").unwrap();
+        assert_eq!(loaded.note, "Check the ablations");
+        assert_eq!(loaded.highlights.len(), 1);
+        assert_eq!(loaded.tags, ["evals", "llm"]);
+
+        set_note(&conn, "a1", "  ").unwrap();
+        delete_highlight(&conn, &highlight.id).unwrap();
+        assert!(set_tags(&conn, "a1", &[]).unwrap().is_empty());
+        let cleared = load(&conn, "a1").unwrap();
+        assert!(cleared.note.is_empty() && cleared.highlights.is_empty(), "a blank note removes it");
+        let notes: i64 = conn.query_row("SELECT COUNT(*) FROM article_notes", [], |row| row.get(0)).unwrap();
+        assert_eq!(notes, 0);
+    }
+}
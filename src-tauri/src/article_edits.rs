@@ -0,0 +1,194 @@
+// Manual corrections to article metadata.
+//
+// Scraped items often come with junk titles, the wrong category or no usable
+// date. `article_update` lets the user fix title, category, source label and
+// published date; every changed field is logged in `article_edits` with the
+// value it replaced, so the original is never lost. A corrected title is kept
+// when a re-crawl later updates the article's content.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{datetime, heat, keywords};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_edits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            article_id TEXT NOT NULL,
+            field TEXT NOT NULL,
+            old_value TEXT NOT NULL,
+            new_value TEXT NOT NULL,
+            edited_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_article_edits_article ON article_edits(article_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+// Fields left out (or blank) are not changed
+#[derive(Debug, Deserialize)]
+pub struct ArticleUpdate {
+    pub id: String,
+    pub title: Option<String>,
+    pub category: Option<String>,
+    pub source: Option<String>,
+    // Any format the crawler understands; stored as UTC
+    pub published_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleEdit {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub edited_at: String,
+}
+
+fn given(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+pub fn apply(conn: &Connection, update: &ArticleUpdate) -> Result<(), String> {
+    let (rowid, title, category, source, published_at): (i64, String, String, String, String) = conn
+        .query_row(
+            "SELECT rowid, title, category, source, published_at FROM articles WHERE id = ?1",
+            params![update.id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .ok_or_else(|| t(Msg::ArticleNotFound))?;
+
+    let published = match given(&update.published_at) {
+        Some(value) => Some(datetime::parse(value).ok_or_else(|| tr(Msg::InvalidDateTime, value))?.to_rfc3339()),
+        None => None,
+    };
+    // (column, old, new); the column names are fixed, never user input
+    let changes: Vec<(&str, &str, &str)> = [
+        ("title", title.as_str(), given(&update.title)),
+        ("category", category.as_str(), given(&update.category)),
+        ("source", source.as_str(), given(&update.source)),
+        ("published_at", published_at.as_str(), published.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(field, old, new)| new.filter(|new| *new != old).map(|new| (field, old, new)))
+    .collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    for (field, old, new) in &changes {
+        conn.execute(&format!("UPDATE articles SET {} = ?1 WHERE rowid = ?2", field), params![new, rowid])
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+        conn.execute(
+            "INSERT INTO article_edits (article_id, field, old_value, new_value, edited_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![update.id, field, old, new, now],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+        match *field {
+            "title" => {
+                conn.execute("UPDATE articles_fts SET title = cjk_segment(?1) WHERE rowid = ?2", params![new, rowid])
+                    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+            }
+            "published_at" => {
+                conn.execute("UPDATE articles SET date_is_estimated = 0 WHERE rowid = ?1", params![rowid])
+                    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+            }
+            _ => {}
+        }
+    }
+    heat::rescore_ids(conn, std::slice::from_ref(&update.id))?;
+    keywords::tag_articles(conn, std::slice::from_ref(&update.id))?;
+    Ok(())
+}
+
+// Metadata changes of an article, oldest first
+pub fn list(conn: &Connection, article_id: &str) -> Result<Vec<ArticleEdit>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT field, old_value, new_value, edited_at FROM article_edits WHERE article_id = ?1 ORDER BY id ASC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let edits = stmt
+        .query_map(params![article_id], |row| {
+            Ok(ArticleEdit {
+                field: row.get(0)?,
+                old_value: row.get(1)?,
+                new_value: row.get(2)?,
+                edited_at: datetime::to_display(&row.get::<_, String>(3)?),
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(edits)
+}
+
+// Edit logs of articles no longer stored or in the trash
+pub fn delete_orphans(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM article_edits WHERE article_id NOT IN (SELECT id FROM articles UNION ALL SELECT id FROM articles_trash)",
+        [],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(title: Option<&str>, category: Option<&str>, published_at: Option<&str>) -> ArticleUpdate {
+        ArticleUpdate {
+            id: "a1".to_string(),
+            title: title.map(str::to_string),
+            category: category.map(str::to_string),
+            source: None,
+            published_at: published_at.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn only_changed_fields_are_written_and_logged() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, url, source, category, published_at, date_is_estimated)
+             VALUES ('a1', 'Untitled', '', 'https://example.com/a1', 'Blog', 'AI', '2025-03-01T12:00:00+00:00', 1)",
+            [],
+        )
+        .unwrap();
+
+        apply(&conn, &update(Some(" Agents ship "), Some("AI"), Some("2025-03-02 08:00:00 +0000"))).unwrap();
+        let (title, published_at, estimated): (String, String, i64) = conn
+            .query_row("SELECT title, published_at, date_is_estimated FROM articles WHERE id = 'a1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(title, "Agents ship");
+        assert_eq!(published_at, "2025-03-02T08:00:00+00:00");
+        assert_eq!(estimated, 0, "a date set by hand isn't an estimate");
+
+        let edits: Vec<(String, String)> = list(&conn, "a1").unwrap().into_iter().map(|e| (e.field, e.old_value)).collect();
+        assert_eq!(
+            edits,
+            [("title".to_string(), "Untitled".to_string()), ("published_at".to_string(), "2025-03-01T12:00:00+00:00".to_string())],
+            "unchanged category isn't logged"
+        );
+
+        apply(&conn, &update(Some("  "), None, None)).unwrap();
+        assert_eq!(list(&conn, "a1").unwrap().len(), 2, "blank fields are left alone");
+        assert!(apply(&conn, &update(None, None, Some("someday"))).is_err());
+        assert!(apply(&conn, &ArticleUpdate { id: "missing".to_string(), ..update(Some("x"), None, None) }).is_err());
+
+        conn.execute("DELETE FROM articles", []).unwrap();
+        delete_orphans(&conn).unwrap();
+        assert!(list(&conn, "a1").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+// Full article export, streamed to a file.
+//
+// With retention raised the table can hold tens of thousands of articles
+// with their content, so `articles_export` never loads them all: a
+// background thread reads CHUNK_SIZE rows at a time by rowid cursor, taking
+// the database lock only per chunk, and writes each chunk straight to
+// `<path>.part`, which is renamed into place once complete. Rows inserted
+// after the export started are left out (the cursor stops at the rowid
+// that was highest at the start). Progress is emitted after every chunk;
+// `articles_export_cancel` stops the export between chunks and removes the
+// partial file.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{article_from_row, Article, DbState, ARTICLE_COLUMNS};
+
+const CHUNK_SIZE: i64 = 500;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+// Cancel flags of running exports
+static RUNNING: Mutex<Option<HashMap<u64, Arc<AtomicBool>>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    // One JSON object per line
+    #[default]
+    Jsonl,
+    // A single JSON array
+    Json,
+    // Without content; opens in spreadsheets
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportFilter {
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub bookmarked_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportStarted {
+    pub id: u64,
+    pub path: String,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportProgress {
+    id: u64,
+    written: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportComplete {
+    id: u64,
+    path: String,
+    written: usize,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+const CSV_HEADER: &str = "id,title,url,source,category,published_at,fetched_at,heat_score,is_read,is_bookmarked,summary";
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(article: &Article) -> String {
+    [
+        csv_field(&article.id),
+        csv_field(&article.title),
+        csv_field(&article.url),
+        csv_field(&article.source),
+        csv_field(&article.category),
+        csv_field(&article.published_at),
+        csv_field(&article.fetched_at),
+        article.heat_score.to_string(),
+        (article.is_read as u8).to_string(),
+        (article.is_bookmarked as u8).to_string(),
+        csv_field(&article.summary),
+    ]
+    .join(",")
+}
+
+// Total matching rows and the rowid the cursor stops at
+fn snapshot(conn: &Connection, filter: &ExportFilter) -> Result<(usize, i64), String> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(MAX(rowid), 0) FROM articles
+         WHERE (?1 IS NULL OR source = ?1) AND (?2 = 0 OR is_bookmarked = 1)",
+        params![filter.source, filter.bookmarked_only],
+        |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)),
+    )
+    .map_err(|e| tr(Msg::DbQueryFailed, e))
+}
+
+// The next chunk after rowid `after`, with the rowid of its last row
+fn chunk(conn: &Connection, filter: &ExportFilter, after: i64, last: i64) -> Result<(Vec<Article>, i64), String> {
+    // rowid goes after the columns article_from_row reads
+    let rowid_index = ARTICLE_COLUMNS.split(',').count();
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {}, rowid FROM articles
+             WHERE rowid > ?1 AND rowid <= ?2 AND (?3 IS NULL OR source = ?3) AND (?4 = 0 OR is_bookmarked = 1)
+             ORDER BY rowid LIMIT ?5",
+            ARTICLE_COLUMNS
+        ))
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map(params![after, last, filter.source, filter.bookmarked_only, CHUNK_SIZE], |row| {
+            Ok((article_from_row(row)?, row.get::<_, i64>(rowid_index)?))
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    let cursor = rows.last().map(|(_, rowid)| *rowid).unwrap_or(after);
+    Ok((rows.into_iter().map(|(article, _)| article).collect(), cursor))
+}
+
+fn write_err(path: &str, e: impl std::fmt::Display) -> String {
+    tr(Msg::WriteFileFailed, format!("{}: {}", path, e))
+}
+
+fn run(app: &AppHandle, id: u64, job: &Job, cancel: &AtomicBool) -> Result<(usize, bool), String> {
+    let part = format!("{}.part", job.path);
+    let file = File::create(&part).map_err(|e| write_err(&part, e))?;
+    let mut out = BufWriter::new(file);
+    let state = app.state::<DbState>();
+
+    match job.format {
+        ExportFormat::Csv => writeln!(out, "{}", CSV_HEADER),
+        ExportFormat::Json => write!(out, "["),
+        ExportFormat::Jsonl => Ok(()),
+    }
+    .map_err(|e| write_err(&part, e))?;
+
+    let mut after = 0;
+    let mut written = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            drop(out);
+            let _ = std::fs::remove_file(&part);
+            return Ok((written, true));
+        }
+        let (articles, cursor) = {
+            let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+            chunk(&conn, &job.filter, after, job.last_rowid)?
+        };
+        if articles.is_empty() {
+            break;
+        }
+        for article in &articles {
+            match job.format {
+                ExportFormat::Jsonl => serde_json::to_writer(&mut out, article)
+                    .map_err(|e| write_err(&part, e))
+                    .and_then(|_| writeln!(out).map_err(|e| write_err(&part, e)))?,
+                ExportFormat::Json => {
+                    write!(out, "{}", if written == 0 { "\n" } else { ",\n" }).map_err(|e| write_err(&part, e))?;
+                    serde_json::to_writer(&mut out, article).map_err(|e| write_err(&part, e))?;
+                }
+                ExportFormat::Csv => writeln!(out, "{}", csv_row(article)).map_err(|e| write_err(&part, e))?,
+            }
+            written += 1;
+        }
+        after = cursor;
+        let _ = app.emit("app://articles-export:progress", ExportProgress { id, written, total: job.total });
+    }
+
+    if let ExportFormat::Json = job.format {
+        writeln!(out, "\n]").map_err(|e| write_err(&part, e))?;
+    }
+    out.flush().map_err(|e| write_err(&part, e))?;
+    drop(out);
+    std::fs::rename(&part, &job.path).map_err(|e| write_err(&job.path, e))?;
+    Ok((written, false))
+}
+
+struct Job {
+    path: String,
+    format: ExportFormat,
+    filter: ExportFilter,
+    total: usize,
+    last_rowid: i64,
+}
+
+// Count what will be exported and start writing it in the background
+pub fn start(app: AppHandle, conn: &Connection, path: String, format: ExportFormat, filter: ExportFilter) -> Result<ExportStarted, String> {
+    let (total, last_rowid) = snapshot(conn, &filter)?;
+    if total == 0 {
+        return Err(t(Msg::ExportNothingSelected));
+    }
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = Arc::new(AtomicBool::new(false));
+    RUNNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(id, cancel.clone());
+    let started = ExportStarted { id, path: path.clone(), total };
+    let job = Job { path, format, filter, total, last_rowid };
+
+    std::thread::spawn(move || {
+        let result = run(&app, id, &job, &cancel);
+        if let Some(running) = RUNNING.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            running.remove(&id);
+        }
+        let complete = match result {
+            Ok((written, cancelled)) => ExportComplete { id, path: job.path, written, cancelled, error: None },
+            Err(error) => {
+                let _ = std::fs::remove_file(format!("{}.part", job.path));
+                ExportComplete { id, path: job.path, written: 0, cancelled: false, error: Some(error) }
+            }
+        };
+        let _ = app.emit("app://articles-export:complete", complete);
+    });
+    Ok(started)
+}
+
+// Ask a running export to stop; false if it isn't running (anymore)
+pub fn cancel(id: u64) -> bool {
+    match RUNNING.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|running| running.get(&id)) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str, source: &str, bookmarked: bool) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at, is_bookmarked)
+             VALUES (?1, ?1, '', '', ?1, ?2, 'AI', '2025-03-01T12:00:00+00:00', '2025-03-01T12:00:00+00:00', ?3)",
+            params![id, source, bookmarked],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\"\nbye"), "\"say \"\"hi\"\"\nbye\"");
+    }
+
+    #[test]
+    fn cursor_walks_chunks_and_stops_at_the_snapshot() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for i in 0..(CHUNK_SIZE + 3) {
+            add_article(&conn, &format!("https://example.com/{}", i), if i % 2 == 0 { "Blog" } else { "Lab" }, i == 1);
+        }
+        let all = ExportFilter::default();
+        let (total, last) = snapshot(&conn, &all).unwrap();
+        assert_eq!(total, CHUNK_SIZE as usize + 3);
+        add_article(&conn, "https://example.com/late", "Blog", false);
+
+        let (first, cursor) = chunk(&conn, &all, 0, last).unwrap();
+        assert_eq!(first.len(), CHUNK_SIZE as usize);
+        let (rest, cursor) = chunk(&conn, &all, cursor, last).unwrap();
+        assert_eq!(rest.len(), 3, "the article added after the start is left out");
+        assert!(chunk(&conn, &all, cursor, last).unwrap().0.is_empty());
+
+        let bookmarked = ExportFilter { source: Some("Lab".to_string()), bookmarked_only: true };
+        assert_eq!(snapshot(&conn, &bookmarked).unwrap().0, 1);
+        let (only, _) = chunk(&conn, &bookmarked, 0, last).unwrap();
+        assert_eq!(only[0].id, "https://example.com/1");
+        assert!(!cancel(u64::MAX), "not running");
+    }
+}
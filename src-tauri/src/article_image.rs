@@ -0,0 +1,184 @@
+// Card image fallback for articles whose feed item carried no image.
+//
+// Most RSS items have no enclosure and many pages no og:image. Whenever a
+// page's full content is fetched (manual add, EPUB / Kindle exports), the
+// in-article images are probed in document order and the first one at
+// least `MIN_WIDTH` x `MIN_HEIGHT` becomes the article's `image_url`, which
+// skips icons, avatars and tracking pixels. Dimensions come from the image
+// header, so only the first few KB of each candidate are downloaded.
+
+use reqwest::header::RANGE;
+use rusqlite::{params, Connection};
+
+use crate::i18n::{tr, Msg};
+use crate::{low_data, HttpClients};
+
+const MIN_WIDTH: u32 = 300;
+const MIN_HEIGHT: u32 = 150;
+// Candidates probed per article
+const MAX_CANDIDATES: usize = 6;
+// Enough for the header of every supported format, including JPEGs with EXIF blocks
+const PROBE_BYTES: usize = 64 * 1024;
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+// Width and height from the start of a PNG, GIF, JPEG or WebP file
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+        return Some((width, height));
+    }
+    if bytes.starts_with(b"GIF8") {
+        return Some((le16(bytes, 6)?, le16(bytes, 8)?));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => Some((le16(bytes, 26)? & 0x3fff, le16(bytes, 28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((le24(bytes, 24)? + 1, le24(bytes, 27)? + 1)),
+            _ => None,
+        };
+    }
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        // Walk the segments up to the first start-of-frame marker
+        let mut pos = 2;
+        while pos + 4 <= bytes.len() {
+            if bytes[pos] != 0xff {
+                return None;
+            }
+            let marker = bytes[pos + 1];
+            if marker == 0xff {
+                pos += 1;
+                continue;
+            }
+            let length = be16(bytes, pos + 2)? as usize;
+            let is_frame = matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc);
+            if is_frame {
+                return Some((be16(bytes, pos + 7)?, be16(bytes, pos + 5)?));
+            }
+            pos += 2 + length;
+        }
+    }
+    None
+}
+
+// Dimensions of a remote image from its first bytes
+async fn probe(client: &reqwest::Client, url: &str) -> Option<(u32, u32)> {
+    let mut response = client
+        .get(url)
+        .header(RANGE, format!("bytes=0-{}", PROBE_BYTES - 1))
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    // Servers that ignore Range send the whole file; stop reading once the header is in
+    let mut bytes = Vec::new();
+    while bytes.len() < PROBE_BYTES {
+        match response.chunk().await.ok()? {
+            Some(chunk) => bytes.extend_from_slice(&chunk),
+            None => break,
+        }
+        if let Some(size) = dimensions(&bytes) {
+            return Some(size);
+        }
+    }
+    dimensions(&bytes)
+}
+
+// First candidate image large enough for a card
+pub async fn first_large_image(http: &HttpClients, candidates: &[String]) -> Option<String> {
+    if low_data::enabled() {
+        return None;
+    }
+    let candidates = candidates
+        .iter()
+        .filter(|url| url.starts_with("http") && !url.to_lowercase().split('?').next().unwrap_or("").ends_with(".svg"))
+        .take(MAX_CANDIDATES);
+    for url in candidates {
+        if let Some((width, height)) = probe(http.for_url(url), url).await {
+            if width >= MIN_WIDTH && height >= MIN_HEIGHT {
+                return Some(url.clone());
+            }
+        }
+    }
+    None
+}
+
+// Set the card image unless the article already has one
+pub fn store_if_missing(conn: &Connection, article_id: &str, image_url: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE articles SET image_url = ?1 WHERE id = ?2 AND (image_url IS NULL OR image_url = '')",
+        params![image_url, article_id],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimensions_read_from_image_headers() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&360u32.to_be_bytes());
+        assert_eq!(dimensions(&png), Some((640, 360)));
+        assert_eq!(dimensions(&png[..20]), None, "truncated header");
+
+        assert_eq!(dimensions(b"GIF89a\x80\x02\x68\x01"), Some((640, 360)));
+
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend_from_slice(&[0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0x68, 0x02, 0x80]);
+        assert_eq!(dimensions(&jpeg), Some((640, 360)), "APP0 skipped, size from SOF0");
+        let mut huffman_first = vec![0xff, 0xd8, 0xff, 0xc4, 0x00, 0x02];
+        huffman_first.extend_from_slice(&jpeg[2..]);
+        assert_eq!(dimensions(&huffman_first), Some((640, 360)), "DHT isn't a frame");
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0x7f, 0x02, 0x00, 0x67, 0x01, 0x00]);
+        assert_eq!(dimensions(&webp), Some((640, 360)));
+
+        assert_eq!(dimensions(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None);
+    }
+
+    #[test]
+    fn existing_card_image_is_kept() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source, image_url)
+             VALUES ('a1', 'One', 'https://example.com/1', 'Blog', ''),
+                    ('a2', 'Two', 'https://example.com/2', 'Blog', 'https://example.com/og.png')",
+            [],
+        )
+        .unwrap();
+        store_if_missing(&conn, "a1", "https://example.com/figure.png").unwrap();
+        store_if_missing(&conn, "a2", "https://example.com/figure.png").unwrap();
+        let images: Vec<String> = conn
+            .prepare("SELECT image_url FROM articles ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(images, ["https://example.com/figure.png", "https://example.com/og.png"]);
+    }
+}
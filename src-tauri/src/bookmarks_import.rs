@@ -0,0 +1,292 @@
+// Import browser bookmarks: Chrome's `Bookmarks` JSON file, Firefox's JSON
+// backup, or the Netscape HTML export both browsers produce.
+//
+// Parsing is synchronous and cheap, so the folder list can be previewed
+// first; the import itself runs in the background through the `manual_add`
+// pipeline and reports progress with `app://bookmarks-import:*` events.
+
+use std::collections::{BTreeMap, HashSet};
+
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::i18n::{tr, Msg};
+use crate::{add_article_from_url, normalize_url, DbState, HttpClients};
+
+const FOLDER_SEPARATOR: &str = " / ";
+const IMPORT_SOURCE: &str = "书签导入";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserBookmark {
+    pub title: String,
+    pub url: String,
+    // Folder path from the root, joined with " / "
+    pub folder: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderCount {
+    pub folder: String,
+    pub count: usize,
+}
+
+pub fn parse_file(path: &str) -> Result<Vec<BrowserBookmark>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| tr(Msg::ReadFileFailed, format!("{}: {}", path, e)))?;
+    let bookmarks = if text.trim_start().starts_with('{') {
+        let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| tr(Msg::BookmarksParseFailed, e))?;
+        let mut out = Vec::new();
+        if let Some(roots) = json.get("roots").and_then(|r| r.as_object()) {
+            for root in roots.values() {
+                walk_chrome(root, "", &mut out);
+            }
+        } else if json.get("children").is_some() {
+            walk_firefox(&json, "", &mut out);
+        } else {
+            return Err(tr(Msg::BookmarksParseFailed, "unknown JSON layout"));
+        }
+        out
+    } else {
+        parse_html(&text)
+    };
+    Ok(bookmarks
+        .into_iter()
+        .filter(|b| b.url.starts_with("http://") || b.url.starts_with("https://"))
+        .collect())
+}
+
+fn join_folder(parent: &str, name: &str) -> String {
+    match (parent.is_empty(), name.trim().is_empty()) {
+        (_, true) => parent.to_string(),
+        (true, false) => name.trim().to_string(),
+        (false, false) => format!("{}{}{}", parent, FOLDER_SEPARATOR, name.trim()),
+    }
+}
+
+// Chrome: {"type": "folder", "name", "children"} / {"type": "url", "name", "url"}
+fn walk_chrome(node: &serde_json::Value, folder: &str, out: &mut Vec<BrowserBookmark>) {
+    let name = node["name"].as_str().unwrap_or("");
+    match node["type"].as_str() {
+        Some("url") => out.push(BrowserBookmark {
+            title: name.to_string(),
+            url: node["url"].as_str().unwrap_or("").to_string(),
+            folder: folder.to_string(),
+        }),
+        Some("folder") => {
+            let path = join_folder(folder, name);
+            for child in node["children"].as_array().into_iter().flatten() {
+                walk_chrome(child, &path, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Firefox: {"type": "text/x-moz-place-container", "title", "children"} / {"type": "text/x-moz-place", "title", "uri"}
+fn walk_firefox(node: &serde_json::Value, folder: &str, out: &mut Vec<BrowserBookmark>) {
+    let title = node["title"].as_str().unwrap_or("");
+    match node["type"].as_str() {
+        Some("text/x-moz-place") => out.push(BrowserBookmark {
+            title: title.to_string(),
+            url: node["uri"].as_str().unwrap_or("").to_string(),
+            folder: folder.to_string(),
+        }),
+        _ => {
+            // The unnamed root container doesn't add a path segment
+            let path = join_folder(folder, title);
+            for child in node["children"].as_array().into_iter().flatten() {
+                walk_firefox(child, &path, out);
+            }
+        }
+    }
+}
+
+// Netscape format: <DT><H3>Folder</H3><DL> ... <DT><A HREF="...">Title</A> ... </DL>.
+// After HTML parsing each nested <DL> ends up inside the <DT> holding its <H3>,
+// so a link's folder path is the <H3> of every enclosing <DT>.
+fn parse_html(html: &str) -> Vec<BrowserBookmark> {
+    let document = Html::parse_document(html);
+    let Ok(links) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+    document
+        .select(&links)
+        .map(|a| {
+            let mut segments: Vec<String> = a
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .filter(|el| el.value().name() == "dt")
+                .filter_map(|dt| dt.children().filter_map(ElementRef::wrap).find(|c| c.value().name() == "h3"))
+                .map(|h3| h3.text().collect::<String>().trim().to_string())
+                .collect();
+            segments.reverse();
+            BrowserBookmark {
+                title: a.text().collect::<String>().trim().to_string(),
+                url: a.value().attr("href").unwrap_or("").trim().to_string(),
+                folder: segments.join(FOLDER_SEPARATOR),
+            }
+        })
+        .collect()
+}
+
+pub fn folder_counts(bookmarks: &[BrowserBookmark]) -> Vec<FolderCount> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for bookmark in bookmarks {
+        *counts.entry(bookmark.folder.as_str()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(folder, count)| FolderCount { folder: folder.to_string(), count })
+        .collect()
+}
+
+// Keep bookmarks in the selected folders (or their subfolders), one per URL; no selection keeps all
+pub fn select(bookmarks: Vec<BrowserBookmark>, folders: &[String]) -> Vec<BrowserBookmark> {
+    let mut seen = HashSet::new();
+    bookmarks
+        .into_iter()
+        .filter(|b| {
+            folders.is_empty()
+                || folders
+                    .iter()
+                    .any(|f| b.folder == *f || b.folder.starts_with(&format!("{}{}", f, FOLDER_SEPARATOR)))
+        })
+        .filter(|b| seen.insert(normalize_url(&b.url)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportFailure {
+    pub url: String,
+    pub title: String,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ImportReport {
+    pub total: usize,
+    pub added: usize,
+    // Already in the library before the import
+    pub duplicates: Vec<String>,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImportProgressEvent {
+    current: usize,
+    total: usize,
+    title: String,
+    added: usize,
+    duplicates: usize,
+    failed: usize,
+}
+
+pub fn spawn_import(app: AppHandle, bookmarks: Vec<BrowserBookmark>) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<DbState>();
+        let http = app.state::<HttpClients>();
+        let mut report = ImportReport {
+            total: bookmarks.len(),
+            ..Default::default()
+        };
+        let _ = app.emit("app://bookmarks-import:start", report.total);
+
+        for (index, bookmark) in bookmarks.into_iter().enumerate() {
+            match add_article_from_url(&state.conn, &http, &bookmark.url, IMPORT_SOURCE).await {
+                Ok(Some(_)) => report.added += 1,
+                Ok(None) => report.duplicates.push(bookmark.url.clone()),
+                Err(error) => report.failed.push(ImportFailure {
+                    url: bookmark.url.clone(),
+                    title: bookmark.title.clone(),
+                    error: error.to_string(),
+                }),
+            }
+            let _ = app.emit(
+                "app://bookmarks-import:progress",
+                ImportProgressEvent {
+                    current: index + 1,
+                    total: report.total,
+                    title: bookmark.title,
+                    added: report.added,
+                    duplicates: report.duplicates.len(),
+                    failed: report.failed.len(),
+                },
+            );
+        }
+
+        let _ = app.emit("app://bookmarks-import:complete", report);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(name: &str, contents: &str) -> Vec<BrowserBookmark> {
+        let path = std::env::temp_dir().join(format!("news-bookmarks-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        let bookmarks = parse_file(&path.to_string_lossy());
+        let _ = std::fs::remove_file(&path);
+        bookmarks.unwrap()
+    }
+
+    fn paths(bookmarks: &[BrowserBookmark]) -> Vec<(String, String)> {
+        bookmarks.iter().map(|b| (b.folder.clone(), b.url.clone())).collect()
+    }
+
+    #[test]
+    fn reads_chrome_firefox_and_netscape_exports() {
+        let chrome = parsed(
+            "chrome.json",
+            r#"{"roots": {"bookmark_bar": {"type": "folder", "name": "Bookmarks bar", "children": [
+                {"type": "folder", "name": "AI", "children": [{"type": "url", "name": "Paper", "url": "https://example.com/paper"}]},
+                {"type": "url", "name": "Settings", "url": "chrome://settings"}]}}}"#,
+        );
+        assert_eq!(paths(&chrome), [("Bookmarks bar / AI".to_string(), "https://example.com/paper".to_string())]);
+
+        let firefox = parsed(
+            "firefox.json",
+            r#"{"title": "", "type": "text/x-moz-place-container", "children": [
+                {"title": "Reading", "type": "text/x-moz-place-container", "children": [
+                    {"title": "Post", "type": "text/x-moz-place", "uri": "https://example.com/post"}]}]}"#,
+        );
+        assert_eq!(paths(&firefox), [("Reading".to_string(), "https://example.com/post".to_string())]);
+
+        let netscape = parsed(
+            "bookmarks.html",
+            r#"<!DOCTYPE NETSCAPE-Bookmark-file-1><DL><p>
+                <DT><H3>AI</H3><DL><p>
+                    <DT><H3>Papers</H3><DL><p><DT><A HREF="https://example.com/a">A</A></DL><p>
+                    <DT><A HREF=" https://example.com/b ">B</A>
+                </DL><p>
+                <DT><A HREF="https://example.com/c">C</A>
+            </DL>"#,
+        );
+        assert_eq!(
+            paths(&netscape),
+            [
+                ("AI / Papers".to_string(), "https://example.com/a".to_string()),
+                ("AI".to_string(), "https://example.com/b".to_string()),
+                (String::new(), "https://example.com/c".to_string()),
+            ]
+        );
+        assert!(parse_file("/nonexistent/bookmarks.html").is_err());
+    }
+
+    #[test]
+    fn selected_folders_include_subfolders_and_skip_repeated_urls() {
+        let bookmark = |folder: &str, url: &str| BrowserBookmark { title: String::new(), url: url.to_string(), folder: folder.to_string() };
+        let bookmarks = vec![
+            bookmark("AI", "https://example.com/a"),
+            bookmark("AI / Papers", "https://example.com/b"),
+            bookmark("AI Weekly", "https://example.com/c"),
+            bookmark("Later", "https://example.com/a/"),
+        ];
+        let counts: Vec<(String, usize)> = folder_counts(&bookmarks).into_iter().map(|c| (c.folder, c.count)).collect();
+        assert_eq!(counts.len(), 4);
+
+        let selected = select(bookmarks.clone(), &["AI".to_string()]);
+        assert_eq!(selected.iter().map(|b| b.url.as_str()).collect::<Vec<_>>(), ["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(select(bookmarks, &[]).len(), 3, "the trailing-slash copy is the same URL");
+    }
+}
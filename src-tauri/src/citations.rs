@@ -0,0 +1,419 @@
+// Reference-manager export for research papers: BibTeX files, or items
+// pushed straight into a running Zotero through its local connector.
+//
+// Papers are recognized by URL (arXiv, OpenReview, ACL Anthology, ...).
+// arXiv items are enriched from the arXiv API with authors, date, abstract
+// and DOI; others are exported from the stored title, link and date.
+
+use std::collections::{HashMap, HashSet};
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+
+use crate::i18n::{t, tr, Msg};
+use crate::{Article, HttpClients};
+
+const ARXIV_API: &str = "https://export.arxiv.org/api/query";
+const ZOTERO_CONNECTOR: &str = "http://127.0.0.1:23119/connector";
+
+// Hosts whose pages are papers (or paper landing pages)
+const PAPER_HOSTS: [&str; 8] = [
+    "arxiv.org",
+    "openreview.net",
+    "aclanthology.org",
+    "papers.nips.cc",
+    "proceedings.mlr.press",
+    "paperswithcode.com",
+    "doi.org",
+    "semanticscholar.org",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Paper {
+    pub title: String,
+    pub authors: Vec<String>,
+    // YYYY-MM-DD
+    pub date: String,
+    pub abstract_note: String,
+    pub url: String,
+    pub arxiv_id: Option<String>,
+    pub doi: Option<String>,
+    pub primary_class: Option<String>,
+}
+
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_lowercase()))
+        .unwrap_or_default()
+}
+
+pub fn is_paper(article: &Article) -> bool {
+    let host = host(&article.url);
+    article.category.eq_ignore_ascii_case("paper")
+        || PAPER_HOSTS.iter().any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+        || (host == "huggingface.co" && article.url.contains("/papers/"))
+}
+
+// arXiv identifier (without version) from abs/pdf/html links and Hugging Face paper pages
+pub fn arxiv_id(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = host(url);
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let raw = match (host.as_str(), segments.as_slice()) {
+        ("arxiv.org" | "export.arxiv.org", ["abs" | "pdf" | "html", rest @ ..]) if !rest.is_empty() => rest.join("/"),
+        ("huggingface.co", ["papers", id, ..]) => id.to_string(),
+        _ => return None,
+    };
+    let id = raw.trim_end_matches(".pdf");
+    let id = match id.rfind('v') {
+        Some(pos) if pos > 0 && id[pos + 1..].chars().all(|c| c.is_ascii_digit()) && pos + 1 < id.len() => &id[..pos],
+        _ => id,
+    };
+    let valid = id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '/' | '-'))
+        && id.chars().any(|c| c.is_ascii_digit());
+    valid.then(|| id.to_string())
+}
+
+// Papers among `articles`, with arXiv metadata filled in where the API answers
+pub async fn collect(http: &HttpClients, articles: &[Article]) -> Vec<Paper> {
+    let mut papers: Vec<Paper> = articles
+        .iter()
+        .filter(|a| is_paper(a))
+        .map(|a| Paper {
+            title: a.title.clone(),
+            date: a.published_at.get(..10).unwrap_or(&a.published_at).to_string(),
+            abstract_note: a.summary.clone(),
+            url: a.url.clone(),
+            arxiv_id: arxiv_id(&a.url),
+            ..Default::default()
+        })
+        .collect();
+
+    let ids: Vec<String> = papers.iter().filter_map(|p| p.arxiv_id.clone()).collect();
+    if ids.is_empty() {
+        return papers;
+    }
+    // Metadata is optional: on failure the stored fields are exported as-is
+    let metadata = match fetch_arxiv(http, &ids).await {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            eprintln!("arXiv metadata lookup failed: {}", e);
+            return papers;
+        }
+    };
+    for paper in &mut papers {
+        if let Some(meta) = paper.arxiv_id.as_ref().and_then(|id| metadata.get(id)) {
+            paper.title = meta.title.clone();
+            paper.authors = meta.authors.clone();
+            paper.date = meta.date.clone();
+            paper.abstract_note = meta.abstract_note.clone();
+            paper.doi = meta.doi.clone();
+            paper.primary_class = meta.primary_class.clone();
+        }
+    }
+    papers
+}
+
+async fn fetch_arxiv(http: &HttpClients, ids: &[String]) -> Result<HashMap<String, Paper>, String> {
+    let response = http
+        .get(true)
+        .get(ARXIV_API)
+        .query(&[("id_list", ids.join(",")), ("max_results", ids.len().to_string())])
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?;
+    let xml = response.text().await.map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    Ok(parse_arxiv_feed(&xml)
+        .into_iter()
+        .filter_map(|p| p.arxiv_id.clone().map(|id| (id, p)))
+        .collect())
+}
+
+// arXiv API responses are Atom: one <entry> per paper
+fn parse_arxiv_feed(xml: &str) -> Vec<Paper> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut papers = Vec::new();
+    let mut current: Option<Paper> = None;
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "entry" {
+                    current = Some(Paper::default());
+                }
+                path.push(name);
+            }
+            Ok(Event::Empty(e)) => {
+                if let Some(paper) = current.as_mut() {
+                    if e.name().as_ref() == b"arxiv:primary_category" {
+                        paper.primary_class = e
+                            .try_get_attribute("term")
+                            .ok()
+                            .flatten()
+                            .and_then(|a| a.decode_and_unescape_value(reader.decoder()).ok())
+                            .map(|v| v.to_string());
+                    }
+                }
+            }
+            Ok(Event::Text(text)) => {
+                let (Some(paper), Ok(text)) = (current.as_mut(), text.unescape()) else { continue };
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                match path.iter().rev().take(2).map(String::as_str).collect::<Vec<_>>().as_slice() {
+                    ["id", "entry"] => {
+                        paper.url = text.clone();
+                        paper.arxiv_id = arxiv_id(&text);
+                    }
+                    ["title", "entry"] => paper.title = text,
+                    ["summary", "entry"] => paper.abstract_note = text,
+                    ["published", "entry"] => paper.date = text.get(..10).unwrap_or(&text).to_string(),
+                    ["arxiv:doi", "entry"] => paper.doi = Some(text),
+                    ["name", "author"] => paper.authors.push(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"entry" {
+                    papers.extend(current.take());
+                }
+                path.pop();
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    papers
+}
+
+// "Ada Lovelace" -> ("Ada", "Lovelace"); "Lovelace, Ada" is taken as already split
+fn split_name(name: &str) -> (String, String) {
+    if let Some((last, first)) = name.split_once(',') {
+        return (first.trim().to_string(), last.trim().to_string());
+    }
+    match name.trim().rsplit_once(' ') {
+        Some((first, last)) => (first.trim().to_string(), last.to_string()),
+        None => (String::new(), name.trim().to_string()),
+    }
+}
+
+fn bibtex_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// authorYEARfirstword, e.g. vaswani2017attention
+fn citation_key(paper: &Paper) -> String {
+    let ascii_word = |s: &str| -> String { s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase() };
+    let author = paper.authors.first().map(|a| ascii_word(&split_name(a).1)).unwrap_or_default();
+    let year = paper.date.get(..4).unwrap_or("");
+    let word = paper
+        .title
+        .split_whitespace()
+        .map(ascii_word)
+        .find(|w| w.len() > 3 && !matches!(w.as_str(), "with" | "from" | "towards" | "that" | "this"))
+        .unwrap_or_default();
+    match &paper.arxiv_id {
+        _ if !author.is_empty() || !word.is_empty() => format!("{}{}{}", author, year, word),
+        Some(id) => format!("arxiv{}", ascii_word(id)),
+        None => format!("paper{}", year),
+    }
+}
+
+pub fn to_bibtex(papers: &[Paper]) -> String {
+    let mut used: HashSet<String> = HashSet::new();
+    let mut out = String::new();
+    for paper in papers {
+        let base = citation_key(paper);
+        let mut key = base.clone();
+        let mut suffix = b'a';
+        while !used.insert(key.clone()) {
+            key = format!("{}{}", base, suffix as char);
+            suffix = suffix.saturating_add(1);
+        }
+
+        let mut fields: Vec<(&str, String)> = vec![("title", format!("{{{}}}", bibtex_escape(&paper.title)))];
+        if !paper.authors.is_empty() {
+            let authors: Vec<String> = paper
+                .authors
+                .iter()
+                .map(|a| match split_name(a) {
+                    (first, last) if first.is_empty() => bibtex_escape(&last),
+                    (first, last) => format!("{}, {}", bibtex_escape(&last), bibtex_escape(&first)),
+                })
+                .collect();
+            fields.push(("author", authors.join(" and ")));
+        }
+        if let Some(year) = paper.date.get(..4) {
+            fields.push(("year", year.to_string()));
+        }
+        if let Some(id) = &paper.arxiv_id {
+            fields.push(("eprint", id.clone()));
+            fields.push(("archivePrefix", "arXiv".to_string()));
+            if let Some(class) = &paper.primary_class {
+                fields.push(("primaryClass", class.clone()));
+            }
+        }
+        if let Some(doi) = &paper.doi {
+            fields.push(("doi", doi.clone()));
+        }
+        fields.push(("url", paper.url.clone()));
+        if !paper.abstract_note.is_empty() {
+            fields.push(("abstract", bibtex_escape(&paper.abstract_note)));
+        }
+
+        out.push_str(&format!("@misc{{{},\n", key));
+        for (name, value) in fields {
+            out.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+        out.push_str("}\n\n");
+    }
+    out
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZoteroCreator {
+    first_name: String,
+    last_name: String,
+    creator_type: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZoteroItem {
+    item_type: &'static str,
+    title: String,
+    creators: Vec<ZoteroCreator>,
+    date: String,
+    url: String,
+    abstract_note: String,
+    #[serde(rename = "DOI", skip_serializing_if = "Option::is_none")]
+    doi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository: Option<&'static str>,
+}
+
+// Save papers into the library selected in the running Zotero desktop app
+pub async fn push_to_zotero(http: &HttpClients, papers: &[Paper]) -> Result<(), String> {
+    // Zotero binds the connector to localhost; a system proxy would intercept it
+    let client = http.get(false);
+    client
+        .get(format!("{}/ping", ZOTERO_CONNECTOR))
+        .timeout(std::time::Duration::from_secs(3))
+        .send()
+        .await
+        .map_err(|_| t(Msg::ZoteroNotRunning))?;
+
+    let items: Vec<ZoteroItem> = papers
+        .iter()
+        .map(|p| ZoteroItem {
+            item_type: "preprint",
+            title: p.title.clone(),
+            creators: p
+                .authors
+                .iter()
+                .map(|a| {
+                    let (first_name, last_name) = split_name(a);
+                    ZoteroCreator { first_name, last_name, creator_type: "author" }
+                })
+                .collect(),
+            date: p.date.clone(),
+            url: p.url.clone(),
+            abstract_note: p.abstract_note.clone(),
+            doi: p.doi.clone(),
+            archive_id: p.arxiv_id.as_ref().map(|id| format!("arXiv:{}", id)),
+            repository: p.arxiv_id.as_ref().map(|_| "arXiv"),
+        })
+        .collect();
+
+    let response = client
+        .post(format!("{}/saveItems", ZOTERO_CONNECTOR))
+        .header("X-Zotero-Connector-API-Version", "3")
+        .json(&serde_json::json!({ "items": items, "uri": papers.first().map(|p| p.url.as_str()).unwrap_or("") }))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ZoteroSaveFailed, e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(tr(Msg::ZoteroSaveFailed, format!("{}: {}", status, body)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARXIV_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+  <title>ArXiv Query</title>
+  <entry>
+    <id>http://arxiv.org/abs/1706.03762v7</id>
+    <published>2017-06-12T17:57:34Z</published>
+    <title>Attention Is All
+      You Need</title>
+    <summary>The dominant sequence transduction models &amp; more.</summary>
+    <author><name>Ashish Vaswani</name></author>
+    <author><name>Noam Shazeer</name></author>
+    <arxiv:doi>10.48550/arXiv.1706.03762</arxiv:doi>
+    <arxiv:primary_category term="cs.CL" scheme="http://arxiv.org/schemas/atom"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn arxiv_ids_come_from_paper_links_without_the_version() {
+        assert_eq!(arxiv_id("https://arxiv.org/abs/1706.03762v7").as_deref(), Some("1706.03762"));
+        assert_eq!(arxiv_id("https://arxiv.org/pdf/2401.00001v2.pdf").as_deref(), Some("2401.00001"));
+        assert_eq!(arxiv_id("https://export.arxiv.org/abs/hep-th/9901001").as_deref(), Some("hep-th/9901001"));
+        assert_eq!(arxiv_id("https://huggingface.co/papers/2401.00001").as_deref(), Some("2401.00001"));
+        assert_eq!(arxiv_id("https://arxiv.org/list/cs.CL/recent"), None);
+        assert_eq!(arxiv_id("https://example.com/abs/1706.03762"), None);
+    }
+
+    #[test]
+    fn arxiv_entries_become_bibtex() {
+        let papers = parse_arxiv_feed(ARXIV_FEED);
+        assert_eq!(papers.len(), 1);
+        let paper = &papers[0];
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!((paper.date.as_str(), paper.arxiv_id.as_deref()), ("2017-06-12", Some("1706.03762")));
+        assert_eq!(paper.authors, ["Ashish Vaswani", "Noam Shazeer"]);
+        assert_eq!(paper.primary_class.as_deref(), Some("cs.CL"));
+
+        let bibtex = to_bibtex(&[paper.clone(), paper.clone()]);
+        assert!(bibtex.starts_with("@misc{vaswani2017attention,\n  title = {{Attention Is All You Need}},\n"), "{}", bibtex);
+        assert!(bibtex.contains("  author = {Vaswani, Ashish and Shazeer, Noam},\n"), "{}", bibtex);
+        assert!(bibtex.contains("  eprint = {1706.03762},\n  archivePrefix = {arXiv},\n  primaryClass = {cs.CL},\n"), "{}", bibtex);
+        assert!(bibtex.contains("  abstract = {The dominant sequence transduction models \\& more.},\n"), "{}", bibtex);
+        assert!(bibtex.contains("@misc{vaswani2017attentiona,"), "repeated keys get a suffix: {}", bibtex);
+    }
+
+    #[test]
+    fn names_and_special_characters_are_bibtex_safe() {
+        assert_eq!(split_name("Lovelace, Ada"), ("Ada".to_string(), "Lovelace".to_string()));
+        assert_eq!(split_name("Ada King Lovelace"), ("Ada King".to_string(), "Lovelace".to_string()));
+        assert_eq!(split_name("Plato"), (String::new(), "Plato".to_string()));
+        assert_eq!(bibtex_escape("50% of {x}_1 ~ y^2 \\ z"), "50\\% of \\{x\\}\\_1 \\textasciitilde{} y\\textasciicircum{}2 \\textbackslash{} z");
+        let untitled = Paper { arxiv_id: Some("2401.00001".to_string()), ..Default::default() };
+        assert_eq!(citation_key(&untitled), "arxiv240100001");
+    }
+}
@@ -0,0 +1,822 @@
+// Encrypted database sync through WebDAV or S3-compatible storage.
+//
+// An upload is a snapshot of the database with machine-specific tables
+// emptied (so API keys and passwords never leave the machine), encrypted
+// with XChaCha20-Poly1305 under a key derived from the user's passphrase,
+// and stored as a single remote object. Another machine pulls that object
+// and either restores it wholesale or merges it row by row: articles are
+// matched by URL and the copy with the newer `updated_at` wins, notes follow
+// the same rule, tags/highlights/sources are unioned. Deletions travel too:
+// an article trashed on the other machine after this one last changed it is
+// trashed here, the pruned URLs (`seen_urls`) are unioned, and a remote
+// article whose URL this machine trashed or pruned isn't brought back.
+//
+// `sync_now` merges before it uploads, so two machines sharing one object
+// converge instead of overwriting each other's changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{get_data_dir, get_setting, read_time, seen_urls, set_setting, trash, HttpClients};
+
+const MAGIC: &[u8; 8] = b"ANASYNC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KDF_ROUNDS: u32 = 210_000;
+
+// Snapshots can be tens of MB; the default client timeout is too short
+const TRANSFER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+// Tables that describe this machine rather than the library; never uploaded
+// (webhook URLs are credentials too)
+const MACHINE_TABLES: [&str; 11] = [
+    "settings",
+    "scheduler_runs",
+    "greader_items",
+    "obsidian_exports",
+    "http_cache",
+    "github_repos",
+    "ui_state",
+    "jobs",
+    "batch_queue",
+    "crawl_runs",
+    "webhooks",
+];
+
+// Machine tables carried over a restore; the others are keyed by article ID and go stale
+const PRESERVED_ON_RESTORE: [&str; 4] = ["settings", "scheduler_runs", "crawl_runs", "webhooks"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    #[default]
+    Webdav,
+    S3,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudSyncConfig {
+    pub provider: Provider,
+    pub webdav_url: String,
+    pub webdav_username: String,
+    pub webdav_password: String,
+    pub s3_endpoint: String,
+    pub s3_region: String,
+    pub s3_bucket: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    pub remote_path: String,
+    pub passphrase: String,
+    // 0 disables the periodic sync
+    pub interval_minutes: u32,
+}
+
+impl Default for CloudSyncConfig {
+    fn default() -> Self {
+        CloudSyncConfig {
+            provider: Provider::Webdav,
+            webdav_url: String::new(),
+            webdav_username: String::new(),
+            webdav_password: String::new(),
+            s3_endpoint: String::new(),
+            s3_region: "us-east-1".to_string(),
+            s3_bucket: String::new(),
+            s3_access_key: String::new(),
+            s3_secret_key: String::new(),
+            remote_path: "ai-news-aggregator/news.db.enc".to_string(),
+            passphrase: String::new(),
+            interval_minutes: 0,
+        }
+    }
+}
+
+impl CloudSyncConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = CloudSyncConfig::default();
+        let or_default = |value: String, default: String| if value.trim().is_empty() { default } else { value };
+        Ok(CloudSyncConfig {
+            provider: match get_setting(conn, "cloud_sync.provider", "webdav")?.as_str() {
+                "s3" => Provider::S3,
+                _ => Provider::Webdav,
+            },
+            webdav_url: get_setting(conn, "cloud_sync.webdav_url", "")?,
+            webdav_username: get_setting(conn, "cloud_sync.webdav_username", "")?,
+            webdav_password: get_setting(conn, "cloud_sync.webdav_password", "")?,
+            s3_endpoint: get_setting(conn, "cloud_sync.s3_endpoint", "")?,
+            s3_region: or_default(get_setting(conn, "cloud_sync.s3_region", "")?, defaults.s3_region),
+            s3_bucket: get_setting(conn, "cloud_sync.s3_bucket", "")?,
+            s3_access_key: get_setting(conn, "cloud_sync.s3_access_key", "")?,
+            s3_secret_key: get_setting(conn, "cloud_sync.s3_secret_key", "")?,
+            remote_path: or_default(get_setting(conn, "cloud_sync.remote_path", "")?, defaults.remote_path),
+            passphrase: get_setting(conn, "cloud_sync.passphrase", "")?,
+            interval_minutes: get_setting(conn, "cloud_sync.interval_minutes", "0")?.parse().unwrap_or(0),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let provider = match self.provider {
+            Provider::Webdav => "webdav",
+            Provider::S3 => "s3",
+        };
+        set_setting(conn, "cloud_sync.provider", provider)?;
+        set_setting(conn, "cloud_sync.webdav_url", self.webdav_url.trim())?;
+        set_setting(conn, "cloud_sync.webdav_username", self.webdav_username.trim())?;
+        set_setting(conn, "cloud_sync.webdav_password", &self.webdav_password)?;
+        set_setting(conn, "cloud_sync.s3_endpoint", self.s3_endpoint.trim())?;
+        set_setting(conn, "cloud_sync.s3_region", self.s3_region.trim())?;
+        set_setting(conn, "cloud_sync.s3_bucket", self.s3_bucket.trim())?;
+        set_setting(conn, "cloud_sync.s3_access_key", self.s3_access_key.trim())?;
+        set_setting(conn, "cloud_sync.s3_secret_key", self.s3_secret_key.trim())?;
+        set_setting(conn, "cloud_sync.remote_path", self.remote_path.trim().trim_matches('/'))?;
+        set_setting(conn, "cloud_sync.passphrase", &self.passphrase)?;
+        set_setting(conn, "cloud_sync.interval_minutes", &self.interval_minutes.to_string())?;
+        Ok(())
+    }
+
+    pub fn is_configured(&self) -> bool {
+        match self.provider {
+            Provider::Webdav => !self.webdav_url.is_empty(),
+            Provider::S3 => {
+                !self.s3_endpoint.is_empty()
+                    && !self.s3_bucket.is_empty()
+                    && !self.s3_access_key.is_empty()
+                    && !self.s3_secret_key.is_empty()
+            }
+        }
+    }
+
+    fn object_url(&self) -> String {
+        let key = self.remote_path.trim_matches('/');
+        match self.provider {
+            Provider::Webdav => format!("{}/{}", self.webdav_url.trim_end_matches('/'), key),
+            // Path-style addressing works with MinIO, R2, B2 and AWS alike
+            Provider::S3 => format!("{}/{}/{}", self.s3_endpoint.trim_end_matches('/'), self.s3_bucket, key),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if !self.is_configured() {
+            return Err(t(Msg::SyncNotConfigured));
+        }
+        if self.passphrase.is_empty() {
+            return Err(t(Msg::SyncPassphraseMissing));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    pub articles_added: usize,
+    pub articles_updated: usize,
+    pub articles_trashed: usize,
+    pub notes_updated: usize,
+    pub highlights_added: usize,
+    pub tags_added: usize,
+    pub sources_added: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncSummary {
+    // None when there was no remote copy yet
+    pub merged: Option<MergeReport>,
+    pub uploaded_bytes: usize,
+}
+
+// Pull + merge the remote copy (if any), then upload the merged result
+pub async fn sync_now(db: &Mutex<Connection>, http: &HttpClients) -> Result<SyncSummary, String> {
+    let config = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        CloudSyncConfig::load(&conn)?
+    }; // Release the lock during network calls
+    config.check()?;
+    let client = http.for_url(&config.object_url());
+
+    let merged = match download(client, &config).await? {
+        Some(encrypted) => {
+            let path = write_temp("pull", &decrypt(&config.passphrase, &encrypted)?)?;
+            let result = {
+                let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+                merge_snapshot(&conn, &path)
+            };
+            let _ = std::fs::remove_file(&path);
+            Some(result?)
+        }
+        None => None,
+    };
+
+    let snapshot = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        write_snapshot(&conn)?
+    };
+    let encrypted = encrypt(&config.passphrase, &snapshot)?;
+    let uploaded_bytes = encrypted.len();
+    upload(client, &config, encrypted).await?;
+
+    Ok(SyncSummary { merged, uploaded_bytes })
+}
+
+// Replace the local library with the remote copy, keeping this machine's settings.
+// The previous database is kept next to the live one as `news.before-restore.db`.
+pub async fn restore(db: &Mutex<Connection>, http: &HttpClients) -> Result<(), String> {
+    let config = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        CloudSyncConfig::load(&conn)?
+    };
+    config.check()?;
+    let client = http.for_url(&config.object_url());
+
+    let encrypted = download(client, &config)
+        .await?
+        .ok_or_else(|| tr(Msg::SyncFailed, "no remote copy found"))?;
+    let path = write_temp("restore", &decrypt(&config.passphrase, &encrypted)?)?;
+
+    let result = {
+        let mut conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        restore_snapshot(&mut conn, &path)
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+// ---------------------------------------------------------------------------
+// Snapshot / merge / restore
+// ---------------------------------------------------------------------------
+
+fn temp_path(name: &str) -> Result<PathBuf, String> {
+    Ok(Path::new(&get_data_dir()?).join(format!("sync-{}.tmp", name)))
+}
+
+fn write_temp(name: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    let path = temp_path(name)?;
+    std::fs::write(&path, bytes).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+fn write_snapshot(conn: &Connection) -> Result<Vec<u8>, String> {
+    let path = temp_path("push")?;
+    // VACUUM INTO refuses to overwrite an existing file
+    let _ = std::fs::remove_file(&path);
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+        .map_err(|e| tr(Msg::SyncFailed, e))?;
+
+    let stripped = (|| -> Result<(), rusqlite::Error> {
+        let snapshot = Connection::open(&path)?;
+        for table in MACHINE_TABLES {
+            snapshot.execute(&format!("DELETE FROM {}", table), [])?;
+        }
+        snapshot.execute("VACUUM", [])?;
+        Ok(())
+    })();
+    let bytes = stripped
+        .map_err(|e| tr(Msg::SyncFailed, e))
+        .and_then(|_| std::fs::read(&path).map_err(|e| tr(Msg::ReadFileFailed, e)));
+    let _ = std::fs::remove_file(&path);
+    bytes
+}
+
+fn merge_snapshot(conn: &Connection, path: &Path) -> Result<MergeReport, String> {
+    conn.execute("ATTACH DATABASE ?1 AS remote", params![path.to_string_lossy()])
+        .map_err(|e| tr(Msg::SyncFailed, e))?;
+    let result = merge_attached(conn);
+    let _ = conn.execute("DETACH DATABASE remote", []);
+    result
+}
+
+struct SyncedArticle {
+    id: String,
+    title: String,
+    summary: Option<String>,
+    content: Option<String>,
+    url: String,
+    source: Option<String>,
+    category: Option<String>,
+    published_at: Option<String>,
+    fetched_at: Option<String>,
+    heat_score: f64,
+    is_read: i32,
+    is_bookmarked: i32,
+    image_url: Option<String>,
+    updated_at: String,
+}
+
+// Snapshots from before a table existed don't have it
+fn remote_has_table(conn: &Connection, table: &str) -> Result<bool, String> {
+    conn.query_row("SELECT 1 FROM remote.sqlite_master WHERE type = 'table' AND name = ?1", params![table], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| tr(Msg::DbQueryFailed, e))
+}
+
+fn merge_attached(conn: &Connection) -> Result<MergeReport, String> {
+    let tx = conn.unchecked_transaction().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    let mut report = MergeReport::default();
+
+    // Deletions first, so the articles below are matched against the merged trash
+    if remote_has_table(&tx, "articles_trash")? {
+        report.articles_trashed = trash::move_articles(
+            &tx,
+            "SELECT a.rowid FROM articles a JOIN remote.articles_trash rt ON rt.url = a.url
+             WHERE rt.deleted_at > COALESCE(a.updated_at, '') LIMIT ?1",
+            -1,
+        )?;
+    }
+    if remote_has_table(&tx, "seen_urls")? {
+        tx.execute("INSERT OR IGNORE INTO seen_urls (url_hash, seen_at) SELECT url_hash, seen_at FROM remote.seen_urls", [])
+            .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+
+    let remote_articles = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, title, summary, content, url, source, category, published_at, fetched_at,
+                        COALESCE(heat_score, 0), COALESCE(is_read, 0), COALESCE(is_bookmarked, 0),
+                        image_url, COALESCE(updated_at, '')
+                 FROM remote.articles",
+            )
+            .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(SyncedArticle {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    summary: row.get(2)?,
+                    content: row.get(3)?,
+                    url: row.get(4)?,
+                    source: row.get(5)?,
+                    category: row.get(6)?,
+                    published_at: row.get(7)?,
+                    fetched_at: row.get(8)?,
+                    heat_score: row.get(9)?,
+                    is_read: row.get(10)?,
+                    is_bookmarked: row.get(11)?,
+                    image_url: row.get(12)?,
+                    updated_at: row.get(13)?,
+                })
+            })
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+        rows
+    };
+
+    for remote in remote_articles {
+        let local: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT rowid, COALESCE(updated_at, '') FROM articles WHERE url = ?1",
+                params![remote.url],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+
+        match local {
+            None if is_deleted_here(&tx, &remote.url)? => {}
+            None => {
+                // IDs are random per machine, a clash is unlikely but would break the insert
+                let id_taken: bool = tx
+                    .query_row("SELECT 1 FROM articles WHERE id = ?1", params![remote.id], |_| Ok(()))
+                    .optional()
+                    .map_err(|e| tr(Msg::DbQueryFailed, e))?
+                    .is_some();
+                let id = if id_taken { uuid::Uuid::new_v4().to_string() } else { remote.id.clone() };
+                let reading_minutes = read_time::reading_minutes(remote.content.as_deref().unwrap_or_default());
+                tx.execute(
+                    "INSERT INTO articles (id, title, summary, content, url, source, category, published_at,
+                                           fetched_at, heat_score, is_read, is_bookmarked, image_url, updated_at,
+                                           reading_minutes)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                    params![
+                        id, remote.title, remote.summary, remote.content, remote.url, remote.source,
+                        remote.category, remote.published_at, remote.fetched_at, remote.heat_score,
+                        remote.is_read, remote.is_bookmarked, remote.image_url, remote.updated_at, reading_minutes
+                    ],
+                )
+                .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+                tx.execute(
+                    "INSERT INTO articles_fts (rowid, title, summary, content) VALUES (?1, cjk_segment(?2), cjk_segment(?3), cjk_segment(?4))",
+                    params![tx.last_insert_rowid(), remote.title, remote.summary, remote.content],
+                )
+                .map_err(|e| tr(Msg::FtsInsertFailed, e))?;
+                report.articles_added += 1;
+            }
+            Some((rowid, local_updated_at)) if remote.updated_at > local_updated_at => {
+                // Setting updated_at explicitly keeps the touch trigger from bumping it to "now";
+                // content_hash is recomputed by the next crawl that sees the article
+                tx.execute(
+                    "UPDATE articles SET title = ?1, summary = ?2, content = ?3, source = ?4, category = ?5,
+                            published_at = ?6, heat_score = ?7, is_read = ?8, is_bookmarked = ?9,
+                            image_url = ?10, updated_at = ?11, content_hash = NULL, reading_minutes = ?12
+                     WHERE rowid = ?13",
+                    params![
+                        remote.title, remote.summary, remote.content, remote.source, remote.category,
+                        remote.published_at, remote.heat_score, remote.is_read, remote.is_bookmarked,
+                        remote.image_url, remote.updated_at,
+                        read_time::reading_minutes(remote.content.as_deref().unwrap_or_default()), rowid
+                    ],
+                )
+                .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+                tx.execute(
+                    "UPDATE articles_fts SET title = cjk_segment(?1), summary = cjk_segment(?2), content = cjk_segment(?3) WHERE rowid = ?4",
+                    params![remote.title, remote.summary, remote.content, rowid],
+                )
+                .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+                report.articles_updated += 1;
+            }
+            _ => {}
+        }
+    }
+
+    // Annotations are keyed by article ID; translate through the URL
+    report.notes_updated = tx
+        .execute(
+            "INSERT OR REPLACE INTO article_notes (article_id, note, updated_at)
+             SELECT l.id, rn.note, rn.updated_at
+             FROM remote.article_notes rn
+             JOIN remote.articles ra ON ra.id = rn.article_id
+             JOIN articles l ON l.url = ra.url
+             LEFT JOIN article_notes ln ON ln.article_id = l.id
+             WHERE ln.article_id IS NULL OR rn.updated_at > ln.updated_at",
+            [],
+        )
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    report.highlights_added = tx
+        .execute(
+            "INSERT INTO article_highlights (id, article_id, text, created_at)
+             SELECT lower(hex(randomblob(16))), l.id, rh.text, rh.created_at
+             FROM remote.article_highlights rh
+             JOIN remote.articles ra ON ra.id = rh.article_id
+             JOIN articles l ON l.url = ra.url
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM article_highlights lh WHERE lh.article_id = l.id AND lh.text = rh.text
+             )",
+            [],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    report.tags_added = tx
+        .execute(
+            "INSERT OR IGNORE INTO article_tags (article_id, tag)
+             SELECT l.id, rt.tag
+             FROM remote.article_tags rt
+             JOIN remote.articles ra ON ra.id = rt.article_id
+             JOIN articles l ON l.url = ra.url",
+            [],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    // Seeded source IDs repeat across machines, so match on name and mint fresh IDs
+    report.sources_added = tx
+        .execute(
+            "INSERT OR IGNORE INTO sources (id, name, url, source_type, is_active)
+             SELECT lower(hex(randomblob(16))), rs.name, rs.url, rs.source_type, rs.is_active
+             FROM remote.sources rs
+             WHERE NOT EXISTS (SELECT 1 FROM sources ls WHERE ls.name = rs.name)",
+            [],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+
+    tx.commit().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(report)
+}
+
+// Trashed or pruned on this machine: a sync doesn't undo that
+fn is_deleted_here(conn: &Connection, url: &str) -> Result<bool, String> {
+    let trashed = conn
+        .query_row("SELECT 1 FROM articles_trash WHERE url = ?1", params![url], |_| Ok(()))
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .is_some();
+    Ok(trashed || seen_urls::is_seen(conn, url).map_err(|e| tr(Msg::DbQueryFailed, e))?)
+}
+
+fn restore_snapshot(conn: &mut Connection, path: &Path) -> Result<(), String> {
+    let backup_path = Path::new(&get_data_dir()?).join("news.before-restore.db");
+    let _ = std::fs::remove_file(&backup_path);
+    conn.execute("VACUUM INTO ?1", params![backup_path.to_string_lossy()])
+        .map_err(|e| tr(Msg::DbRestoreFailed, e))?;
+
+    conn.restore(rusqlite::DatabaseName::Main, path, None::<fn(rusqlite::backup::Progress)>)
+        .map_err(|e| tr(Msg::DbRestoreFailed, e))?;
+
+    // Put this machine's settings back on top of the restored library
+    conn.execute("ATTACH DATABASE ?1 AS previous", params![backup_path.to_string_lossy()])
+        .map_err(|e| tr(Msg::DbRestoreFailed, e))?;
+    let copied = PRESERVED_ON_RESTORE.iter().try_for_each(|table| {
+        conn.execute_batch(&format!(
+            "DELETE FROM main.{0}; INSERT INTO main.{0} SELECT * FROM previous.{0};",
+            table
+        ))
+    });
+    let _ = conn.execute("DETACH DATABASE previous", []);
+    copied.map_err(|e| tr(Msg::DbRestoreFailed, e))
+}
+
+// ---------------------------------------------------------------------------
+// Encryption: MAGIC | salt | nonce | ciphertext+tag
+// ---------------------------------------------------------------------------
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt).into());
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| tr(Msg::SyncEncryptFailed, e))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header || &data[..MAGIC.len()] != MAGIC {
+        return Err(tr(Msg::SyncDecryptFailed, "unrecognized format"));
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = XNonce::from_slice(&data[MAGIC.len() + SALT_LEN..header]);
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt).into());
+    cipher
+        .decrypt(nonce, &data[header..])
+        .map_err(|_| t(Msg::SyncDecryptFailed))
+}
+
+// ---------------------------------------------------------------------------
+// Transport
+// ---------------------------------------------------------------------------
+
+async fn download(client: &reqwest::Client, config: &CloudSyncConfig) -> Result<Option<Vec<u8>>, String> {
+    let url = config.object_url();
+    let request = match config.provider {
+        Provider::Webdav => webdav_auth(client.get(&url), config),
+        Provider::S3 => s3_sign(client.get(&url), config, "GET", &url, &[])?,
+    };
+    let response = request
+        .timeout(TRANSFER_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| tr(Msg::SyncFailed, e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let response = check_status(response).await?;
+    let bytes = response.bytes().await.map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    Ok(Some(bytes.to_vec()))
+}
+
+async fn upload(client: &reqwest::Client, config: &CloudSyncConfig, body: Vec<u8>) -> Result<(), String> {
+    let url = config.object_url();
+    let request = match config.provider {
+        Provider::Webdav => {
+            webdav_mkcol_parents(client, config).await;
+            webdav_auth(client.put(&url), config)
+        }
+        Provider::S3 => s3_sign(client.put(&url), config, "PUT", &url, &body)?,
+    };
+    let response = request
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .timeout(TRANSFER_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| tr(Msg::SyncFailed, e))?;
+    check_status(response).await.map(|_| ())
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(t(Msg::SyncAuthFailed));
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(tr(Msg::SyncFailed, format!("({}) {}", status, body.chars().take(300).collect::<String>())));
+    }
+    Ok(response)
+}
+
+fn webdav_auth(request: reqwest::RequestBuilder, config: &CloudSyncConfig) -> reqwest::RequestBuilder {
+    if config.webdav_username.is_empty() {
+        request
+    } else {
+        request.basic_auth(&config.webdav_username, Some(&config.webdav_password))
+    }
+}
+
+// PUT fails on most servers when the folder is missing; 405 from MKCOL just means it exists
+async fn webdav_mkcol_parents(client: &reqwest::Client, config: &CloudSyncConfig) {
+    let Ok(method) = reqwest::Method::from_bytes(b"MKCOL") else { return };
+    let segments: Vec<&str> = config.remote_path.trim_matches('/').split('/').collect();
+    let mut url = config.webdav_url.trim_end_matches('/').to_string();
+    for segment in &segments[..segments.len().saturating_sub(1)] {
+        url = format!("{}/{}", url, segment);
+        let _ = webdav_auth(client.request(method.clone(), &url), config).send().await;
+    }
+}
+
+// AWS Signature V4 with a signed payload hash
+fn s3_sign(
+    request: reqwest::RequestBuilder,
+    config: &CloudSyncConfig,
+    method: &str,
+    url: &str,
+    body: &[u8],
+) -> Result<reqwest::RequestBuilder, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| tr(Msg::SyncFailed, e))?;
+    let host = match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(tr(Msg::SyncFailed, "invalid S3 endpoint")),
+    };
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(body));
+    let region = if config.s3_region.is_empty() { "us-east-1" } else { config.s3_region.as_str() };
+
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        method,
+        parsed.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let mut key = hmac_sha256(format!("AWS4{}", config.s3_secret_key).as_bytes(), date.as_bytes());
+    for part in [region, "s3", "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    Ok(request
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header(
+            "Authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                config.s3_access_key, scope, signed_headers, signature
+            ),
+        ))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(dir: &Path, name: &str) -> (Connection, PathBuf) {
+        let path = dir.join(name);
+        let conn = crate::init_db_with_conn(Connection::open(&path).unwrap()).unwrap();
+        (conn, path)
+    }
+
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("news-sync-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add_article(conn: &Connection, id: &str, url: &str, updated_at: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source, category, updated_at) VALUES (?1, ?1, ?2, 'Blog', 'AI', ?3)",
+            params![id, url, updated_at],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO articles_fts (rowid, title, summary, content) VALUES (?1, ?2, '', '')",
+            params![conn.last_insert_rowid(), id],
+        )
+        .unwrap();
+    }
+
+    fn trash_url(conn: &Connection, url: &str) {
+        let rowid: i64 = conn.query_row("SELECT rowid FROM articles WHERE url = ?1", params![url], |row| row.get(0)).unwrap();
+        trash::move_articles(conn, "SELECT ?1", rowid).unwrap();
+    }
+
+    fn is_live(conn: &Connection, url: &str) -> bool {
+        conn.query_row("SELECT 1 FROM articles WHERE url = ?1", params![url], |_| Ok(())).optional().unwrap().is_some()
+    }
+
+    #[test]
+    fn snapshot_round_trips_only_under_its_passphrase() {
+        let plaintext = b"SQLite format 3\0 and the rest of the file".to_vec();
+        let encrypted = encrypt("correct horse", &plaintext).unwrap();
+        assert!(encrypted.starts_with(MAGIC));
+        assert_ne!(&encrypted[MAGIC.len() + SALT_LEN + NONCE_LEN..], &plaintext[..]);
+        assert_eq!(decrypt("correct horse", &encrypted).unwrap(), plaintext);
+
+        assert!(decrypt("battery staple", &encrypted).is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt("correct horse", &tampered).is_err());
+        assert!(decrypt("correct horse", &encrypted[..MAGIC.len() + SALT_LEN]).is_err());
+        assert!(decrypt("correct horse", b"not a sync snapshot at all, just bytes").is_err());
+    }
+
+    #[test]
+    fn merge_takes_new_and_newer_articles() {
+        let dir = temp_dir("newer");
+        let (local, _) = database(&dir, "local.db");
+        let (remote, remote_path) = database(&dir, "remote.db");
+        add_article(&local, "kept", "https://a.example/kept", "2026-10-02T00:00:00Z");
+        add_article(&local, "stale", "https://a.example/stale", "2026-10-01T00:00:00Z");
+        add_article(&remote, "kept-remote", "https://a.example/kept", "2026-10-01T00:00:00Z");
+        add_article(&remote, "stale-remote", "https://a.example/stale", "2026-10-02T00:00:00Z");
+        add_article(&remote, "new", "https://a.example/new", "2026-10-02T00:00:00Z");
+        drop(remote);
+
+        let report = merge_snapshot(&local, &remote_path).unwrap();
+        assert_eq!((report.articles_added, report.articles_updated, report.articles_trashed), (1, 1, 0));
+        let title = |url: &str| -> String {
+            local.query_row("SELECT title FROM articles WHERE url = ?1", params![url], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(title("https://a.example/kept"), "kept");
+        assert_eq!(title("https://a.example/stale"), "stale-remote");
+        assert_eq!(title("https://a.example/new"), "new");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_does_not_bring_back_articles_deleted_here() {
+        let dir = temp_dir("deleted-here");
+        let (local, _) = database(&dir, "local.db");
+        let (remote, remote_path) = database(&dir, "remote.db");
+        add_article(&local, "trashed", "https://a.example/trashed", "2026-10-01T00:00:00Z");
+        trash_url(&local, "https://a.example/trashed");
+        // Pruned long ago: the trash entry is gone, only the URL is remembered
+        seen_urls::record(&local, &["https://a.example/pruned".to_string()]).unwrap();
+        add_article(&remote, "trashed", "https://a.example/trashed", "2026-10-05T00:00:00Z");
+        add_article(&remote, "pruned", "https://a.example/pruned", "2026-10-05T00:00:00Z");
+        drop(remote);
+
+        let report = merge_snapshot(&local, &remote_path).unwrap();
+        assert_eq!(report.articles_added, 0);
+        assert!(!is_live(&local, "https://a.example/trashed"));
+        assert!(!is_live(&local, "https://a.example/pruned"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_trashes_what_the_other_machine_trashed_since() {
+        let dir = temp_dir("deleted-there");
+        let (local, _) = database(&dir, "local.db");
+        let (remote, remote_path) = database(&dir, "remote.db");
+        add_article(&local, "old", "https://a.example/old", "2000-01-01T00:00:00Z");
+        // Changed here after the other machine trashed it: this copy wins
+        add_article(&local, "edited", "https://a.example/edited", "2999-01-01T00:00:00Z");
+        add_article(&remote, "old", "https://a.example/old", "2000-01-01T00:00:00Z");
+        add_article(&remote, "edited", "https://a.example/edited", "2000-01-01T00:00:00Z");
+        trash_url(&remote, "https://a.example/old");
+        trash_url(&remote, "https://a.example/edited");
+        drop(remote);
+
+        let report = merge_snapshot(&local, &remote_path).unwrap();
+        assert_eq!(report.articles_trashed, 1);
+        assert!(!is_live(&local, "https://a.example/old"));
+        assert!(is_live(&local, "https://a.example/edited"));
+        let trashed: Vec<String> = trash::list(&local, 0).unwrap().into_iter().map(|a| a.url).collect();
+        assert_eq!(trashed, ["https://a.example/old"]);
+        // The other machine's pruned URLs keep crawls here from re-adding it too
+        assert!(seen_urls::is_seen(&local, "https://a.example/old").unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
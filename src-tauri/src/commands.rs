@@ -0,0 +1,2144 @@
+// Tauri commands exposed to the frontend, with their payload and result
+// types. Commands stay thin: they lock the database, call into the modules
+// that do the work and return its result.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::ai::{summarize_or_template, summarize_text};
+use crate::crawler::{add_article_from_url, is_chinese_site, run_crawl, CrawlResult, HttpClients};
+use crate::db::{
+    cleanup_articles, delete_article_orphans, get_data_dir, get_db_path, get_setting, init_db_at, load_settings,
+    query_article, query_articles_by_ids, query_articles_page, query_search, set_setting, store_summary, update_summary,
+    DbState,
+};
+use crate::i18n::{t, tr, Msg};
+use crate::{
+    ai, ai_limits, annotations, article_edits, article_export, bookmarks_import, citations, cloud_sync,
+    comment_highlights, crawl_metrics, datetime, db_recovery, default_trash_retention_days, digest, email, epub,
+    features, feed_balance, feed_export, feedback, folders, github_trending, glossary, greader, heat, i18n, jobs,
+    keywords, kindle, lazy_summary, light_pages, list_cache, local_api, low_data, network, obsidian, onboarding,
+    palette, paper_code, pdf_export, perf, prefetch, profiles,
+    read_later, read_only, reader, revisions, roundup, rules, scheduler, search_index, share_card, source_discovery,
+    source_packs, star_report, storage, syndication, translate, trash, ui_state, watchlists, webhooks, Article,
+    CategoryCount, CleanupResult, ListQuery, ListResponse, Settings,
+};
+
+#[tauri::command]
+pub async fn health() -> Result<String, String> {
+    let _perf = perf::completion("health");
+    Ok("OK".to_string())
+}
+
+#[tauri::command]
+pub async fn articles_list(
+    state: State<'_, DbState>,
+    cache: State<'_, list_cache::ListCache>,
+    query: ListQuery,
+) -> Result<ListResponse, String> {
+    let _perf = perf::completion("articles_list");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let run = || {
+        let balance = feed_balance::FeedBalance::load(&conn)?;
+        Ok(query_articles_page(&conn, &query, Some(&balance))?)
+    };
+    // The first page is cached; deeper ones only once prefetched
+    let key = list_cache::ListCache::page_key(&query)?;
+    if query.page.unwrap_or(1) <= 1 {
+        cache.page(key, run)
+    } else {
+        Ok(cache.cached_page(&key).map_or_else(run, Ok)?)
+    }
+}
+
+// Warm the next page and the visible articles' pages in the background
+#[tauri::command]
+pub fn articles_prefetch(app: AppHandle, request: prefetch::PrefetchRequest) -> Result<prefetch::PrefetchQueued, String> {
+    prefetch::start(app, request)
+}
+
+// Articles per category as the default feed shows them (muted sources and
+// snoozed articles left out), optionally within one folder; most articles first
+#[tauri::command]
+pub fn category_counts(
+    state: State<DbState>,
+    cache: State<list_cache::ListCache>,
+    folder_id: Option<String>,
+) -> Result<Vec<CategoryCount>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    cache.counts(folder_id.clone().unwrap_or_default(), || {
+        let sql = format!(
+            "SELECT category, COUNT(*), COALESCE(SUM(is_read = 0), 0) FROM articles
+             WHERE source NOT IN (SELECT name FROM sources WHERE muted_until > ?1)
+               AND (snoozed_until IS NULL OR snoozed_until <= ?1)
+               AND (?2 IS NULL OR {})
+             GROUP BY category ORDER BY COUNT(*) DESC, category",
+            folders::source_condition(2)
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+        let counts = stmt
+            .query_map(params![chrono::Utc::now().to_rfc3339(), folder_id], |row| {
+                Ok(CategoryCount {
+                    category: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                    total: row.get(1)?,
+                    unread: row.get(2)?,
+                })
+            })
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+        Ok(counts)
+    })
+}
+
+#[tauri::command]
+pub async fn cleanup_old_articles(state: State<'_, DbState>) -> Result<CleanupResult, String> {
+    let _perf = perf::completion("cleanup_old_articles");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(cleanup_articles(&conn)?)
+}
+
+#[tauri::command]
+pub fn storage_stats(state: State<DbState>) -> Result<storage::StorageStats, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    storage::stats(&conn)
+}
+
+// Articles removed by cleanup that can still be restored
+#[tauri::command]
+pub fn trash_list(state: State<DbState>) -> Result<Vec<trash::TrashedArticle>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let retention_days = get_setting(&conn, "trash_retention_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_trash_retention_days());
+    trash::list(&conn, retention_days)
+}
+
+// Bring a trashed article back into the feed; returns it
+#[tauri::command]
+pub fn article_restore(state: State<DbState>, id: String) -> Result<Article, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    trash::restore(&conn, &id)?;
+    query_article(&conn, &id)?.ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+// Delete everything in the trash for good; returns how many articles went
+#[tauri::command]
+pub fn trash_empty(state: State<DbState>) -> Result<usize, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let removed = trash::empty(&conn)?;
+    delete_article_orphans(&conn)?;
+    Ok(removed)
+}
+
+// Timing of recent crawls per phase and source, newest first
+#[tauri::command]
+pub fn crawl_metrics(state: State<DbState>, limit: Option<usize>) -> Result<Vec<crawl_metrics::CrawlRun>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    crawl_metrics::list(&conn, limit.unwrap_or(20).min(200))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ArticlesExportPayload {
+    #[serde(default)]
+    pub format: article_export::ExportFormat,
+    pub path: Option<String>,
+    #[serde(default, flatten)]
+    pub filter: article_export::ExportFilter,
+}
+
+// Stream every article (or those of one source / the bookmarks) to a file in the background
+#[tauri::command]
+pub fn articles_export(
+    app: AppHandle,
+    state: State<DbState>,
+    payload: Option<ArticlesExportPayload>,
+) -> Result<article_export::ExportStarted, String> {
+    let payload = payload.unwrap_or_default();
+    // Default: ~/.newsagregator/exports/articles-<date>.<ext>
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!(
+            "{}/exports/articles-{}.{}",
+            get_data_dir()?,
+            chrono::Local::now().format("%Y-%m-%d"),
+            payload.format.extension()
+        ),
+    };
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    article_export::start(app, &conn, path, payload.format, payload.filter)
+}
+
+#[tauri::command]
+pub fn articles_export_cancel(id: u64) -> bool {
+    article_export::cancel(id)
+}
+
+// Progress of re-tokenizing existing articles for Chinese search
+#[tauri::command]
+pub fn search_reindex_status(state: State<DbState>) -> Result<search_index::ReindexStatus, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    search_index::status(&conn)
+}
+
+// Start or resume re-tokenizing in the background
+#[tauri::command]
+pub fn search_reindex(app: AppHandle) -> Result<search_index::ReindexStatus, String> {
+    search_index::start(app)
+}
+
+// Slowest commands and queries since the app started
+#[tauri::command]
+pub fn perf_report(limit: Option<usize>) -> perf::PerfReport {
+    perf::report(limit.unwrap_or(20).min(200))
+}
+
+// Search articles
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub keyword: String,
+    // Only bookmarks, annotated articles and / or one source; all articles when absent
+    #[serde(default)]
+    pub scope: search_index::SearchScope,
+}
+
+#[tauri::command]
+pub async fn search_query(state: State<'_, DbState>, query: SearchQuery) -> Result<Vec<Article>, String> {
+    let _perf = perf::completion("search_query");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(query_search(&conn, &query.keyword, &query.scope)?)
+}
+
+// Full records for a list of article IDs in one round trip (clusters, related articles, saved
+// searches, digests), in the order given; IDs that are no longer stored are left out
+#[tauri::command]
+pub async fn articles_get_many(state: State<'_, DbState>, ids: Vec<String>) -> Result<Vec<Article>, String> {
+    let _perf = perf::completion("articles_get_many");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(query_articles_by_ids(&conn, &ids)?)
+}
+
+// Toggle bookmark
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookmarkPayload {
+    pub id: String,
+    pub value: bool,
+}
+
+#[tauri::command]
+pub async fn article_bookmark(state: State<'_, DbState>, payload: BookmarkPayload) -> Result<(), String> {
+    let _perf = perf::completion("article_bookmark");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    conn.execute(
+        "UPDATE articles SET is_bookmarked = ?1 WHERE id = ?2",
+        params![if payload.value { 1 } else { 0 }, payload.id]
+    ).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+
+    // Keep the exported bookmarks feed current if auto-export is on
+    if get_setting(&conn, "feed_auto_export", "false")? == "true" {
+        let path = feed_export_path(&conn)?;
+        if let Err(e) = feed_export::write_bookmarks_feed(&conn, &path) {
+            eprintln!("Bookmarks feed auto-export failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Snooze: hide an article until `until` (None = wake it now)
+#[derive(Debug, Deserialize)]
+pub struct SnoozePayload {
+    pub id: String,
+    pub until: Option<String>,
+    // Emit a notification event when it reappears
+    #[serde(default)]
+    pub notify: bool,
+}
+
+#[tauri::command]
+pub async fn article_snooze(state: State<'_, DbState>, payload: SnoozePayload) -> Result<(), String> {
+    let _perf = perf::completion("article_snooze");
+    let until = match payload.until.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(until) => Some(datetime::parse(until).ok_or_else(|| tr(Msg::InvalidDateTime, until))?.to_rfc3339()),
+        None => None,
+    };
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let updated = conn.execute(
+        "UPDATE articles SET snoozed_until = ?1, snooze_notify = ?2 WHERE id = ?3",
+        params![until, (payload.notify && until.is_some()) as i32, payload.id]
+    ).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    if updated == 0 {
+        return Err(t(Msg::ArticleNotFound));
+    }
+    Ok(())
+}
+
+// Notes, highlights and tags
+#[tauri::command]
+pub async fn article_annotations_get(state: State<'_, DbState>, id: String) -> Result<annotations::Annotations, String> {
+    let _perf = perf::completion("article_annotations_get");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    annotations::load(&conn, &id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotePayload {
+    pub id: String,
+    pub note: String,
+}
+
+#[tauri::command]
+pub async fn article_note_set(state: State<'_, DbState>, payload: NotePayload) -> Result<(), String> {
+    let _perf = perf::completion("article_note_set");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    annotations::set_note(&conn, &payload.id, &payload.note)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HighlightPayload {
+    pub id: String,
+    pub text: String,
+}
+
+#[tauri::command]
+pub async fn article_highlight_add(state: State<'_, DbState>, payload: HighlightPayload) -> Result<annotations::Highlight, String> {
+    let _perf = perf::completion("article_highlight_add");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    annotations::add_highlight(&conn, &payload.id, &payload.text)
+}
+
+#[tauri::command]
+pub async fn article_highlight_delete(state: State<'_, DbState>, highlight_id: String) -> Result<(), String> {
+    let _perf = perf::completion("article_highlight_delete");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    annotations::delete_highlight(&conn, &highlight_id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagsPayload {
+    pub id: String,
+    pub tags: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn article_tags_set(state: State<'_, DbState>, payload: TagsPayload) -> Result<Vec<String>, String> {
+    let _perf = perf::completion("article_tags_set");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    annotations::set_tags(&conn, &payload.id, &payload.tags)
+}
+
+// Obsidian vault export
+#[tauri::command]
+pub async fn obsidian_config_get(state: State<'_, DbState>) -> Result<obsidian::ObsidianConfig, String> {
+    let _perf = perf::completion("obsidian_config_get");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    obsidian::ObsidianConfig::load(&conn)
+}
+
+#[tauri::command]
+pub async fn obsidian_config_update(
+    state: State<'_, DbState>,
+    payload: obsidian::ObsidianConfig,
+) -> Result<obsidian::ObsidianConfig, String> {
+    let _perf = perf::completion("obsidian_config_update");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    obsidian::ObsidianConfig::load(&conn)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportObsidianPayload {
+    pub ids: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn export_obsidian(state: State<'_, DbState>, payload: ExportObsidianPayload) -> Result<obsidian::ObsidianExportResult, String> {
+    let _perf = perf::completion("export_obsidian");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    obsidian::export_articles(&conn, &payload.ids)
+}
+
+// Export bookmarks as RSS
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportFeedPayload {
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportFeedResult {
+    pub path: String,
+}
+
+#[tauri::command]
+pub async fn export_feed(state: State<'_, DbState>, payload: Option<ExportFeedPayload>) -> Result<ExportFeedResult, String> {
+    let _perf = perf::completion("export_feed");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let path = match payload.and_then(|p| p.path).filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => feed_export_path(&conn)?,
+    };
+    feed_export::write_bookmarks_feed(&conn, &path)?;
+    Ok(ExportFeedResult { path })
+}
+
+// Export articles as an EPUB book
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportEpubPayload {
+    // Empty = bookmarks from the last 7 days
+    #[serde(default)]
+    pub ids: Vec<String>,
+    pub title: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportEpubResult {
+    pub path: String,
+    pub articles: usize,
+    pub images: usize,
+}
+
+// Plenty for a week of articles; keeps a runaway page from producing a huge file
+const EPUB_IMAGE_BUDGET: usize = 100 * 1024 * 1024;
+
+// Articles by ID in the given order, or recent bookmarks when no IDs are given
+fn articles_for_export(conn: &Connection, ids: &[String]) -> Result<Vec<Article>, String> {
+    let articles = if ids.is_empty() {
+        epub::recent_bookmarks(conn, 7)?
+    } else {
+        let mut unique = ids.to_vec();
+        let mut seen = std::collections::HashSet::new();
+        unique.retain(|id| seen.insert(id.clone()));
+        let articles = query_articles_by_ids(conn, &unique)?;
+        if articles.len() < unique.len() {
+            return Err(t(Msg::ArticleNotFound));
+        }
+        articles
+    };
+    if articles.is_empty() {
+        return Err(t(Msg::ExportNothingSelected));
+    }
+    Ok(articles)
+}
+
+fn default_book_title() -> String {
+    format!("AI News {}", chrono::Local::now().format("%Y-%m-%d"))
+}
+
+#[tauri::command]
+pub async fn export_epub(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: Option<ExportEpubPayload>) -> Result<ExportEpubResult, String> {
+    let _perf = perf::completion("export_epub");
+    let payload = payload.unwrap_or_default();
+    let articles = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        articles_for_export(&conn, &payload.ids)?
+    }; // Release the lock while fetching pages and images
+
+    let title = payload.title.filter(|t| !t.trim().is_empty()).unwrap_or_else(default_book_title);
+    let book = epub::collect(&state.conn, &http, &title, &articles, EPUB_IMAGE_BUDGET).await?;
+    let bytes = epub::build(&book)?;
+
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!("{}/exports/{}.epub", get_data_dir()?, obsidian::sanitize_filename(&title)),
+    };
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    std::fs::write(&path, bytes).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))?;
+
+    Ok(ExportEpubResult {
+        path,
+        articles: book.chapters.len(),
+        images: book.images.len(),
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportPapersPayload {
+    // Empty = bookmarks from the last 7 days; non-paper articles are skipped
+    #[serde(default)]
+    pub ids: Vec<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportPapersResult {
+    // Output file for BibTeX, empty for Zotero
+    pub path: String,
+    pub papers: usize,
+    pub skipped: usize,
+}
+
+async fn papers_for_export(db: &Mutex<Connection>, http: &HttpClients, ids: &[String]) -> Result<(Vec<citations::Paper>, usize), String> {
+    let articles = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        articles_for_export(&conn, ids)?
+    }; // Release the lock while querying arXiv
+    let papers = citations::collect(http, &articles).await;
+    if papers.is_empty() {
+        return Err(t(Msg::NoPapersSelected));
+    }
+    let skipped = articles.len() - papers.len();
+    Ok((papers, skipped))
+}
+
+#[tauri::command]
+pub async fn export_bibtex(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: Option<ExportPapersPayload>) -> Result<ExportPapersResult, String> {
+    let _perf = perf::completion("export_bibtex");
+    let payload = payload.unwrap_or_default();
+    let (papers, skipped) = papers_for_export(&state.conn, &http, &payload.ids).await?;
+
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!("{}/exports/papers-{}.bib", get_data_dir()?, chrono::Local::now().format("%Y-%m-%d")),
+    };
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    std::fs::write(&path, citations::to_bibtex(&papers))
+        .map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))?;
+
+    Ok(ExportPapersResult { path, papers: papers.len(), skipped })
+}
+
+#[tauri::command]
+pub async fn export_zotero(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: Option<ExportPapersPayload>) -> Result<ExportPapersResult, String> {
+    let _perf = perf::completion("export_zotero");
+    let payload = payload.unwrap_or_default();
+    let (papers, skipped) = papers_for_export(&state.conn, &http, &payload.ids).await?;
+    citations::push_to_zotero(&http, &papers).await?;
+    Ok(ExportPapersResult { path: String::new(), papers: papers.len(), skipped })
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct KindleSendPayload {
+    // Empty = bookmarks from the last 7 days
+    #[serde(default)]
+    pub ids: Vec<String>,
+    pub title: Option<String>,
+}
+
+// Send selected articles to the configured Kindle address as an EPUB
+#[tauri::command]
+pub async fn kindle_send(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: Option<KindleSendPayload>) -> Result<kindle::KindleSendResult, String> {
+    let _perf = perf::completion("kindle_send");
+    let payload = payload.unwrap_or_default();
+    let articles = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        articles_for_export(&conn, &payload.ids)?
+    };
+    let title = match (payload.title.filter(|t| !t.trim().is_empty()), articles.as_slice()) {
+        (Some(title), _) => title,
+        // A single article keeps its own title on the Kindle library shelf
+        (None, [article]) => article.title.clone(),
+        (None, _) => default_book_title(),
+    };
+    kindle::send(&state.conn, &http, &title, &articles).await
+}
+
+// Send the digest for the last `days` days to Kindle
+#[tauri::command]
+pub async fn kindle_send_digest(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: Option<DigestPayload>) -> Result<kindle::KindleSendResult, String> {
+    let _perf = perf::completion("kindle_send_digest");
+    let days = payload.unwrap_or_default().days.unwrap_or(1);
+    let (title, articles) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        kindle::digest_articles(&conn, days)?
+    };
+    kindle::send(&state.conn, &http, &title, &articles).await
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareCardResult {
+    pub path: String,
+}
+
+// Render a PNG share card (title, source, summary, QR code of the link)
+#[tauri::command]
+pub async fn article_share_card(state: State<'_, DbState>, id: String) -> Result<ShareCardResult, String> {
+    let _perf = perf::completion("article_share_card");
+    let (article, font_path) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article = query_article(&conn, &id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+        (article, get_setting(&conn, "card_font_path", "")?)
+    };
+    let path = std::path::Path::new(&get_data_dir()?)
+        .join("share-cards")
+        .join(format!("{}.png", article.id));
+    share_card::render_to_file(&article, &font_path, &path)?;
+    Ok(ShareCardResult {
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PdfExportPayload {
+    pub id: String,
+    // Defaults to exports/<title>.pdf in the data directory
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PdfExportResult {
+    pub path: String,
+    pub pages: usize,
+}
+
+// Sanitized article body for the in-app reader, images served from the local cache
+#[tauri::command]
+pub async fn article_reader(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    id: String,
+) -> Result<reader::ReaderView, String> {
+    let _perf = perf::completion("article_reader");
+    let article = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        query_article(&conn, &id)?.ok_or_else(|| t(Msg::ArticleNotFound))?
+    };
+    Ok(reader::render(&state.conn, &http, &article, &reader::image_dir()?).await)
+}
+
+// Render the cleaned article (re-fetched, else the stored content) into an A4 PDF
+#[tauri::command]
+pub async fn article_export_pdf(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    payload: PdfExportPayload,
+) -> Result<PdfExportResult, String> {
+    let _perf = perf::completion("article_export_pdf");
+    let (article, font_path) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article = query_article(&conn, &payload.id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+        (article, get_setting(&conn, "card_font_path", "")?)
+    };
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!("{}/exports/{}.pdf", get_data_dir()?, obsidian::sanitize_filename(&article.title)),
+    };
+    let pages = pdf_export::export(&state.conn, &http, &article, &font_path, std::path::Path::new(&path)).await?;
+    Ok(PdfExportResult { path, pages })
+}
+
+// Curated source bundles for a new database; with `choice`, enable the picked
+// bundles and mark onboarding finished
+#[tauri::command]
+pub fn first_run_setup(
+    state: State<DbState>,
+    choice: Option<onboarding::FirstRunChoice>,
+) -> Result<onboarding::FirstRunState, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    if let Some(choice) = choice {
+        onboarding::complete(&conn, &choice)?;
+    }
+    onboarding::state(&conn)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SourcePackExportPayload {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Source names to include; empty = all
+    #[serde(default)]
+    pub sources: Vec<String>,
+    // Default: ~/.newsagregator/exports/<name>.sourcepack.json
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourcePackExportResult {
+    pub path: String,
+    pub sources: usize,
+}
+
+// Write sources, with their categories and scraping rules, as a shareable pack
+#[tauri::command]
+pub fn source_pack_export(state: State<DbState>, payload: SourcePackExportPayload) -> Result<SourcePackExportResult, String> {
+    let pack = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        source_packs::build(&conn, &payload.name, &payload.description, &payload.sources)?
+    };
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!("{}/exports/{}.sourcepack.json", get_data_dir()?, obsidian::sanitize_filename(&pack.name)),
+    };
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    let json = serde_json::to_string_pretty(&pack).map_err(|e| tr(Msg::WriteFileFailed, e))?;
+    std::fs::write(&path, json).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))?;
+    Ok(SourcePackExportResult { path, sources: pack.sources.len() })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SourcePackImportPayload {
+    // A pack file, or the pack JSON itself in `content`
+    pub path: Option<String>,
+    pub content: Option<String>,
+}
+
+// Add the sources of a pack; duplicates and invalid entries are skipped with a reason
+#[tauri::command]
+pub fn source_pack_import(state: State<DbState>, payload: SourcePackImportPayload) -> Result<source_packs::ImportResult, String> {
+    let content = match (payload.content.filter(|c| !c.trim().is_empty()), payload.path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => std::fs::read_to_string(&path).map_err(|e| tr(Msg::ReadFileFailed, format!("{}: {}", path, e)))?,
+        (None, None) => return Err(t(Msg::SourcePackInvalid)),
+    };
+    let pack = source_packs::parse(&content)?;
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    source_packs::import(&conn, &pack)
+}
+
+// Add one source by URL, discovering the page's RSS feed
+#[tauri::command]
+pub async fn source_add(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    url: String,
+) -> Result<source_discovery::BulkAddResult, String> {
+    let _perf = perf::completion("source_add");
+    source_discovery::add(&state.conn, &http, &url).await
+}
+
+// Add sources from newline-separated URLs, discovering each page's RSS feed
+#[tauri::command]
+pub async fn sources_bulk_add(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    urls: String,
+) -> Result<Vec<source_discovery::BulkAddResult>, String> {
+    let _perf = perf::completion("sources_bulk_add");
+    source_discovery::bulk_add(&state.conn, &http, &urls).await
+}
+
+// Per-source age cutoff: None = use the global setting, 0 = keep everything
+#[derive(Debug, Deserialize)]
+pub struct SourceMaxAgePayload {
+    pub name: String,
+    pub max_age_days: Option<u32>,
+}
+
+#[tauri::command]
+pub fn source_max_age_set(state: State<DbState>, payload: SourceMaxAgePayload) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    conn.execute(
+        "UPDATE sources SET max_age_days = ?1 WHERE name = ?2",
+        params![payload.max_age_days, payload.name],
+    ).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Mute a source until a time (RFC 3339 or any format `datetime::parse` accepts); None unmutes
+#[derive(Debug, Deserialize)]
+pub struct SourceMutePayload {
+    pub name: String,
+    pub muted_until: Option<String>,
+}
+
+#[tauri::command]
+pub fn source_mute(state: State<DbState>, payload: SourceMutePayload) -> Result<(), String> {
+    let muted_until = match payload.muted_until.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(until) => Some(datetime::parse(until).ok_or_else(|| tr(Msg::InvalidDateTime, until))?.to_rfc3339()),
+        None => None,
+    };
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    conn.execute(
+        "UPDATE sources SET muted_until = ?1 WHERE name = ?2",
+        params![muted_until, payload.name],
+    ).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn folders_list(state: State<DbState>) -> Result<Vec<folders::Folder>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    folders::list(&conn)
+}
+
+#[tauri::command]
+pub fn folder_save(state: State<DbState>, payload: folders::Folder) -> Result<folders::Folder, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    folders::save(&conn, payload)
+}
+
+#[tauri::command]
+pub fn folder_delete(state: State<DbState>, id: String) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    folders::delete(&conn, &id)
+}
+
+// None = take the source out of its folder
+#[derive(Debug, Deserialize)]
+pub struct SourceFolderPayload {
+    pub name: String,
+    pub folder_id: Option<String>,
+}
+
+#[tauri::command]
+pub fn source_folder_set(state: State<DbState>, payload: SourceFolderPayload) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    folders::assign_source(&conn, &payload.name, payload.folder_id.as_deref())
+}
+
+// A source's GitHub trending config: its own, else what its trending URL amounts to; None for other sources
+#[tauri::command]
+pub fn source_trending_get(state: State<DbState>, name: String) -> Result<Option<github_trending::TrendingConfig>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    github_trending::TrendingConfig::load(&conn, &name)
+}
+
+// Create a GitHub trending source from a config, or reconfigure an existing one
+#[derive(Debug, Deserialize)]
+pub struct SourceTrendingPayload {
+    pub name: String,
+    pub config: github_trending::TrendingConfig,
+}
+
+#[tauri::command]
+pub fn source_trending_set(
+    state: State<DbState>,
+    payload: SourceTrendingPayload,
+) -> Result<github_trending::TrendingConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    github_trending::save(&conn, &payload.name, &payload.config)
+}
+
+// Earlier versions of an article replaced by re-crawls
+#[tauri::command]
+pub fn article_revisions_list(state: State<DbState>, id: String) -> Result<Vec<revisions::Revision>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    revisions::list(&conn, &id)
+}
+
+// Syndicated copies collapsed into an article
+#[tauri::command]
+pub fn article_alternates(state: State<DbState>, id: String) -> Result<Vec<syndication::Alternate>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    syndication::list(&conn, &id)
+}
+
+// Keywords extracted from an article, best first
+#[tauri::command]
+pub fn article_keywords_get(state: State<DbState>, id: String) -> Result<Vec<String>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    keywords::for_article(&conn, &id)
+}
+
+// Most used keywords of recent articles, for facet chips (filter with `ListQuery.tag`)
+#[tauri::command]
+pub fn keywords_top(
+    state: State<DbState>,
+    category: Option<String>,
+    days: Option<u32>,
+    limit: Option<usize>,
+) -> Result<Vec<keywords::KeywordCount>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    keywords::top(&conn, category.as_deref(), days.unwrap_or(7), limit.unwrap_or(20).min(100))
+}
+
+// Keywords rising over the last day compared with the week before
+#[tauri::command]
+pub fn keywords_trending(state: State<DbState>, limit: Option<usize>) -> Result<Vec<keywords::TrendingKeyword>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    keywords::trending(&conn, limit.unwrap_or(10).min(100))
+}
+
+// Configured feed file, or bookmarks.xml in the data directory
+pub(crate) fn feed_export_path(conn: &Connection) -> Result<String, String> {
+    let configured = get_setting(conn, "feed_export_path", "")?;
+    if configured.trim().is_empty() {
+        Ok(format!("{}/bookmarks.xml", get_data_dir()?))
+    } else {
+        Ok(configured)
+    }
+}
+
+// Mark as read
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarkReadPayload {
+    pub id: String,
+    #[allow(dead_code)]
+    pub value: bool,
+}
+
+#[tauri::command]
+pub async fn article_mark_read(state: State<'_, DbState>, payload: MarkReadPayload) -> Result<(), String> {
+    let _perf = perf::completion("article_mark_read");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    conn.execute(
+        "UPDATE articles SET is_read = 1 WHERE id = ?1",
+        params![payload.id]
+    ).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Read vs. opened counts, overall and per source; `days` limits to recently fetched articles
+#[derive(Debug, Serialize)]
+pub struct SourceReadingStats {
+    pub source: String,
+    pub total: i64,
+    pub read: i64,
+    pub opened: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadingStats {
+    pub total: i64,
+    pub read: i64,
+    // Opened in the browser at least once
+    pub opened: i64,
+    pub sources: Vec<SourceReadingStats>,
+}
+
+#[tauri::command]
+pub fn reading_stats(state: State<DbState>, days: Option<i64>) -> Result<ReadingStats, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    query_reading_stats(&conn, days)
+}
+
+// Per-source read and click-through counts for articles fetched in the last `days` (all when None)
+fn query_reading_stats(conn: &Connection, days: Option<i64>) -> Result<ReadingStats, String> {
+    let since = days.map(|d| (chrono::Utc::now() - chrono::Duration::days(d.max(1))).to_rfc3339());
+    let mut stmt = conn
+        .prepare(
+            "SELECT source, COUNT(*), SUM(is_read > 0), SUM(opened_at IS NOT NULL) FROM articles
+             WHERE ?1 IS NULL OR fetched_at >= ?1
+             GROUP BY source ORDER BY COUNT(*) DESC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let sources = stmt
+        .query_map(params![since], |row| {
+            Ok(SourceReadingStats {
+                source: row.get(0)?,
+                total: row.get(1)?,
+                read: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                opened: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(ReadingStats {
+        total: sources.iter().map(|s| s.total).sum(),
+        read: sources.iter().map(|s| s.read).sum(),
+        opened: sources.iter().map(|s| s.opened).sum(),
+        sources,
+    })
+}
+
+// Mark every unread article as read, optionally only a category or folder
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkAllReadPayload {
+    pub category: Option<String>,
+    pub folder_id: Option<String>,
+}
+
+#[tauri::command]
+pub async fn articles_mark_all_read(state: State<'_, DbState>, payload: Option<MarkAllReadPayload>) -> Result<usize, String> {
+    let _perf = perf::completion("articles_mark_all_read");
+    let payload = payload.unwrap_or_default();
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let mut sql = "UPDATE articles SET is_read = 1 WHERE is_read = 0 AND (?1 IS NULL OR category = ?1)".to_string();
+    if payload.folder_id.is_some() {
+        sql.push_str(&format!(" AND {}", folders::source_condition(2)));
+    }
+    let category = payload.category.filter(|c| c != "all");
+    let updated = match &payload.folder_id {
+        Some(folder_id) => conn.execute(&sql, params![category, folder_id]),
+        None => conn.execute(&sql, params![category]),
+    }
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(updated)
+}
+
+// Actions for the frontend command palette
+#[tauri::command]
+pub fn commands_palette(state: State<DbState>) -> Result<Vec<palette::PaletteAction>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    palette::actions(&conn)
+}
+
+// Manual add article
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManualAddPayload {
+    pub url: String,
+}
+
+#[tauri::command]
+pub async fn manual_add(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: ManualAddPayload) -> Result<Article, String> {
+    let _perf = perf::completion("manual_add");
+    add_article_from_url(&state.conn, &http, &payload.url, "手动添加")
+        .await?
+        .ok_or_else(|| t(Msg::LinkExists))
+}
+
+// Browser bookmarks import
+#[tauri::command]
+pub fn bookmarks_import_preview(path: String) -> Result<Vec<bookmarks_import::FolderCount>, String> {
+    let bookmarks = bookmarks_import::parse_file(&path)?;
+    Ok(bookmarks_import::folder_counts(&bookmarks))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BookmarksImportPayload {
+    pub path: String,
+    // Folder paths from the preview; empty imports everything
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarksImportStarted {
+    pub total: usize,
+}
+
+#[tauri::command]
+pub fn bookmarks_import(app: AppHandle, payload: BookmarksImportPayload) -> Result<BookmarksImportStarted, String> {
+    let bookmarks = bookmarks_import::parse_file(&payload.path)?;
+    let selected = bookmarks_import::select(bookmarks, &payload.folders);
+    let total = selected.len();
+    bookmarks_import::spawn_import(app, selected);
+    Ok(BookmarksImportStarted { total })
+}
+
+#[tauri::command]
+pub async fn settings_get(state: State<'_, DbState>) -> Result<Settings, String> {
+    let _perf = perf::completion("settings_get");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(load_settings(&conn)?)
+}
+
+#[tauri::command]
+pub async fn settings_update(
+    state: State<'_, DbState>,
+    local_api: State<'_, local_api::LocalApiState>,
+    app: AppHandle,
+    payload: Settings,
+) -> Result<Settings, String> {
+    let _perf = perf::completion("settings_update");
+    let mut settings = payload;
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+
+    set_setting(&conn, "theme", &settings.theme)?;
+    set_setting(&conn, "ai_model", &settings.ai_model)?;
+    set_setting(&conn, "ai_base_url", &settings.ai_base_url)?;
+    set_setting(&conn, "ai_api_key", &settings.ai_api_key)?;
+    set_setting(&conn, "ai_summary_enabled", &settings.ai_summary_enabled.to_string())?;
+
+    // Normalize and apply the language immediately so subsequent messages use it
+    let lang = i18n::Lang::from_code(&settings.language);
+    settings.language = lang.code().to_string();
+    set_setting(&conn, "language", &settings.language)?;
+    i18n::set_lang(lang);
+
+    // A token is required for the local API; generate one the first time it is enabled
+    if settings.local_api_enabled && settings.local_api_token.trim().is_empty() {
+        settings.local_api_token = uuid::Uuid::new_v4().simple().to_string();
+    }
+    set_setting(&conn, "local_api_enabled", &settings.local_api_enabled.to_string())?;
+    set_setting(&conn, "local_api_port", &settings.local_api_port.to_string())?;
+    set_setting(&conn, "local_api_token", &settings.local_api_token)?;
+    set_setting(&conn, "feed_auto_export", &settings.feed_auto_export.to_string())?;
+    set_setting(&conn, "feed_export_path", settings.feed_export_path.trim())?;
+    set_setting(&conn, "card_font_path", settings.card_font_path.trim())?;
+    set_setting(&conn, "max_article_age_days", &settings.max_article_age_days.to_string())?;
+    set_setting(&conn, "crawl_interval_minutes", &settings.crawl_interval_minutes.to_string())?;
+    set_setting(&conn, "cleanup_interval_hours", &settings.cleanup_interval_hours.to_string())?;
+    set_setting(&conn, "trash_retention_days", &settings.trash_retention_days.to_string())?;
+    set_setting(&conn, "seen_urls_retention_days", &settings.seen_urls_retention_days.to_string())?;
+    set_setting(&conn, "http_pool_max_idle_per_host", &settings.http_pool_max_idle_per_host.to_string())?;
+    set_setting(&conn, "http_pool_idle_timeout_secs", &settings.http_pool_idle_timeout_secs.to_string())?;
+    set_setting(&conn, "slow_query_ms", &settings.slow_query_ms.to_string())?;
+    perf::set_slow_query_ms(settings.slow_query_ms);
+    settings.summary_mode = lazy_summary::normalize_mode(&settings.summary_mode).to_string();
+    set_setting(&conn, "summary_mode", &settings.summary_mode)?;
+    set_setting(&conn, "github_reappear_cooloff_days", &settings.github_reappear_cooloff_days.to_string())?;
+    set_setting(&conn, "github_reappear_star_delta", &settings.github_reappear_star_delta.to_string())?;
+    settings.night_batch_start_hour = settings.night_batch_start_hour.min(23);
+    settings.night_batch_end_hour = settings.night_batch_end_hour.min(23);
+    set_setting(&conn, "night_batch_enabled", &settings.night_batch_enabled.to_string())?;
+    set_setting(&conn, "night_batch_start_hour", &settings.night_batch_start_hour.to_string())?;
+    set_setting(&conn, "night_batch_end_hour", &settings.night_batch_end_hour.to_string())?;
+    set_setting(&conn, "night_batch_when_idle", &settings.night_batch_when_idle.to_string())?;
+    set_setting(&conn, "storage_quota_mb", &settings.storage_quota_mb.to_string())?;
+    settings.proxy_down_policy = network::normalize_proxy_down_policy(&settings.proxy_down_policy).to_string();
+    set_setting(&conn, "proxy_down_policy", &settings.proxy_down_policy)?;
+    set_setting(&conn, "low_data_mode", &settings.low_data_mode.to_string())?;
+    low_data::set(settings.low_data_mode);
+
+    let timezone = datetime::DisplayTimezone::from_code(&settings.display_timezone);
+    settings.display_timezone = timezone.code();
+    set_setting(&conn, "display_timezone", &settings.display_timezone)?;
+    datetime::set_display_timezone(timezone);
+    drop(conn);
+
+    apply_local_api_settings(&app, &local_api, &settings)?;
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn profile_list() -> Result<profiles::ProfileRegistry, String> {
+    profiles::load()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileCreatePayload {
+    pub name: String,
+}
+
+// Create a profile with its own freshly seeded database (doesn't switch to it)
+#[tauri::command]
+pub fn profile_create(payload: ProfileCreatePayload) -> Result<profiles::Profile, String> {
+    let profile = profiles::create(&payload.name)?;
+    init_db_at(&profiles::db_path(&profile.id)?).map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(profile)
+}
+
+// Swap the open database for another profile's and apply its settings
+#[tauri::command]
+pub fn profile_switch(
+    app: AppHandle,
+    state: State<DbState>,
+    local_api: State<local_api::LocalApiState>,
+    id: String,
+) -> Result<profiles::Profile, String> {
+    let registry = profiles::load()?;
+    if !registry.profiles.iter().any(|p| p.id == id) {
+        return Err(t(Msg::ProfileNotFound));
+    }
+    let db = init_db_at(&profiles::db_path(&id)?).map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    let settings = load_settings(&db)?;
+    let ai_limits = ai_limits::AiLimitsConfig::load(&db)?;
+    let features = features::load(&db)?;
+    let profile = profiles::set_active(&id)?;
+
+    *state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))? = db;
+    i18n::set_lang(i18n::Lang::from_code(&settings.language));
+    datetime::set_display_timezone(datetime::DisplayTimezone::from_code(&settings.display_timezone));
+    perf::set_slow_query_ms(settings.slow_query_ms);
+    low_data::set(settings.low_data_mode);
+    ai_limits::set(ai_limits);
+    features::set(&features);
+    apply_local_api_settings(&app, &local_api, &settings)?;
+
+    let _ = app.emit("app://profile:switched", profile.clone());
+    Ok(profile)
+}
+
+// Start, restart or stop the local API server to match the given settings
+pub(crate) fn apply_local_api_settings(app: &AppHandle, local_api: &local_api::LocalApiState, settings: &Settings) -> Result<(), String> {
+    let mut slot = local_api.server.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    if let Some(server) = slot.take() {
+        server.stop();
+    }
+    if features::is_enabled(features::LOCAL_API) && settings.local_api_enabled && !settings.local_api_token.is_empty() {
+        let db_path = get_db_path()?;
+        let server = local_api::LocalApiServer::start(app.clone(), &db_path, settings.local_api_port, settings.local_api_token.clone())?;
+        *slot = Some(server);
+    }
+    Ok(())
+}
+
+// Read-later services (Pocket / Instapaper / Wallabag)
+#[tauri::command]
+pub async fn read_later_config_get(state: State<'_, DbState>) -> Result<read_later::ReadLaterConfig, String> {
+    let _perf = perf::completion("read_later_config_get");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    read_later::ReadLaterConfig::load(&conn)
+}
+
+#[tauri::command]
+pub async fn read_later_config_update(
+    state: State<'_, DbState>,
+    payload: read_later::ReadLaterConfig,
+) -> Result<read_later::ReadLaterConfig, String> {
+    let _perf = perf::completion("read_later_config_update");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    read_later::ReadLaterConfig::load(&conn)
+}
+
+#[derive(Debug, Serialize)]
+pub struct PocketAuthStart {
+    pub authorize_url: String,
+}
+
+// Returns the Pocket page the user must open to approve access; call finish afterwards
+#[tauri::command]
+pub async fn read_later_pocket_auth_start(state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<PocketAuthStart, String> {
+    let _perf = perf::completion("read_later_pocket_auth_start");
+    let consumer_key = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        get_setting(&conn, "read_later.pocket_consumer_key", "")?
+    };
+    if consumer_key.is_empty() {
+        return Err(t(Msg::ReadLaterNotConfigured));
+    }
+
+    let (request_token, authorize_url) = read_later::pocket_auth_start(http.get(true), &consumer_key).await?;
+
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    set_setting(&conn, "read_later.pocket_request_token", &request_token)?;
+    Ok(PocketAuthStart { authorize_url })
+}
+
+#[tauri::command]
+pub async fn read_later_pocket_auth_finish(state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<(), String> {
+    let _perf = perf::completion("read_later_pocket_auth_finish");
+    let (consumer_key, request_token) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (
+            get_setting(&conn, "read_later.pocket_consumer_key", "")?,
+            get_setting(&conn, "read_later.pocket_request_token", "")?,
+        )
+    };
+    if request_token.is_empty() {
+        return Err(t(Msg::PocketAuthNotStarted));
+    }
+
+    let access_token = read_later::pocket_auth_finish(http.get(true), &consumer_key, &request_token).await?;
+
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    set_setting(&conn, "read_later.pocket_access_token", &access_token)?;
+    set_setting(&conn, "read_later.pocket_request_token", "")?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendExternalPayload {
+    pub id: String,
+    pub service: read_later::Service,
+}
+
+pub(crate) async fn send_external(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    id: &str,
+    service: read_later::Service,
+) -> Result<(), String> {
+    let (article, config) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article = query_article(&conn, id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+        (article, read_later::ReadLaterConfig::load(&conn)?)
+    };
+
+    // Self-hosted Wallabag instances are often on the LAN; the hosted services need the proxy
+    let use_proxy = match service {
+        read_later::Service::Wallabag => {
+            let url = config.wallabag_url.to_lowercase();
+            !(url.contains("localhost") || url.contains("127.0.0.1") || url.contains("192.168.") || is_chinese_site(&url))
+        }
+        _ => true,
+    };
+    read_later::send(http.get(use_proxy), &config, service, &article.url, &article.title).await
+}
+
+// Queued for later when offline
+#[tauri::command]
+pub async fn article_send_external(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: SendExternalPayload) -> Result<(), String> {
+    let _perf = perf::completion("article_send_external");
+    let result = send_external(&state.conn, &http, &payload.id, payload.service).await;
+    let action = jobs::JobAction::SendExternal { article_id: payload.id, service: payload.service };
+    jobs::queue_if_offline(&state.conn, &http, action, result).await
+}
+
+// Connectivity and proxy state; `refresh` probes now instead of returning the scheduler's last result
+#[tauri::command]
+pub async fn network_status(app: AppHandle, http: State<'_, HttpClients>, refresh: Option<bool>) -> Result<network::NetworkStatus, String> {
+    let _perf = perf::completion("network_status");
+    match network::last_status() {
+        Some(status) if !refresh.unwrap_or(false) => Ok(status),
+        _ => Ok(network::refresh(&app, &http).await),
+    }
+}
+
+// Actions queued while offline, oldest first
+#[tauri::command]
+pub fn pending_jobs(state: State<DbState>) -> Result<Vec<jobs::Job>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    jobs::list(&conn)
+}
+
+#[tauri::command]
+pub fn pending_job_delete(state: State<DbState>, id: i64) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    jobs::delete(&conn, id)
+}
+
+// Google Reader API sync (Miniflux / FreshRSS)
+#[tauri::command]
+pub async fn greader_config_get(state: State<'_, DbState>) -> Result<greader::GReaderConfig, String> {
+    let _perf = perf::completion("greader_config_get");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    greader::GReaderConfig::load(&conn)
+}
+
+#[tauri::command]
+pub async fn greader_config_update(
+    state: State<'_, DbState>,
+    payload: greader::GReaderConfig,
+) -> Result<greader::GReaderConfig, String> {
+    let _perf = perf::completion("greader_config_update");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    greader::GReaderConfig::load(&conn)
+}
+
+#[tauri::command]
+pub async fn greader_sync(state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<greader::SyncReport, String> {
+    let _perf = perf::completion("greader_sync");
+    let (config, snapshot) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (greader::GReaderConfig::load(&conn)?, greader::local_snapshot(&conn)?)
+    }; // Release the lock during network calls
+
+    let client = greader::GReaderClient::login(http.for_url(&config.url).clone(), &config).await?;
+    let (changes, report) = greader::sync(&client, &snapshot).await?;
+
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    greader::apply_local_changes(&conn, &changes)?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn cloud_sync_config_get(state: State<DbState>) -> Result<cloud_sync::CloudSyncConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    cloud_sync::CloudSyncConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn cloud_sync_config_update(
+    state: State<DbState>,
+    payload: cloud_sync::CloudSyncConfig,
+) -> Result<cloud_sync::CloudSyncConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    cloud_sync::CloudSyncConfig::load(&conn)
+}
+
+// Merge the remote copy into the local database, then upload the result
+#[tauri::command]
+pub async fn cloud_sync_now(state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<cloud_sync::SyncSummary, String> {
+    let _perf = perf::completion("cloud_sync_now");
+    let result = cloud_sync::sync_now(&state.conn, &http).await;
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    scheduler::record_run(&conn, scheduler::JOB_CLOUD_SYNC, result.as_ref().err().map(|e| e.as_str()));
+    result
+}
+
+#[tauri::command]
+pub fn webhooks_list(state: State<DbState>) -> Result<Vec<webhooks::Webhook>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    webhooks::list(&conn)
+}
+
+#[tauri::command]
+pub fn webhook_save(state: State<DbState>, payload: webhooks::Webhook) -> Result<webhooks::Webhook, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    webhooks::save(&conn, payload)
+}
+
+#[tauri::command]
+pub fn webhook_delete(state: State<DbState>, id: String) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    webhooks::delete(&conn, &id)
+}
+
+#[tauri::command]
+pub fn watchlists_list(state: State<DbState>) -> Result<Vec<watchlists::Watch>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    watchlists::list(&conn)
+}
+
+#[tauri::command]
+pub fn watchlist_save(state: State<DbState>, payload: watchlists::Watch) -> Result<watchlists::Watch, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    watchlists::save(&conn, payload)
+}
+
+#[tauri::command]
+pub fn watchlist_delete(state: State<DbState>, id: String) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    watchlists::delete(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchlistFeedPayload {
+    id: String,
+    page: Option<usize>,
+    page_size: Option<usize>,
+}
+
+#[tauri::command]
+pub fn watchlist_feed(state: State<DbState>, payload: WatchlistFeedPayload) -> Result<ListResponse, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    watchlists::feed(&conn, &payload.id, payload.page.unwrap_or(1), payload.page_size.unwrap_or(20).clamp(1, 100))
+}
+
+#[tauri::command]
+pub fn rules_list(state: State<DbState>) -> Result<Vec<rules::Rule>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    rules::list(&conn)
+}
+
+#[tauri::command]
+pub fn rule_save(state: State<DbState>, payload: rules::Rule) -> Result<rules::Rule, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    rules::save(&conn, payload)
+}
+
+#[tauri::command]
+pub fn rule_delete(state: State<DbState>, id: String) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    rules::delete(&conn, &id)
+}
+
+// Match history of a rule: which articles it matched and what it did to them
+#[tauri::command]
+pub fn rule_matches(state: State<DbState>, id: String) -> Result<Vec<rules::RuleMatch>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    rules::matches(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RuleTestPayload {
+    rule: rules::Rule,
+    limit: Option<usize>,
+}
+
+// Dry run of a rule, saved or not, against recent articles; nothing is changed
+#[tauri::command]
+pub fn rule_test(state: State<DbState>, payload: RuleTestPayload) -> Result<Vec<Article>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    rules::test(&conn, &payload.rule, payload.limit.unwrap_or(rules::TEST_LIMIT_DEFAULT))
+}
+
+// Post the most recent article to a webhook, ignoring its filters
+#[tauri::command]
+pub async fn webhook_test(state: State<'_, DbState>, http: State<'_, HttpClients>, id: String) -> Result<(), String> {
+    let _perf = perf::completion("webhook_test");
+    let (hook, article) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let hook = webhooks::list(&conn)?
+            .into_iter()
+            .find(|h| h.id == id)
+            .ok_or_else(|| t(Msg::WebhookNotFound))?;
+        (hook, webhooks::test_article(&conn)?)
+    };
+    webhooks::post(&http, &hook, &article).await
+}
+
+#[tauri::command]
+pub fn smtp_config_get(state: State<DbState>) -> Result<email::SmtpConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    email::SmtpConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn smtp_config_update(state: State<DbState>, payload: email::SmtpConfig) -> Result<email::SmtpConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    email::SmtpConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn star_report_config_get(state: State<DbState>) -> Result<star_report::StarReportConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    star_report::StarReportConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn star_report_config_update(state: State<DbState>, payload: star_report::StarReportConfig) -> Result<star_report::StarReportConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    star_report::StarReportConfig::load(&conn)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StarReportPayload {
+    days: Option<i64>,
+    min_gain: Option<u32>,
+}
+
+// Repos that gained `min_gain` stars or passed a milestone; defaults come from the saved config
+#[tauri::command]
+pub fn star_report(state: State<DbState>, payload: Option<StarReportPayload>) -> Result<star_report::StarReport, String> {
+    let payload = payload.unwrap_or_default();
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let mut config = star_report::StarReportConfig::load(&conn)?;
+    config.days = payload.days.unwrap_or(config.days);
+    config.min_gain = payload.min_gain.unwrap_or(config.min_gain);
+    star_report::build(&conn, &config)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DigestPayload {
+    days: Option<i64>,
+}
+
+// Preview of what `digest_email` would send
+#[tauri::command]
+pub fn digest_preview(state: State<DbState>, payload: Option<DigestPayload>) -> Result<digest::Digest, String> {
+    let days = payload.unwrap_or_default().days.unwrap_or(1);
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    digest::build(&conn, days, digest::DEFAULT_PER_CATEGORY)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DigestsListPayload {
+    kind: Option<String>,
+    limit: Option<usize>,
+}
+
+#[tauri::command]
+pub fn digests_list(state: State<DbState>, payload: Option<DigestsListPayload>) -> Result<Vec<digest::DigestSummary>, String> {
+    let payload = payload.unwrap_or_default();
+    let kind = payload.kind.filter(|k| !k.is_empty() && k != "all");
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    digest::list(&conn, kind.as_deref(), payload.limit.unwrap_or(50).clamp(1, 500))
+}
+
+#[tauri::command]
+pub fn digest_get(state: State<DbState>, id: String) -> Result<digest::StoredDigest, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    digest::get(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestExportPayload {
+    id: String,
+    #[serde(default)]
+    format: digest::DigestFormat,
+    path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestExportResult {
+    path: String,
+}
+
+// Write a stored digest to a Markdown or HTML file
+#[tauri::command]
+pub fn digest_export(state: State<DbState>, payload: DigestExportPayload) -> Result<DigestExportResult, String> {
+    let stored = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        digest::get(&conn, &payload.id)?
+    };
+    let path = match payload.path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => format!(
+            "{}/exports/{}.{}",
+            get_data_dir()?,
+            obsidian::sanitize_filename(&stored.title),
+            payload.format.extension()
+        ),
+    };
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    let body = match payload.format {
+        digest::DigestFormat::Markdown => stored.markdown,
+        digest::DigestFormat::Html => stored.html,
+    };
+    std::fs::write(&path, body).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))?;
+    Ok(DigestExportResult { path })
+}
+
+#[tauri::command]
+pub async fn digest_email(state: State<'_, DbState>, payload: Option<DigestPayload>) -> Result<email::DigestEmailResult, String> {
+    let _perf = perf::completion("digest_email");
+    let days = payload.unwrap_or_default().days.unwrap_or(1);
+    email::send_digest(&state.conn, days).await
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DigestWeeklyPayload {
+    #[serde(default)]
+    email: bool,
+}
+
+// Generate and store this week's themed roundup, optionally emailing it
+#[tauri::command]
+pub async fn digest_weekly(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    payload: Option<DigestWeeklyPayload>,
+) -> Result<roundup::WeeklyResult, String> {
+    let _perf = perf::completion("digest_weekly");
+    let email = payload.unwrap_or_default().email;
+    let mut result = roundup::generate(&state.conn, http.get(true)).await?;
+    if email {
+        result.recipients = email::send_report(&state.conn, &result.digest).await?;
+    }
+    Ok(result)
+}
+
+// Replace the local library with the remote copy (local settings are kept)
+#[tauri::command]
+pub async fn cloud_sync_restore(state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<(), String> {
+    let _perf = perf::completion("cloud_sync_restore");
+    cloud_sync::restore(&state.conn, &http).await
+}
+
+// Translate title and full content; `lang` defaults to the UI language
+#[derive(Debug, Deserialize)]
+pub struct TranslatePayload {
+    pub id: String,
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[tauri::command]
+pub async fn article_translate(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: TranslatePayload) -> Result<translate::Translation, String> {
+    let _perf = perf::completion("article_translate");
+    let lang = payload.lang.as_deref().map(i18n::Lang::from_code).unwrap_or_else(i18n::lang);
+    let result = translate::translate_article(&state.conn, http.get(true), &payload.id, lang, payload.force).await;
+    let action = jobs::JobAction::Translate { article_id: payload.id, lang: payload.lang, force: payload.force };
+    jobs::queue_if_offline(&state.conn, &http, action, result).await
+}
+
+// Component scores behind an article's heat, computed as of now
+#[tauri::command]
+pub fn article_heat_breakdown(state: State<DbState>, id: String) -> Result<heat::HeatBreakdown, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    heat::breakdown(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackPayload {
+    id: String,
+    signal: feedback::Signal,
+}
+
+// "More / less like this": learn source and keyword weights, then rescore recent articles
+#[tauri::command]
+pub fn article_feedback(state: State<DbState>, payload: FeedbackPayload) -> Result<heat::HeatBreakdown, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    feedback::record(&conn, &payload.id, payload.signal)?;
+    heat::rescore_recent(&conn, heat::RESCORE_DAYS)?;
+    heat::breakdown(&conn, &payload.id)
+}
+
+#[tauri::command]
+pub fn heat_weights_get(state: State<DbState>) -> Result<feedback::LearnedWeights, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    feedback::LearnedWeights::load(&conn)
+}
+
+#[tauri::command]
+pub fn heat_weights_reset(state: State<DbState>) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    feedback::reset(&conn)?;
+    heat::rescore_recent(&conn, heat::RESCORE_DAYS)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn heat_config_get(state: State<DbState>) -> Result<heat::HeatConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    heat::HeatConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn feed_balance_config_get(state: State<DbState>) -> Result<feed_balance::FeedBalance, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    feed_balance::FeedBalance::load(&conn)
+}
+
+#[tauri::command]
+pub fn feed_balance_config_update(state: State<DbState>, payload: feed_balance::FeedBalance) -> Result<feed_balance::FeedBalance, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    feed_balance::FeedBalance::load(&conn)
+}
+
+// App-wide star filter for GitHub trending repos; sources override it in their trending config
+#[tauri::command]
+pub fn github_star_filter_get(state: State<DbState>) -> Result<github_trending::StarFilter, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    github_trending::StarFilter::load(&conn)
+}
+
+#[tauri::command]
+pub fn github_star_filter_update(
+    state: State<DbState>,
+    payload: github_trending::StarFilter,
+) -> Result<github_trending::StarFilter, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    github_trending::StarFilter::load(&conn)
+}
+
+#[tauri::command]
+pub fn light_pages_config_get(state: State<DbState>) -> Result<light_pages::LightPagesConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    light_pages::LightPagesConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn light_pages_config_update(
+    state: State<DbState>,
+    payload: light_pages::LightPagesConfig,
+) -> Result<light_pages::LightPagesConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    light_pages::LightPagesConfig::load(&conn)
+}
+
+// Save the scoring config and rescore recent articles with it
+#[tauri::command]
+pub fn heat_config_update(state: State<DbState>, payload: heat::HeatConfig) -> Result<heat::HeatConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    heat::rescore_recent(&conn, heat::RESCORE_DAYS)?;
+    heat::HeatConfig::load(&conn)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaperCodePayload {
+    id: String,
+    #[serde(default)]
+    force: bool,
+}
+
+// Implementation repository of a paper article, looked up on first request
+#[tauri::command]
+pub async fn article_paper_code(state: State<'_, DbState>, http: State<'_, HttpClients>, payload: PaperCodePayload) -> Result<Option<String>, String> {
+    let _perf = perf::completion("article_paper_code");
+    paper_code::link_article(&state.conn, &http, &payload.id, payload.force).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentsSummaryPayload {
+    id: String,
+    #[serde(default)]
+    force: bool,
+}
+
+// AI summary of the community reaction in an article's HN / Reddit thread
+#[tauri::command]
+pub async fn article_comments_summarize(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    payload: CommentsSummaryPayload,
+) -> Result<comment_highlights::CommentsSummary, String> {
+    let _perf = perf::completion("article_comments_summarize");
+    comment_highlights::summarize_article(&state.conn, &http, &payload.id, payload.force).await
+}
+
+#[tauri::command]
+pub fn glossary_list(state: State<DbState>) -> Result<Vec<glossary::GlossaryEntry>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    glossary::list(&conn)
+}
+
+#[tauri::command]
+pub fn glossary_save(state: State<DbState>, payload: glossary::GlossaryEntry) -> Result<glossary::GlossaryEntry, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    glossary::save(&conn, payload)
+}
+
+#[tauri::command]
+pub fn glossary_delete(state: State<DbState>, id: String) -> Result<(), String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    glossary::delete(&conn, &id)
+}
+
+// Correct title, category, source label or published date; returns the updated article
+#[tauri::command]
+pub fn article_update(state: State<DbState>, payload: article_edits::ArticleUpdate) -> Result<Article, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    article_edits::apply(&conn, &payload)?;
+    query_article(&conn, &payload.id)?.ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+// Metadata corrections of an article with the values they replaced, oldest first
+#[tauri::command]
+pub fn article_edits_list(state: State<DbState>, id: String) -> Result<Vec<article_edits::ArticleEdit>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    article_edits::list(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SummarySetPayload {
+    pub id: String,
+    // None (or empty) hands the summary back to regeneration
+    pub summary: Option<String>,
+}
+
+// Edit an article's summary by hand; manual summaries are kept by regeneration and re-crawls
+#[tauri::command]
+pub fn article_summary_set(state: State<DbState>, payload: SummarySetPayload) -> Result<Article, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    match payload.summary.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(summary) => {
+            conn.execute(
+                "UPDATE articles SET summary = ?1, summary_source = 'manual', summary_error = NULL WHERE id = ?2",
+                params![summary, payload.id],
+            )
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+            conn.execute(
+                "UPDATE articles_fts SET summary = cjk_segment(?1) WHERE rowid = (SELECT rowid FROM articles WHERE id = ?2)",
+                params![summary, payload.id],
+            )
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+        }
+        None => {
+            conn.execute(
+                "UPDATE articles SET summary_source = 'template', summary_status = 'pending' WHERE id = ?1 AND summary_source = 'manual'",
+                params![payload.id],
+            )
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+        }
+    }
+    query_article(&conn, &payload.id)?.ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SummaryEnsureResult {
+    pub article: Article,
+    // A summary is being generated; `app://summary:ready` follows
+    pub pending: bool,
+}
+
+// Called when the reader opens an article: in lazy summary mode a pending summary is generated now
+#[tauri::command]
+pub fn article_summary_ensure(app: AppHandle, state: State<DbState>, id: String) -> Result<SummaryEnsureResult, String> {
+    let pending = lazy_summary::request(&app, &id)?;
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    let article = query_article(&conn, &id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+    Ok(SummaryEnsureResult { article, pending })
+}
+
+// Per-provider AI request limits
+#[tauri::command]
+pub fn ai_limits_config_get(state: State<DbState>) -> Result<ai_limits::AiLimitsConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    ai_limits::AiLimitsConfig::load(&conn)
+}
+
+#[tauri::command]
+pub fn ai_limits_config_update(state: State<DbState>, payload: ai_limits::AiLimitsConfig) -> Result<ai_limits::AiLimitsConfig, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    payload.save(&conn)?;
+    let config = ai_limits::AiLimitsConfig::load(&conn)?;
+    ai_limits::set(config.clone());
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn features_get(state: State<DbState>) -> Result<Vec<features::FeatureFlag>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    features::load(&conn)
+}
+
+// Turn a feature on or off; the local API starts or stops right away, the scheduler from its next tick
+#[tauri::command]
+pub fn features_set(
+    app: AppHandle,
+    state: State<DbState>,
+    local_api: State<local_api::LocalApiState>,
+    name: String,
+    enabled: bool,
+) -> Result<Vec<features::FeatureFlag>, String> {
+    let (flags, settings) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        features::save(&conn, &name, enabled)?;
+        (features::load(&conn)?, load_settings(&conn)?)
+    };
+    features::set(&flags);
+    if name == features::LOCAL_API {
+        apply_local_api_settings(&app, &local_api, &settings)?;
+    }
+    Ok(flags)
+}
+
+// Saved UI session state (selected category, scroll anchor, open article); `keys` empty = all of it
+#[tauri::command]
+pub fn ui_state_get(
+    state: State<DbState>,
+    keys: Option<Vec<String>>,
+) -> Result<std::collections::HashMap<String, serde_json::Value>, String> {
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    ui_state::get(&conn, &keys.unwrap_or_default())
+}
+
+// Save UI session state; a null value removes the key
+#[tauri::command]
+pub fn ui_state_set(state: State<DbState>, values: std::collections::HashMap<String, serde_json::Value>) -> Result<(), String> {
+    let mut conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    ui_state::set(&mut conn, &values)
+}
+
+// A piece of a summary being streamed by ai_summarize
+#[derive(Debug, Serialize, Clone)]
+pub struct AiSummarizeDelta {
+    id: Option<String>,
+    delta: String,
+}
+
+// AI summarize - calls the configured provider, streaming the reply as
+// `app://ai-summarize:delta` events. With an article `id` the summary is
+// also stored on the article, and the request is queued when offline
+#[tauri::command]
+pub async fn ai_summarize(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    app: AppHandle,
+    content: String,
+    id: Option<String>,
+) -> Result<String, String> {
+    let _perf = perf::completion("ai_summarize");
+    let result = summarize_text(&state.conn, &http, &content, |delta| {
+        let _ = app.emit("app://ai-summarize:delta", AiSummarizeDelta { id: id.clone(), delta: delta.to_string() });
+    })
+    .await;
+    let Some(id) = id else { return result };
+    if read_only::is_enabled() {
+        return result;
+    }
+    if let Ok(summary) = &result {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        update_summary(&conn, &id, summary)?;
+    }
+    jobs::queue_if_offline(&state.conn, &http, jobs::JobAction::Summarize { article_id: id }, result).await
+}
+
+// Check the configured AI endpoint: a tiny completion, plus one embedding
+// when an embedding model is given
+#[tauri::command]
+pub async fn ai_test(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    embedding_model: Option<String>,
+) -> Result<ai::ProbeResult, String> {
+    let _perf = perf::completion("ai_test");
+    let config = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        ai::AiConfig::load(&conn)?
+    };
+    Ok(ai::probe(http.get(true), &config, embedding_model.as_deref()).await)
+}
+
+// Progress update structs
+#[derive(Debug, Serialize, Clone)]
+pub struct SummaryUpdateStartEvent {
+    total: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SummaryUpdateProgressEvent {
+    current: usize,
+    total: usize,
+    title: String,
+    updated: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SummaryUpdateCompleteEvent {
+    total_updated: usize,
+    total_processed: usize,
+}
+
+// Batch regenerate summaries
+#[tauri::command]
+pub async fn articles_regenerate_summaries(
+    state: State<'_, DbState>,
+    http: State<'_, HttpClients>,
+    app: AppHandle,
+) -> Result<usize, String> {
+    let _perf = perf::completion("articles_regenerate_summaries");
+    // Check if AI summarization is enabled and configured (from environment variables or database)
+    let ai_config = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        ai::AiConfig::load(&conn).ok()
+    };
+
+    if ai_config.is_none() {
+        return Err(t(Msg::AiNotConfigured));
+    }
+
+    // Collect all articles with template summaries that need regeneration
+    let (articles, glossary) = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, title, content FROM articles WHERE summary_source = 'template'"
+        ).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+
+        let result: Vec<(String, String, String)> = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+            ))
+        }).map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .filter_map(Result::ok)
+        .collect();
+
+        drop(stmt);
+        let glossary = glossary::list(&conn)?;
+        drop(conn);
+        (result, glossary)
+    };
+
+    let total = articles.len();
+    let mut updated = 0;
+
+    // Emit start event
+    let start_payload = SummaryUpdateStartEvent { total };
+    let _ = app.emit("app://summaries-update:start", start_payload);
+
+    for (index, (id, title, content)) in articles.into_iter().enumerate() {
+        let current = index + 1;
+
+        // Emit progress event
+        let progress_payload = SummaryUpdateProgressEvent {
+            current,
+            total,
+            title: title.clone(),
+            updated,
+        };
+        let _ = app.emit("app://summaries-update:progress", progress_payload);
+
+        // Generate new summary using AI
+        let new_summary = summarize_or_template(&http, ai_config.as_ref(), &glossary, &title, &content, "批量更新").await;
+
+        // Update database - need to acquire lock again
+        {
+            let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+            store_summary(&conn, &id, &new_summary)?;
+        } // conn is dropped here
+
+        updated += 1;
+
+        // Emit updated progress
+        let progress_payload = SummaryUpdateProgressEvent {
+            current,
+            total,
+            title: title.clone(),
+            updated,
+        };
+        let _ = app.emit("app://summaries-update:progress", progress_payload);
+    }
+
+    // Emit complete event
+    let complete_payload = SummaryUpdateCompleteEvent {
+        total_updated: updated,
+        total_processed: total,
+    };
+    let _ = app.emit("app://summaries-update:complete", complete_payload);
+
+    Ok(updated)
+}
+
+// Crawler implementation to fetch from RSS/API sources
+#[tauri::command]
+pub async fn crawler_run_once(app: AppHandle, state: State<'_, DbState>, http: State<'_, HttpClients>) -> Result<CrawlResult, String> {
+    let _perf = perf::completion("crawler_run_once");
+    let result = run_crawl(&app, &state.conn, &http).await;
+    if let Ok(conn) = state.conn.lock() {
+        scheduler::record_run(&conn, scheduler::JOB_CRAWL, result.as_ref().err().map(|e| e.to_string()).as_deref());
+    }
+    Ok(result?)
+}
+
+// Whether the database was opened read-only (writes are disabled)
+#[tauri::command]
+pub fn app_read_only() -> bool {
+    read_only::is_enabled()
+}
+
+// What startup recovery did when the database was found damaged; None = it opened normally
+#[tauri::command]
+pub fn db_recovery_report() -> Option<db_recovery::RecoveryReport> {
+    db_recovery::report()
+}
+
+// Only absolute http(s) URLs are handed to the OS, and in their re-serialized
+// (percent-encoded) form so no shell or handler sees raw input
+fn external_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| tr(Msg::InvalidUrl, e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(tr(Msg::UrlSchemeNotAllowed, scheme)),
+    }
+}
+
+// Open URL in system browser; with `article_id` the click-through is recorded on the article
+#[tauri::command]
+pub async fn open_external(app: AppHandle, state: State<'_, DbState>, url: String, article_id: Option<String>) -> Result<(), String> {
+    let _perf = perf::completion("open_external");
+    let url = external_url(&url)?;
+    app.opener().open_url(url.as_str(), None::<&str>).map_err(|e| tr(Msg::OpenUrlFailed, e))?;
+    // Only a browser that actually opened counts as a click-through
+    if let (Some(id), false) = (article_id, read_only::is_enabled()) {
+        {
+            let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+            conn.execute(
+                "UPDATE articles SET opened_at = ?1, open_count = COALESCE(open_count, 0) + 1 WHERE id = ?2",
+                params![chrono::Utc::now().to_rfc3339(), id],
+            )
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+        }
+        lazy_summary::request(&app, &id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_stats_count_reads_and_click_throughs_per_source() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let last_month = (chrono::Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+        for (id, source, fetched_at, is_read, opened_at) in [
+            ("a1", "Blog", &now, 1, Some(&now)),
+            ("a2", "Blog", &now, 1, None),
+            ("a3", "Blog", &now, 0, None),
+            ("a4", "Papers", &now, 0, None),
+            ("a5", "Papers", &last_month, 1, Some(&last_month)),
+        ] {
+            conn.execute(
+                "INSERT INTO articles (id, title, url, source, published_at, fetched_at, is_read, opened_at)
+                 VALUES (?1, 'Post', ?2, ?3, ?4, ?4, ?5, ?6)",
+                params![id, format!("https://example.com/{}", id), source, fetched_at, is_read, opened_at],
+            )
+            .unwrap();
+        }
+
+        let stats = query_reading_stats(&conn, Some(7)).unwrap();
+        assert_eq!((stats.total, stats.read, stats.opened), (4, 2, 1));
+        let blog = &stats.sources[0];
+        assert_eq!((blog.source.as_str(), blog.total, blog.read, blog.opened), ("Blog", 3, 2, 1));
+        let papers = &stats.sources[1];
+        assert_eq!((papers.total, papers.read, papers.opened), (1, 0, 0), "no reads still counts zero");
+
+        let all = query_reading_stats(&conn, None).unwrap();
+        assert_eq!((all.total, all.read, all.opened), (5, 3, 2));
+    }
+
+    #[test]
+    fn only_http_urls_are_opened_externally() {
+        let url = external_url("  https://example.com/a b?q=\"x\"  ").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/a%20b?q=%22x%22");
+        assert!(external_url("http://example.com").is_ok());
+        for url in ["file:///etc/passwd", "javascript:alert(1)", "ms-settings:", "example.com/no-scheme", ""] {
+            assert!(external_url(url).is_err(), "{} allowed", url);
+        }
+    }
+}
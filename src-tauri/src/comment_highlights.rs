@@ -0,0 +1,328 @@
+// Summaries of the community reaction on HN / Reddit threads
+// (`articles.comments_summary`), next to the article summary.
+//
+// The top-level comments of an article's `discussion_url` come from the
+// Algolia HN API or Reddit's JSON thread view, and the configured AI
+// provider sums them up in a few sentences in the UI language. Threads are
+// summarized on request (`article_comments_summarize`), and after each crawl
+// for new articles with at least AUTO_MIN_COMMENTS comments while the
+// `comment_highlights` feature flag is on.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::ai::{Ai, AiConfig, ChatProvider, ChatRequest};
+use crate::i18n::{self, t, tr, Lang, Msg};
+use crate::{features, HttpClients};
+
+const HN_ITEMS_API: &str = "https://hn.algolia.com/api/v1/items";
+const MAX_COMMENTS: usize = 30;
+// Per comment and in total, to keep the prompt small
+const MAX_COMMENT_CHARS: usize = 600;
+const MAX_PROMPT_CHARS: usize = 8000;
+// Below this a thread has no "reaction" worth summarizing after a crawl
+const AUTO_MIN_COMMENTS: i64 = 10;
+const MAX_AUTO_PER_RUN: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentsSummary {
+    pub id: String,
+    pub summary: String,
+    // Top-level comments the summary was made from
+    pub comment_count: usize,
+    pub summarized_at: String,
+    pub cached: bool,
+}
+
+// Where the top-level comments of a thread can be read as JSON
+enum Thread {
+    HackerNews(String),
+    Reddit(String),
+}
+
+impl Thread {
+    fn of(discussion_url: &str) -> Option<Thread> {
+        let url = reqwest::Url::parse(discussion_url).ok()?;
+        let host = url.host_str()?;
+        if host == "news.ycombinator.com" {
+            let id = url.query_pairs().find(|(key, _)| key == "id")?.1.to_string();
+            return Some(Thread::HackerNews(format!("{}/{}", HN_ITEMS_API, id)));
+        }
+        if host == "reddit.com" || host.ends_with(".reddit.com") {
+            let path = url.path().trim_end_matches('/');
+            return Some(Thread::Reddit(format!(
+                "https://www.reddit.com{}.json?depth=1&sort=top&limit={}",
+                path, MAX_COMMENTS
+            )));
+        }
+        None
+    }
+
+    fn api_url(&self) -> &str {
+        match self {
+            Thread::HackerNews(url) | Thread::Reddit(url) => url,
+        }
+    }
+
+    fn comments(&self, json: &serde_json::Value) -> Vec<String> {
+        let texts: Vec<&str> = match self {
+            Thread::HackerNews(_) => json["children"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|child| child["text"].as_str())
+                .collect(),
+            Thread::Reddit(_) => json[1]["data"]["children"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|child| child["kind"] == "t1" && !child["data"]["stickied"].as_bool().unwrap_or(false))
+                .filter_map(|child| child["data"]["body"].as_str())
+                .collect(),
+        };
+        texts
+            .into_iter()
+            .map(plain_text)
+            .filter(|text| !text.is_empty() && text != "[deleted]" && text != "[removed]")
+            .take(MAX_COMMENTS)
+            .collect()
+    }
+}
+
+// HN comments are HTML, Reddit's Markdown; either way the text reads fine without markup
+fn plain_text(text: &str) -> String {
+    let text = if text.contains('<') {
+        scraper::Html::parse_fragment(&text.replace("<p>", "\n<p>")).root_element().text().collect::<String>()
+    } else {
+        text.to_string()
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_COMMENT_CHARS {
+        text.chars().take(MAX_COMMENT_CHARS).collect::<String>() + "…"
+    } else {
+        text
+    }
+}
+
+async fn fetch_comments(http: &HttpClients, thread: &Thread) -> Result<Vec<String>, String> {
+    let response = http
+        .get(true)
+        .get(thread.api_url())
+        .header("Accept", "application/json")
+        .header("User-Agent", "ai-news-aggregator/1.0")
+        .timeout(Duration::from_secs(20))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?;
+    if !response.status().is_success() {
+        return Err(tr(Msg::HttpRequestFailed, response.status()));
+    }
+    let json: serde_json::Value = response.json().await.map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    Ok(thread.comments(&json))
+}
+
+fn prompt(title: &str, comments: &[String], lang: Lang) -> ChatRequest {
+    let mut user = format!("Story: {}\n\nComments:\n", title);
+    for comment in comments {
+        if user.chars().count() + comment.chars().count() > MAX_PROMPT_CHARS {
+            break;
+        }
+        user.push_str("- ");
+        user.push_str(comment);
+        user.push('\n');
+    }
+    let instructions = match lang {
+        Lang::Zh => "用 2-3 句简体中文（不超过 120 字）概括讨论区对这篇资讯的主要反应：共识、主要质疑和补充的有用信息。只输出概括。",
+        Lang::En => "Sum up in 2-3 sentences (under 60 words) how the discussion reacted to this story: the consensus, the main objections and any useful facts commenters added. Output only the summary.",
+    };
+    ChatRequest {
+        system: instructions.to_string(),
+        user,
+        max_tokens: Some(300),
+        timeout: Duration::from_secs(60),
+    }
+}
+
+fn store(conn: &Connection, id: &str, summary: &str, comment_count: usize, summarized_at: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE articles SET comments_summary = ?1, comments_summary_count = ?2, comments_summarized_at = ?3 WHERE id = ?4",
+        params![summary, comment_count as i64, summarized_at, id],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+struct Target {
+    title: String,
+    discussion_url: Option<String>,
+    cached: Option<(String, i64, String)>,
+}
+
+fn load_target(conn: &Connection, id: &str) -> Result<Target, String> {
+    conn.query_row(
+        "SELECT title, discussion_url, comments_summary, comments_summary_count, comments_summarized_at
+         FROM articles WHERE id = ?1",
+        params![id],
+        |row| {
+            let summary: Option<String> = row.get(2)?;
+            let count: Option<i64> = row.get(3)?;
+            let at: Option<String> = row.get(4)?;
+            Ok(Target {
+                title: row.get(0)?,
+                discussion_url: row.get(1)?,
+                cached: summary.zip(at).map(|(summary, at)| (summary, count.unwrap_or(0), at)),
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| tr(Msg::DbQueryFailed, e))?
+    .ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+async fn summarize_thread(http: &HttpClients, config: &AiConfig, title: &str, thread: &Thread) -> Result<(String, usize), String> {
+    let comments = fetch_comments(http, thread).await?;
+    if comments.is_empty() {
+        return Err(t(Msg::CommentsNotFound));
+    }
+    let ai = Ai::new(http.get(true), config);
+    let summary = ai.complete(&prompt(title, &comments, i18n::lang())).await?;
+    Ok((summary, comments.len()))
+}
+
+// Reaction summary of an article's thread, reusing the stored one unless `force`
+pub async fn summarize_article(db: &Mutex<Connection>, http: &HttpClients, id: &str, force: bool) -> Result<CommentsSummary, String> {
+    let (target, config) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (load_target(&conn, id)?, AiConfig::load(&conn))
+    }; // Release the lock during network calls
+
+    if let (false, Some((summary, count, at))) = (force, target.cached) {
+        return Ok(CommentsSummary {
+            id: id.to_string(),
+            summary,
+            comment_count: count as usize,
+            summarized_at: at,
+            cached: true,
+        });
+    }
+    let thread = target.discussion_url.as_deref().and_then(Thread::of).ok_or_else(|| t(Msg::CommentsNotFound))?;
+    let config = config.map_err(|_| t(Msg::AiNotConfigured))?;
+    let (summary, comment_count) = summarize_thread(http, &config, &target.title, &thread).await?;
+
+    let summarized_at = chrono::Utc::now().to_rfc3339();
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    store(&conn, id, &summary, comment_count, &summarized_at)?;
+    Ok(CommentsSummary { id: id.to_string(), summary, comment_count, summarized_at, cached: false })
+}
+
+// After a crawl: summarize the busiest new threads while the feature flag is on
+pub async fn summarize_new_articles(db: &Mutex<Connection>, http: &HttpClients, article_ids: &[String]) {
+    if !features::is_enabled(features::COMMENT_HIGHLIGHTS) || article_ids.is_empty() {
+        return;
+    }
+    let (targets, config) = {
+        let Ok(conn) = db.lock() else { return };
+        let Ok(config) = AiConfig::load(&conn) else { return };
+        let mut targets: Vec<(String, String, String, i64)> = article_ids
+            .iter()
+            .filter_map(|id| {
+                conn.query_row(
+                    "SELECT title, discussion_url, COALESCE(comment_count, 0) FROM articles
+                     WHERE id = ?1 AND discussion_url IS NOT NULL AND comments_summary IS NULL",
+                    params![id],
+                    |row| Ok((id.clone(), row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .ok()
+            })
+            .filter(|(_, _, _, comments)| *comments >= AUTO_MIN_COMMENTS)
+            .collect();
+        targets.sort_by_key(|target| std::cmp::Reverse(target.3));
+        targets.truncate(MAX_AUTO_PER_RUN);
+        (targets, config)
+    };
+
+    for (id, title, discussion_url, _) in targets {
+        let Some(thread) = Thread::of(&discussion_url) else { continue };
+        match summarize_thread(http, &config, &title, &thread).await {
+            Ok((summary, comment_count)) => {
+                if let Ok(conn) = db.lock() {
+                    if let Err(e) = store(&conn, &id, &summary, comment_count, &chrono::Utc::now().to_rfc3339()) {
+                        eprintln!("Failed to store comment summary for '{}': {}", title, e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Failed to summarize comments of '{}': {}", title, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threads_of_hn_and_reddit() {
+        let thread = Thread::of("https://news.ycombinator.com/item?id=4242").unwrap();
+        assert_eq!(thread.api_url(), "https://hn.algolia.com/api/v1/items/4242");
+        let thread = Thread::of("https://old.reddit.com/r/ml/comments/abc/title/").unwrap();
+        assert_eq!(thread.api_url(), "https://www.reddit.com/r/ml/comments/abc/title.json?depth=1&sort=top&limit=30");
+        assert!(Thread::of("https://news.ycombinator.com/news").is_none(), "no item id");
+        assert!(Thread::of("https://lobste.rs/s/abc").is_none());
+    }
+
+    #[test]
+    fn comments_are_read_as_plain_text() {
+        let hn = Thread::HackerNews(String::new());
+        let json = serde_json::json!({"children": [
+            {"text": "<p>First  point.<p>Second <i>point</i>."},
+            {"text": null},
+            {"text": "[deleted]"}
+        ]});
+        assert_eq!(hn.comments(&json), ["First point. Second point."]);
+
+        let reddit = Thread::Reddit(String::new());
+        let json = serde_json::json!([{}, {"data": {"children": [
+            {"kind": "t1", "data": {"body": "Mod note", "stickied": true}},
+            {"kind": "t1", "data": {"body": "Great **results**"}},
+            {"kind": "more", "data": {"body": "x"}},
+            {"kind": "t1", "data": {"body": "[removed]"}}
+        ]}}]);
+        assert_eq!(reddit.comments(&json), ["Great **results**"]);
+
+        let long = plain_text(&"x".repeat(MAX_COMMENT_CHARS + 5));
+        assert_eq!(long.chars().count(), MAX_COMMENT_CHARS + 1);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn prompt_stays_within_its_budget() {
+        let comments: Vec<String> = (0..30).map(|i| format!("{} {}", i, "y".repeat(MAX_COMMENT_CHARS - 3))).collect();
+        let request = prompt("Open weights", &comments, Lang::En);
+        assert!(request.user.starts_with("Story: Open weights\n\nComments:\n- 0 "));
+        assert!(request.user.chars().count() <= MAX_PROMPT_CHARS);
+        assert!(request.user.lines().filter(|line| line.starts_with("- ")).count() < comments.len());
+        assert_ne!(prompt("t", &[], Lang::Zh).system, request.system);
+    }
+
+    #[test]
+    fn stored_summary_is_the_cached_one() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source, discussion_url)
+             VALUES ('a1', 'Open weights', 'https://example.com/1', 'HN', 'https://news.ycombinator.com/item?id=1')",
+            [],
+        )
+        .unwrap();
+        let target = load_target(&conn, "a1").unwrap();
+        assert_eq!(target.title, "Open weights");
+        assert!(target.cached.is_none());
+
+        store(&conn, "a1", "Mostly positive.", 12, "2026-01-01T00:00:00Z").unwrap();
+        let target = load_target(&conn, "a1").unwrap();
+        assert_eq!(target.cached, Some(("Mostly positive.".into(), 12, "2026-01-01T00:00:00Z".into())));
+        assert!(load_target(&conn, "gone").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+// Timing of crawl runs.
+//
+// Every crawl measures where its time goes, per source and in total:
+// `fetch` is time spent waiting on the network (feed requests and any
+// follow-up requests such as GitHub repo pages), `parse` the rest of the
+// fetcher's work, `ai` summary generation, `db` storing and scoring, and
+// `post` the work after all sources (watchlists, webhooks, paper links).
+// Each run is printed as one log line and kept in `crawl_runs`, newest
+// MAX_RUNS only, for `crawl_metrics`.
+
+use std::future::Future;
+use std::time::Instant;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::datetime;
+use crate::i18n::{tr, Msg};
+
+const MAX_RUNS: i64 = 200;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS crawl_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            started_at TEXT NOT NULL,
+            total_ms INTEGER NOT NULL,
+            inserted INTEGER NOT NULL,
+            updated INTEGER NOT NULL,
+            failed_sources INTEGER NOT NULL,
+            phases TEXT NOT NULL,
+            sources TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct PhaseTimes {
+    pub fetch_ms: u64,
+    pub parse_ms: u64,
+    pub ai_ms: u64,
+    pub db_ms: u64,
+}
+
+impl PhaseTimes {
+    pub fn add(&mut self, other: &PhaseTimes) {
+        self.fetch_ms += other.fetch_ms;
+        self.parse_ms += other.parse_ms;
+        self.ai_ms += other.ai_ms;
+        self.db_ms += other.db_ms;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceMetrics {
+    pub source: String,
+    // Items the fetcher returned
+    pub items: usize,
+    #[serde(flatten)]
+    pub times: PhaseTimes,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlPhases {
+    #[serde(flatten)]
+    pub times: PhaseTimes,
+    pub post_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrawlRun {
+    pub id: i64,
+    pub started_at: String,
+    pub total_ms: u64,
+    pub inserted: usize,
+    pub updated: usize,
+    pub failed_sources: usize,
+    pub phases: CrawlPhases,
+    // Slowest first
+    pub sources: Vec<SourceMetrics>,
+}
+
+pub fn ms_since(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+// Await `future`, adding the time it took to `total_ms`
+pub async fn timed<F: Future>(total_ms: &mut u64, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    *total_ms += ms_since(start);
+    output
+}
+
+// Collects one run's numbers
+pub struct Recorder {
+    started: Instant,
+    started_at: String,
+    sources: Vec<SourceMetrics>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            started: Instant::now(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Recorder {
+    pub fn source(&mut self, metrics: SourceMetrics) {
+        self.sources.push(metrics);
+    }
+
+    // Print the run and store it; `post_ms` covers the work after the sources
+    pub fn finish(
+        mut self,
+        conn: &Connection,
+        inserted: usize,
+        updated: usize,
+        failed_sources: usize,
+        post_ms: u64,
+    ) -> Result<(), String> {
+        let total_ms = ms_since(self.started);
+        let mut times = PhaseTimes::default();
+        for source in &self.sources {
+            times.add(&source.times);
+        }
+        self.sources.sort_by_key(|s| std::cmp::Reverse(s.times.fetch_ms + s.times.parse_ms + s.times.ai_ms + s.times.db_ms));
+        println!(
+            "Crawl finished in {} ms (fetch {} / parse {} / ai {} / db {} / post {}): {} new, {} updated, {} sources failed",
+            total_ms, times.fetch_ms, times.parse_ms, times.ai_ms, times.db_ms, post_ms, inserted, updated, failed_sources
+        );
+
+        let phases = CrawlPhases { times, post_ms };
+        conn.execute(
+            "INSERT INTO crawl_runs (started_at, total_ms, inserted, updated, failed_sources, phases, sources)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                self.started_at,
+                total_ms as i64,
+                inserted as i64,
+                updated as i64,
+                failed_sources as i64,
+                serde_json::to_string(&phases).unwrap_or_else(|_| "{}".to_string()),
+                serde_json::to_string(&self.sources).unwrap_or_else(|_| "[]".to_string())
+            ],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+        conn.execute(
+            "DELETE FROM crawl_runs WHERE id <= (SELECT MAX(id) FROM crawl_runs) - ?1",
+            params![MAX_RUNS],
+        )
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+        Ok(())
+    }
+}
+
+// Recorded runs, newest first
+pub fn list(conn: &Connection, limit: usize) -> Result<Vec<CrawlRun>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, started_at, total_ms, inserted, updated, failed_sources, phases, sources
+             FROM crawl_runs ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let runs = stmt
+        .query_map(params![limit as i64], |row| {
+            let phases: String = row.get(6)?;
+            let sources: String = row.get(7)?;
+            Ok(CrawlRun {
+                id: row.get(0)?,
+                started_at: datetime::to_display(&row.get::<_, String>(1)?),
+                total_ms: row.get::<_, i64>(2)? as u64,
+                inserted: row.get::<_, i64>(3)? as usize,
+                updated: row.get::<_, i64>(4)? as usize,
+                failed_sources: row.get::<_, i64>(5)? as usize,
+                phases: serde_json::from_str(&phases)
+                    .unwrap_or(CrawlPhases { times: PhaseTimes::default(), post_ms: 0 }),
+                sources: serde_json::from_str(&sources).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str, fetch_ms: u64, ai_ms: u64) -> SourceMetrics {
+        SourceMetrics {
+            source: name.to_string(),
+            items: 3,
+            times: PhaseTimes { fetch_ms, parse_ms: 1, ai_ms, db_ms: 1 },
+            error: None,
+        }
+    }
+
+    #[test]
+    fn runs_sum_phases_and_list_slowest_sources_first() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let mut recorder = Recorder::default();
+        recorder.source(source("Fast", 10, 0));
+        recorder.source(source("Slow", 300, 200));
+        recorder.finish(&conn, 4, 1, 0, 7).unwrap();
+
+        let runs = list(&conn, 10).unwrap();
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!((run.inserted, run.updated, run.phases.post_ms), (4, 1, 7));
+        assert_eq!((run.phases.times.fetch_ms, run.phases.times.ai_ms, run.phases.times.db_ms), (310, 200, 2));
+        assert_eq!(run.sources.iter().map(|s| s.source.as_str()).collect::<Vec<_>>(), ["Slow", "Fast"]);
+
+        let mut total = 0;
+        let value = tauri::async_runtime::block_on(timed(&mut total, async { 42 }));
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn only_the_newest_runs_are_kept() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for inserted in 0..(MAX_RUNS as usize + 5) {
+            Recorder::default().finish(&conn, inserted, 0, 0, 0).unwrap();
+        }
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM crawl_runs", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, MAX_RUNS);
+        let newest = list(&conn, 2).unwrap();
+        assert_eq!(newest.iter().map(|r| r.inserted).collect::<Vec<_>>(), [MAX_RUNS as usize + 4, MAX_RUNS as usize + 3]);
+    }
+}
@@ -0,0 +1,1725 @@
+// Crawler: fetching sources (RSS, web pages, GitHub trending), turning
+// entries into articles and storing them, and the HTTP clients it uses.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::ai::summarize_or_template;
+use crate::db::{article_url_exists, get_setting, insert_page_article, query_article, DbState};
+use crate::error::Error;
+use crate::i18n::{t, tr, Msg};
+use crate::{
+    ai, article_image, comment_highlights, crawl_metrics, datetime, default_github_reappear_cooloff_days,
+    default_github_reappear_star_delta, discussion, extractive, github_readme, github_repos, github_trending, glossary, heat, http_cache, keywords,
+    light_pages, lazy_summary, low_data, network, night_batch, paper_code, paywall, platform, read_time, readability, revisions, rules, scrape_rules, seen_urls, shutdown, site_extractors, syndication,
+    watchlists, webhooks,
+    Article, GeneratedSummary, Settings, SummarySource, SummaryStatus,
+};
+
+#[derive(Debug, Serialize)]
+pub struct CrawlResult {
+    pub inserted: usize,
+    // Known articles whose content changed since the last crawl
+    pub updated: usize,
+    // New articles collapsed into an earlier copy with the same content
+    pub syndicated: usize,
+    pub failed_sources: usize,
+}
+
+pub(crate) const GITHUB_BASE: &str = "https://github.com";
+// Content of a trending repo with neither a description nor a README yet
+const GITHUB_PLACEHOLDER: &str = "GitHub trending project";
+
+// Manual adds and bookmark imports fetch from one site at most this often
+const MANUAL_ADD_HOST_INTERVAL: Duration = Duration::from_secs(2);
+
+// Next free fetch slot per host for manual adds
+static MANUAL_ADD_SLOTS: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+
+// Struct for crawled article data (passed between fetch and store)
+struct CrawledArticle {
+    title: String,
+    url: String,
+    content: String,
+    published_at: String,
+    date_is_estimated: bool,
+    image_url: Option<String>,
+    // Stars / points reported by the source, for heat scoring
+    engagement: Option<i64>,
+    // Comment thread on an aggregator (HN, Reddit) with its counts
+    discussion: Option<discussion::Discussion>,
+    // Podcast / video length in seconds
+    media_duration: Option<i64>,
+}
+
+// Fetch a page and store it as an article; None if the URL is already stored
+pub(crate) async fn add_article_from_url(db: &Mutex<Connection>, http: &HttpClients, url: &str, source: &str) -> Result<Option<Article>, Error> {
+    // Normalize URL
+    let normalized_url = normalize_url(url);
+
+    // Check if article already exists
+    {
+        let conn = db.lock()?;
+        if article_url_exists(&conn, &normalized_url) {
+            return Ok(None);
+        }
+    }
+
+    // Fetch page content, spaced out per site so imports don't get the IP blocked
+    wait_for_host_slot(url).await;
+    let request = http
+        .for_url(url)
+        .get(url)
+        .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+        .timeout(std::time::Duration::from_secs(15));
+    let html = http_cache::fetch_text(db, url, request, http_cache::PAGE_TTL).await?.body;
+
+    let mut page = extract_page(url, &html);
+    // Paywalled or heavy: the text of a lighter version makes a better article
+    if let Some(light) = light_pages::lighter(db, http, url, &html).await {
+        if let Some(readable) = readability::extract(&light, url) {
+            let text = extractive::plain_text(&readable.html);
+            if text.chars().count() > page.content.chars().count() {
+                page.content = text;
+            }
+        }
+    }
+    if page.image_url.is_empty() {
+        if let Some(readable) = readability::extract(&html, url) {
+            page.image_url = article_image::first_large_image(http, &readable.images).await.unwrap_or_default();
+        }
+    }
+
+    // Insert into database
+    let conn = db.lock()?;
+    insert_page_article(&conn, &normalized_url, page, source).map(Some)
+}
+
+async fn wait_for_host_slot(url: &str) {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) else { return };
+    let wait = {
+        let mut slots = MANUAL_ADD_SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        let slots = slots.get_or_insert_with(HashMap::new);
+        let now = Instant::now();
+        let slot = slots.get(&host).map_or(now, |next| (*next).max(now));
+        slots.insert(host, slot + MANUAL_ADD_HOST_INTERVAL);
+        slot - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Metadata pulled from a fetched or submitted HTML page
+pub(crate) struct ExtractedPage {
+    pub title: String,
+    pub content: String,
+    pub image_url: String,
+    // RFC 3339; None = unknown, the add time stands in
+    pub published_at: Option<String>,
+    pub media_duration: Option<i64>,
+    pub paywalled: bool,
+}
+
+// The site's own extractor where there is one, generic meta tags for whatever it leaves out
+pub(crate) fn extract_page(url: &str, html: &str) -> ExtractedPage {
+    let generic = extract_generic_page(html);
+    let Some(site) = site_extractors::extract(url, html) else { return generic };
+    ExtractedPage {
+        title: site.title.unwrap_or(generic.title),
+        content: site.content.unwrap_or(generic.content),
+        image_url: site.image_url.unwrap_or(generic.image_url),
+        published_at: site.published_at,
+        media_duration: site.media_duration,
+        paywalled: generic.paywalled,
+    }
+}
+
+fn extract_generic_page(html: &str) -> ExtractedPage {
+    // Parse HTML to extract title and content
+    let document = scraper::Html::parse_document(html);
+
+    // Extract title - try <title>, <h1>, og:title
+    let title = document
+        .select(&scraper::Selector::parse("title").unwrap())
+        .next()
+        .map(|el| el.text().collect::<String>().trim().to_string())
+        .or_else(|| {
+            document
+                .select(&scraper::Selector::parse("meta[property='og:title']").unwrap())
+                .next()
+                .and_then(|el| el.value().attr("content"))
+                .map(|s| s.to_string())
+        })
+        .or_else(|| {
+            document
+                .select(&scraper::Selector::parse("h1").unwrap())
+                .next()
+                .map(|el| el.text().collect::<String>().trim().to_string())
+        })
+        .unwrap_or_else(|| "未知标题".to_string());
+
+    // Extract description/content - try meta description, og:description
+    let content = document
+        .select(&scraper::Selector::parse("meta[name='description']").unwrap())
+        .next()
+        .and_then(|el| el.value().attr("content"))
+        .map(|s| s.to_string())
+        .or_else(|| {
+            document
+                .select(&scraper::Selector::parse("meta[property='og:description']").unwrap())
+                .next()
+                .and_then(|el| el.value().attr("content"))
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| "手动添加的文章".to_string());
+
+    // Extract image URL
+    let image_url = document
+        .select(&scraper::Selector::parse("meta[property='og:image']").unwrap())
+        .next()
+        .and_then(|el| el.value().attr("content"))
+        .unwrap_or("")
+        .to_string();
+
+    ExtractedPage { title, content, image_url, published_at: None, media_duration: None, paywalled: paywall::detect(html) }
+}
+
+// Save a page submitted by the browser extension; the HTML is supplied so no fetch is needed.
+// Returns None when the URL is already stored.
+pub(crate) fn save_submitted_page(state: &DbState, url: &str, html: &str, title: Option<&str>) -> Result<Option<Article>, Error> {
+    let normalized_url = normalize_url(url);
+    let mut page = extract_page(url, html);
+    if let Some(title) = title.map(str::trim).filter(|t| !t.is_empty()) {
+        page.title = title.to_string();
+    }
+
+    let conn = state.conn.lock()?;
+    if article_url_exists(&conn, &normalized_url) {
+        return Ok(None);
+    }
+    insert_page_article(&conn, &normalized_url, page, "浏览器扩展").map(Some)
+}
+
+// Articles stored by a crawl, emitted per source as soon as they are inserted
+#[derive(Debug, Serialize, Clone)]
+struct ArticlesNewEvent {
+    source: String,
+    articles: Vec<Article>,
+}
+
+// The proxy failed the crawl's warm-up probe; `sources` need it and were skipped or fetched directly per `policy`
+#[derive(Debug, Serialize, Clone)]
+struct ProxyDownEvent {
+    proxy: String,
+    policy: &'static str,
+    sources: Vec<String>,
+}
+
+// Where a crawl reports its progress: the app's event bus, or nowhere (`()`)
+pub trait CrawlEvents: Sync {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S);
+}
+
+impl CrawlEvents for AppHandle {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+impl CrawlEvents for () {
+    fn notify<S: Serialize + Clone>(&self, _event: &str, _payload: S) {}
+}
+
+pub(crate) async fn run_crawl(
+    events: &impl CrawlEvents,
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+) -> Result<CrawlResult, Error> {
+    let _busy = shutdown::busy();
+    // Get active sources from database
+    let (sources_data, glossary, lazy_summaries, deferring, proxy_policy) = {
+        let conn = db.lock()?;
+
+        // Age cutoff: the source's own, else the global setting
+        let global_max_age: u32 = get_setting(&conn, "max_article_age_days", "0")?.parse().unwrap_or(0);
+        let mut stmt = conn.prepare(
+            "SELECT name, url, source_type, COALESCE(max_age_days, ?1), scrape_rules, trending_config FROM sources
+             WHERE is_active = 1 AND (muted_until IS NULL OR muted_until <= ?2) LIMIT ?3"
+        ).map_err(Error::Prepare)?;
+
+        let mut sources: Vec<CrawlSource> = stmt
+            .query_map(params![global_max_age, chrono::Utc::now().to_rfc3339(), http.profile.max_sources], |row| {
+                Ok(CrawlSource {
+                    name: row.get(0)?,
+                    url: row.get(1)?,
+                    source_type: row.get(2)?,
+                    max_age_days: row.get(3)?,
+                    rules: row.get::<_, Option<String>>(4)?.and_then(|json| serde_json::from_str(&json).ok()),
+                    trending: row.get::<_, Option<String>>(5)?.and_then(|json| serde_json::from_str(&json).ok()),
+                })
+            })
+            .map_err(Error::Query)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::Collect)?;
+        // Weekly / monthly trending sources wait out their refresh interval
+        sources.retain(|source| source.trending.as_ref().is_none_or(|config| github_trending::is_due(&conn, &source.name, config)));
+
+        (
+            sources,
+            glossary::list(&conn)?,
+            lazy_summary::is_enabled(&conn)?,
+            night_batch::defers(&conn),
+            network::proxy_down_policy(&conn)?,
+        )
+    }; // Release the lock before async operations
+
+    // Warm-up: one cheap request tells whether the proxy works, instead of a timeout per international source
+    let proxied_sources: Vec<String> =
+        sources_data.iter().filter(|source| !is_chinese_site(&source.url)).map(|source| source.name.clone()).collect();
+    let proxy_down = !proxied_sources.is_empty() && !network::proxy_works(http).await;
+    // Only for this crawl: everything else (AI calls, manual adds, sync) keeps using the proxy
+    let _bypass = (proxy_down && proxy_policy == network::PROXY_DOWN_DIRECT).then(|| http.bypass_proxy());
+    if proxy_down {
+        eprintln!("Proxy {} unavailable, {} sources that need it: {}", http.proxy().unwrap_or_default(), proxy_policy, proxied_sources.join(", "));
+        events.notify("app://crawl:proxy-down", ProxyDownEvent {
+            proxy: http.proxy().unwrap_or_default().to_string(),
+            policy: proxy_policy,
+            sources: proxied_sources,
+        });
+    }
+    let skip_proxied = proxy_down && proxy_policy == network::PROXY_DOWN_SKIP;
+
+    // Check if AI summarization is enabled and configured (from environment variables)
+    let ai_config = ai::AiConfig::from_env();
+
+    let mut failed_sources_count = 0;
+    let mut inserted_ids: Vec<String> = Vec::new();
+    let mut rule_events = Vec::new();
+    let mut updated_total = 0;
+    let mut syndicated_total = 0;
+    let mut metrics = crawl_metrics::Recorder::default();
+
+    // Fetch each source, generate summaries, then store its articles right away so the feed fills in live.
+    // Sources that time out get a second try after all the others, when the proxy may have recovered
+    let mut queue: VecDeque<CrawlSource> = sources_data.into();
+    let mut timed_out: Vec<CrawlSource> = Vec::new();
+    let mut retrying = false;
+    loop {
+        let Some(source) = queue.pop_front() else {
+            if retrying || timed_out.is_empty() {
+                break;
+            }
+            retrying = true;
+            queue.extend(timed_out.drain(..));
+            continue;
+        };
+        // The app is closing: keep what's stored, leave the other sources for the next crawl
+        if shutdown::requested() {
+            break;
+        }
+        let source_name = source.name.clone();
+        let mut articles_to_insert: Vec<(String, CrawledArticle, GeneratedSummary)> = Vec::new();
+        let mut summaries_cut_short = false;
+        let mut source_metrics = crawl_metrics::SourceMetrics {
+            source: source_name.clone(),
+            items: 0,
+            times: crawl_metrics::PhaseTimes::default(),
+            error: None,
+        };
+        if skip_proxied && !is_chinese_site(&source.url) {
+            source_metrics.error = Some(t(Msg::ProxyUnavailable));
+            failed_sources_count += 1;
+            metrics.source(source_metrics);
+            continue;
+        }
+        let fetch_started = Instant::now();
+        let result = fetch_articles_from_source(db, http, &source, deferring, &mut source_metrics.times.fetch_ms).await;
+        let times = &mut source_metrics.times;
+        times.parse_ms = crawl_metrics::ms_since(fetch_started).saturating_sub(times.fetch_ms);
+
+        match result {
+            Ok(mut articles) => {
+                source_metrics.items = articles.len();
+                // Skip pruned URLs before spending AI calls on their summaries
+                let db_started = Instant::now();
+                {
+                    let conn = db.lock()?;
+                    articles.retain(|article| !seen_urls::is_seen(&conn, &article.url).unwrap_or(false));
+                    if source.trending.is_some() {
+                        github_trending::record_fetch(&conn, &source_name)?;
+                    }
+                }
+                times.db_ms += crawl_metrics::ms_since(db_started);
+                let ai_started = Instant::now();
+                for article in articles {
+                    let summary = if lazy_summaries || deferring {
+                        // Summarized the first time it's opened, or in the night-batch window
+                        lazy_summary::placeholder(&article.title, &article.content, &source_name)
+                    } else if shutdown::requested() {
+                        // Queued below and summarized on the next start
+                        summaries_cut_short = true;
+                        lazy_summary::placeholder(&article.title, &article.content, &source_name)
+                    } else {
+                        summarize_or_template(http, ai_config.as_ref(), &glossary, &article.title, &article.content, &source_name).await
+                    };
+
+                    articles_to_insert.push((source_name.clone(), article, summary));
+                }
+                times.ai_ms = crawl_metrics::ms_since(ai_started);
+            },
+            Err(e) if !retrying && e.is_timeout() => {
+                eprintln!("Source '{}' timed out, trying again after the other sources: {}", source_name, e);
+                timed_out.push(source);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch from source '{}': {}", source_name, e);
+                source_metrics.error = Some(e.to_string());
+                failed_sources_count += 1;
+            }
+        }
+
+        if articles_to_insert.is_empty() {
+            metrics.source(source_metrics);
+            continue;
+        }
+        let db_started = Instant::now();
+        let new_articles = {
+            let mut conn = db.lock()?;
+            let mut stored = store_crawled_articles(&mut conn, &articles_to_insert)?;
+            // Rules first: they may delete an article or change what its score depends on
+            let applied = rules::apply(&conn, &stored.inserted)?;
+            stored.inserted.retain(|id| !applied.deleted.contains(id));
+            rule_events.extend(applied.events);
+            heat::rescore_ids(&conn, &stored.inserted)?;
+            keywords::tag_articles(&conn, &stored.inserted)?;
+            keywords::tag_articles(&conn, &stored.updated)?;
+            if deferring || summaries_cut_short {
+                let touched: Vec<String> = stored.inserted.iter().chain(&stored.updated).cloned().collect();
+                if !lazy_summaries {
+                    night_batch::queue(&conn, night_batch::TASK_SUMMARY, &touched)?;
+                }
+                if deferring && (source.trending.is_some() || http.is_github_trending(&source.url)) {
+                    night_batch::queue(&conn, night_batch::TASK_README, &touched)?;
+                }
+            }
+            updated_total += stored.updated.len();
+            syndicated_total += stored.syndicated;
+            stored.inserted.iter()
+                .filter_map(|id| query_article(&conn, id).ok().flatten())
+                .collect::<Vec<_>>()
+        };
+        source_metrics.times.db_ms += crawl_metrics::ms_since(db_started);
+        metrics.source(source_metrics);
+        if !new_articles.is_empty() {
+            inserted_ids.extend(new_articles.iter().map(|a| a.id.clone()));
+            events.notify("app://articles:new", ArticlesNewEvent {
+                source: source_name,
+                articles: new_articles,
+            });
+        }
+    }
+    let inserted_total = inserted_ids.len();
+
+    let post_started = Instant::now();
+    let watch_events = {
+        let conn = db.lock()?;
+        watchlists::match_new_articles(&conn, &inserted_ids)?
+    };
+    for event in watch_events {
+        events.notify("app://watchlist:match", event);
+    }
+    for event in rule_events {
+        events.notify("app://rules:match", event);
+    }
+    webhooks::notify_new_articles(db, http, &inserted_ids).await;
+    if deferring || shutdown::requested() {
+        let conn = db.lock()?;
+        night_batch::queue(&conn, night_batch::TASK_PAPER_CODE, &inserted_ids)?;
+        night_batch::queue(&conn, night_batch::TASK_COMMENTS, &inserted_ids)?;
+    } else {
+        paper_code::link_new_articles(db, http, &inserted_ids).await;
+        comment_highlights::summarize_new_articles(db, http, &inserted_ids).await;
+    }
+
+    {
+        let conn = db.lock()?;
+        let post_ms = crawl_metrics::ms_since(post_started);
+        if let Err(e) = metrics.finish(&conn, inserted_total, updated_total, failed_sources_count, post_ms) {
+            eprintln!("Failed to record crawl metrics: {}", e);
+        }
+    }
+
+    Ok(CrawlResult {
+        inserted: inserted_total,
+        updated: updated_total,
+        syndicated: syndicated_total,
+        failed_sources: failed_sources_count
+    })
+}
+
+// Ids touched by a crawl store: new articles, and known URLs whose content changed.
+// `syndicated` counts copies recorded as alternates instead of being inserted
+#[derive(Debug, Default)]
+struct StoredArticles {
+    inserted: Vec<String>,
+    updated: Vec<String>,
+    syndicated: usize,
+}
+
+impl StoredArticles {
+    fn extend(&mut self, other: StoredArticles) {
+        self.inserted.extend(other.inserted);
+        self.updated.extend(other.updated);
+        self.syndicated += other.syndicated;
+    }
+}
+
+// Store crawled articles in one transaction. A constraint violation rolls the
+// batch back and retries article by article, skipping the bad rows.
+fn store_crawled_articles(conn: &mut Connection, articles: &[(String, CrawledArticle, GeneratedSummary)]) -> Result<StoredArticles, Error> {
+    let fetched_at = chrono::Utc::now().to_rfc3339();
+
+    let tx = conn.transaction().map_err(Error::Insert)?;
+    match insert_crawled_articles(&tx, articles, &fetched_at) {
+        Ok(stored) => {
+            tx.commit().map_err(Error::Insert)?;
+            return Ok(stored);
+        }
+        Err(e) if is_constraint_violation(&e) => {
+            eprintln!("Batch insert hit a constraint error, retrying per article: {}", e);
+            tx.rollback().map_err(Error::Insert)?;
+        }
+        Err(e) => return Err(e),
+    }
+
+    let mut stored = StoredArticles::default();
+    for article in articles {
+        let tx = conn.transaction().map_err(Error::Insert)?;
+        match insert_crawled_articles(&tx, std::slice::from_ref(article), &fetched_at) {
+            Ok(one) => {
+                tx.commit().map_err(Error::Insert)?;
+                stored.extend(one);
+            }
+            Err(e) if is_constraint_violation(&e) => {
+                eprintln!("Skipping article '{}': {}", article.1.url, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(stored)
+}
+
+// Stored copy of an article a crawl saw again
+struct StoredVersion {
+    rowid: i64,
+    id: String,
+    title: String,
+    summary: String,
+    content: String,
+    content_hash: Option<String>,
+    fetched_at: String,
+    summary_status: SummaryStatus,
+    manual_summary: bool,
+    // Corrected with `article_update`; re-crawls keep it
+    title_edited: bool,
+}
+
+// Insert articles whose URL isn't stored yet, along with their FTS rows. Known URLs
+// whose content hash changed are updated in place, keeping the old version as a revision.
+// Near-identical copies of a stored article are recorded as its alternates instead.
+fn insert_crawled_articles(
+    conn: &Connection,
+    articles: &[(String, CrawledArticle, GeneratedSummary)],
+    fetched_at: &str,
+) -> Result<StoredArticles, Error> {
+    let mut existing_stmt = conn
+        .prepare_cached(
+            "SELECT rowid, id, title, COALESCE(summary, ''), COALESCE(content, ''), content_hash, COALESCE(fetched_at, ''),
+                    summary_status, summary_source = 'manual',
+                    EXISTS(SELECT 1 FROM article_edits WHERE article_id = articles.id AND field = 'title')
+             FROM articles WHERE url = ?1",
+        )
+        .map_err(Error::Prepare)?;
+    let mut insert_stmt = conn
+        .prepare_cached(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at, image_url, content_hash, date_is_estimated, engagement, summary_status, summary_error, summary_source, discussion_url, comment_count, points, media_duration, reading_minutes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+        )
+        .map_err(Error::Prepare)?;
+    let mut fts_stmt = conn
+        .prepare_cached("INSERT INTO articles_fts (rowid, title, summary, content) VALUES (?1, cjk_segment(?2), cjk_segment(?3), cjk_segment(?4))")
+        .map_err(Error::Prepare)?;
+    let mut update_stmt = conn
+        .prepare_cached(
+            "UPDATE articles SET title = ?1, summary = ?2, content = ?3, content_hash = ?4, fetched_at = ?5, summary_status = ?6,
+                    summary_error = ?7, summary_source = ?8, reading_minutes = ?9
+             WHERE rowid = ?10",
+        )
+        .map_err(Error::Prepare)?;
+    let mut fts_update_stmt = conn
+        .prepare_cached("UPDATE articles_fts SET title = cjk_segment(?1), summary = cjk_segment(?2), content = cjk_segment(?3) WHERE rowid = ?4")
+        .map_err(Error::Prepare)?;
+    // Counts move on every crawl; a feed that stops reporting them keeps the last ones
+    let mut engagement_stmt = conn
+        .prepare_cached(
+            "UPDATE articles SET engagement = COALESCE(?1, engagement), discussion_url = COALESCE(?2, discussion_url),
+                    comment_count = COALESCE(?3, comment_count), points = COALESCE(?4, points)
+             WHERE rowid = ?5",
+        )
+        .map_err(Error::Prepare)?;
+
+    let mut stored = StoredArticles::default();
+    for (source_name, article, summary) in articles {
+        let hash = revisions::content_hash(&article.content);
+        let existing = existing_stmt
+            .query_row(params![&article.url], |row| {
+                Ok(StoredVersion {
+                    rowid: row.get(0)?,
+                    id: row.get(1)?,
+                    title: row.get(2)?,
+                    summary: row.get(3)?,
+                    content: row.get(4)?,
+                    content_hash: row.get(5)?,
+                    fetched_at: row.get(6)?,
+                    summary_status: SummaryStatus::parse(&row.get::<_, Option<String>>(7)?.unwrap_or_default()),
+                    manual_summary: row.get(8)?,
+                    title_edited: row.get(9)?,
+                })
+            })
+            .optional()
+            .map_err(Error::Query)?;
+
+        if let Some(old) = existing {
+            if article.engagement.is_some() || article.discussion.is_some() {
+                let discussion = article.discussion.as_ref();
+                engagement_stmt
+                    .execute(params![
+                        article.engagement,
+                        discussion.map(|d| &d.url),
+                        discussion.and_then(|d| d.comment_count),
+                        discussion.and_then(|d| d.points),
+                        old.rowid
+                    ])
+                    .map_err(Error::Update)?;
+            }
+            // Rows stored before hashing was added are hashed on first comparison
+            let old_hash = old.content_hash.unwrap_or_else(|| revisions::content_hash(&old.content));
+            // An empty description is a feed dropping the field, not an edit
+            if old_hash == hash || article.content.trim().is_empty() {
+                continue;
+            }
+            revisions::record(conn, &old.id, &old.title, &old.summary, &old.content, &old.fetched_at)
+                .map_err(Error::Insert)?;
+            // A summary the user wrote survives content updates
+            let (summary_text, status, error, source) = if old.manual_summary {
+                (&old.summary, old.summary_status, None, SummarySource::Manual)
+            } else {
+                (&summary.text, summary.status, summary.error.clone(), summary.source())
+            };
+            let title = if old.title_edited { &old.title } else { &article.title };
+            update_stmt
+                .execute(params![
+                    title,
+                    summary_text,
+                    &article.content,
+                    &hash,
+                    fetched_at,
+                    status.as_str(),
+                    error,
+                    source.as_str(),
+                    read_time::reading_minutes(&article.content),
+                    old.rowid
+                ])
+                .map_err(Error::Update)?;
+            fts_update_stmt
+                .execute(params![title, summary_text, &article.content, old.rowid])
+                .map_err(Error::FtsInsert)?;
+            if let Some(signature) = syndication::signature(&article.content) {
+                syndication::store_signature(conn, &old.id, &signature).map_err(Error::Insert)?;
+            }
+            stored.updated.push(old.id);
+            continue;
+        }
+
+        // Pruned by cleanup earlier; the feed just hasn't dropped it yet
+        if seen_urls::is_seen(conn, &article.url).map_err(Error::Query)? {
+            continue;
+        }
+        if syndication::is_alternate(conn, &article.url).map_err(Error::Query)? {
+            continue;
+        }
+        let category = source_category(conn, source_name).map_err(Error::Query)?;
+        let signature = syndication::signature(&article.content);
+        if let Some(signature) = &signature {
+            if let Some(canonical_id) = syndication::find_canonical(conn, signature).map_err(Error::Query)? {
+                let copy = syndication::Copy {
+                    url: &article.url,
+                    title: &article.title,
+                    source: source_name,
+                    category: &category,
+                    published_at: &article.published_at,
+                    date_is_estimated: article.date_is_estimated,
+                };
+                syndication::record_alternate(conn, &canonical_id, &copy).map_err(Error::Insert)?;
+                stored.syndicated += 1;
+                continue;
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let rowid = insert_stmt
+            .insert(params![
+                &id,
+                &article.title,
+                summary.text,
+                &article.content,
+                &article.url,
+                source_name,
+                &category,
+                &article.published_at,
+                fetched_at,
+                article.image_url.as_deref().unwrap_or_default(),
+                &hash,
+                article.date_is_estimated,
+                article.engagement,
+                summary.status.as_str(),
+                summary.error,
+                summary.source().as_str(),
+                article.discussion.as_ref().map(|d| &d.url),
+                article.discussion.as_ref().and_then(|d| d.comment_count),
+                article.discussion.as_ref().and_then(|d| d.points),
+                article.media_duration,
+                read_time::reading_minutes(&article.content)
+            ])
+            .map_err(Error::Insert)?;
+
+        // FTS rows share the article's integer rowid
+        fts_stmt
+            .execute(params![rowid, &article.title, summary.text, &article.content])
+            .map_err(Error::FtsInsert)?;
+        if let Some(signature) = &signature {
+            syndication::store_signature(conn, &id, signature).map_err(Error::Insert)?;
+        }
+
+        stored.inserted.push(id);
+    }
+    Ok(stored)
+}
+
+fn is_constraint_violation(e: &Error) -> bool {
+    matches!(e.sqlite(), Some(rusqlite::Error::SqliteFailure(f, _)) if f.code == rusqlite::ErrorCode::ConstraintViolation)
+}
+
+// An active source as a crawl sees it
+struct CrawlSource {
+    name: String,
+    url: String,
+    source_type: String,
+    // Effective cutoff: the source's own, else the global setting
+    max_age_days: u32,
+    rules: Option<scrape_rules::ScrapeRules>,
+    // GitHub trending pages generated from a config instead of `url`
+    trending: Option<github_trending::TrendingConfig>,
+}
+
+// Fetch articles from a source, returning data without database operations.
+// Items published more than `max_age_days` ago (0 = no limit) are dropped.
+async fn fetch_articles_from_source(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    source: &CrawlSource,
+    // Night-batch mode: leave out per-item fetches that can wait
+    deferring: bool,
+    fetch_ms: &mut u64,
+) -> Result<Vec<CrawledArticle>, Error> {
+    let (source_name, url) = (source.name.as_str(), source.url.as_str());
+    let rules = source.rules.as_ref();
+    let articles = match source.source_type.as_str() {
+        "RSS" => match discussion::reddit_json_url(url) {
+            Some(listing_url) => fetch_reddit_listing(db, http, &listing_url, fetch_ms).await?,
+            None => fetch_rss_feed(db, http, source_name, url, fetch_ms).await?,
+        },
+        "WEB" => {
+            // Check if this is a GitHub trending URL
+            if let Some(trending) = &source.trending {
+                let urls = trending.urls(&http.github_base);
+                fetch_github_trending(db, http, source_name, &urls, Some(trending), deferring, fetch_ms).await?
+            } else if http.is_github_trending(url) {
+                fetch_github_trending(db, http, source_name, &[url.to_string()], None, deferring, fetch_ms).await?
+            } else {
+                fetch_web_page(db, http, source_name, url, rules, fetch_ms).await?
+            }
+        },
+        _ => Vec::new()
+    };
+    Ok(skip_old_articles(source_name, articles, source.max_age_days))
+}
+
+// Drop items older than the cutoff, based on the parsed (RFC 3339) published date
+fn skip_old_articles(source_name: &str, articles: Vec<CrawledArticle>, max_age_days: u32) -> Vec<CrawledArticle> {
+    if max_age_days == 0 {
+        return articles;
+    }
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(max_age_days as i64);
+    let total = articles.len();
+    let kept: Vec<CrawledArticle> = articles
+        .into_iter()
+        .filter(|a| {
+            chrono::DateTime::parse_from_rfc3339(&a.published_at)
+                .map(|published| published >= cutoff)
+                .unwrap_or(true)
+        })
+        .collect();
+    if kept.len() < total {
+        println!("{}: skipped {} items older than {} days", source_name, total - kept.len(), max_age_days);
+    }
+    kept
+}
+
+// Proxied and direct HTTP clients, built once at startup and shared by every request
+// so connection pools and TLS sessions are reused
+pub struct HttpClients {
+    proxied: reqwest::Client,
+    direct: reqwest::Client,
+    // Where GitHub trending sources, repository pages and READMEs (API) are fetched from
+    github_base: String,
+    // The proxy `proxied` goes through; None when both clients are the same
+    proxy: Option<String>,
+    // The proxy is down and the policy says to go direct; set while a crawl's `ProxyBypass` lives
+    bypass_proxy: AtomicBool,
+    // This platform's crawl limits (`platform.rs`)
+    pub(crate) profile: &'static platform::CrawlProfile,
+}
+
+// Proxied requests go direct until this is dropped
+pub(crate) struct ProxyBypass<'a>(&'a HttpClients);
+
+impl Drop for ProxyBypass<'_> {
+    fn drop(&mut self) {
+        self.0.bypass_proxy.store(false, Ordering::Relaxed);
+    }
+}
+
+impl HttpClients {
+    // Pool sizing comes from settings and applies from the next start
+    pub(crate) fn new(settings: &Settings) -> Result<Self, Error> {
+        Ok(HttpClients {
+            proxied: create_http_client(true, settings)?,
+            direct: create_http_client(false, settings)?,
+            github_base: GITHUB_BASE.to_string(),
+            proxy: proxy_url(),
+            bypass_proxy: AtomicBool::new(false),
+            profile: platform::crawl_profile(),
+        })
+    }
+
+    // One client for every URL, proxy or not; for tests against a local server
+    #[cfg(feature = "test-support")]
+    pub fn with_client(client: reqwest::Client) -> Self {
+        HttpClients {
+            proxied: client.clone(),
+            direct: client,
+            github_base: GITHUB_BASE.to_string(),
+            proxy: None,
+            bypass_proxy: AtomicBool::new(false),
+            profile: platform::crawl_profile(),
+        }
+    }
+
+    // Fetch GitHub from `base` (scheme and host) instead of github.com
+    #[cfg(feature = "test-support")]
+    pub fn with_github_base(mut self, base: &str) -> Self {
+        self.github_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Report `proxy` as the one the proxied client goes through, for the crawl's warm-up probe
+    #[cfg(feature = "test-support")]
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    // Crawl with the mobile (true) or the desktop limits instead of this build's
+    #[cfg(feature = "test-support")]
+    pub fn with_mobile_profile(mut self, mobile: bool) -> Self {
+        self.profile = platform::profile_for(mobile);
+        self
+    }
+
+    fn is_github_trending(&self, url: &str) -> bool {
+        url.contains("github.com/trending") || url.starts_with(&format!("{}/trending", self.github_base))
+    }
+
+    pub(crate) fn get(&self, use_proxy: bool) -> &reqwest::Client {
+        if use_proxy && !self.bypass_proxy.load(Ordering::Relaxed) { &self.proxied } else { &self.direct }
+    }
+
+    // The proxied client even while bypassing it, for probing the proxy
+    pub(crate) fn proxied(&self) -> &reqwest::Client {
+        &self.proxied
+    }
+
+    pub(crate) fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    pub(crate) fn bypass_proxy(&self) -> ProxyBypass<'_> {
+        self.bypass_proxy.store(true, Ordering::Relaxed);
+        ProxyBypass(self)
+    }
+
+    // Chinese domestic sites go direct, everything else through the proxy
+    pub(crate) fn for_url(&self, url: &str) -> &reqwest::Client {
+        self.get(!is_chinese_site(url))
+    }
+}
+
+// Proxy for international sites from the environment, else the common Clash default on desktop;
+// None on mobile without one in the environment
+pub(crate) fn proxy_url() -> Option<String> {
+    std::env::var("HTTP_PROXY")
+        .or_else(|_| std::env::var("http_proxy"))
+        .or_else(|_| std::env::var("HTTPS_PROXY"))
+        .or_else(|_| std::env::var("https_proxy"))
+        .ok()
+        .or_else(|| platform::default_proxy().map(str::to_string))
+}
+
+// Create HTTP client with optional proxy for international sites. Responses are
+// decompressed transparently (gzip, brotli, zstd) and HTTP/2 is negotiated
+// where the server offers it
+fn create_http_client(use_proxy: bool, settings: &Settings) -> Result<reqwest::Client, Error> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .pool_max_idle_per_host(settings.http_pool_max_idle_per_host as usize)
+        .pool_idle_timeout(std::time::Duration::from_secs(settings.http_pool_idle_timeout_secs as u64))
+        // Grow the flow-control window with the connection, so large feeds don't stall
+        .http2_adaptive_window(true)
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36");
+
+    if let Some(proxy_url) = proxy_url().filter(|_| use_proxy) {
+        match reqwest::Proxy::all(&proxy_url) {
+            Ok(proxy) => {
+                builder = builder.proxy(proxy);
+                println!("Using proxy: {}", proxy_url);
+            }
+            Err(e) => eprintln!("Failed to configure proxy '{}': {}", proxy_url, e),
+        }
+    }
+
+    builder.build().map_err(Error::HttpClient)
+}
+
+// Check if URL or source name indicates a Chinese domestic site (no proxy needed)
+pub(crate) fn is_chinese_site(url: &str) -> bool {
+    let chinese_domains = [
+        ".cn",               // .cn domains
+        "oschina.net",       // OSChina
+        "v2ex.com",          // V2EX
+        "leiphone.com",      // 雷锋网
+        "tmtpost.com",       // 钛媒体
+        "36kr.com",          // 36氪
+        "jiqizhixin.com",    // 机器之心
+        "qbitai.com",        // 量子位
+        "zhidx.com",         // 智东西
+        "infoq.cn",          // InfoQ中文
+        "hellogithub.com",   // HelloGitHub
+        "csdn.net",          // CSDN
+        "juejin.cn",         // 掘金
+        "segmentfault.com",  // SegmentFault
+    ];
+
+    let url_lower = url.to_lowercase();
+    chinese_domains.iter().any(|domain| url_lower.contains(domain))
+}
+
+// Fetch RSS feed and return articles (no database operations)
+// `fetch_ms` here and in the other fetchers accumulates time spent on the network
+async fn fetch_rss_feed(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    source_name: &str,
+    url: &str,
+    fetch_ms: &mut u64,
+) -> Result<Vec<CrawledArticle>, Error> {
+    let client = http.for_url(url);
+
+    // Add headers to mimic a real browser request - reqwest negotiates and decodes compression itself
+    let request = client
+        .get(url)
+        .header("Accept", "application/rss+xml, application/xml, text/xml;q=0.9, */*;q=0.8")
+        .header("Accept-Language", "en-US,en;q=0.9")
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+        .header("Referer", "https://www.google.com/")
+        .header("sec-ch-ua", "\"Not_A Brand\";v=\"8\", \"Chromium\";v=\"120\"")
+        .header("sec-ch-ua-mobile", "?0")
+        .header("sec-ch-ua-platform", "\"Windows\"");
+    let content = crawl_metrics::timed(fetch_ms, http_cache::fetch_text(db, url, request, http_cache::FEED_TTL)).await?.body;
+
+    // Check if response is HTML instead of XML/RSS (common anti-bot response)
+    let content_lower = content.to_lowercase();
+    if content_lower.contains("<!doctype html")
+        || content_lower.contains("just a moment")
+        || content_lower.contains("checking your browser")
+        || content_lower.contains("access denied")
+        || content_lower.contains("<title>404")
+        || content_lower.contains("page not found")
+        || content_lower.contains("<html") {
+        eprintln!("RSS feed {} returned HTML instead of RSS/XML (possible anti-bot protection), skipping: {}", source_name, url);
+        return Ok(Vec::new());
+    }
+
+    // Attempt to parse as RSS
+    let channel = match rss::Channel::read_from(content.as_bytes()) {
+        Ok(channel) => channel,
+        Err(e) => {
+            eprintln!("Could not parse RSS for source: {} - Error: {:?}. Content preview: {:.100}", source_name, e, content);
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut articles = Vec::new();
+
+    for item in channel.items().iter().take(low_data::items_per_source(http.profile)) {
+        if let Some(title) = item.title() {
+            if let Some(link) = item.link() {
+                let description = item.description().unwrap_or("No description available").to_string();
+                let content = description.clone();
+                let pub_date = item.pub_date().unwrap_or("");
+                let (published_at, date_is_estimated) = datetime::normalize(pub_date);
+                let image_url = item.enclosure().map(|e| e.url.to_string());
+                let discussion = discussion::from_rss_item(item);
+
+                articles.push(CrawledArticle {
+                    title: title.to_string(),
+                    url: normalize_url(link),
+                    content,
+                    published_at,
+                    date_is_estimated,
+                    image_url,
+                    engagement: discussion.as_ref().and_then(|d| d.engagement()),
+                    discussion,
+                    media_duration: read_time::from_rss_item(item),
+                });
+            }
+        }
+    }
+
+    Ok(articles)
+}
+
+// Fetch web page and return articles (no database operations)
+async fn fetch_web_page(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    _source_name: &str,
+    url: &str,
+    rules: Option<&scrape_rules::ScrapeRules>,
+    fetch_ms: &mut u64,
+) -> Result<Vec<CrawledArticle>, Error> {
+    let request = http
+        .for_url(url)
+        .get(url)
+        .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8");
+    let content = crawl_metrics::timed(fetch_ms, http_cache::fetch_text(db, url, request, http_cache::FEED_TTL)).await?.body;
+
+    // The source's own selectors, when it has them
+    if let Some(rules) = rules {
+        let articles = scrape_rules::extract(&content, url, rules)?
+            .into_iter()
+            .map(|item| {
+                let (published_at, date_is_estimated) = datetime::normalize(item.date.as_deref().unwrap_or_default());
+                CrawledArticle {
+                    content: item.summary.unwrap_or_else(|| item.title.clone()),
+                    title: item.title,
+                    url: normalize_url(&item.url),
+                    published_at,
+                    date_is_estimated,
+                    image_url: None,
+                    engagement: None,
+                    discussion: None,
+                    media_duration: None,
+                }
+            })
+            .collect();
+        return Ok(articles);
+    }
+
+    let document = scraper::Html::parse_document(&content);
+    let selector = scraper::Selector::parse("a").map_err(|e| Error::InvalidSelector(e.to_string()))?;
+
+    let mut articles = Vec::new();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    for element in document.select(&selector).take(low_data::items_per_source(http.profile)) {
+        if let Some(href) = element.value().attr("href") {
+            if href.starts_with("http") {
+                let abs_url = href.to_string();
+                let title = element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+
+                if !title.is_empty() {
+                    let content = "Web-scraped content".to_string();
+
+                    articles.push(CrawledArticle {
+                        title: title.clone(),
+                        url: normalize_url(&abs_url),
+                        content,
+                        published_at: now.clone(),
+                        date_is_estimated: true,
+                        image_url: None,
+                        engagement: None,
+                        discussion: None,
+                        media_duration: None,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(articles)
+}
+
+// Reddit source via its JSON listing, which unlike the Atom feed has scores and comment counts
+async fn fetch_reddit_listing(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    listing_url: &str,
+    fetch_ms: &mut u64,
+) -> Result<Vec<CrawledArticle>, Error> {
+    let request = http
+        .for_url(listing_url)
+        .get(listing_url)
+        .header("Accept", "application/json")
+        // Reddit throttles generic browser user agents hard
+        .header("User-Agent", "ai-news-aggregator/1.0");
+    let fetched = crawl_metrics::timed(fetch_ms, http_cache::fetch_text(db, listing_url, request, http_cache::FEED_TTL)).await?;
+    if !fetched.is_success() {
+        return Err(tr(Msg::HttpRequestFailed, format!("HTTP {}", fetched.status)).into());
+    }
+    let posts = match discussion::parse_reddit_listing(&fetched.body) {
+        Ok(posts) => posts,
+        Err(e) => {
+            eprintln!("Could not parse Reddit listing {}: {}", listing_url, e);
+            return Ok(Vec::new());
+        }
+    };
+
+    Ok(posts
+        .into_iter()
+        .take(low_data::items_per_source(http.profile))
+        .map(|post| {
+            let published_at = chrono::DateTime::from_timestamp(post.created_utc, 0).map(|at| at.to_rfc3339());
+            CrawledArticle {
+                content: if post.text.trim().is_empty() { post.title.clone() } else { post.text },
+                title: post.title,
+                url: normalize_url(&post.url),
+                date_is_estimated: published_at.is_none(),
+                published_at: published_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+                image_url: None,
+                engagement: post.discussion.engagement(),
+                discussion: Some(post.discussion),
+                media_duration: None,
+            }
+        })
+        .collect())
+}
+
+// Fetch GitHub trending projects with quality filtering
+async fn fetch_github_trending(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    source_name: &str,
+    // One trending page per language of the source; a repo listed on several is taken once
+    urls: &[String],
+    // The source's config (window, star filter); None = a plain daily page with the app-wide filter
+    config: Option<&github_trending::TrendingConfig>,
+    skip_readmes: bool,
+    fetch_ms: &mut u64,
+) -> Result<Vec<CrawledArticle>, Error> {
+    let client = http.get(true); // GitHub needs proxy for international access
+
+    // First pass: extract all project data from the trending pages
+    let mut projects_data: Vec<(String, String, String, String, u32)> = Vec::new();
+
+    for url in urls {
+        let request = client
+            .get(url)
+            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36");
+        let content = match crawl_metrics::timed(fetch_ms, http_cache::fetch_text(db, url, request, http_cache::FEED_TTL)).await {
+            Ok(fetched) => fetched.body,
+            // The other languages' pages still count; a single page failing fails the source
+            Err(e) if urls.len() > 1 => {
+                eprintln!("GitHub Trending [{}]: failed to fetch {}: {}", source_name, url, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let document = scraper::Html::parse_document(&content);
+
+        // GitHub trending article selector
+        let article_selector = scraper::Selector::parse("article.Box-row").map_err(|e| Error::InvalidSelector(e.to_string()))?;
+
+        for row in document.select(&article_selector) {
+            if let Some(name_element) = row.select(&scraper::Selector::parse("h2 a").unwrap()).next() {
+                let project_url = name_element.value().attr("href").unwrap_or("").to_string();
+                let project_name = name_element.text().collect::<String>().trim().to_string();
+
+                let description = row
+                    .select(&scraper::Selector::parse("p").unwrap())
+                    .next()
+                    .map(|el| el.text().collect::<String>().trim().to_string())
+                    .unwrap_or_default();
+
+                let language = row
+                    .select(&scraper::Selector::parse("span[itemprop='programmingLanguage']").unwrap())
+                    .next()
+                    .map(|el| el.text().collect::<String>().trim().to_string())
+                    .unwrap_or_default();
+
+                let stars_text = row
+                    .select(&scraper::Selector::parse("a[href$='/stargazers']").unwrap())
+                    .next()
+                    .map(|el| el.text().collect::<String>().trim().to_string())
+                    .unwrap_or_default();
+                let stars = parse_number(&stars_text);
+
+                if projects_data.iter().any(|(known, ..)| *known == project_url) {
+                    continue;
+                }
+                projects_data.push((project_url, project_name, description, language, stars));
+            }
+        }
+        drop(document); // Explicitly drop document before await
+    }
+
+    let mut articles = Vec::new();
+    let now = chrono::Utc::now();
+
+    let (cooloff_days, star_delta, star_filter) = {
+        let conn = db.lock()?;
+        let cooloff_days = get_setting(&conn, "github_reappear_cooloff_days", "")?
+            .parse()
+            .unwrap_or_else(|_| default_github_reappear_cooloff_days());
+        let star_delta = get_setting(&conn, "github_reappear_star_delta", "")?
+            .parse()
+            .unwrap_or_else(|_| default_github_reappear_star_delta());
+        let star_filter = match config.and_then(|c| c.star_filter.as_ref()) {
+            Some(filter) => filter.clone(),
+            None => github_trending::StarFilter::load(&conn)?,
+        };
+        (cooloff_days, star_delta, star_filter)
+    };
+
+    // Creation dates already known from earlier crawls; star counts are refreshed from this page
+    let cached: Vec<Option<Option<chrono::DateTime<chrono::Utc>>>> = {
+        let conn = db.lock()?;
+        projects_data
+            .iter()
+            .map(|(project_url, _, _, _, stars)| {
+                let full_name = github_repos::full_name(project_url);
+                let cached = github_repos::cached_created_at(&conn, &full_name);
+                if cached.is_some() {
+                    let _ = github_repos::update_stars(&conn, &full_name, *stars);
+                }
+                cached
+            })
+            .collect()
+    };
+
+    // Second pass: fetch pages of repos not seen before and apply quality filter
+    let window_days = config.and_then(|c| c.since.window_days());
+    for ((project_url, project_name, description, language, stars), cached) in projects_data.into_iter().zip(cached) {
+        if project_url.is_empty() {
+            continue;
+        }
+        // Weekly / monthly views only add what no source listed within their window
+        if let Some(days) = window_days {
+            if github_repos::listed_within(&*db.lock()?, &github_repos::full_name(&project_url), days) {
+                continue;
+            }
+        }
+
+        let full_url = format!("{}{}", http.github_base, project_url);
+        let created_at = match cached {
+            Some(created_at) => created_at,
+            None => {
+                // Get project created time by fetching project page
+                let created_at = crawl_metrics::timed(fetch_ms, fetch_github_project_created(client, &full_url)).await;
+                let conn = db.lock()?;
+                if let Err(e) = github_repos::store_metadata(&conn, &github_repos::full_name(&project_url), created_at, stars) {
+                    eprintln!("Failed to cache GitHub metadata for {}: {}", project_url, e);
+                }
+                created_at
+            }
+        };
+
+        // Quality filter: minimum stars by project age (by default 20k under 2 weeks, 30k under 2 months, else 10k)
+        let is_quality = star_filter.passes(created_at.map(|created_time| (now - created_time).num_days()), stars);
+
+        if is_quality {
+            // Repos whose article is gone come back only after the cool-off or a jump in stars
+            let article_url = normalize_url(&full_url);
+            {
+                let conn = db.lock()?;
+                let full_name = github_repos::full_name(&project_url);
+                let exists = article_url_exists(&conn, &article_url);
+                if !exists && !github_repos::may_relist(&conn, &full_name, stars, cooloff_days, star_delta) {
+                    continue;
+                }
+                github_repos::mark_listed(&conn, &full_name, stars, exists)?;
+                if !exists {
+                    seen_urls::forget(&conn, &article_url)?;
+                }
+            }
+
+            let language_info = if !language.is_empty() { format!(" [{}]", language) } else { String::new() };
+            let title = format!("{}{}", project_name, language_info);
+            // The README says what the project does; the description alone is one line
+            let readme = if skip_readmes {
+                None
+            } else {
+                crawl_metrics::timed(fetch_ms, fetch_github_readme(db, client, &http.github_base, &project_url)).await
+            };
+            let content = match (description.is_empty(), readme) {
+                (false, Some(readme)) => format!("{}\n\n{}", description, readme),
+                (true, Some(readme)) => readme,
+                (false, None) => description.clone(),
+                (true, None) => GITHUB_PLACEHOLDER.to_string(),
+            };
+
+            articles.push(CrawledArticle {
+                title,
+                url: article_url,
+                content,
+                // Trending lists are dated by when a project was seen trending
+                published_at: now.to_rfc3339(),
+                date_is_estimated: false,
+                image_url: None,
+                engagement: Some(stars as i64),
+                discussion: None,
+                media_duration: None,
+            });
+        }
+    }
+
+    println!("GitHub Trending [{}]: found {} quality projects (filtered)", source_name, articles.len());
+    Ok(articles)
+}
+
+// README intro for a trending repo stored without one (night-batch mode), after its description
+pub(crate) async fn add_github_readme(db: &Mutex<Connection>, http: &HttpClients, id: &str) -> Result<(), Error> {
+    let Some(article) = query_article(&*db.lock()?, id)? else { return Ok(()) };
+    let Ok(url) = reqwest::Url::parse(&article.url) else { return Ok(()) };
+    let Some(readme) = fetch_github_readme(db, http.get(true), &http.github_base, url.path()).await else {
+        return Ok(());
+    };
+    // Already there when a crawl in the window fetched it meanwhile
+    if article.content.contains(&readme) {
+        return Ok(());
+    }
+    let content = if article.content == GITHUB_PLACEHOLDER {
+        readme
+    } else {
+        format!("{}\n\n{}", article.content, readme)
+    };
+    let conn = db.lock()?;
+    conn.execute(
+        "UPDATE articles SET content = ?1, content_hash = ?2, reading_minutes = ?3 WHERE id = ?4",
+        params![&content, revisions::content_hash(&content), read_time::reading_minutes(&content), id],
+    )
+    .map_err(Error::Update)?;
+    conn.execute(
+        "UPDATE articles_fts SET content = cjk_segment(?1) WHERE rowid = (SELECT rowid FROM articles WHERE id = ?2)",
+        params![&content, id],
+    )
+    .map_err(Error::FtsInsert)?;
+    keywords::tag_articles(&conn, &[id.to_string()])?;
+    Ok(())
+}
+
+// Opening section of a repo's README (see `github_readme.rs`); None if it has none or the API refused
+async fn fetch_github_readme(
+    db: &Mutex<Connection>,
+    client: &reqwest::Client,
+    github_base: &str,
+    project_url: &str,
+) -> Option<String> {
+    if low_data::enabled() {
+        return None;
+    }
+    let url = github_readme::api_url(github_base, &github_repos::full_name(project_url));
+    let request = client
+        .get(&url)
+        .header("Accept", "application/vnd.github.raw")
+        // The API rejects requests without one
+        .header("User-Agent", "ai-news-aggregator")
+        .timeout(std::time::Duration::from_secs(10));
+    let fetched = http_cache::fetch_text(db, &url, request, http_cache::PAGE_TTL).await.ok()?;
+    if !fetched.is_success() {
+        return None;
+    }
+    github_readme::intro(&fetched.body)
+}
+
+// Fetch GitHub project page to get created time
+async fn fetch_github_project_created(client: &reqwest::Client, url: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let response = client
+        .get(url)
+        .header("Accept", "text/html")
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .ok()?;
+
+    let content = response.text().await.ok()?;
+    let document = scraper::Html::parse_document(&content);
+
+    // Look for relative time element with created date
+    // GitHub uses <relative-time> elements for timestamps
+    for time_elem in document.select(&scraper::Selector::parse("relative-time").unwrap()) {
+        if let Some(datetime) = time_elem.value().attr("datetime") {
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(datetime) {
+                return Some(dt.with_timezone(&chrono::Utc));
+            }
+        }
+    }
+
+    // Alternative: look for time element with specific class
+    for time_elem in document.select(&scraper::Selector::parse("time").unwrap()) {
+        if let Some(datetime) = time_elem.value().attr("datetime") {
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(datetime) {
+                return Some(dt.with_timezone(&chrono::Utc));
+            }
+        }
+    }
+
+    None
+}
+
+// Parse number from GitHub's format (e.g., "1.2k" -> 1200, "15.5k" -> 15500)
+fn parse_number(text: &str) -> u32 {
+    let text = text.replace([',', ' '], "");
+    if text.to_lowercase().ends_with('k') {
+        let num: f64 = text[..text.len()-1].parse().unwrap_or(0.0);
+        (num * 1000.0) as u32
+    } else {
+        text.parse().unwrap_or(0)
+    }
+}
+
+// Helper function to normalize URLs (as mentioned in the documentation)
+pub(crate) fn normalize_url(url: &str) -> String {
+    let mut url_clean = url.trim().to_lowercase();
+    if url_clean.ends_with('/') {
+        url_clean.pop();
+    }
+    url_clean
+}
+
+// The source's configured category, else one derived from its name
+fn source_category(conn: &Connection, source_name: &str) -> Result<String, rusqlite::Error> {
+    let configured: Option<String> = conn
+        .prepare_cached("SELECT category FROM sources WHERE name = ?1")?
+        .query_row(params![source_name], |row| row.get(0))
+        .optional()?
+        .flatten();
+    Ok(configured.filter(|c| !c.trim().is_empty()).unwrap_or_else(|| categorize_source(source_name)))
+}
+
+// Helper function to categorize source
+pub(crate) fn categorize_source(source_name: &str) -> String {
+    if source_name.contains("GitHub") {
+        "GitHub".to_string()
+    } else if source_name.contains("AI") || source_name.contains("人工") || source_name.contains("智能") {
+        "AI".to_string()
+    } else {
+        "Tech".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crawled(url: &str, content: &str, published_at: &str) -> CrawledArticle {
+        CrawledArticle {
+            title: format!("Title of {}", url),
+            url: url.to_string(),
+            content: content.to_string(),
+            published_at: published_at.to_string(),
+            date_is_estimated: false,
+            image_url: None,
+            engagement: None,
+            discussion: None,
+            media_duration: None,
+        }
+    }
+
+    fn template_summary() -> GeneratedSummary {
+        GeneratedSummary { text: "Summary".to_string(), status: SummaryStatus::Template, error: None }
+    }
+
+    #[test]
+    fn proxy_bypass_ends_with_its_guard() {
+        let http = HttpClients {
+            proxied: reqwest::Client::new(),
+            direct: reqwest::Client::new(),
+            github_base: GITHUB_BASE.to_string(),
+            proxy: Some("http://127.0.0.1:7897".to_string()),
+            bypass_proxy: AtomicBool::new(false),
+            profile: platform::crawl_profile(),
+        };
+        assert!(std::ptr::eq(http.get(true), http.proxied()));
+        {
+            let _bypass = http.bypass_proxy();
+            assert!(std::ptr::eq(http.get(true), http.get(false)));
+            assert!(std::ptr::eq(http.for_url("https://openai.com/blog"), http.get(false)));
+        }
+        assert!(std::ptr::eq(http.get(true), http.proxied()));
+        assert!(!std::ptr::eq(http.get(true), http.get(false)));
+    }
+
+    #[test]
+    fn pages_from_the_extension_are_saved_once() {
+        let state = DbState { conn: Mutex::new(crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap()) };
+        let html = "<html><head><title>Page title</title></head><body><article><p>Distilled models keep most of the accuracy \
+                    at a fraction of the cost, the authors report.</p></article></body></html>";
+        let article = save_submitted_page(&state, "https://Example.com/Post/", html, Some("  Chosen title "))
+            .unwrap()
+            .expect("stored");
+        assert_eq!(article.url, "https://example.com/post");
+        assert_eq!(article.title, "Chosen title");
+        assert_eq!(article.source, "浏览器扩展");
+
+        assert!(save_submitted_page(&state, "https://example.com/post", html, None).unwrap().is_none(), "already stored");
+        let article = save_submitted_page(&state, "https://example.com/other", html, Some(" ")).unwrap().unwrap();
+        assert_eq!(article.title, "Page title", "a blank title falls back to the page's");
+    }
+
+    #[test]
+    fn crawled_articles_are_inserted_then_updated_when_content_changes() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let batch = vec![
+            ("Feed".to_string(), crawled("https://example.com/a", "First body", &now), template_summary()),
+            ("Feed".to_string(), crawled("https://example.com/b", "Second body", &now), template_summary()),
+        ];
+        let stored = store_crawled_articles(&mut conn, &batch).unwrap();
+        assert_eq!(stored.inserted.len(), 2);
+        assert!(stored.updated.is_empty());
+
+        let stored = store_crawled_articles(&mut conn, &batch).unwrap();
+        assert!(stored.inserted.is_empty() && stored.updated.is_empty(), "unchanged content is left alone");
+
+        let batch = vec![
+            ("Feed".to_string(), crawled("https://example.com/a", "First body, revised", &now), template_summary()),
+            ("Feed".to_string(), crawled("https://example.com/b", "  ", &now), template_summary()),
+        ];
+        let stored = store_crawled_articles(&mut conn, &batch).unwrap();
+        assert_eq!(stored.updated.len(), 1, "an empty description isn't an edit");
+        let content: String = conn
+            .query_row("SELECT content FROM articles WHERE url = 'https://example.com/a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(content, "First body, revised");
+    }
+
+    #[test]
+    fn a_constraint_error_skips_only_the_bad_article() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute_batch(
+            "CREATE TRIGGER reject_bad BEFORE INSERT ON articles WHEN NEW.url LIKE '%/bad'
+             BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+        )
+        .unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let batch = vec![
+            ("Feed".to_string(), crawled("https://example.com/good", "Good body", &now), template_summary()),
+            ("Feed".to_string(), crawled("https://example.com/bad", "Bad body", &now), template_summary()),
+            ("Feed".to_string(), crawled("https://example.com/also-good", "Other body", &now), template_summary()),
+        ];
+        let stored = store_crawled_articles(&mut conn, &batch).unwrap();
+        assert_eq!(stored.inserted.len(), 2);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+        let fts: i64 = conn.query_row("SELECT COUNT(*) FROM articles_fts", [], |row| row.get(0)).unwrap();
+        assert_eq!(fts, 2, "the rolled-back batch left no FTS rows behind");
+    }
+
+    #[test]
+    fn old_articles_are_skipped_but_undated_ones_kept() {
+        let recent = (chrono::Utc::now() - chrono::Duration::days(2)).to_rfc3339();
+        let old = (chrono::Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+        let articles = || {
+            vec![
+                crawled("https://example.com/recent", "", &recent),
+                crawled("https://example.com/old", "", &old),
+                crawled("https://example.com/undated", "", "sometime last spring"),
+            ]
+        };
+        let kept: Vec<String> = skip_old_articles("Feed", articles(), 7).into_iter().map(|a| a.url).collect();
+        assert_eq!(kept, ["https://example.com/recent", "https://example.com/undated"]);
+        assert_eq!(skip_old_articles("Feed", articles(), 0).len(), 3, "0 means no limit");
+    }
+
+    #[test]
+    fn a_manual_summary_survives_content_updates() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let ai = GeneratedSummary { text: "AI summary".to_string(), status: SummaryStatus::Ai, error: None };
+        assert_eq!(ai.source(), SummarySource::Ai);
+        assert_eq!(template_summary().source(), SummarySource::Template);
+        let batch = vec![
+            ("Feed".to_string(), crawled("https://example.com/edited", "Body", &now), ai),
+            ("Feed".to_string(), crawled("https://example.com/plain", "Body", &now), template_summary()),
+        ];
+        store_crawled_articles(&mut conn, &batch).unwrap();
+        conn.execute(
+            "UPDATE articles SET summary = 'Written by hand', summary_source = 'manual' WHERE url = 'https://example.com/edited'",
+            [],
+        )
+        .unwrap();
+
+        let batch = vec![
+            ("Feed".to_string(), crawled("https://example.com/edited", "Body, revised", &now), template_summary()),
+            ("Feed".to_string(), crawled("https://example.com/plain", "Body, revised", &now), template_summary()),
+        ];
+        assert_eq!(store_crawled_articles(&mut conn, &batch).unwrap().updated.len(), 2);
+        let summary = |url: &str| -> (String, String) {
+            conn.query_row("SELECT summary, summary_source FROM articles WHERE url = ?1", params![url], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+        };
+        assert_eq!(summary("https://example.com/edited"), ("Written by hand".to_string(), "manual".to_string()));
+        assert_eq!(summary("https://example.com/plain"), ("Summary".to_string(), "template".to_string()));
+    }
+
+    #[test]
+    fn shared_clients_pick_direct_for_domestic_sites() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let http = HttpClients::new(&crate::load_settings(&conn).unwrap()).unwrap();
+        assert!(!std::ptr::eq(http.get(true), http.get(false)));
+        assert!(std::ptr::eq(http.for_url("https://www.36kr.com/p/1"), http.get(false)));
+        assert!(std::ptr::eq(http.for_url("https://news.example.cn/a"), http.get(false)));
+        assert!(std::ptr::eq(http.for_url("https://openai.com/blog"), http.get(true)));
+        assert!(std::ptr::eq(http.for_url("https://openai.com/blog"), http.for_url("https://arxiv.org/abs/1")), "one client, reused");
+    }
+
+    #[test]
+    fn clients_accept_and_decode_brotli() {
+        use std::io::{Read, Write};
+
+        // "<rss><channel><title>Compressed feed</title></channel></rss>", brotli-compressed
+        const BODY: [u8; 52] = [
+            27, 59, 0, 0, 68, 39, 71, 26, 216, 65, 190, 75, 164, 133, 173, 237, 7, 135, 156, 0, 157, 219, 10, 172, 121, 28,
+            108, 140, 157, 165, 80, 227, 68, 67, 39, 226, 6, 3, 209, 244, 95, 154, 119, 170, 223, 93, 133, 137, 112, 11, 39, 2,
+        ];
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 8192];
+            let n = stream.read(&mut request).unwrap();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Encoding: br\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                BODY.len()
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&BODY).unwrap();
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let client = create_http_client(false, &crate::load_settings(&conn).unwrap()).unwrap();
+        let text = tauri::async_runtime::block_on(async { client.get(&url).send().await?.text().await }).unwrap();
+        assert_eq!(text, "<rss><channel><title>Compressed feed</title></channel></rss>");
+        let request = server.join().unwrap();
+        let accept = request.lines().find(|l| l.starts_with("accept-encoding:")).unwrap_or_default();
+        assert!(accept.contains("br") && accept.contains("gzip") && accept.contains("zstd"), "{}", accept);
+    }
+
+    #[derive(Default)]
+    struct Recorded(Mutex<Vec<(String, serde_json::Value)>>);
+
+    impl CrawlEvents for Recorded {
+        fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+            self.0.lock().unwrap().push((event.to_string(), serde_json::to_value(payload).unwrap()));
+        }
+    }
+
+    #[test]
+    fn each_source_is_stored_and_announced_as_it_finishes() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let feed = |items: &[&str]| {
+                    let items: String = items
+                        .iter()
+                        .map(|slug| {
+                            format!(
+                                "<item><title>Post {slug}</title><link>http://localhost/{slug}</link>\
+                                 <description>Body of {slug}</description><pubDate>{}</pubDate></item>",
+                                chrono::Utc::now().to_rfc2822()
+                            )
+                        })
+                        .collect();
+                    format!("<?xml version=\"1.0\"?><rss version=\"2.0\"><channel><title>Feed</title>{}</channel></rss>", items)
+                };
+                let response = match request.url() {
+                    "/one.xml" => tiny_http::Response::from_string(feed(&["a", "b"])),
+                    "/two.xml" => tiny_http::Response::from_string(feed(&["c"])),
+                    _ => tiny_http::Response::from_string("").with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        let db = Mutex::new(crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap());
+        {
+            let conn = db.lock().unwrap();
+            conn.execute("UPDATE sources SET is_active = 0", []).unwrap();
+            for (name, path) in [("First Feed", "/one.xml"), ("Second Feed", "/two.xml"), ("Gone Feed", "/gone.xml")] {
+                conn.execute(
+                    "INSERT INTO sources (id, name, url, source_type, is_active) VALUES (?1, ?1, ?2, 'RSS', 1)",
+                    params![name, format!("{}{}", base, path)],
+                )
+                .unwrap();
+            }
+        }
+        let http = HttpClients {
+            proxied: reqwest::Client::new(),
+            direct: reqwest::Client::new(),
+            github_base: GITHUB_BASE.to_string(),
+            proxy: None,
+            bypass_proxy: AtomicBool::new(false),
+            profile: platform::profile_for(false),
+        };
+        let events = Recorded::default();
+
+        let result = tauri::async_runtime::block_on(run_crawl(&events, &db, &http)).unwrap();
+        assert_eq!(result.inserted, 3);
+        let mut announced: Vec<(String, usize)> = events
+            .0
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter(|(event, _)| event == "app://articles:new")
+            .map(|(_, payload)| (payload["source"].as_str().unwrap().to_string(), payload["articles"].as_array().unwrap().len()))
+            .collect();
+        announced.sort();
+        assert_eq!(announced, [("First Feed".to_string(), 2), ("Second Feed".to_string(), 1)], "nothing for a source without new articles");
+    }
+}
@@ -0,0 +1,358 @@
+// Parsing of the date strings feeds and pages carry, and the display
+// timezone applied to timestamps in responses.
+//
+// Stored timestamps are always RFC 3339 in UTC so SQL ordering stays
+// consistent. Like the UI language, the display timezone is kept in a
+// process-wide atomic so `article_from_row` can apply it without extra state.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+// Chinese-format dates carry no offset; sites publish them in Beijing time
+const CHINA_OFFSET_SECS: i32 = 8 * 3600;
+
+// Labels sites put in front of the date itself, longest first
+const LABELS: &[&str] = &[
+    "last updated",
+    "published on",
+    "posted on",
+    "updated on",
+    "published",
+    "updated",
+    "posted",
+    "发布时间",
+    "更新时间",
+    "发布于",
+    "更新于",
+    "发表于",
+];
+
+// ISO 8601 without an offset, taken as UTC
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%d %b %Y", "%b %d, %Y", "%B %d, %Y"];
+
+// Offsets without a colon (`+0800`) and RFC 2822 dates whose weekday doesn't match
+const OFFSET_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z", "%d %b %Y %H:%M:%S %z"];
+
+fn strip_label(date_str: &str) -> &str {
+    let mut s = date_str.trim();
+    for label in LABELS {
+        if s.get(..label.len()).is_some_and(|head| head.eq_ignore_ascii_case(label)) {
+            s = &s[label.len()..];
+            break;
+        }
+    }
+    s.trim_start_matches([':', '：', ' ']).trim()
+}
+
+// `2024年1月5日 10:30`, `2024年01月05日`, or `1月5日` (current year)
+fn parse_chinese(s: &str) -> Option<DateTime<Utc>> {
+    if !s.contains('月') {
+        return None;
+    }
+    let beijing = FixedOffset::east_opt(CHINA_OFFSET_SECS)?;
+    let mut normalized = s.replace(['年', '月'], "-").replace('日', " ");
+    if !s.contains('年') {
+        normalized = format!("{}-{}", Utc::now().with_timezone(&beijing).year(), normalized);
+    }
+    let normalized = normalized.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(&normalized, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(&normalized, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })?;
+    beijing
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+pub fn parse(date_str: &str) -> Option<DateTime<Utc>> {
+    let s = strip_label(date_str);
+    if s.is_empty() {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    // Drop a leading weekday so a wrong one doesn't reject the date
+    let without_weekday = s.split_once(", ").map(|(_, rest)| rest).unwrap_or(s);
+    for fmt in OFFSET_FORMATS {
+        if let Ok(dt) = DateTime::parse_from_str(without_weekday, fmt) {
+            return Some(dt.with_timezone(&Utc));
+        }
+    }
+    for fmt in NAIVE_DATETIME_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+    for fmt in DATE_FORMATS {
+        if let Some(naive) = NaiveDate::parse_from_str(s, fmt).ok().and_then(|d| d.and_hms_opt(0, 0, 0)) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+    parse_chinese(s)
+}
+
+// RFC 3339 (UTC) and whether the date is a guess: unparseable or missing dates fall back to now
+pub fn normalize(date_str: &str) -> (String, bool) {
+    match parse(date_str) {
+        Some(dt) => (dt.to_rfc3339(), false),
+        None => (Utc::now().to_rfc3339(), true),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTimezone {
+    // Whatever the system timezone is
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl DisplayTimezone {
+    // "local", "UTC", or an offset such as "+08:00", "+0800", "UTC+8"; unknown codes fall back to local
+    pub fn from_code(code: &str) -> DisplayTimezone {
+        let code = code.trim();
+        let offset = code
+            .get(..3)
+            .filter(|head| head.eq_ignore_ascii_case("utc") || head.eq_ignore_ascii_case("gmt"))
+            .map(|_| &code[3..])
+            .unwrap_or(code);
+        if offset.is_empty() {
+            return match FixedOffset::east_opt(0) {
+                Some(utc) if !code.is_empty() => DisplayTimezone::Fixed(utc),
+                _ => DisplayTimezone::Local,
+            };
+        }
+
+        let (sign, rest) = match offset.chars().next() {
+            Some('+') => (1, &offset[1..]),
+            Some('-') => (-1, &offset[1..]),
+            _ => return DisplayTimezone::Local,
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() == 4 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        match (hours.parse::<i32>(), minutes.parse::<i32>()) {
+            (Ok(h), Ok(m)) if h <= 14 && m < 60 => FixedOffset::east_opt(sign * (h * 3600 + m * 60))
+                .map(DisplayTimezone::Fixed)
+                .unwrap_or(DisplayTimezone::Local),
+            _ => DisplayTimezone::Local,
+        }
+    }
+
+    pub fn code(self) -> String {
+        match self {
+            DisplayTimezone::Local => "local".to_string(),
+            DisplayTimezone::Fixed(offset) if offset.local_minus_utc() == 0 => "UTC".to_string(),
+            DisplayTimezone::Fixed(offset) => {
+                let secs = offset.local_minus_utc();
+                let sign = if secs < 0 { '-' } else { '+' };
+                format!("{}{:02}:{:02}", sign, secs.abs() / 3600, secs.abs() % 3600 / 60)
+            }
+        }
+    }
+}
+
+// Offset in seconds, or LOCAL_SENTINEL for the system timezone
+const LOCAL_SENTINEL: i32 = i32::MIN;
+static DISPLAY_OFFSET: AtomicI32 = AtomicI32::new(LOCAL_SENTINEL);
+
+pub fn set_display_timezone(tz: DisplayTimezone) {
+    let value = match tz {
+        DisplayTimezone::Local => LOCAL_SENTINEL,
+        DisplayTimezone::Fixed(offset) => offset.local_minus_utc(),
+    };
+    DISPLAY_OFFSET.store(value, Ordering::Relaxed);
+}
+
+// A stored RFC 3339 timestamp in the display timezone; anything unparseable is returned as is
+pub fn to_display(timestamp: &str) -> String {
+    let Ok(dt) = DateTime::parse_from_rfc3339(timestamp) else {
+        return timestamp.to_string();
+    };
+    match DISPLAY_OFFSET.load(Ordering::Relaxed) {
+        LOCAL_SENTINEL => dt.with_timezone(&Local).to_rfc3339(),
+        secs => FixedOffset::east_opt(secs)
+            .map(|offset| dt.with_timezone(&offset).to_rfc3339())
+            .unwrap_or_else(|| timestamp.to_string()),
+    }
+}
+
+// Relative age of an article in the display timezone, used for list section headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    Today,
+    Yesterday,
+    // The five days before yesterday
+    ThisWeek,
+    Older,
+}
+
+impl Freshness {
+    pub const ALL: [Freshness; 4] = [Freshness::Today, Freshness::Yesterday, Freshness::ThisWeek, Freshness::Older];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Freshness::Today => "today",
+            Freshness::Yesterday => "yesterday",
+            Freshness::ThisWeek => "this_week",
+            Freshness::Older => "older",
+        }
+    }
+
+    // Days back from today where the bucket starts; None = no lower bound
+    fn start_days_ago(self) -> Option<i64> {
+        match self {
+            Freshness::Today => Some(0),
+            Freshness::Yesterday => Some(1),
+            Freshness::ThisWeek => Some(6),
+            Freshness::Older => None,
+        }
+    }
+
+    // [start, end) as stored-format timestamps, for comparing with `published_at` in SQL
+    pub fn bounds(self) -> (Option<String>, Option<String>) {
+        let start = self.start_days_ago().map(|days| day_start(days).to_rfc3339());
+        let end = match self {
+            Freshness::Today => None,
+            Freshness::Yesterday => Some(day_start(0).to_rfc3339()),
+            Freshness::ThisWeek => Some(day_start(1).to_rfc3339()),
+            Freshness::Older => Some(day_start(6).to_rfc3339()),
+        };
+        (start, end)
+    }
+
+    // Bucket of a stored timestamp; unparseable dates count as older
+    pub fn of(timestamp: &str) -> Freshness {
+        let Ok(published) = DateTime::parse_from_rfc3339(timestamp) else {
+            return Freshness::Older;
+        };
+        Freshness::ALL
+            .into_iter()
+            .find(|bucket| bucket.start_days_ago().is_some_and(|days| published >= day_start(days)))
+            .unwrap_or(Freshness::Older)
+    }
+}
+
+// Midnight `days_ago` days before today, in the display timezone
+fn day_start(days_ago: i64) -> DateTime<Utc> {
+    let now = Utc::now();
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    match DISPLAY_OFFSET.load(Ordering::Relaxed) {
+        LOCAL_SENTINEL => {
+            let date = now.with_timezone(&Local).date_naive() - chrono::Duration::days(days_ago);
+            Local
+                .from_local_datetime(&midnight(date))
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or(now)
+        }
+        secs => {
+            let offset = FixedOffset::east_opt(secs).unwrap_or(Utc.fix());
+            let date = now.with_timezone(&offset).date_naive() - chrono::Duration::days(days_ago);
+            Utc.from_utc_datetime(&(midnight(date) - chrono::Duration::seconds(secs as i64)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(date_str: &str) -> Option<String> {
+        parse(date_str).map(|dt| dt.to_rfc3339())
+    }
+
+    #[test]
+    fn parses_feed_and_page_formats_to_utc() {
+        let expected = Some("2024-01-05T02:30:00+00:00".to_string());
+        assert_eq!(parsed("Fri, 05 Jan 2024 02:30:00 GMT"), expected);
+        assert_eq!(parsed("2024-01-05T10:30:00+08:00"), expected);
+        assert_eq!(parsed("2024-01-05 10:30:00+0800"), expected);
+        assert_eq!(parsed("Mon, 05 Jan 2024 02:30:00 +0000"), expected, "wrong weekday is ignored");
+        assert_eq!(parsed("2024-01-05 02:30"), expected, "no offset is UTC");
+        assert_eq!(parsed("Published on: 2024-01-05T02:30:00Z"), expected);
+        assert_eq!(parsed("发布时间：2024年1月5日 10:30"), expected, "Chinese dates are Beijing time");
+        assert_eq!(parsed("Jan 5, 2024"), Some("2024-01-05T00:00:00+00:00".to_string()));
+        assert_eq!(parsed("2024.01.05"), Some("2024-01-05T00:00:00+00:00".to_string()));
+    }
+
+    #[test]
+    fn unparseable_dates_are_flagged_as_estimated() {
+        assert_eq!(parse("yesterday"), None);
+        assert_eq!(parse("Published:"), None);
+        assert_eq!(normalize("2024-01-05"), ("2024-01-05T00:00:00+00:00".to_string(), false));
+        let (now, estimated) = normalize("");
+        assert!(estimated);
+        assert!(DateTime::parse_from_rfc3339(&now).is_ok());
+    }
+
+    #[test]
+    fn timezone_codes_round_trip() {
+        let beijing = DisplayTimezone::Fixed(FixedOffset::east_opt(CHINA_OFFSET_SECS).unwrap());
+        assert_eq!(DisplayTimezone::from_code("+08:00"), beijing);
+        assert_eq!(DisplayTimezone::from_code("+0800"), beijing);
+        assert_eq!(DisplayTimezone::from_code("UTC+8"), beijing);
+        assert_eq!(DisplayTimezone::from_code("gmt-05:30").code(), "-05:30");
+        assert_eq!(DisplayTimezone::from_code("UTC").code(), "UTC");
+        assert_eq!(DisplayTimezone::from_code("local"), DisplayTimezone::Local);
+        assert_eq!(DisplayTimezone::from_code(""), DisplayTimezone::Local);
+        assert_eq!(DisplayTimezone::from_code("+25:00"), DisplayTimezone::Local);
+        assert_eq!(DisplayTimezone::from_code("Asia/Shanghai"), DisplayTimezone::Local);
+        assert_eq!(DisplayTimezone::from_code(&beijing.code()), beijing);
+    }
+
+    #[test]
+    fn display_applies_the_configured_offset() {
+        set_display_timezone(DisplayTimezone::from_code("+08:00"));
+        assert_eq!(to_display("2024-01-05T02:30:00+00:00"), "2024-01-05T10:30:00+08:00");
+        assert_eq!(to_display("not a date"), "not a date");
+        set_display_timezone(DisplayTimezone::Local);
+    }
+
+    // Offsets from now land in the same bucket whatever the display timezone is
+    fn ago(days: i64) -> String {
+        (Utc::now() - chrono::Duration::days(days)).to_rfc3339()
+    }
+
+    #[test]
+    fn timestamps_fall_into_day_buckets() {
+        assert_eq!(Freshness::of(&ago(0)), Freshness::Today);
+        assert_eq!(Freshness::of(&ago(3)), Freshness::ThisWeek);
+        assert_eq!(Freshness::of(&ago(30)), Freshness::Older);
+        assert_eq!(Freshness::of("not a date"), Freshness::Older);
+    }
+
+    #[test]
+    fn bucket_bounds_are_open_at_the_ends() {
+        assert!(matches!(Freshness::Today.bounds(), (Some(_), None)));
+        assert!(matches!(Freshness::Older.bounds(), (None, Some(_))));
+        let (Some(start), Some(end)) = Freshness::ThisWeek.bounds() else {
+            panic!("this week has both bounds");
+        };
+        assert!(start < end);
+    }
+}
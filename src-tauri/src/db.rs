@@ -0,0 +1,1068 @@
+// Database: opening and migrating the schema, the settings store, and the
+// article queries shared by commands, the local API and the MCP server.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Serialize;
+
+use crate::ai::local_brief;
+use crate::crawler::ExtractedPage;
+use crate::error::Error;
+use crate::search_index::SearchScope;
+use crate::{
+    annotations, article_edits, crawl_metrics, datetime, default_cleanup_interval_hours,
+    default_github_reappear_cooloff_days, default_github_reappear_star_delta, default_http_pool_idle_timeout_secs,
+    default_http_pool_max_idle_per_host, default_local_api_port, default_night_batch_end_hour,
+    default_night_batch_start_hour, default_night_batch_when_idle, default_seen_urls_retention_days,
+    default_slow_query_ms, default_trash_retention_days, digest, feed_balance, feedback, folders, github_repos,
+    glossary, greader, heat, http_cache, jobs, keywords, lazy_summary, list_cache, network, night_batch, obsidian,
+    onboarding, perf, platform, profiles, read_time, revisions, rules, scheduler, search_index, seen_urls, storage, syndication,
+    trash, ui_state, watchlists, webhooks, Article, CleanupResult, FreshnessGroup, GeneratedSummary, ListQuery, ListResponse,
+    Settings, SummarySource, SummaryStatus,
+};
+
+#[derive(Debug)]
+pub struct DbState {
+    pub conn: Mutex<Connection>,
+}
+
+// App data directory (~/.newsagregator), created on first use
+pub(crate) fn get_data_dir() -> Result<String, Error> {
+    let data_dir = match platform::data_dir() {
+        Some(dir) => dir.to_string_lossy().into_owned(),
+        None => {
+            let app_dir = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))
+                .map_err(|_| Error::HomeDirUnknown)?;
+            format!("{}/.newsagregator", app_dir)
+        }
+    };
+
+    // Create directory if it doesn't exist
+    std::fs::create_dir_all(&data_dir)
+        .map_err(|source| Error::CreateDir { path: data_dir.clone(), source })?;
+
+    Ok(data_dir)
+}
+
+pub(crate) fn get_db_path() -> Result<String, Error> {
+    Ok(profiles::active_db_path()?)
+}
+
+// Open the active profile's database
+pub fn init_db() -> Result<Connection, rusqlite::Error> {
+    let db_path = get_db_path().map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    init_db_at(&db_path)
+}
+
+// Open (and create or migrate) the database at `db_path`
+pub(crate) fn init_db_at(db_path: &str) -> Result<Connection, rusqlite::Error> {
+    init_db_with_conn(Connection::open(db_path)?)
+}
+
+// Create or migrate the schema on an open connection, e.g. a `:memory:` database in tests
+pub fn init_db_with_conn(mut db: Connection) -> Result<Connection, rusqlite::Error> {
+    perf::watch(&mut db);
+    list_cache::watch(&db);
+    search_index::register(&db)?;
+
+    // Create articles table if not exists
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS articles (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            summary TEXT,
+            content TEXT,
+            url TEXT UNIQUE NOT NULL,
+            source TEXT,
+            category TEXT,
+            published_at TEXT,
+            fetched_at TEXT,
+            heat_score REAL DEFAULT 0,
+            is_read INTEGER DEFAULT 0,
+            is_bookmarked INTEGER DEFAULT 0,
+            image_url TEXT
+        )",
+        [],
+    )?;
+
+    // Create sources table if not exists
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS sources (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            url TEXT NOT NULL,
+            source_type TEXT NOT NULL,
+            is_active INTEGER DEFAULT 1
+        )",
+        [],
+    )?;
+
+    // Create FTS table for full-text search
+    db.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS articles_fts USING fts5(
+            title, summary, content,
+            tokenize = 'unicode61'
+        )",
+        [],
+    )?;
+
+    // Create settings table if not exists
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        )",
+        [],
+    )?;
+
+    // Per-row modification time, used to resolve conflicts when merging a synced copy.
+    // Triggers keep it current so individual UPDATE statements don't have to.
+    ensure_column(&db, "articles", "updated_at", "TEXT")?;
+    db.execute(
+        "UPDATE articles SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', COALESCE(fetched_at, 'now'))
+         WHERE updated_at IS NULL",
+        [],
+    )?;
+    db.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS articles_updated_at_insert AFTER INSERT ON articles
+         WHEN NEW.updated_at IS NULL
+         BEGIN
+             UPDATE articles SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE rowid = NEW.rowid;
+         END;
+         CREATE TRIGGER IF NOT EXISTS articles_updated_at_update AFTER UPDATE ON articles
+         WHEN NEW.updated_at IS OLD.updated_at
+         BEGIN
+             UPDATE articles SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE rowid = NEW.rowid;
+         END;",
+    )?;
+
+    annotations::init_tables(&db)?;
+    obsidian::init_tables(&db)?;
+    greader::init_tables(&db)?;
+    scheduler::init_tables(&db)?;
+    webhooks::init_tables(&db)?;
+    http_cache::init_tables(&db)?;
+    github_repos::init_tables(&db)?;
+    revisions::init_tables(&db)?;
+    article_edits::init_tables(&db)?;
+    ensure_column(&db, "articles", "content_hash", "TEXT")?;
+    ensure_column(&db, "articles", "date_is_estimated", "INTEGER DEFAULT 0")?;
+    ensure_column(&db, "articles", "paper_code_link", "TEXT")?;
+    ensure_column(&db, "articles", "engagement", "INTEGER")?;
+    ensure_column(&db, "articles", "opened_at", "TEXT")?;
+    ensure_column(&db, "articles", "open_count", "INTEGER DEFAULT 0")?;
+    if ensure_column(&db, "articles", "summary_status", "TEXT NOT NULL DEFAULT 'pending'")? {
+        // Existing rows only had the template wording to go by
+        db.execute(
+            "UPDATE articles SET summary_status = CASE
+                 WHEN summary IS NULL OR summary = '' THEN 'pending'
+                 WHEN summary LIKE '%这篇英文资讯围绕%' THEN 'template'
+                 ELSE 'ai' END",
+            [],
+        )?;
+    }
+    ensure_column(&db, "articles", "summary_error", "TEXT")?;
+    if ensure_column(&db, "articles", "summary_source", "TEXT NOT NULL DEFAULT 'template'")? {
+        db.execute("UPDATE articles SET summary_source = 'ai' WHERE summary_status = 'ai'", [])?;
+    }
+    // Per-source age cutoff in days; NULL = global `max_article_age_days`, 0 = none
+    ensure_column(&db, "sources", "max_age_days", "INTEGER")?;
+    folders::init_tables(&db)?;
+    glossary::init_tables(&db)?;
+    digest::init_tables(&db)?;
+    watchlists::init_tables(&db)?;
+    rules::init_tables(&db)?;
+    feedback::init_tables(&db)?;
+    syndication::init_tables(&db)?;
+    jobs::init_tables(&db)?;
+    night_batch::init_tables(&db)?;
+    trash::init_tables(&db)?;
+    seen_urls::init_tables(&db)?;
+    ui_state::init_tables(&db)?;
+    crawl_metrics::init_tables(&db)?;
+    keywords::init_tables(&db)?;
+    ensure_column(&db, "sources", "folder_id", "TEXT")?;
+    // Snoozed until this time (RFC 3339, UTC); NULL = not muted
+    ensure_column(&db, "sources", "muted_until", "TEXT")?;
+    // Category for the source's articles; NULL = derived from the name
+    ensure_column(&db, "sources", "category", "TEXT")?;
+    // JSON `scrape_rules::ScrapeRules` for WEB sources; NULL = generic link scraping
+    ensure_column(&db, "sources", "scrape_rules", "TEXT")?;
+    // JSON `github_trending::TrendingConfig`; the source's trending pages are generated from it
+    ensure_column(&db, "sources", "trending_config", "TEXT")?;
+    // Last successful fetch of a weekly / monthly trending source (RFC 3339, UTC)
+    ensure_column(&db, "sources", "trending_fetched_at", "TEXT")?;
+    // Hidden from lists until this time (RFC 3339, UTC); snooze_notify asks for a notification when it ends
+    ensure_column(&db, "articles", "snoozed_until", "TEXT")?;
+    ensure_column(&db, "articles", "snooze_notify", "INTEGER DEFAULT 0")?;
+    // Cached full-text translation; translation_hash is the content hash it was made from
+    ensure_column(&db, "articles", "translated_title", "TEXT")?;
+    ensure_column(&db, "articles", "translated_content", "TEXT")?;
+    ensure_column(&db, "articles", "translation_lang", "TEXT")?;
+    ensure_column(&db, "articles", "translation_hash", "TEXT")?;
+    // Comment thread on an aggregator and its counts (`discussion.rs`)
+    ensure_column(&db, "articles", "discussion_url", "TEXT")?;
+    ensure_column(&db, "articles", "comment_count", "INTEGER")?;
+    ensure_column(&db, "articles", "points", "INTEGER")?;
+    // AI summary of the discussion thread, how many comments it read and when
+    ensure_column(&db, "articles", "comments_summary", "TEXT")?;
+    ensure_column(&db, "articles", "comments_summary_count", "INTEGER")?;
+    ensure_column(&db, "articles", "comments_summarized_at", "TEXT")?;
+    ensure_column(&db, "articles", "media_duration", "INTEGER")?;
+    if ensure_column(&db, "articles", "reading_minutes", "INTEGER")? {
+        read_time::backfill(&db)?;
+    }
+    ensure_column(&db, "articles", "paywalled", "INTEGER NOT NULL DEFAULT 0")?;
+    // Heat points added by incoming-article rules (`boost_heat`)
+    ensure_column(&db, "articles", "rule_boost", "REAL NOT NULL DEFAULT 0")?;
+    // First time a repo showed up on trending, and when / at how many stars it last became an article
+    ensure_column(&db, "github_repos", "first_seen_at", "TEXT")?;
+    ensure_column(&db, "github_repos", "listed_at", "TEXT")?;
+    ensure_column(&db, "github_repos", "listed_stars", "INTEGER")?;
+
+    onboarding::init(&db)?;
+    search_index::init(&db)?;
+
+    Ok(db)
+}
+
+// Add a column to a table created by an older version; no-op if it already exists
+// Add a column if missing; true when it was just added
+pub(crate) fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<bool, rusqlite::Error> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(!exists)
+}
+
+// Column list matching `article_from_row`; prefix with a table alias where needed
+pub(crate) const ARTICLE_COLUMNS: &str = "id, title, summary, content, url, source, category, published_at, fetched_at, heat_score, is_read, is_bookmarked, image_url, date_is_estimated, paper_code_link, opened_at, summary_status, summary_error, summary_source, discussion_url, comment_count, points, comments_summary, media_duration, reading_minutes, paywalled";
+
+pub(crate) fn article_from_row(row: &rusqlite::Row) -> rusqlite::Result<Article> {
+    let is_read_val: i32 = row.get(10)?;
+    let is_bookmarked_val: i32 = row.get(11)?;
+    let image_url: Option<String> = row.get(12)?;
+    let date_is_estimated: Option<i32> = row.get(13)?;
+    let published_at: String = row.get(7)?;
+    Ok(Article {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        summary: row.get(2)?,
+        content: row.get(3)?,
+        url: row.get(4)?,
+        source: row.get(5)?,
+        category: row.get(6)?,
+        freshness: datetime::Freshness::of(&published_at),
+        published_at: datetime::to_display(&published_at),
+        fetched_at: datetime::to_display(&row.get::<_, String>(8)?),
+        heat_score: row.get(9)?,
+        is_read: is_read_val > 0,
+        is_bookmarked: is_bookmarked_val > 0,
+        image_url: image_url.unwrap_or_default(),
+        date_is_estimated: date_is_estimated.unwrap_or(0) > 0,
+        paper_code_link: row.get::<_, Option<String>>(14)?.filter(|l| !l.is_empty()),
+        opened_at: row.get::<_, Option<String>>(15)?.map(|at| datetime::to_display(&at)),
+        summary_status: SummaryStatus::parse(&row.get::<_, Option<String>>(16)?.unwrap_or_default()),
+        summary_error: row.get(17)?,
+        summary_source: SummarySource::parse(&row.get::<_, Option<String>>(18)?.unwrap_or_default()),
+        discussion_url: row.get(19)?,
+        comment_count: row.get(20)?,
+        points: row.get(21)?,
+        comments_summary: row.get(22)?,
+        media_duration: row.get(23)?,
+        reading_minutes: row.get(24)?,
+        paywalled: row.get::<_, i32>(25)? != 0,
+    })
+}
+
+// Paginated article listing shared by `articles_list` and the local API
+// `balance` applies the feed composition limits when the query is the default view
+pub(crate) fn query_articles_page(
+    conn: &Connection,
+    query: &ListQuery,
+    balance: Option<&feed_balance::FeedBalance>,
+) -> Result<ListResponse, Error> {
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size;
+    let offset = (page - 1) * page_size;
+
+    // Build query conditions
+    let mut conditions: Vec<String> = Vec::new();
+    let mut params_vec: Vec<String> = Vec::new();
+
+    if let Some(cat) = &query.category {
+        if cat != "all" {
+            params_vec.push(cat.clone());
+            conditions.push(format!("category = ?{}", params_vec.len()));
+        }
+    }
+    if query.bookmarked_only.unwrap_or(false) {
+        conditions.push("is_bookmarked = 1".to_string());
+    }
+    if !query.include_muted.unwrap_or(false) && !query.bookmarked_only.unwrap_or(false) {
+        params_vec.push(chrono::Utc::now().to_rfc3339());
+        conditions.push(format!(
+            "source NOT IN (SELECT name FROM sources WHERE muted_until > ?{})",
+            params_vec.len()
+        ));
+    }
+    if !query.include_snoozed.unwrap_or(false) {
+        params_vec.push(chrono::Utc::now().to_rfc3339());
+        conditions.push(format!("(snoozed_until IS NULL OR snoozed_until <= ?{})", params_vec.len()));
+    }
+    if let Some(folder_id) = &query.folder_id {
+        params_vec.push(folder_id.clone());
+        conditions.push(folders::source_condition(params_vec.len()));
+    }
+    if let Some(tag) = query.tag.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        // Extracted keywords are lowercase; user tags keep their case
+        params_vec.push(tag.to_lowercase());
+        let n = params_vec.len();
+        conditions.push(format!(
+            "(id IN (SELECT article_id FROM article_keywords WHERE keyword = ?{n}) \
+             OR id IN (SELECT article_id FROM article_tags WHERE LOWER(tag) = ?{n}))"
+        ));
+    }
+    if let Some(max_minutes) = query.max_duration_minutes.filter(|m| *m > 0) {
+        params_vec.push((max_minutes * 60).to_string());
+        conditions.push(format!("(media_duration IS NULL OR media_duration <= CAST(?{} AS INTEGER))", params_vec.len()));
+    }
+    if query.short_only.unwrap_or(false) {
+        conditions.push(read_time::short_condition());
+    }
+    if query.hide_paywalled.unwrap_or(false) {
+        conditions.push("paywalled = 0".to_string());
+    }
+    if let Some(freshness) = query.freshness {
+        let (start, end) = freshness.bounds();
+        if let Some(start) = start {
+            params_vec.push(start);
+            conditions.push(format!("published_at >= ?{}", params_vec.len()));
+        }
+        if let Some(end) = end {
+            params_vec.push(end);
+            conditions.push(format!("published_at < ?{}", params_vec.len()));
+        }
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    // Count total
+    let count_query = format!("SELECT COUNT(*) FROM articles{}", where_clause);
+    let total: i64 = conn.query_row(&count_query, params_from_iter(params_vec.iter()), |row| row.get(0))
+        .unwrap_or(0);
+
+    // Get articles
+    if let Some(balance) = balance.filter(|b| b.applies(query)) {
+        let items = balance.query_page(conn, &where_clause, &params_vec, page, page_size)?;
+        let groups = if query.group_by_freshness.unwrap_or(false) {
+            Some(query_freshness_groups(conn, &where_clause, &params_vec)?)
+        } else {
+            None
+        };
+        return Ok(ListResponse { items, total, page, page_size, groups });
+    }
+    let list_query = format!(
+        "SELECT {}
+         FROM articles{}
+         ORDER BY published_at DESC, fetched_at DESC
+         LIMIT ?{} OFFSET ?{}",
+        ARTICLE_COLUMNS,
+        where_clause,
+        params_vec.len() + 1,
+        params_vec.len() + 2
+    );
+
+    let page_size_param = page_size as i64;
+    let offset_param = offset as i64;
+    let mut list_params: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+    list_params.push(&page_size_param);
+    list_params.push(&offset_param);
+
+    let mut stmt = conn.prepare(&list_query)
+        .map_err(Error::Prepare)?;
+
+    let articles: Vec<Article> = stmt.query_map(list_params.as_slice(), article_from_row)
+        .map_err(Error::Query)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Collect)?;
+
+    let groups = if query.group_by_freshness.unwrap_or(false) {
+        Some(query_freshness_groups(conn, &where_clause, &params_vec)?)
+    } else {
+        None
+    };
+
+    Ok(ListResponse {
+        items: articles,
+        total,
+        page,
+        page_size,
+        groups,
+    })
+}
+
+// Article counts per freshness bucket under the list filters, newest bucket first
+fn query_freshness_groups(conn: &Connection, where_clause: &str, params_vec: &[String]) -> Result<Vec<FreshnessGroup>, Error> {
+    // Bucket starts go after the filter parameters: today, yesterday, this week
+    let mut bucket_params = params_vec.to_vec();
+    let mut cases = Vec::new();
+    for freshness in datetime::Freshness::ALL {
+        if let (Some(start), _) = freshness.bounds() {
+            bucket_params.push(start);
+            cases.push(format!("WHEN published_at >= ?{} THEN '{}'", bucket_params.len(), freshness.code()));
+        }
+    }
+    let sql = format!(
+        "SELECT CASE {} ELSE '{}' END AS bucket, COUNT(*) FROM articles{} GROUP BY bucket",
+        cases.join(" "),
+        datetime::Freshness::Older.code(),
+        where_clause
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(Error::Prepare)?;
+    let counts: Vec<(String, i64)> = stmt
+        .query_map(params_from_iter(bucket_params.iter()), |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(Error::Query)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Collect)?;
+
+    Ok(datetime::Freshness::ALL
+        .into_iter()
+        .filter_map(|freshness| {
+            counts
+                .iter()
+                .find(|(bucket, _)| bucket == freshness.code())
+                .map(|(_, count)| FreshnessGroup { freshness, count: *count })
+        })
+        .collect())
+}
+
+pub(crate) fn query_article(conn: &Connection, id: &str) -> Result<Option<Article>, Error> {
+    let sql = format!("SELECT {} FROM articles WHERE id = ?1", ARTICLE_COLUMNS);
+    match conn.query_row(&sql, params![id], article_from_row) {
+        Ok(article) => Ok(Some(article)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(Error::Query(e)),
+    }
+}
+
+// Articles by ID in the order given, each once; IDs not stored are left out
+pub(crate) fn query_articles_by_ids(conn: &Connection, ids: &[String]) -> Result<Vec<Article>, Error> {
+    // Well under SQLite's limit on bound parameters
+    const CHUNK: usize = 500;
+
+    let mut found: HashMap<String, Article> = HashMap::new();
+    for chunk in ids.chunks(CHUNK) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        let sql = format!("SELECT {} FROM articles WHERE id IN ({})", ARTICLE_COLUMNS, placeholders);
+        let mut stmt = conn.prepare(&sql).map_err(Error::Prepare)?;
+        let articles = stmt
+            .query_map(params_from_iter(chunk.iter()), article_from_row)
+            .map_err(Error::Query)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::Collect)?;
+        found.extend(articles.into_iter().map(|article| (article.id.clone(), article)));
+    }
+    Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+}
+
+// FTS5 prefix search shared by `search_query` and the local API
+// A scoped search selects the scope's articles first and matches only those
+pub(crate) fn query_search(conn: &Connection, keyword: &str, scope: &SearchScope) -> Result<Vec<Article>, Error> {
+    let conditions = scope.conditions(2);
+    let matched = "articles.rowid IN (SELECT rowid FROM articles_fts WHERE articles_fts MATCH ?1)";
+    let query = if conditions.is_empty() {
+        format!(
+            "SELECT {} FROM articles
+             WHERE {}
+             ORDER BY published_at DESC
+             LIMIT 100",
+            ARTICLE_COLUMNS, matched
+        )
+    } else {
+        // CROSS JOIN keeps SQLite from reordering: the scope drives the lookup
+        format!(
+            "WITH scope AS (SELECT rowid AS article_rowid FROM articles WHERE {})
+             SELECT {} FROM scope
+             CROSS JOIN articles ON articles.rowid = scope.article_rowid
+             WHERE {}
+             ORDER BY published_at DESC
+             LIMIT 100",
+            conditions.join(" AND "),
+            ARTICLE_COLUMNS,
+            matched
+        )
+    };
+
+    let mut stmt = conn.prepare(&query)
+        .map_err(Error::Prepare)?;
+
+    let mut values = vec![search_index::match_expression(conn, keyword)?];
+    values.extend(scope.source().map(str::to_string));
+
+    let articles: Vec<Article> = stmt.query_map(params_from_iter(values.iter()), article_from_row)
+        .map_err(Error::Query)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Collect)?;
+
+    Ok(articles)
+}
+
+// Hottest articles published within the last `days`, used by the MCP `list_trending` tool
+pub(crate) fn query_trending(conn: &Connection, days: i64, category: Option<&str>, limit: usize) -> Result<Vec<Article>, Error> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let category = category.filter(|c| !c.is_empty() && *c != "all");
+
+    let sql = format!(
+        "SELECT {}
+         FROM articles
+         WHERE published_at >= ?1 AND (?2 IS NULL OR category = ?2)
+         ORDER BY heat_score DESC, published_at DESC
+         LIMIT ?3",
+        ARTICLE_COLUMNS
+    );
+
+    let mut stmt = conn.prepare(&sql)
+        .map_err(Error::Prepare)?;
+
+    let articles: Vec<Article> = stmt.query_map(params![since, category, limit as i64], article_from_row)
+        .map_err(Error::Query)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Collect)?;
+
+    Ok(articles)
+}
+
+// Keep the newest MAX_STORED_ARTICLES articles. Bookmarked, annotated and
+// highlighted ones are never removed; the rest move to the trash, and
+// whatever hung off articles that left the trash goes too, all in one
+// transaction
+// The oldest rows retention may remove (not bookmarked or annotated); bound to ?1 = how many
+pub(crate) const REMOVABLE_OLDEST: &str = "SELECT rowid FROM articles
+     WHERE is_bookmarked = 0
+       AND id NOT IN (SELECT article_id FROM article_notes)
+       AND id NOT IN (SELECT article_id FROM article_highlights)
+     ORDER BY fetched_at ASC LIMIT ?1";
+
+pub(crate) fn cleanup_articles(conn: &Connection) -> Result<CleanupResult, Error> {
+    const MAX_STORED_ARTICLES: i64 = 300;
+
+    let retention_days: u32 = get_setting(conn, "trash_retention_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_trash_retention_days());
+    let seen_retention_days: u32 = get_setting(conn, "seen_urls_retention_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_seen_urls_retention_days());
+    let tx = conn.unchecked_transaction().map_err(Error::Delete)?;
+    let cache_purged = http_cache::purge(&tx)?;
+    seen_urls::purge_expired(&tx, seen_retention_days)?;
+
+    let total: i64 = tx
+        .query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))
+        .map_err(Error::Query)?;
+    let to_delete = (total - MAX_STORED_ARTICLES).max(0);
+    let deleted = if to_delete > 0 { trash::move_articles(&tx, REMOVABLE_OLDEST, to_delete)? } else { 0 };
+    let trash_purged = trash::purge_expired(&tx, retention_days)?;
+
+    if deleted > 0 || trash_purged > 0 {
+        delete_article_orphans(&tx)?;
+    }
+    tx.commit().map_err(Error::Delete)?;
+
+    let mut result = CleanupResult { deleted, trash_purged, cache_purged };
+    storage::enforce_quota(conn, &mut result)?;
+    Ok(result)
+}
+
+// Rows keyed by article id whose article is neither stored nor in the trash
+pub(crate) fn delete_article_orphans(conn: &Connection) -> Result<(), Error> {
+    revisions::delete_orphans(conn)?;
+    article_edits::delete_orphans(conn)?;
+    watchlists::delete_orphans(conn)?;
+    rules::delete_orphans(conn)?;
+    keywords::delete_orphans(conn)?;
+    Ok(syndication::delete_orphans(conn)?)
+}
+
+// Articles whose snooze just ended, emitted by the scheduler
+#[derive(Debug, Serialize, Clone)]
+pub(crate) struct ArticlesUnsnoozedEvent {
+    pub articles: Vec<Article>,
+    // IDs snoozed with `notify`
+    pub notify: Vec<String>,
+}
+
+// Clear snoozes whose time has passed and return the articles that reappear
+pub(crate) fn wake_snoozed_articles(conn: &Connection) -> Result<ArticlesUnsnoozedEvent, Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let sql = format!(
+        "SELECT {}, snooze_notify FROM articles WHERE snoozed_until <= ?1 ORDER BY snoozed_until ASC",
+        ARTICLE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(Error::Prepare)?;
+    let due: Vec<(Article, bool)> = stmt
+        .query_map(params![now], |row| Ok((article_from_row(row)?, row.get::<_, Option<i32>>("snooze_notify")?.unwrap_or(0) > 0)))
+        .map_err(Error::Query)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Collect)?;
+
+    if !due.is_empty() {
+        conn.execute(
+            "UPDATE articles SET snoozed_until = NULL, snooze_notify = 0 WHERE snoozed_until <= ?1",
+            params![now],
+        ).map_err(Error::Update)?;
+    }
+
+    let notify = due.iter().filter(|(_, notify)| *notify).map(|(a, _)| a.id.clone()).collect();
+    Ok(ArticlesUnsnoozedEvent {
+        articles: due.into_iter().map(|(a, _)| a).collect(),
+        notify,
+    })
+}
+
+// Clear mutes whose time has passed; run by the scheduler
+pub(crate) fn unmute_expired_sources(conn: &Connection) -> Result<usize, Error> {
+    conn.execute(
+        "UPDATE sources SET muted_until = NULL WHERE muted_until <= ?1",
+        params![chrono::Utc::now().to_rfc3339()],
+    ).map_err(Error::Update)
+}
+
+pub(crate) fn article_url_exists(conn: &Connection, normalized_url: &str) -> bool {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM articles WHERE url = ?1)",
+        params![normalized_url],
+        |row| row.get(0)
+    ).unwrap_or(false)
+}
+
+// Insert an article built from an extracted page (manual add, browser extension)
+pub(crate) fn insert_page_article(conn: &Connection, normalized_url: &str, page: ExtractedPage, source: &str) -> Result<Article, Error> {
+    let ExtractedPage { title, content, image_url, published_at, media_duration, paywalled } = page;
+
+    // Generate summary
+    let summary = local_brief(&title, &content, source);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    // Pages without a publish date get the add time
+    let date_is_estimated = published_at.is_none();
+    let published_at = published_at.unwrap_or_else(|| now.clone());
+    let reading_minutes = read_time::reading_minutes(&content);
+
+    conn.execute(
+        "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at, image_url, date_is_estimated, summary_status, summary_source, media_duration, reading_minutes, paywalled)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'template', 'template', ?12, ?13, ?14)",
+        params![id, title, summary, content, normalized_url, source, "Tech", &published_at, &now, image_url, date_is_estimated, media_duration, reading_minutes, paywalled]
+    ).map_err(Error::Insert)?;
+
+    // Get the integer rowid for FTS
+    let rowid: i64 = conn.last_insert_rowid();
+
+    // Insert into FTS table
+    conn.execute(
+        "INSERT INTO articles_fts (rowid, title, summary, content) VALUES (?1, cjk_segment(?2), cjk_segment(?3), cjk_segment(?4))",
+        params![rowid, title, summary, content]
+    ).map_err(Error::FtsInsert)?;
+    heat::rescore_ids(conn, std::slice::from_ref(&id))?;
+    keywords::tag_articles(conn, std::slice::from_ref(&id))?;
+
+    Ok(Article {
+        id,
+        title,
+        summary,
+        content,
+        url: normalized_url.to_string(),
+        source: source.to_string(),
+        category: "Tech".to_string(),
+        published_at: datetime::to_display(&published_at),
+        fetched_at: datetime::to_display(&now),
+        heat_score: 0.0,
+        is_read: false,
+        is_bookmarked: false,
+        image_url,
+        date_is_estimated,
+        freshness: datetime::Freshness::of(&published_at),
+        paper_code_link: None,
+        opened_at: None,
+        summary_status: SummaryStatus::Template,
+        summary_error: None,
+        summary_source: SummarySource::Template,
+        discussion_url: None,
+        comment_count: None,
+        points: None,
+        comments_summary: None,
+        media_duration,
+        reading_minutes: Some(reading_minutes),
+        paywalled,
+    })
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> Result<Settings, Error> {
+    // Get settings from DB or use defaults
+    let theme = get_setting(conn, "theme", "auto")?;
+    let ai_model = get_setting(conn, "ai_model", "")?;
+    let ai_base_url = get_setting(conn, "ai_base_url", "")?;
+    let ai_api_key = get_setting(conn, "ai_api_key", "")?;
+    let ai_summary_enabled = get_setting(conn, "ai_summary_enabled", "true")? == "true";
+    let language = get_setting(conn, "language", "zh")?;
+    let local_api_enabled = get_setting(conn, "local_api_enabled", "false")? == "true";
+    let local_api_port = get_setting(conn, "local_api_port", "")?
+        .parse()
+        .unwrap_or_else(|_| default_local_api_port());
+    let local_api_token = get_setting(conn, "local_api_token", "")?;
+    let feed_auto_export = get_setting(conn, "feed_auto_export", "false")? == "true";
+    let feed_export_path = get_setting(conn, "feed_export_path", "")?;
+    let card_font_path = get_setting(conn, "card_font_path", "")?;
+    let max_article_age_days = get_setting(conn, "max_article_age_days", "0")?.parse().unwrap_or(0);
+    let display_timezone = get_setting(conn, "display_timezone", "local")?;
+    let crawl_interval_minutes = get_setting(conn, "crawl_interval_minutes", "0")?.parse().unwrap_or(0);
+    let cleanup_interval_hours = get_setting(conn, "cleanup_interval_hours", "")?
+        .parse()
+        .unwrap_or_else(|_| default_cleanup_interval_hours());
+    let trash_retention_days = get_setting(conn, "trash_retention_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_trash_retention_days());
+    let seen_urls_retention_days = get_setting(conn, "seen_urls_retention_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_seen_urls_retention_days());
+    let http_pool_max_idle_per_host = get_setting(conn, "http_pool_max_idle_per_host", "")?
+        .parse()
+        .unwrap_or_else(|_| default_http_pool_max_idle_per_host());
+    let http_pool_idle_timeout_secs = get_setting(conn, "http_pool_idle_timeout_secs", "")?
+        .parse()
+        .unwrap_or_else(|_| default_http_pool_idle_timeout_secs());
+    let slow_query_ms = get_setting(conn, "slow_query_ms", "")?
+        .parse()
+        .unwrap_or_else(|_| default_slow_query_ms());
+    let summary_mode = lazy_summary::normalize_mode(&get_setting(conn, "summary_mode", "")?).to_string();
+    let github_reappear_cooloff_days = get_setting(conn, "github_reappear_cooloff_days", "")?
+        .parse()
+        .unwrap_or_else(|_| default_github_reappear_cooloff_days());
+    let github_reappear_star_delta = get_setting(conn, "github_reappear_star_delta", "")?
+        .parse()
+        .unwrap_or_else(|_| default_github_reappear_star_delta());
+    let night_batch_enabled = get_setting(conn, "night_batch_enabled", "false")? == "true";
+    let night_batch_start_hour = get_setting(conn, "night_batch_start_hour", "")?
+        .parse()
+        .unwrap_or_else(|_| default_night_batch_start_hour());
+    let night_batch_end_hour = get_setting(conn, "night_batch_end_hour", "")?
+        .parse()
+        .unwrap_or_else(|_| default_night_batch_end_hour());
+    let night_batch_when_idle = get_setting(conn, "night_batch_when_idle", "")?
+        .parse()
+        .unwrap_or_else(|_| default_night_batch_when_idle());
+    let storage_quota_mb = get_setting(conn, "storage_quota_mb", "0")?.parse().unwrap_or(0);
+    let low_data_mode = get_setting(conn, "low_data_mode", "false")? == "true";
+    let proxy_down_policy = network::normalize_proxy_down_policy(&get_setting(conn, "proxy_down_policy", "")?).to_string();
+
+    // Fallback to environment variables if database is empty
+    let ai_model = if ai_model.is_empty() {
+        std::env::var("AI_MODEL").unwrap_or_else(|_| "qwen3-max".to_string())
+    } else {
+        ai_model
+    };
+    let ai_base_url = if ai_base_url.is_empty() {
+        std::env::var("AI_BASE_URL").unwrap_or_default()
+    } else {
+        ai_base_url
+    };
+    let ai_api_key = if ai_api_key.is_empty() {
+        std::env::var("AI_API_KEY").unwrap_or_default()
+    } else {
+        ai_api_key
+    };
+
+    Ok(Settings {
+        theme,
+        ai_model,
+        ai_base_url,
+        ai_api_key,
+        ai_summary_enabled,
+        language,
+        local_api_enabled,
+        local_api_port,
+        local_api_token,
+        feed_auto_export,
+        feed_export_path,
+        card_font_path,
+        max_article_age_days,
+        display_timezone,
+        crawl_interval_minutes,
+        cleanup_interval_hours,
+        trash_retention_days,
+        seen_urls_retention_days,
+        http_pool_max_idle_per_host,
+        http_pool_idle_timeout_secs,
+        slow_query_ms,
+        summary_mode,
+        github_reappear_cooloff_days,
+        github_reappear_star_delta,
+        night_batch_enabled,
+        night_batch_start_hour,
+        night_batch_end_hour,
+        night_batch_when_idle,
+        storage_quota_mb,
+        proxy_down_policy,
+        low_data_mode,
+    })
+}
+
+pub(crate) fn get_setting(conn: &Connection, key: &str, default: &str) -> Result<String, Error> {
+    match conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get::<_, String>(0)
+    ) {
+        Ok(val) => Ok(val),
+        Err(_) => Ok(default.to_string()),
+    }
+}
+
+pub(crate) fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), Error> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value]
+    ).map_err(Error::Insert)?;
+    Ok(())
+}
+
+// Replace an article's summary and its status, keeping the search index in step
+pub(crate) fn store_summary(conn: &Connection, id: &str, summary: &GeneratedSummary) -> Result<(), Error> {
+    conn.execute(
+        "UPDATE articles SET summary = ?1, summary_status = ?2, summary_error = ?3, summary_source = ?4 WHERE id = ?5",
+        params![summary.text, summary.status.as_str(), summary.error, summary.source().as_str(), id],
+    )
+    .map_err(Error::Update)?;
+    conn.execute(
+        "UPDATE articles_fts SET summary = cjk_segment(?1) WHERE rowid = (SELECT rowid FROM articles WHERE id = ?2)",
+        params![summary.text, id],
+    )
+    .map_err(Error::Update)?;
+    Ok(())
+}
+
+// Store a summary the AI just produced
+pub(crate) fn update_summary(conn: &Connection, id: &str, summary: &str) -> Result<(), Error> {
+    store_summary(conn, id, &GeneratedSummary { text: summary.to_string(), status: SummaryStatus::Ai, error: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_db() -> Connection {
+        crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn insert_article(conn: &Connection, id: &str, source: &str, title: &str) {
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, '', '', ?3, ?4, 'tech', ?5, ?5)",
+            params![id, title, format!("https://example.com/{}", id), source, now],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO articles_fts (rowid, title, summary, content)
+             SELECT rowid, cjk_segment(title), '', '' FROM articles WHERE id = ?1",
+            params![id],
+        )
+        .unwrap();
+    }
+
+    fn list_query() -> ListQuery {
+        ListQuery {
+            page: None,
+            page_size: 50,
+            category: None,
+            bookmarked_only: None,
+            include_muted: None,
+            include_snoozed: None,
+            folder_id: None,
+            freshness: None,
+            group_by_freshness: None,
+            tag: None,
+            max_duration_minutes: None,
+            short_only: None,
+            hide_paywalled: None,
+        }
+    }
+
+    fn listed_ids(conn: &Connection, query: &ListQuery) -> Vec<String> {
+        let mut ids: Vec<String> = query_articles_page(conn, query, None).unwrap().items.into_iter().map(|a| a.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn muted_sources_are_hidden_until_the_mute_ends() {
+        let conn = memory_db();
+        for (name, url) in [("Loud Blog", "https://loud.example.com/feed.xml"), ("Quiet Blog", "https://quiet.example.com/feed.xml")] {
+            conn.execute(
+                "INSERT INTO sources (id, name, url, source_type) VALUES (?1, ?1, ?2, 'RSS')",
+                params![name, url],
+            )
+            .unwrap();
+        }
+        insert_article(&conn, "a1", "Loud Blog", "Loud post");
+        insert_article(&conn, "a2", "Quiet Blog", "Quiet post");
+        conn.execute("UPDATE articles SET is_bookmarked = 1 WHERE id = 'a1'", []).unwrap();
+        let tomorrow = (chrono::Utc::now() + chrono::Duration::days(1)).to_rfc3339();
+        conn.execute("UPDATE sources SET muted_until = ?1 WHERE name = 'Loud Blog'", params![tomorrow]).unwrap();
+
+        assert_eq!(listed_ids(&conn, &list_query()), ["a2"]);
+        assert_eq!(listed_ids(&conn, &ListQuery { include_muted: Some(true), ..list_query() }), ["a1", "a2"]);
+        let bookmarks = ListQuery { bookmarked_only: Some(true), ..list_query() };
+        assert_eq!(listed_ids(&conn, &bookmarks), ["a1"], "bookmarks ignore mutes");
+
+        assert_eq!(unmute_expired_sources(&conn).unwrap(), 0, "the mute hasn't ended");
+        let an_hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        conn.execute("UPDATE sources SET muted_until = ?1 WHERE name = 'Loud Blog'", params![an_hour_ago]).unwrap();
+        assert_eq!(unmute_expired_sources(&conn).unwrap(), 1);
+        assert_eq!(listed_ids(&conn, &list_query()), ["a1", "a2"]);
+    }
+
+    #[test]
+    fn snoozed_articles_reappear_when_the_snooze_ends() {
+        let conn = memory_db();
+        for id in ["a1", "a2", "a3"] {
+            insert_article(&conn, id, "Blog", "Post");
+        }
+        let tomorrow = (chrono::Utc::now() + chrono::Duration::days(1)).to_rfc3339();
+        let an_hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        conn.execute("UPDATE articles SET snoozed_until = ?1 WHERE id = 'a1'", params![tomorrow]).unwrap();
+        conn.execute("UPDATE articles SET snoozed_until = ?1, snooze_notify = 1 WHERE id = 'a2'", params![an_hour_ago])
+            .unwrap();
+
+        assert_eq!(listed_ids(&conn, &list_query()), ["a2", "a3"], "a snooze in the past no longer hides");
+        let all = ListQuery { include_snoozed: Some(true), ..list_query() };
+        assert_eq!(listed_ids(&conn, &all), ["a1", "a2", "a3"]);
+
+        let woken = wake_snoozed_articles(&conn).unwrap();
+        assert_eq!(woken.articles.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["a2"]);
+        assert_eq!(woken.notify, ["a2"]);
+        let (until, notify): (Option<String>, i64) = conn
+            .query_row("SELECT snoozed_until, snooze_notify FROM articles WHERE id = 'a2'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((until, notify), (None, 0));
+        assert!(wake_snoozed_articles(&conn).unwrap().articles.is_empty(), "each snooze wakes once");
+    }
+
+    #[test]
+    fn stored_summaries_keep_their_status_and_error() {
+        let conn = memory_db();
+        insert_article(&conn, "a1", "Blog", "Post");
+        let failed = GeneratedSummary {
+            text: "Local brief".to_string(),
+            status: SummaryStatus::Failed,
+            error: Some("HTTP 429".to_string()),
+        };
+        store_summary(&conn, "a1", &failed).unwrap();
+        let article = query_article(&conn, "a1").unwrap().unwrap();
+        assert_eq!(article.summary, "Local brief");
+        assert_eq!(article.summary_status, SummaryStatus::Failed);
+        assert_eq!(article.summary_error.as_deref(), Some("HTTP 429"));
+
+        update_summary(&conn, "a1", "Distilled models keep their accuracy").unwrap();
+        let article = query_article(&conn, "a1").unwrap().unwrap();
+        assert_eq!(article.summary_status, SummaryStatus::Ai);
+        assert_eq!(article.summary_error, None, "a later success clears the error");
+        let found = query_search(&conn, "distilled", &SearchScope::default()).unwrap();
+        assert_eq!(found.len(), 1, "the new summary is searchable");
+
+        for status in [SummaryStatus::Pending, SummaryStatus::Template, SummaryStatus::Ai, SummaryStatus::Failed] {
+            assert_eq!(SummaryStatus::parse(status.as_str()), status);
+        }
+        assert_eq!(SummaryStatus::parse(""), SummaryStatus::Pending, "rows from before statuses were tracked");
+    }
+
+    #[test]
+    fn cleanup_trashes_the_oldest_articles_but_keeps_bookmarks() {
+        let conn = memory_db();
+        let start = chrono::Utc::now() - chrono::Duration::days(30);
+        for i in 0..303 {
+            let fetched_at = (start + chrono::Duration::minutes(i)).to_rfc3339();
+            conn.execute(
+                "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+                 VALUES (?1, 'Post', '', '', ?2, 'Blog', 'tech', ?3, ?3)",
+                params![format!("a{:03}", i), format!("https://example.com/{}", i), fetched_at],
+            )
+            .unwrap();
+        }
+        conn.execute("UPDATE articles SET is_bookmarked = 1 WHERE id = 'a000'", []).unwrap();
+
+        let result = cleanup_articles(&conn).unwrap();
+        assert_eq!(result.deleted, 3);
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 300);
+        assert!(query_article(&conn, "a000").unwrap().is_some(), "bookmarked articles stay");
+        let mut trashed: Vec<String> = conn
+            .prepare("SELECT id FROM articles_trash")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        trashed.sort();
+        assert_eq!(trashed, ["a001", "a002", "a003"]);
+        assert!(seen_urls::is_seen(&conn, "https://example.com/1").unwrap(), "a re-crawl won't bring it back");
+
+        assert_eq!(cleanup_articles(&conn).unwrap().deleted, 0, "nothing over the limit");
+    }
+
+    #[test]
+    fn articles_by_ids_keep_the_given_order_once_each() {
+        let conn = memory_db();
+        for id in ["a1", "a2", "a3"] {
+            insert_article(&conn, id, "Blog", "Post");
+        }
+        let ids: Vec<String> = ["a3", "missing", "a1", "a3"].iter().map(|id| id.to_string()).collect();
+        let found: Vec<String> = query_articles_by_ids(&conn, &ids).unwrap().into_iter().map(|a| a.id).collect();
+        assert_eq!(found, ["a3", "a1"]);
+        assert!(query_articles_by_ids(&conn, &[]).unwrap().is_empty());
+
+        // More IDs than one chunk holds
+        let many: Vec<String> = (0..1200).map(|i| format!("x{}", i)).chain(["a2".to_string()]).collect();
+        let found = query_articles_by_ids(&conn, &many).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "a2");
+    }
+
+    #[test]
+    fn scoped_search_matches_only_within_the_scope() {
+        let conn = memory_db();
+        insert_article(&conn, "a1", "Blog", "Agents in production");
+        insert_article(&conn, "a2", "Papers", "Agents for science");
+        insert_article(&conn, "a3", "Papers", "Agents that annotate");
+        insert_article(&conn, "a4", "Papers", "Compilers");
+        conn.execute("UPDATE articles SET is_bookmarked = 1 WHERE id IN ('a1', 'a4')", []).unwrap();
+        annotations::set_note(&conn, "a3", "Worth a second read").unwrap();
+
+        let search = |scope: SearchScope| {
+            let mut ids: Vec<String> = query_search(&conn, "agents", &scope).unwrap().into_iter().map(|a| a.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(search(SearchScope::default()), ["a1", "a2", "a3"]);
+        assert_eq!(search(SearchScope { bookmarks: true, ..Default::default() }), ["a1"]);
+        assert_eq!(search(SearchScope { annotated: true, ..Default::default() }), ["a3"]);
+        assert_eq!(search(SearchScope { source: Some(" Papers ".into()), ..Default::default() }), ["a2", "a3"]);
+        let both = SearchScope { bookmarks: true, source: Some("Papers".into()), ..Default::default() };
+        assert!(search(both).is_empty(), "scopes combine");
+    }
+}
@@ -0,0 +1,251 @@
+// Local database backups and recovery from a corrupted database at startup.
+//
+// The scheduler copies the live database into `backups/` next to it once a
+// day (`VACUUM INTO`), keeping the KEPT_BACKUPS newest. At startup the
+// database gets a `PRAGMA quick_check` before it's migrated; when SQLite
+// reports it corrupt or not a database at all, the file (with its -wal/-shm)
+// is moved aside as `news.db.corrupt-<time>` and the newest backup that
+// passes the check takes its place, or an empty database when none does.
+// The app then starts normally and the `RecoveryReport` goes out as
+// `app://db:recovered`; since that can fire before the window listens,
+// `db_recovery_report` returns it too. Busy, locked or read-only databases
+// aren't damaged and are left to the read-only fallback.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, ErrorCode};
+use serde::Serialize;
+
+use crate::i18n::{tr, Msg};
+use crate::init_db_with_conn;
+
+const BACKUP_DIR: &str = "backups";
+const KEPT_BACKUPS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    // What opening or checking the database reported
+    pub error: String,
+    // Where the damaged file was moved
+    pub damaged_path: String,
+    // Backup the database was restored from; None = started with an empty database
+    pub restored_from: Option<String>,
+    pub backup_taken_at: Option<String>,
+    pub recovered_at: String,
+}
+
+// Set when this run started from a recovered database
+static REPORT: Mutex<Option<RecoveryReport>> = Mutex::new(None);
+
+pub fn report() -> Option<RecoveryReport> {
+    REPORT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn backup_dir(db_path: &str) -> PathBuf {
+    Path::new(db_path).parent().unwrap_or(Path::new(".")).join(BACKUP_DIR)
+}
+
+// Backups of the database at `db_path`, newest first
+fn backups(db_path: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(backup_dir(db_path)) else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with("news-") && name.ends_with(".db")
+        })
+        .collect();
+    // The timestamp in the name sorts chronologically
+    files.sort();
+    files.reverse();
+    files
+}
+
+// Copy the live database into the backup folder and drop the oldest copies
+pub fn backup(conn: &Connection) -> Result<(), String> {
+    // Nothing to back up for in-memory databases
+    let Some(db_path) = conn.path().filter(|path| !path.is_empty()).map(str::to_string) else { return Ok(()) };
+    let dir = backup_dir(&db_path);
+    std::fs::create_dir_all(&dir).map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", dir.display(), e)))?;
+    let path = dir.join(format!("news-{}.db", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    // VACUUM INTO refuses to overwrite an existing file
+    let _ = std::fs::remove_file(&path);
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+        .map_err(|e| tr(Msg::DbBackupFailed, e))?;
+
+    for old in backups(&db_path).into_iter().skip(KEPT_BACKUPS) {
+        if let Err(e) = std::fs::remove_file(&old) {
+            eprintln!("Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+    Ok(())
+}
+
+fn is_corrupt(error: &rusqlite::Error) -> bool {
+    matches!(error.sqlite_error_code(), Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase))
+}
+
+// Open, check and migrate; a failed check comes back as SQLITE_CORRUPT
+fn open_checked(db_path: &str) -> Result<Connection, rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    // quick_check skips the index cross-checks of integrity_check, which take minutes on a large library
+    let result: String = conn.query_row("PRAGMA quick_check(1)", [], |row| row.get(0))?;
+    if result != "ok" {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+            Some(result),
+        ));
+    }
+    init_db_with_conn(conn)
+}
+
+// Move the database and its -wal/-shm files out of the way; returns the new path of the database
+fn move_aside(db_path: &str) -> Result<String, rusqlite::Error> {
+    let damaged = format!("{}.corrupt-{}", db_path, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    std::fs::rename(db_path, &damaged).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    for suffix in ["-wal", "-shm"] {
+        let path = format!("{}{}", db_path, suffix);
+        if Path::new(&path).exists() {
+            if let Err(e) = std::fs::rename(&path, format!("{}{}", damaged, suffix)) {
+                eprintln!("Failed to move {} aside: {}", path, e);
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    Ok(damaged)
+}
+
+// Replace the damaged database with the newest usable backup, or an empty one
+fn recover(db_path: &str, error: rusqlite::Error) -> Result<(Connection, RecoveryReport), rusqlite::Error> {
+    eprintln!("Database {} is damaged ({}), recovering", db_path, error);
+    let damaged_path = move_aside(db_path)?;
+    let mut report = RecoveryReport {
+        error: error.to_string(),
+        damaged_path,
+        restored_from: None,
+        backup_taken_at: None,
+        recovered_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    for candidate in backups(db_path) {
+        if let Err(e) = std::fs::copy(&candidate, db_path) {
+            eprintln!("Failed to copy backup {}: {}", candidate.display(), e);
+            continue;
+        }
+        match open_checked(db_path) {
+            Ok(conn) => {
+                report.restored_from = Some(candidate.to_string_lossy().to_string());
+                report.backup_taken_at = std::fs::metadata(&candidate)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
+                return Ok((conn, report));
+            }
+            Err(e) => {
+                eprintln!("Backup {} is unusable: {}", candidate.display(), e);
+                let _ = std::fs::remove_file(db_path);
+            }
+        }
+    }
+    Ok((open_checked(db_path)?, report))
+}
+
+// Open the database at `db_path` for the app, recovering it when it's damaged
+pub fn open(db_path: &str) -> Result<(Connection, Option<RecoveryReport>), rusqlite::Error> {
+    let (conn, report) = match open_checked(db_path) {
+        Ok(conn) => (conn, None),
+        Err(e) if is_corrupt(&e) => {
+            let (conn, report) = recover(db_path, e)?;
+            (conn, Some(report))
+        }
+        Err(e) => return Err(e),
+    };
+    *REPORT.lock().unwrap_or_else(|e| e.into_inner()) = report.clone();
+    Ok((conn, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("news-db-recovery-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(BACKUP_DIR)).unwrap();
+        dir
+    }
+
+    fn article_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn backups_are_listed_newest_first_and_pruned() {
+        let dir = temp_dir("prune");
+        let db_path = dir.join("news.db").to_string_lossy().to_string();
+        for name in ["news-20260101-000000.db", "news-20260103-000000.db", "news-20260102-000000.db", "notes.txt"] {
+            std::fs::write(dir.join(BACKUP_DIR).join(name), b"").unwrap();
+        }
+        let names = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect()
+        };
+        assert_eq!(names(backups(&db_path))[..2], ["news-20260103-000000.db", "news-20260102-000000.db"]);
+
+        let conn = open_checked(&db_path).unwrap();
+        backup(&conn).unwrap();
+        let kept = names(backups(&db_path));
+        assert_eq!(kept.len(), KEPT_BACKUPS);
+        assert!(!kept.contains(&"news-20260101-000000.db".to_string()));
+        assert!(!kept.contains(&"notes.txt".to_string()));
+
+        // Nothing to back up in memory
+        backup(&Connection::open_in_memory().unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unusable_backups_are_skipped() {
+        let dir = temp_dir("skip");
+        let db_path = dir.join("news.db").to_string_lossy().to_string();
+        {
+            let conn = open_checked(&db_path).unwrap();
+            conn.execute("INSERT INTO articles (id, title, url, source) VALUES ('a1', 'One', 'https://example.com/1', 'Blog')", [])
+                .unwrap();
+            conn.execute("VACUUM INTO ?1", params![dir.join(BACKUP_DIR).join("news-20260101-000000.db").to_string_lossy()])
+                .unwrap();
+        }
+        std::fs::write(dir.join(BACKUP_DIR).join("news-20260102-000000.db"), b"not a database").unwrap();
+        std::fs::write(&db_path, b"not a database either").unwrap();
+
+        let (conn, report) = open(&db_path).unwrap();
+        let report = report.expect("recovered");
+        assert!(report.restored_from.unwrap().ends_with("news-20260101-000000.db"));
+        assert!(Path::new(&report.damaged_path).exists());
+        assert_eq!(article_count(&conn), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn without_a_backup_an_empty_database_replaces_a_damaged_one() {
+        let dir = temp_dir("empty");
+        let db_path = dir.join("news.db").to_string_lossy().to_string();
+        std::fs::write(&db_path, b"garbage garbage garbage garbage").unwrap();
+        let (conn, report) = open(&db_path).unwrap();
+        let report = report.expect("recovered");
+        assert!(report.restored_from.is_none());
+        assert!(!report.error.is_empty());
+        assert_eq!(article_count(&conn), 0);
+        drop(conn);
+
+        // A healthy database opens as it is
+        let (_, report) = open(&db_path).unwrap();
+        assert!(report.is_none());
+
+        // Other failures aren't treated as damage
+        let error = open(&dir.join(BACKUP_DIR).to_string_lossy()).unwrap_err();
+        assert!(!is_corrupt(&error));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
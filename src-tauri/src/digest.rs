@@ -0,0 +1,393 @@
+// News digest: the hottest recent articles grouped by category, rendered as
+// an HTML document suitable for email clients (inline styles, table-free).
+// Every digest that is sent (and the weekly roundup) is kept in the
+// `digests` table as Markdown and HTML so past briefings can be browsed and
+// exported later.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{query_trending, read_only, Article};
+
+// Display order for known categories; anything else follows alphabetically
+const CATEGORY_ORDER: [&str; 3] = ["AI", "GitHub", "Tech"];
+
+pub const DEFAULT_PER_CATEGORY: usize = 8;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS digests (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            generated_at TEXT NOT NULL,
+            days INTEGER NOT NULL,
+            article_count INTEGER NOT NULL,
+            markdown TEXT NOT NULL,
+            html TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_digests_generated_at ON digests(generated_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+// A rendered report as kept in the `digests` table
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredDigest {
+    pub id: String,
+    // "digest" (category digest) | "weekly" (themed roundup)
+    pub kind: String,
+    pub title: String,
+    pub generated_at: String,
+    pub days: i64,
+    pub article_count: usize,
+    pub markdown: String,
+    pub html: String,
+}
+
+pub fn store(conn: &Connection, digest: &StoredDigest) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO digests (id, kind, title, generated_at, days, article_count, markdown, html)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            digest.id,
+            digest.kind,
+            digest.title,
+            digest.generated_at,
+            digest.days,
+            digest.article_count as i64,
+            digest.markdown,
+            digest.html
+        ],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(())
+}
+
+// History entry without the rendered bodies
+#[derive(Debug, Serialize)]
+pub struct DigestSummary {
+    pub id: String,
+    pub kind: String,
+    pub title: String,
+    pub generated_at: String,
+    pub days: i64,
+    pub article_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl DigestFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            DigestFormat::Markdown => "md",
+            DigestFormat::Html => "html",
+        }
+    }
+}
+
+// Newest first, optionally only one kind
+pub fn list(conn: &Connection, kind: Option<&str>, limit: usize) -> Result<Vec<DigestSummary>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, kind, title, generated_at, days, article_count FROM digests
+             WHERE (?1 IS NULL OR kind = ?1)
+             ORDER BY generated_at DESC LIMIT ?2",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let digests = stmt
+        .query_map(params![kind, limit as i64], |row| {
+            Ok(DigestSummary {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                title: row.get(2)?,
+                generated_at: row.get(3)?,
+                days: row.get(4)?,
+                article_count: row.get::<_, i64>(5)? as usize,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(digests)
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<StoredDigest, String> {
+    conn.query_row(
+        "SELECT id, kind, title, generated_at, days, article_count, markdown, html FROM digests WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(StoredDigest {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                title: row.get(2)?,
+                generated_at: row.get(3)?,
+                days: row.get(4)?,
+                article_count: row.get::<_, i64>(5)? as usize,
+                markdown: row.get(6)?,
+                html: row.get(7)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| tr(Msg::DbQueryFailed, e))?
+    .ok_or_else(|| t(Msg::DigestNotFound))
+}
+
+// Render a built digest and add it to the history (not stored in read-only mode)
+pub fn record(conn: &Connection, digest: &Digest) -> Result<StoredDigest, String> {
+    let stored = StoredDigest {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: "digest".to_string(),
+        title: digest.title.clone(),
+        generated_at: digest.generated_at.clone(),
+        days: digest.days,
+        article_count: digest.article_count(),
+        markdown: render_markdown(digest),
+        html: render_html(digest),
+    };
+    if !read_only::is_enabled() {
+        store(conn, &stored)?;
+    }
+    Ok(stored)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestSection {
+    pub category: String,
+    pub articles: Vec<Article>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    pub title: String,
+    pub generated_at: String,
+    pub days: i64,
+    pub sections: Vec<DigestSection>,
+}
+
+impl Digest {
+    pub fn article_count(&self) -> usize {
+        self.sections.iter().map(|s| s.articles.len()).sum()
+    }
+}
+
+pub fn build(conn: &Connection, days: i64, per_category: usize) -> Result<Digest, String> {
+    let days = days.max(1);
+    let candidates = query_trending(conn, days, None, 500)?;
+
+    let now = chrono::Local::now();
+    Ok(Digest {
+        title: format!("{} · {}", t(Msg::DigestTitle), now.format("%Y-%m-%d")),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        days,
+        sections: by_category(candidates, per_category),
+    })
+}
+
+// Keep the first `per_category` articles of each category, known categories first
+pub(crate) fn by_category(articles: Vec<Article>, per_category: usize) -> Vec<DigestSection> {
+    let mut sections: Vec<DigestSection> = Vec::new();
+    for article in articles {
+        match sections.iter_mut().find(|s| s.category == article.category) {
+            Some(section) if section.articles.len() >= per_category => {}
+            Some(section) => section.articles.push(article),
+            None => sections.push(DigestSection {
+                category: article.category.clone(),
+                articles: vec![article],
+            }),
+        }
+    }
+    let rank = |category: &str| CATEGORY_ORDER.iter().position(|c| *c == category).unwrap_or(CATEGORY_ORDER.len());
+    sections.sort_by(|a, b| rank(&a.category).cmp(&rank(&b.category)).then_with(|| a.category.cmp(&b.category)));
+    sections
+}
+
+pub fn render_html(digest: &Digest) -> String {
+    let mut body = String::new();
+    if digest.sections.is_empty() {
+        body.push_str(&format!("<p style=\"color:#666\">{}</p>", escape(&t(Msg::DigestEmpty))));
+    }
+    for section in &digest.sections {
+        body.push_str(&html_heading(&section.category));
+        for article in &section.articles {
+            body.push_str(&html_article(article));
+        }
+    }
+    html_document(&digest.title, &body)
+}
+
+pub fn render_markdown(digest: &Digest) -> String {
+    let mut out = format!("# {}\n", digest.title);
+    if digest.sections.is_empty() {
+        out.push_str(&format!("\n{}\n", t(Msg::DigestEmpty)));
+    }
+    for section in &digest.sections {
+        out.push_str(&format!("\n## {}\n\n", section.category));
+        for article in &section.articles {
+            md_article(&mut out, article);
+        }
+    }
+    out
+}
+
+// `[` and `]` would end a link's text early
+fn md_escape(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+pub(crate) fn md_article(out: &mut String, article: &Article) {
+    out.push_str(&format!(
+        "- [{}]({}) — {} · {}\n",
+        md_escape(&article.title),
+        article.url,
+        article.source,
+        article.published_at.get(..10).unwrap_or(&article.published_at)
+    ));
+    if !article.summary.trim().is_empty() {
+        out.push_str(&format!("  {}\n", article.summary.trim().replace('\n', " ")));
+    }
+}
+
+pub(crate) fn html_heading(text: &str) -> String {
+    format!(
+        "<h2 style=\"font-size:18px;margin:28px 0 8px;border-bottom:1px solid #eee;padding-bottom:4px\">{}</h2>",
+        escape(text)
+    )
+}
+
+pub(crate) fn html_article(article: &Article) -> String {
+    format!(
+        "<div style=\"margin:14px 0\">\
+         <a href=\"{url}\" style=\"font-size:16px;font-weight:600;color:#1a56db;text-decoration:none\">{title}</a>\
+         <div style=\"font-size:12px;color:#888;margin:2px 0 4px\">{source} · {date}</div>\
+         <div style=\"font-size:14px;color:#333;line-height:1.5\">{summary}</div>\
+         </div>",
+        url = escape(&article.url),
+        title = escape(&article.title),
+        source = escape(&article.source),
+        date = escape(article.published_at.get(..10).unwrap_or(&article.published_at)),
+        summary = escape(&article.summary),
+    )
+}
+
+pub(crate) fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title></head>\
+         <body style=\"font-family:-apple-system,'Segoe UI','PingFang SC','Microsoft YaHei',sans-serif;max-width:680px;margin:0 auto;padding:16px\">\
+         <h1 style=\"font-size:22px\">{title}</h1>{body}\
+         <p style=\"font-size:12px;color:#aaa;margin-top:32px\">AI News Aggregator</p>\
+         </body></html>",
+        title = escape(title),
+        body = body
+    )
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_article;
+
+    fn article(conn: &Connection, id: &str, title: &str, category: &str) -> Article {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, 'First line\nsecond line', '', ?1, 'Blog', ?3, '2024-01-05T12:00:00+00:00', ?4)",
+            params![id, title, category, chrono::Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        query_article(conn, id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn sections_follow_the_category_order_and_are_capped() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let articles = vec![
+            article(&conn, "https://example.com/1", "Robots", "Science"),
+            article(&conn, "https://example.com/2", "Chips", "Tech"),
+            article(&conn, "https://example.com/3", "Agents", "AI"),
+            article(&conn, "https://example.com/4", "Models", "AI"),
+            article(&conn, "https://example.com/5", "Evals", "AI"),
+            article(&conn, "https://example.com/6", "Biology", "Bio"),
+        ];
+        let sections = by_category(articles, 2);
+        let order: Vec<&str> = sections.iter().map(|s| s.category.as_str()).collect();
+        assert_eq!(order, ["AI", "Tech", "Bio", "Science"]);
+        let ai: Vec<&str> = sections[0].articles.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(ai, ["Agents", "Models"], "hottest first, capped at two");
+    }
+
+    #[test]
+    fn renders_escape_titles_and_are_kept_in_history() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let digest = Digest {
+            title: "Digest".to_string(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            days: 1,
+            sections: by_category(vec![article(&conn, "https://example.com/1?a=1&b=2", "[Draft] <Agents>", "AI")], 8),
+        };
+
+        let markdown = render_markdown(&digest);
+        assert!(
+            markdown.contains("- [\\[Draft\\] <Agents>](https://example.com/1?a=1&b=2) — Blog · 2024-01-05\n  First line second line\n"),
+            "{}",
+            markdown
+        );
+        let html = render_html(&digest);
+        assert!(html.contains("href=\"https://example.com/1?a=1&amp;b=2\""), "{}", html);
+        assert!(html.contains(">[Draft] &lt;Agents&gt;</a>"), "{}", html);
+
+        let stored = record(&conn, &digest).unwrap();
+        assert_eq!(stored.article_count, 1);
+        let history = list(&conn, Some("digest"), 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(get(&conn, &history[0].id).unwrap().markdown, markdown);
+        assert!(list(&conn, Some("weekly"), 10).unwrap().is_empty());
+        assert!(get(&conn, "missing").is_err());
+    }
+
+    #[test]
+    fn history_lists_newest_first_within_the_limit() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for (id, kind, generated_at) in [
+            ("d1", "digest", "2024-01-01T08:00:00+00:00"),
+            ("w1", "weekly", "2024-01-07T08:00:00+00:00"),
+            ("d2", "digest", "2024-01-08T08:00:00+00:00"),
+        ] {
+            let digest = StoredDigest {
+                id: id.to_string(),
+                kind: kind.to_string(),
+                title: format!("Report {}", id),
+                generated_at: generated_at.to_string(),
+                days: 1,
+                article_count: 0,
+                markdown: String::new(),
+                html: String::new(),
+            };
+            store(&conn, &digest).unwrap();
+        }
+        let ids = |kind, limit| list(&conn, kind, limit).unwrap().into_iter().map(|d| d.id).collect::<Vec<_>>();
+        assert_eq!(ids(None, 10), ["d2", "w1", "d1"]);
+        assert_eq!(ids(None, 2), ["d2", "w1"]);
+        assert_eq!(ids(Some("digest"), 10), ["d2", "d1"]);
+        assert_eq!(DigestFormat::default().extension(), "md");
+        assert_eq!(DigestFormat::Html.extension(), "html");
+    }
+}
@@ -0,0 +1,170 @@
+// Discussion threads of aggregator sources (Hacker News, Reddit, anything
+// whose feed items carry a `<comments>` link).
+//
+// The article URL stays the story itself; the thread goes into
+// `articles.discussion_url` with its comment and point counts, so the UI
+// can open either and heat scoring sees points + comments as engagement.
+// hnrss.org puts the counts into the item description ("Points: 12",
+// "# Comments: 3"). Reddit's RSS is Atom without counts, so Reddit sources
+// are read from the JSON listing of the same URL instead.
+
+#[derive(Debug, Clone)]
+pub struct Discussion {
+    pub url: String,
+    pub comment_count: Option<i64>,
+    pub points: Option<i64>,
+}
+
+impl Discussion {
+    // What heat scoring counts for the article
+    pub fn engagement(&self) -> Option<i64> {
+        match (self.points, self.comment_count) {
+            (None, None) => None,
+            (points, comments) => Some(points.unwrap_or(0) + comments.unwrap_or(0)),
+        }
+    }
+}
+
+// The number after `label` in a feed description, e.g. "Points: 12"
+fn count_after(text: &str, label: &str) -> Option<i64> {
+    let start = text.find(label)? + label.len();
+    let digits: String = text[start..].trim_start().chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+// Thread of a feed item with a `<comments>` link; counts where the description has them (hnrss)
+pub fn from_rss_item(item: &rss::Item) -> Option<Discussion> {
+    let url = item.comments()?.trim();
+    if !url.starts_with("http") {
+        return None;
+    }
+    let description = item.description().unwrap_or_default();
+    Some(Discussion {
+        url: url.to_string(),
+        comment_count: count_after(description, "# Comments:"),
+        points: count_after(description, "Points:"),
+    })
+}
+
+// JSON listing for a Reddit feed URL (`/r/MachineLearning/.rss` → `/r/MachineLearning/.json`); None for other hosts
+pub fn reddit_json_url(feed_url: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse(feed_url).ok()?;
+    let host = url.host_str()?;
+    if host != "reddit.com" && !host.ends_with(".reddit.com") {
+        return None;
+    }
+    let path = url.path();
+    let path = match path.strip_suffix(".rss") {
+        Some(base) => format!("{}.json", base),
+        None if path.ends_with(".json") => path.to_string(),
+        None => format!("{}/.json", path.trim_end_matches('/')),
+    };
+    url.set_path(&path);
+    Some(url.to_string())
+}
+
+pub struct RedditPost {
+    pub title: String,
+    // The linked story, or the thread itself for text posts
+    pub url: String,
+    pub text: String,
+    pub created_utc: i64,
+    pub discussion: Discussion,
+}
+
+// Posts of a Reddit JSON listing, pinned ones left out
+pub fn parse_reddit_listing(json: &str) -> Result<Vec<RedditPost>, serde_json::Error> {
+    let listing: serde_json::Value = serde_json::from_str(json)?;
+    let posts = listing["data"]["children"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|child| &child["data"])
+        .filter(|post| !post["stickied"].as_bool().unwrap_or(false))
+        .filter_map(|post| {
+            let title = post["title"].as_str()?.trim().to_string();
+            let permalink = post["permalink"].as_str()?;
+            let thread = format!("https://www.reddit.com{}", permalink);
+            let url = match post["url"].as_str() {
+                Some(url) if url.starts_with("http") && !post["is_self"].as_bool().unwrap_or(false) => url.to_string(),
+                _ => thread.clone(),
+            };
+            Some(RedditPost {
+                title,
+                url,
+                text: post["selftext"].as_str().unwrap_or_default().to_string(),
+                created_utc: post["created_utc"].as_f64().unwrap_or(0.0) as i64,
+                discussion: Discussion {
+                    url: thread,
+                    comment_count: post["num_comments"].as_i64(),
+                    points: post["score"].as_i64(),
+                },
+            })
+        })
+        .collect();
+    Ok(posts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(comments: &str, description: &str) -> rss::Item {
+        let mut item = rss::Item::default();
+        item.set_comments(comments.to_string());
+        item.set_description(description.to_string());
+        item
+    }
+
+    #[test]
+    fn hnrss_counts_come_from_the_description() {
+        let discussion = from_rss_item(&item(
+            " https://news.ycombinator.com/item?id=1 ",
+            "<p>Article URL: https://example.com</p><p>Points: 42</p><p># Comments: 7</p>",
+        ))
+        .unwrap();
+        assert_eq!(discussion.url, "https://news.ycombinator.com/item?id=1");
+        assert_eq!((discussion.points, discussion.comment_count), (Some(42), Some(7)));
+        assert_eq!(discussion.engagement(), Some(49));
+
+        let discussion = from_rss_item(&item("https://lobste.rs/s/abc", "A story")).unwrap();
+        assert_eq!(discussion.engagement(), None, "no counts");
+        assert!(from_rss_item(&item("/relative/thread", "")).is_none());
+        assert!(from_rss_item(&rss::Item::default()).is_none());
+    }
+
+    #[test]
+    fn reddit_feeds_map_to_their_json_listing() {
+        assert_eq!(
+            reddit_json_url("https://www.reddit.com/r/MachineLearning/.rss").as_deref(),
+            Some("https://www.reddit.com/r/MachineLearning/.json")
+        );
+        assert_eq!(
+            reddit_json_url("https://old.reddit.com/r/LocalLLaMA/top?t=day").as_deref(),
+            Some("https://old.reddit.com/r/LocalLLaMA/top/.json?t=day")
+        );
+        assert_eq!(reddit_json_url("https://notreddit.com/r/x/.rss"), None);
+    }
+
+    #[test]
+    fn reddit_listing_skips_pinned_posts() {
+        let json = r#"{"data": {"children": [
+            {"data": {"title": "Weekly thread", "permalink": "/r/ml/comments/0/", "stickied": true}},
+            {"data": {"title": " New model ", "permalink": "/r/ml/comments/1/", "url": "https://example.com/model",
+                      "num_comments": 12, "score": 300, "created_utc": 1700000000.0}},
+            {"data": {"title": "Ask: GPUs?", "permalink": "/r/ml/comments/2/", "url": "https://www.reddit.com/r/ml/comments/2/",
+                      "is_self": true, "selftext": "Which one?"}},
+            {"data": {"permalink": "/r/ml/comments/3/"}}
+        ]}}"#;
+        let posts = parse_reddit_listing(json).unwrap();
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].title, "New model");
+        assert_eq!(posts[0].url, "https://example.com/model");
+        assert_eq!(posts[0].discussion.url, "https://www.reddit.com/r/ml/comments/1/");
+        assert_eq!(posts[0].discussion.engagement(), Some(312));
+        assert_eq!(posts[0].created_utc, 1_700_000_000);
+        assert_eq!(posts[1].url, posts[1].discussion.url, "text posts link to the thread");
+        assert_eq!(posts[1].text, "Which one?");
+        assert!(parse_reddit_listing("<html>").is_err());
+    }
+}
@@ -0,0 +1,287 @@
+// SMTP delivery: settings under `smtp.*` keys, plus the digest email that can
+// be sent on demand (`digest_email`) or by the scheduler on a daily/weekly
+// schedule. Send-to-Kindle (`kindle.rs`) uses the same account.
+
+use std::sync::Mutex;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::digest;
+use crate::i18n::{t, tr, Msg};
+use crate::{get_setting, set_setting};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    // Implicit TLS, usually port 465
+    Tls,
+    // Upgrade a plain connection, usually port 587
+    #[default]
+    Starttls,
+    // Plaintext, for local relays only
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestSchedule {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl DigestSchedule {
+    // Period covered by a scheduled digest, also the minimum gap between sends
+    pub fn days(self) -> i64 {
+        match self {
+            DigestSchedule::Off | DigestSchedule::Daily => 1,
+            DigestSchedule::Weekly => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub recipients: Vec<String>,
+    // Send-to-Kindle address (...@kindle.com); `from` must be on the account's approved list
+    pub kindle_email: String,
+    pub digest_schedule: DigestSchedule,
+    // Local hour (0-23) after which the scheduled digest goes out
+    pub digest_hour: u32,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        SmtpConfig {
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::Starttls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            recipients: Vec::new(),
+            kindle_email: String::new(),
+            digest_schedule: DigestSchedule::Off,
+            digest_hour: 8,
+        }
+    }
+}
+
+impl SmtpConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = SmtpConfig::default();
+        Ok(SmtpConfig {
+            host: get_setting(conn, "smtp.host", "")?,
+            port: get_setting(conn, "smtp.port", "")?.parse().unwrap_or(defaults.port),
+            security: match get_setting(conn, "smtp.security", "")?.as_str() {
+                "tls" => SmtpSecurity::Tls,
+                "none" => SmtpSecurity::None,
+                _ => SmtpSecurity::Starttls,
+            },
+            username: get_setting(conn, "smtp.username", "")?,
+            password: get_setting(conn, "smtp.password", "")?,
+            from: get_setting(conn, "smtp.from", "")?,
+            recipients: split_list(&get_setting(conn, "smtp.recipients", "")?),
+            kindle_email: get_setting(conn, "smtp.kindle_email", "")?,
+            digest_schedule: match get_setting(conn, "smtp.digest_schedule", "")?.as_str() {
+                "daily" => DigestSchedule::Daily,
+                "weekly" => DigestSchedule::Weekly,
+                _ => DigestSchedule::Off,
+            },
+            digest_hour: get_setting(conn, "smtp.digest_hour", "")?
+                .parse()
+                .unwrap_or(defaults.digest_hour)
+                .min(23),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let security = match self.security {
+            SmtpSecurity::Tls => "tls",
+            SmtpSecurity::Starttls => "starttls",
+            SmtpSecurity::None => "none",
+        };
+        let schedule = match self.digest_schedule {
+            DigestSchedule::Off => "off",
+            DigestSchedule::Daily => "daily",
+            DigestSchedule::Weekly => "weekly",
+        };
+        set_setting(conn, "smtp.host", self.host.trim())?;
+        set_setting(conn, "smtp.port", &self.port.to_string())?;
+        set_setting(conn, "smtp.security", security)?;
+        set_setting(conn, "smtp.username", self.username.trim())?;
+        set_setting(conn, "smtp.password", &self.password)?;
+        set_setting(conn, "smtp.from", self.from.trim())?;
+        set_setting(conn, "smtp.recipients", &split_list(&self.recipients.join(",")).join(","))?;
+        set_setting(conn, "smtp.kindle_email", self.kindle_email.trim())?;
+        set_setting(conn, "smtp.digest_schedule", schedule)?;
+        set_setting(conn, "smtp.digest_hour", &self.digest_hour.min(23).to_string())?;
+        Ok(())
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.host.is_empty() && !self.from.is_empty()
+    }
+}
+
+// Recipients are stored comma-separated; also accepts semicolons/newlines from user input
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split([',', ';', '\n'])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, String> {
+    address
+        .parse::<Mailbox>()
+        .map_err(|e| tr(Msg::InvalidEmailAddress, format!("{}: {}", address, e)))
+}
+
+pub struct FileAttachment {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+pub async fn send_html(config: &SmtpConfig, recipients: &[String], subject: &str, html: String) -> Result<(), String> {
+    send(config, recipients, subject, html, None).await
+}
+
+pub async fn send(
+    config: &SmtpConfig,
+    recipients: &[String],
+    subject: &str,
+    html: String,
+    attachment: Option<FileAttachment>,
+) -> Result<(), String> {
+    if !config.is_configured() {
+        return Err(t(Msg::SmtpNotConfigured));
+    }
+    if recipients.is_empty() {
+        return Err(t(Msg::EmailNoRecipients));
+    }
+
+    let mut builder = Message::builder().from(parse_mailbox(&config.from)?).subject(subject);
+    for recipient in recipients {
+        builder = builder.to(parse_mailbox(recipient)?);
+    }
+    let message = match attachment {
+        Some(file) => {
+            let content_type = ContentType::parse(file.content_type).map_err(|e| tr(Msg::EmailBuildFailed, e))?;
+            builder.multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::html(html))
+                    .singlepart(Attachment::new(file.filename).body(file.data, content_type)),
+            )
+        }
+        None => builder.singlepart(SinglePart::html(html)),
+    }
+    .map_err(|e| tr(Msg::EmailBuildFailed, e))?;
+
+    transport(config)?
+        .send(message)
+        .await
+        .map_err(|e| tr(Msg::EmailSendFailed, e))?;
+    Ok(())
+}
+
+fn transport(config: &SmtpConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let host = config.host.trim();
+    let builder = match config.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(|e| tr(Msg::EmailSendFailed, e))?,
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|e| tr(Msg::EmailSendFailed, e))?
+        }
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    };
+    let mut builder = builder
+        .port(config.port)
+        .timeout(Some(std::time::Duration::from_secs(60)));
+    if !config.username.is_empty() {
+        builder = builder.credentials(Credentials::new(config.username.clone(), config.password.clone()));
+    }
+    Ok(builder.build())
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestEmailResult {
+    pub recipients: usize,
+    pub articles: usize,
+}
+
+// Build the digest for the last `days` days and mail it to the configured recipients
+pub async fn send_digest(db: &Mutex<Connection>, days: i64) -> Result<DigestEmailResult, String> {
+    let (config, digest) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let digest = digest::build(&conn, days, digest::DEFAULT_PER_CATEGORY)?;
+        (SmtpConfig::load(&conn)?, digest::record(&conn, &digest)?)
+    }; // Release the lock while talking to the SMTP server
+
+    send_html(&config, &config.recipients, &digest.title, digest.html).await?;
+    Ok(DigestEmailResult {
+        recipients: config.recipients.len(),
+        articles: digest.article_count,
+    })
+}
+
+// Mail a stored report (e.g. the weekly roundup); returns the recipient count
+pub async fn send_report(db: &Mutex<Connection>, report: &digest::StoredDigest) -> Result<usize, String> {
+    let config = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        SmtpConfig::load(&conn)?
+    };
+    send_html(&config, &config.recipients, &report.title, report.html.clone()).await?;
+    Ok(config.recipients.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trips_with_recipients_normalized() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(!SmtpConfig::load(&conn).unwrap().is_configured());
+
+        let config = SmtpConfig {
+            host: " smtp.example.com ".to_string(),
+            port: 465,
+            security: SmtpSecurity::Tls,
+            from: "news@example.com".to_string(),
+            recipients: vec!["a@example.com; b@example.com".to_string(), " ".to_string(), "c@example.com\n".to_string()],
+            digest_schedule: DigestSchedule::Weekly,
+            digest_hour: 30,
+            ..Default::default()
+        };
+        config.save(&conn).unwrap();
+
+        let loaded = SmtpConfig::load(&conn).unwrap();
+        assert!(loaded.is_configured());
+        assert_eq!(loaded.host, "smtp.example.com");
+        assert_eq!((loaded.port, loaded.security), (465, SmtpSecurity::Tls));
+        assert_eq!(loaded.recipients, ["a@example.com", "b@example.com", "c@example.com"]);
+        assert_eq!((loaded.digest_schedule, loaded.digest_schedule.days()), (DigestSchedule::Weekly, 7));
+        assert_eq!(loaded.digest_hour, 23);
+    }
+
+    #[test]
+    fn mailboxes_must_be_addresses() {
+        assert_eq!(parse_mailbox("News <news@example.com>").unwrap().email.to_string(), "news@example.com");
+        assert!(parse_mailbox("not an address").is_err());
+    }
+}
@@ -0,0 +1,372 @@
+// EPUB 3 export (with an NCX table of contents for older readers).
+//
+// Each article becomes a chapter: the page is re-fetched and its main
+// content extracted (see `readability`), falling back to the stored content
+// when the page can't be fetched or parsed. Images are downloaded into the
+// book up to a byte budget; images past the budget are dropped rather than
+// left as remote links, so the book reads the same offline.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::digest::escape;
+use crate::i18n::{self, tr, Msg};
+use crate::{article_from_row, article_image, http_cache, light_pages, low_data, paywall, readability, Article, HttpClients, ARTICLE_COLUMNS};
+
+const MAX_IMAGES_PER_ARTICLE: usize = 30;
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+const STYLESHEET: &str = "body { font-family: serif; line-height: 1.6; margin: 0 5%; }
+h1 { font-size: 1.5em; line-height: 1.3; margin-bottom: 0.2em; }
+p.meta { font-size: 0.8em; color: #666; margin-top: 0; }
+img { max-width: 100%; height: auto; }
+pre, code { font-family: monospace; font-size: 0.9em; white-space: pre-wrap; }
+blockquote { margin-left: 1em; padding-left: 0.8em; border-left: 3px solid #ccc; color: #444; }
+";
+
+pub struct Chapter {
+    pub title: String,
+    pub meta: String,
+    pub body_html: String,
+}
+
+pub struct BookImage {
+    pub path: String,
+    pub media_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+pub struct Book {
+    pub title: String,
+    pub chapters: Vec<Chapter>,
+    pub images: Vec<BookImage>,
+}
+
+// Bookmarked articles fetched within the last `days` days, oldest first (reading order)
+pub fn recent_bookmarks(conn: &Connection, days: i64) -> Result<Vec<Article>, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let sql = format!(
+        "SELECT {} FROM articles WHERE is_bookmarked = 1 AND fetched_at >= ?1 ORDER BY published_at ASC",
+        ARTICLE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let articles = stmt
+        .query_map(params![since], article_from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(articles)
+}
+
+// Fetch full content and images for every article; never fails on a single article
+pub async fn collect(db: &Mutex<Connection>, http: &HttpClients, title: &str, articles: &[Article], image_budget: usize) -> Result<Book, String> {
+    let client_for = |url: &str| http.for_url(url);
+
+    let mut book = Book {
+        title: title.to_string(),
+        chapters: Vec::new(),
+        images: Vec::new(),
+    };
+    let mut downloaded: HashMap<String, String> = HashMap::new();
+    let mut budget_left = image_budget;
+
+    for article in articles {
+        let readable = match fetch_text(db, http, &article.url).await {
+            Some(html) => readability::extract(&html, &article.url),
+            None => None,
+        };
+        let (mut body, images) = match readable {
+            Some(r) => (r.html, r.images),
+            None => (fallback_body(article), Vec::new()),
+        };
+        if article.image_url.is_empty() {
+            if let Some(image) = article_image::first_large_image(http, &images).await {
+                if let Ok(conn) = db.lock() {
+                    if let Err(e) = article_image::store_if_missing(&conn, &article.id, &image) {
+                        eprintln!("Failed to store image for '{}': {}", article.title, e);
+                    }
+                }
+            }
+        }
+
+        for url in images.into_iter().take(MAX_IMAGES_PER_ARTICLE) {
+            let local = match downloaded.get(&url) {
+                Some(path) => Some(path.clone()),
+                None => match fetch_image(client_for(&url), &url).await {
+                    Some((data, media_type, ext)) if data.len() <= budget_left => {
+                        budget_left -= data.len();
+                        let path = format!("images/img-{}.{}", book.images.len() + 1, ext);
+                        book.images.push(BookImage { path: path.clone(), media_type, data });
+                        downloaded.insert(url.clone(), path.clone());
+                        Some(path)
+                    }
+                    _ => None,
+                },
+            };
+            let src_attr = format!("src=\"{}\"", escape(&url));
+            body = match local {
+                Some(path) => body.replace(&src_attr, &format!("src=\"{}\"", path)),
+                None => remove_img(&body, &src_attr),
+            };
+        }
+
+        let date = article.published_at.get(..10).unwrap_or(&article.published_at);
+        book.chapters.push(Chapter {
+            title: article.title.clone(),
+            meta: format!(
+                "{} · {} · <a href=\"{}\">{}</a>",
+                escape(&article.source),
+                escape(date),
+                escape(&article.url),
+                escape(&article.url)
+            ),
+            body_html: body,
+        });
+    }
+    Ok(book)
+}
+
+// Full page of the stored article at `url`, checked for a paywall on the way;
+// a lighter version of it when it's paywalled or heavy and one is found
+pub(crate) async fn fetch_text(db: &Mutex<Connection>, http: &HttpClients, url: &str) -> Option<String> {
+    if low_data::enabled() {
+        return None;
+    }
+    let request = http.for_url(url).get(url).timeout(std::time::Duration::from_secs(20));
+    let fetched = http_cache::fetch_text(db, url, request, http_cache::PAGE_TTL).await.ok()?;
+    if !fetched.is_success() {
+        return None;
+    }
+    paywall::record(db, url, &fetched.body);
+    if let Some(light) = light_pages::lighter(db, http, url, &fetched.body).await {
+        return Some(light);
+    }
+    Some(fetched.body)
+}
+
+pub(crate) async fn fetch_image(client: &reqwest::Client, url: &str) -> Option<(Vec<u8>, &'static str, &'static str)> {
+    if low_data::enabled() {
+        return None;
+    }
+    let response = client
+        .get(url)
+        .timeout(std::time::Duration::from_secs(20))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    let (media_type, ext) = match content_type.split(';').next().unwrap_or("").trim() {
+        "image/jpeg" | "image/jpg" => ("image/jpeg", "jpg"),
+        "image/png" => ("image/png", "png"),
+        "image/gif" => ("image/gif", "gif"),
+        "image/webp" => ("image/webp", "webp"),
+        "image/svg+xml" => ("image/svg+xml", "svg"),
+        _ => return None,
+    };
+    let data = response.bytes().await.ok()?;
+    (data.len() <= MAX_IMAGE_BYTES).then(|| (data.to_vec(), media_type, ext))
+}
+
+// Remove embedded images from every chapter, used when a book must shrink
+pub fn strip_images(book: &mut Book) {
+    for image in book.images.drain(..) {
+        let src_attr = format!("src=\"{}\"", image.path);
+        for chapter in &mut book.chapters {
+            chapter.body_html = remove_img(&chapter.body_html, &src_attr);
+        }
+    }
+}
+
+// Drop every <img .../> whose src matches; readability emits them as `<img src="..." alt="..."/>`
+pub(crate) fn remove_img(html: &str, src_attr: &str) -> String {
+    let needle = format!("<img {}", src_attr);
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(&needle) {
+        out.push_str(&rest[..start]);
+        rest = match rest[start..].find("/>") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+pub(crate) fn fallback_body(article: &Article) -> String {
+    let text = if article.content.trim().is_empty() { &article.summary } else { &article.content };
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| format!("<p>{}</p>", escape(l)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn build(book: &Book) -> Result<Vec<u8>, String> {
+    let lang = i18n::lang().code();
+    let book_id = format!("urn:uuid:{}", uuid::Uuid::new_v4());
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype entry must come first and be stored uncompressed
+    add(&mut zip, "mimetype", stored, b"application/epub+zip")?;
+    add(
+        &mut zip,
+        "META-INF/container.xml",
+        deflated,
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )?;
+    add(&mut zip, "OEBPS/style.css", deflated, STYLESHEET.as_bytes())?;
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut nav_items = String::new();
+    let mut nav_points = String::new();
+    for (i, chapter) in book.chapters.iter().enumerate() {
+        let n = i + 1;
+        let file = format!("chapter-{}.xhtml", n);
+        let title = escape(&chapter.title);
+        let xhtml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n\
+             <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n\
+             <head><meta charset=\"utf-8\"/><title>{title}</title><link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/></head>\n\
+             <body>\n<h1>{title}</h1>\n<p class=\"meta\">{meta}</p>\n{body}\n</body>\n</html>\n",
+            lang = lang,
+            title = title,
+            meta = chapter.meta,
+            body = chapter.body_html
+        );
+        add(&mut zip, &format!("OEBPS/{}", file), deflated, xhtml.as_bytes())?;
+
+        manifest.push_str(&format!(
+            "    <item id=\"ch{n}\" href=\"{file}\" media-type=\"application/xhtml+xml\"/>\n"
+        ));
+        spine.push_str(&format!("    <itemref idref=\"ch{n}\"/>\n"));
+        nav_items.push_str(&format!("      <li><a href=\"{file}\">{title}</a></li>\n"));
+        nav_points.push_str(&format!(
+            "    <navPoint id=\"np{n}\" playOrder=\"{n}\"><navLabel><text>{title}</text></navLabel><content src=\"{file}\"/></navPoint>\n"
+        ));
+    }
+    for (i, image) in book.images.iter().enumerate() {
+        add(&mut zip, &format!("OEBPS/{}", image.path), stored, &image.data)?;
+        manifest.push_str(&format!(
+            "    <item id=\"img{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            i + 1,
+            image.path,
+            image.media_type
+        ));
+    }
+
+    let title = escape(&book.title);
+    let nav = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n\
+         <head><meta charset=\"utf-8\"/><title>{title}</title><link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/></head>\n\
+         <body>\n  <nav epub:type=\"toc\" id=\"toc\">\n    <h1>{title}</h1>\n    <ol>\n{nav_items}    </ol>\n  </nav>\n</body>\n</html>\n"
+    );
+    add(&mut zip, "OEBPS/nav.xhtml", deflated, nav.as_bytes())?;
+
+    let ncx = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">\n\
+         <head><meta name=\"dtb:uid\" content=\"{book_id}\"/></head>\n\
+         <docTitle><text>{title}</text></docTitle>\n<navMap>\n{nav_points}</navMap>\n</ncx>\n"
+    );
+    add(&mut zip, "OEBPS/toc.ncx", deflated, ncx.as_bytes())?;
+
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"bookid\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+             <dc:identifier id=\"bookid\">{book_id}</dc:identifier>\n\
+             <dc:title>{title}</dc:title>\n\
+             <dc:language>{lang}</dc:language>\n\
+             <dc:creator>AI News Aggregator</dc:creator>\n\
+             <meta property=\"dcterms:modified\">{modified}</meta>\n\
+         </metadata>\n\
+         <manifest>\n\
+             <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+             <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+             <item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>\n\
+         {manifest}</manifest>\n\
+         <spine toc=\"ncx\">\n    <itemref idref=\"nav\"/>\n{spine}</spine>\n\
+         </package>\n"
+    );
+    add(&mut zip, "OEBPS/content.opf", deflated, opf.as_bytes())?;
+
+    let cursor = zip.finish().map_err(|e| tr(Msg::EpubBuildFailed, e))?;
+    Ok(cursor.into_inner())
+}
+
+fn add(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, options: SimpleFileOptions, data: &[u8]) -> Result<(), String> {
+    zip.start_file(name, options).map_err(|e| tr(Msg::EpubBuildFailed, e))?;
+    zip.write_all(data).map_err(|e| tr(Msg::EpubBuildFailed, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn stripping_images_removes_only_embedded_tags() {
+        let mut book = Book {
+            title: "Bookmarks".to_string(),
+            chapters: vec![Chapter {
+                title: "One".to_string(),
+                meta: String::new(),
+                body_html: "<p>a</p><img src=\"images/1.png\" alt=\"x\"/><p>b</p><img src=\"https://example.com/2.png\" alt=\"\"/>".to_string(),
+            }],
+            images: vec![BookImage { path: "images/1.png".to_string(), media_type: "image/png", data: vec![0; 4] }],
+        };
+        strip_images(&mut book);
+        assert!(book.images.is_empty());
+        assert_eq!(book.chapters[0].body_html, "<p>a</p><p>b</p><img src=\"https://example.com/2.png\" alt=\"\"/>");
+        assert_eq!(remove_img("<img src=\"a\" alt=\"unterminated", "src=\"a\""), "");
+    }
+
+    #[test]
+    fn book_is_a_valid_epub_container() {
+        let book = Book {
+            title: "AI & Agents".to_string(),
+            chapters: vec![Chapter { title: "<One>".to_string(), meta: "Blog".to_string(), body_html: "<p>Body</p>".to_string() }],
+            images: vec![BookImage { path: "images/1.png".to_string(), media_type: "image/png", data: vec![1, 2, 3] }],
+        };
+        let bytes = build(&book).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let mimetype = archive.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+        drop(mimetype);
+
+        let mut opf = String::new();
+        archive.by_name("OEBPS/content.opf").unwrap().read_to_string(&mut opf).unwrap();
+        assert!(opf.contains("<dc:title>AI &amp; Agents</dc:title>"), "{}", opf);
+        assert!(opf.contains("href=\"images/1.png\" media-type=\"image/png\""), "{}", opf);
+        let mut chapter = String::new();
+        archive.by_name("OEBPS/chapter-1.xhtml").unwrap().read_to_string(&mut chapter).unwrap();
+        assert!(chapter.contains("<h1>&lt;One&gt;</h1>") && chapter.contains("<p>Body</p>"), "{}", chapter);
+        assert!(archive.by_name("OEBPS/toc.ncx").is_ok() && archive.by_name("OEBPS/nav.xhtml").is_ok());
+    }
+}
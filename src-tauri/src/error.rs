@@ -0,0 +1,117 @@
+// Typed errors for the database and crawler layers.
+//
+// Functions there return `Error`, so callers can tell a failed query from a
+// poisoned lock or a network failure without matching on message text. The
+// localized message comes from `Display`; commands still answer the
+// frontend with a string, which `?` produces through `From<Error> for
+// String`. Modules that report plain messages pass them through as `Other`.
+
+use crate::i18n::{t, tr, Msg};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}", tr(Msg::DbLockPoisoned, .0))]
+    LockPoisoned(String),
+    #[error("{}", tr(Msg::DbPrepareFailed, .0))]
+    Prepare(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::DbQueryFailed, .0))]
+    Query(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::DbCollectFailed, .0))]
+    Collect(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::DbInsertFailed, .0))]
+    Insert(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::DbUpdateFailed, .0))]
+    Update(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::DbDeleteFailed, .0))]
+    Delete(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::FtsInsertFailed, .0))]
+    FtsInsert(#[source] rusqlite::Error),
+    #[error("{}", tr(Msg::HttpClientFailed, .0))]
+    HttpClient(#[source] reqwest::Error),
+    #[error("{}", tr(Msg::HttpRequestFailed, .0))]
+    Request(#[source] reqwest::Error),
+    #[error("{}", tr(Msg::ReadContentFailed, .0))]
+    ReadContent(#[source] reqwest::Error),
+    // Sending the request or reading the body ran out of time
+    #[error("{}", tr(Msg::HttpTimeout, .0))]
+    Timeout(#[source] reqwest::Error),
+    #[error("{}", tr(Msg::InvalidSelector, .0))]
+    InvalidSelector(String),
+    #[error("{}", t(Msg::HomeDirUnknown))]
+    HomeDirUnknown,
+    #[error("{}", tr(Msg::CreateDirFailed, format!("{}: {}", .path, .source)))]
+    CreateDir { path: String, source: std::io::Error },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl Error {
+    // The SQLite error behind a database failure
+    pub fn sqlite(&self) -> Option<&rusqlite::Error> {
+        match self {
+            Error::Prepare(e)
+            | Error::Query(e)
+            | Error::Collect(e)
+            | Error::Insert(e)
+            | Error::Update(e)
+            | Error::Delete(e)
+            | Error::FtsInsert(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    // A request that ran out of time, which may well go through on another try
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Error::Timeout(_))
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Other(message)
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for Error {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        Error::LockPoisoned(e.to_string())
+    }
+}
+
+impl From<Error> for String {
+    fn from(e: Error) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_errors_expose_their_sqlite_cause() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let e = Error::Query(conn.execute("SELECT * FROM missing", []).unwrap_err());
+        assert!(matches!(e.sqlite(), Some(rusqlite::Error::SqliteFailure(..))));
+        assert!(!e.is_timeout());
+        assert!(e.to_string().contains("missing"), "{}", e);
+
+        let e = Error::from("no feed found".to_string());
+        assert!(e.sqlite().is_none());
+        assert_eq!(String::from(e), "no feed found");
+    }
+
+    #[test]
+    fn a_poisoned_lock_becomes_lock_poisoned() {
+        let lock = std::sync::Arc::new(std::sync::Mutex::new(()));
+        let poisoner = lock.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+        let e: Error = lock.lock().unwrap_err().into();
+        assert!(matches!(e, Error::LockPoisoned(_)));
+        assert!(e.sqlite().is_none());
+    }
+}
@@ -0,0 +1,267 @@
+// Extractive summaries, for when no AI is configured.
+//
+// The text is split into sentences (at 。！？； in Chinese, at . ! ? followed
+// by a space elsewhere) and each becomes a TF-IDF vector over its terms:
+// lowercased words outside CJK, character bigrams inside, as the search
+// index segments them. TextRank then scores every sentence by how similar
+// it is to the rest of the text, and the best ones, in their original
+// order, fill the summary up to a length budget that depends on the
+// language, and three sentences at most. Texts too short to pick from get
+// `None`, and the caller falls back to the template brief.
+
+use std::collections::HashMap;
+
+use crate::keywords::STOP_WORDS;
+use crate::search_index::is_cjk;
+
+// Summary length, in characters (the AI prompt asks for 100 Chinese characters)
+const MAX_CHARS_CJK: usize = 100;
+const MAX_CHARS_OTHER: usize = 280;
+const MAX_SUMMARY_SENTENCES: usize = 3;
+// Shorter fragments (bylines, "Read more") aren't worth a place in the summary
+const MIN_SENTENCE_CHARS_CJK: usize = 8;
+const MIN_SENTENCE_CHARS_OTHER: usize = 25;
+// TextRank is quadratic in sentences; later text rarely holds the gist anyway
+const MAX_INPUT_CHARS: usize = 6000;
+const MAX_SENTENCES: usize = 60;
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Cjk,
+    Other,
+}
+
+fn detect_script(text: &str) -> Script {
+    let (cjk, letters) = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .fold((0usize, 0usize), |(cjk, letters), c| (cjk + is_cjk(c) as usize, letters + 1));
+    // Chinese articles quote plenty of English names, so a minority of CJK is enough
+    if letters > 0 && cjk * 10 >= letters * 3 {
+        Script::Cjk
+    } else {
+        Script::Other
+    }
+}
+
+// Feed descriptions often carry markup
+pub(crate) fn plain_text(text: &str) -> String {
+    let text = if text.contains('<') {
+        scraper::Html::parse_fragment(text).root_element().text().collect::<Vec<_>>().join(" ")
+    } else {
+        text.to_string()
+    };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn split_sentences(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        current.push(c);
+        let ends = match c {
+            '。' | '！' | '？' | '；' => true,
+            // Only before whitespace (not in "3.5"), and not after an initial ("J. Smith")
+            '.' | '!' | '?' => {
+                let next_is_space = chars.get(i + 1).is_none_or(|n| n.is_whitespace());
+                let initial = c == '.'
+                    && i >= 1
+                    && chars[i - 1].is_uppercase()
+                    && chars.get(i.wrapping_sub(2)).is_none_or(|p| !p.is_alphanumeric());
+                next_is_space && !initial
+            }
+            _ => false,
+        };
+        if ends {
+            let sentence = current.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            current.clear();
+        }
+    }
+    let rest = current.trim();
+    if !rest.is_empty() {
+        sentences.push(rest.to_string());
+    }
+    sentences
+}
+
+// Words outside CJK (minus stop words), overlapping bigrams inside
+fn terms(sentence: &str) -> Vec<String> {
+    let chars: Vec<char> = sentence.chars().collect();
+    let mut terms = Vec::new();
+    for run in chars.chunk_by(|a, b| is_cjk(*a) == is_cjk(*b)) {
+        if is_cjk(run[0]) {
+            if run.len() == 1 {
+                terms.push(run[0].to_string());
+            }
+            terms.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+        } else {
+            let text: String = run.iter().collect::<String>().to_lowercase();
+            terms.extend(
+                text.split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| w.chars().count() >= 2 && !STOP_WORDS.contains(w))
+                    .map(str::to_string),
+            );
+        }
+    }
+    terms
+}
+
+// TF-IDF vectors, with sentences as the documents
+fn vectors(sentences: &[Vec<String>]) -> Vec<HashMap<&str, f64>> {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for terms in sentences {
+        let mut seen: Vec<&str> = terms.iter().map(String::as_str).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        for term in seen {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    let n = sentences.len() as f64;
+    sentences
+        .iter()
+        .map(|terms| {
+            let mut vector: HashMap<&str, f64> = HashMap::new();
+            for term in terms {
+                *vector.entry(term.as_str()).or_default() += 1.0;
+            }
+            for (term, weight) in vector.iter_mut() {
+                *weight *= (1.0 + n / document_frequency[term] as f64).ln();
+            }
+            vector
+        })
+        .collect()
+}
+
+fn cosine(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(term, x)| b.get(term).map(|y| x * y)).sum();
+    let norm = |v: &HashMap<&str, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 { 0.0 } else { dot / denominator }
+}
+
+// PageRank over the sentence similarity graph
+fn text_rank(vectors: &[HashMap<&str, f64>]) -> Vec<f64> {
+    let n = vectors.len();
+    let mut weights = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let similarity = cosine(&vectors[i], &vectors[j]);
+            weights[i][j] = similarity;
+            weights[j][i] = similarity;
+        }
+    }
+    let out_weight: Vec<f64> = weights.iter().map(|row| row.iter().sum()).collect();
+
+    let mut scores = vec![1.0; n];
+    for _ in 0..ITERATIONS {
+        scores = (0..n)
+            .map(|i| {
+                let incoming: f64 = (0..n)
+                    .filter(|&j| out_weight[j] > 0.0)
+                    .map(|j| weights[j][i] / out_weight[j] * scores[j])
+                    .sum();
+                (1.0 - DAMPING) + DAMPING * incoming
+            })
+            .collect();
+    }
+    scores
+}
+
+// The most central sentences of `text` in reading order, or None when it's too short to summarize
+pub fn summarize(text: &str) -> Option<String> {
+    let text: String = plain_text(text).chars().take(MAX_INPUT_CHARS).collect();
+    let script = detect_script(&text);
+    let (max_chars, min_sentence_chars) = match script {
+        Script::Cjk => (MAX_CHARS_CJK, MIN_SENTENCE_CHARS_CJK),
+        Script::Other => (MAX_CHARS_OTHER, MIN_SENTENCE_CHARS_OTHER),
+    };
+
+    let sentences: Vec<String> = split_sentences(&text)
+        .into_iter()
+        .filter(|s| s.chars().count() >= min_sentence_chars)
+        .take(MAX_SENTENCES)
+        .collect();
+    if sentences.is_empty() {
+        return None;
+    }
+    let terms: Vec<Vec<String>> = sentences.iter().map(|s| terms(s)).collect();
+    let scores = text_rank(&vectors(&terms));
+
+    // Best first; ties go to the earlier sentence, since leads carry the news
+    let mut ranked: Vec<usize> = (0..sentences.len()).collect();
+    ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+
+    let mut chosen = Vec::new();
+    let mut length = 0;
+    for index in ranked {
+        let sentence_chars = sentences[index].chars().count();
+        if length + sentence_chars > max_chars && !chosen.is_empty() {
+            continue;
+        }
+        chosen.push(index);
+        length += sentence_chars;
+        if length >= max_chars || chosen.len() == MAX_SUMMARY_SENTENCES {
+            break;
+        }
+    }
+    chosen.sort_unstable();
+
+    let separator = if script == Script::Cjk { "" } else { " " };
+    let summary = chosen.iter().map(|&i| sentences[i].as_str()).collect::<Vec<_>>().join(separator);
+    Some(truncate(&summary, max_chars))
+}
+
+// A single long sentence is cut at the budget
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentences_split_at_terminators_but_not_decimals_or_initials() {
+        assert_eq!(
+            split_sentences("GPT-3.5 was compared by J. Smith. It lost! Why? 模型发布了。效果很好；"),
+            ["GPT-3.5 was compared by J. Smith.", "It lost!", "Why?", "模型发布了。", "效果很好；"]
+        );
+        assert_eq!(detect_script("OpenAI 发布了新的推理模型"), Script::Cjk);
+        assert_eq!(detect_script("OpenAI released a reasoning model (推理)"), Script::Other);
+        assert_eq!(plain_text("<p>Hello <b>world</b></p>\n  again"), "Hello world again");
+    }
+
+    #[test]
+    fn summary_keeps_the_central_sentences_in_order() {
+        let text = "The new inference engine serves language models twice as fast on the same hardware. \
+            Our cat, Biscuit, prefers the sunny spot near the kitchen window. \
+            Benchmarks show the inference engine keeps latency low while serving language models at scale. \
+            Engineers built the engine around paged attention to serve models with long contexts. \
+            Tickets for the summer picnic go on sale next Tuesday.";
+        let summary = summarize(text).unwrap();
+        assert!(summary.starts_with("The new inference engine"), "{}", summary);
+        assert!(!summary.contains("Biscuit") && !summary.contains("picnic"), "{}", summary);
+        assert!(summary.chars().count() <= MAX_CHARS_OTHER);
+    }
+
+    #[test]
+    fn short_or_empty_text_has_no_summary_and_long_text_is_cut() {
+        assert_eq!(summarize(""), None);
+        assert_eq!(summarize("Read more"), None);
+        let long = format!("{}。", "大模型推理引擎在同样的硬件上把服务速度提高了一倍".repeat(10));
+        let summary = summarize(&long).unwrap();
+        assert_eq!(summary.chars().count(), MAX_CHARS_CJK + 1);
+        assert!(summary.ends_with('…'));
+    }
+}
@@ -0,0 +1,115 @@
+// Feature flags, so subsystems can ship switched off and be toggled per user
+// without a separate build.
+//
+// Every flag is declared in FLAGS with its default and stored as a
+// `feature.<name>` setting once it has been set. The active values live in
+// memory, reloaded on change and profile switch like `ai_limits`, so
+// subsystems check `is_enabled` without taking the database lock.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::i18n::{tr, Msg};
+use crate::{get_setting, set_setting};
+
+// The token-protected HTTP API (`local_api`), on top of its own enabled setting
+pub const LOCAL_API: &str = "local_api";
+// Background crawls, cleanups and rescoring (`scheduler`)
+pub const SCHEDULER: &str = "scheduler";
+// Embedding-based search; reserved, nothing checks it yet
+pub const SEMANTIC_SEARCH: &str = "semantic_search";
+// AI summaries of HN / Reddit threads after each crawl (`comment_highlights`)
+pub const COMMENT_HIGHLIGHTS: &str = "comment_highlights";
+
+// (name, enabled by default)
+const FLAGS: &[(&str, bool)] =
+    &[(LOCAL_API, true), (SCHEDULER, true), (SEMANTIC_SEARCH, false), (COMMENT_HIGHLIGHTS, false)];
+
+static ACTIVE: Mutex<Option<HashMap<&'static str, bool>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub enabled: bool,
+    pub default_enabled: bool,
+}
+
+fn setting_key(name: &str) -> String {
+    format!("feature.{}", name)
+}
+
+// Every flag with its stored value, else its default
+pub fn load(conn: &Connection) -> Result<Vec<FeatureFlag>, String> {
+    FLAGS
+        .iter()
+        .map(|&(name, default_enabled)| {
+            let stored = get_setting(conn, &setting_key(name), "")?;
+            Ok(FeatureFlag { name: name.to_string(), enabled: stored.parse().unwrap_or(default_enabled), default_enabled })
+        })
+        .collect()
+}
+
+pub fn save(conn: &Connection, name: &str, enabled: bool) -> Result<(), String> {
+    if !FLAGS.iter().any(|&(flag, _)| flag == name) {
+        return Err(tr(Msg::FeatureUnknown, name));
+    }
+    Ok(set_setting(conn, &setting_key(name), &enabled.to_string())?)
+}
+
+// Make `flags` the active values
+pub fn set(flags: &[FeatureFlag]) {
+    let active = FLAGS
+        .iter()
+        .map(|&(name, default_enabled)| {
+            let enabled = flags.iter().find(|f| f.name == name).map_or(default_enabled, |f| f.enabled);
+            (name, enabled)
+        })
+        .collect();
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = Some(active);
+}
+
+// Defaults apply until `set` has run; unknown names are off
+pub fn is_enabled(name: &str) -> bool {
+    if let Some(enabled) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|a| a.get(name)) {
+        return *enabled;
+    }
+    FLAGS.iter().find(|&&(flag, _)| flag == name).is_some_and(|&(_, default_enabled)| default_enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_values_override_defaults() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let enabled = |flags: &[FeatureFlag], name: &str| flags.iter().find(|f| f.name == name).unwrap().enabled;
+        let flags = load(&conn).unwrap();
+        assert_eq!(flags.len(), FLAGS.len());
+        assert!(enabled(&flags, SCHEDULER));
+        assert!(!enabled(&flags, SEMANTIC_SEARCH));
+
+        save(&conn, SCHEDULER, false).unwrap();
+        set_setting(&conn, &setting_key(COMMENT_HIGHLIGHTS), "garbled").unwrap();
+        let flags = load(&conn).unwrap();
+        assert!(!enabled(&flags, SCHEDULER));
+        assert!(!enabled(&flags, COMMENT_HIGHLIGHTS), "unparsable value falls back to the default");
+        assert!(flags.iter().all(|f| f.default_enabled == FLAGS.iter().any(|&(n, d)| n == f.name && d)));
+
+        assert!(save(&conn, "time_travel", true).is_err());
+        assert_eq!(get_setting(&conn, "feature.time_travel", "unset").unwrap(), "unset");
+    }
+
+    #[test]
+    fn active_values_fill_in_defaults() {
+        // Only the flag nothing checks yet differs from its default
+        set(&[FeatureFlag { name: SEMANTIC_SEARCH.into(), enabled: true, default_enabled: false }]);
+        assert!(is_enabled(SEMANTIC_SEARCH));
+        assert!(is_enabled(LOCAL_API));
+        assert!(!is_enabled(COMMENT_HIGHLIGHTS));
+        assert!(!is_enabled("time_travel"));
+    }
+}
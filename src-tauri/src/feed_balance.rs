@@ -0,0 +1,218 @@
+// Category balance for the default (unfiltered) feed.
+//
+// A burst of GitHub trending items can fill whole pages. With balancing on,
+// each page of the "all categories" view holds at most `max_percent` of its
+// slots per limited category; items over the cap move to the next page that
+// has room, so nothing is dropped and paging stays stable. Once the other
+// categories run out, the remaining items fill the last pages uncapped.
+// Category, bookmark and folder views are never balanced.
+
+use std::collections::{HashMap, VecDeque};
+
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, Msg};
+use crate::{article_from_row, get_setting, set_setting, Article, ListQuery, ARTICLE_COLUMNS};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryLimit {
+    pub category: String,
+    // Share of a page's slots, 1-100
+    pub max_percent: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedBalance {
+    pub enabled: bool,
+    pub limits: Vec<CategoryLimit>,
+}
+
+impl Default for FeedBalance {
+    fn default() -> Self {
+        FeedBalance {
+            enabled: true,
+            limits: vec![CategoryLimit { category: "GitHub".to_string(), max_percent: 30 }],
+        }
+    }
+}
+
+impl FeedBalance {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = FeedBalance::default();
+        let limits = get_setting(conn, "feed_balance.limits", "")?;
+        Ok(FeedBalance {
+            enabled: get_setting(conn, "feed_balance.enabled", "true")? == "true",
+            limits: if limits.is_empty() { defaults.limits } else { serde_json::from_str(&limits).unwrap_or_default() },
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let limits: Vec<CategoryLimit> = self
+            .limits
+            .iter()
+            .map(|l| CategoryLimit { category: l.category.trim().to_string(), max_percent: l.max_percent.min(100) })
+            .filter(|l| !l.category.is_empty() && l.max_percent > 0 && l.max_percent < 100)
+            .collect();
+        set_setting(conn, "feed_balance.enabled", if self.enabled { "true" } else { "false" })?;
+        set_setting(conn, "feed_balance.limits", &serde_json::to_string(&limits).unwrap_or_else(|_| "[]".to_string()))?;
+        Ok(())
+    }
+
+    // Only the default view is balanced
+    pub fn applies(&self, query: &ListQuery) -> bool {
+        self.enabled
+            && !self.limits.is_empty()
+            && query.category.as_deref().is_none_or(|c| c == "all")
+            && !query.bookmarked_only.unwrap_or(false)
+            && query.folder_id.is_none()
+            && query.tag.is_none()
+    }
+
+    // Slots per page for each limited category (at least one)
+    fn caps(&self, page_size: usize) -> HashMap<&str, usize> {
+        self.limits
+            .iter()
+            .map(|l| (l.category.as_str(), (page_size * l.max_percent as usize / 100).max(1)))
+            .collect()
+    }
+
+    // Rowids on `page`, given every (rowid, category) of the filtered list in feed order
+    fn page_rowids<'a>(&self, rows: &'a [(i64, String)], page: usize, page_size: usize) -> Vec<i64> {
+        let caps = self.caps(page_size);
+        let mut stream = rows.iter().enumerate();
+        // Feed positions of items pushed past a full page
+        let mut deferred: VecDeque<usize> = VecDeque::new();
+
+        for current in 1..=page {
+            let mut slots: Vec<usize> = Vec::with_capacity(page_size);
+            let mut counts: HashMap<&'a str, usize> = HashMap::new();
+            let fits = |pos: usize, counts: &mut HashMap<&'a str, usize>| {
+                let category = rows[pos].1.as_str();
+                match caps.get(category) {
+                    Some(cap) if counts.get(category).copied().unwrap_or(0) >= *cap => false,
+                    _ => {
+                        *counts.entry(category).or_insert(0) += 1;
+                        true
+                    }
+                }
+            };
+
+            let mut waiting = VecDeque::new();
+            while let Some(pos) = deferred.pop_front() {
+                if slots.len() < page_size && fits(pos, &mut counts) {
+                    slots.push(pos);
+                } else {
+                    waiting.push_back(pos);
+                }
+            }
+            deferred = waiting;
+            while slots.len() < page_size {
+                let Some((pos, _)) = stream.next() else { break };
+                if fits(pos, &mut counts) {
+                    slots.push(pos);
+                } else {
+                    deferred.push_back(pos);
+                }
+            }
+            // Nothing left to mix in: fill up with what's over the cap
+            while slots.len() < page_size {
+                let Some(pos) = deferred.pop_front() else { break };
+                slots.push(pos);
+            }
+
+            if current == page {
+                slots.sort_unstable();
+                return slots.into_iter().map(|pos| rows[pos].0).collect();
+            }
+        }
+        Vec::new()
+    }
+
+    // One balanced page of the list filtered by `where_clause`
+    pub fn query_page(
+        &self,
+        conn: &Connection,
+        where_clause: &str,
+        params_vec: &[String],
+        page: usize,
+        page_size: usize,
+    ) -> Result<Vec<Article>, String> {
+        let sql = format!(
+            "SELECT rowid, category FROM articles{} ORDER BY published_at DESC, fetched_at DESC",
+            where_clause
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+        let rows = stmt
+            .query_map(params_from_iter(params_vec.iter()), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+        let sql = format!("SELECT {} FROM articles WHERE rowid = ?1", ARTICLE_COLUMNS);
+        let mut article_stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+        self.page_rowids(&rows, page, page_size)
+            .into_iter()
+            .map(|rowid| article_stmt.query_row(params![rowid], article_from_row).map_err(|e| tr(Msg::DbQueryFailed, e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(categories: &[&str]) -> Vec<(i64, String)> {
+        categories.iter().enumerate().map(|(i, c)| (i as i64, c.to_string())).collect()
+    }
+
+    fn query(json: serde_json::Value) -> ListQuery {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn capped_items_move_to_later_pages_without_being_dropped() {
+        let balance = FeedBalance { enabled: true, limits: vec![CategoryLimit { category: "GitHub".into(), max_percent: 50 }] };
+        let rows = rows(&["GitHub", "GitHub", "GitHub", "GitHub", "AI", "AI", "Tech"]);
+        assert_eq!(balance.page_rowids(&rows, 1, 4), [0, 1, 4, 5]);
+        assert_eq!(balance.page_rowids(&rows, 2, 4), [2, 3, 6], "the rest fill the last page uncapped");
+        assert!(balance.page_rowids(&rows, 3, 4).is_empty());
+
+        let mut seen: Vec<i64> = (1..=2).flat_map(|page| balance.page_rowids(&rows, page, 4)).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn only_the_default_view_is_balanced() {
+        let balance = FeedBalance::default();
+        assert!(balance.applies(&query(serde_json::json!({ "page_size": 20 }))));
+        assert!(balance.applies(&query(serde_json::json!({ "page_size": 20, "category": "all" }))));
+        assert!(!balance.applies(&query(serde_json::json!({ "page_size": 20, "category": "AI" }))));
+        assert!(!balance.applies(&query(serde_json::json!({ "page_size": 20, "bookmarked_only": true }))));
+        assert!(!balance.applies(&query(serde_json::json!({ "page_size": 20, "tag": "agents" }))));
+        let disabled = FeedBalance { enabled: false, ..FeedBalance::default() };
+        assert!(!disabled.applies(&query(serde_json::json!({ "page_size": 20 }))));
+    }
+
+    #[test]
+    fn saving_drops_limits_that_cap_nothing() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(FeedBalance::load(&conn).unwrap().limits[0].category, "GitHub");
+        FeedBalance {
+            enabled: true,
+            limits: vec![
+                CategoryLimit { category: " Papers ".into(), max_percent: 20 },
+                CategoryLimit { category: "GitHub".into(), max_percent: 150 },
+                CategoryLimit { category: "Tech".into(), max_percent: 0 },
+                CategoryLimit { category: " ".into(), max_percent: 10 },
+            ],
+        }
+        .save(&conn)
+        .unwrap();
+        let limits = FeedBalance::load(&conn).unwrap().limits;
+        assert_eq!(limits.len(), 1);
+        assert_eq!((limits[0].category.as_str(), limits[0].max_percent), ("Papers", 20));
+    }
+}
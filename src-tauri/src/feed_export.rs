@@ -0,0 +1,131 @@
+// Bookmarked articles published as an RSS 2.0 feed, written to a file
+// (for other readers or a static site) or served by the local API.
+
+use rusqlite::Connection;
+
+use crate::i18n::{tr, Msg};
+use crate::{article_from_row, Article, ARTICLE_COLUMNS};
+
+// Feed readers rarely need more than this; keeps the file small
+const MAX_FEED_ITEMS: usize = 200;
+
+pub fn bookmarks_feed_xml(conn: &Connection) -> Result<String, String> {
+    let sql = format!(
+        "SELECT {} FROM articles WHERE is_bookmarked = 1 ORDER BY published_at DESC, fetched_at DESC LIMIT ?1",
+        ARTICLE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let articles: Vec<Article> = stmt
+        .query_map([MAX_FEED_ITEMS as i64], article_from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    let items: Vec<rss::Item> = articles.iter().map(article_to_item).collect();
+
+    let mut channel = rss::Channel::default();
+    channel.set_title("AI News Aggregator - Bookmarks");
+    channel.set_link("https://github.com/kingxsxxx/AI-news-crawler");
+    channel.set_description("Articles bookmarked in AI News Aggregator");
+    channel.set_generator("ai-news-aggregator".to_string());
+    channel.set_last_build_date(chrono::Utc::now().to_rfc2822());
+    channel.set_items(items);
+
+    let bytes = channel
+        .write_to(Vec::new())
+        .map_err(|e| tr(Msg::FeedRenderFailed, e))?;
+    String::from_utf8(bytes).map_err(|e| tr(Msg::FeedRenderFailed, e))
+}
+
+fn article_to_item(article: &Article) -> rss::Item {
+    let mut item = rss::Item::default();
+    item.set_title(article.title.clone());
+    item.set_link(article.url.clone());
+    item.set_description(article.summary.clone());
+    item.set_guid(rss::Guid {
+        value: article.url.clone(),
+        permalink: true,
+    });
+    item.set_source(rss::Source {
+        url: article.url.clone(),
+        title: Some(article.source.clone()),
+    });
+    item.set_categories(vec![rss::Category {
+        name: article.category.clone(),
+        domain: None,
+    }]);
+
+    // Stored dates are RFC 3339; RSS requires RFC 2822
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&article.published_at) {
+        item.set_pub_date(dt.to_rfc2822());
+    }
+    if !article.image_url.is_empty() {
+        item.set_enclosure(rss::Enclosure {
+            url: article.image_url.clone(),
+            length: "0".to_string(),
+            mime_type: guess_image_mime(&article.image_url).to_string(),
+        });
+    }
+    item
+}
+
+fn guess_image_mime(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    if path.ends_with(".png") {
+        "image/png"
+    } else if path.ends_with(".gif") {
+        "image/gif"
+    } else if path.ends_with(".webp") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+pub fn write_bookmarks_feed(conn: &Connection, path: &str) -> Result<(), String> {
+    let xml = bookmarks_feed_xml(conn)?;
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    std::fs::write(path, xml).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn add_article(conn: &Connection, id: &str, bookmarked: bool, image_url: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at, is_bookmarked, image_url)
+             VALUES (?1, ?1, 'Summary & more', '', ?2, 'Blog', 'AI', '2024-01-05T10:30:00+08:00', '2024-01-05T10:30:00+08:00', ?3, ?4)",
+            params![id, format!("https://example.com/{}", id), bookmarked, image_url],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn feed_lists_only_bookmarks_as_rss_items() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "kept", true, "https://example.com/chart.PNG?w=640");
+        add_article(&conn, "skipped", false, "");
+
+        let channel = rss::Channel::read_from(bookmarks_feed_xml(&conn).unwrap().as_bytes()).unwrap();
+        assert_eq!(channel.items().len(), 1);
+        let item = &channel.items()[0];
+        assert_eq!(item.link(), Some("https://example.com/kept"));
+        assert_eq!(item.description(), Some("Summary & more"));
+        let published = chrono::DateTime::parse_from_rfc2822(item.pub_date().unwrap()).unwrap();
+        assert_eq!(published.to_utc().to_rfc3339(), "2024-01-05T02:30:00+00:00");
+        assert_eq!(item.categories()[0].name(), "AI");
+        assert_eq!(item.enclosure().map(|e| e.mime_type()), Some("image/png"));
+    }
+
+    #[test]
+    fn image_types_default_to_jpeg() {
+        assert_eq!(guess_image_mime("https://example.com/a.webp#top"), "image/webp");
+        assert_eq!(guess_image_mime("https://example.com/a.gif"), "image/gif");
+        assert_eq!(guess_image_mime("https://example.com/image?id=3"), "image/jpeg");
+    }
+}
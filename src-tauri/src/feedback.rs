@@ -0,0 +1,195 @@
+// "More like this" / "less like this" feedback, learned into heat weights.
+//
+// Each vote nudges a weight for the article's source and for every keyword
+// of its title (`heat_weights`); the heat score's feedback component is the
+// sum of the weights an article hits. Only the latest vote per article
+// counts: changing or clearing it undoes the previous nudge first, so
+// clicking repeatedly can't run a weight away.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::roundup::keywords;
+
+const SOURCE_STEP: f64 = 2.0;
+const KEYWORD_STEP: f64 = 1.0;
+// Bound on any single learned weight
+const WEIGHT_LIMIT: f64 = 15.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Signal {
+    More,
+    Less,
+    Clear,
+}
+
+impl Signal {
+    fn value(self) -> i32 {
+        match self {
+            Signal::More => 1,
+            Signal::Less => -1,
+            Signal::Clear => 0,
+        }
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_feedback (
+            article_id TEXT PRIMARY KEY,
+            signal INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS heat_weights (
+            kind TEXT NOT NULL,
+            key TEXT NOT NULL,
+            weight REAL NOT NULL,
+            PRIMARY KEY (kind, key)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Learned weights by source name and by title keyword
+#[derive(Debug, Default, Serialize)]
+pub struct LearnedWeights {
+    pub sources: HashMap<String, f64>,
+    pub keywords: HashMap<String, f64>,
+}
+
+impl LearnedWeights {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let mut stmt = conn
+            .prepare("SELECT kind, key, weight FROM heat_weights")
+            .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?)))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+        let mut weights = LearnedWeights::default();
+        for (kind, key, weight) in rows {
+            match kind.as_str() {
+                "source" => weights.sources.insert(key, weight),
+                _ => weights.keywords.insert(key, weight),
+            };
+        }
+        Ok(weights)
+    }
+
+    // Feedback component for an article
+    pub fn score(&self, source: &str, title: &str) -> f64 {
+        let source = self.sources.get(source).copied().unwrap_or(0.0);
+        let keywords: f64 = keywords(title).iter().filter_map(|k| self.keywords.get(k)).sum();
+        source + keywords
+    }
+}
+
+fn adjust(conn: &Connection, kind: &str, key: &str, delta: f64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO heat_weights (kind, key, weight) VALUES (?1, ?2, MAX(-?4, MIN(?4, ?3)))
+         ON CONFLICT(kind, key) DO UPDATE SET weight = MAX(-?4, MIN(?4, weight + ?3))",
+        params![kind, key, delta, WEIGHT_LIMIT],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Record the vote on an article and move the weights by the change from its previous vote
+pub fn record(conn: &Connection, article_id: &str, signal: Signal) -> Result<(), String> {
+    let (source, title): (String, String) = conn
+        .query_row("SELECT source, title FROM articles WHERE id = ?1", params![article_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .ok_or_else(|| t(Msg::ArticleNotFound))?;
+    let previous: i32 = conn
+        .query_row("SELECT signal FROM article_feedback WHERE article_id = ?1", params![article_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .unwrap_or(0);
+
+    let change = (signal.value() - previous) as f64;
+    if change == 0.0 {
+        return Ok(());
+    }
+    adjust(conn, "source", &source, change * SOURCE_STEP)?;
+    for keyword in keywords(&title) {
+        adjust(conn, "keyword", &keyword, change * KEYWORD_STEP)?;
+    }
+    conn.execute("DELETE FROM heat_weights WHERE weight = 0", [])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+
+    match signal {
+        Signal::Clear => conn.execute("DELETE FROM article_feedback WHERE article_id = ?1", params![article_id]),
+        _ => conn.execute(
+            "INSERT OR REPLACE INTO article_feedback (article_id, signal, created_at) VALUES (?1, ?2, ?3)",
+            params![article_id, signal.value(), chrono::Utc::now().to_rfc3339()],
+        ),
+    }
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Forget all votes and learned weights
+pub fn reset(conn: &Connection) -> Result<(), String> {
+    conn.execute("DELETE FROM article_feedback", []).map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    conn.execute("DELETE FROM heat_weights", []).map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str, title: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source) VALUES (?1, ?2, ?1, 'Blog')",
+            params![id, title],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn only_the_latest_vote_counts() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "a1", "Agents benchmark");
+        record(&conn, "a1", Signal::More).unwrap();
+        record(&conn, "a1", Signal::More).unwrap();
+        let weights = LearnedWeights::load(&conn).unwrap();
+        assert_eq!(weights.sources["Blog"], SOURCE_STEP, "repeating a vote changes nothing");
+        assert_eq!(weights.score("Blog", "New agents paper"), SOURCE_STEP + KEYWORD_STEP);
+
+        record(&conn, "a1", Signal::Less).unwrap();
+        let weights = LearnedWeights::load(&conn).unwrap();
+        assert_eq!(weights.sources["Blog"], -SOURCE_STEP);
+        assert_eq!(weights.keywords["benchmark"], -KEYWORD_STEP);
+
+        record(&conn, "a1", Signal::Clear).unwrap();
+        let weights = LearnedWeights::load(&conn).unwrap();
+        assert!(weights.sources.is_empty() && weights.keywords.is_empty(), "zero weights are removed");
+        assert!(record(&conn, "missing", Signal::More).is_err());
+    }
+
+    #[test]
+    fn weights_are_bounded_and_reset_forgets_them() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for i in 0..10 {
+            let id = format!("a{}", i);
+            add_article(&conn, &id, "Agents");
+            record(&conn, &id, Signal::More).unwrap();
+        }
+        assert_eq!(LearnedWeights::load(&conn).unwrap().sources["Blog"], WEIGHT_LIMIT);
+        reset(&conn).unwrap();
+        assert_eq!(LearnedWeights::load(&conn).unwrap().score("Blog", "Agents"), 0.0);
+    }
+}
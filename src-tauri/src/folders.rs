@@ -0,0 +1,205 @@
+// Folders for organizing sources (e.g. International AI / 中文媒体 / GitHub / Papers).
+//
+// Folders nest through `parent_id`; a source belongs to at most one folder
+// via `sources.folder_id`. Filtering by a folder includes its subfolders.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS folders (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            parent_id TEXT,
+            position INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    // None = top level
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    // Sort order among siblings
+    #[serde(default)]
+    pub position: i32,
+    // Names of the sources directly in this folder (filled by `list`, ignored on save)
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Folder>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT f.id, f.name, f.parent_id, f.position,
+                    COALESCE((SELECT json_group_array(s.name) FROM sources s WHERE s.folder_id = f.id), '[]')
+             FROM folders f ORDER BY f.position ASC, f.name ASC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let folders = stmt
+        .query_map([], |row| {
+            let sources: String = row.get(4)?;
+            Ok(Folder {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                position: row.get(3)?,
+                sources: serde_json::from_str(&sources).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(folders)
+}
+
+// Insert when `id` is empty, otherwise update the existing folder
+pub fn save(conn: &Connection, mut folder: Folder) -> Result<Folder, String> {
+    if folder.id.is_empty() {
+        folder.id = uuid::Uuid::new_v4().to_string();
+    }
+    folder.name = folder.name.trim().to_string();
+    folder.parent_id = folder.parent_id.filter(|p| !p.is_empty());
+
+    // Walk up from the new parent; reaching this folder would create a cycle
+    let mut ancestor = folder.parent_id.clone();
+    while let Some(id) = ancestor {
+        if id == folder.id {
+            return Err(t(Msg::FolderCycle));
+        }
+        ancestor = conn
+            .query_row("SELECT parent_id FROM folders WHERE id = ?1", params![id], |row| row.get::<_, Option<String>>(0))
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .ok_or_else(|| t(Msg::FolderNotFound))?;
+    }
+
+    conn.execute(
+        "INSERT INTO folders (id, name, parent_id, position) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, parent_id = excluded.parent_id, position = excluded.position",
+        params![folder.id, folder.name, folder.parent_id, folder.position],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(folder)
+}
+
+// Subfolders move up to the deleted folder's parent; its sources become unfiled
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE folders SET parent_id = (SELECT parent_id FROM folders WHERE id = ?1) WHERE parent_id = ?1",
+        params![id],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    conn.execute("UPDATE sources SET folder_id = NULL WHERE folder_id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    conn.execute("DELETE FROM folders WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// None removes the source from its folder
+pub fn assign_source(conn: &Connection, source_name: &str, folder_id: Option<&str>) -> Result<(), String> {
+    if let Some(folder_id) = folder_id {
+        let exists: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM folders WHERE id = ?1)", params![folder_id], |row| row.get(0))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+        if !exists {
+            return Err(t(Msg::FolderNotFound));
+        }
+    }
+    conn.execute(
+        "UPDATE sources SET folder_id = ?1 WHERE name = ?2",
+        params![folder_id, source_name],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// SQL condition on `articles.source` matching sources in folder `?{param}` or any subfolder
+pub fn source_condition(param: usize) -> String {
+    format!(
+        "source IN (SELECT name FROM sources WHERE folder_id IN (
+            WITH RECURSIVE tree(id) AS (
+                SELECT ?{0} UNION SELECT f.id FROM folders f JOIN tree ON f.parent_id = tree.id
+            ) SELECT id FROM tree))",
+        param
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str, parent_id: Option<&str>) -> Folder {
+        Folder { id: String::new(), name: name.to_string(), parent_id: parent_id.map(str::to_string), position: 0, sources: Vec::new() }
+    }
+
+    fn add_source(conn: &Connection, name: &str) {
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type) VALUES (?1, ?1, ?1, 'RSS')",
+            params![name],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source) VALUES (?1, ?1, ?1, ?1)",
+            params![name],
+        )
+        .unwrap();
+    }
+
+    fn sources_in(conn: &Connection, folder_id: &str) -> Vec<String> {
+        let sql = format!("SELECT source FROM articles WHERE {} ORDER BY source", source_condition(1));
+        let mut stmt = conn.prepare(&sql).unwrap();
+        stmt.query_map(params![folder_id], |row| row.get(0)).unwrap().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn folder_filter_includes_subfolders_and_cycles_are_rejected() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let media = save(&conn, folder("  Media ", None)).unwrap();
+        assert_eq!(media.name, "Media");
+        let chinese = save(&conn, folder("中文媒体", Some(&media.id))).unwrap();
+        add_source(&conn, "Blog");
+        add_source(&conn, "机器之心");
+        add_source(&conn, "Unfiled");
+        assign_source(&conn, "Blog", Some(&media.id)).unwrap();
+        assign_source(&conn, "机器之心", Some(&chinese.id)).unwrap();
+        assert!(assign_source(&conn, "Unfiled", Some("missing")).is_err());
+
+        assert_eq!(sources_in(&conn, &media.id), ["Blog", "机器之心"]);
+        assert_eq!(sources_in(&conn, &chinese.id), ["机器之心"]);
+
+        let mut moved = media.clone();
+        moved.parent_id = Some(chinese.id.clone());
+        assert!(save(&conn, moved).is_err(), "parent under its own child");
+        let mut itself = chinese.clone();
+        itself.parent_id = Some(chinese.id.clone());
+        assert!(save(&conn, itself).is_err());
+    }
+
+    #[test]
+    fn deleting_a_folder_lifts_subfolders_and_unfiles_sources() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let root = save(&conn, folder("Research", None)).unwrap();
+        let middle = save(&conn, folder("Papers", Some(&root.id))).unwrap();
+        let leaf = save(&conn, folder("arXiv", Some(&middle.id))).unwrap();
+        add_source(&conn, "Lab blog");
+        assign_source(&conn, "Lab blog", Some(&middle.id)).unwrap();
+
+        delete(&conn, &middle.id).unwrap();
+        let folders = list(&conn).unwrap();
+        assert_eq!(folders.len(), 2);
+        let arxiv = folders.iter().find(|f| f.id == leaf.id).unwrap();
+        assert_eq!(arxiv.parent_id.as_deref(), Some(root.id.as_str()));
+        assert!(folders.iter().all(|f| f.sources.is_empty()));
+    }
+}
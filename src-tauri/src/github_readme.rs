@@ -0,0 +1,335 @@
+// README excerpts for GitHub trending articles.
+//
+// A trending row only carries a one-line description, which gives summaries
+// little to work with. For each repo that passes the quality filter the
+// crawler fetches its README through the GitHub API (raw media type, cached
+// for a day like other pages) and stores `intro` of it after the
+// description. `intro` is the first meaningful section: badges, images,
+// tables, code blocks, link-only lines (tables of contents) and the title
+// heading are skipped, a leading contents/installation/license section is
+// skipped whole, and paragraphs are collected until the next heading once
+// there is MIN_INTRO_CHARS of text, up to MAX_INTRO_CHARS.
+
+const MIN_INTRO_CHARS: usize = 80;
+const MAX_INTRO_CHARS: usize = 1500;
+const API_BASE: &str = "https://api.github.com";
+// Sections that never describe the project
+const SKIPPED_SECTIONS: &[&str] = &[
+    "contents", "table of contents", "toc", "installation", "install", "getting started", "quick start",
+    "quickstart", "usage", "license", "contributing", "sponsors", "news", "updates", "changelog", "目录", "安装",
+    "快速开始", "许可证",
+];
+
+// The API endpoint serving a repo's README whatever its file name; `github_base` is github.com or a test server
+pub fn api_url(github_base: &str, full_name: &str) -> String {
+    let base = if github_base == "https://github.com" { API_BASE } else { github_base };
+    format!("{}/repos/{}/readme", base, full_name)
+}
+
+// Heading text if the line is a Markdown (`# x`) or HTML (`<h2>x</h2>`) heading
+fn heading(line: &str) -> Option<String> {
+    if let Some(rest) = line.strip_prefix('#') {
+        let text = rest.trim_start_matches('#');
+        if text.is_empty() || text.starts_with(' ') {
+            return Some(inline_text(text));
+        }
+    }
+    let lower = line.to_ascii_lowercase();
+    if lower.len() > 3 && lower.starts_with("<h") && lower.as_bytes()[2].is_ascii_digit() {
+        return Some(inline_text(&strip_tags(line)));
+    }
+    None
+}
+
+// Setext underline (`===` / `---`) of the previous line
+fn is_underline(line: &str) -> bool {
+    line.len() >= 3 && (line.chars().all(|c| c == '=') || line.chars().all(|c| c == '-'))
+}
+
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+// Plain text of a Markdown line: images dropped, links reduced to their text, emphasis and code marks removed
+fn inline_text(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut text = String::with_capacity(line.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let is_image = c == '!' && chars.get(i + 1) == Some(&'[');
+        if c == '[' || is_image {
+            let open = if is_image { i + 1 } else { i };
+            if let Some((label, end)) = link_at(&chars, open) {
+                if !is_image {
+                    text.push_str(&inline_text(&label));
+                }
+                i = end;
+                continue;
+            }
+        }
+        if c == '_' && chars.get(i + 1) == Some(&'_') {
+            i += 1;
+        } else if !matches!(c, '*' | '`') {
+            text.push(c);
+        }
+        i += 1;
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// `[label](target)` or `[label][ref]` starting at `open`: the label and the index after the link
+fn link_at(chars: &[char], open: usize) -> Option<(String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    for (j, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    let label: String = chars[open + 1..close].iter().collect();
+    let (opener, closer) = match chars.get(close + 1) {
+        Some('(') => ('(', ')'),
+        Some('[') => ('[', ']'),
+        _ => return None,
+    };
+    let mut depth = 0;
+    for (j, &c) in chars.iter().enumerate().skip(close + 1) {
+        if c == opener {
+            depth += 1;
+        } else if c == closer {
+            depth -= 1;
+            if depth == 0 {
+                return Some((label, j + 1));
+            }
+        }
+    }
+    None
+}
+
+// Nothing but links, images and separators, like badge rows and table-of-contents entries
+fn is_link_only(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    let mut links = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let open = if c == '!' && chars.get(i + 1) == Some(&'[') { Some(i + 1) } else { (c == '[').then_some(i) };
+        if let Some((_, end)) = open.and_then(|open| link_at(&chars, open)) {
+            links += 1;
+            i = end;
+            continue;
+        }
+        if c.is_alphanumeric() {
+            return false;
+        }
+        i += 1;
+    }
+    links > 0
+}
+
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).or_else(|| line.strip_prefix("+ ")) {
+        return Some(rest);
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    (digits > 0).then(|| line[digits..].strip_prefix(". ")).flatten()
+}
+
+// The first meaningful section of a README, as plain text paragraphs
+pub fn intro(readme: &str) -> Option<String> {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut in_comment = false;
+    let mut skipping_section = false;
+    let total = |paragraphs: &[String], current: &[String]| {
+        paragraphs.iter().chain(current).map(|p| p.chars().count()).sum::<usize>()
+    };
+
+    let lines: Vec<&str> = readme.lines().map(str::trim).collect();
+    for (n, line) in lines.iter().enumerate() {
+        if let Some(marker) = fence {
+            if line.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if in_comment {
+            in_comment = !line.contains("-->");
+            continue;
+        }
+        if line.starts_with("<!--") {
+            in_comment = !line.contains("-->");
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| line.starts_with(m)) {
+            fence = Some(marker);
+            continue;
+        }
+
+        // A setext heading is the line before its underline
+        let next_is_underline = lines.get(n + 1).is_some_and(|next| is_underline(next)) && !line.is_empty();
+        if is_underline(line) && n > 0 && !lines[n - 1].is_empty() {
+            continue;
+        }
+        if let Some(title) = heading(line).or_else(|| next_is_underline.then(|| inline_text(line))) {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+            if total(&paragraphs, &current) >= MIN_INTRO_CHARS {
+                break;
+            }
+            let title = title.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+            let skipped = SKIPPED_SECTIONS.contains(&title.as_str());
+            // Below the first heading, a skipped section ends whatever little was collected
+            if skipped && !paragraphs.is_empty() {
+                break;
+            }
+            skipping_section = skipped;
+            continue;
+        }
+        if skipping_section {
+            continue;
+        }
+
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+            continue;
+        }
+        if line.starts_with('|') || line.starts_with("> [!") || is_link_only(line) {
+            continue;
+        }
+        let text = if line.starts_with('<') {
+            inline_text(&strip_tags(line))
+        } else if let Some(item) = list_item(line) {
+            if is_link_only(item) {
+                continue;
+            }
+            // Items stay on their own lines
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+            paragraphs.push(format!("- {}", inline_text(item)));
+            continue;
+        } else {
+            inline_text(line.trim_start_matches('>').trim_start())
+        };
+        if text.chars().any(char::is_alphanumeric) {
+            current.push(text);
+        }
+        if total(&paragraphs, &current) >= MAX_INTRO_CHARS {
+            break;
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+
+    let mut intro = String::new();
+    for paragraph in paragraphs {
+        let separator = if intro.is_empty() { "" } else if paragraph.starts_with("- ") { "\n" } else { "\n\n" };
+        if intro.chars().count() + separator.len() + paragraph.chars().count() > MAX_INTRO_CHARS {
+            if intro.is_empty() {
+                intro = paragraph.chars().take(MAX_INTRO_CHARS - 1).collect::<String>() + "…";
+            }
+            break;
+        }
+        intro.push_str(separator);
+        intro.push_str(&paragraph);
+    }
+    (!intro.is_empty()).then_some(intro)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_url_targets_the_api_host() {
+        assert_eq!(api_url("https://github.com", "acme/llm-kit"), "https://api.github.com/repos/acme/llm-kit/readme");
+        assert_eq!(api_url("http://127.0.0.1:9000", "acme/llm-kit"), "http://127.0.0.1:9000/repos/acme/llm-kit/readme");
+    }
+
+    #[test]
+    fn inline_markup_is_reduced_to_text() {
+        assert_eq!(
+            inline_text("A **fast** `kv` cache ![logo](a.png) for [vLLM](https://x.y/(v)) and __more__"),
+            "A fast kv cache for vLLM and more"
+        );
+        assert_eq!(inline_text("[unclosed link"), "[unclosed link");
+        assert!(is_link_only("[![CI](badge.svg)](ci) | [Docs][docs]"));
+        assert!(!is_link_only("See [the docs](docs) first"));
+        assert!(!is_link_only("---"));
+        assert_eq!(heading("## Features"), Some("Features".into()));
+        assert_eq!(heading("<h1 align=\"center\">LLM Kit</h1>"), Some("LLM Kit".into()));
+        assert_eq!(heading("#hashtag"), None);
+    }
+
+    #[test]
+    fn intro_is_the_first_meaningful_section() {
+        let readme = "\
+<!-- header -->
+# LLM Kit
+[![CI](badge.svg)](ci) [![PyPI](pypi.svg)](pypi)
+
+## Table of Contents
+- [Install](#install)
+- [Usage](#usage)
+
+## Overview
+LLM Kit is a *serving library* for large language models,
+with paged attention and continuous batching.
+
+```bash
+pip install llm-kit
+```
+- Runs on a single GPU
+- OpenAI-compatible server
+
+## Installation
+pip install llm-kit
+";
+        assert_eq!(
+            intro(readme).unwrap(),
+            "LLM Kit is a serving library for large language models, with paged attention and continuous batching.\n\
+             - Runs on a single GPU\n\
+             - OpenAI-compatible server"
+        );
+    }
+
+    #[test]
+    fn intro_of_a_readme_without_prose() {
+        assert_eq!(intro(""), None);
+        assert_eq!(intro("# Title\n[![CI](badge.svg)](ci)\n\n## License\nMIT\n"), None);
+
+        let long = format!("Setext title\n============\n\n{}\n", "word ".repeat(400));
+        let text = intro(&long).unwrap();
+        assert_eq!(text.chars().count(), MAX_INTRO_CHARS);
+        assert!(text.ends_with('…'));
+    }
+}
@@ -0,0 +1,246 @@
+// Per-repository metadata for GitHub trending sources, keyed by full name
+// (`owner/repo`).
+//
+// The quality filter needs each repo's creation date, which is only on the
+// repo page. A creation date never changes, so a repo page is fetched the
+// first time the repo shows up and again only after METADATA_TTL; failed
+// lookups are retried sooner. Star counts come from the trending page
+// itself and are refreshed on every crawl; the last count of each day is
+// also kept in `repo_snapshots` for the star report.
+//
+// The same repos churn through trending for weeks. `first_seen_at` records
+// when a repo first showed up, `listed_at` / `listed_stars` when it last
+// became an article. Once that article is gone (cleanup, deletion) the repo
+// only gets a new one after `github_reappear_cooloff_days` or once it has
+// gained `github_reappear_star_delta` stars; that decision replaces the
+// `seen_urls` skip for repo URLs.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::i18n::{tr, Msg};
+
+const METADATA_TTL_DAYS: i64 = 30;
+
+// Repo page didn't yield a creation date (rate limit, layout change, ...)
+const RETRY_FAILED_HOURS: i64 = 24;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS github_repos (
+            full_name TEXT PRIMARY KEY,
+            created_at TEXT,
+            stars INTEGER NOT NULL DEFAULT 0,
+            fetched_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS repo_snapshots (
+            full_name TEXT NOT NULL,
+            day TEXT NOT NULL,
+            stars INTEGER NOT NULL,
+            PRIMARY KEY (full_name, day)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_repo_snapshots_day ON repo_snapshots(day)", [])?;
+    Ok(())
+}
+
+// `owner/repo` from a trending link such as `/owner/repo`
+pub fn full_name(project_url: &str) -> String {
+    project_url.trim_matches('/').to_lowercase()
+}
+
+// Creation date if the cached metadata is still fresh: `Some(None)` is a recent failed lookup,
+// `None` means the repo page needs fetching
+pub fn cached_created_at(conn: &Connection, full_name: &str) -> Option<Option<chrono::DateTime<chrono::Utc>>> {
+    let (created_at, fetched_at): (Option<String>, i64) = conn
+        .query_row(
+            "SELECT created_at, fetched_at FROM github_repos WHERE full_name = ?1",
+            params![full_name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .ok()
+        .flatten()?;
+
+    let age = chrono::Utc::now().timestamp() - fetched_at;
+    let created_at = created_at
+        .and_then(|c| chrono::DateTime::parse_from_rfc3339(&c).ok())
+        .map(|c| c.with_timezone(&chrono::Utc));
+    let ttl = if created_at.is_some() { METADATA_TTL_DAYS * 24 * 3600 } else { RETRY_FAILED_HOURS * 3600 };
+    (age < ttl).then_some(created_at)
+}
+
+pub fn store_metadata(
+    conn: &Connection,
+    full_name: &str,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    stars: u32,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO github_repos (full_name, created_at, stars, fetched_at, first_seen_at) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(full_name) DO UPDATE SET
+            created_at = excluded.created_at, stars = excluded.stars, fetched_at = excluded.fetched_at,
+            first_seen_at = COALESCE(github_repos.first_seen_at, excluded.first_seen_at)",
+        params![
+            full_name,
+            created_at.map(|c| c.to_rfc3339()),
+            stars,
+            chrono::Utc::now().timestamp(),
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    record_snapshot(conn, full_name, stars)
+}
+
+pub fn update_stars(conn: &Connection, full_name: &str, stars: u32) -> Result<(), String> {
+    // Rows cached before first-seen tracking get it now
+    conn.execute(
+        "UPDATE github_repos SET stars = ?1, first_seen_at = COALESCE(first_seen_at, ?3) WHERE full_name = ?2",
+        params![stars, full_name, chrono::Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    record_snapshot(conn, full_name, stars)
+}
+
+// Whether a repo with no stored article may become one: never listed, listed at least
+// `cooloff_days` ago, or up `star_delta` stars since
+pub fn may_relist(conn: &Connection, full_name: &str, stars: u32, cooloff_days: u32, star_delta: u32) -> bool {
+    let listed: Option<(Option<String>, Option<i64>)> = conn
+        .query_row(
+            "SELECT listed_at, listed_stars FROM github_repos WHERE full_name = ?1",
+            params![full_name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .ok()
+        .flatten();
+    let Some((Some(listed_at), listed_stars)) = listed else {
+        return true;
+    };
+    let Ok(listed_at) = chrono::DateTime::parse_from_rfc3339(&listed_at) else {
+        return true;
+    };
+    let since_listed = chrono::Utc::now() - listed_at.with_timezone(&chrono::Utc);
+    let cooled_off = since_listed >= chrono::Duration::days(cooloff_days as i64);
+    let gained = stars as i64 - listed_stars.unwrap_or(0);
+    cooled_off || gained >= star_delta as i64
+}
+
+// Whether the repo became an article within the last `days`
+pub fn listed_within(conn: &Connection, full_name: &str, days: u32) -> bool {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+    conn.query_row(
+        "SELECT 1 FROM github_repos WHERE full_name = ?1 AND listed_at >= ?2",
+        params![full_name, cutoff],
+        |_| Ok(()),
+    )
+    .optional()
+    .is_ok_and(|row| row.is_some())
+}
+
+// A new article for the repo; with `keep_earlier`, only if none was recorded (articles stored before tracking)
+pub fn mark_listed(conn: &Connection, full_name: &str, stars: u32, keep_earlier: bool) -> Result<(), String> {
+    let sql = if keep_earlier {
+        "UPDATE github_repos SET listed_at = ?1, listed_stars = ?2 WHERE full_name = ?3 AND listed_at IS NULL"
+    } else {
+        "UPDATE github_repos SET listed_at = ?1, listed_stars = ?2 WHERE full_name = ?3"
+    };
+    conn.execute(sql, params![chrono::Utc::now().to_rfc3339(), stars, full_name])
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// One row per repo and UTC day; later crawls on the same day overwrite it
+fn record_snapshot(conn: &Connection, full_name: &str, stars: u32) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO repo_snapshots (full_name, day, stars) VALUES (?1, ?2, ?3)
+         ON CONFLICT(full_name, day) DO UPDATE SET stars = excluded.stars",
+        params![full_name, chrono::Utc::now().format("%Y-%m-%d").to_string(), stars],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn age(conn: &Connection, full_name: &str, hours: i64) {
+        conn.execute(
+            "UPDATE github_repos SET fetched_at = fetched_at - ?1 WHERE full_name = ?2",
+            params![hours * 3600, full_name],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn creation_dates_are_cached_and_failed_lookups_retried_sooner() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(full_name("/Acme/LLM-Kit/"), "acme/llm-kit");
+        assert_eq!(cached_created_at(&conn, "acme/llm-kit"), None, "never fetched");
+
+        let created = chrono::DateTime::parse_from_rfc3339("2024-01-05T00:00:00Z").unwrap().to_utc();
+        store_metadata(&conn, "acme/llm-kit", Some(created), 1200).unwrap();
+        store_metadata(&conn, "acme/broken", None, 10).unwrap();
+        assert_eq!(cached_created_at(&conn, "acme/llm-kit"), Some(Some(created)));
+        assert_eq!(cached_created_at(&conn, "acme/broken"), Some(None), "recent failure isn't refetched");
+
+        age(&conn, "acme/llm-kit", RETRY_FAILED_HOURS + 1);
+        age(&conn, "acme/broken", RETRY_FAILED_HOURS + 1);
+        assert_eq!(cached_created_at(&conn, "acme/llm-kit"), Some(Some(created)));
+        assert_eq!(cached_created_at(&conn, "acme/broken"), None);
+        age(&conn, "acme/llm-kit", METADATA_TTL_DAYS * 24);
+        assert_eq!(cached_created_at(&conn, "acme/llm-kit"), None);
+    }
+
+    #[test]
+    fn star_updates_keep_one_snapshot_per_day() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        store_metadata(&conn, "acme/llm-kit", None, 100).unwrap();
+        update_stars(&conn, "acme/llm-kit", 150).unwrap();
+        update_stars(&conn, "acme/llm-kit", 180).unwrap();
+        let snapshots: Vec<u32> = conn
+            .prepare("SELECT stars FROM repo_snapshots WHERE full_name = 'acme/llm-kit'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(snapshots, [180]);
+        let stars: u32 = conn
+            .query_row("SELECT stars FROM github_repos WHERE full_name = 'acme/llm-kit'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stars, 180);
+    }
+
+    #[test]
+    fn listed_repos_wait_out_the_cooloff_unless_they_gain_stars() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(may_relist(&conn, "acme/llm-kit", 100, 7, 500), "never tracked");
+        store_metadata(&conn, "acme/llm-kit", None, 100).unwrap();
+        assert!(may_relist(&conn, "acme/llm-kit", 100, 7, 500), "never listed");
+        assert!(!listed_within(&conn, "acme/llm-kit", 7));
+
+        mark_listed(&conn, "acme/llm-kit", 100, false).unwrap();
+        assert!(listed_within(&conn, "acme/llm-kit", 7));
+        assert!(!may_relist(&conn, "acme/llm-kit", 400, 7, 500));
+        assert!(may_relist(&conn, "acme/llm-kit", 600, 7, 500), "enough new stars");
+
+        let eight_days_ago = (chrono::Utc::now() - chrono::Duration::days(8)).to_rfc3339();
+        conn.execute("UPDATE github_repos SET listed_at = ?1 WHERE full_name = 'acme/llm-kit'", params![eight_days_ago])
+            .unwrap();
+        assert!(!listed_within(&conn, "acme/llm-kit", 7));
+        assert!(may_relist(&conn, "acme/llm-kit", 100, 7, 500), "cooled off");
+
+        // An earlier listing is kept
+        mark_listed(&conn, "acme/llm-kit", 900, true).unwrap();
+        assert!(!listed_within(&conn, "acme/llm-kit", 7));
+        mark_listed(&conn, "acme/llm-kit", 900, false).unwrap();
+        assert!(listed_within(&conn, "acme/llm-kit", 7));
+        assert!(!may_relist(&conn, "acme/llm-kit", 1000, 7, 500));
+    }
+}
@@ -0,0 +1,468 @@
+// GitHub trending sources configured by language rather than by URL.
+//
+// A WEB source pointing at github.com/trending follows one trending page. A
+// source can instead carry a `TrendingConfig`, stored as JSON in
+// `sources.trending_config`: the programming languages to follow (one page
+// each; none = the overall page), the `since` window and the spoken language
+// of the repos. The crawler generates the page URLs from it and lists each
+// repo once however many of the pages have it. `source_trending_set` creates
+// or edits such a source and keeps `sources.url` at its first page. Plain
+// trending URLs, like the sources seeded before configs existed, read as the
+// config they amount to, so they can be edited the same way.
+//
+// Repos on a trending page only become articles when they pass the star
+// filter: a minimum star count by repo age, since a week-old repo with 5k
+// stars is news and a decade-old one isn't. The app-wide filter is a setting
+// (`github_star_filter_get` / `_update`); a config's `star_filter` overrides
+// it for that source, e.g. with far lower counts for a niche language or
+// `enabled: false` to take every repo the page lists.
+//
+// Weekly and monthly pages change slowly and mostly list repos the daily
+// page already had, so a source following one is only fetched once its
+// window's refresh interval has passed since its last successful fetch
+// (`sources.trending_fetched_at`), and skips repos the repo registry shows
+// became an article within the window; what's left are the steady climbers
+// that never made it onto the daily page.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::crawler::GITHUB_BASE;
+use crate::i18n::{tr, Msg};
+use crate::{get_setting, set_setting};
+
+const STAR_FILTER_KEY: &str = "github_trending.star_filter";
+
+// Each language is one more page fetched per crawl
+const MAX_LANGUAGES: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Since {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Since {
+    fn param(self) -> &'static str {
+        match self {
+            Since::Daily => "daily",
+            Since::Weekly => "weekly",
+            Since::Monthly => "monthly",
+        }
+    }
+
+    fn from_param(value: &str) -> Option<Self> {
+        [Since::Daily, Since::Weekly, Since::Monthly].into_iter().find(|since| since.param() == value)
+    }
+
+    // Days a trending page of this window looks back; None = daily, where listed repos are refreshed
+    pub fn window_days(self) -> Option<u32> {
+        match self {
+            Since::Daily => None,
+            Since::Weekly => Some(7),
+            Since::Monthly => Some(30),
+        }
+    }
+
+    // Minimum time between fetches; None = every crawl
+    fn refresh_interval(self) -> Option<chrono::Duration> {
+        match self {
+            Since::Daily => None,
+            Since::Weekly => Some(chrono::Duration::days(1)),
+            Since::Monthly => Some(chrono::Duration::days(7)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrendingConfig {
+    // GitHub's language slugs ("python", "c++", "jupyter-notebook"); empty = all languages
+    pub languages: Vec<String>,
+    pub since: Since,
+    // ISO 639-1 code ("zh", "en") of the language the repos are written in; None = any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spoken_language: Option<String>,
+    // None = the app-wide filter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub star_filter: Option<StarFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgeBucket {
+    // Repos created less than this many days ago
+    pub max_age_days: u32,
+    pub min_stars: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarFilter {
+    // false = no filter, every repo on the page is taken
+    pub enabled: bool,
+    // Youngest first; the first bucket a repo's age falls in applies
+    pub buckets: Vec<AgeBucket>,
+    // Repos older than every bucket, and repos whose creation date is unknown
+    pub min_stars: u32,
+}
+
+impl Default for StarFilter {
+    fn default() -> Self {
+        StarFilter {
+            enabled: true,
+            buckets: vec![
+                AgeBucket { max_age_days: 14, min_stars: 20_000 },
+                AgeBucket { max_age_days: 60, min_stars: 30_000 },
+            ],
+            min_stars: 10_000,
+        }
+    }
+}
+
+impl StarFilter {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        Ok(serde_json::from_str(&get_setting(conn, STAR_FILTER_KEY, "")?).unwrap_or_default())
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let json = serde_json::to_string(&self.normalized()).map_err(|e| tr(Msg::TrendingConfigInvalid, e))?;
+        Ok(set_setting(conn, STAR_FILTER_KEY, &json)?)
+    }
+
+    // Buckets sorted youngest first, zero-day and repeated ages dropped
+    fn normalized(&self) -> Self {
+        let mut buckets: Vec<AgeBucket> = self.buckets.iter().filter(|b| b.max_age_days > 0).cloned().collect();
+        buckets.sort_by_key(|b| b.max_age_days);
+        buckets.dedup_by_key(|b| b.max_age_days);
+        StarFilter { enabled: self.enabled, buckets, min_stars: self.min_stars }
+    }
+
+    // Whether a repo `age_days` old (None = unknown) with `stars` becomes an article
+    pub fn passes(&self, age_days: Option<i64>, stars: u32) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let min_stars = age_days
+            .and_then(|age| self.buckets.iter().find(|b| age < b.max_age_days as i64))
+            .map_or(self.min_stars, |b| b.min_stars);
+        stars >= min_stars
+    }
+}
+
+// Lowercase with dashes for spaces, the way GitHub spells them in trending URLs
+fn slug(language: &str) -> String {
+    language.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+// Percent-decoded URL path segment ("c%23" -> "c#")
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = segment.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+impl TrendingConfig {
+    // The config a plain trending page URL (`/trending[/<language>]?since=..&spoken_language_code=..`) amounts to
+    pub fn from_url(url: &str) -> Option<Self> {
+        let url = reqwest::Url::parse(url).ok()?;
+        if url.host_str()?.trim_start_matches("www.") != "github.com" {
+            return None;
+        }
+        let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+        if segments.next() != Some("trending") {
+            return None;
+        }
+        let language = match (segments.next(), segments.next()) {
+            (None, _) => None,
+            (Some(language), None) => Some(decode(language)),
+            // `/trending/developers/...` and the like list people, not repos
+            (Some(_), Some(_)) => return None,
+        };
+        let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.to_string());
+        Some(TrendingConfig {
+            languages: language.into_iter().collect(),
+            since: query("since").and_then(|since| Since::from_param(&since)).unwrap_or_default(),
+            spoken_language: query("spoken_language_code").filter(|code| !code.is_empty()),
+            star_filter: None,
+        })
+    }
+
+    // Languages slugged and deduplicated; errors on languages or codes GitHub has no page for
+    pub fn normalized(&self) -> Result<Self, String> {
+        let mut languages: Vec<String> = Vec::new();
+        for language in &self.languages {
+            let slug = slug(language);
+            if slug.is_empty() || languages.contains(&slug) {
+                continue;
+            }
+            if !slug.chars().all(|c| c.is_alphanumeric() || "-+#.'".contains(c)) {
+                return Err(tr(Msg::TrendingConfigInvalid, language));
+            }
+            languages.push(slug);
+        }
+        if languages.len() > MAX_LANGUAGES {
+            return Err(tr(Msg::TrendingConfigInvalid, format!("{} languages (max {})", languages.len(), MAX_LANGUAGES)));
+        }
+        let spoken_language = match self.spoken_language.as_deref().map(str::trim).filter(|code| !code.is_empty()) {
+            Some(code) if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) => {
+                Some(code.to_ascii_lowercase())
+            }
+            Some(code) => return Err(tr(Msg::TrendingConfigInvalid, code)),
+            None => None,
+        };
+        Ok(TrendingConfig {
+            languages,
+            since: self.since,
+            spoken_language,
+            star_filter: self.star_filter.as_ref().map(StarFilter::normalized),
+        })
+    }
+
+    // Trending pages under `github_base` (github.com or a test server), one per language
+    pub fn urls(&self, github_base: &str) -> Vec<String> {
+        let page = |language: Option<&str>| -> Option<String> {
+            let mut url = reqwest::Url::parse(github_base).ok()?;
+            {
+                let mut path = url.path_segments_mut().ok()?;
+                path.pop_if_empty().push("trending");
+                if let Some(language) = language {
+                    path.push(language);
+                }
+            }
+            let mut query = Vec::new();
+            if self.since != Since::Daily {
+                query.push(("since", self.since.param()));
+            }
+            if let Some(code) = &self.spoken_language {
+                query.push(("spoken_language_code", code.as_str()));
+            }
+            // An empty query would still leave a bare "?"
+            if !query.is_empty() {
+                url.query_pairs_mut().extend_pairs(query);
+            }
+            Some(url.to_string())
+        };
+        if self.languages.is_empty() {
+            page(None).into_iter().collect()
+        } else {
+            self.languages.iter().filter_map(|language| page(Some(language))).collect()
+        }
+    }
+
+    // The source's stored config, else what its URL amounts to; None for sources that aren't trending pages
+    pub fn load(conn: &Connection, source_name: &str) -> Result<Option<Self>, String> {
+        let row: Option<(String, Option<String>)> = conn
+            .query_row(
+                "SELECT url, trending_config FROM sources WHERE name = ?1",
+                params![source_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+        Ok(row.and_then(|(url, config)| {
+            config.and_then(|json| serde_json::from_str(&json).ok()).or_else(|| Self::from_url(&url))
+        }))
+    }
+}
+
+// Create the WEB source `name` from a config, or reconfigure it; returns the config as stored
+pub fn save(conn: &Connection, name: &str, config: &TrendingConfig) -> Result<TrendingConfig, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(tr(Msg::TrendingConfigInvalid, "name"));
+    }
+    let config = config.normalized()?;
+    let json = serde_json::to_string(&config).map_err(|e| tr(Msg::TrendingConfigInvalid, e))?;
+    // The first page stands for the source wherever only a URL is shown
+    let url = config.urls(GITHUB_BASE).into_iter().next().unwrap_or_else(|| format!("{}/trending", GITHUB_BASE));
+    let updated = conn
+        .execute(
+            // A new window or languages are fetched on the next crawl
+            "UPDATE sources SET url = ?1, source_type = 'WEB', trending_config = ?2, trending_fetched_at = NULL
+             WHERE name = ?3",
+            params![url, json, name],
+        )
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type, is_active, category, trending_config)
+             VALUES (?1, ?2, ?3, 'WEB', 1, 'GitHub', ?4)",
+            params![uuid::Uuid::new_v4().to_string(), name, url, json],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    Ok(config)
+}
+
+// Whether a source with this config is fetched in this crawl
+pub fn is_due(conn: &Connection, source_name: &str, config: &TrendingConfig) -> bool {
+    let Some(interval) = config.since.refresh_interval() else { return true };
+    let fetched_at: Option<String> = conn
+        .query_row("SELECT trending_fetched_at FROM sources WHERE name = ?1", params![source_name], |row| row.get(0))
+        .optional()
+        .ok()
+        .flatten()
+        .flatten();
+    let Some(fetched_at) = fetched_at.and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok()) else {
+        return true;
+    };
+    chrono::Utc::now() - fetched_at.with_timezone(&chrono::Utc) >= interval
+}
+
+pub fn record_fetch(conn: &Connection, source_name: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE sources SET trending_fetched_at = ?1 WHERE name = ?2",
+        params![chrono::Utc::now().to_rfc3339(), source_name],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_trending_urls_read_as_configs() {
+        let config = TrendingConfig::from_url("https://github.com/trending/c%23?since=weekly&spoken_language_code=zh").unwrap();
+        assert_eq!(config.languages, ["c#"]);
+        assert_eq!(config.since, Since::Weekly);
+        assert_eq!(config.spoken_language.as_deref(), Some("zh"));
+
+        let config = TrendingConfig::from_url("https://www.github.com/trending?since=hourly").unwrap();
+        assert!(config.languages.is_empty());
+        assert_eq!(config.since, Since::Daily, "unknown window");
+        assert!(TrendingConfig::from_url("https://github.com/trending/developers/python").is_none());
+        assert!(TrendingConfig::from_url("https://github.com/acme/llm-kit").is_none());
+        assert!(TrendingConfig::from_url("https://gitlab.com/trending").is_none());
+    }
+
+    #[test]
+    fn configs_are_normalized_and_checked() {
+        let config = TrendingConfig {
+            languages: vec![" Jupyter Notebook ".into(), "jupyter-notebook".into(), "C++".into(), " ".into()],
+            spoken_language: Some(" ZH ".into()),
+            ..Default::default()
+        };
+        let normalized = config.normalized().unwrap();
+        assert_eq!(normalized.languages, ["jupyter-notebook", "c++"]);
+        assert_eq!(normalized.spoken_language.as_deref(), Some("zh"));
+
+        let bad_language = TrendingConfig { languages: vec!["rust/../x".into()], ..Default::default() };
+        assert!(bad_language.normalized().is_err());
+        let bad_code = TrendingConfig { spoken_language: Some("chinese".into()), ..Default::default() };
+        assert!(bad_code.normalized().is_err());
+        let too_many = TrendingConfig { languages: (0..=MAX_LANGUAGES).map(|i| format!("lang{}", i)).collect(), ..Default::default() };
+        assert!(too_many.normalized().is_err());
+    }
+
+    #[test]
+    fn one_page_per_language() {
+        let config = TrendingConfig { since: Since::Monthly, spoken_language: Some("en".into()), ..Default::default() };
+        assert_eq!(config.urls("https://github.com"), ["https://github.com/trending?since=monthly&spoken_language_code=en"]);
+        let config = TrendingConfig { languages: vec!["python".into(), "c#".into()], ..Default::default() };
+        assert_eq!(
+            config.urls("http://127.0.0.1:9000/"),
+            ["http://127.0.0.1:9000/trending/python", "http://127.0.0.1:9000/trending/c%23"]
+        );
+    }
+
+    #[test]
+    fn save_creates_or_reconfigures_the_source() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(save(&conn, "  ", &TrendingConfig::default()).is_err());
+        let config = TrendingConfig { languages: vec!["Rust".into()], ..Default::default() };
+        save(&conn, "Trending Rust", &config).unwrap();
+        let url: String =
+            conn.query_row("SELECT url FROM sources WHERE name = 'Trending Rust'", [], |row| row.get(0)).unwrap();
+        assert_eq!(url, format!("{}/trending/rust", GITHUB_BASE));
+        assert_eq!(TrendingConfig::load(&conn, "Trending Rust").unwrap().unwrap().languages, ["rust"]);
+
+        let config = TrendingConfig { languages: Vec::new(), since: Since::Weekly, ..Default::default() };
+        save(&conn, "Trending Rust", &config).unwrap();
+        let sources: i64 = conn.query_row("SELECT COUNT(*) FROM sources", [], |row| row.get(0)).unwrap();
+        assert_eq!(sources, 1);
+        assert_eq!(TrendingConfig::load(&conn, "Trending Rust").unwrap().unwrap().since, Since::Weekly);
+
+        // Sources without a stored config read their URL
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type, is_active) VALUES ('s2', 'Plain', 'https://github.com/trending/go', 'WEB', 1)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(TrendingConfig::load(&conn, "Plain").unwrap().unwrap().languages, ["go"]);
+        assert!(TrendingConfig::load(&conn, "Missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn star_thresholds_depend_on_repo_age() {
+        let filter = StarFilter::default();
+        assert!(filter.passes(Some(3), 20_000));
+        assert!(!filter.passes(Some(3), 19_999));
+        assert!(!filter.passes(Some(30), 25_000));
+        assert!(filter.passes(Some(400), 10_000));
+        assert!(!filter.passes(None, 9_999), "unknown age uses the base count");
+        assert!(StarFilter { enabled: false, ..StarFilter::default() }.passes(Some(3), 0));
+
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(StarFilter::load(&conn).unwrap().min_stars, 10_000);
+        let filter = StarFilter {
+            enabled: true,
+            buckets: vec![
+                AgeBucket { max_age_days: 30, min_stars: 500 },
+                AgeBucket { max_age_days: 0, min_stars: 1 },
+                AgeBucket { max_age_days: 7, min_stars: 100 },
+                AgeBucket { max_age_days: 30, min_stars: 900 },
+            ],
+            min_stars: 1_000,
+        };
+        filter.save(&conn).unwrap();
+        let saved = StarFilter::load(&conn).unwrap();
+        let ages: Vec<u32> = saved.buckets.iter().map(|b| b.max_age_days).collect();
+        assert_eq!(ages, [7, 30]);
+        assert!(saved.passes(Some(2), 100));
+        assert!(!saved.passes(Some(10), 499));
+    }
+
+    #[test]
+    fn weekly_and_monthly_pages_wait_for_their_interval() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let daily = TrendingConfig::default();
+        let weekly = TrendingConfig { since: Since::Weekly, ..Default::default() };
+        save(&conn, "Weekly", &weekly).unwrap();
+        assert!(is_due(&conn, "Weekly", &weekly), "never fetched");
+
+        record_fetch(&conn, "Weekly").unwrap();
+        assert!(!is_due(&conn, "Weekly", &weekly));
+        assert!(is_due(&conn, "Weekly", &daily), "daily pages are fetched every crawl");
+
+        let two_days_ago = (chrono::Utc::now() - chrono::Duration::days(2)).to_rfc3339();
+        conn.execute("UPDATE sources SET trending_fetched_at = ?1 WHERE name = 'Weekly'", params![two_days_ago]).unwrap();
+        assert!(is_due(&conn, "Weekly", &weekly));
+        let monthly = TrendingConfig { since: Since::Monthly, ..Default::default() };
+        assert!(!is_due(&conn, "Weekly", &monthly));
+
+        // Reconfiguring fetches on the next crawl
+        save(&conn, "Weekly", &monthly).unwrap();
+        assert!(is_due(&conn, "Weekly", &monthly));
+        assert_eq!(Since::Daily.window_days(), None);
+        assert_eq!(Since::Monthly.window_days(), Some(30));
+    }
+}
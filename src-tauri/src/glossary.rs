@@ -0,0 +1,140 @@
+// User terminology glossary for AI translation and summarization
+// (e.g. "transformer → Transformer 架构", "智谱 → Zhipu AI").
+//
+// Only entries whose term occurs in the text being processed are added to
+// the prompt, so a large glossary doesn't bloat every request.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, Lang, Msg};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS glossary (
+            id TEXT PRIMARY KEY,
+            term TEXT NOT NULL,
+            translation TEXT NOT NULL,
+            lang TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    #[serde(default)]
+    pub id: String,
+    pub term: String,
+    // How the term should be rendered
+    pub translation: String,
+    // Target language code the entry applies to; None = every language
+    #[serde(default)]
+    pub lang: Option<String>,
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<GlossaryEntry>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, term, translation, lang FROM glossary ORDER BY term COLLATE NOCASE ASC")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok(GlossaryEntry {
+                id: row.get(0)?,
+                term: row.get(1)?,
+                translation: row.get(2)?,
+                lang: row.get(3)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(entries)
+}
+
+// Insert when `id` is empty, otherwise replace the existing entry
+pub fn save(conn: &Connection, mut entry: GlossaryEntry) -> Result<GlossaryEntry, String> {
+    if entry.id.is_empty() {
+        entry.id = uuid::Uuid::new_v4().to_string();
+    }
+    entry.term = entry.term.trim().to_string();
+    entry.translation = entry.translation.trim().to_string();
+    entry.lang = entry
+        .lang
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| Lang::from_code(&l).code().to_string());
+
+    conn.execute(
+        "INSERT OR REPLACE INTO glossary (id, term, translation, lang) VALUES (?1, ?2, ?3, ?4)",
+        params![entry.id, entry.term, entry.translation, entry.lang],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(entry)
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM glossary WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Prompt lines for the entries that occur in `text`; empty when none do
+pub fn prompt_hint(entries: &[GlossaryEntry], text: &str, lang: Lang) -> String {
+    let haystack = text.to_lowercase();
+    let lines: Vec<String> = entries
+        .iter()
+        .filter(|e| !e.term.is_empty() && e.lang.as_deref().is_none_or(|l| l == lang.code()))
+        .filter(|e| haystack.contains(&e.term.to_lowercase()))
+        .map(|e| format!("- {} → {}", e.term, e.translation))
+        .collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let heading = match lang {
+        Lang::Zh => "请按以下术语表翻译专有名词：",
+        Lang::En => "Render these terms as given:",
+    };
+    format!("\n\n{}\n{}", heading, lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(term: &str, translation: &str, lang: Option<&str>) -> GlossaryEntry {
+        GlossaryEntry { id: String::new(), term: term.to_string(), translation: translation.to_string(), lang: lang.map(str::to_string) }
+    }
+
+    #[test]
+    fn hint_lists_only_terms_in_the_text_for_the_target_language() {
+        let entries = [
+            entry("Transformer", "Transformer 架构", Some("zh")),
+            entry("智谱", "Zhipu AI", Some("en")),
+            entry("RLHF", "RLHF", None),
+            entry("", "ignored", None),
+        ];
+        let hint = prompt_hint(&entries, "A new transformer trained with rlhf", Lang::Zh);
+        assert!(hint.contains("- Transformer → Transformer 架构"));
+        assert!(hint.contains("- RLHF → RLHF"));
+        assert!(!hint.contains("Zhipu"));
+        assert!(!hint.contains("ignored"));
+
+        assert_eq!(prompt_hint(&entries, "A new transformer", Lang::En), "");
+        assert!(prompt_hint(&entries, "智谱发布新模型", Lang::En).contains("- 智谱 → Zhipu AI"));
+    }
+
+    #[test]
+    fn save_trims_and_normalizes_the_language() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let saved = save(&conn, entry("  agent ", " 智能体 ", Some("zh-CN"))).unwrap();
+        assert_eq!((saved.term.as_str(), saved.translation.as_str(), saved.lang.as_deref()), ("agent", "智能体", Some("zh")));
+        let any = save(&conn, entry("LoRA", "LoRA", Some("  "))).unwrap();
+        assert_eq!(any.lang, None);
+
+        let terms: Vec<String> = list(&conn).unwrap().into_iter().map(|e| e.term).collect();
+        assert_eq!(terms, ["agent", "LoRA"]);
+        delete(&conn, &saved.id).unwrap();
+        assert_eq!(list(&conn).unwrap().len(), 1);
+    }
+}
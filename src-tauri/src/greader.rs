@@ -0,0 +1,465 @@
+// Sync with Google Reader-compatible servers (Miniflux, FreshRSS).
+//
+// Subscriptions are merged as a union in both directions. Read/starred state
+// is reconciled three-way against `greader_items`, the state both sides agreed
+// on at the last sync: whichever side changed since then wins, and when both
+// changed differently the "true" value (read / starred) is kept.
+//
+// Articles are matched to remote items by normalized URL; remote items that
+// were never crawled locally are left alone.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{get_setting, normalize_url, set_setting};
+
+const STATE_READ: &str = "user/-/state/com.google/read";
+const STATE_STARRED: &str = "user/-/state/com.google/starred";
+// How many recent remote items are fetched for state reconciliation
+const READING_LIST_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GReaderConfig {
+    // e.g. https://rss.example.com (Miniflux) or https://example.com/api/greader.php (FreshRSS)
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl GReaderConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        Ok(GReaderConfig {
+            url: get_setting(conn, "greader.url", "")?,
+            username: get_setting(conn, "greader.username", "")?,
+            password: get_setting(conn, "greader.password", "")?,
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        set_setting(conn, "greader.url", self.url.trim().trim_end_matches('/'))?;
+        set_setting(conn, "greader.username", self.username.trim())?;
+        set_setting(conn, "greader.password", &self.password)?;
+        Ok(())
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.url.is_empty() && !self.username.is_empty()
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS greader_items (
+            article_id TEXT PRIMARY KEY,
+            remote_id TEXT NOT NULL,
+            is_read INTEGER NOT NULL DEFAULT 0,
+            is_starred INTEGER NOT NULL DEFAULT 0,
+            synced_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub subscriptions_pushed: usize,
+    pub subscriptions_pulled: usize,
+    pub read_pushed: usize,
+    pub read_pulled: usize,
+    pub starred_pushed: usize,
+    pub starred_pulled: usize,
+    pub matched_items: usize,
+}
+
+pub struct RemoteSubscription {
+    pub title: String,
+    pub url: String,
+}
+
+pub struct RemoteItem {
+    pub id: String,
+    pub url: String,
+    pub is_read: bool,
+    pub is_starred: bool,
+}
+
+// Snapshot of everything the network phase needs, taken under the DB lock
+pub struct LocalSnapshot {
+    pub feed_urls: Vec<(String, String)>,
+    pub articles: HashMap<String, LocalItem>,
+}
+
+pub struct LocalItem {
+    pub article_id: String,
+    pub is_read: bool,
+    pub is_starred: bool,
+    pub base: Option<(bool, bool)>,
+}
+
+pub fn local_snapshot(conn: &Connection) -> Result<LocalSnapshot, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, url FROM sources WHERE source_type = 'RSS'")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let feed_urls = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.id, a.url, a.is_read, a.is_bookmarked, g.is_read, g.is_starred
+             FROM articles a LEFT JOIN greader_items g ON g.article_id = a.id",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let base_read: Option<i32> = row.get(4)?;
+            let base_starred: Option<i32> = row.get(5)?;
+            Ok((
+                row.get::<_, String>(1)?,
+                LocalItem {
+                    article_id: row.get(0)?,
+                    is_read: row.get::<_, i32>(2)? > 0,
+                    is_starred: row.get::<_, i32>(3)? > 0,
+                    base: base_read.zip(base_starred).map(|(r, s)| (r > 0, s > 0)),
+                },
+            ))
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    Ok(LocalSnapshot {
+        feed_urls,
+        articles: rows.into_iter().collect(),
+    })
+}
+
+pub struct GReaderClient {
+    http: reqwest::Client,
+    base: String,
+    auth: String,
+    token: String,
+}
+
+impl GReaderClient {
+    pub async fn login(http: reqwest::Client, config: &GReaderConfig) -> Result<Self, String> {
+        if !config.is_configured() {
+            return Err(t(Msg::SyncNotConfigured));
+        }
+        let base = config.url.trim_end_matches('/').to_string();
+        let response = http
+            .post(format!("{}/accounts/ClientLogin", base))
+            .form(&[("Email", config.username.as_str()), ("Passwd", config.password.as_str())])
+            .send()
+            .await
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+        let body = checked_text(response).await?;
+        let auth = body
+            .lines()
+            .find_map(|line| line.strip_prefix("Auth="))
+            .map(|s| s.trim().to_string())
+            .ok_or_else(|| t(Msg::SyncAuthFailed))?;
+
+        let mut client = GReaderClient { http, base, auth, token: String::new() };
+        let token_response = client.get("/reader/api/0/token").send().await.map_err(|e| tr(Msg::SyncFailed, e))?;
+        client.token = checked_text(token_response).await?.trim().to_string();
+        Ok(client)
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .get(format!("{}{}", self.base, path))
+            .header("Authorization", format!("GoogleLogin auth={}", self.auth))
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .post(format!("{}{}", self.base, path))
+            .header("Authorization", format!("GoogleLogin auth={}", self.auth))
+    }
+
+    pub async fn subscriptions(&self) -> Result<Vec<RemoteSubscription>, String> {
+        let response = self
+            .get("/reader/api/0/subscription/list?output=json")
+            .send()
+            .await
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+        let json: serde_json::Value = serde_json::from_str(&checked_text(response).await?)
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+        Ok(json["subscriptions"]
+            .as_array()
+            .map(|subs| {
+                subs.iter()
+                    .filter_map(|sub| {
+                        let url = sub["url"]
+                            .as_str()
+                            .or_else(|| sub["id"].as_str().and_then(|id| id.strip_prefix("feed/")))?;
+                        Some(RemoteSubscription {
+                            title: sub["title"].as_str().unwrap_or(url).to_string(),
+                            url: url.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn subscribe(&self, url: &str, title: &str) -> Result<(), String> {
+        let stream = format!("feed/{}", url);
+        let response = self
+            .post("/reader/api/0/subscription/edit")
+            .form(&[("ac", "subscribe"), ("s", stream.as_str()), ("t", title), ("T", self.token.as_str())])
+            .send()
+            .await
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+        checked_text(response).await.map(|_| ())
+    }
+
+    pub async fn reading_list(&self) -> Result<Vec<RemoteItem>, String> {
+        let response = self
+            .get(&format!(
+                "/reader/api/0/stream/contents/user/-/state/com.google/reading-list?output=json&n={}",
+                READING_LIST_LIMIT
+            ))
+            .send()
+            .await
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+        let json: serde_json::Value = serde_json::from_str(&checked_text(response).await?)
+            .map_err(|e| tr(Msg::SyncFailed, e))?;
+
+        Ok(json["items"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let id = item["id"].as_str()?.to_string();
+                        let url = item["canonical"][0]["href"]
+                            .as_str()
+                            .or_else(|| item["alternate"][0]["href"].as_str())?;
+                        let categories: HashSet<&str> = item["categories"]
+                            .as_array()
+                            .map(|c| c.iter().filter_map(|v| v.as_str()).collect())
+                            .unwrap_or_default();
+                        // Servers return either the "user/-/" or the "user/<id>/" form
+                        let has_state = |state: &str| {
+                            let suffix = state.trim_start_matches("user/-");
+                            categories.iter().any(|c| c.starts_with("user/") && c.ends_with(suffix))
+                        };
+                        Some(RemoteItem {
+                            id,
+                            url: normalize_url(url),
+                            is_read: has_state(STATE_READ),
+                            is_starred: has_state(STATE_STARRED),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn set_state(&self, remote_ids: &[String], state: &str, value: bool) -> Result<(), String> {
+        for chunk in remote_ids.chunks(100) {
+            let mut form: Vec<(&str, &str)> = chunk.iter().map(|id| ("i", id.as_str())).collect();
+            form.push((if value { "a" } else { "r" }, state));
+            form.push(("T", self.token.as_str()));
+            let response = self
+                .post("/reader/api/0/edit-tag")
+                .form(&form)
+                .send()
+                .await
+                .map_err(|e| tr(Msg::SyncFailed, e))?;
+            checked_text(response).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn checked_text(response: reqwest::Response) -> Result<String, String> {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(t(Msg::SyncAuthFailed));
+    }
+    if !status.is_success() {
+        return Err(tr(Msg::SyncFailed, format!("({}) {}", status, body)));
+    }
+    Ok(body)
+}
+
+// Decision for one flag given local, remote and last-synced values
+#[derive(Debug, PartialEq, Eq)]
+enum Resolution {
+    InSync,
+    Push(bool),
+    Pull(bool),
+}
+
+fn resolve(local: bool, remote: bool, base: Option<bool>) -> Resolution {
+    if local == remote {
+        return Resolution::InSync;
+    }
+    match base {
+        Some(base) if base == remote => Resolution::Push(local),
+        Some(base) if base == local => Resolution::Pull(remote),
+        // First sync or both sides changed: keep the "true" state
+        _ if local => Resolution::Push(true),
+        _ => Resolution::Pull(true),
+    }
+}
+
+// Local changes decided during reconciliation, applied under the DB lock afterwards
+#[derive(Default)]
+pub struct LocalChanges {
+    pub new_sources: Vec<RemoteSubscription>,
+    pub read: Vec<(String, bool)>,
+    pub starred: Vec<(String, bool)>,
+    pub synced: Vec<(String, String, bool, bool)>,
+}
+
+pub async fn sync(client: &GReaderClient, snapshot: &LocalSnapshot) -> Result<(LocalChanges, SyncReport), String> {
+    let mut report = SyncReport::default();
+    let mut changes = LocalChanges::default();
+
+    // Subscriptions: union in both directions
+    let remote_subs = client.subscriptions().await?;
+    let remote_urls: HashSet<String> = remote_subs.iter().map(|s| normalize_url(&s.url)).collect();
+    let local_urls: HashSet<String> = snapshot.feed_urls.iter().map(|(_, url)| normalize_url(url)).collect();
+
+    for (name, url) in &snapshot.feed_urls {
+        if !remote_urls.contains(&normalize_url(url)) {
+            match client.subscribe(url, name).await {
+                Ok(()) => report.subscriptions_pushed += 1,
+                Err(e) => eprintln!("GReader: failed to subscribe '{}': {}", url, e),
+            }
+        }
+    }
+    for sub in remote_subs {
+        if !local_urls.contains(&normalize_url(&sub.url)) {
+            report.subscriptions_pulled += 1;
+            changes.new_sources.push(sub);
+        }
+    }
+
+    // Item state
+    let mut push_read: HashMap<bool, Vec<String>> = HashMap::new();
+    let mut push_starred: HashMap<bool, Vec<String>> = HashMap::new();
+
+    for item in client.reading_list().await? {
+        let Some(local) = snapshot.articles.get(&item.url) else { continue };
+        report.matched_items += 1;
+
+        let mut is_read = local.is_read;
+        let mut is_starred = local.is_starred;
+
+        match resolve(local.is_read, item.is_read, local.base.map(|b| b.0)) {
+            Resolution::InSync => {}
+            Resolution::Push(value) => push_read.entry(value).or_default().push(item.id.clone()),
+            Resolution::Pull(value) => {
+                is_read = value;
+                changes.read.push((local.article_id.clone(), value));
+                report.read_pulled += 1;
+            }
+        }
+        match resolve(local.is_starred, item.is_starred, local.base.map(|b| b.1)) {
+            Resolution::InSync => {}
+            Resolution::Push(value) => push_starred.entry(value).or_default().push(item.id.clone()),
+            Resolution::Pull(value) => {
+                is_starred = value;
+                changes.starred.push((local.article_id.clone(), value));
+                report.starred_pulled += 1;
+            }
+        }
+
+        changes.synced.push((local.article_id.clone(), item.id, is_read, is_starred));
+    }
+
+    for (value, ids) in push_read {
+        client.set_state(&ids, STATE_READ, value).await?;
+        report.read_pushed += ids.len();
+    }
+    for (value, ids) in push_starred {
+        client.set_state(&ids, STATE_STARRED, value).await?;
+        report.starred_pushed += ids.len();
+    }
+
+    Ok((changes, report))
+}
+
+pub fn apply_local_changes(conn: &Connection, changes: &LocalChanges) -> Result<(), String> {
+    for sub in &changes.new_sources {
+        conn.execute(
+            "INSERT OR IGNORE INTO sources (id, name, url, source_type, is_active) VALUES (?1, ?2, ?3, 'RSS', 1)",
+            params![uuid::Uuid::new_v4().to_string(), sub.title, sub.url],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    for (id, value) in &changes.read {
+        conn.execute("UPDATE articles SET is_read = ?1 WHERE id = ?2", params![*value as i32, id])
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    }
+    for (id, value) in &changes.starred {
+        conn.execute("UPDATE articles SET is_bookmarked = ?1 WHERE id = ?2", params![*value as i32, id])
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    for (article_id, remote_id, is_read, is_starred) in &changes.synced {
+        conn.execute(
+            "INSERT OR REPLACE INTO greader_items (article_id, remote_id, is_read, is_starred, synced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![article_id, remote_id, *is_read as i32, *is_starred as i32, now],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_side_that_changed_since_the_last_sync_wins() {
+        assert_eq!(resolve(true, true, None), Resolution::InSync);
+        assert_eq!(resolve(true, false, Some(false)), Resolution::Push(true));
+        assert_eq!(resolve(false, true, Some(false)), Resolution::Pull(true));
+        assert_eq!(resolve(false, true, Some(true)), Resolution::Push(false));
+        assert_eq!(resolve(true, false, Some(true)), Resolution::Pull(false));
+        // First sync: the "true" state is kept whichever side has it
+        assert_eq!(resolve(true, false, None), Resolution::Push(true));
+        assert_eq!(resolve(false, true, None), Resolution::Pull(true));
+    }
+
+    #[test]
+    fn applied_changes_become_the_next_base() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, is_read, is_bookmarked) VALUES ('a1', 'Post', 'https://example.com/post', 0, 1)",
+            [],
+        )
+        .unwrap();
+        let before = local_snapshot(&conn).unwrap();
+        assert!(before.articles["https://example.com/post"].base.is_none());
+
+        let changes = LocalChanges {
+            new_sources: vec![RemoteSubscription { title: "Remote Feed".to_string(), url: "https://remote.example.com/feed".to_string() }],
+            read: vec![("a1".to_string(), true)],
+            starred: vec![],
+            synced: vec![("a1".to_string(), "tag:remote/1".to_string(), true, true)],
+        };
+        apply_local_changes(&conn, &changes).unwrap();
+
+        let after = local_snapshot(&conn).unwrap();
+        let item = &after.articles["https://example.com/post"];
+        assert!(item.is_read && item.is_starred);
+        assert_eq!(item.base, Some((true, true)));
+        assert!(after.feed_urls.contains(&("Remote Feed".to_string(), "https://remote.example.com/feed".to_string())));
+    }
+}
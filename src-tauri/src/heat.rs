@@ -0,0 +1,308 @@
+// Heat scoring: the 0-100 `articles.heat_score` used for trending lists,
+// digests and the heat indicator.
+//
+// The score is the sum of six components, each explainable on its own
+// (`article_heat_breakdown`):
+// - engagement: stars / points the source reported (`articles.engagement`), log-scaled
+// - recency: decays with age, halving every `half_life_hours`
+// - AI importance: AI category plus major labs, models and release words in the title
+// - keyword boost: the user's weighted keywords (negative weights push down)
+// - feedback: source and keyword weights learned from "more / less like this"
+// - rules: points added by `boost_heat` actions of incoming-article rules (`rules.rs`)
+// New articles are scored after each crawl and recent ones are rescored
+// hourly by the scheduler so recency stays current.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::feedback::LearnedWeights;
+use crate::i18n::{t, tr, Msg};
+use crate::watchlists::mentions;
+use crate::{get_setting, set_setting};
+
+const ENGAGEMENT_MAX: f64 = 35.0;
+// Engagement at which the component is maxed out
+const ENGAGEMENT_CEILING: f64 = 100_000.0;
+const RECENCY_MAX: f64 = 30.0;
+const CATEGORY_AI_POINTS: f64 = 10.0;
+const IMPORTANT_TERM_POINTS: f64 = 5.0;
+const IMPORTANT_TERMS_MAX: f64 = 15.0;
+const KEYWORD_BOOST_LIMIT: f64 = 20.0;
+const FEEDBACK_LIMIT: f64 = 20.0;
+pub const RULE_BOOST_LIMIT: f64 = 50.0;
+
+// Articles published within this many days are rescored periodically
+pub const RESCORE_DAYS: i64 = 7;
+
+const IMPORTANT_TERMS: &[&str] = &[
+    "openai", "anthropic", "deepmind", "google", "meta", "microsoft", "nvidia", "xai", "gpt", "claude", "gemini",
+    "llama", "deepseek", "qwen", "mistral", "agi", "open source", "open-source", "release", "launch", "benchmark",
+    "state-of-the-art", "开源", "发布", "大模型", "智能体", "推理",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordWeight {
+    pub keyword: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeatConfig {
+    pub half_life_hours: f64,
+    pub keyword_weights: Vec<KeywordWeight>,
+}
+
+impl Default for HeatConfig {
+    fn default() -> Self {
+        HeatConfig {
+            half_life_hours: 24.0,
+            keyword_weights: Vec::new(),
+        }
+    }
+}
+
+impl HeatConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = HeatConfig::default();
+        Ok(HeatConfig {
+            half_life_hours: get_setting(conn, "heat.half_life_hours", "")?
+                .parse()
+                .ok()
+                .filter(|h: &f64| *h > 0.0)
+                .unwrap_or(defaults.half_life_hours),
+            keyword_weights: serde_json::from_str(&get_setting(conn, "heat.keyword_weights", "[]")?).unwrap_or_default(),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let weights: Vec<KeywordWeight> = self
+            .keyword_weights
+            .iter()
+            .map(|w| KeywordWeight { keyword: w.keyword.trim().to_string(), weight: w.weight })
+            .filter(|w| !w.keyword.is_empty() && w.weight != 0.0)
+            .collect();
+        set_setting(conn, "heat.half_life_hours", &self.half_life_hours.max(1.0).to_string())?;
+        set_setting(conn, "heat.keyword_weights", &serde_json::to_string(&weights).unwrap_or_else(|_| "[]".to_string()))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeatBreakdown {
+    pub engagement: f64,
+    pub recency: f64,
+    pub ai_importance: f64,
+    pub keyword_boost: f64,
+    pub feedback: f64,
+    pub rules: f64,
+    // Sum of the components, clamped to 0-100
+    pub total: f64,
+}
+
+// The fields scoring looks at; `published_at` is the stored UTC value
+pub struct HeatInput {
+    pub title: String,
+    pub summary: String,
+    pub source: String,
+    pub category: String,
+    pub published_at: String,
+    pub engagement: Option<i64>,
+    pub rule_boost: f64,
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+pub fn score(input: &HeatInput, config: &HeatConfig, learned: &LearnedWeights, now: chrono::DateTime<chrono::Utc>) -> HeatBreakdown {
+    let engagement = input
+        .engagement
+        .filter(|n| *n > 0)
+        .map(|n| ENGAGEMENT_MAX * ((1.0 + n as f64).ln() / (1.0 + ENGAGEMENT_CEILING).ln()).min(1.0))
+        .unwrap_or(0.0);
+
+    let recency = chrono::DateTime::parse_from_rfc3339(&input.published_at)
+        .map(|published| {
+            let age_hours = (now - published.with_timezone(&chrono::Utc)).num_minutes().max(0) as f64 / 60.0;
+            RECENCY_MAX * 0.5f64.powf(age_hours / config.half_life_hours)
+        })
+        .unwrap_or(0.0);
+
+    let title = input.title.to_lowercase();
+    let terms = IMPORTANT_TERMS.iter().filter(|term| mentions(&title, term)).count() as f64;
+    let category = if input.category.eq_ignore_ascii_case("AI") { CATEGORY_AI_POINTS } else { 0.0 };
+    let ai_importance = category + (terms * IMPORTANT_TERM_POINTS).min(IMPORTANT_TERMS_MAX);
+
+    let text = format!("{} {}", input.title, input.summary).to_lowercase();
+    let keyword_boost = config
+        .keyword_weights
+        .iter()
+        .filter(|w| mentions(&text, &w.keyword.to_lowercase()))
+        .map(|w| w.weight)
+        .sum::<f64>()
+        .clamp(-KEYWORD_BOOST_LIMIT, KEYWORD_BOOST_LIMIT);
+
+    let feedback = learned.score(&input.source, &input.title).clamp(-FEEDBACK_LIMIT, FEEDBACK_LIMIT);
+    let rules = input.rule_boost.clamp(-RULE_BOOST_LIMIT, RULE_BOOST_LIMIT);
+
+    HeatBreakdown {
+        engagement: round(engagement),
+        recency: round(recency),
+        ai_importance: round(ai_importance),
+        keyword_boost: round(keyword_boost),
+        feedback: round(feedback),
+        rules: round(rules),
+        total: round((engagement + recency + ai_importance + keyword_boost + feedback + rules).clamp(0.0, 100.0)),
+    }
+}
+
+const INPUT_COLUMNS: &str =
+    "id, title, COALESCE(summary, ''), source, category, published_at, engagement, COALESCE(rule_boost, 0)";
+
+fn input_from_row(row: &rusqlite::Row) -> rusqlite::Result<(String, HeatInput)> {
+    Ok((
+        row.get(0)?,
+        HeatInput {
+            title: row.get(1)?,
+            summary: row.get(2)?,
+            source: row.get(3)?,
+            category: row.get(4)?,
+            published_at: row.get(5)?,
+            engagement: row.get(6)?,
+            rule_boost: row.get(7)?,
+        },
+    ))
+}
+
+fn store_scores(conn: &Connection, inputs: Vec<(String, HeatInput)>) -> Result<usize, String> {
+    let config = HeatConfig::load(conn)?;
+    let learned = LearnedWeights::load(conn)?;
+    let now = chrono::Utc::now();
+    let mut update = conn
+        .prepare_cached("UPDATE articles SET heat_score = ?1 WHERE id = ?2")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    for (id, input) in &inputs {
+        update
+            .execute(params![score(input, &config, &learned, now).total, id])
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    }
+    Ok(inputs.len())
+}
+
+pub fn rescore_ids(conn: &Connection, ids: &[String]) -> Result<usize, String> {
+    let sql = format!("SELECT {} FROM articles WHERE id = ?1", INPUT_COLUMNS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let mut inputs = Vec::new();
+    for id in ids {
+        if let Some(input) = stmt.query_row(params![id], input_from_row).optional().map_err(|e| tr(Msg::DbQueryFailed, e))? {
+            inputs.push(input);
+        }
+    }
+    store_scores(conn, inputs)
+}
+
+// Rescore everything published in the last `days` days (recency changes with time)
+pub fn rescore_recent(conn: &Connection, days: i64) -> Result<usize, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let sql = format!("SELECT {} FROM articles WHERE published_at >= ?1", INPUT_COLUMNS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let inputs = stmt
+        .query_map(params![since], input_from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    store_scores(conn, inputs)
+}
+
+// Components of an article's score as of now
+pub fn breakdown(conn: &Connection, id: &str) -> Result<HeatBreakdown, String> {
+    let sql = format!("SELECT {} FROM articles WHERE id = ?1", INPUT_COLUMNS);
+    let (_, input) = conn
+        .query_row(&sql, params![id], input_from_row)
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .ok_or_else(|| t(Msg::ArticleNotFound))?;
+    Ok(score(&input, &HeatConfig::load(conn)?, &LearnedWeights::load(conn)?, chrono::Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(title: &str, published_at: &str, engagement: Option<i64>) -> HeatInput {
+        HeatInput {
+            title: title.to_string(),
+            summary: String::new(),
+            source: "Blog".to_string(),
+            category: "AI".to_string(),
+            published_at: published_at.to_string(),
+            engagement,
+            rule_boost: 0.0,
+        }
+    }
+
+    fn now() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339("2025-03-02T12:00:00Z").unwrap().to_utc()
+    }
+
+    #[test]
+    fn components_add_up_and_stay_in_bounds() {
+        let config = HeatConfig::default();
+        let learned = LearnedWeights::default();
+        let fresh = score(&input("OpenAI and Anthropic launch a benchmark release", "2025-03-02T12:00:00+00:00", Some(100_000)), &config, &learned, now());
+        assert_eq!(fresh.engagement, ENGAGEMENT_MAX);
+        assert_eq!(fresh.recency, RECENCY_MAX);
+        assert_eq!(fresh.ai_importance, CATEGORY_AI_POINTS + IMPORTANT_TERMS_MAX, "term points are capped");
+        assert_eq!(fresh.total, 90.0);
+
+        let day_old = score(&input("Quiet update", "2025-03-01T12:00:00+00:00", None), &config, &learned, now());
+        assert_eq!((day_old.engagement, day_old.recency), (0.0, RECENCY_MAX / 2.0), "halves every 24 hours");
+        let undated = score(&input("Quiet update", "not a date", Some(-5)), &config, &learned, now());
+        assert_eq!((undated.engagement, undated.recency), (0.0, 0.0));
+
+        let mut buried = input("Crypto metaverse update", "2025-03-01T12:00:00+00:00", None);
+        buried.category = "Tech".to_string();
+        buried.rule_boost = -500.0;
+        let config = HeatConfig {
+            keyword_weights: vec![KeywordWeight { keyword: "Crypto".into(), weight: -15.0 }, KeywordWeight { keyword: "metaverse".into(), weight: -15.0 }],
+            ..HeatConfig::default()
+        };
+        let buried = score(&buried, &config, &learned, now());
+        assert_eq!(buried.keyword_boost, -KEYWORD_BOOST_LIMIT);
+        assert_eq!(buried.rules, -RULE_BOOST_LIMIT);
+        assert_eq!(buried.total, 0.0);
+    }
+
+    #[test]
+    fn config_drops_empty_weights_and_rescoring_stores_the_total() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        HeatConfig {
+            half_life_hours: 0.0,
+            keyword_weights: vec![
+                KeywordWeight { keyword: " agents ".into(), weight: 5.0 },
+                KeywordWeight { keyword: "".into(), weight: 3.0 },
+                KeywordWeight { keyword: "noise".into(), weight: 0.0 },
+            ],
+        }
+        .save(&conn)
+        .unwrap();
+        let config = HeatConfig::load(&conn).unwrap();
+        assert_eq!(config.half_life_hours, 1.0);
+        assert_eq!(config.keyword_weights.len(), 1);
+        assert_eq!(config.keyword_weights[0].keyword, "agents");
+
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, url, source, category, published_at, engagement)
+             VALUES ('a1', 'Agents everywhere', '', 'https://example.com/a1', 'Blog', 'AI', ?1, 10)",
+            params![chrono::Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        assert_eq!(rescore_ids(&conn, &["a1".to_string(), "missing".to_string()]).unwrap(), 1);
+        let stored: f64 = conn.query_row("SELECT heat_score FROM articles WHERE id = 'a1'", [], |row| row.get(0)).unwrap();
+        let parts = breakdown(&conn, "a1").unwrap();
+        assert_eq!(parts.keyword_boost, 5.0);
+        assert!((stored - parts.total).abs() < 1.0);
+        assert!(breakdown(&conn, "missing").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+// Response cache for page and feed fetches (URL → body, validators, time).
+//
+// A copy younger than the caller's TTL is returned without touching the
+// network; an older one is revalidated with If-None-Match /
+// If-Modified-Since, so an unchanged page costs a 304 instead of a full
+// download. Only successful responses are stored. Requests that run out
+// of time fail with `Error::Timeout`, which crawls retry once at the end.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::Error;
+use crate::i18n::{tr, Msg};
+
+// Crawls and retries within this window reuse the feed as fetched
+pub const FEED_TTL: Duration = Duration::from_secs(10 * 60);
+
+// Article pages rarely change once published
+pub const PAGE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Entries not refreshed for this long are dropped by `purge`
+const MAX_AGE_DAYS: i64 = 7;
+
+// Bigger bodies are passed through uncached
+const MAX_BODY_BYTES: usize = 5 * 1024 * 1024;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS http_cache (
+            url TEXT PRIMARY KEY,
+            status INTEGER NOT NULL,
+            content_type TEXT,
+            etag TEXT,
+            last_modified TEXT,
+            body TEXT NOT NULL,
+            fetched_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub struct Fetched {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Fetched {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+struct Entry {
+    status: u16,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+    fetched_at: i64,
+}
+
+fn lookup(conn: &Connection, url: &str) -> Option<Entry> {
+    conn.query_row(
+        "SELECT status, etag, last_modified, body, fetched_at FROM http_cache WHERE url = ?1",
+        params![url],
+        |row| {
+            Ok(Entry {
+                status: row.get(0)?,
+                etag: row.get(1)?,
+                last_modified: row.get(2)?,
+                body: row.get(3)?,
+                fetched_at: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+// `failed` for most request errors, `Timeout` when the request ran out of time
+fn request_error(failed: fn(reqwest::Error) -> Error, e: reqwest::Error) -> Error {
+    if e.is_timeout() { Error::Timeout(e) } else { failed(e) }
+}
+
+// Send `request` (a GET for `url`) unless a copy younger than `ttl` is cached
+pub async fn fetch_text(
+    db: &Mutex<Connection>,
+    url: &str,
+    request: reqwest::RequestBuilder,
+    ttl: Duration,
+) -> Result<Fetched, Error> {
+    let now = chrono::Utc::now().timestamp();
+    let cached = {
+        let conn = db.lock()?;
+        lookup(&conn, url)
+    }; // Release the lock during the request
+
+    let mut request = request;
+    if let Some(entry) = &cached {
+        if now - entry.fetched_at < ttl.as_secs() as i64 {
+            return Ok(Fetched { status: entry.status, body: entry.body.clone() });
+        }
+        if let Some(etag) = &entry.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await.map_err(|e| request_error(Error::Request, e))?;
+    let status = response.status();
+
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(entry) = cached {
+            if let Ok(conn) = db.lock() {
+                let _ = conn.execute("UPDATE http_cache SET fetched_at = ?1 WHERE url = ?2", params![now, url]);
+            }
+            return Ok(Fetched { status: entry.status, body: entry.body });
+        }
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE);
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    let body = response.text().await.map_err(|e| request_error(Error::ReadContent, e))?;
+
+    if status.is_success() && body.len() <= MAX_BODY_BYTES {
+        if let Ok(conn) = db.lock() {
+            let _ = conn.execute(
+                "INSERT OR REPLACE INTO http_cache (url, status, content_type, etag, last_modified, body, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![url, status.as_u16(), content_type, etag, last_modified, &body, now],
+            );
+        }
+    }
+
+    Ok(Fetched { status: status.as_u16(), body })
+}
+
+// Drop entries that haven't been fetched or revalidated recently
+pub fn purge(conn: &Connection) -> Result<usize, String> {
+    let cutoff = chrono::Utc::now().timestamp() - MAX_AGE_DAYS * 24 * 60 * 60;
+    conn.execute("DELETE FROM http_cache WHERE fetched_at < ?1", params![cutoff])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+// Drop every entry (storage quota)
+pub fn clear(conn: &Connection) -> Result<usize, String> {
+    conn.execute("DELETE FROM http_cache", []).map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn store(conn: &Connection, url: &str, fetched_at: i64) {
+        conn.execute(
+            "INSERT OR REPLACE INTO http_cache (url, status, etag, body, fetched_at) VALUES (?1, 200, '\"v1\"', 'cached body', ?2)",
+            params![url, fetched_at],
+        )
+        .unwrap();
+    }
+
+    // Answers one request with `response` and hands back the request it got
+    fn serve_once(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let n = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn fresh_copies_skip_the_network_and_stale_ones_revalidate() {
+        let db = Mutex::new(crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap());
+        let client = reqwest::Client::new();
+        let now = chrono::Utc::now().timestamp();
+
+        // Nothing listens on the discard port, so only the cache can answer
+        let offline = "http://127.0.0.1:9/feed.xml";
+        store(&db.lock().unwrap(), offline, now);
+        let fetched = tauri::async_runtime::block_on(fetch_text(&db, offline, client.get(offline), FEED_TTL)).unwrap();
+        assert_eq!((fetched.status, fetched.body.as_str()), (200, "cached body"));
+
+        let (url, server) = serve_once("HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        store(&db.lock().unwrap(), &url, now - 3600);
+        let fetched = tauri::async_runtime::block_on(fetch_text(&db, &url, client.get(&url), FEED_TTL)).unwrap();
+        assert_eq!(fetched.body, "cached body");
+        assert!(server.join().unwrap().contains("if-none-match: \"v1\""));
+        let refreshed: i64 = db
+            .lock()
+            .unwrap()
+            .query_row("SELECT fetched_at FROM http_cache WHERE url = ?1", params![url], |row| row.get(0))
+            .unwrap();
+        assert!(refreshed >= now);
+    }
+
+    #[test]
+    fn errors_are_not_cached_and_old_entries_are_purged() {
+        let db = Mutex::new(crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap());
+        let (url, server) = serve_once("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbusy");
+        let fetched = tauri::async_runtime::block_on(fetch_text(&db, &url, reqwest::Client::new().get(&url), FEED_TTL)).unwrap();
+        server.join().unwrap();
+        assert!(!fetched.is_success());
+
+        let conn = db.lock().unwrap();
+        assert!(lookup(&conn, &url).is_none());
+        store(&conn, "https://example.com/old", chrono::Utc::now().timestamp() - (MAX_AGE_DAYS + 1) * 24 * 60 * 60);
+        store(&conn, "https://example.com/new", chrono::Utc::now().timestamp());
+        assert_eq!(purge(&conn).unwrap(), 1);
+        assert_eq!(clear(&conn).unwrap(), 1);
+    }
+}
@@ -0,0 +1,432 @@
+// Localized command errors and user-facing messages.
+//
+// The active language is kept in a process-wide atomic so any code path
+// (including lock-poisoned errors where the settings table is unreachable)
+// can produce a message without threading extra state around.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Zh,
+    En,
+}
+
+impl Lang {
+    // Unknown codes fall back to Chinese, the app's original language
+    pub fn from_code(code: &str) -> Lang {
+        if code.trim().to_lowercase().starts_with("en") {
+            Lang::En
+        } else {
+            Lang::Zh
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::Zh => "zh",
+            Lang::En => "en",
+        }
+    }
+}
+
+static CURRENT_LANG: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_lang(lang: Lang) {
+    let value = match lang {
+        Lang::Zh => 0,
+        Lang::En => 1,
+    };
+    CURRENT_LANG.store(value, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    match CURRENT_LANG.load(Ordering::Relaxed) {
+        1 => Lang::En,
+        _ => Lang::Zh,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    // Database
+    DbLockPoisoned,
+    DbPrepareFailed,
+    DbQueryFailed,
+    DbCollectFailed,
+    DbInsertFailed,
+    DbUpdateFailed,
+    DbDeleteFailed,
+    FtsInsertFailed,
+    FtsDeleteFailed,
+    HomeDirUnknown,
+    CreateDirFailed,
+    WriteFileFailed,
+    ReadFileFailed,
+    DbOpenReadOnlyFailed,
+
+    // Articles
+    ArticleNotFound,
+    LinkExists,
+    ReadContentFailed,
+    InvalidDateTime,
+    TrashRestoreConflict,
+
+    // HTTP / crawler
+    HttpRequestFailed,
+    HttpTimeout,
+    ProxyUnavailable,
+    HttpClientFailed,
+    InvalidSelector,
+
+    // AI
+    AiBaseUrlMissing,
+    AiApiKeyMissing,
+    AiNotConfigured,
+    AiRequestFailed,
+    AiErrorResponse,
+    AiParseFailed,
+    AiBadFormat,
+    AiEmbeddingsUnsupported,
+
+    // Export
+    FeedRenderFailed,
+    ObsidianVaultMissing,
+    ExportNothingSelected,
+    EpubBuildFailed,
+    FontNotFound,
+    ShareCardRenderFailed,
+    PdfRenderFailed,
+    ShareCardScanHint,
+    NoPapersSelected,
+    ZoteroNotRunning,
+    ZoteroSaveFailed,
+
+    // Import
+    BookmarksParseFailed,
+    SourcePackInvalid,
+
+    // Sources
+    FolderNotFound,
+    FolderCycle,
+    WatchNameMissing,
+    RuleNameMissing,
+    RuleInvalid,
+    NoUrlsGiven,
+    SourceExists,
+    SourceTypeUnsupported,
+    TrendingConfigInvalid,
+    UiStateInvalid,
+
+    // Profiles
+    ProfileNotFound,
+    ProfileNameMissing,
+
+    // Feature flags
+    FeatureUnknown,
+
+    // Comment highlights
+    CommentsNotFound,
+
+    // Read-later services
+    ReadLaterNotConfigured,
+    ReadLaterSendFailed,
+    PocketAuthNotStarted,
+
+    // Sync
+    SyncNotConfigured,
+    SyncAuthFailed,
+    SyncFailed,
+    SyncPassphraseMissing,
+    SyncEncryptFailed,
+    SyncDecryptFailed,
+    DbRestoreFailed,
+    DbBackupFailed,
+
+    // Webhooks
+    WebhookNotFound,
+    WebhookSendFailed,
+
+    // Email / digest
+    SmtpNotConfigured,
+    EmailNoRecipients,
+    InvalidEmailAddress,
+    EmailBuildFailed,
+    EmailSendFailed,
+    DigestTitle,
+    DigestEmpty,
+    DigestNotFound,
+    WeeklyTitle,
+    WeeklyArticles,
+    WeeklyMore,
+    StarReportTitle,
+    StarReportEmpty,
+    StarReportSince,
+    StarReportMilestone,
+    KindleNotConfigured,
+    KindleTooLarge,
+
+    // System
+    OpenUrlFailed,
+    InvalidUrl,
+    UrlSchemeNotAllowed,
+    ReadOnlyMode,
+    JobQueuedOffline,
+    LocalApiBindFailed,
+    StdinReadFailed,
+    StdoutWriteFailed,
+}
+
+impl Msg {
+    pub fn text(self, lang: Lang) -> &'static str {
+        match lang {
+            Lang::Zh => self.zh(),
+            Lang::En => self.en(),
+        }
+    }
+
+    fn zh(self) -> &'static str {
+        match self {
+            Msg::DbLockPoisoned => "数据库锁异常",
+            Msg::DbPrepareFailed => "SQL 预处理失败",
+            Msg::DbQueryFailed => "查询失败",
+            Msg::DbCollectFailed => "读取查询结果失败",
+            Msg::DbInsertFailed => "插入失败",
+            Msg::DbUpdateFailed => "更新失败",
+            Msg::DbDeleteFailed => "删除失败",
+            Msg::FtsInsertFailed => "FTS 插入失败",
+            Msg::FtsDeleteFailed => "FTS 删除失败",
+            Msg::HomeDirUnknown => "无法确定用户主目录",
+            Msg::CreateDirFailed => "创建目录失败",
+            Msg::WriteFileFailed => "写入文件失败",
+            Msg::ReadFileFailed => "读取文件失败",
+            Msg::DbOpenReadOnlyFailed => "以只读方式打开数据库失败",
+            Msg::ArticleNotFound => "文章不存在",
+            Msg::LinkExists => "该链接已存在",
+            Msg::ReadContentFailed => "读取内容失败",
+            Msg::InvalidDateTime => "无法识别的时间格式",
+            Msg::TrashRestoreConflict => "信息流中已有相同链接的文章",
+            Msg::HttpRequestFailed => "HTTP 请求失败",
+            Msg::HttpTimeout => "HTTP 请求超时",
+            Msg::ProxyUnavailable => "代理不可用，已跳过",
+            Msg::HttpClientFailed => "创建 HTTP 客户端失败",
+            Msg::InvalidSelector => "无效的选择器",
+            Msg::AiBaseUrlMissing => "请先在设置中配置 AI API Base URL",
+            Msg::AiApiKeyMissing => "请先在设置中配置 AI API Key",
+            Msg::AiNotConfigured => "请先在设置中配置 AI API (Base URL 和 API Key)，或确保 .env 文件中有正确的配置",
+            Msg::AiRequestFailed => "API 请求失败",
+            Msg::AiErrorResponse => "API 返回错误",
+            Msg::AiParseFailed => "解析响应失败",
+            Msg::AiBadFormat => "API 响应格式错误",
+            Msg::AiEmbeddingsUnsupported => "该 AI 服务不提供向量嵌入接口",
+            Msg::FeedRenderFailed => "生成订阅源失败",
+            Msg::ExportNothingSelected => "没有可导出的文章",
+            Msg::EpubBuildFailed => "生成 EPUB 失败",
+            Msg::FontNotFound => "未找到可用字体，请在设置中指定字体文件",
+            Msg::ShareCardRenderFailed => "生成分享卡片失败",
+            Msg::PdfRenderFailed => "生成 PDF 失败",
+            Msg::ShareCardScanHint => "扫码阅读原文",
+            Msg::NoPapersSelected => "所选文章中没有论文",
+            Msg::ZoteroNotRunning => "无法连接 Zotero，请先启动 Zotero 桌面版",
+            Msg::ZoteroSaveFailed => "保存到 Zotero 失败",
+            Msg::BookmarksParseFailed => "无法解析书签文件",
+            Msg::SourcePackInvalid => "不是有效的订阅源包",
+            Msg::FolderNotFound => "文件夹不存在",
+            Msg::FolderCycle => "不能把文件夹移到它自己或其子文件夹下",
+            Msg::WatchNameMissing => "请填写关注项名称",
+            Msg::RuleNameMissing => "请填写规则名称",
+            Msg::RuleInvalid => "规则无效",
+            Msg::NoUrlsGiven => "请至少填写一个链接",
+            Msg::SourceExists => "该订阅源已存在",
+            Msg::SourceTypeUnsupported => "不支持的订阅源类型",
+            Msg::TrendingConfigInvalid => "GitHub 热门配置无效",
+            Msg::UiStateInvalid => "界面状态无效",
+            Msg::ProfileNotFound => "配置档案不存在",
+            Msg::ProfileNameMissing => "请填写配置档案名称",
+            Msg::FeatureUnknown => "未知的功能开关",
+            Msg::CommentsNotFound => "这篇资讯没有可读取的讨论评论",
+            Msg::ObsidianVaultMissing => "请先在设置中配置 Obsidian 仓库路径",
+            Msg::ReadLaterNotConfigured => "请先在设置中配置该稍后读服务",
+            Msg::ReadLaterSendFailed => "发送到稍后读服务失败",
+            Msg::PocketAuthNotStarted => "请先发起 Pocket 授权",
+            Msg::SyncNotConfigured => "请先在设置中配置同步服务",
+            Msg::SyncAuthFailed => "同步服务认证失败，请检查用户名和密码",
+            Msg::SyncFailed => "同步失败",
+            Msg::SyncPassphraseMissing => "请先设置同步加密口令",
+            Msg::SyncEncryptFailed => "加密同步数据失败",
+            Msg::SyncDecryptFailed => "解密同步数据失败，请检查加密口令",
+            Msg::DbRestoreFailed => "恢复数据库失败",
+            Msg::DbBackupFailed => "备份数据库失败",
+            Msg::WebhookNotFound => "Webhook 不存在",
+            Msg::WebhookSendFailed => "Webhook 推送失败",
+            Msg::SmtpNotConfigured => "请先在设置中配置 SMTP 服务器和发件人",
+            Msg::EmailNoRecipients => "请先设置收件人",
+            Msg::InvalidEmailAddress => "邮箱地址无效",
+            Msg::EmailBuildFailed => "生成邮件失败",
+            Msg::EmailSendFailed => "发送邮件失败",
+            Msg::DigestTitle => "AI 资讯摘要",
+            Msg::DigestEmpty => "这段时间没有新的资讯",
+            Msg::DigestNotFound => "摘要不存在",
+            Msg::WeeklyTitle => "AI 资讯周报",
+            Msg::WeeklyArticles => "篇文章",
+            Msg::WeeklyMore => "更多资讯",
+            Msg::StarReportTitle => "GitHub 星标增长报告",
+            Msg::StarReportEmpty => "这段时间没有仓库达到设定的阈值",
+            Msg::StarReportSince => "对比",
+            Msg::StarReportMilestone => "突破",
+            Msg::KindleNotConfigured => "请先在 SMTP 设置中填写 Kindle 接收邮箱",
+            Msg::KindleTooLarge => "单篇文章过大，无法发送到 Kindle",
+            Msg::OpenUrlFailed => "打开链接失败",
+            Msg::InvalidUrl => "链接格式无效",
+            Msg::UrlSchemeNotAllowed => "只能打开 http(s) 链接",
+            Msg::ReadOnlyMode => "数据库以只读模式打开，无法执行此操作",
+            Msg::JobQueuedOffline => "当前处于离线状态，操作已加入队列，联网后自动执行",
+            Msg::LocalApiBindFailed => "本地 API 端口绑定失败",
+            Msg::StdinReadFailed => "读取标准输入失败",
+            Msg::StdoutWriteFailed => "写入标准输出失败",
+        }
+    }
+
+    fn en(self) -> &'static str {
+        match self {
+            Msg::DbLockPoisoned => "Database lock poisoned",
+            Msg::DbPrepareFailed => "Failed to prepare query",
+            Msg::DbQueryFailed => "Query failed",
+            Msg::DbCollectFailed => "Failed to read query results",
+            Msg::DbInsertFailed => "Insert failed",
+            Msg::DbUpdateFailed => "Update failed",
+            Msg::DbDeleteFailed => "Delete failed",
+            Msg::FtsInsertFailed => "Failed to update search index",
+            Msg::FtsDeleteFailed => "Failed to remove from search index",
+            Msg::HomeDirUnknown => "Cannot determine home directory",
+            Msg::CreateDirFailed => "Failed to create directory",
+            Msg::WriteFileFailed => "Failed to write file",
+            Msg::ReadFileFailed => "Failed to read file",
+            Msg::DbOpenReadOnlyFailed => "Failed to open the database read-only",
+            Msg::ArticleNotFound => "Article not found",
+            Msg::LinkExists => "This link already exists",
+            Msg::ReadContentFailed => "Failed to read page content",
+            Msg::InvalidDateTime => "Unrecognized date/time",
+            Msg::TrashRestoreConflict => "An article with this URL is already in the feed",
+            Msg::HttpRequestFailed => "HTTP request failed",
+            Msg::HttpTimeout => "HTTP request timed out",
+            Msg::ProxyUnavailable => "Proxy unavailable, skipped",
+            Msg::HttpClientFailed => "Failed to create HTTP client",
+            Msg::InvalidSelector => "Invalid selector",
+            Msg::AiBaseUrlMissing => "Please configure the AI API Base URL in settings first",
+            Msg::AiApiKeyMissing => "Please configure the AI API Key in settings first",
+            Msg::AiNotConfigured => "Please configure the AI API (Base URL and API Key) in settings, or make sure your .env file is set up correctly",
+            Msg::AiRequestFailed => "API request failed",
+            Msg::AiErrorResponse => "API returned an error",
+            Msg::AiParseFailed => "Failed to parse response",
+            Msg::AiBadFormat => "Unexpected API response format",
+            Msg::AiEmbeddingsUnsupported => "This AI provider has no embeddings endpoint",
+            Msg::FeedRenderFailed => "Failed to render feed",
+            Msg::ExportNothingSelected => "No articles to export",
+            Msg::EpubBuildFailed => "Failed to build EPUB",
+            Msg::FontNotFound => "No usable font found, set a font file in settings",
+            Msg::ShareCardRenderFailed => "Failed to render share card",
+            Msg::PdfRenderFailed => "Failed to render PDF",
+            Msg::ShareCardScanHint => "Scan to read the article",
+            Msg::NoPapersSelected => "No papers among the selected articles",
+            Msg::ZoteroNotRunning => "Cannot reach Zotero, start the Zotero desktop app first",
+            Msg::ZoteroSaveFailed => "Failed to save to Zotero",
+            Msg::BookmarksParseFailed => "Failed to parse bookmarks file",
+            Msg::SourcePackInvalid => "Not a valid source pack",
+            Msg::FolderNotFound => "Folder not found",
+            Msg::FolderCycle => "A folder can't be moved into itself or one of its subfolders",
+            Msg::WatchNameMissing => "Please enter a name for the watch",
+            Msg::RuleNameMissing => "Please enter a name for the rule",
+            Msg::RuleInvalid => "Invalid rule",
+            Msg::NoUrlsGiven => "Enter at least one URL",
+            Msg::SourceExists => "Source already exists",
+            Msg::SourceTypeUnsupported => "Unsupported source type",
+            Msg::TrendingConfigInvalid => "Invalid GitHub trending configuration",
+            Msg::UiStateInvalid => "Invalid UI state",
+            Msg::ProfileNotFound => "Profile not found",
+            Msg::ProfileNameMissing => "Enter a profile name",
+            Msg::FeatureUnknown => "Unknown feature flag",
+            Msg::CommentsNotFound => "This article has no discussion comments to read",
+            Msg::ObsidianVaultMissing => "Please configure the Obsidian vault path in settings first",
+            Msg::ReadLaterNotConfigured => "Please configure this read-later service in settings first",
+            Msg::ReadLaterSendFailed => "Failed to send to read-later service",
+            Msg::PocketAuthNotStarted => "Please start Pocket authorization first",
+            Msg::SyncNotConfigured => "Please configure the sync service in settings first",
+            Msg::SyncAuthFailed => "Sync service authentication failed, check username and password",
+            Msg::SyncFailed => "Sync failed",
+            Msg::SyncPassphraseMissing => "Please set a sync encryption passphrase first",
+            Msg::SyncEncryptFailed => "Failed to encrypt sync data",
+            Msg::SyncDecryptFailed => "Failed to decrypt sync data, check the passphrase",
+            Msg::DbRestoreFailed => "Failed to restore database",
+            Msg::DbBackupFailed => "Failed to back up database",
+            Msg::WebhookNotFound => "Webhook not found",
+            Msg::WebhookSendFailed => "Webhook delivery failed",
+            Msg::SmtpNotConfigured => "Please configure the SMTP server and sender in settings first",
+            Msg::EmailNoRecipients => "Please set at least one recipient",
+            Msg::InvalidEmailAddress => "Invalid email address",
+            Msg::EmailBuildFailed => "Failed to build email",
+            Msg::EmailSendFailed => "Failed to send email",
+            Msg::DigestTitle => "AI News Digest",
+            Msg::DigestEmpty => "No new articles in this period",
+            Msg::DigestNotFound => "Digest not found",
+            Msg::WeeklyTitle => "AI News Weekly",
+            Msg::WeeklyArticles => "articles",
+            Msg::WeeklyMore => "More stories",
+            Msg::StarReportTitle => "GitHub Star Gains",
+            Msg::StarReportEmpty => "No repositories crossed the thresholds in this period",
+            Msg::StarReportSince => "vs.",
+            Msg::StarReportMilestone => "passed",
+            Msg::KindleNotConfigured => "Please set the Kindle address in SMTP settings first",
+            Msg::KindleTooLarge => "An article is too large to send to Kindle",
+            Msg::OpenUrlFailed => "Failed to open URL",
+            Msg::InvalidUrl => "Invalid URL",
+            Msg::UrlSchemeNotAllowed => "Only http(s) links can be opened",
+            Msg::ReadOnlyMode => "The database is open read-only; this action is disabled",
+            Msg::JobQueuedOffline => "You are offline; the action was queued and will run once the connection is back",
+            Msg::LocalApiBindFailed => "Failed to bind the local API port",
+            Msg::StdinReadFailed => "Failed to read stdin",
+            Msg::StdoutWriteFailed => "Failed to write stdout",
+        }
+    }
+}
+
+// Message in the current language
+pub fn t(msg: Msg) -> String {
+    msg.text(lang()).to_string()
+}
+
+// Message in the current language followed by a detail, e.g. "插入失败: UNIQUE constraint failed"
+pub fn tr(msg: Msg, detail: impl Display) -> String {
+    format!("{}: {}", msg.text(lang()), detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_codes_fall_back_to_chinese() {
+        assert_eq!(Lang::from_code("en"), Lang::En);
+        assert_eq!(Lang::from_code(" en-US "), Lang::En);
+        assert_eq!(Lang::from_code("EN_gb"), Lang::En);
+        assert_eq!(Lang::from_code("zh-CN"), Lang::Zh);
+        assert_eq!(Lang::from_code(""), Lang::Zh);
+        assert_eq!(Lang::from_code("fr"), Lang::Zh);
+        for lang in [Lang::Zh, Lang::En] {
+            assert_eq!(Lang::from_code(lang.code()), lang);
+        }
+    }
+
+    #[test]
+    fn messages_follow_the_current_language() {
+        assert_eq!(Msg::ArticleNotFound.text(Lang::Zh), "文章不存在");
+        assert_eq!(Msg::DbInsertFailed.text(Lang::En), "Insert failed");
+
+        set_lang(Lang::En);
+        assert_eq!(lang(), Lang::En);
+        assert_eq!(tr(Msg::DbInsertFailed, "UNIQUE constraint failed"), "Insert failed: UNIQUE constraint failed");
+        set_lang(Lang::Zh);
+        assert_eq!(t(Msg::DbInsertFailed), "插入失败");
+    }
+}
@@ -0,0 +1,262 @@
+// Offline queue for actions that need the network.
+//
+// When summarizing, translating or sending to a read-later service fails
+// and the connectivity check says the machine is offline, the action is
+// stored in `jobs` instead of being lost. The scheduler replays pending jobs
+// once the network is back; a job that still fails while online keeps its
+// error and is retried on later ticks up to `MAX_ATTEMPTS` times, after
+// which it stays in the queue for the user to inspect or delete.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, t, tr, Msg};
+use crate::commands::send_external;
+use crate::{ai, generate_ai_summary, glossary, network, read_later, read_only, shutdown, translate, update_summary, HttpClients};
+
+const MAX_ATTEMPTS: i64 = 5;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (kind, payload)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobAction {
+    Summarize {
+        article_id: String,
+    },
+    Translate {
+        article_id: String,
+        lang: Option<String>,
+        #[serde(default)]
+        force: bool,
+    },
+    SendExternal {
+        article_id: String,
+        service: read_later::Service,
+    },
+}
+
+impl JobAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobAction::Summarize { .. } => "summarize",
+            JobAction::Translate { .. } => "translate",
+            JobAction::SendExternal { .. } => "send_external",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub action: JobAction,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub completed: usize,
+    pub failed: usize,
+    pub remaining: usize,
+}
+
+// Queue an action; the same action queued twice is stored once
+pub fn enqueue(conn: &Connection, action: &JobAction) -> Result<(), String> {
+    let payload = serde_json::to_string(action).map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT OR IGNORE INTO jobs (kind, payload, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+        params![action.kind(), payload, now],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(())
+}
+
+// Pass `result` through, unless it failed because the machine is offline:
+// then queue `action` for replay and say so instead
+pub async fn queue_if_offline<T>(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    action: JobAction,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    let Err(e) = result else { return result };
+    if read_only::is_enabled() || network::is_online(http).await {
+        return Err(e);
+    }
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    enqueue(&conn, &action)?;
+    Err(t(Msg::JobQueuedOffline))
+}
+
+fn job_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<Job>> {
+    let payload: String = row.get(1)?;
+    // Payloads from a newer build that this one can't parse are skipped
+    let Ok(action) = serde_json::from_str(&payload) else { return Ok(None) };
+    Ok(Some(Job {
+        id: row.get(0)?,
+        action,
+        attempts: row.get(2)?,
+        last_error: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    }))
+}
+
+// Queued jobs, oldest first
+pub fn list(conn: &Connection) -> Result<Vec<Job>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, payload, attempts, last_error, created_at, updated_at FROM jobs ORDER BY id ASC")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let jobs = stmt
+        .query_map([], job_from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(jobs.into_iter().flatten().collect())
+}
+
+pub fn delete(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM jobs WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Whether any job is still due for replay
+pub fn has_pending(conn: &Connection) -> bool {
+    conn.query_row("SELECT 1 FROM jobs WHERE attempts < ?1 LIMIT 1", params![MAX_ATTEMPTS], |_| Ok(()))
+        .optional()
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+async fn summarize(db: &Mutex<Connection>, http: &HttpClients, article_id: &str) -> Result<(), String> {
+    let (config, glossary, (title, content)) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article: (String, String) = conn
+            .query_row("SELECT title, content FROM articles WHERE id = ?1", params![article_id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+            .ok_or_else(|| t(Msg::ArticleNotFound))?;
+        (ai::AiConfig::load(&conn)?, glossary::list(&conn)?, article)
+    };
+    let summary = generate_ai_summary(http.get(true), &config, &glossary, &title, &content).await?;
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(update_summary(&conn, article_id, &summary)?)
+}
+
+async fn run(db: &Mutex<Connection>, http: &HttpClients, action: &JobAction) -> Result<(), String> {
+    match action {
+        JobAction::Summarize { article_id } => summarize(db, http, article_id).await,
+        JobAction::Translate { article_id, lang, force } => {
+            let lang = lang.as_deref().map(i18n::Lang::from_code).unwrap_or_else(i18n::lang);
+            translate::translate_article(db, http.get(true), article_id, lang, *force).await.map(|_| ())
+        }
+        JobAction::SendExternal { article_id, service } => send_external(db, http, article_id, *service).await,
+    }
+}
+
+// Run pending jobs in queue order. Stops early if the connection drops again or the app is closing
+pub async fn replay(db: &Mutex<Connection>, http: &HttpClients) -> Result<ReplayReport, String> {
+    let pending: Vec<Job> = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        list(&conn)?.into_iter().filter(|job| job.attempts < MAX_ATTEMPTS).collect()
+    };
+
+    let _busy = shutdown::busy();
+    let mut report = ReplayReport { completed: 0, failed: 0, remaining: 0 };
+    let mut jobs = pending.into_iter();
+    for job in jobs.by_ref() {
+        // The rest stay queued for the next start
+        if shutdown::requested() {
+            report.remaining += 1;
+            break;
+        }
+        let result = run(db, http, &job.action).await;
+        if result.is_err() && !network::is_online(http).await {
+            report.remaining += 1;
+            break;
+        }
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        match result {
+            Ok(()) => {
+                delete(&conn, job.id)?;
+                report.completed += 1;
+            }
+            Err(e) => {
+                eprintln!("Queued {} job {} failed: {}", job.action.kind(), job.id, e);
+                conn.execute(
+                    "UPDATE jobs SET attempts = attempts + 1, last_error = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![e, chrono::Utc::now().to_rfc3339(), job.id],
+                )
+                .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+                report.failed += 1;
+            }
+        }
+    }
+    report.remaining += jobs.count();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_action_is_queued_once_and_exhausted_jobs_are_not_pending() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(!has_pending(&conn));
+        let summarize = JobAction::Summarize { article_id: "a1".to_string() };
+        enqueue(&conn, &summarize).unwrap();
+        enqueue(&conn, &summarize).unwrap();
+        enqueue(&conn, &JobAction::Translate { article_id: "a1".to_string(), lang: Some("en".to_string()), force: false }).unwrap();
+        let jobs = list(&conn).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(matches!(&jobs[0].action, JobAction::Summarize { article_id } if article_id == "a1"));
+        assert!(has_pending(&conn));
+
+        conn.execute("UPDATE jobs SET attempts = ?1", params![MAX_ATTEMPTS]).unwrap();
+        assert!(!has_pending(&conn), "failed too often; left for the user");
+        for job in jobs {
+            delete(&conn, job.id).unwrap();
+        }
+        assert!(list(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_payloads_are_skipped() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO jobs (kind, payload, created_at, updated_at) VALUES ('archive', '{\"kind\":\"archive\"}', '', '')",
+            [],
+        )
+        .unwrap();
+        enqueue(&conn, &JobAction::Summarize { article_id: "a1".to_string() }).unwrap();
+        assert_eq!(list(&conn).unwrap().len(), 1);
+        let payload: String = conn.query_row("SELECT payload FROM jobs WHERE kind = 'summarize'", [], |row| row.get(0)).unwrap();
+        assert_eq!(payload, r#"{"kind":"summarize","article_id":"a1"}"#);
+    }
+}
@@ -0,0 +1,390 @@
+// Local keyword extraction, so tags, search facets and trending topics work
+// without an AI provider.
+//
+// Every stored article gets up to MAX_KEYWORDS keywords in
+// `article_keywords`, taken from its title and content. Outside CJK text
+// that's RAKE: candidate phrases are the runs of words between stop words
+// and punctuation, scored by the degree / frequency of their words. Chinese
+// has no spaces to split phrases at, so its candidates are character
+// bigrams, scored by how often they occur. A candidate must occur twice or
+// be in the title (which doubles its score), and scores are weighted by
+// inverse document frequency over the keywords already stored, so terms
+// every article uses sink below the specific ones.
+//
+// New and edited articles are tagged when they're stored; the scheduler
+// backfills older ones BACKFILL_BATCH at a time. `top` lists the most used
+// keywords for facet chips and `trending` the ones rising over the last day.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Serialize;
+
+use crate::extractive::plain_text;
+use crate::i18n::{tr, Msg};
+use crate::search_index::is_cjk;
+
+const MAX_KEYWORDS: usize = 8;
+const MAX_PHRASE_WORDS: usize = 3;
+const TITLE_WEIGHT: f64 = 2.0;
+// Enough text for the keywords; stored content is truncated anyway
+const MAX_INPUT_CHARS: usize = 6000;
+pub const BACKFILL_BATCH: usize = 200;
+// `trending`: the last day against the daily average of the week before
+const TRENDING_BASELINE_DAYS: i64 = 7;
+const MIN_TRENDING_ARTICLES: i64 = 3;
+
+pub const STOP_WORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "below", "between", "both", "but", "by", "can", "could", "did", "do",
+    "does", "doing", "down", "during", "each", "even", "every", "few", "for", "from", "further", "get", "gets", "got",
+    "had", "has", "have", "having", "he", "her", "here", "hers", "him", "his", "how", "however", "i", "if", "in",
+    "into", "is", "it", "its", "itself", "just", "let", "like", "made", "make", "makes", "many", "may", "me", "might",
+    "more", "most", "much", "must", "my", "new", "no", "nor", "not", "now", "of", "off", "on", "once", "one", "only",
+    "or", "other", "our", "ours", "out", "over", "own", "per", "read", "really", "said", "same", "says", "see", "she",
+    "should", "since", "so", "some", "still", "such", "than", "that", "the", "their", "them", "then", "there",
+    "these", "they", "this", "those", "through", "to", "too", "two", "under", "until", "up", "us", "use", "used",
+    "using", "very", "via", "was", "way", "we", "well", "were", "what", "when", "where", "whether", "which", "while",
+    "who", "whom", "why", "will", "with", "within", "without", "would", "yet", "you", "your", "yours",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeywordCount {
+    pub keyword: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendingKeyword {
+    pub keyword: String,
+    // Articles in the last 24 hours
+    pub recent: i64,
+    // Articles per day over the week before
+    pub baseline: f64,
+    // (recent + 1) / (baseline + 1)
+    pub ratio: f64,
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_keywords (
+            article_id TEXT NOT NULL,
+            keyword TEXT NOT NULL,
+            score REAL NOT NULL,
+            PRIMARY KEY (article_id, keyword)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_article_keywords_keyword ON article_keywords(keyword)", [])?;
+    Ok(())
+}
+
+// Part of a word: letters, digits, and the joiners in "gpt-4o", "node.js", "c++"
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() && !is_cjk(c) || matches!(c, '-' | '.' | '+' | '\'')
+}
+
+// RAKE candidate phrases, lowercased, in order of occurrence
+fn phrases(text: &str) -> Vec<Vec<String>> {
+    let mut phrases = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut flush = |current: &mut Vec<String>| {
+        if !current.is_empty() {
+            // Overlong runs are split rather than dropped
+            for chunk in current.chunks(MAX_PHRASE_WORDS) {
+                phrases.push(chunk.to_vec());
+            }
+            current.clear();
+        }
+    };
+    let lower = text.to_lowercase();
+    let mut chars = lower.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !is_word_char(c) {
+            // Spaces separate words; any other punctuation (and CJK) ends the phrase
+            if !c.is_whitespace() {
+                flush(&mut current);
+            }
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some(&(i, next)) = chars.peek() {
+            if !is_word_char(next) {
+                break;
+            }
+            end = i + next.len_utf8();
+            chars.next();
+        }
+        let raw = &lower[start..end];
+        // Sentence-final dots and quotes aren't part of the word
+        let word = raw.trim_matches(|c: char| matches!(c, '.' | '\'' | '-'));
+        let ends_sentence = raw.ends_with('.');
+        let is_stop = STOP_WORDS.contains(&word);
+        let is_word = word.chars().count() >= 2 && word.chars().any(char::is_alphabetic);
+        if is_stop || !is_word {
+            flush(&mut current);
+        } else {
+            current.push(word.to_string());
+        }
+        if ends_sentence {
+            flush(&mut current);
+        }
+    }
+    flush(&mut current);
+    phrases
+}
+
+// Phrase → RAKE score and occurrences
+fn rake(text: &str) -> HashMap<String, (f64, usize)> {
+    let phrases = phrases(text);
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += phrase.len() as f64;
+        }
+    }
+    let mut scored: HashMap<String, (f64, usize)> = HashMap::new();
+    for phrase in &phrases {
+        let score: f64 = phrase.iter().map(|w| degree[w.as_str()] / frequency[w.as_str()]).sum();
+        let entry = scored.entry(phrase.join(" ")).or_insert((score, 0));
+        entry.1 += 1;
+    }
+    scored
+}
+
+// Overlapping CJK bigrams → occurrences
+fn cjk_bigrams(text: &str) -> HashMap<String, usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut counts = HashMap::new();
+    for run in chars.chunk_by(|a, b| is_cjk(*a) == is_cjk(*b)).filter(|run| is_cjk(run[0])) {
+        for pair in run.windows(2) {
+            *counts.entry(pair.iter().collect::<String>()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+// Candidate keywords of an article with their scores before IDF weighting
+pub fn extract(title: &str, content: &str) -> Vec<(String, f64)> {
+    let title = plain_text(title).to_lowercase();
+    let text: String = format!("{}. {}", title, plain_text(content)).chars().take(MAX_INPUT_CHARS).collect();
+
+    let mut candidates: Vec<(String, f64)> = Vec::new();
+    for (phrase, (score, occurrences)) in rake(&text) {
+        let in_title = title.contains(&phrase);
+        // The title is part of the text, so a title phrase has one occurrence already
+        if occurrences >= 2 || in_title {
+            let weight = if in_title { TITLE_WEIGHT } else { 1.0 };
+            candidates.push((phrase, score * weight * (occurrences as f64).sqrt()));
+        }
+    }
+    for (bigram, occurrences) in cjk_bigrams(&text) {
+        let in_title = title.contains(&bigram);
+        if occurrences >= 2 || in_title {
+            let weight = if in_title { TITLE_WEIGHT } else { 1.0 };
+            candidates.push((bigram, occurrences as f64 * weight));
+        }
+    }
+    candidates
+}
+
+// Extract and store the keywords of `ids`, replacing earlier ones; returns how many were tagged
+pub fn tag_articles(conn: &Connection, ids: &[String]) -> Result<usize, String> {
+    let tagged: f64 = conn
+        .query_row("SELECT COUNT(DISTINCT article_id) FROM article_keywords", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))? as f64;
+    let mut article_stmt = conn
+        .prepare_cached("SELECT title, COALESCE(content, '') FROM articles WHERE id = ?1")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let mut df_stmt = conn
+        .prepare_cached("SELECT COUNT(*) FROM article_keywords WHERE keyword = ?1")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+
+    let mut count = 0;
+    for id in ids {
+        let Ok((title, content)) =
+            article_stmt.query_row(params![id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        else {
+            continue;
+        };
+        let mut keywords: Vec<(String, f64)> = Vec::new();
+        for (keyword, score) in extract(&title, &content) {
+            let df: f64 = df_stmt
+                .query_row(params![keyword], |row| row.get::<_, i64>(0))
+                .map_err(|e| tr(Msg::DbQueryFailed, e))? as f64;
+            keywords.push((keyword, score * (((tagged + 1.0) / (df + 1.0)).ln() + 1.0)));
+        }
+        keywords.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        keywords.truncate(MAX_KEYWORDS);
+
+        conn.execute("DELETE FROM article_keywords WHERE article_id = ?1", params![id])
+            .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+        for (keyword, score) in &keywords {
+            conn.execute(
+                "INSERT INTO article_keywords (article_id, keyword, score) VALUES (?1, ?2, ?3)",
+                params![id, keyword, score],
+            )
+            .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+// Tag up to BACKFILL_BATCH articles stored before keywords existed, newest first
+pub fn backfill(conn: &Connection) -> Result<usize, String> {
+    let ids: Vec<String> = conn
+        .prepare(
+            "SELECT id FROM articles WHERE id NOT IN (SELECT DISTINCT article_id FROM article_keywords)
+             ORDER BY published_at DESC LIMIT ?1",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![BACKFILL_BATCH as i64], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    tag_articles(conn, &ids)
+}
+
+// An article's keywords, best first
+pub fn for_article(conn: &Connection, article_id: &str) -> Result<Vec<String>, String> {
+    conn.prepare("SELECT keyword FROM article_keywords WHERE article_id = ?1 ORDER BY score DESC")
+        .and_then(|mut stmt| stmt.query_map(params![article_id], |row| row.get(0))?.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| tr(Msg::DbQueryFailed, e))
+}
+
+// Most used keywords among articles published in the last `days`, optionally in one category
+pub fn top(conn: &Connection, category: Option<&str>, days: u32, limit: usize) -> Result<Vec<KeywordCount>, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+    let mut values = vec![since];
+    let mut sql = "SELECT k.keyword, COUNT(*) AS n FROM article_keywords k JOIN articles a ON a.id = k.article_id
+                   WHERE a.published_at >= ?1"
+        .to_string();
+    if let Some(category) = category.filter(|c| *c != "all") {
+        values.push(category.to_string());
+        sql.push_str(" AND a.category = ?2");
+    }
+    sql.push_str(&format!(" GROUP BY k.keyword ORDER BY n DESC, k.keyword ASC LIMIT {}", limit));
+
+    conn.prepare(&sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(params_from_iter(values.iter()), |row| {
+                Ok(KeywordCount { keyword: row.get(0)?, count: row.get(1)? })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))
+}
+
+// Keywords in at least MIN_TRENDING_ARTICLES articles of the last day, by how much that beats the week before
+pub fn trending(conn: &Connection, limit: usize) -> Result<Vec<TrendingKeyword>, String> {
+    let now = chrono::Utc::now();
+    let day_ago = (now - chrono::Duration::days(1)).to_rfc3339();
+    let baseline_start = (now - chrono::Duration::days(1 + TRENDING_BASELINE_DAYS)).to_rfc3339();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT k.keyword,
+                    SUM(CASE WHEN a.published_at >= ?1 THEN 1 ELSE 0 END) AS recent,
+                    SUM(CASE WHEN a.published_at < ?1 THEN 1 ELSE 0 END) AS earlier
+             FROM article_keywords k JOIN articles a ON a.id = k.article_id
+             WHERE a.published_at >= ?2
+             GROUP BY k.keyword
+             HAVING recent >= ?3",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let mut keywords: Vec<TrendingKeyword> = stmt
+        .query_map(params![day_ago, baseline_start, MIN_TRENDING_ARTICLES], |row| {
+            let recent: i64 = row.get(1)?;
+            let baseline = row.get::<_, i64>(2)? as f64 / TRENDING_BASELINE_DAYS as f64;
+            Ok(TrendingKeyword {
+                keyword: row.get(0)?,
+                recent,
+                baseline,
+                ratio: (recent as f64 + 1.0) / (baseline + 1.0),
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    keywords.sort_by(|a, b| b.ratio.total_cmp(&a.ratio).then(b.recent.cmp(&a.recent)));
+    keywords.truncate(limit);
+    Ok(keywords)
+}
+
+pub fn delete_orphans(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM article_keywords WHERE article_id NOT IN (SELECT id FROM articles UNION ALL SELECT id FROM articles_trash)",
+        [],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str, title: &str, content: &str, hours_ago: i64) {
+        let published_at = (chrono::Utc::now() - chrono::Duration::hours(hours_ago)).to_rfc3339();
+        conn.execute(
+            "INSERT INTO articles (id, title, content, url, source, category, published_at)
+             VALUES (?1, ?2, ?3, ?1, 'Blog', 'AI', ?4)",
+            params![id, title, content, published_at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn phrases_break_at_stop_words_and_punctuation() {
+        let words = |text: &str| phrases(text).into_iter().map(|p| p.join(" ")).collect::<Vec<_>>();
+        assert_eq!(
+            words("The GPT-4o model runs on Node.js, and speculative decoding is faster."),
+            ["gpt-4o model runs", "node.js", "speculative decoding", "faster"]
+        );
+        assert_eq!(words("Paged attention. Speculative decoding 2024"), ["paged attention", "speculative decoding"]);
+        assert_eq!(cjk_bigrams("大模型，大模型")["模型"], 2);
+    }
+
+    #[test]
+    fn keywords_come_from_the_title_or_repeated_phrases() {
+        let keywords: Vec<String> = extract(
+            "Speculative decoding explained",
+            "Speculative decoding drafts tokens with a small model. The large model checks them once. \
+             A small model is cheap, and the large model stays exact. Everything else is mentioned once.",
+        )
+        .into_iter()
+        .map(|(keyword, _)| keyword)
+        .collect();
+        assert!(keywords.contains(&"speculative decoding explained".to_string()), "{:?}", keywords);
+        assert!(keywords.contains(&"small model".to_string()), "{:?}", keywords);
+        assert!(!keywords.iter().any(|k| k.contains("mentioned")), "{:?}", keywords);
+
+        let chinese: Vec<String> = extract("大模型推理", "推理速度提升").into_iter().map(|(k, _)| k).collect();
+        assert!(chinese.contains(&"推理".to_string()) && chinese.contains(&"模型".to_string()), "{:?}", chinese);
+        assert!(!chinese.contains(&"速度".to_string()), "{:?}", chinese);
+    }
+
+    #[test]
+    fn tagged_articles_feed_top_and_trending() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for (i, hours_ago) in [1, 2, 3, 100].into_iter().enumerate() {
+            add_article(&conn, &format!("a{}", i), "Agent frameworks", "Agent frameworks everywhere.", hours_ago);
+        }
+        add_article(&conn, "old", "Vector databases", "Vector databases again.", 24 * 30);
+
+        assert_eq!(backfill(&conn).unwrap(), 5);
+        assert_eq!(for_article(&conn, "a0").unwrap(), ["agent frameworks"]);
+        assert_eq!(backfill(&conn).unwrap(), 0, "nothing left untagged");
+
+        let top = top(&conn, Some("AI"), 7, 10).unwrap();
+        assert_eq!(top.len(), 1, "the month-old article is out of range");
+        assert_eq!((top[0].keyword.as_str(), top[0].count), ("agent frameworks", 4));
+
+        let trending = trending(&conn, 10).unwrap();
+        assert_eq!(trending.len(), 1);
+        assert_eq!(trending[0].recent, 3);
+        assert!(trending[0].ratio > 1.0);
+    }
+}
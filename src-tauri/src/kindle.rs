@@ -0,0 +1,147 @@
+// Send-to-Kindle via Amazon's email gateway, using the SMTP account.
+//
+// Books are EPUBs (Amazon converts them on arrival). Mail providers cap
+// message size well below Amazon's 50 MB limit once base64 overhead is
+// counted, so a book that is too large is first rebuilt without images and
+// then split into numbered parts, one email each.
+
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::email::{self, FileAttachment, SmtpConfig};
+use crate::epub::{self, Book};
+use crate::i18n::{t, tr, Msg};
+use crate::{digest, obsidian, Article, HttpClients};
+
+// Raw attachment size that stays under a 25 MB message limit after base64
+const MAX_ATTACHMENT_BYTES: usize = 18 * 1024 * 1024;
+
+// Leaves room for text, markup and zip overhead next to the images
+const IMAGE_BUDGET: usize = MAX_ATTACHMENT_BYTES * 3 / 4;
+
+#[derive(Debug, Serialize)]
+pub struct KindleSendResult {
+    pub parts: usize,
+    pub articles: usize,
+    pub images_dropped: bool,
+}
+
+// Articles of the digest for the last `days` days, in digest order; the digest is added to the history
+pub fn digest_articles(conn: &Connection, days: i64) -> Result<(String, Vec<Article>), String> {
+    let digest = digest::build(conn, days, digest::DEFAULT_PER_CATEGORY)?;
+    if digest.article_count() > 0 {
+        digest::record(conn, &digest)?;
+    }
+    let title = digest.title.clone();
+    let articles: Vec<Article> = digest.sections.into_iter().flat_map(|s| s.articles).collect();
+    if articles.is_empty() {
+        return Err(t(Msg::ExportNothingSelected));
+    }
+    Ok((title, articles))
+}
+
+pub async fn send(db: &Mutex<Connection>, http: &HttpClients, title: &str, articles: &[Article]) -> Result<KindleSendResult, String> {
+    let config = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        SmtpConfig::load(&conn)?
+    }; // Release the lock while fetching and sending
+    if config.kindle_email.is_empty() {
+        return Err(t(Msg::KindleNotConfigured));
+    }
+
+    let mut book = epub::collect(db, http, title, articles, IMAGE_BUDGET).await?;
+    let mut images_dropped = false;
+    let mut bytes = epub::build(&book)?;
+    if bytes.len() > MAX_ATTACHMENT_BYTES && !book.images.is_empty() {
+        epub::strip_images(&mut book);
+        images_dropped = true;
+        bytes = epub::build(&book)?;
+    }
+    let parts = if bytes.len() > MAX_ATTACHMENT_BYTES {
+        split(book, bytes.len())?
+    } else {
+        vec![(title.to_string(), bytes)]
+    };
+
+    let part_count = parts.len();
+    let recipients = vec![config.kindle_email.clone()];
+    for (part_title, data) in parts {
+        let attachment = FileAttachment {
+            filename: format!("{}.epub", obsidian::sanitize_filename(&part_title)),
+            content_type: "application/epub+zip",
+            data,
+        };
+        let body = format!("<p>{}</p>", digest::escape(&part_title));
+        email::send(&config, &recipients, &part_title, body, Some(attachment)).await?;
+    }
+
+    Ok(KindleSendResult {
+        parts: part_count,
+        articles: articles.len(),
+        images_dropped,
+    })
+}
+
+// Split an image-free book into parts that each fit, sized by the chapters' share of the whole
+fn split(book: Book, total_bytes: usize) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let total_html: usize = book.chapters.iter().map(|c| c.body_html.len()).sum::<usize>().max(1);
+    let target_html = total_html * MAX_ATTACHMENT_BYTES / total_bytes * 9 / 10;
+
+    let mut groups: Vec<Vec<epub::Chapter>> = vec![Vec::new()];
+    let mut current = 0;
+    for chapter in book.chapters {
+        let size = chapter.body_html.len();
+        if current + size > target_html && groups.last().is_some_and(|g| !g.is_empty()) {
+            groups.push(Vec::new());
+            current = 0;
+        }
+        current += size;
+        if let Some(group) = groups.last_mut() {
+            group.push(chapter);
+        }
+    }
+
+    let count = groups.len();
+    let mut parts = Vec::with_capacity(count);
+    for (i, chapters) in groups.into_iter().enumerate() {
+        let part = Book {
+            title: format!("{} ({}/{})", book.title, i + 1, count),
+            chapters,
+            images: Vec::new(),
+        };
+        let bytes = epub::build(&part)?;
+        // A single oversized chapter can't be split further
+        if bytes.len() > MAX_ATTACHMENT_BYTES {
+            return Err(t(Msg::KindleTooLarge));
+        }
+        parts.push((part.title, bytes));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(n: usize) -> epub::Chapter {
+        epub::Chapter { title: format!("Chapter {}", n), meta: String::new(), body_html: format!("<p>{}</p>", "x".repeat(993)) }
+    }
+
+    #[test]
+    fn oversized_books_split_by_chapter_share() {
+        let book = Book { title: "Digest".to_string(), chapters: (1..=4).map(chapter).collect(), images: Vec::new() };
+        // As if the whole book were exactly at the limit: 90% of it per part
+        let parts = split(book, MAX_ATTACHMENT_BYTES).unwrap();
+        let titles: Vec<&str> = parts.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(titles, ["Digest (1/2)", "Digest (2/2)"]);
+    }
+
+    #[test]
+    fn empty_digest_is_not_sent_or_kept() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(digest_articles(&conn, 1).is_err());
+        assert!(digest::list(&conn, None, 10).unwrap().is_empty(), "empty digests aren't kept");
+    }
+}
@@ -0,0 +1,158 @@
+// Summaries generated on first open instead of at crawl time.
+//
+// With `summary_mode` = "lazy" the crawler stores the template brief with
+// status `pending` and makes no AI calls, so articles that are never read
+// cost nothing. Opening one (`article_summary_ensure` from the reader, or
+// `open_external` with its id) starts the AI summary in the background and
+// returns at once; `app://summary:ready` carries the result when it's
+// stored. An article is summarized once at a time however often it's
+// opened, and not at all in read-only mode.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::i18n::{tr, Msg};
+use crate::{
+    ai, get_setting, glossary, local_brief, night_batch, read_only, store_summary, summarize_or_template, DbState,
+    GeneratedSummary, HttpClients, SummaryStatus,
+};
+
+pub const MODE_CRAWL: &str = "crawl";
+pub const MODE_LAZY: &str = "lazy";
+
+// Articles being summarized right now
+static IN_FLIGHT: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryReady {
+    pub id: String,
+    pub summary: String,
+    pub status: SummaryStatus,
+    pub error: Option<String>,
+}
+
+// Unknown values fall back to summarizing during the crawl
+pub fn normalize_mode(mode: &str) -> &'static str {
+    if mode.trim().eq_ignore_ascii_case(MODE_LAZY) {
+        MODE_LAZY
+    } else {
+        MODE_CRAWL
+    }
+}
+
+pub fn is_enabled(conn: &Connection) -> Result<bool, String> {
+    Ok(normalize_mode(&get_setting(conn, "summary_mode", MODE_CRAWL)?) == MODE_LAZY)
+}
+
+// What the crawler stores in lazy mode
+pub fn placeholder(title: &str, content: &str, source: &str) -> GeneratedSummary {
+    GeneratedSummary { text: local_brief(title, content, source), status: SummaryStatus::Pending, error: None }
+}
+
+fn claim(id: &str) -> bool {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(HashSet::new).insert(id.to_string())
+}
+
+fn release(id: &str) {
+    if let Some(in_flight) = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        in_flight.remove(id);
+    }
+}
+
+// Start summarizing `id` if it's still pending; true when a summary:ready event will follow
+pub fn request(app: &AppHandle, id: &str) -> Result<bool, String> {
+    if read_only::is_enabled() {
+        return Ok(false);
+    }
+    let state = app.state::<DbState>();
+    let article = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        // Night-batch mode queues summaries the same way when crawling outside its window
+        if !is_enabled(&conn)? && !night_batch::is_queued(&conn, id, night_batch::TASK_SUMMARY) {
+            return Ok(false);
+        }
+        conn.query_row(
+            "SELECT title, COALESCE(content, ''), COALESCE(source, '') FROM articles WHERE id = ?1 AND summary_status = 'pending'",
+            params![id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+    };
+    let Some((title, content, source)) = article else { return Ok(false) };
+    if !claim(id) {
+        return Ok(true);
+    }
+
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<DbState>();
+        let http = app.state::<HttpClients>();
+        let setup = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e)).and_then(|conn| {
+            // Not configured: the template brief becomes the summary
+            Ok((ai::AiConfig::load(&conn).ok(), glossary::list(&conn)?))
+        });
+        let result = match setup {
+            Ok((ai_config, glossary)) => {
+                let summary = summarize_or_template(&http, ai_config.as_ref(), &glossary, &title, &content, &source).await;
+                state
+                    .conn
+                    .lock()
+                    .map_err(|e| tr(Msg::DbLockPoisoned, e))
+                    .and_then(|conn| Ok(store_summary(&conn, &id, &summary)?))
+                    .map(|_| summary)
+            }
+            Err(e) => Err(e),
+        };
+        release(&id);
+        let ready = match result {
+            Ok(summary) => SummaryReady { id, summary: summary.text, status: summary.status, error: summary.error },
+            Err(e) => {
+                eprintln!("Lazy summary for {} failed: {}", id, e);
+                SummaryReady { id, summary: String::new(), status: SummaryStatus::Pending, error: Some(e) }
+            }
+        };
+        let _ = app.emit("app://summary:ready", ready);
+    });
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_defaults_to_crawl_time() {
+        assert_eq!(normalize_mode(" Lazy "), MODE_LAZY);
+        assert_eq!(normalize_mode("eager"), MODE_CRAWL);
+        assert_eq!(normalize_mode(""), MODE_CRAWL);
+
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(!is_enabled(&conn).unwrap());
+        crate::set_setting(&conn, "summary_mode", "lazy").unwrap();
+        assert!(is_enabled(&conn).unwrap());
+    }
+
+    #[test]
+    fn placeholder_is_pending() {
+        let summary = placeholder("Open weights release", "A lab released its model weights.", "Blog");
+        assert_eq!(summary.status, SummaryStatus::Pending);
+        assert!(summary.error.is_none());
+        assert!(!summary.text.is_empty());
+    }
+
+    #[test]
+    fn an_article_is_claimed_once_at_a_time() {
+        let id = "lazy-summary-claim-test";
+        assert!(claim(id));
+        assert!(!claim(id), "already in flight");
+        release(id);
+        assert!(claim(id));
+        release(id);
+    }
+}
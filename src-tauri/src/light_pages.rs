@@ -0,0 +1,243 @@
+// Lighter alternatives for paywalled or heavy article pages.
+//
+// When a fetched article page is paywalled (`paywall::detect`) or larger
+// than `heavy_kb`, its content is taken from a lighter version instead, the
+// first of these that yields readable text: the AMP page the article links
+// with `<link rel="amphtml">`, its print version (a per-domain URL template,
+// else `<link rel="alternate" media="print">`), and a reader service such as
+// r.jina.ai that takes the article URL appended to `reader_url`. Which of
+// them are tried, and in what order, is configurable per domain; domains
+// without a rule use `default_strategies`. The reader service is only used
+// once `reader_url` is set, since it sends the URL to a third party. Manual
+// adds store the lighter page's text as the article content, so summaries
+// work from the article itself; EPUB/PDF exports and the prefetch read it.
+
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::digest::escape;
+use crate::{get_setting, http_cache, low_data, paywall, readability, set_setting, HttpClients};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    Amp,
+    Print,
+    Reader,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainRule {
+    // The host itself or any subdomain of it
+    pub domain: String,
+    // Tried in order; empty = never look for a lighter page on this domain
+    pub strategies: Vec<Strategy>,
+    // Print version, with `{url}` standing for the article URL, e.g. "{url}?view=print"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightPagesConfig {
+    pub enabled: bool,
+    // Pages above this size (KB of HTML) count as heavy
+    pub heavy_kb: u32,
+    // Reader service prefix, e.g. "https://r.jina.ai/"; empty = off
+    pub reader_url: String,
+    pub default_strategies: Vec<Strategy>,
+    pub domains: Vec<DomainRule>,
+}
+
+impl Default for LightPagesConfig {
+    fn default() -> Self {
+        LightPagesConfig {
+            enabled: true,
+            heavy_kb: 1024,
+            reader_url: String::new(),
+            default_strategies: vec![Strategy::Amp, Strategy::Print, Strategy::Reader],
+            domains: Vec::new(),
+        }
+    }
+}
+
+impl LightPagesConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = LightPagesConfig::default();
+        let json = |key: &str| get_setting(conn, key, "").map(|v| (!v.is_empty()).then_some(v));
+        Ok(LightPagesConfig {
+            enabled: get_setting(conn, "light_pages.enabled", "true")? == "true",
+            heavy_kb: get_setting(conn, "light_pages.heavy_kb", "")?.parse().unwrap_or(defaults.heavy_kb),
+            reader_url: get_setting(conn, "light_pages.reader_url", "")?,
+            default_strategies: json("light_pages.default_strategies")?
+                .and_then(|v| serde_json::from_str(&v).ok())
+                .unwrap_or(defaults.default_strategies),
+            domains: json("light_pages.domains")?.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or_default(),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let domains: Vec<DomainRule> = self
+            .domains
+            .iter()
+            .map(|rule| DomainRule {
+                domain: rule.domain.trim().to_ascii_lowercase().trim_start_matches("www.").to_string(),
+                strategies: rule.strategies.clone(),
+                print_url: rule.print_url.as_deref().map(str::trim).filter(|t| t.contains("{url}")).map(str::to_string),
+            })
+            .filter(|rule| !rule.domain.is_empty())
+            .collect();
+        set_setting(conn, "light_pages.enabled", if self.enabled { "true" } else { "false" })?;
+        set_setting(conn, "light_pages.heavy_kb", &self.heavy_kb.max(1).to_string())?;
+        set_setting(conn, "light_pages.reader_url", self.reader_url.trim())?;
+        set_setting(
+            conn,
+            "light_pages.default_strategies",
+            &serde_json::to_string(&self.default_strategies).unwrap_or_else(|_| "[]".to_string()),
+        )?;
+        set_setting(conn, "light_pages.domains", &serde_json::to_string(&domains).unwrap_or_else(|_| "[]".to_string()))?;
+        Ok(())
+    }
+
+    fn rule_for(&self, url: &str) -> Option<&DomainRule> {
+        let host = reqwest::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+        self.domains.iter().find(|rule| {
+            host == rule.domain || host.strip_suffix(&rule.domain).is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+}
+
+// `<link rel=... href=...>` of the page, resolved against its URL
+fn linked(document: &Html, selector: &str, base: &str) -> Option<String> {
+    let href = document.select(&Selector::parse(selector).ok()?).next()?.value().attr("href")?;
+    reqwest::Url::parse(base).ok()?.join(href.trim()).ok().map(|u| u.to_string())
+}
+
+fn candidate(config: &LightPagesConfig, rule: Option<&DomainRule>, strategy: Strategy, url: &str, html: &str) -> Option<String> {
+    match strategy {
+        Strategy::Amp => linked(&Html::parse_document(html), "link[rel='amphtml']", url),
+        Strategy::Print => rule
+            .and_then(|r| r.print_url.as_deref())
+            .map(|template| template.replace("{url}", url))
+            .or_else(|| linked(&Html::parse_document(html), "link[rel='alternate'][media='print']", url)),
+        Strategy::Reader => {
+            let reader = config.reader_url.trim();
+            (!reader.is_empty()).then(|| format!("{}{}", reader, url))
+        }
+    }
+}
+
+// Reader services answer with text or Markdown; paragraphs become <p>s so readability takes them
+fn text_to_html(text: &str) -> String {
+    let paragraphs: String = text
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>", escape(p)))
+        .collect();
+    format!("<html><body><article>{}</article></body></html>", paragraphs)
+}
+
+// A lighter page with the content of `html` (fetched from `url`), if it's paywalled or heavy and one is found
+pub async fn lighter(db: &Mutex<Connection>, http: &HttpClients, url: &str, html: &str) -> Option<String> {
+    let config = {
+        let conn = db.lock().ok()?;
+        LightPagesConfig::load(&conn).ok()?
+    };
+    let heavy = html.len() > config.heavy_kb as usize * 1024;
+    if !config.enabled || low_data::enabled() || !(heavy || paywall::detect(html)) {
+        return None;
+    }
+    let rule = config.rule_for(url);
+    let strategies = rule.map_or(&config.default_strategies, |r| &r.strategies);
+
+    for &strategy in strategies {
+        let Some(alternative) = candidate(&config, rule, strategy, url, html) else { continue };
+        if alternative == url {
+            continue;
+        }
+        let request = http.for_url(&alternative).get(&alternative).timeout(std::time::Duration::from_secs(20));
+        let Ok(fetched) = http_cache::fetch_text(db, &alternative, request, http_cache::PAGE_TTL).await else { continue };
+        if !fetched.is_success() {
+            continue;
+        }
+        let page = if strategy == Strategy::Reader && !fetched.body.trim_start().starts_with('<') {
+            text_to_html(&fetched.body)
+        } else {
+            fetched.body
+        };
+        // A lighter page behind the same paywall is no better
+        if strategy != Strategy::Reader && paywall::detect(&page) {
+            continue;
+        }
+        if readability::extract(&page, &alternative).is_some() {
+            return Some(page);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trip_drops_unusable_rules() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let loaded = LightPagesConfig::load(&conn).unwrap();
+        assert!(loaded.enabled);
+        assert_eq!(loaded.default_strategies, [Strategy::Amp, Strategy::Print, Strategy::Reader]);
+
+        let config = LightPagesConfig {
+            heavy_kb: 0,
+            reader_url: " https://r.jina.ai/ ".into(),
+            domains: vec![
+                DomainRule {
+                    domain: " WWW.Example.com ".into(),
+                    strategies: vec![Strategy::Print],
+                    print_url: Some("{url}?view=print".into()),
+                },
+                DomainRule { domain: "news.test".into(), strategies: Vec::new(), print_url: Some("/print".into()) },
+                DomainRule { domain: "  ".into(), strategies: vec![Strategy::Amp], print_url: None },
+            ],
+            ..LightPagesConfig::default()
+        };
+        config.save(&conn).unwrap();
+        let loaded = LightPagesConfig::load(&conn).unwrap();
+        assert_eq!(loaded.heavy_kb, 1);
+        assert_eq!(loaded.reader_url, "https://r.jina.ai/");
+        assert_eq!(loaded.domains.len(), 2);
+        assert_eq!(loaded.domains[0].domain, "example.com");
+        assert_eq!(loaded.domains[1].print_url, None, "template without {{url}}");
+
+        assert_eq!(loaded.rule_for("https://blog.example.com/post").unwrap().domain, "example.com");
+        assert!(loaded.rule_for("https://notexample.com/post").is_none());
+    }
+
+    #[test]
+    fn candidates_per_strategy() {
+        let html = r#"<html><head><link rel="amphtml" href="/amp/post"><link rel="alternate" media="print" href="?print=1"></head></html>"#;
+        let url = "https://news.test/post";
+        let config = LightPagesConfig::default();
+        assert_eq!(candidate(&config, None, Strategy::Amp, url, html).as_deref(), Some("https://news.test/amp/post"));
+        assert_eq!(candidate(&config, None, Strategy::Print, url, html).as_deref(), Some("https://news.test/post?print=1"));
+        assert_eq!(candidate(&config, None, Strategy::Reader, url, html), None, "reader service off");
+        assert_eq!(candidate(&config, None, Strategy::Amp, url, "<html></html>"), None);
+
+        let rule = DomainRule { domain: "news.test".into(), strategies: vec![Strategy::Print], print_url: Some("{url}/print".into()) };
+        assert_eq!(candidate(&config, Some(&rule), Strategy::Print, url, html).as_deref(), Some("https://news.test/post/print"));
+        let config = LightPagesConfig { reader_url: "https://r.jina.ai/".into(), ..config };
+        assert_eq!(candidate(&config, None, Strategy::Reader, url, html).as_deref(), Some("https://r.jina.ai/https://news.test/post"));
+    }
+
+    #[test]
+    fn reader_text_becomes_paragraphs() {
+        assert_eq!(
+            text_to_html("Title\n\n  First <b>para</b>.  \n\n\n"),
+            "<html><body><article><p>Title</p><p>First &lt;b&gt;para&lt;/b&gt;.</p></article></body></html>"
+        );
+    }
+}
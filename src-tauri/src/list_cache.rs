@@ -0,0 +1,163 @@
+// Cache for the queries behind the common navigation path.
+//
+// The frontend re-queries the category counts and the first page of the
+// feed on every window focus. Both are kept here, along with pages
+// `articles_prefetch` warmed ahead of the user, keyed by their filter
+// set, in small LRU caches (CAPACITY entries each). Every connection opened
+// by `init_db_at` gets an SQLite update hook that bumps a generation
+// counter whenever a row of a table the lists read from changes; entries
+// from an older generation are misses. Muting, snoozing and the freshness
+// buckets also depend on the clock, so entries expire after TTL as well.
+// Writes from another process only show up once the TTL has passed.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+
+use crate::{CategoryCount, ListQuery, ListResponse};
+
+const CAPACITY: usize = 16;
+const TTL: Duration = Duration::from_secs(60);
+// Writes to these invalidate cached lists
+const WATCHED_TABLES: &[&str] = &["articles", "sources", "folders", "settings", "article_keywords", "article_tags"];
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Invalidate everything cached, e.g. after the connection was replaced
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+// Invalidate on every write `conn` makes to a watched table
+pub fn watch(conn: &Connection) {
+    conn.update_hook(Some(|_action, _db: &str, table: &str, _rowid| {
+        if WATCHED_TABLES.contains(&table) {
+            invalidate();
+        }
+    }));
+    invalidate();
+}
+
+struct Entry<T> {
+    key: String,
+    generation: u64,
+    stored: Instant,
+    value: T,
+}
+
+// Most recently used first
+struct Lru<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T: Clone> Lru<T> {
+    const fn new() -> Self {
+        Lru { entries: Vec::new() }
+    }
+
+    fn get(&mut self, key: &str) -> Option<T> {
+        let generation = GENERATION.load(Ordering::Relaxed);
+        let index = self.entries.iter().position(|e| e.key == key)?;
+        let entry = self.entries.remove(index);
+        if entry.generation != generation || entry.stored.elapsed() > TTL {
+            return None;
+        }
+        let value = entry.value.clone();
+        self.entries.insert(0, entry);
+        Some(value)
+    }
+
+    // `generation` is the one read before the value was queried, so a write
+    // that raced the query leaves the entry stale
+    fn put(&mut self, key: String, generation: u64, value: T) {
+        self.entries.retain(|e| e.key != key);
+        self.entries.insert(0, Entry { key, generation, stored: Instant::now(), value });
+        self.entries.truncate(CAPACITY);
+    }
+}
+
+// Managed state
+pub struct ListCache {
+    pages: Mutex<Lru<ListResponse>>,
+    counts: Mutex<Lru<Vec<CategoryCount>>>,
+}
+
+impl Default for ListCache {
+    fn default() -> Self {
+        ListCache { pages: Mutex::new(Lru::new()), counts: Mutex::new(Lru::new()) }
+    }
+}
+
+fn cached<T: Clone>(lru: &Mutex<Lru<T>>, key: String, query: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    if let Some(value) = lru.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(value);
+    }
+    let generation = GENERATION.load(Ordering::Relaxed);
+    let value = query()?;
+    lru.lock().unwrap_or_else(|e| e.into_inner()).put(key, generation, value.clone());
+    Ok(value)
+}
+
+impl ListCache {
+    pub fn page_key(query: &ListQuery) -> Result<String, String> {
+        serde_json::to_string(query).map_err(|e| e.to_string())
+    }
+
+    // A cached page without querying on a miss
+    pub fn cached_page(&self, key: &str) -> Option<ListResponse> {
+        self.pages.lock().unwrap_or_else(|e| e.into_inner()).get(key)
+    }
+
+    pub fn page(&self, key: String, query: impl FnOnce() -> Result<ListResponse, String>) -> Result<ListResponse, String> {
+        cached(&self.pages, key, query)
+    }
+
+    pub fn counts(&self, key: String, query: impl FnOnce() -> Result<Vec<CategoryCount>, String>) -> Result<Vec<CategoryCount>, String> {
+        cached(&self.counts, key, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Other tests' connections bump the generation concurrently, so a fresh
+    // entry is retried until one put and get land in the same generation
+    fn hit_after_put(lru: &mut Lru<u32>, key: &str, value: u32) -> bool {
+        (0..100).any(|_| {
+            lru.put(key.to_string(), GENERATION.load(Ordering::Relaxed), value);
+            lru.get(key) == Some(value)
+        })
+    }
+
+    #[test]
+    fn stale_generations_miss_and_old_entries_are_evicted() {
+        let mut lru: Lru<u32> = Lru::new();
+        assert!(hit_after_put(&mut lru, "page:1", 1));
+
+        let generation = GENERATION.load(Ordering::Relaxed);
+        lru.put("page:2".to_string(), generation, 2);
+        invalidate();
+        assert_eq!(lru.get("page:2"), None, "written since");
+        assert!(lru.entries.iter().all(|e| e.key != "page:2"), "stale entries are dropped on lookup");
+
+        for i in 0..(CAPACITY as u32 + 4) {
+            lru.put(format!("key:{}", i), generation, i);
+        }
+        assert_eq!(lru.entries.len(), CAPACITY);
+        assert_eq!(lru.entries[0].key, format!("key:{}", CAPACITY + 3), "most recent first");
+        assert!(lru.entries.iter().all(|e| e.key != "key:0"));
+    }
+
+    #[test]
+    fn writes_to_watched_tables_invalidate() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE articles (id TEXT)", []).unwrap();
+        watch(&conn);
+        let before = GENERATION.load(Ordering::Relaxed);
+        conn.execute("INSERT INTO articles (id) VALUES ('a1')", []).unwrap();
+        assert!(GENERATION.load(Ordering::Relaxed) > before);
+    }
+}
@@ -0,0 +1,346 @@
+// Optional HTTP API bound to 127.0.0.1, so scripts and launcher extensions
+// (Raycast, Alfred, ...) can query the news database as JSON.
+//
+// Reads run on the server thread with a dedicated read-only SQLite
+// connection, so they never contend with the app's `DbState` lock. The only
+// write route is `POST /api/save` for the browser extension, which goes
+// through the app's shared connection like `manual_add` does.
+
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::i18n::{t, tr, Msg};
+use crate::search_index::SearchScope;
+use crate::{query_article, query_articles_page, query_search, save_submitted_page, DbState, ListQuery};
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+// Upper bound for HTML bodies posted by the browser extension
+const MAX_SAVE_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+pub struct LocalApiServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+}
+
+impl LocalApiServer {
+    pub fn start(app: AppHandle, db_path: &str, port: u16, token: String) -> Result<Self, String> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        ).map_err(|e| tr(Msg::DbOpenReadOnlyFailed, e))?;
+
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| tr(Msg::LocalApiBindFailed, format!("127.0.0.1:{}: {}", port, e)))?;
+        let server = Arc::new(server);
+
+        let worker = Arc::clone(&server);
+        let thread = std::thread::spawn(move || {
+            for request in worker.incoming_requests() {
+                handle_request(&app, &conn, &token, request);
+            }
+        });
+
+        println!("Local API listening on http://127.0.0.1:{}", port);
+        Ok(LocalApiServer { server, thread: Some(thread), port })
+    }
+
+    pub fn stop(mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        println!("Local API on port {} stopped", self.port);
+    }
+}
+
+// Managed state holding the running server, if any
+#[derive(Default)]
+pub struct LocalApiState {
+    pub server: Mutex<Option<LocalApiServer>>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SaveRequest {
+    url: String,
+    html: String,
+    title: Option<String>,
+}
+
+fn handle_request(app: &AppHandle, conn: &Connection, token: &str, request: Request) {
+    // CORS preflight from the extension/bookmarklet; carries no token
+    if *request.method() == Method::Options {
+        respond_preflight(request);
+        return;
+    }
+
+    let raw_url = request.url().to_string();
+    let parsed = match reqwest::Url::parse(&format!("http://127.0.0.1{}", raw_url)) {
+        Ok(url) => url,
+        Err(_) => {
+            respond_error(request, 400, "invalid url");
+            return;
+        }
+    };
+    let params: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+
+    if !is_authorized(&request, token, param("token").as_deref()) {
+        respond_error(request, 401, "unauthorized");
+        return;
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_string();
+
+    if path == "/api/save" {
+        if *request.method() != Method::Post {
+            respond_error(request, 405, "method not allowed");
+            return;
+        }
+        handle_save(app, request);
+        return;
+    }
+
+    if *request.method() != Method::Get {
+        respond_error(request, 405, "method not allowed");
+        return;
+    }
+
+    // Bookmarks as RSS, for feed readers (pass the token as ?token=)
+    if path == "/api/feed.xml" {
+        match crate::feed_export::bookmarks_feed_xml(conn) {
+            Ok(xml) => respond_body(request, 200, "application/rss+xml; charset=utf-8", xml),
+            Err(e) => respond_error(request, 500, &e),
+        }
+        return;
+    }
+
+    let page = param("page").and_then(|v| v.parse().ok());
+    let page_size = param("page_size")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let result = match path.as_str() {
+        "/api/health" => Ok(serde_json::json!({ "status": "ok" })),
+        "/api/articles" => query_articles_page(conn, &ListQuery {
+            page,
+            page_size,
+            category: param("category"),
+            bookmarked_only: None,
+            include_muted: None,
+            include_snoozed: None,
+            folder_id: None,
+            freshness: None,
+            group_by_freshness: None,
+            tag: param("tag"),
+            max_duration_minutes: param("max_duration_minutes").and_then(|v| v.parse().ok()),
+            short_only: param("short_only").map(|v| v == "true"),
+            hide_paywalled: param("hide_paywalled").map(|v| v == "true"),
+        }, None).map_err(String::from).and_then(to_json),
+        "/api/bookmarks" => query_articles_page(conn, &ListQuery {
+            page,
+            page_size,
+            category: param("category"),
+            bookmarked_only: Some(true),
+            include_muted: None,
+            include_snoozed: None,
+            folder_id: None,
+            freshness: None,
+            group_by_freshness: None,
+            tag: param("tag"),
+            max_duration_minutes: param("max_duration_minutes").and_then(|v| v.parse().ok()),
+            short_only: param("short_only").map(|v| v == "true"),
+            hide_paywalled: param("hide_paywalled").map(|v| v == "true"),
+        }, None).map_err(String::from).and_then(to_json),
+        "/api/search" => match param("q").filter(|q| !q.trim().is_empty()) {
+            Some(q) => query_search(conn, q.trim(), &SearchScope::default()).map_err(String::from).and_then(to_json),
+            None => {
+                respond_error(request, 400, "missing query parameter 'q'");
+                return;
+            }
+        },
+        _ => match path.strip_prefix("/api/articles/") {
+            Some(id) if !id.is_empty() => match query_article(conn, id) {
+                Ok(Some(article)) => to_json(article),
+                Ok(None) => {
+                    respond_error(request, 404, "article not found");
+                    return;
+                }
+                Err(e) => Err(e.to_string()),
+            },
+            _ => {
+                respond_error(request, 404, "not found");
+                return;
+            }
+        },
+    };
+
+    match result {
+        Ok(body) => respond_json(request, 200, &body),
+        Err(e) => respond_error(request, 500, &e),
+    }
+}
+
+// POST /api/save {url, html, title?}: extract and insert without re-fetching the page
+fn handle_save(app: &AppHandle, mut request: Request) {
+    let mut body = String::new();
+    let read = request
+        .as_reader()
+        .take(MAX_SAVE_BODY_BYTES + 1)
+        .read_to_string(&mut body);
+    if read.is_err() {
+        respond_error(request, 400, "body must be UTF-8 JSON");
+        return;
+    }
+    if body.len() as u64 > MAX_SAVE_BODY_BYTES {
+        respond_error(request, 413, "payload too large");
+        return;
+    }
+
+    let payload: SaveRequest = match serde_json::from_str(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            respond_error(request, 400, &format!("invalid payload: {}", e));
+            return;
+        }
+    };
+    if !(payload.url.starts_with("http://") || payload.url.starts_with("https://")) {
+        respond_error(request, 400, "url must be http(s)");
+        return;
+    }
+
+    if let Err(e) = crate::read_only::ensure_writable() {
+        respond_error(request, 403, &e);
+        return;
+    }
+
+    let state = app.state::<DbState>();
+    match save_submitted_page(&state, &payload.url, &payload.html, payload.title.as_deref()) {
+        Ok(Some(article)) => respond_json(request, 201, &article),
+        Ok(None) => respond_error(request, 409, &t(Msg::LinkExists)),
+        Err(e) => respond_error(request, 500, &e.to_string()),
+    }
+}
+
+// Accepts `Authorization: Bearer <token>`, `X-Api-Token: <token>` or `?token=<token>`
+fn is_authorized(request: &Request, token: &str, query_token: Option<&str>) -> bool {
+    if token.is_empty() {
+        return false;
+    }
+    let header_token = request.headers().iter().find_map(|h| {
+        let field = h.field.as_str().as_str();
+        let value = h.value.as_str();
+        if field.eq_ignore_ascii_case("authorization") {
+            value.strip_prefix("Bearer ").map(|v| v.trim().to_string())
+        } else if field.eq_ignore_ascii_case("x-api-token") {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    });
+    header_token.as_deref().or(query_token) == Some(token)
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("serialize failed: {}", e))
+}
+
+fn respond_json(request: Request, status: u16, body: &impl Serialize) {
+    let payload = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    respond_body(request, status, "application/json; charset=utf-8", payload);
+}
+
+fn respond_body(request: Request, status: u16, content_type: &str, payload: String) {
+    let header = Header::from_bytes("Content-Type", content_type)
+        .expect("static header is valid");
+    let mut response = Response::from_string(payload)
+        .with_status_code(status)
+        .with_header(header);
+    for header in cors_headers() {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        eprintln!("Local API failed to send response: {}", e);
+    }
+}
+
+fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, &ErrorBody { error: message });
+}
+
+fn respond_preflight(request: Request) {
+    let mut response = Response::empty(204);
+    for header in cors_headers() {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        eprintln!("Local API failed to send response: {}", e);
+    }
+}
+
+// The token is the access control; CORS is open so pages can reach the save endpoint
+fn cors_headers() -> Vec<Header> {
+    [
+        ("Access-Control-Allow-Origin", "*"),
+        ("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+        ("Access-Control-Allow-Headers", "Authorization, Content-Type, X-Api-Token"),
+        ("Access-Control-Allow-Private-Network", "true"),
+    ]
+    .iter()
+    .filter_map(|(k, v)| Header::from_bytes(*k, *v).ok())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // The request as the server receives it after a client sent `raw`
+    fn received(raw: &'static str, check: impl FnOnce(Request)) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            stream.write_all(raw.as_bytes()).unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response
+        });
+        check(server.recv().unwrap());
+        let response = client.join().unwrap();
+        assert!(response.contains("Access-Control-Allow-Origin: *"), "{}", response);
+    }
+
+    #[test]
+    fn token_comes_from_a_header_or_the_query() {
+        received("GET /api/health HTTP/1.1\r\nAuthorization: Bearer secret\r\nConnection: close\r\n\r\n", |request| {
+            assert!(is_authorized(&request, "secret", None));
+            assert!(!is_authorized(&request, "other", None));
+            respond_json(request, 200, &serde_json::json!({ "status": "ok" }));
+        });
+        received("GET /api/health HTTP/1.1\r\nx-api-token:  secret \r\nConnection: close\r\n\r\n", |request| {
+            assert!(is_authorized(&request, "secret", Some("wrong")), "the header wins over the query");
+            respond_error(request, 401, "unauthorized");
+        });
+        received("GET /api/feed.xml?token=secret HTTP/1.1\r\nConnection: close\r\n\r\n", |request| {
+            assert!(is_authorized(&request, "secret", Some("secret")));
+            assert!(!is_authorized(&request, "secret", None));
+            assert!(!is_authorized(&request, "", Some("")), "no token configured, nothing is allowed");
+            respond_preflight(request);
+        });
+    }
+}
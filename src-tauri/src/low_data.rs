@@ -0,0 +1,46 @@
+// Low-data mode for metered connections (`low_data_mode` setting).
+//
+// While it's on, the fetchers hold back on their own, whatever the frontend
+// asks for: no image downloads (card images aren't probed, the reader and
+// exports go without), no full-page fetches (the reader, exports and the
+// prefetch use the stored content, lighter pages aren't looked for), no
+// README fetches for GitHub trending repos, and at most `ITEMS_PER_SOURCE`
+// items per source in a crawl. Feeds themselves are still fetched.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::platform::CrawlProfile;
+
+const ITEMS_PER_SOURCE: usize = 5;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Items taken from each source in a crawl under `profile`
+pub fn items_per_source(profile: &CrawlProfile) -> usize {
+    let items = profile.items_per_source;
+    if enabled() { items.min(ITEMS_PER_SOURCE) } else { items }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::profile_for;
+
+    #[test]
+    fn items_are_capped_only_while_enabled() {
+        // Nothing else under unit test reads the switch
+        set(true);
+        assert_eq!(items_per_source(profile_for(false)), ITEMS_PER_SOURCE);
+        assert_eq!(items_per_source(profile_for(true)), ITEMS_PER_SOURCE.min(profile_for(true).items_per_source));
+        set(false);
+        assert_eq!(items_per_source(profile_for(false)), profile_for(false).items_per_source);
+    }
+}
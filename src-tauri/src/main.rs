@@ -4,5 +4,14 @@ fn main() {
     // Load environment variables from .env files
     let _ = dotenvy::dotenv();
 
+    // MCP clients launch the binary with `--mcp` and talk JSON-RPC over stdio
+    if std::env::args().any(|arg| arg == "--mcp") {
+        if let Err(e) = ai_news_aggregator::run_mcp_stdio() {
+            eprintln!("MCP server failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     ai_news_aggregator::run();
 }
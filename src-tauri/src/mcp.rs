@@ -0,0 +1,195 @@
+// Model Context Protocol server over stdio, so MCP clients (Claude Desktop,
+// editors, agents) can query the crawled news database.
+//
+// Launched with `ai-news-aggregator --mcp`. Messages are newline-delimited
+// JSON-RPC 2.0; stdout is reserved for protocol frames, so diagnostics go to
+// stderr only.
+
+use std::io::{BufRead, Write};
+
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value};
+
+use crate::i18n::{self, tr, Msg};
+use crate::search_index::SearchScope;
+use crate::{get_setting, query_article, query_search, query_trending};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+pub fn serve_stdio(db_path: &str) -> Result<(), String> {
+    let conn = Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    ).map_err(|e| tr(Msg::DbOpenReadOnlyFailed, e))?;
+    // Messages in the app's language; nothing else has set it in this process
+    i18n::set_lang(i18n::Lang::from_code(&get_setting(&conn, "language", "zh").unwrap_or_default()));
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| tr(Msg::StdinReadFailed, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&conn, &message),
+            Err(e) => Some(error_response(Value::Null, -32700, &format!("Parse error: {}", e))),
+        };
+
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response).map_err(|e| tr(Msg::StdoutWriteFailed, e))?;
+            stdout.flush().map_err(|e| tr(Msg::StdoutWriteFailed, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Returns None for notifications, which must not be answered
+fn handle_message(conn: &Connection, message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let id = match id {
+        Some(id) => id,
+        None => {
+            if method != "notifications/initialized" {
+                eprintln!("MCP: ignoring notification '{}'", method);
+            }
+            return None;
+        }
+    };
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": "ai-news-aggregator",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => Ok(call_tool(conn, &params)),
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_articles",
+            "description": "Full-text search over crawled AI/tech news articles (titles, summaries, content).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search keywords" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 10, max 100)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_article",
+            "description": "Get a single article by its ID, including full stored content.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Article ID" }
+                },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "list_trending",
+            "description": "List the hottest recent articles, ranked by heat score then publish time.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "days": { "type": "integer", "description": "Look-back window in days (default 3, max 365)" },
+                    "category": { "type": "string", "description": "Optional category filter (AI, Tech, GitHub)" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 20, max 100)" }
+                }
+            }
+        }
+    ])
+}
+
+// Tool failures are reported in-band with `isError` per the MCP spec
+fn call_tool(conn: &Connection, params: &Value) -> Value {
+    let name = params["name"].as_str().unwrap_or_default();
+    let args = &params["arguments"];
+    let limit = |default: u64| args["limit"].as_u64().unwrap_or(default).clamp(1, 100) as usize;
+
+    let outcome = match name {
+        "search_articles" => match args["query"].as_str().map(str::trim).filter(|q| !q.is_empty()) {
+            Some(query) => query_search(conn, query, &SearchScope::default()).map(|mut articles| {
+                articles.truncate(limit(10));
+                json!(articles)
+            }).map_err(String::from),
+            None => Err("missing required argument 'query'".to_string()),
+        },
+        "get_article" => match args["id"].as_str() {
+            Some(id) => match query_article(conn, id) {
+                Ok(Some(article)) => Ok(json!(article)),
+                Ok(None) => Err(format!("article '{}' not found", id)),
+                Err(e) => Err(e.to_string()),
+            },
+            None => Err("missing required argument 'id'".to_string()),
+        },
+        "list_trending" => {
+            let days = args["days"].as_i64().unwrap_or(3).clamp(1, 365);
+            query_trending(conn, days, args["category"].as_str(), limit(20)).map(|articles| json!(articles)).map_err(String::from)
+        }
+        _ => Err(format!("unknown tool '{}'", name)),
+    };
+
+    match outcome {
+        Ok(value) => json!({
+            "content": [{ "type": "text", "text": serde_json::to_string_pretty(&value).unwrap_or_default() }],
+            "isError": false,
+        }),
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": e }],
+            "isError": true,
+        }),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_trending_clamps_days_out_of_range() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        for days in [json!(i64::MAX), json!(i64::MIN), json!(0)] {
+            let result = call_tool(&conn, &json!({ "name": "list_trending", "arguments": { "days": days } }));
+            assert_eq!(result["isError"], json!(false), "days {}: {}", days, result);
+        }
+    }
+
+    #[test]
+    fn unknown_tool_is_reported_in_band() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let result = call_tool(&conn, &json!({ "name": "drop_tables", "arguments": {} }));
+        assert_eq!(result["isError"], json!(true));
+    }
+}
@@ -0,0 +1,190 @@
+// Connectivity checks.
+//
+// A failed request alone doesn't say whether the machine is offline or the
+// remote end is down, so callers that want to defer work ask here first.
+// Domestic and international hosts are probed through their usual routes;
+// reaching either one counts as online. The proxy's port is checked on its
+// own so "proxy not running" can be told apart from "proxy can't get out".
+//
+// The scheduler refreshes the status every tick and emits
+// `app://network:changed` when any of it changes; while offline it skips
+// network jobs and ticks faster, so a crawl that came due runs as soon as
+// the connection is back.
+//
+// Before a crawl the proxy gets a cheap request of its own (`proxy_works`).
+// When it's down, `proxy_down_policy` decides what happens to the sources
+// that need it: "skip" records them as failed with "proxy unavailable"
+// without fetching, "direct" sends every request straight out until a
+// probe reaches the proxy again. Either way the crawl doesn't sit through a
+// timeout per international feed.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use rusqlite::Connection;
+
+use crate::{get_setting, proxy_url, HttpClients};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const PROXY_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const DIRECT_PROBE_URL: &str = "https://www.baidu.com";
+const PROXIED_PROBE_URL: &str = "https://www.gstatic.com/generate_204";
+
+// What a crawl does with sources that need the proxy while it's down
+pub const PROXY_DOWN_SKIP: &str = "skip";
+pub const PROXY_DOWN_DIRECT: &str = "direct";
+
+// Unknown values fall back to skipping
+pub fn normalize_proxy_down_policy(policy: &str) -> &'static str {
+    if policy.trim() == PROXY_DOWN_DIRECT { PROXY_DOWN_DIRECT } else { PROXY_DOWN_SKIP }
+}
+
+pub fn proxy_down_policy(conn: &Connection) -> Result<&'static str, String> {
+    Ok(normalize_proxy_down_policy(&get_setting(conn, "proxy_down_policy", PROXY_DOWN_SKIP)?))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStatus {
+    pub online: bool,
+    // Domestic probe reached without the proxy
+    pub direct: bool,
+    // International probe reached through the proxy
+    pub proxied: bool,
+    // Empty when none is configured (mobile without one in the environment)
+    pub proxy: String,
+    // The proxy accepts connections at all
+    pub proxy_listening: bool,
+    pub checked_at: String,
+}
+
+impl NetworkStatus {
+    fn same_state(&self, other: &NetworkStatus) -> bool {
+        self.online == other.online
+            && self.direct == other.direct
+            && self.proxied == other.proxied
+            && self.proxy_listening == other.proxy_listening
+    }
+}
+
+static LAST_STATUS: Mutex<Option<NetworkStatus>> = Mutex::new(None);
+
+async fn reachable(client: &reqwest::Client, url: &str) -> bool {
+    client.head(url).timeout(PROBE_TIMEOUT).send().await.is_ok()
+}
+
+async fn proxy_listening(proxy: &str) -> bool {
+    let Some(address) = reqwest::Url::parse(proxy)
+        .ok()
+        .and_then(|url| Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?)))
+    else {
+        return false;
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .is_some_and(|addr| TcpStream::connect_timeout(&addr, PROXY_CONNECT_TIMEOUT).is_ok())
+    })
+    .await
+    .unwrap_or(false)
+}
+
+// The proxy accepts connections and gets a request out; true when there's no proxy to check
+pub async fn proxy_works(http: &HttpClients) -> bool {
+    let Some(proxy) = http.proxy() else { return true };
+    proxy_listening(proxy).await && reachable(http.proxied(), PROXIED_PROBE_URL).await
+}
+
+pub async fn is_online(http: &HttpClients) -> bool {
+    reachable(http.get(false), DIRECT_PROBE_URL).await || reachable(http.get(true), PROXIED_PROBE_URL).await
+}
+
+// Probe everything, remember the result and announce it if it changed
+pub async fn refresh(app: &AppHandle, http: &HttpClients) -> NetworkStatus {
+    let proxy = proxy_url();
+    let listening = match &proxy {
+        Some(proxy) => proxy_listening(proxy).await,
+        None => false,
+    };
+    let direct = reachable(http.get(false), DIRECT_PROBE_URL).await;
+    let proxied = reachable(http.proxied(), PROXIED_PROBE_URL).await;
+    let status = NetworkStatus {
+        online: direct || proxied,
+        direct,
+        proxied,
+        proxy: proxy.unwrap_or_default(),
+        proxy_listening: listening,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let changed = {
+        let mut last = LAST_STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = last.as_ref().is_none_or(|last| !last.same_state(&status));
+        *last = Some(status.clone());
+        changed
+    };
+    if changed {
+        let _ = app.emit("app://network:changed", status.clone());
+    }
+    status
+}
+
+// Result of the latest probe, if any ran yet
+pub fn last_status() -> Option<NetworkStatus> {
+    LAST_STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// Known offline from the latest probe (unknown counts as online)
+pub fn is_known_offline() -> bool {
+    last_status().is_some_and(|status| !status.online)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(online: bool, checked_at: &str) -> NetworkStatus {
+        NetworkStatus {
+            online,
+            direct: online,
+            proxied: false,
+            proxy: String::new(),
+            proxy_listening: false,
+            checked_at: checked_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn proxy_down_policy_defaults_to_skipping() {
+        assert_eq!(normalize_proxy_down_policy(" direct "), PROXY_DOWN_DIRECT);
+        assert_eq!(normalize_proxy_down_policy("wait"), PROXY_DOWN_SKIP);
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(proxy_down_policy(&conn).unwrap(), PROXY_DOWN_SKIP);
+        crate::set_setting(&conn, "proxy_down_policy", "direct").unwrap();
+        assert_eq!(proxy_down_policy(&conn).unwrap(), PROXY_DOWN_DIRECT);
+    }
+
+    #[test]
+    fn status_changes_ignore_the_check_time() {
+        assert!(status(true, "12:00").same_state(&status(true, "12:05")), "only the state counts");
+        assert!(!status(true, "12:00").same_state(&status(false, "12:00")));
+    }
+
+    #[test]
+    fn proxy_port_is_checked_on_its_own() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = format!("http://{}", listener.local_addr().unwrap());
+        let closed = {
+            let spare = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", spare.local_addr().unwrap())
+        };
+        assert!(tauri::async_runtime::block_on(proxy_listening(&open)));
+        assert!(!tauri::async_runtime::block_on(proxy_listening(&closed)));
+        assert!(!tauri::async_runtime::block_on(proxy_listening("not a url")));
+    }
+}
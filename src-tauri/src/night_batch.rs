@@ -0,0 +1,317 @@
+// Night-batch mode: expensive work waits for a quiet time.
+//
+// With `night_batch_enabled`, a crawl outside the batch window only stores
+// what the feeds carry: AI summaries stay pending template briefs (as in
+// lazy mode), GitHub trending repos go in with their one-line description,
+// and paper-code links and comment-thread summaries are skipped. Each
+// skipped step is queued in `batch_queue`, and the scheduler works the queue
+// off BATCH_SIZE entries at a time while the window is open: between
+// `night_batch_start_hour` and `night_batch_end_hour` local time, or, with
+// `night_batch_when_idle`, once the UI has sent no command for IDLE_MINUTES
+// and the machine runs on AC power. Crawls while the window is open do
+// everything at once as before. Opening an article whose summary is still
+// queued summarizes it right away, as lazy mode does. Steps queued by a crawl
+// cut short when the app closed run on the next start even with the mode off.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::i18n::{tr, Msg};
+use crate::{
+    ai, comment_highlights, crawler, default_night_batch_end_hour, default_night_batch_start_hour, get_setting,
+    glossary, paper_code, shutdown, store_summary, summarize_or_template, HttpClients,
+};
+
+pub const TASK_SUMMARY: &str = "summary";
+pub const TASK_README: &str = "readme";
+pub const TASK_PAPER_CODE: &str = "paper_code";
+pub const TASK_COMMENTS: &str = "comments";
+
+// Queue entries handled per scheduler run
+const BATCH_SIZE: usize = 20;
+const IDLE_MINUTES: u64 = 15;
+
+// When the UI last sent a command; None = not since startup
+static LAST_ACTIVITY: Mutex<Option<Instant>> = Mutex::new(None);
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS batch_queue (
+            article_id TEXT NOT NULL,
+            task TEXT NOT NULL,
+            queued_at TEXT NOT NULL,
+            PRIMARY KEY (article_id, task)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+struct Config {
+    enabled: bool,
+    start_hour: u32,
+    end_hour: u32,
+    when_idle: bool,
+}
+
+impl Config {
+    fn load(conn: &Connection) -> Config {
+        let hour = |key: &str, default: u32| {
+            get_setting(conn, key, "").ok().and_then(|v| v.parse().ok()).unwrap_or(default).min(23)
+        };
+        Config {
+            enabled: get_setting(conn, "night_batch_enabled", "false").is_ok_and(|v| v == "true"),
+            start_hour: hour("night_batch_start_hour", default_night_batch_start_hour()),
+            end_hour: hour("night_batch_end_hour", default_night_batch_end_hour()),
+            when_idle: get_setting(conn, "night_batch_when_idle", "true").is_ok_and(|v| v == "true"),
+        }
+    }
+
+    fn in_hours(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+// Any command from the UI means the user is around
+pub fn touch() {
+    *LAST_ACTIVITY.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+}
+
+fn is_idle() -> bool {
+    LAST_ACTIVITY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_none_or(|at| at.elapsed() >= Duration::from_secs(IDLE_MINUTES * 60))
+}
+
+// Machines without a battery count as on AC power
+#[cfg(target_os = "linux")]
+fn on_ac_power() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else { return true };
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap_or_default().trim().to_string();
+    let mains: Vec<String> = supplies
+        .filter_map(Result::ok)
+        .filter(|supply| read(supply.path().join("type")) == "Mains")
+        .map(|supply| read(supply.path().join("online")))
+        .collect();
+    mains.is_empty() || mains.iter().any(|online| online == "1")
+}
+
+#[cfg(target_os = "macos")]
+fn on_ac_power() -> bool {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .map(|out| !String::from_utf8_lossy(&out.stdout).contains("'Battery Power'"))
+        .unwrap_or(true)
+}
+
+#[cfg(target_os = "windows")]
+fn on_ac_power() -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    // BatteryStatus 1 = discharging; no battery prints nothing
+    std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_Battery).BatteryStatus"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim() != "1")
+        .unwrap_or(true)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn on_ac_power() -> bool {
+    true
+}
+
+// Whether deferred work may run now
+pub fn window_open(conn: &Connection) -> bool {
+    use chrono::Timelike;
+    let config = Config::load(conn);
+    config.enabled && (config.in_hours(chrono::Local::now().hour()) || (config.when_idle && is_idle() && on_ac_power()))
+}
+
+// Whether queued work may run now: in the window, or any time with night-batch mode off
+// (steps a crawl queued when the app was closed mid-run)
+pub fn may_run(conn: &Connection) -> bool {
+    !Config::load(conn).enabled || window_open(conn)
+}
+
+// Whether a crawl should queue its expensive steps instead of running them
+pub fn defers(conn: &Connection) -> bool {
+    Config::load(conn).enabled && !window_open(conn)
+}
+
+pub fn queue(conn: &Connection, task: &str, ids: &[String]) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut stmt = conn
+        .prepare_cached("INSERT OR IGNORE INTO batch_queue (article_id, task, queued_at) VALUES (?1, ?2, ?3)")
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    for id in ids {
+        stmt.execute(params![id, task, now]).map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    Ok(())
+}
+
+pub fn is_queued(conn: &Connection, id: &str, task: &str) -> bool {
+    conn.query_row("SELECT 1 FROM batch_queue WHERE article_id = ?1 AND task = ?2", params![id, task], |_| Ok(()))
+        .optional()
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+pub fn has_queued(conn: &Connection) -> bool {
+    conn.query_row("SELECT 1 FROM batch_queue LIMIT 1", [], |_| Ok(())).optional().ok().flatten().is_some()
+}
+
+// Oldest entries, removed from the queue as they're taken so a failing one isn't retried forever
+fn take(conn: &Connection) -> Result<Vec<(String, String)>, String> {
+    let entries: Vec<(String, String)> = conn
+        .prepare("SELECT article_id, task FROM batch_queue ORDER BY queued_at, rowid LIMIT ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![BATCH_SIZE as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    for (id, task) in &entries {
+        conn.execute("DELETE FROM batch_queue WHERE article_id = ?1 AND task = ?2", params![id, task])
+            .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    }
+    Ok(entries)
+}
+
+async fn summarize(db: &Mutex<Connection>, http: &HttpClients, id: &str) -> Result<(), String> {
+    let (article, config, glossary) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article = conn
+            .query_row(
+                "SELECT title, COALESCE(content, ''), COALESCE(source, '') FROM articles WHERE id = ?1 AND summary_status = 'pending'",
+                params![id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            )
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+        (article, ai::AiConfig::load(&conn).ok(), glossary::list(&conn)?)
+    };
+    // Summarized when it was opened, or gone
+    let Some((title, content, source)) = article else { return Ok(()) };
+    let summary = summarize_or_template(http, config.as_ref(), &glossary, &title, &content, &source).await;
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    Ok(store_summary(&conn, id, &summary)?)
+}
+
+// Put back what a run took but didn't get to before the app started shutting down
+fn requeue(db: &Mutex<Connection>, task: &str, ids: &[String]) -> Result<(), String> {
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    queue(&conn, task, ids)
+}
+
+// Work off the oldest queued steps; returns how many were taken
+pub async fn run(db: &Mutex<Connection>, http: &HttpClients) -> Result<usize, String> {
+    let _busy = shutdown::busy();
+    let entries = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        take(&conn)?
+    };
+    let ids_for = |task: &str| -> Vec<String> {
+        entries.iter().filter(|(_, t)| t == task).map(|(id, _)| id.clone()).collect()
+    };
+
+    let summaries = ids_for(TASK_SUMMARY);
+    for (i, id) in summaries.iter().enumerate() {
+        if shutdown::requested() {
+            requeue(db, TASK_SUMMARY, &summaries[i..])?;
+            break;
+        }
+        if let Err(e) = summarize(db, http, id).await {
+            eprintln!("Deferred summary for {} failed: {}", id, e);
+        }
+    }
+    let readmes = ids_for(TASK_README);
+    for (i, id) in readmes.iter().enumerate() {
+        if shutdown::requested() {
+            requeue(db, TASK_README, &readmes[i..])?;
+            break;
+        }
+        if let Err(e) = crawler::add_github_readme(db, http, id).await {
+            eprintln!("Deferred README fetch for {} failed: {}", id, e);
+        }
+    }
+    if shutdown::requested() {
+        requeue(db, TASK_PAPER_CODE, &ids_for(TASK_PAPER_CODE))?;
+        requeue(db, TASK_COMMENTS, &ids_for(TASK_COMMENTS))?;
+        return Ok(entries.len());
+    }
+    paper_code::link_new_articles(db, http, &ids_for(TASK_PAPER_CODE)).await;
+    comment_highlights::summarize_new_articles(db, http, &ids_for(TASK_COMMENTS)).await;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set_setting;
+
+    #[test]
+    fn window_hours_may_wrap_past_midnight() {
+        let config = |start_hour, end_hour| Config { enabled: true, start_hour, end_hour, when_idle: false };
+        assert!(config(1, 6).in_hours(1));
+        assert!(!config(1, 6).in_hours(6));
+        assert!(config(23, 6).in_hours(23));
+        assert!(config(23, 6).in_hours(0));
+        assert!(!config(23, 6).in_hours(12));
+        assert!(!config(3, 3).in_hours(3), "an empty window");
+
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        set_setting(&conn, "night_batch_start_hour", "30").unwrap();
+        set_setting(&conn, "night_batch_end_hour", "soon").unwrap();
+        let config = Config::load(&conn);
+        assert!(!config.enabled);
+        assert_eq!(config.start_hour, 23);
+        assert_eq!(config.end_hour, default_night_batch_end_hour());
+    }
+
+    #[test]
+    fn crawls_defer_only_outside_an_enabled_window() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(may_run(&conn));
+        assert!(!defers(&conn));
+
+        // Enabled with a window that never opens
+        set_setting(&conn, "night_batch_enabled", "true").unwrap();
+        set_setting(&conn, "night_batch_start_hour", "3").unwrap();
+        set_setting(&conn, "night_batch_end_hour", "3").unwrap();
+        set_setting(&conn, "night_batch_when_idle", "false").unwrap();
+        assert!(!window_open(&conn));
+        assert!(!may_run(&conn));
+        assert!(defers(&conn));
+    }
+
+    #[test]
+    fn queue_is_taken_oldest_first_in_batches() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(!has_queued(&conn));
+        let ids: Vec<String> = (0..BATCH_SIZE + 2).map(|i| format!("a{:02}", i)).collect();
+        queue(&conn, TASK_SUMMARY, &ids).unwrap();
+        queue(&conn, TASK_SUMMARY, &ids[..1]).unwrap();
+        queue(&conn, TASK_COMMENTS, &ids[..1]).unwrap();
+        assert!(is_queued(&conn, "a00", TASK_SUMMARY));
+        assert!(!is_queued(&conn, "a01", TASK_COMMENTS));
+
+        let taken = take(&conn).unwrap();
+        assert_eq!(taken.len(), BATCH_SIZE);
+        assert_eq!(taken[0], ("a00".to_string(), TASK_SUMMARY.to_string()));
+        assert!(!is_queued(&conn, "a00", TASK_SUMMARY));
+        let rest = take(&conn).unwrap();
+        assert_eq!(rest.len(), 3, "queued once however often it's queued");
+        assert!(take(&conn).unwrap().is_empty());
+        assert!(!has_queued(&conn));
+    }
+}
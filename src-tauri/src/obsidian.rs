@@ -0,0 +1,287 @@
+// Obsidian vault exporter: renders articles into Markdown notes using a
+// configurable template and filename pattern.
+//
+// `obsidian_exports` maps article IDs to the note written for them, so a
+// re-export overwrites the existing note instead of creating a duplicate,
+// even if the title or the filename pattern changed since.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::annotations;
+use crate::i18n::{t, tr, Msg};
+use crate::{get_setting, query_article, set_setting, Article};
+
+pub const DEFAULT_TEMPLATE: &str = "---
+title: \"{{title}}\"
+source: \"{{source}}\"
+url: {{url}}
+published: {{published_at}}
+tags:
+{{tags_yaml}}
+---
+
+# {{title}}
+
+> [!summary]
+> {{summary}}
+
+## Highlights
+
+{{highlights}}
+
+## Notes
+
+{{notes}}
+
+[原文链接]({{url}})
+";
+
+pub const DEFAULT_FILENAME_PATTERN: &str = "{{date}} {{title}}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObsidianConfig {
+    pub vault_path: String,
+    pub folder: String,
+    pub template: String,
+    pub filename_pattern: String,
+}
+
+impl Default for ObsidianConfig {
+    fn default() -> Self {
+        ObsidianConfig {
+            vault_path: String::new(),
+            folder: "AI News".to_string(),
+            template: DEFAULT_TEMPLATE.to_string(),
+            filename_pattern: DEFAULT_FILENAME_PATTERN.to_string(),
+        }
+    }
+}
+
+impl ObsidianConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = ObsidianConfig::default();
+        let or_default = |value: String, default: String| if value.trim().is_empty() { default } else { value };
+        Ok(ObsidianConfig {
+            vault_path: get_setting(conn, "obsidian.vault_path", "")?,
+            folder: get_setting(conn, "obsidian.folder", &defaults.folder)?,
+            template: or_default(get_setting(conn, "obsidian.template", "")?, defaults.template),
+            filename_pattern: or_default(get_setting(conn, "obsidian.filename_pattern", "")?, defaults.filename_pattern),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        set_setting(conn, "obsidian.vault_path", self.vault_path.trim())?;
+        set_setting(conn, "obsidian.folder", self.folder.trim())?;
+        set_setting(conn, "obsidian.template", &self.template)?;
+        set_setting(conn, "obsidian.filename_pattern", self.filename_pattern.trim())?;
+        Ok(())
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS obsidian_exports (
+            article_id TEXT PRIMARY KEY,
+            file_path TEXT NOT NULL,
+            exported_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ObsidianExportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub files: Vec<String>,
+}
+
+pub fn export_articles(conn: &Connection, ids: &[String]) -> Result<ObsidianExportResult, String> {
+    let config = ObsidianConfig::load(conn)?;
+    if config.vault_path.trim().is_empty() {
+        return Err(t(Msg::ObsidianVaultMissing));
+    }
+    let target_dir = Path::new(config.vault_path.trim()).join(config.folder.trim());
+    std::fs::create_dir_all(&target_dir)
+        .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", target_dir.display(), e)))?;
+
+    let mut result = ObsidianExportResult::default();
+    for id in ids {
+        let article = query_article(conn, id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+        let annotations = annotations::load(conn, id)?;
+        let markdown = render(&config.template, &article, &annotations);
+
+        // Reuse the previously written note if it is still there
+        let previous: Option<String> = conn
+            .query_row(
+                "SELECT file_path FROM obsidian_exports WHERE article_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .ok();
+        let (path, is_update) = match previous.map(PathBuf::from).filter(|p| p.exists()) {
+            Some(path) => (path, true),
+            None => (unique_note_path(&target_dir, &render_filename(&config.filename_pattern, &article)), false),
+        };
+
+        std::fs::write(&path, markdown)
+            .map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path.display(), e)))?;
+
+        let path_str = path.to_string_lossy().to_string();
+        conn.execute(
+            "INSERT OR REPLACE INTO obsidian_exports (article_id, file_path, exported_at) VALUES (?1, ?2, ?3)",
+            params![id, path_str, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+
+        if is_update {
+            result.updated += 1;
+        } else {
+            result.created += 1;
+        }
+        result.files.push(path_str);
+    }
+
+    Ok(result)
+}
+
+// Placeholders: {{id}} {{title}} {{url}} {{source}} {{category}} {{published_at}} {{date}}
+// {{summary}} {{content}} {{notes}} {{highlights}} {{tags}} {{tags_yaml}}
+fn render(template: &str, article: &Article, annotations: &annotations::Annotations) -> String {
+    let highlights = annotations
+        .highlights
+        .iter()
+        .map(|h| format!("> {}", h.text.replace('\n', "\n> ")))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let tags = annotations
+        .tags
+        .iter()
+        .map(|tag| format!("#{}", tag.replace(' ', "-")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let tags_yaml = if annotations.tags.is_empty() {
+        "  - ai-news".to_string()
+    } else {
+        annotations
+            .tags
+            .iter()
+            .map(|tag| format!("  - {}", tag.replace(' ', "-")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    template
+        .replace("{{id}}", &article.id)
+        .replace("{{title}}", &article.title.replace('"', "'"))
+        .replace("{{url}}", &article.url)
+        .replace("{{source}}", &article.source)
+        .replace("{{category}}", &article.category)
+        .replace("{{published_at}}", &article.published_at)
+        .replace("{{date}}", article_date(article))
+        .replace("{{summary}}", &article.summary.replace('\n', "\n> "))
+        .replace("{{content}}", &article.content)
+        .replace("{{notes}}", &annotations.note)
+        .replace("{{highlights}}", &highlights)
+        .replace("{{tags_yaml}}", &tags_yaml)
+        .replace("{{tags}}", &tags)
+}
+
+fn article_date(article: &Article) -> &str {
+    article.published_at.get(..10).unwrap_or(&article.published_at)
+}
+
+fn render_filename(pattern: &str, article: &Article) -> String {
+    let name = pattern
+        .replace("{{date}}", article_date(article))
+        .replace("{{title}}", &article.title)
+        .replace("{{source}}", &article.source)
+        .replace("{{id}}", &article.id);
+    sanitize_filename(&name)
+}
+
+// Strip characters that are invalid on Windows/macOS or break Obsidian links
+pub(crate) fn sanitize_filename(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let collapsed = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let truncated: String = collapsed.chars().take(120).collect();
+    let trimmed = truncated.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if trimmed.is_empty() {
+        "untitled".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn unique_note_path(dir: &Path, stem: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.md", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).md", stem, n));
+        n += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str, title: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, 'Line one\nline two', '', 'https://example.com/post', 'Blog', 'AI', ?3, ?3)",
+            params![id, title, chrono::Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn filenames_drop_characters_that_break_paths_or_links() {
+        assert_eq!(sanitize_filename("2024-01-05 GPT-5: what's [new]? a/b #1"), "2024-01-05 GPT-5 what's new a b 1");
+        assert_eq!(sanitize_filename(" ...hidden. "), "hidden");
+        assert_eq!(sanitize_filename("???"), "untitled");
+        assert_eq!(sanitize_filename(&"长".repeat(200)).chars().count(), 120);
+    }
+
+    #[test]
+    fn notes_render_annotations_and_are_overwritten_on_reexport() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "a1", "Say \"hello\" to agents");
+        annotations::set_note(&conn, "a1", "Worth a second read").unwrap();
+        annotations::add_highlight(&conn, "a1", "  first line\nsecond line ").unwrap();
+        annotations::set_tags(&conn, "a1", &[" agents ".to_string(), "open source".to_string(), String::new()]).unwrap();
+
+        let vault = std::env::temp_dir().join(format!("news-obsidian-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&vault);
+        ObsidianConfig { vault_path: vault.to_string_lossy().to_string(), ..Default::default() }.save(&conn).unwrap();
+
+        let first = export_articles(&conn, &["a1".to_string()]).unwrap();
+        assert_eq!((first.created, first.updated), (1, 0));
+        let note = std::fs::read_to_string(&first.files[0]).unwrap();
+        assert!(note.contains("title: \"Say 'hello' to agents\""), "{}", note);
+        assert!(note.contains("tags:\n  - agents\n  - open-source\n"), "{}", note);
+        assert!(note.contains("> Line one\n> line two"), "{}", note);
+        assert!(note.contains("> first line\n> second line"), "{}", note);
+        assert!(note.contains("Worth a second read"), "{}", note);
+
+        conn.execute("UPDATE articles SET title = 'Renamed' WHERE id = 'a1'", []).unwrap();
+        let second = export_articles(&conn, &["a1".to_string()]).unwrap();
+        assert_eq!((second.created, second.updated), (0, 1));
+        assert_eq!(second.files, first.files);
+        assert_eq!(std::fs::read_dir(vault.join("AI News")).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&vault);
+    }
+}
@@ -0,0 +1,232 @@
+// First-run source selection.
+//
+// A new database starts without sources. `first_run_setup` offers curated
+// bundles the user picks from; enabling one adds its sources (names already
+// taken are left alone, so a bundle can be enabled again later to fill in
+// what was deleted). Completing the step, even with nothing picked, sets
+// `onboarding.completed` so the frontend stops showing it. Databases that
+// already had sources before this existed count as onboarded.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::github_trending::{self, TrendingConfig};
+use crate::i18n::{lang, tr, Lang, Msg};
+use crate::{get_setting, set_setting};
+
+const COMPLETED_KEY: &str = "onboarding.completed";
+
+// (name, url, source_type)
+type BundleSource = (&'static str, &'static str, &'static str);
+
+struct BundleDef {
+    id: &'static str,
+    title: (&'static str, &'static str),
+    description: (&'static str, &'static str),
+    recommended: bool,
+    sources: &'static [BundleSource],
+    // (source name, languages) of the sources above added as configured GitHub trending sources
+    trending: &'static [(&'static str, &'static [&'static str])],
+}
+
+const BUNDLES: &[BundleDef] = &[
+    BundleDef {
+        id: "ai_research",
+        title: ("AI 研究与资讯", "AI research & news"),
+        description: ("AI 研究讨论与主流科技媒体的 AI 频道", "AI research discussion and the AI sections of major tech outlets"),
+        recommended: true,
+        sources: &[
+            ("Hacker News Frontpage", "https://hnrss.org/frontpage", "RSS"),
+            ("Hacker News AI", "https://hnrss.org/newest?q=AI+OR+machine+learning+OR+GPT+OR+LLM", "RSS"),
+            ("Reddit MachineLearning", "https://www.reddit.com/r/MachineLearning/.rss", "RSS"),
+            ("Dev.to AI Tag", "https://dev.to/feed/tag/ai", "RSS"),
+            ("The Verge AI", "https://www.theverge.com/ai-ml/rss", "RSS"),
+            ("Ars Technica AI", "https://arstechnica.com/ai/feed/", "RSS"),
+            ("TechCrunch AI", "https://techcrunch.com/category/artificial-intelligence/feed/", "RSS"),
+        ],
+        trending: &[],
+    },
+    BundleDef {
+        id: "chinese_tech",
+        title: ("中文科技媒体", "Chinese tech media"),
+        description: ("国内开发者社区与技术媒体", "Chinese developer communities and tech media"),
+        recommended: true,
+        sources: &[
+            ("OSChina 资讯", "https://www.oschina.net/news/rss", "RSS"),
+            ("V2EX 技术新穗", "https://www.v2ex.com/index.xml", "RSS"),
+            ("InfoQ 中文", "https://www.infoq.cn/feed", "RSS"),
+        ],
+        trending: &[],
+    },
+    BundleDef {
+        id: "github",
+        title: ("GitHub 热门", "GitHub trending"),
+        description: ("GitHub 每日热门仓库，按语言分类", "Daily trending GitHub repositories, overall and by language"),
+        recommended: true,
+        sources: &[
+            ("GitHub Trending (all)", "https://github.com/trending", "WEB"),
+            ("GitHub Trending by language", "https://github.com/trending/python", "WEB"),
+        ],
+        trending: &[("GitHub Trending by language", &["python", "typescript", "rust"])],
+    },
+    BundleDef {
+        id: "podcasts",
+        title: ("播客", "Podcasts"),
+        description: ("AI 领域的访谈与技术播客", "Interviews and technical podcasts about AI"),
+        recommended: false,
+        sources: &[
+            ("Latent Space", "https://www.latent.space/feed", "RSS"),
+            ("Practical AI", "https://changelog.com/practicalai/feed", "RSS"),
+            ("Lex Fridman Podcast", "https://lexfridman.com/feed/podcast/", "RSS"),
+        ],
+        trending: &[],
+    },
+];
+
+#[derive(Debug, Serialize)]
+pub struct OnboardingSource {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub source_type: &'static str,
+    // Already in the source list
+    pub added: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Bundle {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub recommended: bool,
+    pub sources: Vec<OnboardingSource>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FirstRunState {
+    pub completed: bool,
+    pub bundles: Vec<Bundle>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FirstRunChoice {
+    // Bundle ids to enable; empty = skip without adding anything
+    #[serde(default)]
+    pub bundles: Vec<String>,
+}
+
+fn localized((zh, en): (&'static str, &'static str)) -> &'static str {
+    match lang() {
+        Lang::Zh => zh,
+        Lang::En => en,
+    }
+}
+
+fn source_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row("SELECT 1 FROM sources WHERE name = ?1", params![name], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| tr(Msg::DbQueryFailed, e))
+}
+
+// Mark databases that had sources before onboarding existed as onboarded
+pub fn init(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value)
+         SELECT ?1, CASE WHEN EXISTS (SELECT 1 FROM sources) THEN 'true' ELSE 'false' END",
+        params![COMPLETED_KEY],
+    )?;
+    Ok(())
+}
+
+pub fn state(conn: &Connection) -> Result<FirstRunState, String> {
+    let mut bundles = Vec::new();
+    for def in BUNDLES {
+        let mut sources = Vec::new();
+        for &(name, url, source_type) in def.sources {
+            sources.push(OnboardingSource { name, url, source_type, added: source_exists(conn, name)? });
+        }
+        bundles.push(Bundle {
+            id: def.id,
+            title: localized(def.title),
+            description: localized(def.description),
+            recommended: def.recommended,
+            sources,
+        });
+    }
+    Ok(FirstRunState {
+        completed: get_setting(conn, COMPLETED_KEY, "false")? == "true",
+        bundles,
+    })
+}
+
+// Add the chosen bundles' sources and mark onboarding done. Unknown ids are ignored
+pub fn complete(conn: &Connection, choice: &FirstRunChoice) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("INSERT OR IGNORE INTO sources (id, name, url, source_type, is_active) VALUES (?1, ?2, ?3, ?4, 1)")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    for def in BUNDLES.iter().filter(|def| choice.bundles.iter().any(|id| id == def.id)) {
+        for &(name, url, source_type) in def.sources {
+            if let Some(&(_, languages)) = def.trending.iter().find(|(trending, _)| *trending == name) {
+                // Left alone when the name is taken, like the plain sources
+                if !source_exists(conn, name)? {
+                    let languages = languages.iter().map(|l| l.to_string()).collect();
+                    github_trending::save(conn, name, &TrendingConfig { languages, ..Default::default() })?;
+                }
+                continue;
+            }
+            stmt.execute(params![uuid::Uuid::new_v4().to_string(), name, url, source_type])
+                .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+        }
+    }
+    Ok(set_setting(conn, COMPLETED_KEY, "true")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM sources", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn enabling_a_bundle_adds_its_sources_once() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let first_run = state(&conn).unwrap();
+        assert!(!first_run.completed);
+        assert_eq!(source_count(&conn), 0, "nothing seeded");
+        let ids: Vec<&str> = first_run.bundles.iter().map(|b| b.id).collect();
+        assert_eq!(ids, ["ai_research", "chinese_tech", "github", "podcasts"]);
+
+        let choice = FirstRunChoice { bundles: vec!["podcasts".to_string(), "unknown".to_string()] };
+        complete(&conn, &choice).unwrap();
+        let first_run = state(&conn).unwrap();
+        assert!(first_run.completed);
+        let podcasts = first_run.bundles.iter().find(|b| b.id == "podcasts").unwrap();
+        assert!(podcasts.sources.iter().all(|s| s.added));
+        assert_eq!(source_count(&conn), 3);
+
+        complete(&conn, &choice).unwrap();
+        assert_eq!(source_count(&conn), 3, "names already taken are left alone");
+    }
+
+    #[test]
+    fn skipping_still_completes_onboarding() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        complete(&conn, &FirstRunChoice { bundles: Vec::new() }).unwrap();
+        assert!(state(&conn).unwrap().completed);
+        assert_eq!(source_count(&conn), 0);
+    }
+
+    #[test]
+    fn github_bundle_configures_its_trending_languages() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        complete(&conn, &FirstRunChoice { bundles: vec!["github".to_string()] }).unwrap();
+        let by_language = TrendingConfig::load(&conn, "GitHub Trending by language").unwrap().unwrap();
+        assert_eq!(by_language.languages, ["python", "typescript", "rust"]);
+        let stored: Option<String> = conn
+            .query_row("SELECT trending_config FROM sources WHERE name = 'GitHub Trending (all)'", [], |row| row.get(0))
+            .unwrap();
+        assert!(stored.is_none(), "the overall page stays a plain URL");
+    }
+}
@@ -0,0 +1,173 @@
+// Action registry for the frontend command palette.
+//
+// Each action is either a backend command to invoke (with prefilled
+// arguments plus any `params` the user has to type in) or a frontend route
+// to open. Add an entry here when adding a command worth reaching from the
+// keyboard; per-folder entries are generated from the database.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::folders;
+use crate::i18n::{lang, Lang};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    // `invoke(command, args)` after filling in `params`
+    Command,
+    // Open `route` in the UI with `args`
+    Navigate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionParam {
+    // Dotted path in `args` the value goes to, e.g. "payload.url"
+    pub path: &'static str,
+    pub label: &'static str,
+    // "text" | "url"
+    pub input: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaletteAction {
+    pub id: String,
+    pub title: String,
+    pub group: &'static str,
+    pub kind: ActionKind,
+    pub command: Option<&'static str>,
+    pub route: Option<&'static str>,
+    pub args: Value,
+    pub params: Vec<ActionParam>,
+    // Suggested key binding, `mod` = Ctrl / Cmd
+    pub shortcut: Option<&'static str>,
+}
+
+fn localized(zh: &'static str, en: &'static str) -> &'static str {
+    match lang() {
+        Lang::Zh => zh,
+        Lang::En => en,
+    }
+}
+
+fn command(id: &str, title: &str, group: &'static str, command: &'static str) -> PaletteAction {
+    PaletteAction {
+        id: id.to_string(),
+        title: title.to_string(),
+        group,
+        kind: ActionKind::Command,
+        command: Some(command),
+        route: None,
+        args: json!({}),
+        params: Vec::new(),
+        shortcut: None,
+    }
+}
+
+fn navigate(id: &str, title: &str, route: &'static str) -> PaletteAction {
+    PaletteAction {
+        id: id.to_string(),
+        title: title.to_string(),
+        group: "navigation",
+        kind: ActionKind::Navigate,
+        command: None,
+        route: Some(route),
+        args: json!({}),
+        params: Vec::new(),
+        shortcut: None,
+    }
+}
+
+fn param(path: &'static str, label: &'static str, input: &'static str) -> ActionParam {
+    ActionParam { path, label, input }
+}
+
+pub fn actions(conn: &Connection) -> Result<Vec<PaletteAction>, String> {
+    let mut actions = vec![
+        PaletteAction {
+            shortcut: Some("mod+r"),
+            ..command("crawl_now", localized("立即抓取", "Crawl now"), "crawl", "crawler_run_once")
+        },
+        PaletteAction {
+            params: vec![param("payload.url", localized("链接", "URL"), "url")],
+            shortcut: Some("mod+n"),
+            ..command("add_url", localized("添加链接", "Add URL"), "articles", "manual_add")
+        },
+        PaletteAction {
+            params: vec![param("payload.keyword", localized("关键词", "Keyword"), "text")],
+            shortcut: Some("mod+k"),
+            ..command("search", localized("搜索文章", "Search articles"), "articles", "search_query")
+        },
+        PaletteAction {
+            shortcut: Some("shift+a"),
+            ..command("mark_all_read", localized("全部标为已读", "Mark all as read"), "articles", "articles_mark_all_read")
+        },
+        command(
+            "regenerate_summaries",
+            localized("重新生成摘要", "Regenerate summaries"),
+            "articles",
+            "articles_regenerate_summaries",
+        ),
+        command("cleanup", localized("清理旧文章", "Clean up old articles"), "articles", "cleanup_old_articles"),
+        command("export_feed", localized("导出收藏 RSS", "Export bookmarks feed"), "export", "export_feed"),
+        command("export_epub", localized("导出 EPUB", "Export EPUB"), "export", "export_epub"),
+        command("kindle_send_digest", localized("发送摘要到 Kindle", "Send digest to Kindle"), "export", "kindle_send_digest"),
+        command("digest_email", localized("发送摘要邮件", "Send digest email"), "export", "digest_email"),
+        command("digest_weekly", localized("生成本周周报", "Generate weekly roundup"), "export", "digest_weekly"),
+        command("cloud_sync_now", localized("立即同步", "Sync now"), "sync", "cloud_sync_now"),
+        command("greader_sync", localized("同步 Google Reader 服务", "Sync Google Reader service"), "sync", "greader_sync"),
+        PaletteAction {
+            shortcut: Some("mod+,"),
+            ..navigate("open_settings", localized("打开设置", "Open settings"), "settings")
+        },
+        navigate("open_bookmarks", localized("查看收藏", "Show bookmarks"), "bookmarks"),
+    ];
+
+    for folder in folders::list(conn)? {
+        actions.push(PaletteAction {
+            args: json!({ "folder_id": folder.id }),
+            ..navigate(
+                &format!("open_folder:{}", folder.id),
+                &format!("{}: {}", localized("打开文件夹", "Open folder"), folder.name),
+                "folder",
+            )
+        });
+    }
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_folder_gets_an_entry_and_ids_are_unique() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let before = actions(&conn).unwrap().len();
+        let papers = folders::save(
+            &conn,
+            folders::Folder { id: String::new(), name: "Papers".into(), parent_id: None, position: 0, sources: Vec::new() },
+        )
+        .unwrap();
+
+        let actions = actions(&conn).unwrap();
+        assert_eq!(actions.len(), before + 1);
+        let entry = actions.iter().find(|a| a.id == format!("open_folder:{}", papers.id)).unwrap();
+        assert_eq!(entry.route, Some("folder"));
+        assert_eq!(entry.args, json!({ "folder_id": papers.id }));
+        assert!(entry.title.ends_with(": Papers"));
+
+        let mut ids: Vec<&str> = actions.iter().map(|a| a.id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), actions.len());
+        for action in &actions {
+            match action.kind {
+                ActionKind::Command => assert!(action.command.is_some() && action.route.is_none(), "{}", action.id),
+                ActionKind::Navigate => assert!(action.route.is_some() && action.command.is_none(), "{}", action.id),
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Links from research papers to their implementation (`articles.paper_code_link`).
+//
+// The article text is scanned for a GitHub repository link first (arXiv
+// abstracts often end with "code is available at github.com/..."); for
+// arXiv papers without one, the Hugging Face papers API and then Papers
+// with Code are asked. The column stays NULL until a lookup has run and is
+// '' when none found a repository, so lookups aren't repeated every crawl.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+
+use crate::citations;
+use crate::i18n::{t, tr, Msg};
+use crate::{query_article, Article, HttpClients};
+
+const HUGGINGFACE_PAPERS_API: &str = "https://huggingface.co/api/papers";
+const PAPERS_WITH_CODE_API: &str = "https://paperswithcode.com/api/v1";
+
+// Lookups per crawl, to stay polite to the APIs
+const MAX_LOOKUPS_PER_RUN: usize = 20;
+
+// First path segments on github.com that aren't user or organization names
+const RESERVED_OWNERS: [&str; 10] = [
+    "topics", "features", "orgs", "sponsors", "about", "settings", "marketplace", "apps", "login", "trending",
+];
+
+// `https://github.com/owner/repo` for the first repository link in `text`
+pub fn github_repo_link(text: &str) -> Option<String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    text.match_indices("github.com/").find_map(|(pos, needle)| {
+        let mut parts = text[pos + needle.len()..].splitn(3, '/');
+        let owner: String = parts.next()?.chars().take_while(|c| is_name_char(*c)).collect();
+        let repo: String = parts.next()?.chars().take_while(|c| is_name_char(*c)).collect();
+        let repo = repo.trim_end_matches('.').trim_end_matches(".git");
+        if owner.is_empty() || repo.is_empty() || RESERVED_OWNERS.contains(&owner.to_lowercase().as_str()) {
+            return None;
+        }
+        Some(format!("https://github.com/{}/{}", owner, repo))
+    })
+}
+
+async fn from_huggingface(http: &HttpClients, arxiv_id: &str) -> Result<Option<String>, String> {
+    let response = http
+        .get(true)
+        .get(format!("{}/{}", HUGGINGFACE_PAPERS_API, arxiv_id))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let json: serde_json::Value = response.json().await.map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    Ok(json["githubRepo"].as_str().and_then(github_repo_link))
+}
+
+// Official implementation if marked, else the most starred one
+async fn from_papers_with_code(http: &HttpClients, arxiv_id: &str) -> Result<Option<String>, String> {
+    let client = http.get(true);
+    let papers: serde_json::Value = client
+        .get(format!("{}/papers/", PAPERS_WITH_CODE_API))
+        .query(&[("arxiv_id", arxiv_id)])
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?
+        .json()
+        .await
+        .map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    let Some(paper_id) = papers["results"][0]["id"].as_str() else {
+        return Ok(None);
+    };
+
+    let repos: serde_json::Value = client
+        .get(format!("{}/papers/{}/repositories/", PAPERS_WITH_CODE_API, paper_id))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?
+        .json()
+        .await
+        .map_err(|e| tr(Msg::ReadContentFailed, e))?;
+    let best = repos["results"].as_array().and_then(|repos| {
+        repos.iter().max_by_key(|r| (r["is_official"].as_bool().unwrap_or(false), r["stars"].as_u64().unwrap_or(0)))
+    });
+    Ok(best.and_then(|r| r["url"].as_str()).and_then(github_repo_link))
+}
+
+// Repository for a paper article; None when it isn't a paper or nothing was found
+pub async fn resolve(http: &HttpClients, article: &Article) -> Option<String> {
+    if !citations::is_paper(article) {
+        return None;
+    }
+    if let Some(link) = github_repo_link(&article.content).or_else(|| github_repo_link(&article.summary)) {
+        return Some(link);
+    }
+    let arxiv_id = citations::arxiv_id(&article.url)?;
+    match from_huggingface(http, &arxiv_id).await {
+        Ok(Some(link)) => return Some(link),
+        Ok(None) => {}
+        Err(e) => eprintln!("Hugging Face paper lookup failed for {}: {}", arxiv_id, e),
+    }
+    match from_papers_with_code(http, &arxiv_id).await {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("Papers with Code lookup failed for {}: {}", arxiv_id, e);
+            None
+        }
+    }
+}
+
+fn store(conn: &Connection, id: &str, link: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE articles SET paper_code_link = ?1 WHERE id = ?2",
+        params![link.unwrap_or(""), id],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Look up code links for newly crawled papers
+pub async fn link_new_articles(db: &Mutex<Connection>, http: &HttpClients, article_ids: &[String]) {
+    let papers: Vec<Article> = {
+        let Ok(conn) = db.lock() else { return };
+        article_ids
+            .iter()
+            .filter_map(|id| query_article(&conn, id).ok().flatten())
+            .filter(citations::is_paper)
+            .take(MAX_LOOKUPS_PER_RUN)
+            .collect()
+    }; // Release the lock during network calls
+
+    for article in papers {
+        let link = resolve(http, &article).await;
+        if let Ok(conn) = db.lock() {
+            if let Err(e) = store(&conn, &article.id, link.as_deref()) {
+                eprintln!("Failed to store code link for '{}': {}", article.title, e);
+            }
+        }
+    }
+}
+
+// Code link for one article, looked up now unless a lookup already ran (or `force`)
+pub async fn link_article(db: &Mutex<Connection>, http: &HttpClients, id: &str, force: bool) -> Result<Option<String>, String> {
+    let (article, known) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let article = query_article(&conn, id)?.ok_or_else(|| t(Msg::ArticleNotFound))?;
+        let known: Option<String> = conn
+            .query_row("SELECT paper_code_link FROM articles WHERE id = ?1", params![id], |row| row.get(0))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+        (article, known)
+    };
+    if let (false, Some(link)) = (force, known) {
+        return Ok(Some(link).filter(|l| !l.is_empty()));
+    }
+
+    let link = resolve(http, &article).await;
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    store(&conn, id, link.as_deref())?;
+    Ok(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_repository_link_is_normalized() {
+        assert_eq!(
+            github_repo_link("Code is available at https://github.com/acme/llm-kit.git."),
+            Some("https://github.com/acme/llm-kit".to_string())
+        );
+        assert_eq!(
+            github_repo_link("See github.com/Acme/LLM_Kit/tree/main/eval for details"),
+            Some("https://github.com/Acme/LLM_Kit".to_string())
+        );
+        assert_eq!(
+            github_repo_link("https://github.com/topics/llm and https://github.com/acme/kit"),
+            Some("https://github.com/acme/kit".to_string()),
+            "reserved paths are skipped"
+        );
+        assert_eq!(github_repo_link("https://github.com/acme"), None);
+        assert_eq!(github_repo_link("no links here"), None);
+    }
+}
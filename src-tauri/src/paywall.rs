@@ -0,0 +1,133 @@
+// Paywall detection on fetched article pages.
+//
+// Whenever an article's full page is fetched (manual add, the browser
+// extension, prefetch, EPUB / PDF / Kindle exports) the HTML is checked
+// for the usual paywall markers: the schema.org `isAccessibleForFree: false`
+// publishers declare for search engines, a locked or metered
+// `article:content_tier`, the paywall containers of common paywall vendors
+// and "subscribe to keep reading" copy. The result is stored as
+// `articles.paywalled`, shown as a badge, and `articles_list` leaves such
+// articles out with `hide_paywalled`. A later fetch that finds no markers
+// clears the flag again.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+
+use crate::i18n::{tr, Msg};
+
+// Class / id fragments of paywall overlays and their vendors (Piano, Poool, Zephr, Tinypass)
+const CONTAINER_MARKERS: &[&str] = &[
+    "paywall",
+    "piano-offer",
+    "tp-modal",
+    "tp-container",
+    "poool-widget",
+    "zephr-",
+    "subscriber-only",
+    "subscribers-only",
+    "regwall",
+    "meteredcontent",
+];
+
+// Lowercased copy shown in place of the rest of the article
+const TEXT_MARKERS: &[&str] = &[
+    "subscribe to continue reading",
+    "subscribe to keep reading",
+    "subscribe to read the full",
+    "this article is for subscribers only",
+    "this content is for subscribers",
+    "already a subscriber? log in",
+    "to continue reading, subscribe",
+    "订阅后阅读全文",
+    "付费阅读",
+    "开通会员后阅读",
+    "订阅即可阅读",
+    "本文为付费内容",
+];
+
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn declares_locked(html: &str) -> bool {
+    let squeezed = compact(&html.to_lowercase());
+    squeezed.contains("\"isaccessibleforfree\":false")
+        || squeezed.contains("\"isaccessibleforfree\":\"false\"")
+        || ["locked", "metered"].iter().any(|tier| {
+            squeezed.contains(&format!("name=\"article:content_tier\"content=\"{}\"", tier))
+                || squeezed.contains(&format!("property=\"article:content_tier\"content=\"{}\"", tier))
+        })
+}
+
+fn has_container(html: &str) -> bool {
+    let lower = html.to_lowercase();
+    ["class=\"", "id=\""].iter().any(|attr| {
+        lower.match_indices(attr).any(|(start, _)| {
+            let value = &lower[start + attr.len()..];
+            let value = &value[..value.find('"').unwrap_or(value.len())];
+            CONTAINER_MARKERS.iter().any(|marker| value.contains(marker))
+        })
+    })
+}
+
+fn has_text_marker(html: &str) -> bool {
+    let lower = html.to_lowercase();
+    TEXT_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+// Whether a fetched page hides its article behind a paywall
+pub fn detect(html: &str) -> bool {
+    declares_locked(html) || has_container(html) || has_text_marker(html)
+}
+
+pub fn store(conn: &Connection, url: &str, paywalled: bool) -> Result<(), String> {
+    conn.execute("UPDATE articles SET paywalled = ?1 WHERE url = ?2", params![paywalled, url])
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+// Check a page fetched for the stored article at `url` and record the result
+pub fn record(db: &Mutex<Connection>, url: &str, html: &str) {
+    let paywalled = detect(html);
+    if let Ok(conn) = db.lock() {
+        if let Err(e) = store(&conn, url, paywalled) {
+            eprintln!("Failed to record paywall state of {}: {}", url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paywall_markers() {
+        assert!(detect(r#"<script type="application/ld+json">{"isAccessibleForFree" : "False"}</script>"#));
+        assert!(detect(r#"<meta property="article:content_tier" content="metered">"#));
+        assert!(!detect(r#"<meta property="article:content_tier" content="free">"#));
+        assert!(detect(r#"<div class="article-body tp-container-inner">"#));
+        assert!(detect(r#"<section id="Paywall-Overlay">"#));
+        assert!(detect("<p>Subscribe to keep reading.</p>"));
+        assert!(detect("<p>本文为付费内容</p>"));
+        // A marker outside class / id attributes doesn't count
+        assert!(!detect(r#"<p>How the paywall debate shaped the industry</p><a href="/paywall-faq">FAQ</a>"#));
+        assert!(!detect(""));
+    }
+
+    #[test]
+    fn a_later_free_fetch_clears_the_flag() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute("INSERT INTO articles (id, title, url, source) VALUES ('a1', 'One', 'https://example.com/1', 'Blog')", [])
+            .unwrap();
+        let db = Mutex::new(conn);
+        let paywalled = |db: &Mutex<Connection>| -> bool {
+            db.lock().unwrap().query_row("SELECT paywalled FROM articles WHERE id = 'a1'", [], |row| row.get(0)).unwrap()
+        };
+        record(&db, "https://example.com/1", "<div class=\"regwall\"></div>");
+        assert!(paywalled(&db));
+        record(&db, "https://example.com/1", "<article><p>Full text.</p></article>");
+        assert!(!paywalled(&db));
+        record(&db, "https://example.com/unknown", "<div class=\"regwall\"></div>");
+    }
+}
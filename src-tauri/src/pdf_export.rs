@@ -0,0 +1,353 @@
+// Single-article PDF export.
+//
+// The page is re-fetched and cleaned with `readability` (the stored content
+// is used when that fails), laid out on A4 pages and rasterized with the
+// share card's font handling, so Chinese text renders without embedding a
+// CID font. Each page is one image: the PNG encoder's deflate stream goes
+// into the PDF as-is (FlateDecode with the PNG predictor), which keeps the
+// file small without another compression dependency. Text in the PDF is
+// therefore not selectable; the original URL is printed under the title.
+
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::sync::Mutex;
+
+use ab_glyph::FontVec;
+use image::{DynamicImage, ImageFormat, Rgba, RgbImage, RgbaImage};
+use rusqlite::Connection;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::i18n::{tr, Msg};
+use crate::share_card::{draw_text, load_font, measure, wrap};
+use crate::{epub, readability, Article, HttpClients};
+
+// A4 at 150 dpi
+const PAGE_WIDTH: u32 = 1240;
+const PAGE_HEIGHT: u32 = 1754;
+const PAGE_WIDTH_PT: f32 = 595.0;
+const PAGE_HEIGHT_PT: f32 = 842.0;
+const MARGIN: f32 = 120.0;
+const FOOTER: f32 = 80.0;
+
+const TITLE_SIZE: f32 = 44.0;
+const HEADING_SIZE: f32 = 32.0;
+const BODY_SIZE: f32 = 24.0;
+const META_SIZE: f32 = 20.0;
+
+const TEXT_COLOR: [u8; 3] = [17, 17, 17];
+const QUOTE_COLOR: [u8; 3] = [85, 85, 85];
+const MUTED_COLOR: [u8; 3] = [136, 136, 136];
+
+const BLOCK_TAGS: [&str; 11] = ["h1", "h2", "h3", "h4", "h5", "h6", "p", "li", "blockquote", "pre", "figcaption"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum BlockKind {
+    Heading,
+    Paragraph,
+    ListItem,
+    Quote,
+    Preformatted,
+}
+
+struct Block {
+    kind: BlockKind,
+    text: String,
+}
+
+// Text blocks of the cleaned HTML in document order; nested blocks belong to their outermost one
+fn blocks(html: &str) -> Vec<Block> {
+    let fragment = Html::parse_fragment(html);
+    let Ok(selector) = Selector::parse(&BLOCK_TAGS.join(", ")) else { return Vec::new() };
+    fragment
+        .select(&selector)
+        .filter(|el| {
+            !el.ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|a| BLOCK_TAGS.contains(&a.value().name()))
+        })
+        .filter_map(|el| {
+            let name = el.value().name();
+            let kind = match name {
+                "li" => BlockKind::ListItem,
+                "blockquote" => BlockKind::Quote,
+                "pre" => BlockKind::Preformatted,
+                "p" | "figcaption" => BlockKind::Paragraph,
+                _ => BlockKind::Heading,
+            };
+            let raw: String = el.text().collect();
+            let text = if kind == BlockKind::Preformatted {
+                raw.trim_end().to_string()
+            } else {
+                raw.split_whitespace().collect::<Vec<_>>().join(" ")
+            };
+            (!text.trim().is_empty()).then_some(Block { kind, text })
+        })
+        .collect()
+}
+
+struct Line {
+    text: String,
+    x: f32,
+    baseline: f32,
+    size: f32,
+    color: [u8; 3],
+}
+
+// Lines per page, top to bottom
+fn layout(font: &FontVec, article: &Article, blocks: &[Block]) -> Vec<Vec<Line>> {
+    let width = PAGE_WIDTH as f32 - MARGIN * 2.0;
+    let bottom = PAGE_HEIGHT as f32 - MARGIN - FOOTER;
+    let mut pages: Vec<Vec<Line>> = vec![Vec::new()];
+    let mut y = MARGIN;
+
+    let push = |pages: &mut Vec<Vec<Line>>, y: &mut f32, text: String, x: f32, size: f32, color: [u8; 3]| {
+        let height = size * 1.5;
+        if *y + height > bottom && !pages.last().is_some_and(|p| p.is_empty()) {
+            pages.push(Vec::new());
+            *y = MARGIN;
+        }
+        *y += height;
+        if let Some(page) = pages.last_mut() {
+            page.push(Line { text, x, baseline: *y - size * 0.4, size, color });
+        }
+    };
+
+    for line in wrap(font, TITLE_SIZE, &article.title, width, usize::MAX) {
+        push(&mut pages, &mut y, line, MARGIN, TITLE_SIZE, TEXT_COLOR);
+    }
+    let date = article.published_at.get(..10).unwrap_or(&article.published_at);
+    push(&mut pages, &mut y, format!("{} · {}", article.source, date), MARGIN, META_SIZE, MUTED_COLOR);
+    for line in wrap(font, META_SIZE, &article.url, width, 2) {
+        push(&mut pages, &mut y, line, MARGIN, META_SIZE, MUTED_COLOR);
+    }
+    y += BODY_SIZE;
+
+    for block in blocks {
+        let (size, indent, color, prefix) = match block.kind {
+            BlockKind::Heading => (HEADING_SIZE, 0.0, TEXT_COLOR, ""),
+            BlockKind::Paragraph => (BODY_SIZE, 0.0, TEXT_COLOR, ""),
+            BlockKind::ListItem => (BODY_SIZE, 36.0, TEXT_COLOR, "• "),
+            BlockKind::Quote => (BODY_SIZE, 36.0, QUOTE_COLOR, ""),
+            BlockKind::Preformatted => (META_SIZE, 24.0, TEXT_COLOR, ""),
+        };
+        if block.kind == BlockKind::Heading {
+            y += size * 0.5;
+        }
+        let source_lines: Vec<&str> = if block.kind == BlockKind::Preformatted {
+            block.text.lines().collect()
+        } else {
+            vec![block.text.as_str()]
+        };
+        let prefix_width = measure(font, size, prefix);
+        for (i, line) in source_lines
+            .into_iter()
+            .flat_map(|l| wrap(font, size, l, width - indent - prefix_width, usize::MAX))
+            .enumerate()
+        {
+            let text = if i == 0 { format!("{}{}", prefix, line) } else { line };
+            let x = MARGIN + indent + if i == 0 { 0.0 } else { prefix_width };
+            push(&mut pages, &mut y, text, x, size, color);
+        }
+        y += size * 0.6;
+    }
+    pages
+}
+
+fn render_page(font: &FontVec, lines: &[Line], number: usize, total: usize) -> RgbImage {
+    let mut img = RgbaImage::from_pixel(PAGE_WIDTH, PAGE_HEIGHT, Rgba([255, 255, 255, 255]));
+    for line in lines {
+        draw_text(&mut img, font, &line.text, line.x, line.baseline, line.size, line.color);
+    }
+    let footer = format!("{} / {}", number, total);
+    let x = (PAGE_WIDTH as f32 - measure(font, META_SIZE, &footer)) / 2.0;
+    draw_text(&mut img, font, &footer, x, PAGE_HEIGHT as f32 - MARGIN / 2.0, META_SIZE, MUTED_COLOR);
+    DynamicImage::ImageRgba8(img).into_rgb8()
+}
+
+// Concatenated IDAT payload (a zlib stream) of a PNG file
+fn png_idat(png: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut pos = 8;
+    while pos + 12 <= png.len() {
+        let length = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+        let kind = &png[pos + 4..pos + 8];
+        let end = (pos + 8 + length).min(png.len());
+        if kind == b"IDAT" {
+            data.extend_from_slice(&png[pos + 8..end]);
+        }
+        pos += 12 + length;
+    }
+    data
+}
+
+// PDF text string; UTF-16BE so titles in any script survive
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        let _ = write!(out, "{:04X}", unit);
+    }
+    out.push('>');
+    out
+}
+
+fn build_pdf(title: &str, pages: &[RgbImage]) -> Result<Vec<u8>, String> {
+    // Objects: 1 catalog, 2 page tree, 3 info, then per page: page, contents, image
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + i * 3).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(format!("<< /Title {} /Producer (AI News Aggregator) >>", pdf_string(title)).into_bytes());
+
+    for (i, page) in pages.iter().enumerate() {
+        let (contents_id, image_id) = (page_ids[i] + 1, page_ids[i] + 2);
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {w} {h}] /Resources << /XObject << /Im{n} {image} 0 R >> >> /Contents {contents} 0 R >>",
+                w = PAGE_WIDTH_PT,
+                h = PAGE_HEIGHT_PT,
+                n = i,
+                image = image_id,
+                contents = contents_id,
+            )
+            .into_bytes(),
+        );
+
+        let draw = format!("q {} 0 0 {} 0 0 cm /Im{} Do Q", PAGE_WIDTH_PT, PAGE_HEIGHT_PT, i);
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", draw.len(), draw).into_bytes());
+
+        let mut png = Cursor::new(Vec::new());
+        page.write_to(&mut png, ImageFormat::Png)
+            .map_err(|e| tr(Msg::PdfRenderFailed, e))?;
+        let data = png_idat(png.get_ref());
+        let mut image = format!(
+            "<< /Type /XObject /Subtype /Image /Width {w} /Height {h} /ColorSpace /DeviceRGB /BitsPerComponent 8 \
+             /Filter /FlateDecode /DecodeParms << /Predictor 15 /Colors 3 /BitsPerComponent 8 /Columns {w} >> /Length {len} >>\nstream\n",
+            w = page.width(),
+            h = page.height(),
+            len = data.len(),
+        )
+        .into_bytes();
+        image.extend_from_slice(&data);
+        image.extend_from_slice(b"\nendstream");
+        objects.push(image);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    out.extend_from_slice(table.as_bytes());
+    Ok(out)
+}
+
+// Render `article` into a PDF at `out_path`; returns the page count
+pub async fn export(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    article: &Article,
+    font_path: &str,
+    out_path: &std::path::Path,
+) -> Result<usize, String> {
+    let html = match epub::fetch_text(db, http, &article.url).await {
+        Some(page) => readability::extract(&page, &article.url).map(|r| r.html),
+        None => None,
+    }
+    .unwrap_or_else(|| epub::fallback_body(article));
+
+    let font = load_font(font_path)?;
+    let laid_out = layout(&font, article, &blocks(&html));
+    let total = laid_out.len();
+    let pages: Vec<RgbImage> = laid_out
+        .iter()
+        .enumerate()
+        .map(|(i, lines)| render_page(&font, lines, i + 1, total))
+        .collect();
+    let pdf = build_pdf(&article.title, &pages)?;
+
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    std::fs::write(out_path, pdf).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", out_path.display(), e)))?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_keep_document_order_and_outermost_elements() {
+        let blocks = blocks(
+            "<h2>Results</h2><p>Accuracy   went\n up.</p><blockquote><p>Quoted once</p></blockquote>\
+             <ul><li>First</li><li> </li></ul><pre>let x = 1;\n  x + 1\n</pre>",
+        );
+        let kinds: Vec<&str> = blocks
+            .iter()
+            .map(|b| match b.kind {
+                BlockKind::Heading => "heading",
+                BlockKind::Paragraph => "paragraph",
+                BlockKind::ListItem => "item",
+                BlockKind::Quote => "quote",
+                BlockKind::Preformatted => "pre",
+            })
+            .collect();
+        assert_eq!(kinds, ["heading", "paragraph", "quote", "item", "pre"]);
+        assert_eq!(blocks[1].text, "Accuracy went up.");
+        assert_eq!(blocks[2].text, "Quoted once");
+        assert_eq!(blocks[4].text, "let x = 1;\n  x + 1", "code keeps its line breaks");
+    }
+
+    #[test]
+    fn pdf_has_one_image_page_per_rendered_page_and_a_valid_xref() {
+        let page = RgbImage::from_pixel(8, 8, image::Rgb([255, 255, 255]));
+        let pdf = build_pdf("大模型 news", &[page.clone(), page]).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains(&format!("/Title {}", pdf_string("大模型 news"))));
+        assert_eq!(pdf_string("AI"), "<FEFF00410049>");
+
+        // Every xref entry points at its object
+        let xref = text.rfind("\nxref\n").unwrap() + 1;
+        let entries: Vec<usize> = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .map(|l| l[..10].parse().unwrap())
+            .collect();
+        assert_eq!(entries.len(), 3 + 2 * 3);
+        for (i, offset) in entries.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()), "object {}", i + 1);
+        }
+    }
+
+    #[test]
+    fn png_idat_joins_the_data_chunks() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, data) in [(&b"IHDR"[..], &b"head"[..]), (b"IDAT", b"abc"), (b"IDAT", b"de"), (b"IEND", b"")] {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&[0; 4]);
+        }
+        assert_eq!(png_idat(&png), b"abcde");
+        assert!(png_idat(&png[..10]).is_empty());
+    }
+}
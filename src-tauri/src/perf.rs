@@ -0,0 +1,177 @@
+// Command and query timings, for troubleshooting slow machines.
+//
+// Every command's duration is added to per-command totals kept in memory
+// for the session. Synchronous commands are timed around their dispatch. Async commands return from
+// dispatch at once, so they also hold a `Completion` guard that adds the
+// time until their future finishes. SQLite's profile hook reports each
+// statement's run time; statements slower than `slow_query_ms` are logged
+// and the slowest SLOW_QUERIES_KEPT are kept. `perf_report`
+// returns both.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+const SLOW_QUERIES_KEPT: usize = 50;
+// Longest SQL text kept per slow query
+const MAX_SQL_CHARS: usize = 300;
+
+#[derive(Default)]
+struct Stats {
+    calls: u64,
+    total: Duration,
+    max: Duration,
+}
+
+static COMMANDS: Mutex<Option<HashMap<String, Stats>>> = Mutex::new(None);
+static SLOW_QUERIES: Mutex<Vec<SlowQuery>> = Mutex::new(Vec::new());
+static SLOW_QUERY_MS: AtomicU64 = AtomicU64::new(200);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    pub sql: String,
+    pub duration_ms: f64,
+    pub at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub calls: u64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerfReport {
+    pub slow_query_ms: u64,
+    // Slowest single call first
+    pub commands: Vec<CommandStats>,
+    // Slowest first
+    pub slow_queries: Vec<SlowQuery>,
+}
+
+fn ms(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 100.0).round() / 100.0
+}
+
+fn record(command: &str, elapsed: Duration, call: bool) {
+    let mut commands = COMMANDS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = commands.get_or_insert_with(HashMap::new).entry(command.to_string()).or_default();
+    if call {
+        stats.calls += 1;
+    }
+    stats.total += elapsed;
+    stats.max = stats.max.max(elapsed);
+}
+
+// Time a command's dispatch and count the call
+pub fn dispatch<T>(command: &str, run: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let output = run();
+    record(command, start.elapsed(), true);
+    output
+}
+
+// Held by async commands for their whole run
+pub struct Completion {
+    command: &'static str,
+    start: Instant,
+}
+
+pub fn completion(command: &'static str) -> Completion {
+    Completion { command, start: Instant::now() }
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        // The call itself was counted at dispatch
+        record(self.command, self.start.elapsed(), false);
+    }
+}
+
+// 0 turns the warning off
+pub fn set_slow_query_ms(threshold: u64) {
+    SLOW_QUERY_MS.store(threshold, Ordering::Relaxed);
+}
+
+fn on_query(sql: &str, duration: Duration) {
+    let threshold = SLOW_QUERY_MS.load(Ordering::Relaxed);
+    if threshold == 0 || duration < Duration::from_millis(threshold) {
+        return;
+    }
+    let sql: String = sql.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(MAX_SQL_CHARS).collect();
+    eprintln!("Slow query ({} ms): {}", ms(duration), sql);
+
+    let mut slow = SLOW_QUERIES.lock().unwrap_or_else(|e| e.into_inner());
+    slow.push(SlowQuery { sql, duration_ms: ms(duration), at: chrono::Utc::now().to_rfc3339() });
+    slow.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+    slow.truncate(SLOW_QUERIES_KEPT);
+}
+
+// Watch every statement run on `conn`
+pub fn watch(conn: &mut Connection) {
+    conn.profile(Some(on_query));
+}
+
+pub fn report(limit: usize) -> PerfReport {
+    let mut commands: Vec<CommandStats> = COMMANDS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .flatten()
+        .map(|(command, stats)| CommandStats {
+            command: command.clone(),
+            calls: stats.calls,
+            total_ms: ms(stats.total),
+            avg_ms: if stats.calls == 0 { 0.0 } else { ms(stats.total / stats.calls as u32) },
+            max_ms: ms(stats.max),
+        })
+        .collect();
+    commands.sort_by(|a, b| b.max_ms.total_cmp(&a.max_ms));
+    commands.truncate(limit);
+
+    let mut slow_queries = SLOW_QUERIES.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    slow_queries.truncate(limit);
+    for query in &mut slow_queries {
+        query.at = crate::datetime::to_display(&query.at);
+    }
+    PerfReport { slow_query_ms: SLOW_QUERY_MS.load(Ordering::Relaxed), commands, slow_queries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_queries_over_the_threshold_are_kept() {
+        set_slow_query_ms(50);
+        on_query("SELECT  'perf_test', 1\n  FROM articles", Duration::from_millis(10));
+        on_query("SELECT  'perf_test', 2\n  FROM articles", Duration::from_millis(80));
+        on_query("SELECT 'perf_test', 3", Duration::from_millis(120));
+        set_slow_query_ms(0);
+        on_query("SELECT 'perf_test', 4", Duration::from_secs(5));
+        set_slow_query_ms(200);
+
+        // Other tests' databases are watched too
+        let kept: Vec<SlowQuery> =
+            report(SLOW_QUERIES_KEPT).slow_queries.into_iter().filter(|q| q.sql.contains("perf_test")).collect();
+        let sql: Vec<&str> = kept.iter().map(|q| q.sql.as_str()).collect();
+        assert_eq!(sql, ["SELECT 'perf_test', 3", "SELECT 'perf_test', 2 FROM articles"]);
+        assert_eq!(kept[0].duration_ms, 120.0);
+    }
+
+    #[test]
+    fn async_commands_add_their_run_time_to_the_dispatched_call() {
+        dispatch("perf_test_command", || ());
+        drop(completion("perf_test_command"));
+        let report = report(usize::MAX);
+        let stats = report.commands.iter().find(|c| c.command == "perf_test_command").unwrap();
+        assert_eq!(stats.calls, 1);
+    }
+}
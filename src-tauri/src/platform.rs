@@ -0,0 +1,87 @@
+// Differences between the desktop app and the mobile builds (`mobile_entry_point`).
+//
+// On Android and iOS there is no HOME to keep `.newsagregator` in, so the
+// data directory is the app's own, handed over from Tauri's path resolver at
+// startup. Nothing listens on 127.0.0.1:7897 there either: only a proxy set
+// in the environment is used. Crawls are cut down to spare battery and data:
+// fewer sources and items per source, scheduled crawls at most hourly, fewer
+// idle connections kept per host and one AI request in flight at a time by
+// default. Settings can still raise the pool and AI limits.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Desktop proxy when none is set in the environment, the common Clash default
+const DESKTOP_DEFAULT_PROXY: &str = "http://127.0.0.1:7897";
+
+pub struct CrawlProfile {
+    // Active sources fetched per crawl
+    pub max_sources: u32,
+    pub items_per_source: usize,
+    // Floor for `crawl_interval_minutes` when the scheduler runs the crawl
+    pub min_crawl_interval_minutes: u64,
+    // Defaults of settings that size concurrent requests
+    pub http_pool_max_idle_per_host: u32,
+    pub ai_max_concurrent: u32,
+}
+
+const DESKTOP: CrawlProfile = CrawlProfile {
+    max_sources: 20,
+    items_per_source: 12,
+    min_crawl_interval_minutes: 0,
+    http_pool_max_idle_per_host: 8,
+    ai_max_concurrent: 2,
+};
+
+const MOBILE: CrawlProfile = CrawlProfile {
+    max_sources: 10,
+    items_per_source: 8,
+    min_crawl_interval_minutes: 60,
+    http_pool_max_idle_per_host: 2,
+    ai_max_concurrent: 1,
+};
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub const fn is_mobile() -> bool {
+    cfg!(mobile)
+}
+
+pub fn crawl_profile() -> &'static CrawlProfile {
+    profile_for(is_mobile())
+}
+
+pub fn profile_for(mobile: bool) -> &'static CrawlProfile {
+    if mobile { &MOBILE } else { &DESKTOP }
+}
+
+// Proxy to use when the environment sets none
+pub fn default_proxy() -> Option<&'static str> {
+    (!is_mobile()).then_some(DESKTOP_DEFAULT_PROXY)
+}
+
+// Set once at startup on mobile, before anything opens the database
+#[cfg(mobile)]
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR.set(dir);
+}
+
+pub fn data_dir() -> Option<&'static PathBuf> {
+    DATA_DIR.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mobile_profile_crawls_less() {
+        let (desktop, mobile) = (profile_for(false), profile_for(true));
+        assert!(mobile.max_sources < desktop.max_sources);
+        assert!(mobile.items_per_source < desktop.items_per_source);
+        assert!(mobile.min_crawl_interval_minutes >= 60);
+        assert_eq!(mobile.ai_max_concurrent, 1);
+        assert_eq!(crawl_profile().max_sources, profile_for(is_mobile()).max_sources);
+        assert_eq!(default_proxy(), (!is_mobile()).then_some(DESKTOP_DEFAULT_PROXY));
+    }
+}
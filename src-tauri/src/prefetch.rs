@@ -0,0 +1,125 @@
+// Background warming of what the user is likely to open next.
+//
+// `articles_prefetch` is called with the list query on screen and the ids
+// of the visible articles. It returns at once; a background task then puts
+// the next page into the list cache (so paging forward is answered from
+// memory) and fetches the visible articles' pages into the HTTP cache, one
+// at a time, so reading one doesn't wait on the network or, for the stored
+// fields, on a slow disk. Pages already cached within `PAGE_TTL` cost
+// nothing, and nothing is fetched while the machine is known to be offline
+// or in low-data mode.
+
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::i18n::{tr, Msg};
+use crate::list_cache::ListCache;
+use crate::{epub, feed_balance, low_data, network, query_articles_page, DbState, HttpClients, ListQuery};
+
+// Visible articles warmed per call
+const MAX_ARTICLES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct PrefetchRequest {
+    pub query: ListQuery,
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrefetchQueued {
+    // Page number that will be cached, if there is a next page
+    pub next_page: Option<usize>,
+    pub articles: usize,
+}
+
+fn urls(conn: &Connection, ids: &[String]) -> Result<Vec<String>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let mut stmt = conn
+        .prepare(&format!("SELECT url FROM articles WHERE id IN ({})", placeholders))
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let urls = stmt
+        .query_map(params_from_iter(ids.iter()), |row| row.get(0))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(urls)
+}
+
+fn warm_next_page(app: &AppHandle, query: ListQuery) -> Result<(), String> {
+    let key = ListCache::page_key(&query)?;
+    let state = app.state::<DbState>();
+    let cache = app.state::<ListCache>();
+    let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    cache.page(key, || {
+        let balance = feed_balance::FeedBalance::load(&conn)?;
+        Ok(query_articles_page(&conn, &query, Some(&balance))?)
+    })?;
+    Ok(())
+}
+
+pub fn start(app: AppHandle, request: PrefetchRequest) -> Result<PrefetchQueued, String> {
+    let mut ids = request.ids;
+    ids.truncate(MAX_ARTICLES);
+    let (next, urls) = {
+        let state = app.state::<DbState>();
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let page = request.query.page.unwrap_or(1).max(1);
+        // The current page is cached by articles_list, so its total is at hand
+        let total = app
+            .state::<ListCache>()
+            .cached_page(&ListCache::page_key(&request.query)?)
+            .map(|response| response.total as usize);
+        let has_next = total.is_none_or(|total| page * request.query.page_size < total);
+        let next = has_next.then(|| ListQuery { page: Some(page + 1), ..request.query });
+        // Low-data mode fetches no pages, only the list is warmed
+        let urls = if low_data::enabled() { Vec::new() } else { urls(&conn, &ids)? };
+        (next, urls)
+    };
+    let queued = PrefetchQueued { next_page: next.as_ref().and_then(|q| q.page), articles: urls.len() };
+
+    tauri::async_runtime::spawn(async move {
+        if let Some(next) = next {
+            if let Err(e) = warm_next_page(&app, next) {
+                eprintln!("Prefetching the next page failed: {}", e);
+            }
+        }
+        if network::is_known_offline() {
+            return;
+        }
+        let state = app.state::<DbState>();
+        let http = app.state::<HttpClients>();
+        for url in urls {
+            let _ = epub::fetch_text(&state.conn, &http, &url).await;
+        }
+    });
+    Ok(queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_of_the_visible_articles() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source)
+             VALUES ('a1', 'One', 'https://example.com/1', 'Blog'), ('a2', 'Two', 'https://example.com/2', 'Blog')",
+            [],
+        )
+        .unwrap();
+        let mut found = urls(&conn, &["a2".to_string(), "a1".to_string(), "gone".to_string()]).unwrap();
+        found.sort();
+        assert_eq!(found, ["https://example.com/1", "https://example.com/2"]);
+        assert!(urls(&conn, &[]).unwrap().is_empty());
+
+        let request: PrefetchRequest = serde_json::from_str(r#"{"query": {"page": 2, "page_size": 20}}"#).unwrap();
+        assert!(request.ids.is_empty());
+        assert_eq!(request.query.page, Some(2));
+    }
+}
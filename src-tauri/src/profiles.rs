@@ -0,0 +1,135 @@
+// Isolated profiles (e.g. work monitoring vs. personal reading).
+//
+// Each profile is a separate database, so articles, sources and settings
+// never mix. The registry lives outside any database in
+// `~/.newsagregator/profiles.json`; the default profile keeps using
+// `news.db` so existing installs need no migration, others live in
+// `profiles/<id>/news.db`. The desktop app, the local API and `--mcp` all
+// open the active profile.
+
+use serde::{Deserialize, Serialize};
+
+use crate::get_data_dir;
+use crate::i18n::{t, tr, Msg};
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileRegistry {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> Self {
+        ProfileRegistry {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "默认".to_string(),
+                created_at: String::new(),
+            }],
+        }
+    }
+}
+
+fn registry_path() -> Result<String, String> {
+    Ok(format!("{}/profiles.json", get_data_dir()?))
+}
+
+// A missing or unreadable registry means only the default profile exists
+pub fn load() -> Result<ProfileRegistry, String> {
+    match std::fs::read_to_string(registry_path()?) {
+        Ok(text) => Ok(parse(&text)),
+        Err(_) => Ok(ProfileRegistry::default()),
+    }
+}
+
+// An active id that no longer names a profile falls back to the default
+fn parse(text: &str) -> ProfileRegistry {
+    let mut registry: ProfileRegistry = serde_json::from_str(text).unwrap_or_default();
+    if !registry.profiles.iter().any(|p| p.id == registry.active) {
+        registry.active = DEFAULT_PROFILE_ID.to_string();
+    }
+    registry
+}
+
+fn save(registry: &ProfileRegistry) -> Result<(), String> {
+    let path = registry_path()?;
+    let json = serde_json::to_string_pretty(registry).map_err(|e| tr(Msg::WriteFileFailed, e))?;
+    std::fs::write(&path, json).map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", path, e)))
+}
+
+pub fn db_path(profile_id: &str) -> Result<String, String> {
+    let data_dir = get_data_dir()?;
+    if profile_id == DEFAULT_PROFILE_ID {
+        return Ok(format!("{}/news.db", data_dir));
+    }
+    let dir = format!("{}/profiles/{}", data_dir, profile_id);
+    std::fs::create_dir_all(&dir).map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", dir, e)))?;
+    Ok(format!("{}/news.db", dir))
+}
+
+pub fn active_db_path() -> Result<String, String> {
+    db_path(&load()?.active)
+}
+
+pub fn create(name: &str) -> Result<Profile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(t(Msg::ProfileNameMissing));
+    }
+    let mut registry = load()?;
+    let profile = Profile {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        name: name.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    registry.profiles.push(profile.clone());
+    save(&registry)?;
+    Ok(profile)
+}
+
+pub fn set_active(profile_id: &str) -> Result<Profile, String> {
+    let mut registry = load()?;
+    let profile = registry
+        .profiles
+        .iter()
+        .find(|p| p.id == profile_id)
+        .cloned()
+        .ok_or_else(|| t(Msg::ProfileNotFound))?;
+    registry.active = profile.id.clone();
+    save(&registry)?;
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_falls_back_to_the_default_profile() {
+        let registry = parse(
+            r#"{"active": "work", "profiles": [
+                {"id": "default", "name": "默认", "created_at": ""},
+                {"id": "work", "name": "Work", "created_at": "2025-03-01T12:00:00Z"}
+            ]}"#,
+        );
+        assert_eq!(registry.active, "work");
+        assert_eq!(registry.profiles.len(), 2);
+
+        let removed = parse(r#"{"active": "gone", "profiles": [{"id": "default", "name": "默认", "created_at": ""}]}"#);
+        assert_eq!(removed.active, DEFAULT_PROFILE_ID);
+
+        let corrupt = parse("{not json");
+        assert_eq!(corrupt.active, DEFAULT_PROFILE_ID);
+        assert_eq!(corrupt.profiles.len(), 1);
+    }
+}
@@ -0,0 +1,247 @@
+// "Send to read-later service" integration: Pocket, Instapaper and Wallabag.
+//
+// Credentials live in the settings table under `read_later.*` keys and are
+// edited through `read_later_config_get/update`, separate from the main
+// `Settings` payload.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{get_setting, set_setting};
+
+const POCKET_REDIRECT_URI: &str = "https://getpocket.com/connected_applications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    Pocket,
+    Instapaper,
+    Wallabag,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadLaterConfig {
+    pub pocket_consumer_key: String,
+    pub pocket_access_token: String,
+    pub instapaper_username: String,
+    pub instapaper_password: String,
+    pub wallabag_url: String,
+    pub wallabag_client_id: String,
+    pub wallabag_client_secret: String,
+    pub wallabag_username: String,
+    pub wallabag_password: String,
+}
+
+// (settings key, accessor) pairs so load/save stay in sync
+type Field = (&'static str, fn(&mut ReadLaterConfig) -> &mut String);
+
+const FIELDS: [Field; 9] = [
+    ("read_later.pocket_consumer_key", |c| &mut c.pocket_consumer_key),
+    ("read_later.pocket_access_token", |c| &mut c.pocket_access_token),
+    ("read_later.instapaper_username", |c| &mut c.instapaper_username),
+    ("read_later.instapaper_password", |c| &mut c.instapaper_password),
+    ("read_later.wallabag_url", |c| &mut c.wallabag_url),
+    ("read_later.wallabag_client_id", |c| &mut c.wallabag_client_id),
+    ("read_later.wallabag_client_secret", |c| &mut c.wallabag_client_secret),
+    ("read_later.wallabag_username", |c| &mut c.wallabag_username),
+    ("read_later.wallabag_password", |c| &mut c.wallabag_password),
+];
+
+impl ReadLaterConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let mut config = ReadLaterConfig::default();
+        for (key, field) in FIELDS {
+            *field(&mut config) = get_setting(conn, key, "")?;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let mut config = self.clone();
+        for (key, field) in FIELDS {
+            set_setting(conn, key, field(&mut config).trim())?;
+        }
+        Ok(())
+    }
+
+    pub fn is_configured(&self, service: Service) -> bool {
+        match service {
+            Service::Pocket => !self.pocket_consumer_key.is_empty() && !self.pocket_access_token.is_empty(),
+            Service::Instapaper => !self.instapaper_username.is_empty(),
+            Service::Wallabag => {
+                !self.wallabag_url.is_empty()
+                    && !self.wallabag_client_id.is_empty()
+                    && !self.wallabag_client_secret.is_empty()
+                    && !self.wallabag_username.is_empty()
+            }
+        }
+    }
+}
+
+pub async fn send(
+    client: &reqwest::Client,
+    config: &ReadLaterConfig,
+    service: Service,
+    url: &str,
+    title: &str,
+) -> Result<(), String> {
+    if !config.is_configured(service) {
+        return Err(t(Msg::ReadLaterNotConfigured));
+    }
+    match service {
+        Service::Pocket => send_pocket(client, config, url, title).await,
+        Service::Instapaper => send_instapaper(client, config, url, title).await,
+        Service::Wallabag => send_wallabag(client, config, url, title).await,
+    }
+}
+
+async fn send_pocket(client: &reqwest::Client, config: &ReadLaterConfig, url: &str, title: &str) -> Result<(), String> {
+    let response = client
+        .post("https://getpocket.com/v3/add")
+        .header("X-Accept", "application/json")
+        .json(&serde_json::json!({
+            "url": url,
+            "title": title,
+            "consumer_key": config.pocket_consumer_key,
+            "access_token": config.pocket_access_token,
+        }))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    check_status(response).await.map(|_| ())
+}
+
+// Instapaper Simple API: basic auth, password may be empty for accounts without one
+async fn send_instapaper(client: &reqwest::Client, config: &ReadLaterConfig, url: &str, title: &str) -> Result<(), String> {
+    let response = client
+        .post("https://www.instapaper.com/api/add")
+        .basic_auth(&config.instapaper_username, Some(&config.instapaper_password))
+        .form(&[("url", url), ("title", title)])
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    check_status(response).await.map(|_| ())
+}
+
+// Wallabag uses OAuth2 password grant; a fresh token per send keeps no refresh state around
+async fn send_wallabag(client: &reqwest::Client, config: &ReadLaterConfig, url: &str, title: &str) -> Result<(), String> {
+    let base = config.wallabag_url.trim_end_matches('/');
+    let token_response = client
+        .post(format!("{}/oauth/v2/token", base))
+        .form(&[
+            ("grant_type", "password"),
+            ("client_id", config.wallabag_client_id.as_str()),
+            ("client_secret", config.wallabag_client_secret.as_str()),
+            ("username", config.wallabag_username.as_str()),
+            ("password", config.wallabag_password.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    let token_json = check_status(token_response).await?;
+    let access_token = token_json["access_token"]
+        .as_str()
+        .ok_or_else(|| tr(Msg::ReadLaterSendFailed, "missing access_token"))?;
+
+    let response = client
+        .post(format!("{}/api/entries.json", base))
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({ "url": url, "title": title }))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    check_status(response).await.map(|_| ())
+}
+
+// Step 1 of Pocket OAuth: returns (request_token, authorize_url) for the user to open
+pub async fn pocket_auth_start(client: &reqwest::Client, consumer_key: &str) -> Result<(String, String), String> {
+    let response = client
+        .post("https://getpocket.com/v3/oauth/request")
+        .header("X-Accept", "application/json")
+        .json(&serde_json::json!({
+            "consumer_key": consumer_key,
+            "redirect_uri": POCKET_REDIRECT_URI,
+        }))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    let json = check_status(response).await?;
+    let code = json["code"]
+        .as_str()
+        .ok_or_else(|| tr(Msg::ReadLaterSendFailed, "missing request code"))?
+        .to_string();
+    let authorize_url = reqwest::Url::parse_with_params(
+        "https://getpocket.com/auth/authorize",
+        &[("request_token", code.as_str()), ("redirect_uri", POCKET_REDIRECT_URI)],
+    )
+    .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    Ok((code, authorize_url.to_string()))
+}
+
+// Step 2 of Pocket OAuth: exchange the authorized request token for an access token
+pub async fn pocket_auth_finish(client: &reqwest::Client, consumer_key: &str, request_token: &str) -> Result<String, String> {
+    let response = client
+        .post("https://getpocket.com/v3/oauth/authorize")
+        .header("X-Accept", "application/json")
+        .json(&serde_json::json!({
+            "consumer_key": consumer_key,
+            "code": request_token,
+        }))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::ReadLaterSendFailed, e))?;
+    let json = check_status(response).await?;
+    json["access_token"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| tr(Msg::ReadLaterSendFailed, "missing access_token"))
+}
+
+// Non-2xx becomes an error; the body is parsed as JSON when possible
+async fn check_status(response: reqwest::Response) -> Result<serde_json::Value, String> {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(tr(Msg::ReadLaterSendFailed, format!("({}) {}", status, body)));
+    }
+    Ok(serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trips_through_settings_trimmed() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let config = ReadLaterConfig {
+            instapaper_username: " reader@example.com ".to_string(),
+            wallabag_url: "https://wallabag.example.com/".to_string(),
+            wallabag_client_id: "id".to_string(),
+            wallabag_client_secret: "secret".to_string(),
+            ..Default::default()
+        };
+        config.save(&conn).unwrap();
+
+        let loaded = ReadLaterConfig::load(&conn).unwrap();
+        assert_eq!(loaded.instapaper_username, "reader@example.com");
+        assert!(loaded.is_configured(Service::Instapaper));
+        assert!(!loaded.is_configured(Service::Wallabag), "no username yet");
+        assert!(!loaded.is_configured(Service::Pocket));
+    }
+
+    #[test]
+    fn unconfigured_service_fails_before_any_request() {
+        let client = reqwest::Client::new();
+        let sent = tauri::async_runtime::block_on(send(
+            &client,
+            &ReadLaterConfig::default(),
+            Service::Pocket,
+            "https://example.com",
+            "Example",
+        ));
+        assert!(sent.is_err());
+    }
+}
@@ -0,0 +1,170 @@
+// Read-only mode, for a database another instance writes to (e.g. on a
+// synced drive).
+//
+// Enabled with `--read-only` / `NEWS_READ_ONLY=1`, or automatically when the
+// database can't be opened for writing. The connection is opened read-only,
+// the scheduler pauses, and any command not in READ_COMMANDS is rejected
+// with a clear message before it runs instead of failing on a lock.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+
+use crate::i18n::{t, Msg};
+use crate::perf;
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Commands that never write to the database
+const READ_COMMANDS: &[&str] = &[
+    "health",
+    "app_read_only",
+    "db_recovery_report",
+    "articles_list",
+    "category_counts",
+    "articles_get_many",
+    "articles_prefetch",
+    "search_query",
+    "search_reindex_status",
+    "article_annotations_get",
+    "article_revisions_list",
+    "article_edits_list",
+    "article_alternates",
+    "article_keywords_get",
+    "keywords_top",
+    "keywords_trending",
+    "trash_list",
+    "crawl_metrics",
+    "perf_report",
+    "articles_export",
+    "articles_export_cancel",
+    "article_share_card",
+    "article_export_pdf",
+    "article_reader",
+    "article_heat_breakdown",
+    "heat_config_get",
+    "feed_balance_config_get",
+    "light_pages_config_get",
+    "github_star_filter_get",
+    "heat_weights_get",
+    "obsidian_config_get",
+    "export_feed",
+    "source_pack_export",
+    "source_trending_get",
+    "export_epub",
+    "export_bibtex",
+    "export_zotero",
+    "kindle_send",
+    "kindle_send_digest",
+    "folders_list",
+    "glossary_list",
+    "commands_palette",
+    "bookmarks_import_preview",
+    "settings_get",
+    "profile_list",
+    "ai_summarize",
+    "article_summary_ensure",
+    "ai_limits_config_get",
+    "ai_test",
+    "features_get",
+    "ui_state_get",
+    "read_later_config_get",
+    "pending_jobs",
+    "network_status",
+    "greader_config_get",
+    "cloud_sync_config_get",
+    "watchlists_list",
+    "watchlist_feed",
+    "rules_list",
+    "rule_test",
+    "rule_matches",
+    "webhooks_list",
+    "smtp_config_get",
+    "digest_preview",
+    "digests_list",
+    "digest_get",
+    "digest_export",
+    "star_report_config_get",
+    "star_report",
+    "open_external",
+    "reading_stats",
+    "storage_stats",
+];
+
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == "--read-only")
+        || std::env::var("NEWS_READ_ONLY").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+pub fn set(enabled: bool) {
+    READ_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+pub fn allows(command: &str) -> bool {
+    !is_enabled() || READ_COMMANDS.contains(&command)
+}
+
+// For write paths outside the command handler (local API save route)
+pub fn ensure_writable() -> Result<(), String> {
+    if is_enabled() {
+        Err(t(Msg::ReadOnlyMode))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn open(db_path: &str) -> Result<Connection, rusqlite::Error> {
+    let mut conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+    perf::watch(&mut conn);
+    Ok(conn)
+}
+
+// Opening for writing failed because the file is read-only or held by another writer
+pub fn is_write_denied(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::ReadOnly | ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_connection_rejects_writes_as_write_denied() {
+        let path = std::env::temp_dir().join(format!("news-read-only-{}.db", std::process::id()));
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)", []).unwrap();
+        }
+        let conn = open(path.to_str().unwrap()).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+        let error = conn.execute("INSERT INTO t (id) VALUES (1)", []).unwrap_err();
+        assert!(is_write_denied(&error), "{:?}", error);
+
+        let writable = Connection::open_in_memory().unwrap();
+        writable.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)", []).unwrap();
+        writable.execute("INSERT INTO t (id) VALUES (1)", []).unwrap();
+        let duplicate = writable.execute("INSERT INTO t (id) VALUES (1)", []).unwrap_err();
+        assert!(!is_write_denied(&duplicate), "constraint errors aren't about access");
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn read_commands_are_listed_once_and_exclude_writes() {
+        let mut commands = READ_COMMANDS.to_vec();
+        commands.sort_unstable();
+        commands.dedup();
+        assert_eq!(commands.len(), READ_COMMANDS.len());
+        for write in ["crawler_run_once", "settings_update", "manual_add", "articles_mark_all_read"] {
+            assert!(!READ_COMMANDS.contains(&write), "{}", write);
+        }
+    }
+}
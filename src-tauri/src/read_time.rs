@@ -0,0 +1,143 @@
+// Media durations and reading times, for "how long is this" filters.
+//
+// Podcast feeds give each episode an `<itunes:duration>` and video feeds
+// (Media RSS) a `duration` attribute on `<media:content>`; the crawler
+// stores it in seconds as `articles.media_duration`. Every article also gets
+// `reading_minutes`, estimated from its stored content when it's inserted or
+// its content changes. `articles_list` can cap the media duration
+// (`max_duration_minutes`, text articles unaffected) or keep only short reads
+// (`short_only`): episodes and videos up to SHORT_READ_MINUTES long and text
+// articles readable in that time.
+
+use rusqlite::{params, Connection};
+
+use crate::extractive::plain_text;
+use crate::search_index::is_cjk;
+
+pub const SHORT_READ_MINUTES: i64 = 5;
+const WORDS_PER_MINUTE: usize = 230;
+// Chinese is read per character
+const CJK_CHARS_PER_MINUTE: usize = 400;
+
+// "1:02:03", "62:03" or "3723" in seconds
+pub fn parse_duration(text: &str) -> Option<i64> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut seconds = 0;
+    for part in parts {
+        let value: f64 = part.trim().parse().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        seconds = seconds * 60 + value as i64;
+    }
+    (seconds > 0).then_some(seconds)
+}
+
+// Duration of a feed item's audio or video, in seconds
+pub fn from_rss_item(item: &rss::Item) -> Option<i64> {
+    if let Some(seconds) = item.itunes_ext().and_then(|ext| ext.duration()).and_then(parse_duration) {
+        return Some(seconds);
+    }
+    let media = item.extensions().get("media")?;
+    let grouped = media.get("group").into_iter().flatten().filter_map(|group| group.children().get("content")).flatten();
+    media
+        .get("content")
+        .into_iter()
+        .flatten()
+        .chain(grouped)
+        .find_map(|content| content.attrs().get("duration").and_then(|d| parse_duration(d)))
+}
+
+// Estimated minutes to read `content`, at least 1
+pub fn reading_minutes(content: &str) -> i64 {
+    let text = plain_text(content);
+    let cjk = text.chars().filter(|&c| is_cjk(c)).count();
+    let words = text.split(|c: char| c.is_whitespace() || is_cjk(c)).filter(|w| w.chars().any(char::is_alphanumeric)).count();
+    let minutes = words as f64 / WORDS_PER_MINUTE as f64 + cjk as f64 / CJK_CHARS_PER_MINUTE as f64;
+    (minutes.ceil() as i64).max(1)
+}
+
+// Reading times for rows stored before the column existed
+pub fn backfill(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let rows: Vec<(i64, String)> = conn
+        .prepare("SELECT rowid, COALESCE(content, '') FROM articles WHERE reading_minutes IS NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    let mut update = conn.prepare("UPDATE articles SET reading_minutes = ?1 WHERE rowid = ?2")?;
+    for (rowid, content) in rows {
+        update.execute(params![reading_minutes(&content), rowid])?;
+    }
+    Ok(())
+}
+
+// `articles_list` condition for `short_only`
+pub fn short_condition() -> String {
+    format!("COALESCE(media_duration, reading_minutes * 60) <= {}", SHORT_READ_MINUTES * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_in_any_feed_format() {
+        assert_eq!(parse_duration("1:02:03"), Some(3723));
+        assert_eq!(parse_duration(" 62:03 "), Some(3723));
+        assert_eq!(parse_duration("3723"), Some(3723));
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("1:2:3:4"), None);
+        assert_eq!(parse_duration("-5"), None);
+        assert_eq!(parse_duration("about an hour"), None);
+    }
+
+    #[test]
+    fn durations_from_itunes_and_media_rss() {
+        let feed = r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+                          xmlns:media="http://search.yahoo.com/mrss/"><channel><title>t</title>
+            <item><title>Episode</title><itunes:duration>45:00</itunes:duration></item>
+            <item><title>Video</title><media:group><media:content url="v.mp4" duration="95"/></media:group></item>
+            <item><title>Post</title><media:content url="a.png"/></item>
+        </channel></rss>"#;
+        let channel = rss::Channel::read_from(feed.as_bytes()).unwrap();
+        let durations: Vec<Option<i64>> = channel.items().iter().map(from_rss_item).collect();
+        assert_eq!(durations, [Some(2700), Some(95), None]);
+    }
+
+    #[test]
+    fn reading_time_counts_words_and_chinese_characters() {
+        assert_eq!(reading_minutes(""), 1);
+        assert_eq!(reading_minutes(&"word ".repeat(WORDS_PER_MINUTE * 2)), 2);
+        assert_eq!(reading_minutes(&"word ".repeat(WORDS_PER_MINUTE * 2 + 1)), 3);
+        assert_eq!(reading_minutes(&"字".repeat(CJK_CHARS_PER_MINUTE * 3)), 3);
+    }
+
+    #[test]
+    fn backfill_and_short_reads() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let long = "word ".repeat(WORDS_PER_MINUTE * 10);
+        for (id, content, duration) in [("text", long.as_str(), None), ("clip", "", Some(120)), ("talk", "", Some(3600))] {
+            conn.execute(
+                "INSERT INTO articles (id, title, url, source, content, media_duration) VALUES (?1, ?1, ?1, 'Blog', ?2, ?3)",
+                params![id, content, duration],
+            )
+            .unwrap();
+        }
+        conn.execute("UPDATE articles SET reading_minutes = NULL", []).unwrap();
+        backfill(&conn).unwrap();
+        let minutes: i64 =
+            conn.query_row("SELECT reading_minutes FROM articles WHERE id = 'text'", [], |row| row.get(0)).unwrap();
+        assert_eq!(minutes, 10);
+
+        let short: Vec<String> = conn
+            .prepare(&format!("SELECT id FROM articles WHERE {} ORDER BY id", short_condition()))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(short, ["clip"]);
+    }
+}
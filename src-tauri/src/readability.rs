@@ -0,0 +1,203 @@
+// Main-content extraction for article pages: finds the element that holds
+// the article body and re-serializes it as clean, well-formed XHTML with a
+// small whitelist of tags. Used for offline exports (EPUB) where the stored
+// RSS snippet is not enough.
+
+use std::collections::HashMap;
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::digest::escape;
+
+// Below this much text the extraction is probably a nav/landing page
+const MIN_TEXT_LEN: usize = 200;
+
+// Likely content containers, tried in order before the paragraph-density fallback
+const CONTENT_SELECTORS: &str = "article, main, [role='main'], [itemprop='articleBody'], .post-content, \
+    .entry-content, .article-content, .article-body, .post-body, #content";
+
+const SKIP_TAGS: [&str; 14] = [
+    "script", "style", "noscript", "iframe", "form", "button", "input", "select", "nav", "header", "footer",
+    "aside", "svg", "template",
+];
+
+// Kept (with their semantics) in the output; everything else is unwrapped
+const KEEP_TAGS: [&str; 29] = [
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "li", "blockquote", "pre", "code", "em", "strong", "b",
+    "i", "a", "img", "br", "hr", "figure", "figcaption", "table", "thead", "tbody", "tr", "th", "td",
+];
+
+// Boilerplate blocks inside article containers
+const SKIP_CLASS_HINTS: [&str; 8] = ["share", "social", "comment", "related", "newsletter", "subscribe", "advert", "promo"];
+
+pub struct Readable {
+    pub html: String,
+    // Absolute image URLs in document order, as referenced by `html`
+    pub images: Vec<String>,
+}
+
+pub fn extract(html: &str, base_url: &str) -> Option<Readable> {
+    let document = Html::parse_document(html);
+    let base = reqwest::Url::parse(base_url).ok();
+    let root = find_content_root(&document)?;
+    if visible_text_len(root) < MIN_TEXT_LEN {
+        return None;
+    }
+
+    let mut out = Readable { html: String::new(), images: Vec::new() };
+    serialize_children(root, base.as_ref(), &mut out);
+    Some(out)
+}
+
+fn find_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let selector = Selector::parse(CONTENT_SELECTORS).ok()?;
+    if let Some(el) = document.select(&selector).find(|el| visible_text_len(*el) >= MIN_TEXT_LEN) {
+        return Some(el);
+    }
+
+    // Fallback: the parent with the most paragraph text
+    let paragraphs = Selector::parse("p").ok()?;
+    let mut scores = HashMap::new();
+    for p in document.select(&paragraphs) {
+        if let Some(parent) = p.parent().and_then(ElementRef::wrap) {
+            *scores.entry(parent.id()).or_insert(0usize) += p.text().map(|t| t.trim().len()).sum::<usize>();
+        }
+    }
+    scores
+        .into_iter()
+        .max_by_key(|(_, score)| *score)
+        .and_then(|(id, _)| document.tree.get(id))
+        .and_then(ElementRef::wrap)
+}
+
+fn should_skip(el: &ElementRef) -> bool {
+    let value = el.value();
+    if SKIP_TAGS.contains(&value.name()) {
+        return true;
+    }
+    let hints = format!("{} {}", value.attr("class").unwrap_or(""), value.attr("id").unwrap_or("")).to_lowercase();
+    SKIP_CLASS_HINTS.iter().any(|h| hints.contains(h))
+}
+
+fn visible_text_len(el: ElementRef) -> usize {
+    let mut len = 0;
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => len += text.trim().chars().count(),
+            Node::Element(_) => {
+                if let Some(child_el) = ElementRef::wrap(child) {
+                    if !should_skip(&child_el) {
+                        len += visible_text_len(child_el);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    len
+}
+
+fn serialize_children(el: ElementRef, base: Option<&reqwest::Url>, out: &mut Readable) {
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => out.html.push_str(&escape(text)),
+            Node::Element(_) => {
+                if let Some(child_el) = ElementRef::wrap(child) {
+                    serialize_element(child_el, base, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn serialize_element(el: ElementRef, base: Option<&reqwest::Url>, out: &mut Readable) {
+    if should_skip(&el) {
+        return;
+    }
+    let name = el.value().name();
+    if !KEEP_TAGS.contains(&name) {
+        serialize_children(el, base, out);
+        return;
+    }
+
+    match name {
+        "img" => {
+            // Lazy-loading sites keep the real URL in data-src
+            let src = ["data-src", "data-original", "src"]
+                .iter()
+                .filter_map(|attr| el.value().attr(attr))
+                .find(|s| !s.is_empty() && !s.starts_with("data:"));
+            if let Some(url) = src.and_then(|s| absolutize(base, s)) {
+                let alt = el.value().attr("alt").unwrap_or("");
+                out.html.push_str(&format!("<img src=\"{}\" alt=\"{}\"/>", escape(&url), escape(alt)));
+                out.images.push(url);
+            }
+        }
+        "br" | "hr" => out.html.push_str(&format!("<{}/>", name)),
+        "a" => {
+            match el.value().attr("href").and_then(|h| absolutize(base, h)) {
+                Some(href) => out.html.push_str(&format!("<a href=\"{}\">", escape(&href))),
+                None => out.html.push_str("<a>"),
+            }
+            serialize_children(el, base, out);
+            out.html.push_str("</a>");
+        }
+        _ => {
+            out.html.push_str(&format!("<{}>", name));
+            serialize_children(el, base, out);
+            out.html.push_str(&format!("</{}>", name));
+        }
+    }
+}
+
+fn absolutize(base: Option<&reqwest::Url>, href: &str) -> Option<String> {
+    let href = href.trim();
+    let url = match base {
+        Some(base) => base.join(href).ok()?,
+        None => reqwest::Url::parse(href).ok()?,
+    };
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAGRAPH: &str = "Paged attention splits the key-value cache into fixed-size blocks, \
+        so long prompts no longer need one contiguous allocation and memory waste drops sharply.";
+
+    #[test]
+    fn keeps_the_article_body_and_drops_boilerplate() {
+        let page = format!(
+            r#"<html><body><nav>Home | About</nav>
+            <article><h1>Serving <em>fast</em></h1>
+            <p>{p}</p><p>{p} <a href="/docs?a=1&amp;b=2">docs</a> <a href="javascript:void(0)">share</a></p>
+            <img data-src="/img/chart.png" src="data:image/gif;base64,R0lGOD" alt="Chart &quot;1&quot;">
+            <div class="share-buttons">Share on X</div><span>unwrapped</span><script>track()</script>
+            </article></body></html>"#,
+            p = PARAGRAPH
+        );
+        let readable = extract(&page, "https://example.com/posts/serving").unwrap();
+        assert!(readable.html.starts_with("<h1>Serving <em>fast</em></h1>"), "{}", readable.html);
+        assert!(readable.html.contains("<a href=\"https://example.com/docs?a=1&amp;b=2\">docs</a>"), "{}", readable.html);
+        assert!(readable.html.contains("<a>share</a>"), "{}", readable.html);
+        assert!(readable.html.contains("<img src=\"https://example.com/img/chart.png\" alt=\"Chart &quot;1&quot;\"/>"), "{}", readable.html);
+        assert!(readable.html.contains("unwrapped") && !readable.html.contains("<span>"), "{}", readable.html);
+        for dropped in ["Home", "Share on X", "track()"] {
+            assert!(!readable.html.contains(dropped), "{} kept: {}", dropped, readable.html);
+        }
+        assert_eq!(readable.images, ["https://example.com/img/chart.png"]);
+    }
+
+    #[test]
+    fn pages_without_a_container_use_the_densest_paragraphs() {
+        let page = format!(
+            "<html><body><div><p>Menu</p></div><div class=\"story\"><p>{p}</p><p>{p}</p></div></body></html>",
+            p = PARAGRAPH
+        );
+        let readable = extract(&page, "https://example.com/").unwrap();
+        assert!(readable.html.starts_with("<p>Paged attention") && !readable.html.contains("Menu"), "{}", readable.html);
+        assert!(extract("<html><body><main><p>Too short to be an article.</p></main></body></html>", "https://example.com/").is_none());
+    }
+}
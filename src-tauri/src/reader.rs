@@ -0,0 +1,273 @@
+// In-app reader view of an article (`article_reader`).
+//
+// The article's page is fetched like for the exports (HTTP cache, paywall
+// check, lighter-page fallback) and its main content extracted by
+// `readability`, whose tag whitelist already leaves out scripts, styles,
+// iframes, forms and every attribute but `href` / `src` / `alt`, with links
+// and images made absolute. On top of that, images from ad and analytics
+// hosts and tracking pixels are dropped, tracking parameters (`utm_*`,
+// `fbclid`, ...) are stripped from links, and the remaining images are
+// downloaded into the `images/` folder of the data directory and referenced
+// through the `newsimg` URI scheme, so opening the reader loads nothing from
+// third parties and works offline once read. Images that can't be cached are
+// left out. Without a readable page the stored content is shown instead.
+// The image cache counts towards the storage quota, which evicts it oldest
+// first.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::get_data_dir;
+use crate::digest::escape;
+use crate::epub::{fallback_body, fetch_image, remove_img};
+use crate::{article_image, epub, readability, storage, Article, HttpClients};
+
+// Serves the image cache to the webview; see `image_url`
+pub const IMAGE_SCHEME: &str = "newsimg";
+const MAX_IMAGES: usize = 30;
+// Width and height at or below which an image is a tracking pixel
+const PIXEL_SIZE: u32 = 2;
+
+// Hosts (and their subdomains) of ad, analytics and feed-tracking images
+const TRACKER_HOSTS: &[&str] = &[
+    "doubleclick.net",
+    "google-analytics.com",
+    "googletagmanager.com",
+    "googlesyndication.com",
+    "facebook.com",
+    "scorecardresearch.com",
+    "quantserve.com",
+    "pixel.wp.com",
+    "stats.wp.com",
+    "feeds.feedburner.com",
+    "feedblitz.com",
+    "mc.yandex.ru",
+    "hm.baidu.com",
+    "cnzz.com",
+];
+
+// Query parameters that only identify the click or campaign
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi"];
+const TRACKING_PREFIXES: &[&str] = &["utm_", "ga_"];
+
+// Cached files are named by the SHA-256 of their URL plus one of these
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct ReaderView {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub source: String,
+    pub published_at: String,
+    // Sanitized body, ready to render as-is
+    pub html: String,
+    // false = the page couldn't be read and `html` is the stored content
+    pub from_page: bool,
+    pub images: usize,
+}
+
+pub fn image_dir() -> Result<PathBuf, String> {
+    Ok(Path::new(&get_data_dir()?).join(storage::IMAGE_DIR))
+}
+
+fn host_matches(url: &str, domains: &[&str]) -> bool {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_ascii_lowercase)) else {
+        return false;
+    };
+    domains.iter().any(|domain| host == *domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.')))
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    TRACKING_PARAMS.contains(&key.as_str()) || TRACKING_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+// The URL without tracking parameters; None when there were none
+fn clean_url(url: &str) -> Option<String> {
+    let mut parsed = reqwest::Url::parse(url).ok()?;
+    let pairs: Vec<(String, String)> = parsed.query_pairs().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    if !pairs.iter().any(|(key, _)| is_tracking_param(key)) {
+        return None;
+    }
+    let kept: Vec<&(String, String)> = pairs.iter().filter(|(key, _)| !is_tracking_param(key)).collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    }
+    Some(parsed.to_string())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"").replace("&gt;", ">").replace("&lt;", "<").replace("&amp;", "&")
+}
+
+// Strip tracking parameters from every link `readability` wrote
+fn clean_links(html: &str) -> String {
+    const NEEDLE: &str = "<a href=\"";
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(NEEDLE) {
+        let value_start = start + NEEDLE.len();
+        let Some(len) = rest[value_start..].find('"') else { break };
+        let href = unescape(&rest[value_start..value_start + len]);
+        out.push_str(&rest[..value_start]);
+        out.push_str(&escape(&clean_url(&href).unwrap_or(href)));
+        rest = &rest[value_start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// The webview's URL for a cached file (custom schemes are served over http on Windows and Android)
+fn image_url(file_name: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", IMAGE_SCHEME, file_name)
+    } else {
+        format!("{}://localhost/{}", IMAGE_SCHEME, file_name)
+    }
+}
+
+fn cached_file(image_dir: &Path, key: &str) -> Option<String> {
+    IMAGE_TYPES
+        .iter()
+        .map(|(ext, _)| format!("{}.{}", key, ext))
+        .find(|name| image_dir.join(name).is_file())
+}
+
+// The cached copy of the image at `url`, downloading it first; None for pixels and failed downloads
+async fn cache_image(http: &HttpClients, image_dir: &Path, url: &str) -> Option<String> {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    if let Some(name) = cached_file(image_dir, &key) {
+        return Some(name);
+    }
+    let (data, _, ext) = fetch_image(http.for_url(url), url).await?;
+    if article_image::dimensions(&data).is_some_and(|(w, h)| w <= PIXEL_SIZE && h <= PIXEL_SIZE) {
+        return None;
+    }
+    let name = format!("{}.{}", key, ext);
+    std::fs::create_dir_all(image_dir).ok()?;
+    if let Err(e) = std::fs::write(image_dir.join(&name), data) {
+        eprintln!("Failed to cache image {}: {}", url, e);
+        return None;
+    }
+    Some(name)
+}
+
+// The reader view of a stored article, with its images cached under `image_dir`
+pub async fn render(db: &Mutex<Connection>, http: &HttpClients, article: &Article, image_dir: &Path) -> ReaderView {
+    let readable = match epub::fetch_text(db, http, &article.url).await {
+        Some(html) => readability::extract(&html, &article.url),
+        None => None,
+    };
+    let from_page = readable.is_some();
+    let (mut html, image_urls) = match readable {
+        Some(r) => (clean_links(&r.html), r.images),
+        None => (fallback_body(article), Vec::new()),
+    };
+
+    let mut images = 0;
+    let mut seen: Vec<&String> = Vec::new();
+    for url in &image_urls {
+        if seen.contains(&url) {
+            continue;
+        }
+        seen.push(url);
+        let src_attr = format!("src=\"{}\"", escape(url));
+        let cached = if images < MAX_IMAGES && !host_matches(url, TRACKER_HOSTS) {
+            cache_image(http, image_dir, url).await
+        } else {
+            None
+        };
+        html = match cached {
+            Some(name) => {
+                images += 1;
+                html.replace(&src_attr, &format!("src=\"{}\"", escape(&image_url(&name))))
+            }
+            None => remove_img(&html, &src_attr),
+        };
+    }
+
+    ReaderView {
+        id: article.id.clone(),
+        title: article.title.clone(),
+        url: article.url.clone(),
+        source: article.source.clone(),
+        published_at: article.published_at.clone(),
+        html,
+        from_page,
+        images,
+    }
+}
+
+// Response to a `newsimg://localhost/<file>` request from the webview
+pub fn serve_image(image_dir: &Path, path: &str) -> tauri::http::Response<Vec<u8>> {
+    let name = path.trim_start_matches('/');
+    // Only names `cache_image` writes: a hex digest and a known extension, never a path
+    let content_type = name.split_once('.').and_then(|(key, ext)| {
+        let valid_key = key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit());
+        IMAGE_TYPES.iter().find(|(known, _)| *known == ext).filter(|_| valid_key).map(|(_, mime)| *mime)
+    });
+    let body = content_type.and_then(|_| std::fs::read(image_dir.join(name)).ok());
+    let response = tauri::http::Response::builder();
+    match (content_type, body) {
+        (Some(content_type), Some(body)) => response
+            .header("Content-Type", content_type)
+            .header("Content-Security-Policy", "default-src 'none'; style-src 'unsafe-inline'")
+            .body(body),
+        _ => response.status(404).body(Vec::new()),
+    }
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracking_parameters_are_stripped_from_links() {
+        assert_eq!(
+            clean_url("https://example.com/post?id=3&utm_source=feed&FBCLID=x").as_deref(),
+            Some("https://example.com/post?id=3")
+        );
+        assert_eq!(clean_url("https://example.com/post?utm_medium=rss").as_deref(), Some("https://example.com/post"));
+        assert_eq!(clean_url("https://example.com/post?id=3"), None, "nothing to strip");
+        assert_eq!(
+            clean_links(r#"<p><a href="https://example.com/a?x=1&amp;gclid=2">A</a> and <a href="/b">B</a></p>"#),
+            r#"<p><a href="https://example.com/a?x=1">A</a> and <a href="/b">B</a></p>"#
+        );
+        assert!(host_matches("https://ad.doubleclick.net/pixel.gif", TRACKER_HOSTS));
+        assert!(!host_matches("https://notdoubleclick.net/a.png", TRACKER_HOSTS));
+        assert!(!host_matches("data:image/png;base64,AAAA", TRACKER_HOSTS));
+    }
+
+    #[test]
+    fn only_cached_image_names_are_served() {
+        let dir = std::env::temp_dir().join(format!("news-reader-images-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = hex::encode(Sha256::digest(b"https://example.com/chart.png"));
+        std::fs::write(dir.join(format!("{}.png", key)), b"png bytes").unwrap();
+        assert_eq!(cached_file(&dir, &key), Some(format!("{}.png", key)));
+        assert_eq!(cached_file(&dir, "missing"), None);
+
+        let response = serve_image(&dir, &format!("/{}.png", key));
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["Content-Type"], "image/png");
+        assert_eq!(response.body(), b"png bytes");
+        assert_eq!(serve_image(&dir, &format!("/{}.exe", key)).status(), 404);
+        assert_eq!(serve_image(&dir, "/../secrets.png").status(), 404);
+        assert_eq!(serve_image(&dir, &format!("/{}.gif", key)).status(), 404, "not cached");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
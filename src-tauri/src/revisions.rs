@@ -0,0 +1,134 @@
+// Change detection for re-crawled articles.
+//
+// Every crawled article carries a hash of its content. When a feed item with a
+// known URL comes back with different content (a correction, an update), the
+// stored version is copied into `article_revisions` before the article is
+// overwritten, so earlier versions stay readable.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::i18n::{tr, Msg};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            article_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            summary TEXT,
+            content TEXT,
+            content_hash TEXT NOT NULL,
+            fetched_at TEXT,
+            replaced_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_article_revisions_article ON article_revisions(article_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+// Whitespace-insensitive, so re-wrapped feed text doesn't count as a change
+pub fn content_hash(content: &str) -> String {
+    let normalized = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct Revision {
+    pub id: i64,
+    pub article_id: String,
+    pub title: String,
+    pub summary: String,
+    pub content: String,
+    pub fetched_at: String,
+    // When this version was superseded
+    pub replaced_at: String,
+}
+
+// Keep the version about to be overwritten
+pub fn record(conn: &Connection, article_id: &str, title: &str, summary: &str, content: &str, fetched_at: &str) -> Result<(), rusqlite::Error> {
+    conn.prepare_cached(
+        "INSERT INTO article_revisions (article_id, title, summary, content, content_hash, fetched_at, replaced_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?
+    .execute(params![
+        article_id,
+        title,
+        summary,
+        content,
+        content_hash(content),
+        fetched_at,
+        chrono::Utc::now().to_rfc3339()
+    ])?;
+    Ok(())
+}
+
+// Earlier versions of an article, newest first
+pub fn list(conn: &Connection, article_id: &str) -> Result<Vec<Revision>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, article_id, title, COALESCE(summary, ''), COALESCE(content, ''), COALESCE(fetched_at, ''), replaced_at
+             FROM article_revisions WHERE article_id = ?1 ORDER BY id DESC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let revisions = stmt
+        .query_map(params![article_id], |row| {
+            Ok(Revision {
+                id: row.get(0)?,
+                article_id: row.get(1)?,
+                title: row.get(2)?,
+                summary: row.get(3)?,
+                content: row.get(4)?,
+                fetched_at: row.get(5)?,
+                replaced_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(revisions)
+}
+
+// Revisions of articles no longer stored or in the trash
+pub fn delete_orphans(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM article_revisions WHERE article_id NOT IN (SELECT id FROM articles UNION ALL SELECT id FROM articles_trash)",
+        [],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_ignores_rewrapping_but_not_edits() {
+        assert_eq!(content_hash("Model  weights\nreleased. "), content_hash("Model weights released."));
+        assert_ne!(content_hash("Model weights released."), content_hash("Model weights withdrawn."));
+    }
+
+    #[test]
+    fn revisions_list_newest_first_and_orphans_are_removed() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute("INSERT INTO articles (id, title, url, source) VALUES ('a1', 'Kept', 'https://example.com/a1', 'Blog')", [])
+            .unwrap();
+        record(&conn, "a1", "First", "s1", "c1", "2025-03-01T12:00:00Z").unwrap();
+        record(&conn, "a1", "Second", "s2", "c2", "2025-03-02T12:00:00Z").unwrap();
+        record(&conn, "gone", "Deleted", "", "", "").unwrap();
+
+        let titles: Vec<String> = list(&conn, "a1").unwrap().into_iter().map(|r| r.title).collect();
+        assert_eq!(titles, ["Second", "First"]);
+        assert_eq!(list(&conn, "gone").unwrap().len(), 1);
+
+        delete_orphans(&conn).unwrap();
+        assert_eq!(list(&conn, "a1").unwrap().len(), 2);
+        assert!(list(&conn, "gone").unwrap().is_empty());
+    }
+}
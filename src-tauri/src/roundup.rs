@@ -0,0 +1,320 @@
+// Weekly roundup: the week's hottest stories clustered into themes and
+// written up as a longer Markdown report, with an HTML rendering for email.
+//
+// Themes come from title keywords the stories share, so the roundup works
+// without AI; when a provider is configured each theme also gets a name and
+// a short overview. Every roundup is stored in `digests`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::Datelike;
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::digest::{self, DigestSection, StoredDigest};
+use crate::i18n::{lang, t, tr, Lang, Msg};
+use crate::ai::{Ai, AiConfig, ChatProvider, ChatRequest};
+use crate::{glossary, query_trending, Article};
+
+const DAYS: i64 = 7;
+const CANDIDATES: usize = 80;
+const MAX_THEMES: usize = 8;
+const PER_THEME: usize = 6;
+// Stories that didn't fit a theme, listed per category after the themes
+const PER_CATEGORY_EXTRA: usize = 5;
+// Title keywords a story must share with a theme to join it
+const MIN_SHARED_KEYWORDS: usize = 2;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "that", "this", "are", "was", "its", "into", "your", "you", "how", "what",
+    "why", "new", "now", "about", "over", "after", "more", "than", "has", "have", "will", "can", "our", "their",
+    "all", "out", "not", "but", "via", "using", "use", "just", "get",
+];
+
+#[derive(Debug, Serialize)]
+pub struct Theme {
+    pub name: String,
+    // AI-written overview; empty without an AI provider
+    pub overview: String,
+    pub keywords: Vec<String>,
+    pub articles: Vec<Article>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyResult {
+    pub digest: StoredDigest,
+    pub themes: usize,
+    // Recipients the roundup was emailed to; 0 when not sent
+    pub recipients: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c)
+}
+
+// Lowercased words of 3+ letters, plus character bigrams for Chinese text
+pub(crate) fn keywords(title: &str) -> HashSet<String> {
+    let mut keywords = HashSet::new();
+    let lower = title.to_lowercase();
+    for token in lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let chars: Vec<char> = token.chars().collect();
+        for run in chars.chunk_by(|a, b| is_cjk(*a) == is_cjk(*b)) {
+            if is_cjk(run[0]) {
+                keywords.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+            } else {
+                let word: String = run.iter().collect();
+                if run.len() >= 3 && !run.iter().all(|c| c.is_ascii_digit()) && !STOPWORDS.contains(&word.as_str()) {
+                    keywords.insert(word);
+                }
+            }
+        }
+    }
+    keywords
+}
+
+// Greedy clustering in heat order: a story joins the theme it shares the most
+// keywords with, or starts a new one. Single-story "themes" become extras.
+fn cluster(candidates: Vec<Article>) -> (Vec<Theme>, Vec<DigestSection>) {
+    let mut clusters: Vec<(HashMap<String, usize>, Vec<Article>)> = Vec::new();
+    for article in candidates {
+        let words = keywords(&article.title);
+        let best = clusters
+            .iter()
+            .enumerate()
+            .map(|(i, (counts, _))| (i, words.iter().filter(|w| counts.contains_key(*w)).count()))
+            .filter(|(_, shared)| *shared >= MIN_SHARED_KEYWORDS)
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(i, _)| i);
+        match best {
+            // A full theme drops further (cooler) coverage of the same story
+            Some(i) if clusters[i].1.len() >= PER_THEME => {}
+            Some(i) => {
+                for word in words {
+                    *clusters[i].0.entry(word).or_default() += 1;
+                }
+                clusters[i].1.push(article);
+            }
+            None => clusters.push((words.into_iter().map(|w| (w, 1)).collect(), vec![article])),
+        }
+    }
+
+    let mut themes = Vec::new();
+    let mut extras = Vec::new();
+    for (counts, articles) in clusters {
+        if articles.len() < 2 || themes.len() >= MAX_THEMES {
+            extras.extend(articles);
+            continue;
+        }
+        let mut shared: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n >= 2).collect();
+        shared.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        themes.push(Theme {
+            // The hottest story's headline until the AI names the theme
+            name: articles[0].title.clone(),
+            overview: String::new(),
+            keywords: shared.into_iter().take(5).map(|(w, _)| w).collect(),
+            articles,
+        });
+    }
+    (themes, digest::by_category(extras, PER_CATEGORY_EXTRA))
+}
+
+// Name and overview for a theme: first line of the reply is the name, the rest the overview
+fn parse_description(reply: &str) -> Option<(String, String)> {
+    let mut lines = reply.lines().map(str::trim).filter(|l| !l.is_empty());
+    let name = lines.next()?.trim_matches(|c: char| c == '#' || c == '*' || c.is_whitespace()).to_string();
+    let overview = lines.collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, overview))
+}
+
+fn describe_prompt(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Zh => "以下是本周属于同一主题的几条新闻。第一行写出不超过 12 个字的主题名，然后另起一行用 2 到 3 句话概括这一主题本周的进展。不要使用 Markdown。",
+        Lang::En => "These news stories from this week share a theme. On the first line write a theme name of at most six words, then on a new line summarize the week's developments in 2-3 sentences. Do not use Markdown.",
+    }
+}
+
+fn render_markdown(title: &str, period: &str, themes: &[Theme], extras: &[DigestSection]) -> String {
+    let mut out = format!("# {}\n\n{}\n", title, period);
+    if themes.is_empty() && extras.is_empty() {
+        out.push_str(&format!("\n{}\n", t(Msg::DigestEmpty)));
+    }
+    for (i, theme) in themes.iter().enumerate() {
+        out.push_str(&format!("\n## {}. {}\n\n", i + 1, theme.name));
+        if !theme.overview.is_empty() {
+            out.push_str(&format!("{}\n\n", theme.overview));
+        }
+        if !theme.keywords.is_empty() {
+            out.push_str(&format!("*{}*\n\n", theme.keywords.join(" · ")));
+        }
+        for article in &theme.articles {
+            digest::md_article(&mut out, article);
+        }
+    }
+    for section in extras {
+        out.push_str(&format!("\n## {} · {}\n\n", t(Msg::WeeklyMore), section.category));
+        for article in &section.articles {
+            digest::md_article(&mut out, article);
+        }
+    }
+    out
+}
+
+fn render_html(title: &str, period: &str, themes: &[Theme], extras: &[DigestSection]) -> String {
+    let mut body = format!("<p style=\"color:#666\">{}</p>", digest::escape(period));
+    if themes.is_empty() && extras.is_empty() {
+        body.push_str(&format!("<p style=\"color:#666\">{}</p>", digest::escape(&t(Msg::DigestEmpty))));
+    }
+    for (i, theme) in themes.iter().enumerate() {
+        body.push_str(&digest::html_heading(&format!("{}. {}", i + 1, theme.name)));
+        if !theme.overview.is_empty() {
+            body.push_str(&format!(
+                "<p style=\"font-size:15px;color:#333;line-height:1.6\">{}</p>",
+                digest::escape(&theme.overview)
+            ));
+        }
+        for article in &theme.articles {
+            body.push_str(&digest::html_article(article));
+        }
+    }
+    for section in extras {
+        body.push_str(&digest::html_heading(&format!("{} · {}", t(Msg::WeeklyMore), section.category)));
+        for article in &section.articles {
+            body.push_str(&digest::html_article(article));
+        }
+    }
+    digest::html_document(title, &body)
+}
+
+// Cluster the last week's hottest stories, describe the themes when AI is
+// configured, and store the rendered roundup
+pub async fn generate(db: &Mutex<Connection>, client: &reqwest::Client) -> Result<WeeklyResult, String> {
+    let (candidates, ai_config, glossary) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (query_trending(&conn, DAYS, None, CANDIDATES)?, AiConfig::load(&conn).ok(), glossary::list(&conn)?)
+    }; // Release the lock during AI calls
+
+    let (mut themes, extras) = cluster(candidates);
+    let article_count = themes.iter().map(|th| th.articles.len()).sum::<usize>()
+        + extras.iter().map(|s| s.articles.len()).sum::<usize>();
+
+    let lang = lang();
+    if let Some(config) = &ai_config {
+        let ai = Ai::new(client, config);
+        for theme in &mut themes {
+            let stories: Vec<String> = theme
+                .articles
+                .iter()
+                .map(|a| format!("- {}: {}", a.title, a.summary.chars().take(200).collect::<String>()))
+                .collect();
+            let stories = stories.join("\n");
+            let request = ChatRequest {
+                system: format!("{}{}", describe_prompt(lang), glossary::prompt_hint(&glossary, &stories, lang)),
+                user: stories,
+                max_tokens: Some(400),
+                timeout: std::time::Duration::from_secs(60),
+            };
+            match ai.complete(&request).await {
+                Ok(reply) => {
+                    if let Some((name, overview)) = parse_description(&reply) {
+                        theme.name = name;
+                        theme.overview = overview;
+                    }
+                }
+                Err(e) => eprintln!("Weekly roundup: theme description failed: {}", e),
+            }
+        }
+    }
+
+    let now = chrono::Local::now();
+    let week = now.iso_week();
+    let title = format!("{} · {}-W{:02}", t(Msg::WeeklyTitle), week.year(), week.week());
+    let period = format!(
+        "{} – {} · {} {}",
+        (now - chrono::Duration::days(DAYS)).format("%Y-%m-%d"),
+        now.format("%Y-%m-%d"),
+        article_count,
+        t(Msg::WeeklyArticles)
+    );
+
+    let digest = StoredDigest {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: "weekly".to_string(),
+        markdown: render_markdown(&title, &period, &themes, &extras),
+        html: render_html(&title, &period, &themes, &extras),
+        title,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        days: DAYS,
+        article_count,
+    };
+    {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        digest::store(&conn, &digest)?;
+    }
+
+    Ok(WeeklyResult {
+        digest,
+        themes: themes.len(),
+        recipients: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_article;
+    use rusqlite::params;
+
+    fn article(conn: &Connection, id: &str, title: &str) -> Article {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, '', '', ?1, 'Blog', 'AI', '2024-01-05T12:00:00+00:00', '2024-01-05T12:00:00+00:00')",
+            params![id, title],
+        )
+        .unwrap();
+        query_article(conn, id).unwrap().unwrap()
+    }
+
+    fn set(words: &[&str]) -> HashSet<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn keywords_skip_stopwords_numbers_and_short_words() {
+        assert_eq!(keywords("How the new Llama 4 beats GPT-4o in 2025"), set(&["llama", "beats", "gpt"]));
+        assert_eq!(keywords("智谱发布GLM模型"), set(&["智谱", "谱发", "发布", "glm", "模型"]));
+        assert!(keywords("AI, ML & 3D").is_empty());
+    }
+
+    #[test]
+    fn stories_sharing_keywords_form_themes_and_the_rest_become_extras() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let candidates = vec![
+            article(&conn, "https://example.com/1", "Meta releases Llama weights openly"),
+            article(&conn, "https://example.com/2", "Chip export rules tightened again"),
+            article(&conn, "https://example.com/3", "Llama weights leak ahead of release"),
+            article(&conn, "https://example.com/4", "Benchmarks for Llama weights disputed"),
+            article(&conn, "https://example.com/5", "Startup raises seed round"),
+        ];
+        let (themes, extras) = cluster(candidates);
+        assert_eq!(themes.len(), 1);
+        assert_eq!(themes[0].name, "Meta releases Llama weights openly", "named after the hottest story");
+        assert_eq!(themes[0].articles.len(), 3);
+        assert_eq!(themes[0].keywords, ["llama", "weights"]);
+        let extra: Vec<&str> = extras.iter().flat_map(|s| s.articles.iter().map(|a| a.title.as_str())).collect();
+        assert_eq!(extra, ["Chip export rules tightened again", "Startup raises seed round"]);
+    }
+
+    #[test]
+    fn description_reply_splits_into_name_and_overview() {
+        assert_eq!(
+            parse_description("## **Open weights** \n\nMeta shipped Llama.\nOthers followed."),
+            Some(("Open weights".to_string(), "Meta shipped Llama. Others followed.".to_string()))
+        );
+        assert_eq!(parse_description("Name only"), Some(("Name only".to_string(), String::new())));
+        assert_eq!(parse_description("  \n## \n"), None);
+    }
+}
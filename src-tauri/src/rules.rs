@@ -0,0 +1,647 @@
+// Rules for incoming articles.
+//
+// A rule has conditions on an article's title, content (summary and body),
+// source or URL: contains / doesn't contain (case-insensitive), equals, or a
+// regular expression (case-insensitive too). All conditions must hold, or
+// any one with `match_any`. Each matching article gets the rule's actions:
+// set its category, add a tag, mark it read, delete it, boost its heat, or
+// report it as an `app://rules:match` event. Rules run in `position` order
+// on every article a crawl inserts, before it's scored and tagged with
+// keywords. A delete moves the article to the trash, which also keeps
+// crawls from bringing it back, and ends the evaluation for it. Manual
+// adds aren't touched: the user picked those. `rule_test` runs a rule,
+// saved or not, against recent articles without applying anything.
+//
+// Every match is kept in `rule_matches` with the actions it ran and the
+// article's title and URL at the time, so `rule_matches` can show why an
+// article was hidden or boosted, also once it's in the trash. Like watch
+// matches, they go when their article leaves the trash.
+
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::heat::RULE_BOOST_LIMIT;
+use crate::i18n::{t, tr, Msg};
+use crate::{annotations, datetime, query_article, query_articles_by_ids, trash, Article};
+
+// Compiled size limit per regex; plenty for hand-written patterns
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+pub const TEST_LIMIT_DEFAULT: usize = 200;
+pub const TEST_LIMIT_MAX: usize = 2000;
+// Matches `rule_matches` returns, newest first
+const HISTORY_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Title,
+    // Summary and body
+    Content,
+    Source,
+    Url,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Contains,
+    NotContains,
+    Equals,
+    Regex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    pub field: Field,
+    pub operator: Operator,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    SetCategory { category: String },
+    AddTag { tag: String },
+    MarkRead,
+    Delete,
+    // Heat points, negative to push down; added to those of other rules
+    BoostHeat { points: f64 },
+    Notify,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // false = every condition must hold, true = any one
+    #[serde(default)]
+    pub match_any: bool,
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+    // Evaluation order, lowest first; new rules go last
+    #[serde(default)]
+    pub position: i64,
+    // Filled in by `list`
+    #[serde(default)]
+    pub match_count: i64,
+    #[serde(default)]
+    pub last_matched_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatchEvent {
+    pub rule_id: String,
+    pub name: String,
+    pub articles: Vec<Article>,
+}
+
+// One article a rule matched
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatch {
+    pub article_id: String,
+    // As when it matched
+    pub title: String,
+    pub url: String,
+    pub matched_at: String,
+    // What the rule did to it
+    pub actions: Vec<Action>,
+    // Deleted by this or another rule, or by cleanup
+    pub in_trash: bool,
+}
+
+// What a crawl needs to know after the rules ran on its new articles
+#[derive(Debug, Default)]
+pub struct Applied {
+    // Moved to the trash
+    pub deleted: Vec<String>,
+    // Matches of rules with a `notify` action
+    pub events: Vec<RuleMatchEvent>,
+}
+
+enum Test {
+    Contains(String),
+    NotContains(String),
+    Equals(String),
+    Regex(Regex),
+}
+
+struct Compiled<'a> {
+    rule: &'a Rule,
+    tests: Vec<(Field, Test)>,
+}
+
+impl Compiled<'_> {
+    fn new(rule: &Rule) -> Result<Compiled<'_>, String> {
+        let tests = rule
+            .conditions
+            .iter()
+            .map(|condition| {
+                let value = condition.value.trim();
+                if value.is_empty() {
+                    return Err(tr(Msg::RuleInvalid, format!("{:?}", condition.field)));
+                }
+                let test = match condition.operator {
+                    Operator::Contains => Test::Contains(value.to_lowercase()),
+                    Operator::NotContains => Test::NotContains(value.to_lowercase()),
+                    Operator::Equals => Test::Equals(value.to_lowercase()),
+                    Operator::Regex => Test::Regex(
+                        RegexBuilder::new(value)
+                            .case_insensitive(true)
+                            .size_limit(REGEX_SIZE_LIMIT)
+                            .build()
+                            .map_err(|e| tr(Msg::RuleInvalid, e))?,
+                    ),
+                };
+                Ok((condition.field, test))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Compiled { rule, tests })
+    }
+
+    fn matches(&self, candidate: &Candidate) -> bool {
+        let holds = |(field, test): &(Field, Test)| {
+            let text = candidate.field(*field);
+            match test {
+                Test::Contains(value) => text.to_lowercase().contains(value.as_str()),
+                Test::NotContains(value) => !text.to_lowercase().contains(value.as_str()),
+                Test::Equals(value) => text.trim().to_lowercase() == *value,
+                Test::Regex(regex) => regex.is_match(text),
+            }
+        };
+        if self.rule.match_any {
+            self.tests.iter().any(holds)
+        } else {
+            !self.tests.is_empty() && self.tests.iter().all(holds)
+        }
+    }
+}
+
+// The fields conditions look at
+struct Candidate {
+    rowid: i64,
+    id: String,
+    title: String,
+    content: String,
+    source: String,
+    url: String,
+}
+
+impl Candidate {
+    const COLUMNS: &'static str = "rowid, id, title, COALESCE(summary, '') || '\n' || COALESCE(content, ''), source, url";
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Candidate {
+            rowid: row.get(0)?,
+            id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            source: row.get(4)?,
+            url: row.get(5)?,
+        })
+    }
+
+    fn field(&self, field: Field) -> &str {
+        match field {
+            Field::Title => &self.title,
+            Field::Content => &self.content,
+            Field::Source => &self.source,
+            Field::Url => &self.url,
+        }
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS rules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            match_any INTEGER NOT NULL DEFAULT 0,
+            conditions TEXT NOT NULL DEFAULT '[]',
+            actions TEXT NOT NULL DEFAULT '[]',
+            position INTEGER NOT NULL DEFAULT 0,
+            match_count INTEGER NOT NULL DEFAULT 0,
+            last_matched_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS rule_matches (
+            rule_id TEXT NOT NULL,
+            article_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            url TEXT NOT NULL,
+            actions TEXT NOT NULL DEFAULT '[]',
+            matched_at TEXT NOT NULL,
+            PRIMARY KEY (rule_id, article_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_rule_matches_article ON rule_matches(article_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+// Rules in evaluation order
+pub fn list(conn: &Connection) -> Result<Vec<Rule>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, enabled, match_any, conditions, actions, position, match_count, last_matched_at
+             FROM rules ORDER BY position ASC, name COLLATE NOCASE ASC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rules = stmt
+        .query_map([], |row| {
+            Ok(Rule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                enabled: row.get::<_, i32>(2)? == 1,
+                match_any: row.get::<_, i32>(3)? == 1,
+                conditions: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                actions: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+                position: row.get(6)?,
+                match_count: row.get(7)?,
+                last_matched_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(rules)
+}
+
+// Trimmed values, empty names / tags / categories rejected, boosts bounded;
+// regexes must compile
+fn normalized(mut rule: Rule) -> Result<Rule, String> {
+    rule.name = rule.name.trim().to_string();
+    if rule.name.is_empty() {
+        return Err(t(Msg::RuleNameMissing));
+    }
+    if rule.conditions.is_empty() || rule.actions.is_empty() {
+        return Err(t(Msg::RuleInvalid));
+    }
+    for condition in &mut rule.conditions {
+        condition.value = condition.value.trim().to_string();
+    }
+    for action in &mut rule.actions {
+        match action {
+            Action::SetCategory { category: value } | Action::AddTag { tag: value } => {
+                *value = value.trim().to_string();
+                if value.is_empty() {
+                    return Err(t(Msg::RuleInvalid));
+                }
+            }
+            Action::BoostHeat { points } => {
+                if !points.is_finite() {
+                    return Err(t(Msg::RuleInvalid));
+                }
+                *points = points.clamp(-RULE_BOOST_LIMIT, RULE_BOOST_LIMIT);
+            }
+            Action::MarkRead | Action::Delete | Action::Notify => {}
+        }
+    }
+    Compiled::new(&rule)?;
+    Ok(rule)
+}
+
+// Insert when `id` is empty, otherwise update the existing rule; match counts are kept
+pub fn save(conn: &Connection, rule: Rule) -> Result<Rule, String> {
+    let mut rule = normalized(rule)?;
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+        rule.position = conn
+            .query_row("SELECT COALESCE(MAX(position), 0) + 1 FROM rules", [], |row| row.get(0))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    }
+    conn.execute(
+        "INSERT INTO rules (id, name, enabled, match_any, conditions, actions, position)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, enabled = excluded.enabled,
+             match_any = excluded.match_any, conditions = excluded.conditions, actions = excluded.actions,
+             position = excluded.position",
+        params![
+            rule.id,
+            rule.name,
+            rule.enabled as i32,
+            rule.match_any as i32,
+            serde_json::to_string(&rule.conditions).unwrap_or_else(|_| "[]".to_string()),
+            serde_json::to_string(&rule.actions).unwrap_or_else(|_| "[]".to_string()),
+            rule.position,
+        ],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    list(conn)?
+        .into_iter()
+        .find(|saved| saved.id == rule.id)
+        .ok_or_else(|| t(Msg::RuleInvalid))
+}
+
+// Changes rules already made to articles stay
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM rule_matches WHERE rule_id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    conn.execute("DELETE FROM rules WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Matches of articles no longer stored or in the trash
+pub fn delete_orphans(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM rule_matches WHERE article_id NOT IN (SELECT id FROM articles UNION ALL SELECT id FROM articles_trash)",
+        [],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// The articles a rule matched, most recent first
+pub fn matches(conn: &Connection, id: &str) -> Result<Vec<RuleMatch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.article_id, m.title, m.url, m.matched_at, m.actions,
+                NOT EXISTS (SELECT 1 FROM articles a WHERE a.id = m.article_id)
+             FROM rule_matches m WHERE m.rule_id = ?1
+             ORDER BY m.matched_at DESC LIMIT ?2",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let matches = stmt
+        .query_map(params![id, HISTORY_LIMIT], |row| {
+            Ok(RuleMatch {
+                article_id: row.get(0)?,
+                title: row.get(1)?,
+                url: row.get(2)?,
+                matched_at: datetime::to_display(&row.get::<_, String>(3)?),
+                actions: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                in_trash: row.get(5)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(matches)
+}
+
+fn run_action(conn: &Connection, action: &Action, candidate: &Candidate) -> Result<(), String> {
+    let update = |sql: &str, value: &dyn rusqlite::ToSql| {
+        conn.execute(sql, params![value, candidate.id])
+            .map(|_| ())
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))
+    };
+    match action {
+        Action::SetCategory { category } => update("UPDATE articles SET category = ?1 WHERE id = ?2", category),
+        Action::AddTag { tag } => annotations::add_tag(conn, &candidate.id, tag),
+        Action::MarkRead => update("UPDATE articles SET is_read = ?1 WHERE id = ?2", &1),
+        Action::BoostHeat { points } => update("UPDATE articles SET rule_boost = rule_boost + ?1 WHERE id = ?2", points),
+        // `apply` handles these: they end the evaluation or wait for it to end
+        Action::Delete | Action::Notify => Ok(()),
+    }
+}
+
+// Run the enabled rules on newly crawled articles
+pub fn apply(conn: &Connection, article_ids: &[String]) -> Result<Applied, String> {
+    let mut applied = Applied::default();
+    if article_ids.is_empty() {
+        return Ok(applied);
+    }
+    let rules: Vec<Rule> = list(conn)?.into_iter().filter(|rule| rule.enabled).collect();
+    if rules.is_empty() {
+        return Ok(applied);
+    }
+    // Saved rules compiled before; one that no longer does (e.g. a newer regex syntax) is skipped
+    let active: Vec<Compiled> = rules.iter().filter_map(|rule| Compiled::new(rule).ok()).collect();
+
+    let sql = format!("SELECT {} FROM articles WHERE id = ?1", Candidate::COLUMNS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut notify: Vec<(usize, String)> = Vec::new();
+    for id in article_ids {
+        let Some(candidate) = stmt
+            .query_row(params![id], Candidate::from_row)
+            .optional()
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        else {
+            continue;
+        };
+        for (index, compiled) in active.iter().enumerate() {
+            if !compiled.matches(&candidate) {
+                continue;
+            }
+            conn.execute(
+                "UPDATE rules SET match_count = match_count + 1, last_matched_at = ?1 WHERE id = ?2",
+                params![now, compiled.rule.id],
+            )
+            .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+            conn.execute(
+                "INSERT OR REPLACE INTO rule_matches (rule_id, article_id, title, url, actions, matched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    compiled.rule.id,
+                    candidate.id,
+                    candidate.title,
+                    candidate.url,
+                    serde_json::to_string(&compiled.rule.actions).unwrap_or_else(|_| "[]".to_string()),
+                    now,
+                ],
+            )
+            .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+            for action in &compiled.rule.actions {
+                run_action(conn, action, &candidate)?;
+            }
+            if compiled.rule.actions.contains(&Action::Notify) {
+                notify.push((index, candidate.id.clone()));
+            }
+            if compiled.rule.actions.contains(&Action::Delete) {
+                trash::move_articles(conn, "SELECT rowid FROM articles WHERE rowid = ?1", candidate.rowid)?;
+                applied.deleted.push(candidate.id.clone());
+                break;
+            }
+        }
+    }
+
+    for (index, compiled) in active.iter().enumerate() {
+        let articles: Vec<Article> = notify
+            .iter()
+            .filter(|(rule, id)| *rule == index && !applied.deleted.contains(id))
+            .filter_map(|(_, id)| query_article(conn, id).ok().flatten())
+            .collect();
+        if !articles.is_empty() {
+            applied.events.push(RuleMatchEvent {
+                rule_id: compiled.rule.id.clone(),
+                name: compiled.rule.name.clone(),
+                articles,
+            });
+        }
+    }
+    Ok(applied)
+}
+
+// Dry run: the most recently fetched articles (up to `limit`) `rule` would match, newest first
+pub fn test(conn: &Connection, rule: &Rule, limit: usize) -> Result<Vec<Article>, String> {
+    let compiled = Compiled::new(rule)?;
+    let sql = format!(
+        "SELECT {} FROM articles ORDER BY fetched_at DESC LIMIT ?1",
+        Candidate::COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let matching: Vec<String> = stmt
+        .query_map(params![limit.clamp(1, TEST_LIMIT_MAX) as i64], Candidate::from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?
+        .into_iter()
+        .filter(|candidate| compiled.matches(candidate))
+        .map(|candidate| candidate.id)
+        .collect();
+    Ok(query_articles_by_ids(conn, &matching)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(json: &str) -> Rule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn add_article(conn: &Connection, id: &str, title: &str, url: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, '', '', ?3, 'Blog', 'AI', ?4, ?4)",
+            params![id, title, url, chrono::Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+    }
+
+    fn candidate(title: &str, url: &str) -> Candidate {
+        Candidate {
+            rowid: 0,
+            id: String::new(),
+            title: title.to_string(),
+            content: String::new(),
+            source: "Blog".to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn rules_parse_with_defaults_and_are_validated() {
+        let parsed = rule(
+            r#"{"name": " Sponsored ", "conditions": [{"field": "title", "operator": "regex", "value": "^sponsored:"}],
+                "actions": [{"type": "boost_heat", "points": -1e9}, {"type": "add_tag", "tag": " ads "}]}"#,
+        );
+        assert!(parsed.enabled && !parsed.match_any && parsed.id.is_empty());
+        let saved = normalized(parsed.clone()).unwrap();
+        assert_eq!(saved.name, "Sponsored");
+        assert_eq!(
+            saved.actions,
+            [Action::BoostHeat { points: -RULE_BOOST_LIMIT }, Action::AddTag { tag: "ads".to_string() }]
+        );
+
+        let mut unnamed = parsed.clone();
+        unnamed.name = "  ".to_string();
+        assert!(normalized(unnamed).is_err());
+        let mut bad_regex = parsed.clone();
+        bad_regex.conditions[0].value = "(unclosed".to_string();
+        assert!(normalized(bad_regex).is_err());
+        let mut no_actions = parsed;
+        no_actions.actions.clear();
+        assert!(normalized(no_actions).is_err());
+    }
+
+    #[test]
+    fn conditions_combine_with_all_or_any() {
+        let mut both = rule(
+            r#"{"name": "r", "actions": [{"type": "mark_read"}], "conditions": [
+                {"field": "title", "operator": "contains", "value": "GPT"},
+                {"field": "url", "operator": "not_contains", "value": "/sponsored/"}]}"#,
+        );
+        let compiled = Compiled::new(&both).unwrap();
+        assert!(compiled.matches(&candidate("New gpt release", "https://example.com/news/1")));
+        assert!(!compiled.matches(&candidate("New GPT release", "https://example.com/sponsored/1")));
+
+        both.match_any = true;
+        let compiled = Compiled::new(&both).unwrap();
+        assert!(compiled.matches(&candidate("Weather", "https://example.com/news/1")));
+        assert!(!compiled.matches(&candidate("Weather", "https://example.com/sponsored/1")));
+
+        let equals = rule(
+            r#"{"name": "r", "actions": [{"type": "notify"}], "conditions": [{"field": "source", "operator": "equals", "value": "blog"}]}"#,
+        );
+        assert!(Compiled::new(&equals).unwrap().matches(&candidate("Anything", "https://example.com")));
+    }
+
+    #[test]
+    fn apply_runs_actions_and_records_matches() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "a1", "Sponsored: buy our GPUs", "https://example.com/1");
+        add_article(&conn, "a2", "GPT-5 benchmarks", "https://example.com/2");
+        let hide = save(
+            &conn,
+            rule(r#"{"name": "Hide ads", "conditions": [{"field": "title", "operator": "regex", "value": "^sponsored"}],
+                     "actions": [{"type": "delete"}, {"type": "notify"}]}"#),
+        )
+        .unwrap();
+        save(
+            &conn,
+            rule(r#"{"name": "Models", "conditions": [{"field": "title", "operator": "contains", "value": "gpt"}],
+                     "actions": [{"type": "set_category", "category": "Models"}, {"type": "mark_read"}, {"type": "notify"}]}"#),
+        )
+        .unwrap();
+
+        let dry_run: Vec<String> = test(&conn, &hide, TEST_LIMIT_DEFAULT).unwrap().into_iter().map(|a| a.id).collect();
+        assert_eq!(dry_run, ["a1"]);
+        assert_eq!(list(&conn).unwrap()[0].match_count, 0, "a dry run records nothing");
+
+        let applied = apply(&conn, &["a1".to_string(), "a2".to_string()]).unwrap();
+        assert_eq!(applied.deleted, ["a1"]);
+        assert_eq!(applied.events.len(), 1, "deleted articles aren't notified");
+        assert_eq!(applied.events[0].name, "Models");
+        let (category, is_read): (String, i32) = conn
+            .query_row("SELECT category, is_read FROM articles WHERE id = 'a2'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((category.as_str(), is_read), ("Models", 1));
+
+        let history = matches(&conn, &hide.id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].in_trash);
+        assert_eq!(list(&conn).unwrap()[0].match_count, 1);
+    }
+
+    #[test]
+    fn match_history_keeps_titles_as_matched_and_goes_with_its_article() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "a1", "Sponsored: GPUs", "https://example.com/1");
+        add_article(&conn, "a2", "Sponsored: chairs", "https://example.com/2");
+        let hide = save(
+            &conn,
+            rule(r#"{"name": "Hide ads", "conditions": [{"field": "title", "operator": "contains", "value": "sponsored"}],
+                     "actions": [{"type": "delete"}]}"#),
+        )
+        .unwrap();
+        apply(&conn, &["a1".to_string(), "a2".to_string()]).unwrap();
+        assert_eq!(matches(&conn, &hide.id).unwrap().len(), 2);
+
+        // Gone from the trash: its match goes with it, the other stays as recorded
+        conn.execute("DELETE FROM articles_trash WHERE id = 'a1'", []).unwrap();
+        delete_orphans(&conn).unwrap();
+        let history = matches(&conn, &hide.id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].article_id.as_str(), history[0].title.as_str()), ("a2", "Sponsored: chairs"));
+        assert_eq!(history[0].actions, [Action::Delete]);
+
+        delete(&conn, &hide.id).unwrap();
+        assert!(matches(&conn, &hide.id).unwrap().is_empty(), "a deleted rule leaves no history");
+        assert!(matches(&conn, "unknown").unwrap().is_empty());
+    }
+}
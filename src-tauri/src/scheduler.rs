@@ -0,0 +1,347 @@
+// Background job loop for periodic work (crawls, cloud sync, digest email,
+// star report, offline queue replay, ...).
+//
+// Every TICK the loop asks each job whether it is enabled and how often it
+// should run, and runs it when that interval has passed since the last run
+// recorded in `scheduler_runs`. Run times live in the database so restarts
+// don't reset the schedule. While offline only local work runs and the loop
+// ticks every OFFLINE_TICK; jobs that came due meanwhile aren't recorded, so
+// they run on the first tick after the network is back.
+//
+// The wait between ticks is slept in WAKE_CHECK slices. A slice that took
+// far longer by the wall clock than it slept means the machine was suspended;
+// the loop then ticks right away instead of finishing the wait, emitting
+// `app://scheduler:resumed` first, so a crawl whose interval passed during
+// the sleep runs as soon as the machine is back (and online). Only the wall
+// clock tells, so any forward jump of MIN_ASLEEP or more counts as a suspend
+// too: an NTP step or the clock being set by hand also ticks early.
+
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::email::{self, DigestSchedule};
+use crate::platform::CrawlProfile;
+use crate::star_report::{self, StarReportConfig};
+use crate::{cleanup_articles, cloud_sync, db_recovery, features, get_setting, heat, jobs, keywords, network, night_batch, read_only, run_crawl, shutdown, unmute_expired_sources, wake_snoozed_articles, DbState, HttpClients};
+
+const TICK: Duration = Duration::from_secs(60);
+const OFFLINE_TICK: Duration = Duration::from_secs(15);
+const WAKE_CHECK: Duration = Duration::from_secs(15);
+// Wall-clock time beyond a slice that counts as the machine having been asleep
+const MIN_ASLEEP: Duration = Duration::from_secs(120);
+
+pub const JOB_CRAWL: &str = "crawl";
+pub const JOB_CLOUD_SYNC: &str = "cloud_sync";
+pub const JOB_DIGEST_EMAIL: &str = "digest_email";
+pub const JOB_STAR_REPORT: &str = "star_report";
+pub const JOB_HEAT_RESCORE: &str = "heat_rescore";
+pub const JOB_CLEANUP: &str = "cleanup";
+pub const JOB_KEYWORD_BACKFILL: &str = "keyword_backfill";
+pub const JOB_NIGHT_BATCH: &str = "night_batch";
+pub const JOB_BACKUP: &str = "backup";
+
+// Recency decays continuously, so recent heat scores are refreshed this often
+const HEAT_RESCORE_INTERVAL: Duration = Duration::from_secs(3600);
+// Articles stored before keyword extraction are tagged a batch at a time
+const KEYWORD_BACKFILL_INTERVAL: Duration = Duration::from_secs(300);
+// Deferred crawl steps are worked off a batch at a time while the window is open
+const NIGHT_BATCH_INTERVAL: Duration = Duration::from_secs(120);
+// Local copy of the database to recover from if it gets damaged
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduler_runs (
+            job TEXT PRIMARY KEY,
+            last_run_at TEXT NOT NULL,
+            last_error TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResumedEvent {
+    asleep_minutes: u64,
+    // A scheduled crawl came due while asleep and runs now (once online)
+    catch_up_crawl: bool,
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let asleep = wait(if network::is_known_offline() { OFFLINE_TICK } else { TICK }).await;
+            if shutdown::requested() {
+                break;
+            }
+            tick(&app, asleep).await;
+        }
+    });
+}
+
+// Sleep for `duration`, cut short when the machine turns out to have been suspended; how long it was, then
+async fn wait(duration: Duration) -> Option<Duration> {
+    wait_in_slices(duration, WAKE_CHECK, chrono::Utc::now).await
+}
+
+// `wait` with the slice length and the wall clock passed in
+pub(crate) async fn wait_in_slices(
+    duration: Duration,
+    wake_check: Duration,
+    now: impl Fn() -> chrono::DateTime<chrono::Utc>,
+) -> Option<Duration> {
+    let mut left = duration;
+    while !left.is_zero() {
+        let slice = left.min(wake_check);
+        let before = now();
+        tokio::time::sleep(slice).await;
+        let asleep = (now() - before).to_std().unwrap_or_default().saturating_sub(slice);
+        if asleep >= MIN_ASLEEP {
+            return Some(asleep);
+        }
+        left -= slice;
+    }
+    None
+}
+
+// Minutes between scheduled crawls; 0 = off. On mobile no more often than the platform's floor, to spare the battery
+pub(crate) fn crawl_interval_minutes(conn: &Connection, profile: &CrawlProfile) -> u64 {
+    let interval: u64 = get_setting(conn, "crawl_interval_minutes", "0").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+    if interval > 0 { interval.max(profile.min_crawl_interval_minutes) } else { 0 }
+}
+
+fn is_crawl_due(conn: &Connection, profile: &CrawlProfile) -> bool {
+    let interval = crawl_interval_minutes(conn, profile);
+    interval > 0 && is_due(conn, JOB_CRAWL, Duration::from_secs(interval * 60))
+}
+
+async fn tick(app: &AppHandle, asleep: Option<Duration>) {
+    // Nothing to do against a database another instance writes to, or with the scheduler switched off
+    if read_only::is_enabled() || !features::is_enabled(features::SCHEDULER) {
+        return;
+    }
+    let state = app.state::<DbState>();
+    let http = app.state::<HttpClients>();
+
+    if let Some(asleep) = asleep {
+        let catch_up_crawl = state.conn.lock().is_ok_and(|conn| is_crawl_due(&conn, http.profile));
+        let asleep_minutes = asleep.as_secs() / 60;
+        eprintln!("Resumed after {} min asleep{}", asleep_minutes, if catch_up_crawl { ", catching up on the crawl" } else { "" });
+        let _ = app.emit("app://scheduler:resumed", ResumedEvent { asleep_minutes, catch_up_crawl });
+    }
+
+    if let Ok(conn) = state.conn.lock() {
+        if let Err(e) = unmute_expired_sources(&conn) {
+            eprintln!("Failed to unmute sources: {}", e);
+        }
+        match wake_snoozed_articles(&conn) {
+            Ok(event) if !event.articles.is_empty() => {
+                let _ = app.emit("app://articles:unsnoozed", event);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to wake snoozed articles: {}", e),
+        }
+        if is_due(&conn, JOB_HEAT_RESCORE, HEAT_RESCORE_INTERVAL) {
+            let result = heat::rescore_recent(&conn, heat::RESCORE_DAYS).map(|_| ());
+            if let Err(e) = &result {
+                eprintln!("Failed to rescore articles: {}", e);
+            }
+            record_run(&conn, JOB_HEAT_RESCORE, result.err().as_deref());
+        }
+        if is_due(&conn, JOB_KEYWORD_BACKFILL, KEYWORD_BACKFILL_INTERVAL) {
+            let result = keywords::backfill(&conn).map(|_| ());
+            if let Err(e) = &result {
+                eprintln!("Failed to extract keywords: {}", e);
+            }
+            record_run(&conn, JOB_KEYWORD_BACKFILL, result.err().as_deref());
+        }
+        let cleanup_hours: u64 = get_setting(&conn, "cleanup_interval_hours", "6").ok().and_then(|v| v.parse().ok()).unwrap_or(6);
+        if cleanup_hours > 0 && is_due(&conn, JOB_CLEANUP, Duration::from_secs(cleanup_hours * 3600)) {
+            let result = cleanup_articles(&conn);
+            match &result {
+                Ok(cleanup) => {
+                    let _ = app.emit("app://cleanup:complete", cleanup);
+                }
+                Err(e) => eprintln!("Scheduled cleanup failed: {}", e),
+            }
+            record_run(&conn, JOB_CLEANUP, result.err().map(|e| e.to_string()).as_deref());
+        }
+        if is_due(&conn, JOB_BACKUP, BACKUP_INTERVAL) {
+            let result = db_recovery::backup(&conn);
+            if let Err(e) = &result {
+                eprintln!("Failed to back up database: {}", e);
+            }
+            record_run(&conn, JOB_BACKUP, result.err().as_deref());
+        }
+    }
+
+    // Everything below needs the network
+    if !network::refresh(app, &http).await.online {
+        return;
+    }
+
+    let jobs_pending = state.conn.lock().is_ok_and(|conn| jobs::has_pending(&conn));
+    if jobs_pending {
+        match jobs::replay(&state.conn, &http).await {
+            Ok(report) => {
+                let _ = app.emit("app://jobs:replayed", report);
+            }
+            Err(e) => eprintln!("Failed to replay queued jobs: {}", e),
+        }
+    }
+
+    let crawl_due = {
+        let Ok(conn) = state.conn.lock() else { return };
+        is_crawl_due(&conn, http.profile)
+    };
+    if crawl_due {
+        let result = run_crawl(app, &state.conn, &http).await;
+        match &result {
+            Ok(crawl) => {
+                let _ = app.emit("app://crawl:complete", crawl);
+            }
+            Err(e) => eprintln!("Scheduled crawl failed: {}", e),
+        }
+        if let Ok(conn) = state.conn.lock() {
+            record_run(&conn, JOB_CRAWL, result.err().map(|e| e.to_string()).as_deref());
+        }
+    }
+
+    let night_batch_due = {
+        let Ok(conn) = state.conn.lock() else { return };
+        night_batch::has_queued(&conn)
+            && is_due(&conn, JOB_NIGHT_BATCH, NIGHT_BATCH_INTERVAL)
+            && night_batch::may_run(&conn)
+    };
+    if night_batch_due {
+        let result = night_batch::run(&state.conn, &http).await.map(|_| ());
+        if let Err(e) = &result {
+            eprintln!("Night batch failed: {}", e);
+        }
+        if let Ok(conn) = state.conn.lock() {
+            record_run(&conn, JOB_NIGHT_BATCH, result.err().as_deref());
+        }
+    }
+
+    let cloud_sync_due = {
+        let Ok(conn) = state.conn.lock() else { return };
+        cloud_sync::CloudSyncConfig::load(&conn)
+            .ok()
+            .filter(|c| c.interval_minutes > 0 && c.is_configured())
+            .is_some_and(|c| is_due(&conn, JOB_CLOUD_SYNC, Duration::from_secs(c.interval_minutes as u64 * 60)))
+    };
+    if cloud_sync_due {
+        let result = cloud_sync::sync_now(&state.conn, &http).await;
+        if let Err(e) = &result {
+            eprintln!("Scheduled cloud sync failed: {}", e);
+        }
+        if let Ok(conn) = state.conn.lock() {
+            record_run(&conn, JOB_CLOUD_SYNC, result.err().as_deref());
+        }
+    }
+
+    let digest_days = {
+        let Ok(conn) = state.conn.lock() else { return };
+        email::SmtpConfig::load(&conn)
+            .ok()
+            .filter(|c| c.digest_schedule != DigestSchedule::Off && c.is_configured())
+            .filter(|c| is_due_at_hour(&conn, JOB_DIGEST_EMAIL, c.digest_hour, c.digest_schedule.days()))
+            .map(|c| c.digest_schedule.days())
+    };
+    if let Some(days) = digest_days {
+        let result = email::send_digest(&state.conn, days).await.map(|_| ());
+        if let Err(e) = &result {
+            eprintln!("Scheduled digest email failed: {}", e);
+        }
+        if let Ok(conn) = state.conn.lock() {
+            record_run(&conn, JOB_DIGEST_EMAIL, result.err().as_deref());
+        }
+    }
+
+    let star_report_due = {
+        let Ok(conn) = state.conn.lock() else { return };
+        StarReportConfig::load(&conn)
+            .ok()
+            .filter(|c| c.enabled)
+            .is_some_and(|c| is_due(&conn, JOB_STAR_REPORT, Duration::from_secs(c.days as u64 * 24 * 3600)))
+    };
+    if star_report_due {
+        let result = star_report::run_scheduled(&state.conn).await;
+        if let Err(e) = &result {
+            eprintln!("Scheduled star report failed: {}", e);
+        }
+        if let Ok(conn) = state.conn.lock() {
+            record_run(&conn, JOB_STAR_REPORT, result.err().as_deref());
+        }
+    }
+}
+
+fn last_run(conn: &Connection, job: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    conn.query_row("SELECT last_run_at FROM scheduler_runs WHERE job = ?1", params![job], |row| row.get::<_, String>(0))
+        .optional()
+        .unwrap_or(None)
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+}
+
+fn is_due(conn: &Connection, job: &str, interval: Duration) -> bool {
+    let Some(last_run) = last_run(conn, job) else {
+        return true;
+    };
+    let elapsed = chrono::Utc::now().signed_duration_since(last_run);
+    elapsed.to_std().map(|e| e >= interval).unwrap_or(false)
+}
+
+// Calendar-style jobs: due once the local clock passes `hour`, at most once every `every_days` days
+fn is_due_at_hour(conn: &Connection, job: &str, hour: u32, every_days: i64) -> bool {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    if now.hour() < hour {
+        return false;
+    }
+    match last_run(conn, job) {
+        Some(last_run) => {
+            let last_date = last_run.with_timezone(&chrono::Local).date_naive();
+            (now.date_naive() - last_date).num_days() >= every_days
+        }
+        None => true,
+    }
+}
+
+// Manual runs record too, so the next scheduled run is counted from them
+pub fn record_run(conn: &Connection, job: &str, error: Option<&str>) {
+    let _ = conn.execute(
+        "INSERT OR REPLACE INTO scheduler_runs (job, last_run_at, last_error) VALUES (?1, ?2, ?3)",
+        params![job, chrono::Utc::now().to_rfc3339(), error],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::profile_for;
+
+    #[test]
+    fn a_crawl_is_due_once_its_interval_passed_since_the_last_run() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let desktop = profile_for(false);
+        crate::set_setting(&conn, "crawl_interval_minutes", "0").unwrap();
+        assert_eq!(crawl_interval_minutes(&conn, desktop), 0);
+        assert!(!is_crawl_due(&conn, desktop), "scheduled crawls are off");
+
+        crate::set_setting(&conn, "crawl_interval_minutes", "15").unwrap();
+        assert_eq!(crawl_interval_minutes(&conn, desktop), 15);
+        assert_eq!(crawl_interval_minutes(&conn, profile_for(true)), 60, "mobile floor");
+        assert!(is_crawl_due(&conn, desktop), "never ran");
+
+        record_run(&conn, JOB_CRAWL, None);
+        assert!(!is_crawl_due(&conn, desktop));
+        // As after waking from a suspend that outlasted the interval
+        let before_sleep = (chrono::Utc::now() - chrono::Duration::minutes(90)).to_rfc3339();
+        conn.execute("UPDATE scheduler_runs SET last_run_at = ?1 WHERE job = ?2", params![before_sleep, JOB_CRAWL]).unwrap();
+        assert!(is_crawl_due(&conn, desktop));
+    }
+}
@@ -0,0 +1,172 @@
+// CSS selector rules for WEB sources.
+//
+// Without rules a WEB source is scraped generically (its first absolute
+// links). A source can instead carry rules, stored as JSON in
+// `sources.scrape_rules` and shipped in source packs: `item` selects one
+// element per article, and the optional `title`, `link`, `summary` and
+// `date` selectors are matched inside it. Relative links are resolved
+// against the page URL.
+
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, Msg};
+
+// Items taken per page, like the other fetchers
+const MAX_ITEMS: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrapeRules {
+    pub item: String,
+    // Default: the item's own text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    // Default: the item itself if it is a link, else its first link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    // Read from the `datetime` attribute, else the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+pub struct ScrapedItem {
+    pub title: String,
+    pub url: String,
+    pub summary: Option<String>,
+    pub date: Option<String>,
+}
+
+struct Compiled {
+    item: Selector,
+    title: Option<Selector>,
+    link: Selector,
+    summary: Option<Selector>,
+    date: Option<Selector>,
+}
+
+fn compile(selector: &str) -> Result<Selector, String> {
+    Selector::parse(selector).map_err(|e| tr(Msg::InvalidSelector, format!("{}: {}", selector, e)))
+}
+
+impl ScrapeRules {
+    fn compile(&self) -> Result<Compiled, String> {
+        Ok(Compiled {
+            item: compile(&self.item)?,
+            title: self.title.as_deref().map(compile).transpose()?,
+            link: compile(self.link.as_deref().unwrap_or("a[href]"))?,
+            summary: self.summary.as_deref().map(compile).transpose()?,
+            date: self.date.as_deref().map(compile).transpose()?,
+        })
+    }
+
+    // Every selector parses
+    pub fn validate(&self) -> Result<(), String> {
+        self.compile().map(|_| ())
+    }
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first<'a>(item: ElementRef<'a>, selector: &Selector) -> Option<ElementRef<'a>> {
+    item.select(selector).next()
+}
+
+pub fn extract(html: &str, page_url: &str, rules: &ScrapeRules) -> Result<Vec<ScrapedItem>, String> {
+    let compiled = rules.compile()?;
+    let base = reqwest::Url::parse(page_url).ok();
+    let document = Html::parse_document(html);
+
+    let mut items = Vec::new();
+    for item in document.select(&compiled.item) {
+        let link = if item.value().name() == "a" && rules.link.is_none() {
+            Some(item)
+        } else {
+            first(item, &compiled.link)
+        };
+        let Some(href) = link.and_then(|a| a.value().attr("href")) else { continue };
+        let url = match &base {
+            Some(base) => match base.join(href.trim()) {
+                Ok(url) => url.to_string(),
+                Err(_) => continue,
+            },
+            None => href.trim().to_string(),
+        };
+        if !url.starts_with("http") {
+            continue;
+        }
+        let title = match &compiled.title {
+            Some(selector) => first(item, selector).map(text).unwrap_or_default(),
+            None => text(item),
+        };
+        if title.is_empty() {
+            continue;
+        }
+        let summary = compiled.summary.as_ref().and_then(|s| first(item, s)).map(text).filter(|s| !s.is_empty());
+        let date = compiled
+            .date
+            .as_ref()
+            .and_then(|s| first(item, s))
+            .map(|el| el.value().attr("datetime").map(str::to_string).unwrap_or_else(|| text(el)))
+            .filter(|d| !d.is_empty());
+        items.push(ScrapedItem { title, url, summary, date });
+        if items.len() >= MAX_ITEMS {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><body>
+        <article><h2>Scaling laws revisited</h2><a href="/blog/scaling">Read</a>
+            <p class="lede">Bigger isn't always better.</p><time datetime="2025-03-01">March 1</time></article>
+        <article><h2> </h2><a href="/blog/untitled">Read</a></article>
+        <article><h2>No link here</h2></article>
+        <article><h2>Mail us</h2><a href="mailto:lab@example.com">Mail</a></article>
+        <article><h2>Tokenizers</h2><a href="https://cdn.example.org/tok">Read</a><time>yesterday</time></article>
+    </body></html>"#;
+
+    fn rules(item: &str) -> ScrapeRules {
+        ScrapeRules {
+            item: item.to_string(),
+            title: Some("h2".to_string()),
+            link: None,
+            summary: Some(".lede".to_string()),
+            date: Some("time".to_string()),
+        }
+    }
+
+    #[test]
+    fn items_resolve_links_and_skip_unusable_entries() {
+        let items = extract(PAGE, "https://lab.example.com/blog/", &rules("article")).unwrap();
+        let found: Vec<(&str, &str)> = items.iter().map(|i| (i.title.as_str(), i.url.as_str())).collect();
+        assert_eq!(
+            found,
+            [("Scaling laws revisited", "https://lab.example.com/blog/scaling"), ("Tokenizers", "https://cdn.example.org/tok")]
+        );
+        assert_eq!(items[0].summary.as_deref(), Some("Bigger isn't always better."));
+        assert_eq!(items[0].date.as_deref(), Some("2025-03-01"), "datetime attribute first");
+        assert_eq!(items[1].date.as_deref(), Some("yesterday"));
+        assert_eq!(items[1].summary, None);
+    }
+
+    #[test]
+    fn link_items_use_their_own_text_and_bad_selectors_are_rejected() {
+        let html = r#"<ul><li><a class="post" href="p/1"> First   post </a></li><li><a class="post" href="p/2">Second</a></li></ul>"#;
+        let rules = ScrapeRules { item: "a.post".into(), title: None, link: None, summary: None, date: None };
+        let items = extract(html, "https://example.com/news/", &rules).unwrap();
+        assert_eq!(items.iter().map(|i| i.title.as_str()).collect::<Vec<_>>(), ["First post", "Second"]);
+        assert_eq!(items[0].url, "https://example.com/news/p/1");
+
+        let broken = ScrapeRules { date: Some("time[".into()), ..rules };
+        assert!(broken.validate().is_err());
+        assert!(extract(html, "https://example.com/", &broken).is_err());
+    }
+}
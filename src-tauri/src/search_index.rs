@@ -0,0 +1,324 @@
+// Chinese-aware full-text indexing.
+//
+// FTS5's unicode61 tokenizer treats a run of Chinese characters as a single
+// token, so "智能" never matches an article about "人工智能". Text is
+// therefore segmented before it reaches `articles_fts`: every run of CJK
+// characters is replaced by its overlapping character bigrams ("人工 工智
+// 智能"), and searches are segmented the same way into phrase queries.
+// Writers go through the `cjk_segment()` SQL function registered on every
+// connection `init_db_at` opens.
+//
+// Databases indexed before segmentation need their existing rows
+// re-tokenized. `search_reindex` does that in the background, BATCH_SIZE
+// rows per transaction in rowid order, saving its cursor with each batch so
+// an interrupted run picks up where it stopped; the database lock is
+// released between batches. Until it completes, searches also try the old
+// unsegmented form.
+//
+// A search can be scoped to bookmarks, to annotated articles (a note or a
+// highlight) and / or to one source. The scope's articles are selected
+// first and only they are matched, so a small personal collection gets its
+// full result limit instead of what's left after the whole library.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::i18n::{tr, Msg};
+use crate::{get_setting, set_setting, DbState};
+
+const DONE_KEY: &str = "search.cjk_indexed";
+const CURSOR_KEY: &str = "search.reindex_cursor";
+const BATCH_SIZE: i64 = 200;
+// Pause between batches so commands waiting on the lock get in
+const BATCH_PAUSE: std::time::Duration = std::time::Duration::from_millis(20);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+struct ReindexComplete {
+    completed: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReindexStatus {
+    pub completed: bool,
+    pub running: bool,
+    // Articles re-tokenized so far, of all stored
+    pub done: i64,
+    pub total: i64,
+}
+
+// Which articles a search looks at; all of them by default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchScope {
+    pub bookmarks: bool,
+    // Articles with a note or at least one highlight
+    pub annotated: bool,
+    pub source: Option<String>,
+}
+
+impl SearchScope {
+    // SQL conditions on `articles` for the scope, `source = ?n` with n = `source_param`
+    pub fn conditions(&self, source_param: usize) -> Vec<String> {
+        let mut conditions = Vec::new();
+        if self.bookmarks {
+            conditions.push("is_bookmarked = 1".to_string());
+        }
+        if self.annotated {
+            conditions.push(
+                "(id IN (SELECT article_id FROM article_notes) OR id IN (SELECT article_id FROM article_highlights))"
+                    .to_string(),
+            );
+        }
+        if self.source().is_some() {
+            conditions.push(format!("source = ?{}", source_param));
+        }
+        conditions
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+}
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+// Text as stored in the index: CJK runs become space-separated bigrams
+pub fn segment(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() * 2);
+    for run in chars.chunk_by(|a, b| is_cjk(*a) == is_cjk(*b)) {
+        if !is_cjk(run[0]) {
+            out.extend(run);
+        } else if run.len() == 1 {
+            out.push(' ');
+            out.push(run[0]);
+            out.push(' ');
+        } else {
+            for pair in run.windows(2) {
+                out.push(' ');
+                out.extend(pair);
+            }
+            out.push(' ');
+        }
+    }
+    out
+}
+
+fn quote(phrase: &str) -> String {
+    format!("\"{}\"", phrase.replace('"', "\"\""))
+}
+
+// FTS5 MATCH expression for a search box entry: every word must match, as a
+// prefix for Latin words, as a bigram phrase for Chinese
+pub fn match_expression(conn: &Connection, keyword: &str) -> Result<String, String> {
+    let words: Vec<&str> = keyword.split_whitespace().collect();
+    let segmented: Vec<String> = words
+        .iter()
+        .map(|word| {
+            let tokens = segment(word);
+            let tokens = tokens.split_whitespace().collect::<Vec<_>>().join(" ");
+            // A lone character only occurs as the start of a bigram
+            if tokens.chars().count() == 1 || !word.chars().any(is_cjk) {
+                format!("{}*", quote(&tokens))
+            } else {
+                quote(&tokens)
+            }
+        })
+        .collect();
+    let segmented = segmented.join(" ");
+    if is_completed(conn)? {
+        return Ok(segmented);
+    }
+    // Rows not re-tokenized yet hold whole runs as single tokens
+    let legacy: Vec<String> = words.iter().map(|word| format!("{}*", quote(word))).collect();
+    Ok(format!("({}) OR ({})", segmented, legacy.join(" ")))
+}
+
+pub fn register(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.create_scalar_function(
+        "cjk_segment",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| segment(&text))),
+    )
+}
+
+// A database without articles has nothing to re-tokenize
+pub fn init(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value)
+         SELECT ?1, CASE WHEN EXISTS (SELECT 1 FROM articles) THEN 'false' ELSE 'true' END",
+        params![DONE_KEY],
+    )?;
+    Ok(())
+}
+
+fn is_completed(conn: &Connection) -> Result<bool, String> {
+    Ok(get_setting(conn, DONE_KEY, "false")? == "true")
+}
+
+pub fn status(conn: &Connection) -> Result<ReindexStatus, String> {
+    let cursor: i64 = get_setting(conn, CURSOR_KEY, "0")?.parse().unwrap_or(0);
+    let (done, total) = conn
+        .query_row(
+            "SELECT COALESCE(SUM(rowid <= ?1), 0), COUNT(*) FROM articles",
+            params![cursor],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    let completed = is_completed(conn)?;
+    Ok(ReindexStatus {
+        completed,
+        running: RUNNING.load(Ordering::Relaxed),
+        done: if completed { total } else { done },
+        total,
+    })
+}
+
+// Re-tokenize the next batch; false once past the last article
+fn reindex_batch(conn: &Connection) -> Result<bool, String> {
+    let cursor: i64 = get_setting(conn, CURSOR_KEY, "0")?.parse().unwrap_or(0);
+    let end: Option<i64> = conn
+        .query_row(
+            "SELECT MAX(rowid) FROM (SELECT rowid FROM articles WHERE rowid > ?1 ORDER BY rowid LIMIT ?2)",
+            params![cursor, BATCH_SIZE],
+            |row| row.get(0),
+        )
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    let tx = conn.unchecked_transaction().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    let Some(end) = end else {
+        set_setting(&tx, DONE_KEY, "true")?;
+        set_setting(&tx, CURSOR_KEY, "0")?;
+        tx.commit().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+        return Ok(false);
+    };
+    tx.execute("DELETE FROM articles_fts WHERE rowid > ?1 AND rowid <= ?2", params![cursor, end])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    tx.execute(
+        "INSERT INTO articles_fts (rowid, title, summary, content)
+         SELECT rowid, cjk_segment(title), cjk_segment(summary), cjk_segment(content)
+         FROM articles WHERE rowid > ?1 AND rowid <= ?2",
+        params![cursor, end],
+    )
+    .map_err(|e| tr(Msg::FtsInsertFailed, e))?;
+    set_setting(&tx, CURSOR_KEY, &end.to_string())?;
+    tx.commit().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(true)
+}
+
+// Start (or resume) re-tokenizing in the background; a no-op when done or already running
+pub fn start(app: AppHandle) -> Result<ReindexStatus, String> {
+    let state = app.state::<DbState>();
+    let current = {
+        let conn = state.conn.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        status(&conn)?
+    };
+    if current.completed || RUNNING.swap(true, Ordering::Relaxed) {
+        return Ok(current);
+    }
+
+    std::thread::spawn(move || {
+        let state = app.state::<DbState>();
+        let result = loop {
+            let step = state
+                .conn
+                .lock()
+                .map_err(|e| tr(Msg::DbLockPoisoned, e))
+                .and_then(|conn| reindex_batch(&conn).and_then(|more| Ok((more, status(&conn)?))));
+            match step {
+                Ok((true, progress)) => {
+                    let _ = app.emit("app://search-reindex:progress", progress);
+                    std::thread::sleep(BATCH_PAUSE);
+                }
+                Ok((false, _)) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        RUNNING.store(false, Ordering::Relaxed);
+        if let Err(e) = &result {
+            eprintln!("Search reindex stopped: {}", e);
+        }
+        let _ = app.emit("app://search-reindex:complete", ReindexComplete { completed: result.is_ok(), error: result.err() });
+    });
+    Ok(ReindexStatus { running: true, ..current })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(conn: &Connection, keyword: &str) -> i64 {
+        let expression = match_expression(conn, keyword).unwrap();
+        conn.query_row("SELECT COUNT(*) FROM articles_fts WHERE articles_fts MATCH ?1", params![expression], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn chinese_runs_become_bigrams() {
+        assert_eq!(segment("人工智能").split_whitespace().collect::<Vec<_>>(), ["人工", "工智", "智能"]);
+        assert_eq!(segment("GPT-5发布").split_whitespace().collect::<Vec<_>>(), ["GPT-5", "发布"]);
+        assert_eq!(segment("新 model").split_whitespace().collect::<Vec<_>>(), ["新", "model"]);
+        assert_eq!(segment("plain text"), "plain text");
+    }
+
+    #[test]
+    fn scope_conditions() {
+        assert!(SearchScope::default().conditions(2).is_empty());
+        let scope = SearchScope { bookmarks: true, annotated: false, source: Some("  ".into()) };
+        assert_eq!(scope.conditions(2), ["is_bookmarked = 1"]);
+        let scope = SearchScope { bookmarks: false, annotated: true, source: Some(" Blog ".into()) };
+        let conditions = scope.conditions(3);
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(conditions[1], "source = ?3");
+        assert_eq!(scope.source(), Some("Blog"));
+    }
+
+    #[test]
+    fn reindex_segments_rows_indexed_before_segmentation() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(status(&conn).unwrap().completed, "an empty database starts indexed");
+        for (id, title) in [("a1", "人工智能的新进展"), ("a2", "Open weights release")] {
+            conn.execute(
+                "INSERT INTO articles (id, title, url, source, summary, content) VALUES (?1, ?2, ?3, 'Blog', '', '')",
+                params![id, title, format!("https://example.com/{}", id)],
+            )
+            .unwrap();
+            // As written before segmentation existed
+            conn.execute(
+                "INSERT INTO articles_fts (rowid, title, summary, content)
+                 SELECT rowid, title, summary, content FROM articles WHERE id = ?1",
+                params![id],
+            )
+            .unwrap();
+        }
+        set_setting(&conn, DONE_KEY, "false").unwrap();
+
+        let before = status(&conn).unwrap();
+        assert!(!before.completed);
+        assert_eq!((before.done, before.total), (0, 2));
+        assert!(match_expression(&conn, "智能").unwrap().contains(" OR "), "legacy form tried too");
+        assert_eq!(matches(&conn, "智能"), 0);
+        assert_eq!(matches(&conn, "weig"), 1);
+
+        while reindex_batch(&conn).unwrap() {}
+        let after = status(&conn).unwrap();
+        assert!(after.completed);
+        assert_eq!((after.done, after.total), (2, 2));
+        assert!(!match_expression(&conn, "智能").unwrap().contains(" OR "));
+        assert_eq!(matches(&conn, "智能"), 1);
+        assert_eq!(matches(&conn, "智"), 1, "a lone character matches as a bigram prefix");
+        assert_eq!(matches(&conn, "智能 release"), 0, "every word must match");
+        assert_eq!(get_setting(&conn, CURSOR_KEY, "").unwrap(), "0");
+    }
+}
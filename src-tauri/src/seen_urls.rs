@@ -0,0 +1,106 @@
+// URLs the feed has let go of.
+//
+// Feeds keep listing an item long after cleanup trashed it, and the
+// crawler's dedup only looks at stored rows, so without a memory the item
+// would be inserted again on the next crawl. Every URL cleanup removes is
+// recorded here as a 64-bit hash with the time it was pruned; the crawler
+// skips recorded URLs. Entries expire after `seen_urls_retention_days`,
+// by which time feeds have long dropped the item. Restoring an article from
+// the trash forgets its URL.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use crate::i18n::{tr, Msg};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS seen_urls (
+            url_hash INTEGER PRIMARY KEY,
+            seen_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// First 8 bytes of the SHA-256 of the normalized URL
+fn url_hash(normalized_url: &str) -> i64 {
+    let digest = Sha256::digest(normalized_url.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_be_bytes(bytes)
+}
+
+pub fn record(conn: &Connection, normalized_urls: &[String]) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut stmt = conn
+        .prepare_cached("INSERT OR REPLACE INTO seen_urls (url_hash, seen_at) VALUES (?1, ?2)")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    for url in normalized_urls {
+        stmt.execute(params![url_hash(url), now]).map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    }
+    Ok(())
+}
+
+pub fn is_seen(conn: &Connection, normalized_url: &str) -> Result<bool, rusqlite::Error> {
+    conn.prepare_cached("SELECT 1 FROM seen_urls WHERE url_hash = ?1")?
+        .query_row(params![url_hash(normalized_url)], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+}
+
+pub fn forget(conn: &Connection, normalized_url: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM seen_urls WHERE url_hash = ?1", params![url_hash(normalized_url)])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Drop entries recorded more than `retention_days` ago (0 = keep them)
+pub fn purge_expired(conn: &Connection, retention_days: u32) -> Result<usize, String> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(retention_days as i64)).to_rfc3339();
+    conn.execute("DELETE FROM seen_urls WHERE seen_at < ?1", params![cutoff])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_urls_are_seen_until_forgotten_or_expired() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let urls = ["https://example.com/a1".to_string(), "https://example.com/a2".to_string()];
+        assert!(!is_seen(&conn, &urls[0]).unwrap());
+        record(&conn, &urls).unwrap();
+        record(&conn, &urls[..1]).unwrap();
+        assert!(is_seen(&conn, &urls[0]).unwrap() && is_seen(&conn, &urls[1]).unwrap());
+        assert!(!is_seen(&conn, "https://example.com/a3").unwrap());
+
+        forget(&conn, &urls[0]).unwrap();
+        assert!(!is_seen(&conn, &urls[0]).unwrap());
+
+        conn.execute("UPDATE seen_urls SET seen_at = '2000-01-01T00:00:00+00:00'", []).unwrap();
+        assert_eq!(purge_expired(&conn, 0).unwrap(), 0, "0 keeps them");
+        assert_eq!(purge_expired(&conn, 90).unwrap(), 1);
+        assert!(!is_seen(&conn, &urls[1]).unwrap());
+    }
+
+    #[test]
+    fn trashing_records_the_url_and_restoring_forgets_it() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES ('a1', 'One', '', '', 'https://example.com/a1', 'Blog', 'AI', '2025-03-01T12:00:00+00:00', '')",
+            [],
+        )
+        .unwrap();
+        crate::trash::move_articles(&conn, "SELECT rowid FROM articles LIMIT ?1", 10).unwrap();
+        assert!(is_seen(&conn, "https://example.com/a1").unwrap());
+        crate::trash::restore(&conn, "a1").unwrap();
+        assert!(!is_seen(&conn, "https://example.com/a1").unwrap());
+    }
+}
@@ -0,0 +1,291 @@
+// Shareable PNG card for an article: title, source/date, summary and a QR
+// code of the link, laid out on a fixed 1080x1440 canvas that chat apps
+// show without cropping.
+//
+// Text is rasterized with ab_glyph from a system font so Chinese titles
+// render; `card_font_path` in settings overrides the auto-detected font.
+
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use image::{ImageFormat, Rgba, RgbaImage};
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::i18n::{t, tr, Msg};
+use crate::Article;
+
+const WIDTH: u32 = 1080;
+const HEIGHT: u32 = 1440;
+const MARGIN: f32 = 72.0;
+const FOOTER_HEIGHT: u32 = 320;
+const QR_SIZE: u32 = 240;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const FOOTER_BACKGROUND: [u8; 3] = [244, 246, 250];
+const ACCENT: [u8; 3] = [26, 86, 219];
+const TITLE_COLOR: [u8; 3] = [17, 17, 17];
+const BODY_COLOR: [u8; 3] = [68, 68, 68];
+const MUTED_COLOR: [u8; 3] = [136, 136, 136];
+
+// Checked in order; the first that exists and parses is used
+const FONT_CANDIDATES: [&str; 14] = [
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-zenhei.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+pub(crate) fn load_font(configured: &str) -> Result<FontVec, String> {
+    let configured = configured.trim();
+    let candidates: Vec<&str> = if configured.is_empty() {
+        FONT_CANDIDATES.to_vec()
+    } else {
+        vec![configured]
+    };
+    candidates
+        .into_iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find_map(|data| FontVec::try_from_vec_and_index(data, 0).ok())
+        .ok_or_else(|| t(Msg::FontNotFound))
+}
+
+pub fn render_to_file(article: &Article, font_path: &str, out_path: &std::path::Path) -> Result<(), String> {
+    let font = load_font(font_path)?;
+    let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, rgba(BACKGROUND));
+    let content_width = WIDTH as f32 - MARGIN * 2.0;
+
+    fill_rect(&mut img, 0, 0, WIDTH, 16, ACCENT);
+
+    let date = article.published_at.get(..10).unwrap_or(&article.published_at);
+    let meta = format!("{} · {}", article.source, date);
+    draw_text(&mut img, &font, &ellipsize(&font, 30.0, &meta, content_width), MARGIN, 140.0, 30.0, MUTED_COLOR);
+
+    let mut baseline = 240.0;
+    for line in wrap(&font, 56.0, &article.title, content_width, 4) {
+        draw_text(&mut img, &font, &line, MARGIN, baseline, 56.0, TITLE_COLOR);
+        baseline += 74.0;
+    }
+
+    baseline += 4.0;
+    fill_rect(&mut img, MARGIN as u32, baseline as u32, 80, 6, ACCENT);
+    baseline += 76.0;
+
+    let summary_limit = (HEIGHT - FOOTER_HEIGHT) as f32 - 48.0;
+    let max_summary_lines = if baseline <= summary_limit {
+        ((summary_limit - baseline) / 52.0).floor() as usize + 1
+    } else {
+        0
+    };
+    for line in wrap(&font, 34.0, &article.summary, content_width, max_summary_lines) {
+        draw_text(&mut img, &font, &line, MARGIN, baseline, 34.0, BODY_COLOR);
+        baseline += 52.0;
+    }
+
+    // Footer: branding on the left, QR code on the right
+    let footer_top = HEIGHT - FOOTER_HEIGHT;
+    fill_rect(&mut img, 0, footer_top, WIDTH, FOOTER_HEIGHT, FOOTER_BACKGROUND);
+    let text_width = content_width - QR_SIZE as f32 - 40.0;
+    draw_text(&mut img, &font, "AI News Aggregator", MARGIN, (footer_top + 120) as f32, 38.0, TITLE_COLOR);
+    draw_text(&mut img, &font, &t(Msg::ShareCardScanHint), MARGIN, (footer_top + 176) as f32, 28.0, MUTED_COLOR);
+    let domain = reqwest::Url::parse(&article.url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_string()))
+        .unwrap_or_default();
+    draw_text(&mut img, &font, &ellipsize(&font, 26.0, &domain, text_width), MARGIN, (footer_top + 226) as f32, 26.0, MUTED_COLOR);
+
+    draw_qr(&mut img, &article.url, WIDTH - MARGIN as u32 - QR_SIZE, footer_top + (FOOTER_HEIGHT - QR_SIZE) / 2)?;
+
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| tr(Msg::CreateDirFailed, format!("{}: {}", parent.display(), e)))?;
+    }
+    img.save_with_format(out_path, ImageFormat::Png)
+        .map_err(|e| tr(Msg::WriteFileFailed, format!("{}: {}", out_path.display(), e)))
+}
+
+fn rgba(color: [u8; 3]) -> Rgba<u8> {
+    Rgba([color[0], color[1], color[2], 255])
+}
+
+fn fill_rect(img: &mut RgbaImage, x: u32, y: u32, w: u32, h: u32, color: [u8; 3]) {
+    for py in y..(y + h).min(img.height()) {
+        for px in x..(x + w).min(img.width()) {
+            img.put_pixel(px, py, rgba(color));
+        }
+    }
+}
+
+fn draw_qr(img: &mut RgbaImage, url: &str, x: u32, y: u32) -> Result<(), String> {
+    let code = QrCode::with_error_correction_level(url.as_bytes(), EcLevel::M)
+        .map_err(|e| tr(Msg::ShareCardRenderFailed, e))?;
+    let modules = code.width() as u32;
+    // Four-module quiet zone on each side, as scanners expect
+    let module_px = (QR_SIZE / (modules + 8)).max(1);
+    let total = module_px * (modules + 8);
+    let offset = (QR_SIZE.saturating_sub(total)) / 2;
+    fill_rect(img, x + offset, y + offset, total, total, BACKGROUND);
+
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let (mx, my) = (i as u32 % modules, i as u32 / modules);
+            fill_rect(
+                img,
+                x + offset + (mx + 4) * module_px,
+                y + offset + (my + 4) * module_px,
+                module_px,
+                module_px,
+                TITLE_COLOR,
+            );
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn draw_text(img: &mut RgbaImage, font: &FontVec, text: &str, x: f32, baseline: f32, size: f32, color: [u8; 3]) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(prev) = previous {
+            caret += scaled.kern(prev, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else { continue };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= img.width() as i32 || py >= img.height() as i32 {
+                return;
+            }
+            let alpha = coverage.clamp(0.0, 1.0);
+            let pixel = img.get_pixel_mut(px as u32, py as u32);
+            for (channel, target) in pixel.0.iter_mut().zip(color) {
+                *channel = (*channel as f32 * (1.0 - alpha) + target as f32 * alpha).round() as u8;
+            }
+        });
+    }
+}
+
+pub(crate) fn measure(font: &FontVec, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(prev) = previous {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Latin words stay whole; CJK and other characters can break anywhere
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || (c.is_ascii_punctuation() && !word.is_empty()) {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        tokens.push(if c.is_whitespace() { " ".to_string() } else { c.to_string() });
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+pub(crate) fn wrap(font: &FontVec, size: f32, text: &str, max_width: f32, max_lines: usize) -> Vec<String> {
+    if max_lines == 0 {
+        return Vec::new();
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut truncated = false;
+    for token in tokenize(text.trim()) {
+        let candidate = format!("{}{}", current, token);
+        if measure(font, size, &candidate) <= max_width || current.trim().is_empty() {
+            current = candidate;
+            continue;
+        }
+        lines.push(current.trim_end().to_string());
+        if lines.len() == max_lines {
+            truncated = true;
+            break;
+        }
+        current = token.trim_start().to_string();
+    }
+    if !truncated && !current.trim().is_empty() {
+        lines.push(current.trim_end().to_string());
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        truncated = true;
+    }
+    if truncated {
+        if let Some(last) = lines.last_mut() {
+            *last = ellipsize(font, size, &format!("{}…", last), max_width);
+        }
+    }
+    lines
+}
+
+// Shorten a single line to fit, ending with an ellipsis when cut
+fn ellipsize(font: &FontVec, size: f32, text: &str, max_width: f32) -> String {
+    if measure(font, size, text) <= max_width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.trim_end_matches('…').chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if measure(font, size, &candidate) <= max_width {
+            return candidate;
+        }
+    }
+    "…".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin_words_stay_whole_and_cjk_breaks_anywhere() {
+        assert_eq!(tokenize("GPT-4o 发布, fast!"), ["GPT-4o", " ", "发", "布", ",", " ", "fast!"]);
+        assert!(load_font("/nonexistent/font.ttf").is_err());
+    }
+
+    #[test]
+    fn long_text_wraps_and_ends_with_an_ellipsis() {
+        // Rendering needs a system font; machines without any have nothing to measure with
+        let Ok(font) = load_font("") else { return };
+        let text = "Speculative decoding drafts tokens with a small model and verifies them with the large one";
+        let lines = wrap(&font, 40.0, text, 400.0, 2);
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| measure(&font, 40.0, line) <= 400.0), "{:?}", lines);
+        assert!(lines[0].starts_with("Speculative") && lines[1].ends_with('…'), "{:?}", lines);
+        assert_eq!(wrap(&font, 40.0, "Short", 400.0, 2), ["Short"]);
+        assert!(wrap(&font, 40.0, text, 400.0, 0).is_empty());
+    }
+}
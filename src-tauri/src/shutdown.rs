@@ -0,0 +1,117 @@
+// Graceful shutdown.
+//
+// Closing the app (last window, tray quit, Cmd+Q) raises `ExitRequested`;
+// the exit is held back while `begin` tells long-running work to wrap up.
+// The scheduler stops ticking; a running crawl fetches no further sources,
+// stores what it already has with template briefs in place of the AI
+// summaries it didn't get to, and queues the remaining summaries and
+// post-crawl steps in `batch_queue`; offline-job replay and deferred
+// night-batch work stop between items, putting back what they took. Once
+// nothing is `busy` any more, or after GRACE, the database is checkpointed
+// and the app exits. Work queued this way runs on the next start, whether or
+// not night-batch mode is on.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+use crate::DbState;
+
+// How long in-flight work gets before the app exits anyway
+const GRACE: Duration = Duration::from_secs(10);
+const POLL: Duration = Duration::from_millis(100);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+// Set once in-flight work is done and the real exit may go through
+static DRAINED: AtomicBool = AtomicBool::new(false);
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+
+pub fn requested() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+// Held by work that should finish (or persist itself) before the app exits
+pub struct Busy(());
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn busy() -> Busy {
+    BUSY.fetch_add(1, Ordering::SeqCst);
+    Busy(())
+}
+
+// Flush the WAL into the database file and truncate it; a no-op in rollback-journal mode
+pub fn checkpoint(conn: &Connection) {
+    if let Err(e) = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())) {
+        eprintln!("Failed to checkpoint database: {}", e);
+    }
+}
+
+// Called on `ExitRequested`: true = hold the exit back, `begin` exits the app once work has drained
+pub fn hold_exit() -> bool {
+    !DRAINED.load(Ordering::SeqCst)
+}
+
+// Start shutting down: wait for in-flight work, checkpoint, then exit
+pub fn begin(app: &AppHandle, code: i32) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        while BUSY.load(Ordering::SeqCst) > 0 && started.elapsed() < GRACE {
+            tokio::time::sleep(POLL).await;
+        }
+        if BUSY.load(Ordering::SeqCst) > 0 {
+            eprintln!("Exiting with work still in flight after {} s", GRACE.as_secs());
+        }
+        if let Ok(conn) = app.state::<DbState>().conn.lock() {
+            checkpoint(&conn);
+        }
+        DRAINED.store(true, Ordering::SeqCst);
+        app.exit(code);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busy_work_holds_the_exit_until_drained() {
+        let busy = busy();
+        assert!(BUSY.load(Ordering::SeqCst) >= 1);
+        assert!(!requested());
+        assert!(hold_exit(), "nothing has drained yet");
+        drop(busy);
+    }
+
+    #[test]
+    fn checkpoint_truncates_the_wal() {
+        let dir = std::env::temp_dir().join(format!("news-shutdown-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("news.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(())).unwrap();
+        conn.execute_batch("CREATE TABLE t (x); INSERT INTO t VALUES (1);").unwrap();
+        let wal = dir.join("news.db-wal");
+        assert!(std::fs::metadata(&wal).unwrap().len() > 0);
+
+        checkpoint(&conn);
+        assert_eq!(std::fs::metadata(&wal).unwrap().len(), 0);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1, "nothing lost in the checkpoint");
+
+        // Rollback-journal databases have nothing to checkpoint
+        checkpoint(&Connection::open_in_memory().unwrap());
+        drop(conn);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
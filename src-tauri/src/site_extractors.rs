@@ -0,0 +1,274 @@
+// Extractors for sites whose meta tags make a poor article (manual add and
+// the browser extension).
+//
+// The generic path reads `<title>` and the meta description, which on
+// WeChat articles is empty, on Zhihu is the site slogan and on arXiv the
+// first lines of the abstract, and never carries a publish date. For hosts
+// listed in EXTRACTORS the page's own markup is read instead: title, body
+// text, publish date, lead image and, for YouTube, the video length. Fields
+// an extractor can't find fall back to the generic ones.
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::datetime;
+
+// Body text kept per page; the reader fetches the full page when it needs it
+const MAX_BODY_CHARS: usize = 20_000;
+
+#[derive(Debug, Default)]
+pub struct SitePage {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    // RFC 3339 (UTC)
+    pub published_at: Option<String>,
+    pub image_url: Option<String>,
+    // Seconds, for videos
+    pub media_duration: Option<i64>,
+}
+
+struct Extractor {
+    // The host itself or any subdomain of it
+    hosts: &'static [&'static str],
+    extract: fn(&Html, &str) -> SitePage,
+}
+
+const EXTRACTORS: &[Extractor] = &[
+    Extractor { hosts: &["mp.weixin.qq.com"], extract: wechat },
+    Extractor { hosts: &["zhihu.com"], extract: zhihu },
+    Extractor { hosts: &["medium.com"], extract: medium },
+    Extractor { hosts: &["arxiv.org"], extract: arxiv },
+    Extractor { hosts: &["youtube.com", "youtu.be"], extract: youtube },
+];
+
+fn host_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+// What the site's extractor finds on the page; None for hosts without one
+pub fn extract(url: &str, html: &str) -> Option<SitePage> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_ascii_lowercase();
+    let extractor = EXTRACTORS.iter().find(|e| e.hosts.iter().any(|domain| host_matches(&host, domain)))?;
+    Some((extractor.extract)(&Html::parse_document(html), html))
+}
+
+fn select<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    document.select(&Selector::parse(selector).ok()?).next()
+}
+
+fn meta(document: &Html, selector: &str) -> Option<String> {
+    select(document, selector)
+        .and_then(|el| el.value().attr("content"))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn text_of(document: &Html, selector: &str) -> Option<String> {
+    let text = select(document, selector)?.text().collect::<Vec<_>>().join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// Paragraph text of a content element, one paragraph per line
+fn body_of(document: &Html, selector: &str) -> Option<String> {
+    let root = select(document, selector)?;
+    let paragraphs = Selector::parse("p, h2, h3, li, blockquote, pre").ok()?;
+    let mut lines: Vec<String> = root
+        .select(&paragraphs)
+        .map(|el| el.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines.dedup();
+    if lines.is_empty() {
+        // Editors that write bare text with <br>s
+        lines.push(root.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    let body = lines.join("\n\n");
+    if body.trim().is_empty() {
+        return None;
+    }
+    Some(if body.chars().count() > MAX_BODY_CHARS { body.chars().take(MAX_BODY_CHARS).collect() } else { body })
+}
+
+fn date(text: &str) -> Option<String> {
+    datetime::parse(text).map(|dt| dt.to_rfc3339())
+}
+
+// A quoted string assigned to a JS variable, e.g. `var ct = "1700000000";`
+fn js_string(html: &str, name: &str) -> Option<String> {
+    let start = html.find(&format!("var {} = ", name))? + name.len() + 7;
+    let rest = html[start..].trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &rest[1..];
+    Some(value[..value.find(quote)?].to_string())
+}
+
+// ISO 8601 durations as schema.org uses them ("PT1H2M3S") in seconds
+fn iso_duration(text: &str) -> Option<i64> {
+    let time = text.trim().strip_prefix("PT")?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in time.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'H' | 'M' | 'S' => {
+                let value: i64 = number.parse().ok()?;
+                seconds += value * match c {
+                    'H' => 3600,
+                    'M' => 60,
+                    _ => 1,
+                };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    (seconds > 0).then_some(seconds)
+}
+
+// WeChat official-account articles: the body is `#js_content`, the publish time a unix timestamp in a script
+fn wechat(document: &Html, html: &str) -> SitePage {
+    let published_at = js_string(html, "ct")
+        .and_then(|ts| ts.parse::<i64>().ok())
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.to_rfc3339())
+        .or_else(|| text_of(document, "#publish_time").and_then(|t| date(&t)));
+    SitePage {
+        title: text_of(document, "#activity-name").or_else(|| meta(document, "meta[property='og:title']")),
+        content: body_of(document, "#js_content"),
+        published_at,
+        image_url: meta(document, "meta[property='og:image']").or_else(|| js_string(html, "msg_cdn_url")),
+        media_duration: None,
+    }
+}
+
+// Zhihu columns (`zhuanlan.zhihu.com/p/...`) and answers
+fn zhihu(document: &Html, _html: &str) -> SitePage {
+    let published_at = meta(document, "meta[itemprop='datePublished']")
+        .and_then(|d| date(&d))
+        .or_else(|| text_of(document, ".ContentItem-time").and_then(|t| date(&t)));
+    SitePage {
+        title: text_of(document, "h1.Post-Title").or_else(|| text_of(document, "h1.QuestionHeader-title")),
+        content: body_of(document, ".Post-RichText").or_else(|| body_of(document, ".RichContent-inner")),
+        published_at,
+        image_url: meta(document, "meta[property='og:image']"),
+        media_duration: None,
+    }
+}
+
+fn medium(document: &Html, _html: &str) -> SitePage {
+    SitePage {
+        title: text_of(document, "article h1").or_else(|| meta(document, "meta[property='og:title']")),
+        content: body_of(document, "article"),
+        published_at: meta(document, "meta[property='article:published_time']").and_then(|d| date(&d)),
+        image_url: meta(document, "meta[property='og:image']"),
+        media_duration: None,
+    }
+}
+
+// Abstract pages (`/abs/...`) carry Highwire citation tags
+fn arxiv(document: &Html, _html: &str) -> SitePage {
+    let abstract_text = meta(document, "meta[name='citation_abstract']").or_else(|| {
+        text_of(document, "blockquote.abstract").map(|t| t.trim_start_matches("Abstract:").trim().to_string())
+    });
+    let authors: Vec<String> = Selector::parse("meta[name='citation_author']")
+        .map(|selector| {
+            document.select(&selector).filter_map(|el| el.value().attr("content")).map(str::to_string).collect()
+        })
+        .unwrap_or_default();
+    let content = abstract_text.map(|text| {
+        if authors.is_empty() {
+            text
+        } else {
+            format!("{}\n\n{}", authors.join(", "), text)
+        }
+    });
+    SitePage {
+        title: meta(document, "meta[name='citation_title']"),
+        content,
+        published_at: meta(document, "meta[name='citation_date']")
+            .or_else(|| meta(document, "meta[name='citation_online_date']"))
+            .and_then(|d| date(&d)),
+        image_url: None,
+        media_duration: None,
+    }
+}
+
+// Watch pages: schema.org microdata has the upload date and length, the description the full text
+fn youtube(document: &Html, _html: &str) -> SitePage {
+    SitePage {
+        title: meta(document, "meta[name='title']").or_else(|| meta(document, "meta[property='og:title']")),
+        content: meta(document, "meta[itemprop='description']")
+            .or_else(|| meta(document, "meta[property='og:description']")),
+        published_at: meta(document, "meta[itemprop='datePublished']")
+            .or_else(|| meta(document, "meta[itemprop='uploadDate']"))
+            .and_then(|d| date(&d)),
+        image_url: meta(document, "meta[property='og:image']"),
+        media_duration: meta(document, "meta[itemprop='duration']").and_then(|d| iso_duration(&d)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extractors_are_chosen_by_host() {
+        assert!(host_matches("zhuanlan.zhihu.com", "zhihu.com"));
+        assert!(host_matches("zhihu.com", "zhihu.com"));
+        assert!(!host_matches("notzhihu.com", "zhihu.com"));
+        assert!(extract("https://example.com/post", "<html></html>").is_none());
+        assert!(extract("not a url", "<html></html>").is_none());
+        let page = extract("https://WWW.YouTube.com/watch?v=x", "<html></html>").unwrap();
+        assert!(page.title.is_none(), "missing fields stay empty for the generic fallback");
+    }
+
+    #[test]
+    fn iso_durations_and_script_values() {
+        assert_eq!(iso_duration("PT1H2M3S"), Some(3723));
+        assert_eq!(iso_duration("PT45S"), Some(45));
+        assert_eq!(iso_duration("PT0S"), None);
+        assert_eq!(iso_duration("P1D"), None);
+        assert_eq!(iso_duration("PT1.5M"), None);
+        assert_eq!(js_string("var ct = \"1700000000\";", "ct").as_deref(), Some("1700000000"));
+        assert_eq!(js_string("var msg_cdn_url = 'https://img/x.jpg';", "msg_cdn_url").as_deref(), Some("https://img/x.jpg"));
+        assert_eq!(js_string("var ct = 1700000000;", "ct"), None, "unquoted");
+    }
+
+    #[test]
+    fn wechat_article() {
+        let html = r#"<html><head><meta property="og:image" content="https://mmbiz.qpic.cn/cover.jpg"></head><body>
+            <h1 id="activity-name">  大模型周报  </h1>
+            <div id="js_content"><p>第一段。</p><p>第一段。</p><p><span>第二段</span> 内容。</p></div>
+            <script>var ct = "1700000000";</script></body></html>"#;
+        let page = extract("https://mp.weixin.qq.com/s/abc", html).unwrap();
+        assert_eq!(page.title.as_deref(), Some("大模型周报"));
+        assert_eq!(page.content.as_deref(), Some("第一段。\n\n第二段 内容。"));
+        assert_eq!(page.published_at.as_deref(), Some("2023-11-14T22:13:20+00:00"));
+        assert_eq!(page.image_url.as_deref(), Some("https://mmbiz.qpic.cn/cover.jpg"));
+    }
+
+    #[test]
+    fn arxiv_and_youtube_pages() {
+        let html = r#"<html><head>
+            <meta name="citation_title" content="Attention Is All You Need">
+            <meta name="citation_author" content="Vaswani, Ashish"><meta name="citation_author" content="Shazeer, Noam">
+            <meta name="citation_date" content="2017-06-12T00:00:00Z">
+            <meta name="citation_abstract" content="The dominant sequence transduction models...">
+        </head></html>"#;
+        let page = extract("https://arxiv.org/abs/1706.03762", html).unwrap();
+        assert_eq!(page.title.as_deref(), Some("Attention Is All You Need"));
+        assert_eq!(page.content.as_deref(), Some("Vaswani, Ashish, Shazeer, Noam\n\nThe dominant sequence transduction models..."));
+        assert_eq!(page.published_at.as_deref(), Some("2017-06-12T00:00:00+00:00"));
+
+        let html = r#"<html><head><meta name="title" content="Paper walkthrough">
+            <meta itemprop="description" content="We read the paper.">
+            <meta itemprop="uploadDate" content="2024-05-01T12:00:00Z"><meta itemprop="duration" content="PT12M30S">
+        </head></html>"#;
+        let page = extract("https://youtu.be/abc", html).unwrap();
+        assert_eq!(page.title.as_deref(), Some("Paper walkthrough"));
+        assert_eq!(page.content.as_deref(), Some("We read the paper."));
+        assert_eq!(page.published_at.as_deref(), Some("2024-05-01T12:00:00+00:00"));
+        assert_eq!(page.media_duration, Some(750));
+    }
+}
@@ -0,0 +1,361 @@
+// Adding sources from a pasted list of URLs.
+//
+// Each line is fetched once. An RSS document is added as an RSS source
+// under its channel title; an HTML page is searched for
+// `<link rel="alternate">` feeds, then for a feed at the usual paths
+// (`/feed`, `/rss.xml`, ...), and only when none parses is the page itself
+// added as a WEB source. Every line gets its own result, so one bad URL
+// doesn't stop the rest.
+//
+// Duplicates are caught on the URL's identity rather than its spelling:
+// scheme, `www.`, default ports, fragments and trailing slashes are ignored,
+// and a URL that redirects is compared (and stored) under where it ends up.
+// The input is checked before anything is fetched and the discovered feed
+// again afterwards, so a homepage whose feed is already subscribed is
+// reported as that existing source.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use scraper::{Html, Selector};
+use serde::Serialize;
+
+use crate::i18n::{t, tr, Msg};
+use crate::HttpClients;
+
+const COMMON_FEED_PATHS: &[&str] = &["/feed", "/rss", "/feed.xml", "/rss.xml", "/atom.xml", "/index.xml"];
+// Lines handled per call
+const MAX_URLS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddStatus {
+    Added,
+    Exists,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkAddResult {
+    // The line as pasted
+    pub input: String,
+    pub status: AddStatus,
+    pub name: Option<String>,
+    // URL stored for the source (the discovered feed for RSS)
+    pub url: Option<String>,
+    pub source_type: Option<String>,
+    pub error: Option<String>,
+}
+
+// An existing source the URL duplicates
+pub(crate) struct Existing {
+    pub name: String,
+    pub url: String,
+}
+
+struct Discovered {
+    name: String,
+    url: String,
+    source_type: &'static str,
+}
+
+// Body and final URL after redirects
+async fn fetch(http: &HttpClients, url: &str) -> Result<(reqwest::Url, String), String> {
+    let response = http
+        .for_url(url)
+        .get(url)
+        .header("Accept", "application/rss+xml, application/xml, text/html;q=0.9, */*;q=0.8")
+        .timeout(std::time::Duration::from_secs(20))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::HttpRequestFailed, e))?;
+    if !response.status().is_success() {
+        return Err(tr(Msg::HttpRequestFailed, response.status()));
+    }
+    let final_url = canonical(response.url());
+    let body = response.text().await.map_err(|e| tr(Msg::HttpRequestFailed, e))?;
+    Ok((final_url, body))
+}
+
+// The URL as stored: no fragment
+fn canonical(url: &reqwest::Url) -> reqwest::Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+// What two URLs must share to be the same source: host without `www.`,
+// explicit non-default port, path without trailing slash, and query
+fn identity(url: &reqwest::Url) -> String {
+    let host = url.host_str().unwrap_or_default().trim_start_matches("www.");
+    let port = url.port().map(|p| format!(":{}", p)).unwrap_or_default();
+    let path = url.path().trim_end_matches('/');
+    let query = url.query().map(|q| format!("?{}", q)).unwrap_or_default();
+    format!("{}{}{}{}", host, port, path, query)
+}
+
+pub(crate) fn parse_source_url(input: &str) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(input.trim()).map_err(|e| tr(Msg::InvalidUrl, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(tr(Msg::UrlSchemeNotAllowed, url.scheme()));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(tr(Msg::InvalidUrl, input));
+    }
+    Ok(canonical(&url))
+}
+
+// Channel title when the body is an RSS feed the crawler can read
+fn rss_title(body: &str) -> Option<String> {
+    rss::Channel::read_from(body.as_bytes()).ok().map(|channel| channel.title().trim().to_string())
+}
+
+// Feed links advertised by a page, absolute, in document order
+fn alternate_feeds(page_url: &reqwest::Url, html: &Html) -> Vec<String> {
+    let selector = Selector::parse(
+        "link[rel~='alternate'][type='application/rss+xml'], link[rel~='alternate'][type='application/atom+xml']",
+    )
+    .unwrap();
+    html.select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| page_url.join(href.trim()).ok())
+        .map(|url| url.to_string())
+        .collect()
+}
+
+fn page_title(html: &Html, fallback: &reqwest::Url) -> String {
+    let selector = Selector::parse("title").unwrap();
+    html.select(&selector)
+        .next()
+        .map(|title| title.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| fallback.host_str().unwrap_or_default().to_string())
+}
+
+async fn discover(http: &HttpClients, url: &reqwest::Url) -> Result<Discovered, String> {
+    let (final_url, body) = fetch(http, url.as_str()).await?;
+    let url = &final_url;
+    if let Some(title) = rss_title(&body) {
+        let name = if title.is_empty() { url.host_str().unwrap_or_default().to_string() } else { title };
+        return Ok(Discovered { name, url: url.to_string(), source_type: "RSS" });
+    }
+
+    // Parsed documents aren't Send, so everything needed from the page is pulled out first
+    let (advertised, title) = {
+        let html = Html::parse_document(&body);
+        (alternate_feeds(url, &html), page_title(&html, url))
+    };
+    let guessed = COMMON_FEED_PATHS.iter().filter_map(|path| url.join(path).ok()).map(|u| u.to_string());
+    for candidate in advertised.into_iter().chain(guessed) {
+        if candidate == url.as_str() {
+            continue;
+        }
+        let Ok((feed_url, body)) = fetch(http, &candidate).await else { continue };
+        if let Some(feed_title) = rss_title(&body) {
+            let name = if feed_title.is_empty() { title } else { feed_title };
+            return Ok(Discovered { name, url: feed_url.to_string(), source_type: "RSS" });
+        }
+    }
+    Ok(Discovered { name: title, url: url.to_string(), source_type: "WEB" })
+}
+
+// Existing source with the same identity as `url`, whatever its spelling
+pub(crate) fn find_duplicate(conn: &Connection, url: &reqwest::Url) -> Result<Option<Existing>, String> {
+    let wanted = identity(url);
+    let mut stmt = conn
+        .prepare("SELECT name, url FROM sources")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let sources = stmt
+        .query_map([], |row| Ok(Existing { name: row.get(0)?, url: row.get(1)? }))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(sources.into_iter().find(|source| {
+        reqwest::Url::parse(source.url.trim()).is_ok_and(|existing| identity(&existing) == wanted)
+    }))
+}
+
+// Names are unique; a second "Blog" becomes "Blog (2)"
+pub(crate) fn unique_name(conn: &Connection, name: &str) -> Result<String, String> {
+    let taken = |candidate: &str| -> Result<bool, String> {
+        conn.query_row("SELECT 1 FROM sources WHERE name = ?1", params![candidate], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+            .map_err(|e| tr(Msg::DbQueryFailed, e))
+    };
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while taken(&candidate)? {
+        candidate = format!("{} ({})", name, n);
+        n += 1;
+    }
+    Ok(candidate)
+}
+
+fn exists_result(input: &str, existing: Existing, source_type: Option<String>) -> BulkAddResult {
+    let error = tr(Msg::SourceExists, format!("{} ({})", existing.name, existing.url));
+    BulkAddResult {
+        input: input.to_string(),
+        status: AddStatus::Exists,
+        name: Some(existing.name),
+        url: Some(existing.url),
+        source_type,
+        error: Some(error),
+    }
+}
+
+async fn add_one(db: &Mutex<Connection>, http: &HttpClients, input: &str) -> Result<BulkAddResult, String> {
+    let url = parse_source_url(input)?;
+    {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        if let Some(existing) = find_duplicate(&conn, &url)? {
+            return Ok(exists_result(input, existing, None));
+        }
+    }
+    let found = discover(http, &url).await?;
+    let found_url = parse_source_url(&found.url)?;
+
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    if let Some(existing) = find_duplicate(&conn, &found_url)? {
+        return Ok(exists_result(input, existing, Some(found.source_type.to_string())));
+    }
+    let name = unique_name(&conn, &found.name)?;
+    conn.execute(
+        "INSERT INTO sources (id, name, url, source_type, is_active) VALUES (?1, ?2, ?3, ?4, 1)",
+        params![uuid::Uuid::new_v4().to_string(), name, found.url, found.source_type],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(BulkAddResult {
+        input: input.to_string(),
+        status: AddStatus::Added,
+        name: Some(name),
+        url: Some(found.url),
+        source_type: Some(found.source_type.to_string()),
+        error: None,
+    })
+}
+
+// Add a single URL; a duplicate is an error naming the existing source
+pub async fn add(db: &Mutex<Connection>, http: &HttpClients, url: &str) -> Result<BulkAddResult, String> {
+    let result = add_one(db, http, url).await?;
+    match result.status {
+        AddStatus::Exists => Err(result.error.unwrap_or_else(|| t(Msg::SourceExists))),
+        _ => Ok(result),
+    }
+}
+
+// Add every URL in a newline-separated list; blank lines and `#` comments are skipped
+pub async fn bulk_add(db: &Mutex<Connection>, http: &HttpClients, text: &str) -> Result<Vec<BulkAddResult>, String> {
+    let inputs: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if inputs.is_empty() {
+        return Err(t(Msg::NoUrlsGiven));
+    }
+
+    let mut results = Vec::new();
+    for input in inputs.into_iter().take(MAX_URLS) {
+        let result = add_one(db, http, input).await.unwrap_or_else(|e| BulkAddResult {
+            input: input.to_string(),
+            status: AddStatus::Failed,
+            name: None,
+            url: None,
+            source_type: None,
+            error: Some(e),
+        });
+        results.push(result);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(input: &str) -> reqwest::Url {
+        parse_source_url(input).unwrap()
+    }
+
+    #[test]
+    fn source_urls_must_be_web_urls() {
+        assert_eq!(url(" https://example.com/feed#latest ").as_str(), "https://example.com/feed");
+        assert!(parse_source_url("example.com/feed").is_err());
+        assert!(parse_source_url("ftp://example.com/feed").is_err());
+        assert!(parse_source_url("file:///etc/passwd").is_err());
+        assert!(parse_source_url("https://").is_err());
+    }
+
+    #[test]
+    fn spelling_variants_share_an_identity() {
+        let feed = identity(&url("https://example.com/blog/feed"));
+        for variant in [
+            "http://example.com/blog/feed",
+            "https://www.example.com/blog/feed/",
+            "https://example.com:443/blog/feed",
+            "https://example.com/blog/feed#top",
+        ] {
+            assert_eq!(identity(&url(variant)), feed, "{}", variant);
+        }
+        for different in [
+            "https://example.com:8443/blog/feed",
+            "https://example.com/blog/feed?lang=en",
+            "https://blog.example.com/blog/feed",
+            "https://example.com/blog",
+        ] {
+            assert_ne!(identity(&url(different)), feed, "{}", different);
+        }
+    }
+
+    #[test]
+    fn duplicates_name_the_existing_source() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute("DELETE FROM sources", []).unwrap();
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type) VALUES ('s1', 'Example', 'https://www.example.com/feed/', 'RSS')",
+            [],
+        )
+        .unwrap();
+
+        let existing = find_duplicate(&conn, &url("http://example.com/feed")).unwrap().expect("duplicate found");
+        assert_eq!((existing.name.as_str(), existing.url.as_str()), ("Example", "https://www.example.com/feed/"));
+        assert!(find_duplicate(&conn, &url("https://example.com/rss")).unwrap().is_none());
+    }
+
+    #[test]
+    fn pages_advertise_feeds_and_titles() {
+        let page = url("https://example.com/blog/");
+        let html = Html::parse_document(
+            r#"<html><head><title>
+                Example   Blog
+            </title>
+            <link rel="alternate" type="application/atom+xml" href="/atom.xml">
+            <link rel="alternate" type="text/html" href="/amp/">
+            <link rel="alternate home" type="application/rss+xml" href=" feed.xml ">
+            </head></html>"#,
+        );
+        assert_eq!(alternate_feeds(&page, &html), ["https://example.com/atom.xml", "https://example.com/blog/feed.xml"]);
+        assert_eq!(page_title(&html, &page), "Example Blog");
+        assert_eq!(page_title(&Html::parse_document("<p>untitled</p>"), &page), "example.com");
+
+        let feed = r#"<?xml version="1.0"?><rss version="2.0"><channel><title> Example Blog </title><link>https://example.com</link><description></description></channel></rss>"#;
+        assert_eq!(rss_title(feed).as_deref(), Some("Example Blog"));
+        assert_eq!(rss_title("<html><body>not a feed</body></html>"), None);
+    }
+
+    #[test]
+    fn taken_names_get_a_number() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute("DELETE FROM sources", []).unwrap();
+        assert_eq!(unique_name(&conn, "Blog").unwrap(), "Blog");
+        for (id, name) in [("s1", "Blog"), ("s2", "Blog (2)")] {
+            conn.execute(
+                "INSERT INTO sources (id, name, url, source_type) VALUES (?1, ?2, ?1, 'RSS')",
+                params![id, name],
+            )
+            .unwrap();
+        }
+        assert_eq!(unique_name(&conn, "Blog").unwrap(), "Blog (3)");
+    }
+}
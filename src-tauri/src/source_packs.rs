@@ -0,0 +1,249 @@
+// Shareable source packs.
+//
+// A source pack is a JSON file bundling a curated set of sources so they
+// can be passed around and imported in one go:
+//
+//     {
+//       "format": "ai-news-source-pack",
+//       "version": 1,
+//       "name": "LLM labs",
+//       "description": "Official blogs of the big model labs",
+//       "sources": [
+//         {"name": "Example Lab", "url": "https://example.com/blog", "source_type": "WEB",
+//          "category": "AI", "scrape_rules": {"item": "article", "title": "h2", "date": "time"}}
+//       ]
+//     }
+//
+// `category`, `scrape_rules` (see `scrape_rules.rs`), `github_trending` (see
+// `github_trending.rs`) and `max_age_days` are optional. Importing checks every entry on its own: a source whose URL is
+// already subscribed (same identity as in `source_discovery`) or that
+// doesn't validate is skipped with the reason, the rest are added.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::github_trending::TrendingConfig;
+use crate::i18n::{t, tr, Msg};
+use crate::scrape_rules::ScrapeRules;
+use crate::{categorize_source, source_discovery};
+
+pub const FORMAT: &str = "ai-news-source-pack";
+pub const VERSION: u32 = 1;
+const SOURCE_TYPES: &[&str] = &["RSS", "WEB", "API"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackSource {
+    pub name: String,
+    pub url: String,
+    pub source_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_rules: Option<ScrapeRules>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_trending: Option<TrendingConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourcePack {
+    pub format: String,
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub sources: Vec<PackSource>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedSource {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub pack: String,
+    // Names as stored (a taken name gets a " (n)" suffix)
+    pub added: Vec<String>,
+    pub skipped: Vec<SkippedSource>,
+}
+
+// Pack the named sources (all when empty) under a pack name and description
+pub fn build(conn: &Connection, name: &str, description: &str, names: &[String]) -> Result<SourcePack, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, url, source_type, category, scrape_rules, max_age_days, trending_config FROM sources ORDER BY name")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<u32>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    let sources: Vec<PackSource> = rows
+        .into_iter()
+        .filter(|(source_name, ..)| names.is_empty() || names.contains(source_name))
+        .map(|(name, url, source_type, category, rules, max_age_days, trending)| PackSource {
+            // Written out explicitly so the pack means the same wherever it's imported
+            category: Some(category.filter(|c| !c.trim().is_empty()).unwrap_or_else(|| categorize_source(&name))),
+            scrape_rules: rules.and_then(|json| serde_json::from_str(&json).ok()),
+            github_trending: trending.and_then(|json| serde_json::from_str(&json).ok()),
+            name,
+            url,
+            source_type,
+            max_age_days,
+        })
+        .collect();
+    if sources.is_empty() {
+        return Err(t(Msg::ExportNothingSelected));
+    }
+    Ok(SourcePack {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        name: name.trim().to_string(),
+        description: description.trim().to_string(),
+        sources,
+    })
+}
+
+pub fn parse(content: &str) -> Result<SourcePack, String> {
+    let pack: SourcePack = serde_json::from_str(content).map_err(|e| tr(Msg::SourcePackInvalid, e))?;
+    if pack.format != FORMAT {
+        return Err(tr(Msg::SourcePackInvalid, &pack.format));
+    }
+    if pack.version > VERSION {
+        return Err(tr(Msg::SourcePackInvalid, format!("version {}", pack.version)));
+    }
+    Ok(pack)
+}
+
+fn check(conn: &Connection, source: &PackSource) -> Result<(), String> {
+    if source.name.trim().is_empty() {
+        return Err(tr(Msg::SourcePackInvalid, "name"));
+    }
+    if !SOURCE_TYPES.contains(&source.source_type.as_str()) {
+        return Err(tr(Msg::SourceTypeUnsupported, &source.source_type));
+    }
+    if let Some(rules) = &source.scrape_rules {
+        rules.validate()?;
+    }
+    if let Some(trending) = &source.github_trending {
+        trending.normalized()?;
+    }
+    let url = source_discovery::parse_source_url(&source.url)?;
+    if let Some(existing) = source_discovery::find_duplicate(conn, &url)? {
+        return Err(tr(Msg::SourceExists, format!("{} ({})", existing.name, existing.url)));
+    }
+    Ok(())
+}
+
+pub fn import(conn: &Connection, pack: &SourcePack) -> Result<ImportResult, String> {
+    let mut result = ImportResult { pack: pack.name.clone(), added: Vec::new(), skipped: Vec::new() };
+    for source in &pack.sources {
+        if let Err(reason) = check(conn, source) {
+            result.skipped.push(SkippedSource { name: source.name.clone(), reason });
+            continue;
+        }
+        let name = source_discovery::unique_name(conn, source.name.trim())?;
+        let rules = source.scrape_rules.as_ref().and_then(|rules| serde_json::to_string(rules).ok());
+        let trending = source
+            .github_trending
+            .as_ref()
+            .and_then(|trending| trending.normalized().ok())
+            .and_then(|trending| serde_json::to_string(&trending).ok());
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type, is_active, category, scrape_rules, max_age_days, trending_config)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6, ?7, ?8)",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                name,
+                source.url.trim(),
+                source.source_type,
+                source.category.as_deref().map(str::trim).filter(|c| !c.is_empty()),
+                rules,
+                source.max_age_days,
+                trending
+            ],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+        result.added.push(name);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(sources: serde_json::Value) -> String {
+        serde_json::json!({ "format": FORMAT, "version": VERSION, "name": "LLM labs", "sources": sources }).to_string()
+    }
+
+    #[test]
+    fn parse_checks_format_and_version() {
+        assert!(parse(&pack(serde_json::json!([]))).is_ok());
+        assert!(parse(r#"{"format": "opml", "version": 1, "name": "x", "sources": []}"#).is_err());
+        assert!(parse(&format!(r#"{{"format": "{}", "version": 99, "name": "x", "sources": []}}"#, FORMAT)).is_err());
+        assert!(parse("not json").is_err());
+    }
+
+    #[test]
+    fn import_skips_invalid_entries_and_export_round_trips() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO sources (id, name, url, source_type) VALUES ('s1', 'Lab', 'https://lab.example.com/feed', 'RSS')",
+            [],
+        )
+        .unwrap();
+        let parsed = parse(&pack(serde_json::json!([
+            { "name": "Lab", "url": "https://other.example.com/blog", "source_type": "WEB", "category": "AI",
+              "scrape_rules": { "item": "article", "title": "h2" }, "max_age_days": 14 },
+            { "name": "Copy", "url": "https://lab.example.com/feed/", "source_type": "RSS" },
+            { "name": "Telegram", "url": "https://t.me/ai", "source_type": "TG" },
+            { "name": "Broken", "url": "https://broken.example.com", "source_type": "WEB", "scrape_rules": { "item": "[" } },
+            { "name": " ", "url": "https://blank.example.com", "source_type": "RSS" }
+        ])))
+        .unwrap();
+        let result = import(&conn, &parsed).unwrap();
+        assert_eq!(result.added, ["Lab (2)"]);
+        assert_eq!(result.skipped.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["Copy", "Telegram", "Broken", " "]);
+
+        let exported = build(&conn, " Mine ", "", &["Lab (2)".to_string()]).unwrap();
+        assert_eq!(exported.name, "Mine");
+        let source = &exported.sources[0];
+        assert_eq!((source.url.as_str(), source.category.as_deref(), source.max_age_days), ("https://other.example.com/blog", Some("AI"), Some(14)));
+        assert_eq!(source.scrape_rules.as_ref().unwrap().title.as_deref(), Some("h2"));
+        assert!(build(&conn, "None", "", &["Missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn trending_configs_travel_with_the_pack() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let parsed = parse(&pack(serde_json::json!([
+            { "name": "Trending", "url": "https://github.com/trending/rust", "source_type": "WEB",
+              "github_trending": { "languages": ["Rust", "Go"], "since": "weekly" } },
+            { "name": "Bad trending", "url": "https://github.com/trending", "source_type": "WEB",
+              "github_trending": { "spoken_language": "chinese" } }
+        ])))
+        .unwrap();
+        let result = import(&conn, &parsed).unwrap();
+        assert_eq!(result.added, ["Trending"]);
+        assert_eq!(result.skipped[0].name, "Bad trending");
+
+        let exported = build(&conn, "Mine", "", &["Trending".to_string()]).unwrap();
+        let trending = exported.sources[0].github_trending.as_ref().unwrap();
+        assert_eq!(trending.languages, ["rust", "go"]);
+        assert_eq!(trending.since, crate::github_trending::Since::Weekly);
+    }
+}
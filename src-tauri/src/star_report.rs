@@ -0,0 +1,287 @@
+// Report of GitHub repos whose star counts jumped, built from `repo_snapshots`.
+//
+// Trending pages only show today's ranking. Comparing each repo's latest
+// snapshot with the one at the start of the window surfaces repos that
+// gained at least `min_gain` stars or passed a milestone (10k, 50k, ...);
+// repos first seen inside the window are compared against that sighting.
+// When enabled the scheduler stores the report in `digests` every `days`
+// days and mails it to the digest recipients.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{self, StoredDigest};
+use crate::email::{self, SmtpConfig};
+use crate::i18n::{t, tr, Msg};
+use crate::{get_setting, set_setting};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarReportConfig {
+    // Scheduled report on/off
+    pub enabled: bool,
+    pub days: i64,
+    pub min_gain: u32,
+    // Star totals worth reporting when passed, ascending
+    pub milestones: Vec<u32>,
+}
+
+impl Default for StarReportConfig {
+    fn default() -> Self {
+        StarReportConfig {
+            enabled: false,
+            days: 7,
+            min_gain: 5000,
+            milestones: vec![10_000, 50_000, 100_000],
+        }
+    }
+}
+
+impl StarReportConfig {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let defaults = StarReportConfig::default();
+        let milestones = get_setting(conn, "star_report.milestones", "")?;
+        Ok(StarReportConfig {
+            enabled: get_setting(conn, "star_report.enabled", "false")? == "true",
+            days: get_setting(conn, "star_report.days", "")?.parse().unwrap_or(defaults.days).max(1),
+            min_gain: get_setting(conn, "star_report.min_gain", "")?.parse().unwrap_or(defaults.min_gain),
+            milestones: if milestones.is_empty() { defaults.milestones } else { parse_milestones(&milestones) },
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        let milestones: Vec<String> = self.sorted_milestones().iter().map(|m| m.to_string()).collect();
+        set_setting(conn, "star_report.enabled", if self.enabled { "true" } else { "false" })?;
+        set_setting(conn, "star_report.days", &self.days.max(1).to_string())?;
+        set_setting(conn, "star_report.min_gain", &self.min_gain.to_string())?;
+        set_setting(conn, "star_report.milestones", &milestones.join(","))?;
+        Ok(())
+    }
+
+    fn sorted_milestones(&self) -> Vec<u32> {
+        let mut milestones: Vec<u32> = self.milestones.iter().copied().filter(|m| *m > 0).collect();
+        milestones.sort_unstable();
+        milestones.dedup();
+        milestones
+    }
+}
+
+fn parse_milestones(value: &str) -> Vec<u32> {
+    value.split(',').filter_map(|m| m.trim().parse().ok()).collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepoGain {
+    pub full_name: String,
+    pub url: String,
+    pub stars: u32,
+    // Stars at the start of the window (or when first seen)
+    pub previous: u32,
+    pub gained: u32,
+    // Highest milestone passed inside the window
+    pub milestone: Option<u32>,
+    pub since: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StarReport {
+    pub title: String,
+    pub generated_at: String,
+    pub days: i64,
+    pub min_gain: u32,
+    pub repos: Vec<RepoGain>,
+}
+
+pub fn build(conn: &Connection, config: &StarReportConfig) -> Result<StarReport, String> {
+    let days = config.days.max(1);
+    let start = (chrono::Utc::now() - chrono::Duration::days(days)).format("%Y-%m-%d").to_string();
+
+    // Baseline: the last snapshot on or before the window start, else the first one inside it
+    let mut stmt = conn
+        .prepare(
+            "SELECT full_name,
+                COALESCE(
+                    (SELECT day || ' ' || stars FROM repo_snapshots b WHERE b.full_name = s.full_name AND b.day <= ?1 ORDER BY day DESC LIMIT 1),
+                    (SELECT day || ' ' || stars FROM repo_snapshots b WHERE b.full_name = s.full_name AND b.day >= ?1 ORDER BY day ASC LIMIT 1)
+                ),
+                (SELECT stars FROM repo_snapshots l WHERE l.full_name = s.full_name ORDER BY day DESC LIMIT 1)
+             FROM repo_snapshots s WHERE s.day >= ?1 GROUP BY full_name",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map(params![start], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u32>(2)?))
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    let milestones = config.sorted_milestones();
+    let mut repos: Vec<RepoGain> = rows
+        .into_iter()
+        .filter_map(|(full_name, baseline, stars)| {
+            let (since, previous) = baseline.split_once(' ')?;
+            let previous: u32 = previous.parse().ok()?;
+            let gained = stars.saturating_sub(previous);
+            let milestone = milestones.iter().rev().copied().find(|m| previous < *m && stars >= *m);
+            (gained >= config.min_gain.max(1) || milestone.is_some()).then(|| RepoGain {
+                url: format!("https://github.com/{}", full_name),
+                full_name,
+                stars,
+                previous,
+                gained,
+                milestone,
+                since: since.to_string(),
+            })
+        })
+        .collect();
+    repos.sort_by(|a, b| b.gained.cmp(&a.gained).then_with(|| a.full_name.cmp(&b.full_name)));
+
+    Ok(StarReport {
+        title: format!("{} · {}", t(Msg::StarReportTitle), chrono::Local::now().format("%Y-%m-%d")),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        days,
+        min_gain: config.min_gain,
+        repos,
+    })
+}
+
+fn thousands(n: u32) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn describe(repo: &RepoGain) -> String {
+    let mut line = format!(
+        "★ {} · +{} · {} {}",
+        thousands(repo.stars),
+        thousands(repo.gained),
+        t(Msg::StarReportSince),
+        repo.since
+    );
+    if let Some(milestone) = repo.milestone {
+        line.push_str(&format!(" · {} {}", t(Msg::StarReportMilestone), thousands(milestone)));
+    }
+    line
+}
+
+pub fn render_markdown(report: &StarReport) -> String {
+    let mut out = format!("# {}\n\n", report.title);
+    if report.repos.is_empty() {
+        out.push_str(&format!("{}\n", t(Msg::StarReportEmpty)));
+    }
+    for repo in &report.repos {
+        out.push_str(&format!("- [{}]({}) — {}\n", repo.full_name, repo.url, describe(repo)));
+    }
+    out
+}
+
+pub fn render_html(report: &StarReport) -> String {
+    let mut body = String::new();
+    if report.repos.is_empty() {
+        body.push_str(&format!("<p style=\"color:#666\">{}</p>", digest::escape(&t(Msg::StarReportEmpty))));
+    }
+    for repo in &report.repos {
+        body.push_str(&format!(
+            "<div style=\"margin:12px 0\">\
+             <a href=\"{url}\" style=\"font-size:16px;font-weight:600;color:#1a56db;text-decoration:none\">{name}</a>\
+             <div style=\"font-size:13px;color:#555;margin-top:2px\">{detail}</div>\
+             </div>",
+            url = digest::escape(&repo.url),
+            name = digest::escape(&repo.full_name),
+            detail = digest::escape(&describe(repo)),
+        ));
+    }
+    digest::html_document(&report.title, &body)
+}
+
+// Scheduled run: store the report in the digest history and mail it when SMTP is set up
+pub async fn run_scheduled(db: &Mutex<Connection>) -> Result<(), String> {
+    let (stored, smtp) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        let config = StarReportConfig::load(&conn)?;
+        let report = build(&conn, &config)?;
+        let stored = StoredDigest {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: "stars".to_string(),
+            title: report.title.clone(),
+            generated_at: report.generated_at.clone(),
+            days: report.days,
+            article_count: report.repos.len(),
+            markdown: render_markdown(&report),
+            html: render_html(&report),
+        };
+        digest::store(&conn, &stored)?;
+        (stored, SmtpConfig::load(&conn)?)
+    }; // Release the lock while talking to the SMTP server
+
+    if smtp.is_configured() && !smtp.recipients.is_empty() && stored.article_count > 0 {
+        email::send_report(db, &stored).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(conn: &Connection, full_name: &str, days_ago: i64, stars: u32) {
+        let day = (chrono::Utc::now() - chrono::Duration::days(days_ago)).format("%Y-%m-%d").to_string();
+        conn.execute(
+            "INSERT INTO repo_snapshots (full_name, day, stars) VALUES (?1, ?2, ?3)",
+            params![full_name, day, stars],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn report_lists_big_gains_and_passed_milestones() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        snapshot(&conn, "acme/rocket", 10, 1_000);
+        snapshot(&conn, "acme/rocket", 1, 9_000);
+        snapshot(&conn, "acme/steady", 10, 9_500);
+        snapshot(&conn, "acme/steady", 1, 10_200);
+        snapshot(&conn, "acme/slow", 10, 200);
+        snapshot(&conn, "acme/slow", 1, 900);
+        snapshot(&conn, "acme/fresh", 3, 100);
+        snapshot(&conn, "acme/fresh", 0, 6_000);
+        snapshot(&conn, "acme/stale", 30, 100);
+
+        let config = StarReportConfig { min_gain: 5000, ..StarReportConfig::default() };
+        let report = build(&conn, &config).unwrap();
+        let repos: Vec<(&str, u32, Option<u32>)> =
+            report.repos.iter().map(|r| (r.full_name.as_str(), r.gained, r.milestone)).collect();
+        assert_eq!(
+            repos,
+            [("acme/rocket", 8_000, None), ("acme/fresh", 5_900, None), ("acme/steady", 700, Some(10_000))]
+        );
+        let first_seen = (chrono::Utc::now() - chrono::Duration::days(3)).format("%Y-%m-%d").to_string();
+        assert_eq!(report.repos[1].since, first_seen, "first sighting in the window is the baseline");
+        assert!(render_markdown(&report).contains("[acme/rocket](https://github.com/acme/rocket) — ★ 9,000 · +8,000"));
+    }
+
+    #[test]
+    fn config_round_trip_sorts_milestones_and_clamps_days() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let loaded = StarReportConfig::load(&conn).unwrap();
+        assert_eq!((loaded.days, loaded.milestones.clone()), (7, vec![10_000, 50_000, 100_000]));
+
+        StarReportConfig { enabled: true, days: 0, min_gain: 100, milestones: vec![50_000, 0, 1_000, 50_000] }.save(&conn).unwrap();
+        let loaded = StarReportConfig::load(&conn).unwrap();
+        assert!(loaded.enabled);
+        assert_eq!(loaded.days, 1);
+        assert_eq!(loaded.milestones, [1_000, 50_000]);
+        assert_eq!(parse_milestones(" 5, x,20 "), [5, 20]);
+        assert_eq!(thousands(1_234_567), "1,234,567");
+        assert_eq!(thousands(999), "999");
+    }
+}
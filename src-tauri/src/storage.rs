@@ -0,0 +1,210 @@
+// Disk usage (`storage_stats`) and the global storage quota.
+//
+// Usage counts the database pages in use, the `images/` folder of the data
+// directory where cached images go, and `logs/` for log files. The WAL file
+// and free pages are reported but left out of the quota: both shrink on
+// their own (checkpoint, VACUUM). With `storage_quota_mb` set, every
+// cleanup ends with `enforce_quota`, which while usage is over the quota
+// empties the HTTP cache, deletes cached images oldest first, empties the
+// trash and then removes the oldest removable articles for good, keeping at
+// least MIN_KEPT_ARTICLES. Bookmarked and annotated articles are never
+// removed. The database is vacuumed afterwards so the file shrinks too.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::db::{delete_article_orphans, get_data_dir, get_setting, REMOVABLE_OLDEST};
+use crate::i18n::{tr, Msg};
+use crate::{http_cache, trash, CleanupResult};
+
+pub const IMAGE_DIR: &str = "images";
+pub const LOG_DIR: &str = "logs";
+const MIN_KEPT_ARTICLES: i64 = 50;
+// Articles removed per step while over the quota
+const EVICT_BATCH: i64 = 50;
+
+#[derive(Debug, Serialize)]
+pub struct StorageStats {
+    // Database pages in use
+    pub database_bytes: u64,
+    // Free pages inside the database file, reclaimed by VACUUM
+    pub database_free_bytes: u64,
+    pub wal_bytes: u64,
+    // Part of `database_bytes`
+    pub http_cache_bytes: u64,
+    pub image_cache_bytes: u64,
+    pub log_bytes: u64,
+    // What the quota is compared against: database pages in use, images and logs
+    pub used_bytes: u64,
+    // None = no quota
+    pub quota_bytes: Option<u64>,
+}
+
+fn data_subdir(name: &str) -> Option<PathBuf> {
+    get_data_dir().ok().map(|dir| Path::new(&dir).join(name))
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else { return 0 };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+// Pages in use and free pages, in bytes
+fn database_pages(conn: &Connection) -> Result<(u64, u64), String> {
+    let pragma = |name: &str| {
+        conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0))
+            .map(|v| v.max(0) as u64)
+            .map_err(|e| tr(Msg::DbQueryFailed, e))
+    };
+    let page_size = pragma("page_size")?;
+    let free = pragma("freelist_count")?;
+    let total = pragma("page_count")?;
+    Ok((total.saturating_sub(free) * page_size, free * page_size))
+}
+
+fn quota_bytes(conn: &Connection) -> Option<u64> {
+    let mb: u64 = get_setting(conn, "storage_quota_mb", "0").ok()?.parse().ok()?;
+    (mb > 0).then_some(mb * 1024 * 1024)
+}
+
+fn used_bytes(conn: &Connection) -> Result<u64, String> {
+    let folders: u64 = [IMAGE_DIR, LOG_DIR].into_iter().filter_map(data_subdir).map(|dir| dir_size(&dir)).sum();
+    Ok(database_pages(conn)?.0 + folders)
+}
+
+pub fn stats(conn: &Connection) -> Result<StorageStats, String> {
+    let (database_bytes, database_free_bytes) = database_pages(conn)?;
+    let wal_bytes = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .and_then(|path| std::fs::metadata(format!("{}-wal", path)).ok())
+        .map_or(0, |meta| meta.len());
+    let http_cache_bytes: i64 = conn
+        .query_row("SELECT COALESCE(SUM(LENGTH(body)), 0) FROM http_cache", [], |row| row.get(0))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    let image_cache_bytes = data_subdir(IMAGE_DIR).map_or(0, |dir| dir_size(&dir));
+    let log_bytes = data_subdir(LOG_DIR).map_or(0, |dir| dir_size(&dir));
+    Ok(StorageStats {
+        database_bytes,
+        database_free_bytes,
+        wal_bytes,
+        http_cache_bytes: http_cache_bytes.max(0) as u64,
+        image_cache_bytes,
+        log_bytes,
+        used_bytes: database_bytes + image_cache_bytes + log_bytes,
+        quota_bytes: quota_bytes(conn),
+    })
+}
+
+// Cached images, oldest first
+fn cached_images() -> Vec<PathBuf> {
+    let Some(dir) = data_subdir(IMAGE_DIR) else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+            Some((meta.modified().unwrap_or(std::time::UNIX_EPOCH), entry.path()))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+// Evict caches, then tighten retention, until usage is within the quota; counts go into `result`
+pub fn enforce_quota(conn: &Connection, result: &mut CleanupResult) -> Result<(), String> {
+    let Some(quota) = quota_bytes(conn) else { return Ok(()) };
+    if used_bytes(conn)? <= quota {
+        return Ok(());
+    }
+
+    result.cache_purged += http_cache::clear(conn)?;
+    for image in cached_images() {
+        if used_bytes(conn)? <= quota {
+            break;
+        }
+        if let Err(e) = std::fs::remove_file(&image) {
+            eprintln!("Failed to remove cached image {}: {}", image.display(), e);
+        }
+    }
+    if used_bytes(conn)? > quota {
+        result.trash_purged += trash::empty(conn)?;
+    }
+    let mut removed = 0;
+    while used_bytes(conn)? > quota {
+        let stored: i64 = conn
+            .query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))
+            .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+        let batch = EVICT_BATCH.min(stored - MIN_KEPT_ARTICLES);
+        if batch <= 0 {
+            break;
+        }
+        // Through the trash so their URLs are remembered and not crawled again
+        let moved = trash::move_articles(conn, REMOVABLE_OLDEST, batch)?;
+        trash::empty(conn)?;
+        if moved == 0 {
+            break;
+        }
+        removed += moved;
+    }
+    result.deleted += removed;
+    if removed > 0 {
+        delete_article_orphans(conn)?;
+    }
+
+    conn.execute("VACUUM", []).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set_setting;
+
+    #[test]
+    fn quota_is_off_unless_a_positive_size_is_set() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(quota_bytes(&conn), None);
+        set_setting(&conn, "storage_quota_mb", "-1").unwrap();
+        assert_eq!(quota_bytes(&conn), None);
+        set_setting(&conn, "storage_quota_mb", "0").unwrap();
+        assert_eq!(quota_bytes(&conn), None);
+
+        // Without a quota nothing is evicted
+        let mut result = CleanupResult { deleted: 0, trash_purged: 0, cache_purged: 0 };
+        enforce_quota(&conn, &mut result).unwrap();
+        assert_eq!((result.deleted, result.trash_purged, result.cache_purged), (0, 0, 0));
+
+        set_setting(&conn, "storage_quota_mb", "2").unwrap();
+        assert_eq!(quota_bytes(&conn), Some(2 * 1024 * 1024));
+    }
+
+    #[test]
+    fn sizes_of_folders_and_database_pages() {
+        let dir = std::env::temp_dir().join(format!("news-storage-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.png"), [0u8; 100]).unwrap();
+        std::fs::write(dir.join("nested").join("b.png"), [0u8; 50]).unwrap();
+        assert_eq!(dir_size(&dir), 150);
+        assert_eq!(dir_size(&dir.join("missing")), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let (used, _) = database_pages(&conn).unwrap();
+        assert!(used > 0);
+        conn.execute("CREATE TABLE filler (data BLOB)", []).unwrap();
+        conn.execute("INSERT INTO filler VALUES (zeroblob(200000))", []).unwrap();
+        assert!(database_pages(&conn).unwrap().0 >= used + 200_000);
+        conn.execute("DELETE FROM filler", []).unwrap();
+        assert!(database_pages(&conn).unwrap().1 >= 190_000, "deleted rows leave free pages");
+    }
+}
@@ -0,0 +1,162 @@
+// Entry points for the integration tests in `tests/`, behind the
+// `test-support` feature so they never ship in the app.
+//
+// The tests run a real crawl against an in-memory database and a local
+// HTTP server serving fixture feeds: `memory_db` gives a migrated
+// `:memory:` connection, `HttpClients::with_client` sends every request
+// through one un-proxied client, and `RecordedEvents` stands in for the
+// app's event bus.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+pub use crate::crawler::{CrawlEvents, CrawlResult, HttpClients};
+pub use crate::db_recovery::RecoveryReport;
+pub use crate::error::Error;
+pub use crate::github_trending::{Since, StarFilter, TrendingConfig};
+pub use crate::reader::ReaderView;
+pub use crate::rules::{Action, Condition, Field, Operator, Rule, RuleMatch};
+pub use crate::search_index::SearchScope;
+pub use crate::{init_db_with_conn, Article, ListQuery, ListResponse};
+
+// A migrated, empty database that lives as long as the connection
+pub fn memory_db() -> Result<Mutex<Connection>, rusqlite::Error> {
+    Ok(Mutex::new(init_db_with_conn(Connection::open_in_memory()?)?))
+}
+
+// An active source, `source_type` "RSS" or "WEB" as in the sources table
+pub fn add_source(conn: &Connection, name: &str, url: &str, source_type: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO sources (id, name, url, source_type, is_active) VALUES (?1, ?2, ?3, ?4, 1)",
+        params![uuid::Uuid::new_v4().to_string(), name, url, source_type],
+    )?;
+    Ok(())
+}
+
+// `source_trending_set`: create or reconfigure a GitHub trending source
+pub fn set_trending_source(conn: &Connection, name: &str, config: &TrendingConfig) -> Result<TrendingConfig, String> {
+    crate::github_trending::save(conn, name, config)
+}
+
+// `rule_save`
+pub fn save_rule(conn: &Connection, rule: Rule) -> Result<Rule, String> {
+    crate::rules::save(conn, rule)
+}
+
+// `rule_matches`
+pub fn rule_matches(conn: &Connection, id: &str) -> Result<Vec<RuleMatch>, String> {
+    crate::rules::matches(conn, id)
+}
+
+// `rule_test`: what `rule` would match among the `limit` most recent articles
+pub fn test_rule(conn: &Connection, rule: &Rule, limit: usize) -> Result<Vec<Article>, String> {
+    crate::rules::test(conn, rule, limit)
+}
+
+// The database file at `path` as the app opens it at startup, restored from a backup when damaged
+pub fn open_db(path: &str) -> Result<(Mutex<Connection>, Option<RecoveryReport>), rusqlite::Error> {
+    let (conn, report) = crate::db_recovery::open(path)?;
+    Ok((Mutex::new(conn), report))
+}
+
+// The scheduler's daily backup
+pub fn backup_db(conn: &Connection) -> Result<(), String> {
+    crate::db_recovery::backup(conn)
+}
+
+// `manual_add`: fetch a page and store it; None if the URL is already stored
+pub async fn add_url(db: &Mutex<Connection>, http: &HttpClients, url: &str) -> Result<Option<Article>, Error> {
+    crate::add_article_from_url(db, http, url, "手动添加").await
+}
+
+// `article_reader`, with the image cache in `image_dir`
+pub async fn reader(
+    db: &Mutex<Connection>,
+    http: &HttpClients,
+    id: &str,
+    image_dir: &std::path::Path,
+) -> Result<ReaderView, Error> {
+    let article = crate::db::query_article(&*db.lock()?, id)?.ok_or_else(|| Error::Other(id.to_string()))?;
+    Ok(crate::reader::render(db, http, &article, image_dir).await)
+}
+
+// The scheduler's wait between ticks, checking for a suspend every `wake_check` against `now`
+pub async fn scheduler_wait(
+    duration: Duration,
+    wake_check: Duration,
+    now: impl Fn() -> chrono::DateTime<chrono::Utc>,
+) -> Option<Duration> {
+    crate::scheduler::wait_in_slices(duration, wake_check, now).await
+}
+
+// `low_data_mode` as `settings_update` saves and applies it
+pub fn set_low_data_mode(conn: &Connection, enabled: bool) -> Result<(), Error> {
+    crate::set_setting(conn, "low_data_mode", &enabled.to_string())?;
+    crate::low_data::set(enabled);
+    Ok(())
+}
+
+// Minutes the scheduler waits between crawls under `http`'s crawl profile
+pub fn crawl_interval_minutes(conn: &Connection, http: &HttpClients) -> u64 {
+    crate::scheduler::crawl_interval_minutes(conn, http.profile)
+}
+
+pub async fn crawl(events: &impl CrawlEvents, db: &Mutex<Connection>, http: &HttpClients) -> Result<CrawlResult, Error> {
+    crate::run_crawl(events, db, http).await
+}
+
+// One scheduler run of night-batch mode's deferred work, whether or not its window is open
+pub async fn night_batch(db: &Mutex<Connection>, http: &HttpClients) -> Result<usize, String> {
+    crate::night_batch::run(db, http).await
+}
+
+// Full-text search, as the search box runs it
+pub fn search(conn: &Connection, keyword: &str) -> Result<Vec<Article>, Error> {
+    crate::query_search(conn, keyword, &SearchScope::default())
+}
+
+// Search limited to bookmarks, annotated articles and / or a source
+pub fn search_in(conn: &Connection, keyword: &str, scope: &SearchScope) -> Result<Vec<Article>, Error> {
+    crate::query_search(conn, keyword, scope)
+}
+
+// One page of `articles_list`
+pub fn list(conn: &Connection, query: &ListQuery) -> Result<ListResponse, Error> {
+    crate::query_articles_page(conn, query, None)
+}
+
+// `articles_get_many`
+pub fn articles_by_ids(conn: &Connection, ids: &[String]) -> Result<Vec<Article>, Error> {
+    crate::query_articles_by_ids(conn, ids)
+}
+
+pub fn article_by_url(conn: &Connection, url: &str) -> Result<Option<Article>, Error> {
+    let id: Option<String> = conn
+        .query_row("SELECT id FROM articles WHERE url = ?1", params![url], |row| row.get(0))
+        .optional()
+        .map_err(Error::Query)?;
+    match id {
+        Some(id) => crate::query_article(conn, &id),
+        None => Ok(None),
+    }
+}
+
+// Events a crawl emitted, in order, with their JSON payloads
+#[derive(Default)]
+pub struct RecordedEvents(Mutex<Vec<(String, serde_json::Value)>>);
+
+impl RecordedEvents {
+    pub fn take(&self) -> Vec<(String, serde_json::Value)> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl CrawlEvents for RecordedEvents {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        let payload = serde_json::to_value(payload).unwrap_or(serde_json::Value::Null);
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push((event.to_string(), payload));
+    }
+}
@@ -0,0 +1,211 @@
+// Full-text article translation through the configured AI provider.
+//
+// The translation is cached on the article row together with the target
+// language and a hash of the source content, so each article is paid for
+// once per language and re-translated only after its content changes.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::i18n::{t, tr, Lang, Msg};
+use crate::ai::{Ai, AiConfig, ChatProvider, ChatRequest};
+use crate::{glossary, revisions};
+
+// Characters per request; long articles are translated paragraph-aligned in chunks
+const CHUNK_CHARS: usize = 3000;
+
+#[derive(Debug, Serialize)]
+pub struct Translation {
+    pub id: String,
+    pub lang: String,
+    pub title: String,
+    pub content: String,
+    // Served from the cache, or the content was already in the target language
+    pub cached: bool,
+}
+
+struct Source {
+    title: String,
+    content: String,
+    translated_title: Option<String>,
+    translated_content: Option<String>,
+    translation_lang: Option<String>,
+    translation_hash: Option<String>,
+}
+
+fn language_name(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Zh => "简体中文",
+        Lang::En => "English",
+    }
+}
+
+// Rough check so Chinese articles aren't sent off to be "translated" into Chinese
+fn is_in_language(text: &str, lang: Lang) -> bool {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return true;
+    }
+    let cjk = letters.iter().filter(|c| ('\u{4e00}'..='\u{9fff}').contains(*c)).count();
+    let cjk_ratio = cjk as f64 / letters.len() as f64;
+    match lang {
+        Lang::Zh => cjk_ratio > 0.3,
+        Lang::En => cjk_ratio < 0.05,
+    }
+}
+
+// Split on paragraph boundaries into pieces of at most CHUNK_CHARS (longer paragraphs are cut)
+fn chunks(content: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in content.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = paragraph.chars().collect();
+        for piece in chars.chunks(CHUNK_CHARS) {
+            let piece: String = piece.iter().collect();
+            if !current.is_empty() && current.chars().count() + piece.chars().count() > CHUNK_CHARS {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&piece);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+async fn translate_text(ai: &Ai<'_>, glossary: &[glossary::GlossaryEntry], text: &str, lang: Lang) -> Result<String, String> {
+    let request = ChatRequest {
+        system: format!(
+            "Translate the user's text into {}. Keep the paragraph breaks, names and technical terms; output only the translation.{}",
+            language_name(lang),
+            glossary::prompt_hint(glossary, text, lang)
+        ),
+        user: text.to_string(),
+        max_tokens: None,
+        timeout: std::time::Duration::from_secs(120),
+    };
+    ai.complete(&request).await
+}
+
+fn load_source(conn: &Connection, id: &str) -> Result<Source, String> {
+    conn.query_row(
+        "SELECT title, COALESCE(content, ''), translated_title, translated_content, translation_lang, translation_hash
+         FROM articles WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Source {
+                title: row.get(0)?,
+                content: row.get(1)?,
+                translated_title: row.get(2)?,
+                translated_content: row.get(3)?,
+                translation_lang: row.get(4)?,
+                translation_hash: row.get(5)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| tr(Msg::DbQueryFailed, e))?
+    .ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+// Translate an article into `lang`, reusing the cached translation unless `force`
+pub async fn translate_article(
+    db: &Mutex<Connection>,
+    client: &reqwest::Client,
+    id: &str,
+    lang: Lang,
+    force: bool,
+) -> Result<Translation, String> {
+    let (source, ai_config, glossary) = {
+        let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+        (load_source(&conn, id)?, AiConfig::load(&conn).ok(), glossary::list(&conn)?)
+    }; // Release the lock during AI calls
+
+    let hash = revisions::content_hash(&source.content);
+    let translation = |title: String, content: String, cached: bool| Translation {
+        id: id.to_string(),
+        lang: lang.code().to_string(),
+        title,
+        content,
+        cached,
+    };
+
+    if !force && source.translation_lang.as_deref() == Some(lang.code()) && source.translation_hash.as_deref() == Some(hash.as_str()) {
+        if let (Some(title), Some(content)) = (source.translated_title, source.translated_content) {
+            return Ok(translation(title, content, true));
+        }
+    }
+    if is_in_language(&format!("{} {}", source.title, source.content), lang) {
+        return Ok(translation(source.title, source.content, true));
+    }
+    let config = ai_config.ok_or_else(|| t(Msg::AiNotConfigured))?;
+
+    let ai = Ai::new(client, &config);
+    let title = translate_text(&ai, &glossary, &source.title, lang).await?;
+    let mut parts = Vec::new();
+    for chunk in chunks(&source.content) {
+        parts.push(translate_text(&ai, &glossary, &chunk, lang).await?);
+    }
+    let content = parts.join("\n\n");
+
+    let conn = db.lock().map_err(|e| tr(Msg::DbLockPoisoned, e))?;
+    conn.execute(
+        "UPDATE articles SET translated_title = ?1, translated_content = ?2, translation_lang = ?3, translation_hash = ?4
+         WHERE id = ?5",
+        params![title, content, lang.code(), hash, id],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+
+    Ok(translation(title, content, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_check_and_chunking() {
+        assert!(is_in_language("大模型推理速度提升了三倍 with vLLM", Lang::Zh));
+        assert!(!is_in_language("Inference got three times faster", Lang::Zh));
+        assert!(is_in_language("Inference got three times faster", Lang::En));
+        assert!(is_in_language("2025 — 42%", Lang::En), "nothing to translate");
+
+        assert_eq!(chunks("One.\n\n\n\nTwo."), ["One.\n\nTwo."]);
+        let long = "x".repeat(CHUNK_CHARS + 10);
+        let parts = chunks(&format!("Intro.\n\n{}", long));
+        assert_eq!(parts.len(), 3, "an over-long paragraph is cut and doesn't join the intro");
+        assert_eq!(parts[0], "Intro.");
+        assert!(parts.iter().all(|p| p.chars().count() <= CHUNK_CHARS));
+        assert_eq!(parts[2], "x".repeat(10));
+        assert!(chunks("  \n\n ").is_empty());
+    }
+
+    #[test]
+    fn stored_translation_is_reused_until_content_changes() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO articles (id, title, url, source, content, translated_title, translated_content, translation_lang, translation_hash)
+             VALUES ('a1', 'Faster inference', 'https://example.com/a1', 'Blog', 'Three times faster.', '推理更快', '快了三倍。', 'zh', ?1)",
+            params![revisions::content_hash("Three times faster.")],
+        )
+        .unwrap();
+        let db = Mutex::new(conn);
+        let client = reqwest::Client::new();
+
+        let cached = tauri::async_runtime::block_on(translate_article(&db, &client, "a1", Lang::Zh, false)).unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.title, "推理更快");
+
+        let english = tauri::async_runtime::block_on(translate_article(&db, &client, "a1", Lang::En, false)).unwrap();
+        assert!(english.cached, "already in English");
+        assert_eq!(english.content, "Three times faster.");
+
+        assert!(tauri::async_runtime::block_on(translate_article(&db, &client, "missing", Lang::Zh, false)).is_err());
+    }
+}
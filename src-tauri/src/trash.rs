@@ -0,0 +1,253 @@
+// Trash for articles removed by retention.
+//
+// Cleanup doesn't drop articles outright: the rows are copied into
+// `articles_trash` with a `deleted_at` stamp and kept for
+// `trash_retention_days` (0 = until the trash is emptied), so an article
+// cleaned up before it was bookmarked can still be restored. The trash
+// copies every column `articles` has at the time, adding missing ones as
+// new migrations appear, which keeps this module free of the column list.
+// Revisions, edits and watch matches of trashed articles are kept; their
+// syndication signatures are not, so a restored article isn't compared
+// against new copies again.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::i18n::{t, tr, Msg};
+use crate::{datetime, ensure_column, seen_urls};
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS articles_trash (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            deleted_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_articles_trash_deleted ON articles_trash(deleted_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashedArticle {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub source: String,
+    pub category: String,
+    pub published_at: String,
+    pub deleted_at: String,
+    // When retention removes it for good; None = kept until emptied
+    pub purge_at: Option<String>,
+}
+
+// Columns of `articles`, each also present in the trash, as a SQL list
+fn shared_columns(conn: &Connection) -> Result<String, String> {
+    let columns: Vec<(String, String, Option<String>)> = conn
+        .prepare("PRAGMA table_info(articles)")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(1)?, row.get(2)?, row.get(4)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    for (name, column_type, default) in &columns {
+        // Keep the default so rows trashed before the column existed restore
+        // into NOT NULL columns
+        let definition = match default {
+            Some(default) => format!("{} DEFAULT {}", column_type, default),
+            None => column_type.clone(),
+        };
+        ensure_column(conn, "articles_trash", name, &definition).map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    }
+    Ok(columns.into_iter().map(|(name, _, _)| name).collect::<Vec<_>>().join(", "))
+}
+
+// Move the articles whose rowids `selection` returns (bound to ?1 = `limit`)
+// into the trash, together with their FTS rows, and record their URLs so
+// crawls don't bring them back. Returns how many moved
+pub fn move_articles(conn: &Connection, selection: &str, limit: i64) -> Result<usize, String> {
+    let columns = shared_columns(conn)?;
+    let urls: Vec<String> = conn
+        .prepare(&format!("SELECT url FROM articles WHERE rowid IN ({})", selection))
+        .and_then(|mut stmt| stmt.query_map(params![limit], |row| row.get(0))?.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    seen_urls::record(conn, &urls)?;
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO articles_trash ({cols}, deleted_at)
+             SELECT {cols}, ?2 FROM articles WHERE rowid IN ({selection})",
+            cols = columns,
+            selection = selection
+        ),
+        params![limit, chrono::Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    // FTS rows first, while the selection still matches the same articles
+    conn.execute(&format!("DELETE FROM articles_fts WHERE rowid IN ({})", selection), params![limit])
+        .map_err(|e| tr(Msg::FtsDeleteFailed, e))?;
+    conn.execute(&format!("DELETE FROM articles WHERE rowid IN ({})", selection), params![limit])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+// Trashed articles, most recently deleted first
+pub fn list(conn: &Connection, retention_days: u32) -> Result<Vec<TrashedArticle>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, url, source, category, published_at, deleted_at
+             FROM articles_trash ORDER BY deleted_at DESC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                row.get::<_, String>(6)?,
+            ))
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, title, url, source, category, published_at, deleted_at)| {
+            let purge_at = (retention_days > 0)
+                .then(|| chrono::DateTime::parse_from_rfc3339(&deleted_at).ok())
+                .flatten()
+                .map(|deleted| (deleted + chrono::Duration::days(retention_days as i64)).to_rfc3339());
+            TrashedArticle {
+                id,
+                title,
+                url,
+                source,
+                category,
+                published_at: datetime::to_display(&published_at),
+                deleted_at: datetime::to_display(&deleted_at),
+                purge_at: purge_at.as_deref().map(datetime::to_display),
+            }
+        })
+        .collect())
+}
+
+// Put an article back in the feed. Its fetch time is reset to now so the
+// next retention run doesn't trash it again straight away
+pub fn restore(conn: &Connection, id: &str) -> Result<(), String> {
+    let url: String = conn
+        .query_row("SELECT url FROM articles_trash WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .ok_or_else(|| t(Msg::ArticleNotFound))?;
+    // A later crawl may have stored the same story again
+    let live = conn
+        .query_row("SELECT 1 FROM articles WHERE url = ?1 OR id = ?2", params![url, id], |_| Ok(()))
+        .optional()
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+    if live.is_some() {
+        return Err(tr(Msg::TrashRestoreConflict, url));
+    }
+
+    let columns = shared_columns(conn)?;
+    let tx = conn.unchecked_transaction().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    tx.execute(
+        &format!("INSERT INTO articles ({cols}) SELECT {cols} FROM articles_trash WHERE id = ?1", cols = columns),
+        params![id],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    let rowid = tx.last_insert_rowid();
+    tx.execute(
+        "UPDATE articles SET fetched_at = ?1 WHERE rowid = ?2",
+        params![chrono::Utc::now().to_rfc3339(), rowid],
+    )
+    .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    tx.execute(
+        "INSERT INTO articles_fts (rowid, title, summary, content)
+         SELECT rowid, cjk_segment(title), cjk_segment(summary), cjk_segment(content) FROM articles WHERE rowid = ?1",
+        params![rowid],
+    )
+    .map_err(|e| tr(Msg::FtsInsertFailed, e))?;
+    tx.execute("DELETE FROM articles_trash WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    seen_urls::forget(&tx, &url)?;
+    tx.commit().map_err(|e| tr(Msg::DbUpdateFailed, e))
+}
+
+// Drop entries deleted more than `retention_days` ago (0 = keep them)
+pub fn purge_expired(conn: &Connection, retention_days: u32) -> Result<usize, String> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(retention_days as i64)).to_rfc3339();
+    conn.execute("DELETE FROM articles_trash WHERE deleted_at < ?1", params![cutoff])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+pub fn empty(conn: &Connection) -> Result<usize, String> {
+    conn.execute("DELETE FROM articles_trash", [])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?1, '', '', ?1, 'Blog', 'AI', '2025-03-01T12:00:00+00:00', '2025-03-01T12:00:00+00:00')",
+            params![id],
+        )
+        .unwrap();
+    }
+
+    fn trash(conn: &Connection, id: &str) -> usize {
+        move_articles(conn, &format!("SELECT rowid FROM articles WHERE id = '{}' LIMIT ?1", id), 10).unwrap()
+    }
+
+    #[test]
+    fn trashed_articles_restore_with_a_fresh_fetch_time() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "https://example.com/a1");
+        add_article(&conn, "https://example.com/a2");
+        assert_eq!(trash(&conn, "https://example.com/a1"), 1);
+
+        let trashed = list(&conn, 30).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].title, "https://example.com/a1");
+        assert!(trashed[0].purge_at.is_some());
+        assert!(list(&conn, 0).unwrap()[0].purge_at.is_none(), "kept until emptied");
+
+        restore(&conn, "https://example.com/a1").unwrap();
+        assert!(list(&conn, 30).unwrap().is_empty());
+        let fetched_at: String = conn
+            .query_row("SELECT fetched_at FROM articles WHERE id = 'https://example.com/a1'", [], |row| row.get(0))
+            .unwrap();
+        assert!(fetched_at.as_str() > "2025-03-02", "{}", fetched_at);
+        assert!(restore(&conn, "https://example.com/a1").is_err(), "no longer in the trash");
+    }
+
+    #[test]
+    fn restore_refuses_a_story_stored_again_and_retention_purges() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "https://example.com/a1");
+        trash(&conn, "https://example.com/a1");
+        add_article(&conn, "https://example.com/a1");
+        assert!(restore(&conn, "https://example.com/a1").is_err());
+
+        conn.execute("UPDATE articles_trash SET deleted_at = '2000-01-01T00:00:00+00:00'", []).unwrap();
+        assert_eq!(purge_expired(&conn, 0).unwrap(), 0);
+        assert_eq!(purge_expired(&conn, 30).unwrap(), 1);
+
+        add_article(&conn, "https://example.com/a2");
+        trash(&conn, "https://example.com/a2");
+        assert_eq!(empty(&conn).unwrap(), 1);
+    }
+}
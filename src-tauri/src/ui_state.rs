@@ -0,0 +1,137 @@
+// Session state of the UI, so reopening the app lands where it was left.
+//
+// A small key-value store the frontend writes as the user moves around
+// (`ui_state_set`) and reads once on start (`ui_state_get`): the selected
+// category, the article the feed was scrolled to, the article that was open.
+// The backend doesn't interpret the values, any JSON goes; the frontend
+// validates what it gets back, since e.g. an anchored article may have been
+// cleaned up meanwhile. Setting a key to null removes it. This is state of
+// one machine, so cloud sync leaves it out.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::i18n::{tr, Msg};
+
+const MAX_KEY_LEN: usize = 64;
+// Per value, as JSON
+const MAX_VALUE_BYTES: usize = 16 * 1024;
+const MAX_KEYS: i64 = 200;
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ui_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// The requested keys that are set (all keys when `keys` is empty)
+pub fn get(conn: &Connection, keys: &[String]) -> Result<HashMap<String, serde_json::Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM ui_state")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(rows
+        .into_iter()
+        .filter(|(key, _)| keys.is_empty() || keys.contains(key))
+        .filter_map(|(key, value)| serde_json::from_str(&value).ok().map(|value| (key, value)))
+        .collect())
+}
+
+// Store every value, null removing the key; nothing is stored when one of them is invalid
+pub fn set(conn: &mut Connection, values: &HashMap<String, serde_json::Value>) -> Result<(), String> {
+    let mut rows = Vec::new();
+    for (key, value) in values {
+        let key = key.trim();
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(tr(Msg::UiStateInvalid, key));
+        }
+        let json = (!value.is_null()).then(|| value.to_string());
+        if json.as_ref().is_some_and(|json| json.len() > MAX_VALUE_BYTES) {
+            return Err(tr(Msg::UiStateInvalid, format!("{}: > {} KB", key, MAX_VALUE_BYTES / 1024)));
+        }
+        rows.push((key, json));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let tx = conn.transaction().map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    for (key, json) in rows {
+        match json {
+            Some(json) => tx.execute(
+                "INSERT INTO ui_state (key, value, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                params![key, json, now],
+            ),
+            None => tx.execute("DELETE FROM ui_state WHERE key = ?1", params![key]),
+        }
+        .map_err(|e| tr(Msg::DbUpdateFailed, e))?;
+    }
+    // Keys the frontend stopped using would otherwise pile up
+    tx.execute(
+        "DELETE FROM ui_state WHERE key NOT IN (SELECT key FROM ui_state ORDER BY updated_at DESC LIMIT ?1)",
+        params![MAX_KEYS],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    tx.commit().map_err(|e| tr(Msg::DbUpdateFailed, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, serde_json::Value)]) -> HashMap<String, serde_json::Value> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+    }
+
+    #[test]
+    fn values_round_trip_and_null_removes() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        set(&mut conn, &values(&[("category", json!("AI")), ("anchor", json!({"id": "a1", "offset": 120}))])).unwrap();
+        let stored = get(&conn, &[]).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored["anchor"], json!({"id": "a1", "offset": 120}));
+        assert_eq!(get(&conn, &["category".to_string(), "open".to_string()]).unwrap().len(), 1);
+
+        set(&mut conn, &values(&[("category", serde_json::Value::Null), ("category2", json!(1))])).unwrap();
+        let stored = get(&conn, &[]).unwrap();
+        assert!(!stored.contains_key("category"));
+        assert_eq!(stored["category2"], json!(1));
+    }
+
+    #[test]
+    fn one_invalid_value_stores_nothing() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let too_big = json!("x".repeat(MAX_VALUE_BYTES));
+        assert!(set(&mut conn, &values(&[("ok", json!(true)), ("big", too_big)])).is_err());
+        assert!(set(&mut conn, &values(&[("ok", json!(true)), ("  ", json!(1))])).is_err());
+        assert!(set(&mut conn, &values(&[(&"k".repeat(MAX_KEY_LEN + 1), json!(1))])).is_err());
+        assert!(get(&conn, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn oldest_keys_beyond_the_limit_are_dropped() {
+        let mut conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        conn.execute(
+            "INSERT INTO ui_state (key, value, updated_at)
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+             SELECT 'old' || i, '0', '2020-01-01T00:00:00+00:00' FROM n",
+            params![MAX_KEYS],
+        )
+        .unwrap();
+        set(&mut conn, &values(&[("category", json!("AI"))])).unwrap();
+        let stored = get(&conn, &[]).unwrap();
+        assert_eq!(stored.len() as i64, MAX_KEYS);
+        assert!(stored.contains_key("category"));
+    }
+}
@@ -0,0 +1,349 @@
+// Watchlists: organizations, models or people followed across all sources.
+//
+// A watch has a name plus aliases ("Anthropic", "Claude"). Articles whose
+// title or summary mention any of them are linked in `watchlist_matches`
+// and tagged with the watch name, so they also show up under tag filters
+// and in exports. New articles are matched after every crawl; saving a
+// watch re-matches existing articles. Watches with `notify` report their
+// new matches as `app://watchlist:match` events.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::annotations;
+use crate::i18n::{t, tr, Msg};
+use crate::{article_from_row, query_article, Article, ListResponse, ARTICLE_COLUMNS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchKind {
+    Organization,
+    Model,
+    Person,
+}
+
+impl WatchKind {
+    fn code(self) -> &'static str {
+        match self {
+            WatchKind::Organization => "organization",
+            WatchKind::Model => "model",
+            WatchKind::Person => "person",
+        }
+    }
+
+    fn from_code(code: &str) -> Self {
+        match code {
+            "model" => WatchKind::Model,
+            "person" => WatchKind::Person,
+            _ => WatchKind::Organization,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watch {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: WatchKind,
+    // Other spellings that count as a mention; the name always does
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub notify: bool,
+    // Filled in by `list`
+    #[serde(default)]
+    pub match_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchlistMatchEvent {
+    pub watch_id: String,
+    pub name: String,
+    pub articles: Vec<Article>,
+}
+
+// Whether lowercased `term` occurs in lowercased `haystack`. Latin-script terms
+// must stand alone ("Meta" shouldn't match "metadata"); Chinese terms match anywhere
+pub(crate) fn mentions(haystack: &str, term: &str) -> bool {
+    if term.is_empty() {
+        return false;
+    }
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    haystack.match_indices(term).any(|(pos, _)| {
+        let before = haystack[..pos].chars().next_back();
+        let after = haystack[pos + term.len()..].chars().next();
+        let joined_before = is_word(term.chars().next()) && is_word(before);
+        let joined_after = is_word(term.chars().next_back()) && is_word(after);
+        !joined_before && !joined_after
+    })
+}
+
+impl Watch {
+    fn matches(&self, title: &str, summary: &str) -> bool {
+        let haystack = format!("{} {}", title, summary).to_lowercase();
+        std::iter::once(&self.name)
+            .chain(self.aliases.iter())
+            .any(|term| mentions(&haystack, &term.trim().to_lowercase()))
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS watchlists (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            aliases TEXT NOT NULL DEFAULT '[]',
+            notify INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS watchlist_matches (
+            watch_id TEXT NOT NULL,
+            article_id TEXT NOT NULL,
+            matched_at TEXT NOT NULL,
+            PRIMARY KEY (watch_id, article_id)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_watchlist_matches_article ON watchlist_matches(article_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Watch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT w.id, w.name, w.kind, w.aliases, w.notify,
+                (SELECT COUNT(*) FROM watchlist_matches m WHERE m.watch_id = w.id)
+             FROM watchlists w ORDER BY w.name COLLATE NOCASE ASC",
+        )
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let watches = stmt
+        .query_map([], |row| {
+            let aliases: String = row.get(3)?;
+            Ok(Watch {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: WatchKind::from_code(&row.get::<_, String>(2)?),
+                aliases: serde_json::from_str(&aliases).unwrap_or_default(),
+                notify: row.get::<_, i32>(4)? == 1,
+                match_count: row.get(5)?,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(watches)
+}
+
+fn record_match(conn: &Connection, watch: &Watch, article_id: &str) -> Result<bool, String> {
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO watchlist_matches (watch_id, article_id, matched_at) VALUES (?1, ?2, ?3)",
+            params![watch.id, article_id, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    annotations::add_tag(conn, article_id, &watch.name)?;
+    Ok(inserted > 0)
+}
+
+// Insert when `id` is empty, otherwise replace the existing watch; matches are rebuilt
+// from all stored articles
+pub fn save(conn: &Connection, mut watch: Watch) -> Result<Watch, String> {
+    watch.name = watch.name.trim().to_string();
+    if watch.name.is_empty() {
+        return Err(t(Msg::WatchNameMissing));
+    }
+    if watch.id.is_empty() {
+        watch.id = uuid::Uuid::new_v4().to_string();
+    }
+    watch.aliases = watch
+        .aliases
+        .iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty() && !a.eq_ignore_ascii_case(&watch.name))
+        .collect();
+
+    conn.execute(
+        "INSERT OR REPLACE INTO watchlists (id, name, kind, aliases, notify) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            watch.id,
+            watch.name,
+            watch.kind.code(),
+            serde_json::to_string(&watch.aliases).unwrap_or_else(|_| "[]".to_string()),
+            watch.notify as i32,
+        ],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+
+    conn.execute("DELETE FROM watchlist_matches WHERE watch_id = ?1", params![watch.id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    let mut stmt = conn
+        .prepare("SELECT id, title, summary FROM articles")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let matching: Vec<String> = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?
+        .into_iter()
+        .filter(|(_, title, summary)| watch.matches(title, summary))
+        .map(|(id, _, _)| id)
+        .collect();
+    for article_id in &matching {
+        record_match(conn, &watch, article_id)?;
+    }
+    watch.match_count = matching.len() as i64;
+    Ok(watch)
+}
+
+// Tags already given to articles are left in place
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM watchlist_matches WHERE watch_id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    conn.execute("DELETE FROM watchlists WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Matches of articles no longer stored or in the trash
+pub fn delete_orphans(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM watchlist_matches WHERE article_id NOT IN (SELECT id FROM articles UNION ALL SELECT id FROM articles_trash)",
+        [],
+    )
+    .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Match newly crawled articles; returns the new matches of watches that notify
+pub fn match_new_articles(conn: &Connection, article_ids: &[String]) -> Result<Vec<WatchlistMatchEvent>, String> {
+    if article_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let watches = list(conn)?;
+    if watches.is_empty() {
+        return Ok(Vec::new());
+    }
+    let articles: Vec<Article> = article_ids
+        .iter()
+        .filter_map(|id| query_article(conn, id).ok().flatten())
+        .collect();
+
+    let mut events = Vec::new();
+    for watch in &watches {
+        let mut matched = Vec::new();
+        for article in articles.iter().filter(|a| watch.matches(&a.title, &a.summary)) {
+            if record_match(conn, watch, &article.id)? {
+                matched.push(article.clone());
+            }
+        }
+        if watch.notify && !matched.is_empty() {
+            events.push(WatchlistMatchEvent {
+                watch_id: watch.id.clone(),
+                name: watch.name.clone(),
+                articles: matched,
+            });
+        }
+    }
+    Ok(events)
+}
+
+// Matching articles of one watch, newest first
+pub fn feed(conn: &Connection, id: &str, page: usize, page_size: usize) -> Result<ListResponse, String> {
+    let page = page.max(1);
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM watchlist_matches WHERE watch_id = ?1", params![id], |row| row.get(0))
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?;
+
+    let sql = format!(
+        "SELECT {} FROM articles
+         INNER JOIN watchlist_matches m ON m.article_id = articles.id
+         WHERE m.watch_id = ?1
+         ORDER BY published_at DESC, fetched_at DESC
+         LIMIT ?2 OFFSET ?3",
+        ARTICLE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let items = stmt
+        .query_map(params![id, page_size as i64, ((page - 1) * page_size) as i64], article_from_row)
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+
+    Ok(ListResponse {
+        items,
+        total,
+        page,
+        page_size,
+        groups: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_article(conn: &Connection, id: &str, title: &str) {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at)
+             VALUES (?1, ?2, '', '', ?1, 'Blog', 'AI', '2025-03-01T12:00:00+00:00', '2025-03-01T12:00:00+00:00')",
+            params![id, title],
+        )
+        .unwrap();
+    }
+
+    fn watch(name: &str, aliases: &[&str], notify: bool) -> Watch {
+        Watch {
+            id: String::new(),
+            name: name.to_string(),
+            kind: WatchKind::Organization,
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            notify,
+            match_count: 0,
+        }
+    }
+
+    #[test]
+    fn latin_terms_must_stand_alone() {
+        assert!(mentions("meta releases llama", "meta"));
+        assert!(!mentions("metadata for llms", "meta"));
+        assert!(mentions("gpt-4o launches", "gpt-4o"));
+        assert!(mentions("智谱发布新模型", "智谱"));
+        assert!(mentions("openai智谱", "智谱"));
+        assert!(!mentions("anything", ""));
+    }
+
+    #[test]
+    fn saving_matches_existing_articles_and_new_ones_notify() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        add_article(&conn, "a1", "Anthropic raises new round");
+        add_article(&conn, "a2", "Claude gets a bigger context window");
+        add_article(&conn, "a3", "Metadata standards for datasets");
+        assert!(save(&conn, watch("  ", &[], false)).is_err());
+
+        let saved = save(&conn, watch(" Anthropic ", &["Claude", "anthropic", " "], true)).unwrap();
+        assert_eq!(saved.aliases, ["Claude"]);
+        assert_eq!(saved.match_count, 2);
+        assert_eq!(annotations::load_tags(&conn, "a2").unwrap(), ["Anthropic"]);
+
+        add_article(&conn, "a4", "Claude 4 benchmarks");
+        let ids = ["a3".to_string(), "a4".to_string()];
+        let events = match_new_articles(&conn, &ids).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].articles.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["a4"]);
+        assert!(match_new_articles(&conn, &ids).unwrap().is_empty(), "already matched");
+
+        let feed = feed(&conn, &saved.id, 1, 2).unwrap();
+        assert_eq!((feed.total, feed.items.len()), (3, 2));
+
+        delete(&conn, &saved.id).unwrap();
+        assert!(list(&conn).unwrap().is_empty());
+        assert_eq!(annotations::load_tags(&conn, "a2").unwrap(), ["Anthropic"], "tags stay");
+    }
+}
@@ -0,0 +1,313 @@
+// Outgoing Slack / Discord webhooks for newly crawled articles.
+//
+// Each webhook has its own filters (categories, keywords, minimum heat
+// score); after a crawl the inserted articles are matched against every
+// active webhook and posted with title, summary and link.
+
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tr, Msg};
+use crate::{query_article, Article, HttpClients};
+
+// Keeps a large first crawl from flooding the channel
+const MAX_POSTS_PER_RUN: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+}
+
+impl WebhookKind {
+    fn code(self) -> &'static str {
+        match self {
+            WebhookKind::Slack => "slack",
+            WebhookKind::Discord => "discord",
+        }
+    }
+
+    fn from_code(code: &str) -> Self {
+        match code {
+            "discord" => WebhookKind::Discord,
+            _ => WebhookKind::Slack,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: WebhookKind,
+    pub url: String,
+    // Empty list = no restriction
+    #[serde(default)]
+    pub categories: Vec<String>,
+    // Case-insensitive, any keyword in title or summary matches
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub min_heat_score: f64,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+impl Webhook {
+    fn matches(&self, article: &Article) -> bool {
+        if article.heat_score < self.min_heat_score {
+            return false;
+        }
+        if !self.categories.is_empty() && !self.categories.iter().any(|c| c == &article.category) {
+            return false;
+        }
+        if !self.keywords.is_empty() {
+            let haystack = format!("{} {}", article.title, article.summary).to_lowercase();
+            if !self.keywords.iter().any(|k| haystack.contains(&k.to_lowercase())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhooks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            url TEXT NOT NULL,
+            categories TEXT NOT NULL DEFAULT '[]',
+            keywords TEXT NOT NULL DEFAULT '[]',
+            min_heat_score REAL NOT NULL DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Webhook>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name, kind, url, categories, keywords, min_heat_score, is_active FROM webhooks ORDER BY name")
+        .map_err(|e| tr(Msg::DbPrepareFailed, e))?;
+    let hooks = stmt
+        .query_map([], |row| {
+            let categories: String = row.get(4)?;
+            let keywords: String = row.get(5)?;
+            Ok(Webhook {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: WebhookKind::from_code(&row.get::<_, String>(2)?),
+                url: row.get(3)?,
+                categories: serde_json::from_str(&categories).unwrap_or_default(),
+                keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+                min_heat_score: row.get(6)?,
+                is_active: row.get::<_, i32>(7)? == 1,
+            })
+        })
+        .map_err(|e| tr(Msg::DbQueryFailed, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| tr(Msg::DbCollectFailed, e))?;
+    Ok(hooks)
+}
+
+// Insert when `id` is empty, otherwise replace the existing row
+pub fn save(conn: &Connection, mut hook: Webhook) -> Result<Webhook, String> {
+    if hook.id.is_empty() {
+        hook.id = uuid::Uuid::new_v4().to_string();
+    }
+    let clean = |items: &[String]| -> Vec<String> {
+        items.iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+    };
+    hook.name = hook.name.trim().to_string();
+    hook.url = hook.url.trim().to_string();
+    hook.categories = clean(&hook.categories);
+    hook.keywords = clean(&hook.keywords);
+
+    conn.execute(
+        "INSERT OR REPLACE INTO webhooks (id, name, kind, url, categories, keywords, min_heat_score, is_active)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            hook.id,
+            hook.name,
+            hook.kind.code(),
+            hook.url,
+            serde_json::to_string(&hook.categories).unwrap_or_else(|_| "[]".to_string()),
+            serde_json::to_string(&hook.keywords).unwrap_or_else(|_| "[]".to_string()),
+            hook.min_heat_score,
+            hook.is_active as i32,
+        ],
+    )
+    .map_err(|e| tr(Msg::DbInsertFailed, e))?;
+    Ok(hook)
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])
+        .map_err(|e| tr(Msg::DbDeleteFailed, e))?;
+    Ok(())
+}
+
+// Post newly inserted articles to every matching webhook; failures are logged, not returned,
+// so a broken webhook never fails a crawl
+pub async fn notify_new_articles(db: &Mutex<Connection>, http: &HttpClients, article_ids: &[String]) {
+    if article_ids.is_empty() {
+        return;
+    }
+    let (hooks, articles) = {
+        let Ok(conn) = db.lock() else { return };
+        let hooks: Vec<Webhook> = list(&conn).unwrap_or_default().into_iter().filter(|h| h.is_active).collect();
+        if hooks.is_empty() {
+            return;
+        }
+        let articles: Vec<Article> = article_ids
+            .iter()
+            .filter_map(|id| query_article(&conn, id).ok().flatten())
+            .collect();
+        (hooks, articles)
+    }; // Release the lock during network calls
+
+    for hook in hooks {
+        for article in articles.iter().filter(|a| hook.matches(a)).take(MAX_POSTS_PER_RUN) {
+            if let Err(e) = post(http, &hook, article).await {
+                eprintln!("Webhook '{}' failed: {}", hook.name, e);
+                break;
+            }
+            // Discord allows ~5 requests per 2 seconds per webhook
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+}
+
+pub async fn post(http: &HttpClients, hook: &Webhook, article: &Article) -> Result<(), String> {
+    let response = http
+        .for_url(&hook.url)
+        .post(&hook.url)
+        .json(&payload(hook.kind, article))
+        .send()
+        .await
+        .map_err(|e| tr(Msg::WebhookSendFailed, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(tr(Msg::WebhookSendFailed, format!("({}) {}", status, body)));
+    }
+    Ok(())
+}
+
+pub fn test_article(conn: &Connection) -> Result<Article, String> {
+    let latest: String = conn
+        .query_row("SELECT id FROM articles ORDER BY fetched_at DESC LIMIT 1", [], |row| row.get(0))
+        .map_err(|_| t(Msg::ArticleNotFound))?;
+    query_article(conn, &latest)?.ok_or_else(|| t(Msg::ArticleNotFound))
+}
+
+fn payload(kind: WebhookKind, article: &Article) -> serde_json::Value {
+    let summary: String = article.summary.chars().take(300).collect();
+    match kind {
+        WebhookKind::Slack => serde_json::json!({
+            "text": format!("{} - {}", article.title, article.url),
+            "blocks": [
+                {
+                    "type": "section",
+                    "text": {
+                        "type": "mrkdwn",
+                        "text": format!("*<{}|{}>*\n{}", article.url, slack_escape(&article.title), slack_escape(&summary)),
+                    }
+                },
+                {
+                    "type": "context",
+                    "elements": [{ "type": "mrkdwn", "text": format!("{} · {}", slack_escape(&article.source), article.category) }]
+                }
+            ]
+        }),
+        WebhookKind::Discord => serde_json::json!({
+            "embeds": [{
+                "title": article.title.chars().take(256).collect::<String>(),
+                "url": article.url,
+                "description": summary,
+                "footer": { "text": format!("{} · {}", article.source, article.category) },
+            }]
+        }),
+    }
+}
+
+// Slack mrkdwn treats these as control characters
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(conn: &Connection, id: &str, title: &str, category: &str, heat_score: f64) -> Article {
+        conn.execute(
+            "INSERT INTO articles (id, title, summary, content, url, source, category, published_at, fetched_at, heat_score)
+             VALUES (?1, ?2, 'A summary', '', ?1, 'R&D <Blog>', ?3, ?4, ?4, ?5)",
+            params![id, title, category, chrono::Utc::now().to_rfc3339(), heat_score],
+        )
+        .unwrap();
+        query_article(conn, id).unwrap().unwrap()
+    }
+
+    fn hook(categories: &[&str], keywords: &[&str], min_heat_score: f64) -> Webhook {
+        Webhook {
+            id: String::new(),
+            name: " Team ".to_string(),
+            kind: WebhookKind::Discord,
+            url: " https://discord.example.com/hook ".to_string(),
+            categories: categories.iter().map(|s| s.to_string()).collect(),
+            keywords: keywords.iter().map(|s| s.to_string()).collect(),
+            min_heat_score,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn saved_webhooks_are_trimmed_and_listed() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let saved = save(&conn, hook(&[" AI ", ""], &["agents"], 10.0)).unwrap();
+        let listed = list(&conn).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, saved.id);
+        assert_eq!((listed[0].name.as_str(), listed[0].url.as_str()), ("Team", "https://discord.example.com/hook"));
+        assert_eq!(listed[0].categories, ["AI"]);
+        assert_eq!(listed[0].kind, WebhookKind::Discord);
+    }
+
+    #[test]
+    fn filters_on_category_keywords_and_heat() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let agents = article(&conn, "a1", "Building AGENTS with tools", "AI", 50.0);
+        let chips = article(&conn, "a2", "New accelerator chips", "Hardware", 5.0);
+
+        assert!(hook(&[], &[], 0.0).matches(&chips));
+        assert!(hook(&["AI"], &["agents"], 20.0).matches(&agents));
+        assert!(!hook(&["AI"], &[], 0.0).matches(&chips));
+        assert!(!hook(&[], &["agents"], 0.0).matches(&chips));
+        assert!(!hook(&[], &[], 20.0).matches(&chips));
+    }
+
+    #[test]
+    fn slack_text_is_escaped() {
+        let conn = crate::init_db_with_conn(Connection::open_in_memory().unwrap()).unwrap();
+        let article = article(&conn, "a1", "<b>Tools</b> & agents", "AI", 0.0);
+        let slack = payload(WebhookKind::Slack, &article);
+        let section = slack["blocks"][0]["text"]["text"].as_str().unwrap();
+        assert!(section.starts_with("*<a1|&lt;b&gt;Tools&lt;/b&gt; &amp; agents>*"), "{}", section);
+        assert_eq!(slack["blocks"][1]["elements"][0]["text"], "R&amp;D &lt;Blog&gt; · AI");
+        let discord = payload(WebhookKind::Discord, &article);
+        assert_eq!(discord["embeds"][0]["title"], "<b>Tools</b> & agents");
+    }
+}
@@ -0,0 +1,97 @@
+// Shared helpers for the integration tests: a local HTTP server for fixture
+// responses, and the crawl's HTTP clients pointed at it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ai_news_aggregator::test_support::HttpClients;
+use tiny_http::{Header, Response, Server};
+
+// Serves fixed responses by path until the test process exits; anything else is a 404.
+// Every request path is logged, in order
+pub struct MockServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start(routes: &[(&str, &str, String)]) -> Self {
+        Self::start_with_delays(routes, &[])
+    }
+
+    // The first request for each of `delayed` is answered only after its delay; later ones at once
+    pub fn start_with_delays(routes: &[(&str, &str, String)], delayed: &[(&str, Duration)]) -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("bind mock server"));
+        let addr = server.server_addr().to_ip().expect("mock server listens on TCP");
+        let routes: HashMap<String, (String, String)> = routes
+            .iter()
+            .map(|(path, content_type, body)| (path.to_string(), (content_type.to_string(), body.clone())))
+            .collect();
+        let mut delays: HashMap<String, Duration> =
+            delayed.iter().map(|(path, delay)| (path.to_string(), *delay)).collect();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                log.lock().unwrap().push(request.url().to_string());
+                let response = match routes.get(request.url()) {
+                    Some((content_type, body)) => {
+                        let header = Header::from_bytes("Content-Type", content_type.as_bytes()).expect("valid header");
+                        Response::from_string(body.clone()).with_header(header)
+                    }
+                    None => Response::from_string("not found").with_status_code(404),
+                };
+                match delays.remove(request.url()) {
+                    Some(delay) => {
+                        std::thread::spawn(move || {
+                            std::thread::sleep(delay);
+                            let _ = request.respond(response);
+                        });
+                    }
+                    None => {
+                        let _ = request.respond(response);
+                    }
+                }
+            }
+        });
+        MockServer { base_url: format!("http://{}", addr), requests }
+    }
+
+    // Paths requested so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+pub fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read fixture {}: {}", path, e))
+}
+
+// No proxy, so requests reach the mock server
+pub fn http_clients() -> HttpClients {
+    let client = reqwest::Client::builder().build().expect("build HTTP client");
+    HttpClients::with_client(client)
+}
+
+// Like `http_clients`, giving up on requests after `timeout`
+pub fn http_clients_with_timeout(timeout: Duration) -> HttpClients {
+    let client = reqwest::Client::builder().timeout(timeout).build().expect("build HTTP client");
+    HttpClients::with_client(client)
+}
+
+// Crawls summarize with the template brief; a configured endpoint would be called for real
+pub fn without_ai() {
+    std::env::remove_var("AI_BASE_URL");
+    std::env::remove_var("AI_API_KEY");
+}
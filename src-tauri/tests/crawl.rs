@@ -0,0 +1,746 @@
+// End-to-end crawl: fixture feed → insert → search, against an in-memory
+// database and a local server. Run with `cargo test --features test-support`.
+
+mod common;
+
+use std::time::Duration;
+
+use ai_news_aggregator::test_support::{self, RecordedEvents};
+use ai_news_aggregator::SummaryStatus;
+use common::{fixture, http_clients, http_clients_with_timeout, without_ai, MockServer};
+
+fn feed_server() -> MockServer {
+    MockServer::start(&[("/feed.xml", "application/rss+xml", fixture("feed.xml"))])
+}
+
+#[test]
+fn crawl_stores_feed_items_and_indexes_them() {
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+    let events = RecordedEvents::default();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&events, &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 3);
+    assert_eq!(result.failed_sources, 0);
+
+    let conn = db.lock().unwrap();
+    let article = test_support::article_by_url(&conn, "http://localhost/posts/sparse-transformers")
+        .unwrap()
+        .expect("article stored");
+    assert_eq!(article.title, "Sparse transformers for long documents");
+    assert_eq!(article.source, "Fixture AI Blog");
+    assert_eq!(article.category, "AI");
+    assert!(!article.summary.is_empty());
+
+    let hits = test_support::search(&conn, "retrieval").unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].url, "http://localhost/posts/retrieval-benchmarks");
+
+    let events = events.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, "app://articles:new");
+    assert_eq!(events[0].1["articles"].as_array().map(Vec::len), Some(3));
+}
+
+#[test]
+fn recrawl_does_not_duplicate_articles() {
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+    let http = http_clients();
+
+    let first = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    let second = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(first.inserted, 3);
+    assert_eq!(second.inserted, 0);
+    assert_eq!(test_support::search(&db.lock().unwrap(), "transformers").unwrap().len(), 1);
+}
+
+#[test]
+fn articles_are_fetched_by_id_list_in_the_order_given() {
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+    tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+
+    let conn = db.lock().unwrap();
+    let id = |url: &str| test_support::article_by_url(&conn, url).unwrap().expect("article stored").id;
+    let sparse = id("http://localhost/posts/sparse-transformers");
+    let retrieval = id("http://localhost/posts/retrieval-benchmarks");
+    let ids = vec![retrieval.clone(), "missing".to_string(), sparse.clone(), retrieval.clone()];
+
+    let articles = test_support::articles_by_ids(&conn, &ids).unwrap();
+    let got: Vec<&str> = articles.iter().map(|a| a.id.as_str()).collect();
+    assert_eq!(got, [retrieval.as_str(), sparse.as_str()]);
+    assert_eq!(articles[1].title, "Sparse transformers for long documents");
+    assert!(test_support::articles_by_ids(&conn, &[]).unwrap().is_empty());
+}
+
+#[test]
+fn scoped_search_only_matches_bookmarks_annotations_or_the_source() {
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+    tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+
+    let conn = db.lock().unwrap();
+    conn.execute("UPDATE articles SET is_bookmarked = 1 WHERE url = 'http://localhost/posts/retrieval-benchmarks'", [])
+        .unwrap();
+    conn.execute(
+        "INSERT INTO article_highlights (id, article_id, text, created_at)
+         SELECT 'h1', id, 'sparse attention', '2024-01-01T00:00:00Z' FROM articles
+         WHERE url = 'http://localhost/posts/sparse-transformers'",
+        [],
+    )
+    .unwrap();
+    let titles = |keyword: &str, scope: test_support::SearchScope| -> Vec<String> {
+        test_support::search_in(&conn, keyword, &scope).unwrap().into_iter().map(|a| a.title).collect()
+    };
+    let bookmarks = || test_support::SearchScope { bookmarks: true, ..Default::default() };
+    let annotated = || test_support::SearchScope { annotated: true, ..Default::default() };
+    let source = |name: &str| test_support::SearchScope { source: Some(name.to_string()), ..Default::default() };
+
+    assert_eq!(titles("retrieval", bookmarks()), ["Benchmarking retrieval pipelines"]);
+    assert!(titles("transformers", bookmarks()).is_empty());
+    assert_eq!(titles("transformers", annotated()), ["Sparse transformers for long documents"]);
+    assert!(titles("retrieval", annotated()).is_empty());
+    assert_eq!(titles("transformers", source("Fixture AI Blog")).len(), 1);
+    assert!(titles("transformers", source("Other Blog")).is_empty());
+    let both = test_support::SearchScope { annotated: true, bookmarks: true, ..Default::default() };
+    assert!(titles("transformers", both).is_empty());
+}
+
+fn rule(name: &str, field: test_support::Field, operator: test_support::Operator, value: &str, actions: Vec<test_support::Action>) -> test_support::Rule {
+    test_support::Rule {
+        id: String::new(),
+        name: name.to_string(),
+        enabled: true,
+        match_any: false,
+        conditions: vec![test_support::Condition { field, operator, value: value.to_string() }],
+        actions,
+        position: 0,
+        match_count: 0,
+        last_matched_at: None,
+    }
+}
+
+#[test]
+fn rules_act_on_new_articles_record_their_matches_and_dry_runs_change_nothing() {
+    use test_support::{Action, Field, Operator};
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    let (drop, sparse) = {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+        let invalid = rule("Broken", Field::Title, Operator::Regex, "(unclosed", vec![Action::MarkRead]);
+        assert!(test_support::save_rule(&conn, invalid).is_err());
+        let drop = rule("No benchmarks", Field::Title, Operator::Regex, r"^bench\w*", vec![Action::Delete]);
+        let drop = test_support::save_rule(&conn, drop).unwrap();
+        let actions = vec![
+            Action::SetCategory { category: "Research".to_string() },
+            Action::AddTag { tag: "long-context".to_string() },
+            Action::BoostHeat { points: 40.0 },
+            Action::Notify,
+        ];
+        let sparse = rule("Sparse", Field::Content, Operator::Contains, "SPARSE ATTENTION", actions);
+        (drop, test_support::save_rule(&conn, sparse).unwrap())
+    };
+    let events = RecordedEvents::default();
+    let http = http_clients();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&events, &db, &http)).unwrap();
+    assert_eq!(result.inserted, 2);
+    {
+        let conn = db.lock().unwrap();
+        assert!(test_support::article_by_url(&conn, "http://localhost/posts/retrieval-benchmarks").unwrap().is_none());
+        let sparse = test_support::article_by_url(&conn, "http://localhost/posts/sparse-transformers").unwrap().unwrap();
+        assert_eq!(sparse.category, "Research");
+        let tags: Vec<String> = conn
+            .prepare("SELECT tag FROM article_tags WHERE article_id = ?1")
+            .unwrap()
+            .query_map([&sparse.id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(tags.contains(&"long-context".to_string()));
+        let boost: f64 = conn.query_row("SELECT rule_boost FROM articles WHERE id = ?1", [&sparse.id], |row| row.get(0)).unwrap();
+        assert_eq!(boost, 40.0);
+    }
+    let matched: Vec<_> = events.take().into_iter().filter(|(name, _)| name == "app://rules:match").collect();
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].1["name"], "Sparse");
+    assert_eq!(matched[0].1["articles"].as_array().map(Vec::len), Some(1));
+
+    // The deleted item is in the trash, so its URL isn't crawled again
+    let again = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(again.inserted, 0);
+
+    let conn = db.lock().unwrap();
+    // Match history says what each rule did, also to articles now in the trash
+    let dropped = test_support::rule_matches(&conn, &drop.id).unwrap();
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].title, "Benchmarking retrieval pipelines");
+    assert_eq!(dropped[0].actions, [Action::Delete]);
+    assert!(dropped[0].in_trash);
+    let boosted = test_support::rule_matches(&conn, &sparse.id).unwrap();
+    assert_eq!(boosted.len(), 1);
+    assert!(!boosted[0].in_trash);
+    assert!(boosted[0].actions.contains(&Action::BoostHeat { points: 40.0 }));
+
+    let dry = rule("Chinese", Field::Title, Operator::Regex, "开源", vec![Action::Delete]);
+    let hits = test_support::test_rule(&conn, &dry, 50).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].title, "开源大模型周报");
+    assert!(test_support::article_by_url(&conn, &hits[0].url).unwrap().is_some());
+}
+
+#[test]
+fn unreachable_source_is_counted_as_failed() {
+    without_ai();
+    let server = feed_server();
+    // Nothing listens on a port that was bound and released
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+        test_support::add_source(&conn, "Offline Blog", &format!("http://{}/feed.xml", closed), "RSS").unwrap();
+    }
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 3);
+    assert_eq!(result.failed_sources, 1);
+}
+
+#[test]
+fn source_that_times_out_is_retried_after_the_others() {
+    without_ai();
+    // The feed answers too late the first time only
+    let server = MockServer::start_with_delays(
+        &[
+            ("/slow.xml", "application/rss+xml", fixture("feed.xml")),
+            ("/hn.xml", "application/rss+xml", fixture("hn_feed.xml")),
+        ],
+        &[("/slow.xml", Duration::from_secs(2))],
+    );
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "Slow Blog", &server.url("/slow.xml"), "RSS").unwrap();
+        test_support::add_source(&conn, "Hacker News Frontpage", &server.url("/hn.xml"), "RSS").unwrap();
+    }
+    let http = http_clients_with_timeout(Duration::from_millis(500));
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(result.inserted, 4);
+    assert_eq!(result.failed_sources, 0);
+    assert_eq!(server.requests(), ["/slow.xml", "/hn.xml", "/slow.xml"]);
+    assert!(test_support::article_by_url(&db.lock().unwrap(), "http://localhost/posts/sparse-transformers").unwrap().is_some());
+}
+
+// A feed needing the proxy and a domestic one (direct), with the proxy down and `policy` set
+fn crawl_with_proxy_down(policy: &str) -> (MockServer, test_support::CrawlResult, Vec<(String, serde_json::Value)>) {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/feed.xml", "application/rss+xml", fixture("feed.xml")),
+        // The domain in the path makes it a domestic site
+        ("/36kr.com/hn.xml", "application/rss+xml", fixture("hn_feed.xml")),
+    ]);
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+        test_support::add_source(&conn, "Domestic News", &server.url("/36kr.com/hn.xml"), "RSS").unwrap();
+        conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('proxy_down_policy', ?1)", [policy]).unwrap();
+    }
+    let http = http_clients().with_proxy(&format!("http://{}", closed));
+    let events = RecordedEvents::default();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&events, &db, &http)).unwrap();
+    (server, result, events.take())
+}
+
+#[test]
+fn proxied_sources_are_skipped_while_the_proxy_is_down() {
+    let (server, result, events) = crawl_with_proxy_down("skip");
+    assert_eq!(result.inserted, 1);
+    assert_eq!(result.failed_sources, 1);
+    assert_eq!(server.requests(), ["/36kr.com/hn.xml"]);
+    assert_eq!(events[0].0, "app://crawl:proxy-down");
+    assert_eq!(events[0].1["policy"], "skip");
+    assert_eq!(events[0].1["sources"], serde_json::json!(["Fixture AI Blog"]));
+}
+
+#[test]
+fn proxied_sources_go_direct_while_the_proxy_is_down_if_configured() {
+    let (server, result, events) = crawl_with_proxy_down("direct");
+    assert_eq!(result.inserted, 4);
+    assert_eq!(result.failed_sources, 0);
+    assert_eq!(server.requests(), ["/feed.xml", "/36kr.com/hn.xml"]);
+    assert_eq!(events[0].0, "app://crawl:proxy-down");
+    assert_eq!(events[0].1["policy"], "direct");
+}
+
+// A feed of `items` posts that no other feed links to
+fn numbered_feed(feed: usize, items: usize) -> String {
+    let items: String = (0..items)
+        .map(|item| {
+            format!(
+                "<item><title>Post {item} of feed {feed}</title><link>http://localhost/feed-{feed}/post-{item}</link>\
+                 <description>Post {item}.</description><pubDate>Mon, 12 Oct 2026 08:00:00 GMT</pubDate></item>"
+            )
+        })
+        .collect();
+    format!("<?xml version=\"1.0\"?><rss version=\"2.0\"><channel><title>Feed {feed}</title><link>http://localhost/</link>\
+             <description>Numbered</description>{items}</channel></rss>")
+}
+
+#[test]
+fn mobile_profile_crawls_fewer_sources_and_items_less_often() {
+    without_ai();
+    let feeds: Vec<(String, String)> = (0..12).map(|feed| (format!("/feed-{}.xml", feed), numbered_feed(feed, 14))).collect();
+    let routes: Vec<(&str, &str, String)> =
+        feeds.iter().map(|(path, body)| (path.as_str(), "application/rss+xml", body.clone())).collect();
+    let server = MockServer::start(&routes);
+
+    let crawl = |mobile: bool| {
+        let db = test_support::memory_db().unwrap();
+        let http = http_clients().with_mobile_profile(mobile);
+        {
+            let conn = db.lock().unwrap();
+            for (path, _) in &feeds {
+                test_support::add_source(&conn, path, &server.url(path), "RSS").unwrap();
+            }
+            conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('crawl_interval_minutes', '15')", []).unwrap();
+        }
+        let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+        let conn = db.lock().unwrap();
+        let sources: i64 = conn.query_row("SELECT COUNT(DISTINCT source) FROM articles", [], |row| row.get(0)).unwrap();
+        let most_per_source: i64 = conn
+            .query_row("SELECT MAX(n) FROM (SELECT COUNT(*) AS n FROM articles GROUP BY source)", [], |row| row.get(0))
+            .unwrap();
+        (result.inserted, sources, most_per_source, test_support::crawl_interval_minutes(&conn, &http))
+    };
+
+    assert_eq!(crawl(false), (144, 12, 12, 15), "desktop: every source, 12 items each, the interval as set");
+    assert_eq!(crawl(true), (80, 10, 8, 60), "mobile: 10 sources, 8 items each, at most hourly");
+}
+
+#[test]
+fn scheduler_wait_sleeps_in_slices_and_stops_at_a_clock_jump() {
+    use std::cell::Cell;
+    let start = chrono::Utc::now();
+
+    // Steady clock: 50ms in 20ms slices is three slices (20 + 20 + 10), two clock reads each
+    let reads = Cell::new(0);
+    let steady = || {
+        reads.set(reads.get() + 1);
+        start
+    };
+    let begun = std::time::Instant::now();
+    let asleep = tauri::async_runtime::block_on(test_support::scheduler_wait(
+        Duration::from_millis(50),
+        Duration::from_millis(20),
+        steady,
+    ));
+    assert_eq!(asleep, None);
+    assert_eq!(reads.get(), 6);
+    assert!(begun.elapsed() >= Duration::from_millis(50));
+
+    // The clock jumps 3 minutes during the second slice: the wait ends there
+    let reads = Cell::new(0);
+    let jumping = || {
+        reads.set(reads.get() + 1);
+        if reads.get() >= 4 { start + chrono::Duration::minutes(3) } else { start }
+    };
+    let asleep = tauri::async_runtime::block_on(test_support::scheduler_wait(
+        Duration::from_secs(60),
+        Duration::from_millis(20),
+        jumping,
+    ));
+    assert_eq!(asleep, Some(Duration::from_secs(180) - Duration::from_millis(20)));
+    assert_eq!(reads.get(), 4);
+}
+
+#[test]
+fn missing_feed_stores_nothing() {
+    without_ai();
+    let server = feed_server();
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Gone Blog", &server.url("/moved.xml"), "RSS").unwrap();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 0);
+    assert!(test_support::search(&db.lock().unwrap(), "transformers").unwrap().is_empty());
+}
+
+#[test]
+fn github_trending_keeps_only_popular_repositories() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+        ("/repos/acme/llm-kit/readme", "text/plain", fixture("github_readme.md")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "GitHub Trending", &server.url("/trending"), "WEB").unwrap();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(result.inserted, 1);
+
+    let conn = db.lock().unwrap();
+    let repo_url = server.url("/acme/llm-kit");
+    let article = test_support::article_by_url(&conn, &repo_url).unwrap().expect("popular repository stored");
+    assert_eq!(article.title, "acme / llm-kit [Rust]");
+    assert_eq!(article.category, "GitHub");
+    assert!(article.content.starts_with("Toolkit for serving large language models on a single GPU\n\nllm-kit serves"));
+    assert!(article.content.contains("- Paged attention with int4 and fp8 weights"));
+    assert!(!article.content.contains("cargo install"));
+    assert!(!article.content.contains("badge"));
+    assert!(test_support::article_by_url(&conn, &server.url("/someone/side-project")).unwrap().is_none());
+}
+
+#[test]
+fn github_trending_repo_returns_only_after_cooloff() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "GitHub Trending", &server.url("/trending"), "WEB").unwrap();
+    let crawl = || tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    let remove_article = || {
+        let conn = db.lock().unwrap();
+        // Its search entry goes with it, as when the app deletes an article
+        conn.execute(
+            "DELETE FROM articles_fts WHERE rowid = (SELECT rowid FROM articles WHERE url = ?1)",
+            [server.url("/acme/llm-kit")],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM articles WHERE url = ?1", [server.url("/acme/llm-kit")]).unwrap();
+    };
+
+    assert_eq!(crawl().inserted, 1);
+    remove_article();
+    assert_eq!(crawl().inserted, 0, "still trending within the cool-off");
+
+    db.lock()
+        .unwrap()
+        .execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('github_reappear_cooloff_days', '0')", [])
+        .unwrap();
+    assert_eq!(crawl().inserted, 1);
+}
+
+#[test]
+fn configured_trending_source_crawls_each_language_page_once_per_repo() {
+    without_ai();
+    // Both language pages list the same repos
+    let server = MockServer::start(&[
+        ("/trending/rust?since=weekly", "text/html", fixture("github_trending.html")),
+        ("/trending/jupyter-notebook?since=weekly", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    let config = test_support::TrendingConfig {
+        languages: vec!["Rust".to_string(), "Jupyter Notebook".to_string(), "rust".to_string()],
+        since: test_support::Since::Weekly,
+        spoken_language: None,
+        star_filter: None,
+    };
+    let stored = test_support::set_trending_source(&db.lock().unwrap(), "GitHub Weekly", &config).unwrap();
+    assert_eq!(stored.languages, ["rust", "jupyter-notebook"]);
+    let url: String = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT url FROM sources WHERE name = 'GitHub Weekly'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(url, "https://github.com/trending/rust?since=weekly");
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(result.inserted, 1);
+    assert_eq!(result.failed_sources, 0);
+    let article = test_support::article_by_url(&db.lock().unwrap(), &server.url("/acme/llm-kit")).unwrap().unwrap();
+    assert_eq!(article.source, "GitHub Weekly");
+    assert_eq!(article.category, "GitHub");
+
+    let invalid = test_support::TrendingConfig { spoken_language: Some("chinese".to_string()), ..config };
+    assert!(test_support::set_trending_source(&db.lock().unwrap(), "GitHub Weekly", &invalid).is_err());
+}
+
+#[test]
+fn trending_source_without_star_filter_takes_every_repo() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    let no_filter = test_support::StarFilter { enabled: false, ..Default::default() };
+    let config = test_support::TrendingConfig { star_filter: Some(no_filter), ..Default::default() };
+    test_support::set_trending_source(&db.lock().unwrap(), "GitHub Everything", &config).unwrap();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(result.inserted, 2);
+    let conn = db.lock().unwrap();
+    let side_project = test_support::article_by_url(&conn, &server.url("/someone/side-project")).unwrap().unwrap();
+    assert_eq!(side_project.title, "someone / side-project [Python]");
+}
+
+#[test]
+fn weekly_trending_skips_listed_repos_and_waits_for_its_refresh() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/trending/rust?since=weekly", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "GitHub Trending", &server.url("/trending"), "WEB").unwrap();
+        let config = test_support::TrendingConfig {
+            languages: vec!["rust".to_string()],
+            since: test_support::Since::Weekly,
+            star_filter: Some(test_support::StarFilter { enabled: false, ..Default::default() }),
+            ..Default::default()
+        };
+        test_support::set_trending_source(&conn, "GitHub Weekly", &config).unwrap();
+    }
+    let crawl = || tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    // Items each source's fetcher returned in the latest crawl
+    let fetched = || -> Vec<(String, u64)> {
+        let sources: String = db
+            .lock()
+            .unwrap()
+            .query_row("SELECT sources FROM crawl_runs ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        let sources: Vec<serde_json::Value> = serde_json::from_str(&sources).unwrap();
+        let mut fetched: Vec<(String, u64)> = sources
+            .iter()
+            .map(|s| (s["source"].as_str().unwrap().to_string(), s["items"].as_u64().unwrap()))
+            .collect();
+        fetched.sort();
+        fetched
+    };
+
+    // The daily page lists llm-kit first; the weekly view only adds the repo nobody listed
+    assert_eq!(crawl().inserted, 2);
+    assert_eq!(fetched(), [("GitHub Trending".to_string(), 1), ("GitHub Weekly".to_string(), 1)]);
+    let conn = db.lock().unwrap();
+    let side_project = test_support::article_by_url(&conn, &server.url("/someone/side-project")).unwrap().unwrap();
+    assert_eq!(side_project.source, "GitHub Weekly");
+    drop(conn);
+
+    crawl();
+    assert_eq!(fetched(), [("GitHub Trending".to_string(), 1)], "weekly source not due again within a day");
+}
+
+#[test]
+fn aggregator_items_keep_discussion_link_and_counts() {
+    without_ai();
+    let server = MockServer::start(&[("/hn.xml", "application/rss+xml", fixture("hn_feed.xml"))]);
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Hacker News Frontpage", &server.url("/hn.xml"), "RSS").unwrap();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 1);
+
+    let conn = db.lock().unwrap();
+    let article = test_support::article_by_url(&conn, "http://localhost/posts/tiny-inference")
+        .unwrap()
+        .expect("story stored under its own URL");
+    assert_eq!(article.discussion_url.as_deref(), Some("https://news.ycombinator.com/item?id=41000001"));
+    assert_eq!(article.points, Some(312));
+    assert_eq!(article.comment_count, Some(87));
+    assert!(article.heat_score > 0.0);
+}
+
+#[test]
+fn podcast_durations_drive_length_filters() {
+    without_ai();
+    let server = MockServer::start(&[("/podcast.xml", "application/rss+xml", fixture("podcast_feed.xml"))]);
+    let db = test_support::memory_db().unwrap();
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Podcast", &server.url("/podcast.xml"), "RSS").unwrap();
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 2);
+
+    let conn = db.lock().unwrap();
+    let long = test_support::article_by_url(&conn, "http://localhost/podcast/scaling-laws").unwrap().unwrap();
+    assert_eq!(long.media_duration, Some(3930));
+    let short = test_support::article_by_url(&conn, "http://localhost/podcast/quick-take").unwrap().unwrap();
+    assert_eq!(short.media_duration, Some(270));
+    assert_eq!(short.reading_minutes, Some(1));
+
+    for query in [
+        serde_json::json!({"page_size": 20, "max_duration_minutes": 10}),
+        serde_json::json!({"page_size": 20, "short_only": true}),
+    ] {
+        let query: test_support::ListQuery = serde_json::from_value(query).unwrap();
+        let page = test_support::list(&conn, &query).unwrap();
+        let urls: Vec<&str> = page.items.iter().map(|a| a.url.as_str()).collect();
+        assert_eq!(urls, ["http://localhost/podcast/quick-take"]);
+    }
+}
+
+#[test]
+fn night_batch_defers_readmes_and_summaries_outside_its_window() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+        ("/repos/acme/llm-kit/readme", "text/plain", fixture("github_readme.md")),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::add_source(&conn, "GitHub Trending", &server.url("/trending"), "WEB").unwrap();
+        // An empty window and no idle runs: the window never opens by itself
+        conn.execute_batch(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES
+                 ('night_batch_enabled', 'true'), ('night_batch_start_hour', '3'),
+                 ('night_batch_end_hour', '3'), ('night_batch_when_idle', 'false')",
+        )
+        .unwrap();
+    }
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    assert_eq!(result.inserted, 1);
+    let repo_url = server.url("/acme/llm-kit");
+    let article = test_support::article_by_url(&db.lock().unwrap(), &repo_url).unwrap().unwrap();
+    assert_eq!(article.content, "Toolkit for serving large language models on a single GPU");
+    assert_eq!(article.summary_status, SummaryStatus::Pending);
+
+    let taken = tauri::async_runtime::block_on(test_support::night_batch(&db, &http)).unwrap();
+    assert_eq!(taken, 4, "summary, README, paper-code link and comment summary");
+    let article = test_support::article_by_url(&db.lock().unwrap(), &repo_url).unwrap().unwrap();
+    assert!(article.content.contains("llm-kit serves"));
+    assert_eq!(article.summary_status, SummaryStatus::Template);
+}
+
+#[test]
+fn damaged_database_is_restored_from_latest_backup() {
+    without_ai();
+    let server = feed_server();
+    let dir = std::env::temp_dir().join(format!("news-recovery-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("news.db").to_string_lossy().to_string();
+
+    let (db, report) = test_support::open_db(&db_path).unwrap();
+    assert!(report.is_none());
+    test_support::add_source(&db.lock().unwrap(), "Fixture AI Blog", &server.url("/feed.xml"), "RSS").unwrap();
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http_clients())).unwrap();
+    assert_eq!(result.inserted, 3);
+    test_support::backup_db(&db.lock().unwrap()).unwrap();
+    drop(db);
+
+    std::fs::write(&db_path, vec![0x5a; 8192]).unwrap();
+    let (db, report) = test_support::open_db(&db_path).unwrap();
+    let report = report.expect("recovery report");
+    assert!(report.restored_from.is_some());
+    assert!(std::path::Path::new(&report.damaged_path).exists());
+    assert!(test_support::article_by_url(&db.lock().unwrap(), "http://localhost/posts/sparse-transformers").unwrap().is_some());
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn paywalled_pages_are_flagged_and_can_be_hidden() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/premium/chips", "text/html", fixture("paywalled_article.html")),
+        ("/free/chips", "text/html", "<html><head><title>Free chips</title></head><body><p>Open to all.</p></body></html>".to_string()),
+    ]);
+    let db = test_support::memory_db().unwrap();
+    let http = http_clients();
+
+    let locked = tauri::async_runtime::block_on(test_support::add_url(&db, &http, &server.url("/premium/chips")))
+        .unwrap()
+        .expect("article stored");
+    let open = tauri::async_runtime::block_on(test_support::add_url(&db, &http, &server.url("/free/chips")))
+        .unwrap()
+        .expect("article stored");
+    assert!(locked.paywalled);
+    assert!(!open.paywalled);
+
+    let query: test_support::ListQuery =
+        serde_json::from_value(serde_json::json!({"page_size": 20, "hide_paywalled": true})).unwrap();
+    let listed = test_support::list(&db.lock().unwrap(), &query).unwrap();
+    let ids: Vec<&str> = listed.items.iter().map(|a| a.id.as_str()).collect();
+    assert_eq!(ids, [open.id.as_str()]);
+}
+
+#[test]
+fn paywalled_page_takes_its_content_from_the_amp_version() {
+    without_ai();
+    let server = MockServer::start(&[
+        ("/premium/chips", "text/html", fixture("paywalled_article.html")),
+        ("/premium/chips/amp", "text/html", fixture("amp_article.html")),
+    ]);
+    let db = test_support::memory_db().unwrap();
+
+    let article = tauri::async_runtime::block_on(test_support::add_url(&db, &http_clients(), &server.url("/premium/chips")))
+        .unwrap()
+        .expect("article stored");
+    assert!(article.paywalled);
+    assert!(article.content.contains("removes the memory bandwidth bottleneck"));
+}
+
+#[test]
+fn reader_view_is_sanitized_and_serves_cached_images() {
+    without_ai();
+    let chart = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"360\"><rect width=\"640\" height=\"360\"/></svg>";
+    let server = MockServer::start(&[
+        ("/posts/distill", "text/html", fixture("reader_article.html")),
+        ("/img/accuracy.svg", "image/svg+xml", chart.to_string()),
+    ]);
+    let db = test_support::memory_db().unwrap();
+    let http = http_clients();
+    let image_dir = std::env::temp_dir().join(format!("news-reader-images-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&image_dir);
+
+    let article = tauri::async_runtime::block_on(test_support::add_url(&db, &http, &server.url("/posts/distill")))
+        .unwrap()
+        .expect("article stored");
+    let view = tauri::async_runtime::block_on(test_support::reader(&db, &http, &article.id, &image_dir)).unwrap();
+
+    assert!(view.from_page);
+    assert!(view.html.contains("four times faster"));
+    for stripped in ["<script", "gtag", "onclick", "<iframe", "stats.wp.com", "utm_", "fbclid", "Subscribe"] {
+        assert!(!view.html.contains(stripped), "{} left in {}", stripped, view.html);
+    }
+    assert!(view.html.contains(&format!("href=\"{}\"", server.url("/papers/distill?page=2"))));
+    assert!(view.html.contains("href=\"https://example.org/weights\""));
+
+    assert_eq!(view.images, 1);
+    assert!(!view.html.contains(&server.url("/img/accuracy.svg")));
+    let cached: Vec<_> = std::fs::read_dir(&image_dir).unwrap().filter_map(Result::ok).collect();
+    assert_eq!(cached.len(), 1);
+    let file_name = cached[0].file_name().to_string_lossy().to_string();
+    assert!(file_name.ends_with(".svg"));
+    assert!(view.html.contains(&format!("localhost/{}", file_name)));
+    assert_eq!(std::fs::read_to_string(cached[0].path()).unwrap(), chart);
+    let _ = std::fs::remove_dir_all(&image_dir);
+}
@@ -0,0 +1,63 @@
+// Low-data mode end to end. It's a process-wide switch, so these tests get a
+// binary of their own instead of sharing one with tests/crawl.rs.
+
+// Not every shared helper is used here
+#[allow(dead_code)]
+mod common;
+
+use ai_news_aggregator::test_support;
+use common::{fixture, http_clients, without_ai, MockServer};
+
+#[test]
+fn low_data_mode_takes_fewer_items_and_skips_readmes_and_images() {
+    without_ai();
+    let chart = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"360\"><rect width=\"640\" height=\"360\"/></svg>";
+    let server = MockServer::start(&[
+        ("/digest.xml", "application/rss+xml", fixture("long_feed.xml")),
+        ("/trending", "text/html", fixture("github_trending.html")),
+        ("/acme/llm-kit", "text/html", fixture("github_repo.html")),
+        ("/someone/side-project", "text/html", fixture("github_repo.html")),
+        ("/repos/acme/llm-kit/readme", "text/plain", fixture("github_readme.md")),
+        ("/posts/distill", "text/html", fixture("reader_article.html")),
+        ("/img/accuracy.svg", "image/svg+xml", chart.to_string()),
+    ]);
+    let http = http_clients().with_github_base(server.base_url());
+    let db = test_support::memory_db().unwrap();
+    {
+        let conn = db.lock().unwrap();
+        test_support::set_low_data_mode(&conn, true).unwrap();
+        test_support::add_source(&conn, "Fixture Research Digest", &server.url("/digest.xml"), "RSS").unwrap();
+        test_support::add_source(&conn, "GitHub Trending", &server.url("/trending"), "WEB").unwrap();
+    }
+
+    let result = tauri::async_runtime::block_on(test_support::crawl(&(), &db, &http)).unwrap();
+    let stored: i64 = db
+        .lock()
+        .unwrap()
+        .query_row(
+            "SELECT COUNT(*) FROM articles WHERE source = 'Fixture Research Digest'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(stored, 5, "feed has 8 items, low-data mode takes 5");
+    assert_eq!(result.inserted, 6, "5 feed items and the popular repository");
+    let repo = test_support::article_by_url(&db.lock().unwrap(), &server.url("/acme/llm-kit"))
+        .unwrap()
+        .expect("popular repository stored");
+    assert!(!repo.content.contains("Paged attention"), "README section left out");
+
+    let article = tauri::async_runtime::block_on(test_support::add_url(&db, &http, &server.url("/posts/distill")))
+        .unwrap()
+        .expect("article stored");
+    let image_dir = std::env::temp_dir().join(format!("news-low-data-images-{}", std::process::id()));
+    let view = tauri::async_runtime::block_on(test_support::reader(&db, &http, &article.id, &image_dir)).unwrap();
+    assert!(!view.from_page, "reader uses the stored content");
+    assert_eq!(view.images, 0);
+    let _ = std::fs::remove_dir_all(&image_dir);
+
+    let requests = server.requests();
+    assert!(!requests.iter().any(|path| path.contains("/readme")), "README fetched: {:?}", requests);
+    assert!(!requests.iter().any(|path| path.starts_with("/img/")), "image fetched: {:?}", requests);
+    assert_eq!(requests.iter().filter(|path| *path == "/posts/distill").count(), 1, "page fetched once, when added");
+}
@@ -42,7 +42,7 @@ export function ArticleCard({ article, onToggleBookmark }: ArticleCardProps): JS
 
   const handleTitleClick = (event: React.MouseEvent): void => {
     event.preventDefault();
-    void api.openExternal(article.url);
+    void api.openExternal(article.url, article.id);
   };
 
   const handleBookmarkClick = (): void => {
@@ -1,14 +1,15 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Article, CrawlResult, ListResponse, Settings } from "../types";
+import type { Article, CrawlResult, Freshness, ListResponse, Settings } from "../types";
 
 export const api = {
   health: () => invoke<string>("health"),
-  openExternal: (url: string) => invoke<void>("open_external", { url }),
+  isReadOnly: () => invoke<boolean>("app_read_only"),
+  openExternal: (url: string, articleId?: string) => invoke<void>("open_external", { url, articleId }),
   runCrawler: () => invoke<CrawlResult>("crawler_run_once"),
   regenerateSummaries: () => invoke<number>("articles_regenerate_summaries"),
-  listArticles: (page = 1, pageSize = 20, category?: string) =>
+  listArticles: (page = 1, pageSize = 20, category?: string, freshness?: Freshness, groupByFreshness?: boolean) =>
     invoke<ListResponse>("articles_list", {
-      query: { page, page_size: pageSize, category },
+      query: { page, page_size: pageSize, category, freshness, group_by_freshness: groupByFreshness },
     }),
   searchArticles: (keyword: string) =>
     invoke<Article[]>("search_query", { payload: { keyword } }),
@@ -17,7 +18,7 @@ export const api = {
   toggleRead: (id: string, value: boolean) =>
     invoke<void>("article_mark_read", { payload: { id, value } }),
   manualAdd: (url: string) => invoke<Article>("manual_add", { payload: { url } }),
-  summarize: (content: string) => invoke<string>("ai_summarize", { content }),
+  summarize: (content: string, id?: string) => invoke<string>("ai_summarize", { content, id }),
   getSettings: () => invoke<Settings>("settings_get"),
   updateSettings: (payload: Settings) => invoke<Settings>("settings_update", { payload }),
 };
@@ -12,14 +12,201 @@ export type Article = {
   is_read: boolean;
   is_bookmarked: boolean;
   image_url: string;
+  date_is_estimated: boolean;
+  freshness: Freshness;
+  paper_code_link: string | null;
+  opened_at: string | null;
+  summary_status: SummaryStatus;
+  summary_error: string | null;
+  summary_source: SummarySource;
+  discussion_url: string | null;
+  comment_count: number | null;
+  points: number | null;
+  comments_summary: string | null;
+  media_duration: number | null;
+  reading_minutes: number | null;
+  paywalled: boolean;
 };
 
+export type SummaryStatus = "pending" | "template" | "ai" | "failed";
+
+export type SummarySource = "template" | "ai" | "manual";
+
+export type Freshness = "today" | "yesterday" | "this_week" | "older";
+
 export type Settings = {
   theme: string;
   ai_model: string;
   ai_base_url: string;
   ai_api_key: string;
   ai_summary_enabled: boolean;
+  language?: string;
+  local_api_enabled?: boolean;
+  local_api_port?: number;
+  local_api_token?: string;
+  feed_auto_export?: boolean;
+  feed_export_path?: string;
+  card_font_path?: string;
+  max_article_age_days?: number;
+  display_timezone?: string;
+  crawl_interval_minutes?: number;
+  cleanup_interval_hours?: number;
+  trash_retention_days?: number;
+  seen_urls_retention_days?: number;
+  http_pool_max_idle_per_host?: number;
+  http_pool_idle_timeout_secs?: number;
+  slow_query_ms?: number;
+  summary_mode?: "crawl" | "lazy";
+  github_reappear_cooloff_days?: number;
+  github_reappear_star_delta?: number;
+  night_batch_enabled?: boolean;
+  night_batch_start_hour?: number;
+  night_batch_end_hour?: number;
+  night_batch_when_idle?: boolean;
+  storage_quota_mb?: number;
+  proxy_down_policy?: "skip" | "direct";
+  low_data_mode?: boolean;
+};
+
+export type Folder = {
+  id: string;
+  name: string;
+  parent_id: string | null;
+  position: number;
+  sources: string[];
+};
+
+export type PaletteAction = {
+  id: string;
+  title: string;
+  group: string;
+  kind: "command" | "navigate";
+  command: string | null;
+  route: string | null;
+  args: Record<string, unknown>;
+  params: { path: string; label: string; input: "text" | "url" }[];
+  shortcut: string | null;
+};
+
+export type Profile = {
+  id: string;
+  name: string;
+  created_at: string;
+};
+
+export type ProfileRegistry = {
+  active: string;
+  profiles: Profile[];
+};
+
+export type Translation = {
+  id: string;
+  lang: string;
+  title: string;
+  content: string;
+  cached: boolean;
+};
+
+export type GlossaryEntry = {
+  id: string;
+  term: string;
+  translation: string;
+  lang: string | null;
+};
+
+export type StoredDigest = {
+  id: string;
+  kind: string;
+  title: string;
+  generated_at: string;
+  days: number;
+  article_count: number;
+  markdown: string;
+  html: string;
+};
+
+export type DigestSummary = Omit<StoredDigest, 'markdown' | 'html'>;
+
+export type StarReportConfig = {
+  enabled: boolean;
+  days: number;
+  min_gain: number;
+  milestones: number[];
+};
+
+export type RepoGain = {
+  full_name: string;
+  url: string;
+  stars: number;
+  previous: number;
+  gained: number;
+  milestone: number | null;
+  since: string;
+};
+
+export type StarReport = {
+  title: string;
+  generated_at: string;
+  days: number;
+  min_gain: number;
+  repos: RepoGain[];
+};
+
+export type WeeklyRoundup = {
+  digest: StoredDigest;
+  themes: number;
+  recipients: number;
+};
+
+export type Watch = {
+  id: string;
+  name: string;
+  kind: "organization" | "model" | "person";
+  aliases: string[];
+  notify: boolean;
+  match_count: number;
+};
+
+export type HeatBreakdown = {
+  engagement: number;
+  recency: number;
+  ai_importance: number;
+  keyword_boost: number;
+  feedback: number;
+  total: number;
+};
+
+export type FeedbackSignal = "more" | "less" | "clear";
+
+export type LearnedWeights = {
+  sources: Record<string, number>;
+  keywords: Record<string, number>;
+};
+
+export type HeatConfig = {
+  half_life_hours: number;
+  keyword_weights: { keyword: string; weight: number }[];
+};
+
+export type FeedBalanceConfig = {
+  enabled: boolean;
+  limits: { category: string; max_percent: number }[];
+};
+
+export type ListQuery = {
+  page?: number;
+  page_size: number;
+  category?: string;
+  bookmarked_only?: boolean;
+  include_muted?: boolean;
+  include_snoozed?: boolean;
+  folder_id?: string;
+  freshness?: Freshness;
+  group_by_freshness?: boolean;
+  tag?: string;
+  max_duration_minutes?: number;
+  short_only?: boolean;
+  hide_paywalled?: boolean;
 };
 
 export type ListResponse = {
@@ -27,13 +214,24 @@ export type ListResponse = {
   total: number;
   page: number;
   page_size: number;
+  groups: { freshness: Freshness; count: number }[] | null;
 };
 
 export type CrawlResult = {
   inserted: number;
+  updated: number;
+  syndicated: number;
   failed_sources: number;
 };
 
+export type ArticleAlternate = {
+  url: string;
+  title: string;
+  source: string;
+  published_at: string;
+  detected_at: string;
+};
+
 export type SummaryUpdateStatus = {
   isRunning: boolean;
   total: number | null;
@@ -42,3 +240,264 @@ export type SummaryUpdateStatus = {
   currentTitle: string | null;
   error: string | null;
 };
+
+export type PdfExportResult = {
+  path: string;
+  pages: number;
+};
+
+export type JobAction =
+  | { kind: "summarize"; article_id: string }
+  | { kind: "translate"; article_id: string; lang: string | null; force: boolean }
+  | { kind: "send_external"; article_id: string; service: "pocket" | "instapaper" | "wallabag" };
+
+export type PendingJob = {
+  id: number;
+  action: JobAction;
+  attempts: number;
+  last_error: string | null;
+  created_at: string;
+  updated_at: string;
+};
+
+export type JobReplayReport = {
+  completed: number;
+  failed: number;
+  remaining: number;
+};
+
+export type NetworkStatus = {
+  online: boolean;
+  direct: boolean;
+  proxied: boolean;
+  proxy: string;
+  proxy_listening: boolean;
+  checked_at: string;
+};
+
+export type AiProviderLimit = {
+  host: string;
+  max_concurrent: number;
+  requests_per_minute: number;
+};
+
+export type AiLimitsConfig = {
+  default_max_concurrent: number;
+  default_requests_per_minute: number;
+  providers: AiProviderLimit[];
+};
+
+export type AiProvider = "openai" | "anthropic";
+
+export type AiTestResult = {
+  provider: AiProvider;
+  model: string;
+  reply: string | null;
+  error: string | null;
+  latency_ms: number;
+  embedding_dimensions: number | null;
+  embedding_error: string | null;
+};
+
+export type FeatureName = "local_api" | "scheduler" | "semantic_search";
+
+export type FeatureFlag = {
+  name: FeatureName;
+  enabled: boolean;
+  default_enabled: boolean;
+};
+
+export type AiSummarizeDeltaEvent = {
+  id: string | null;
+  delta: string;
+};
+
+export type ArticleUpdate = {
+  id: string;
+  title?: string;
+  category?: string;
+  source?: string;
+  published_at?: string;
+};
+
+export type ArticleEdit = {
+  field: "title" | "category" | "source" | "published_at";
+  old_value: string;
+  new_value: string;
+  edited_at: string;
+};
+
+export type BulkAddStatus = "added" | "exists" | "failed";
+
+export type BulkAddResult = {
+  input: string;
+  status: BulkAddStatus;
+  name: string | null;
+  url: string | null;
+  source_type: string | null;
+  error: string | null;
+};
+
+export type CleanupResult = {
+  deleted: number;
+  trash_purged: number;
+  cache_purged: number;
+};
+
+export type TrashedArticle = {
+  id: string;
+  title: string;
+  url: string;
+  source: string;
+  category: string;
+  published_at: string;
+  deleted_at: string;
+  purge_at: string | null;
+};
+
+export type OnboardingSource = {
+  name: string;
+  url: string;
+  source_type: string;
+  added: boolean;
+};
+
+export type SourceBundle = {
+  id: "ai_research" | "chinese_tech" | "github" | "podcasts";
+  title: string;
+  description: string;
+  recommended: boolean;
+  sources: OnboardingSource[];
+};
+
+export type FirstRunState = {
+  completed: boolean;
+  bundles: SourceBundle[];
+};
+
+export type ScrapeRules = {
+  item: string;
+  title?: string;
+  link?: string;
+  summary?: string;
+  date?: string;
+};
+
+export type PackSource = {
+  name: string;
+  url: string;
+  source_type: "RSS" | "WEB" | "API";
+  category?: string;
+  scrape_rules?: ScrapeRules;
+  max_age_days?: number;
+};
+
+export type SourcePack = {
+  format: "ai-news-source-pack";
+  version: number;
+  name: string;
+  description: string;
+  sources: PackSource[];
+};
+
+export type SourcePackImportResult = {
+  pack: string;
+  added: string[];
+  skipped: { name: string; reason: string }[];
+};
+
+export type CrawlPhaseTimes = {
+  fetch_ms: number;
+  parse_ms: number;
+  ai_ms: number;
+  db_ms: number;
+};
+
+export type CrawlSourceMetrics = CrawlPhaseTimes & {
+  source: string;
+  items: number;
+  error: string | null;
+};
+
+export type CrawlRun = {
+  id: number;
+  started_at: string;
+  total_ms: number;
+  inserted: number;
+  updated: number;
+  failed_sources: number;
+  phases: CrawlPhaseTimes & { post_ms: number };
+  sources: CrawlSourceMetrics[];
+};
+
+export type CommandStats = {
+  command: string;
+  calls: number;
+  total_ms: number;
+  avg_ms: number;
+  max_ms: number;
+};
+
+export type SlowQuery = {
+  sql: string;
+  duration_ms: number;
+  at: string;
+};
+
+export type PerfReport = {
+  slow_query_ms: number;
+  commands: CommandStats[];
+  slow_queries: SlowQuery[];
+};
+
+export type ArticlesExportFormat = "jsonl" | "json" | "csv";
+
+export type ArticlesExportStarted = {
+  id: number;
+  path: string;
+  total: number;
+};
+
+export type ArticlesExportProgress = {
+  id: number;
+  written: number;
+  total: number;
+};
+
+export type ArticlesExportComplete = {
+  id: number;
+  path: string;
+  written: number;
+  cancelled: boolean;
+  error: string | null;
+};
+
+export type CategoryCount = {
+  category: string;
+  total: number;
+  unread: number;
+};
+
+export type PrefetchQueued = {
+  next_page: number | null;
+  articles: number;
+};
+
+export type SearchReindexStatus = {
+  completed: boolean;
+  running: boolean;
+  done: number;
+  total: number;
+};
+
+export type SummaryEnsureResult = {
+  article: Article;
+  pending: boolean;
+};
+
+export type SummaryReadyEvent = {
+  id: string;
+  summary: string;
+  status: SummaryStatus;
+  error: string | null;
+};